@@ -0,0 +1,82 @@
+// API key of the HTTP server, kept in the browser once entered
+const STORAGE_KEY = 'forge-api-key';
+
+export const getApiKey = () => localStorage.getItem(STORAGE_KEY);
+
+// Event stream read through fetch, so the API key is sent in a header rather than in the URL, where
+// it would end up in server and proxy logs. Offers the handlers of an EventSource.
+export const openEventStream = (url) => {
+    const controller = new AbortController();
+    const stream = { onopen: null, onmessage: null, onerror: null, close: () => controller.abort() };
+    window.fetch(url, { headers: { Accept: 'text/event-stream' }, signal: controller.signal })
+        .then(async (response) => {
+            if (!response.ok || !response.body) {
+                throw new Error(`The event stream answered ${response.status}`);
+            }
+            stream.onopen?.();
+            const reader = response.body.pipeThrough(new TextDecoderStream()).getReader();
+            let buffer = '';
+            for (;;) {
+                const { value, done } = await reader.read();
+                if (done) {
+                    throw new Error('The event stream was closed');
+                }
+                buffer += value;
+                const events = buffer.split('\n\n');
+                buffer = events.pop();
+                for (const event of events) {
+                    const data = event.split('\n')
+                        .filter((line) => line.startsWith('data:'))
+                        .map((line) => line.slice(5).replace(/^ /, ''))
+                        .join('\n');
+                    if (data) {
+                        stream.onmessage?.({ data });
+                    }
+                }
+            }
+        })
+        .catch((error) => {
+            if (!controller.signal.aborted) {
+                stream.onerror?.(error);
+            }
+        });
+    return stream;
+};
+
+// Send the API key with every API request, asking for it (or signing in) when the server rejects
+// a request
+export const installApiKeyFetch = () => {
+    const originalFetch = window.fetch.bind(window);
+    const apiFetch = (input, init = {}) => {
+        const key = getApiKey();
+        if (!key) {
+            return originalFetch(input, init);
+        }
+        const headers = new Headers(init.headers || {});
+        headers.set('X-API-Key', key);
+        return originalFetch(input, {...init, headers});
+    };
+
+    window.fetch = async (input, init) => {
+        const url = typeof input === 'string' ? input : input.url;
+        if (!url.startsWith('/api')) {
+            return originalFetch(input, init);
+        }
+        const response = await apiFetch(input, init);
+        if (response.status !== 401) {
+            return response;
+        }
+        // Servers with an OIDC sign-in send the user to their provider
+        const status = await originalFetch('/api/auth/status').then((r) => r.json()).catch(() => null);
+        if (status?.oidc) {
+            window.location.href = '/api/auth/oidc/login';
+            return response;
+        }
+        const key = window.prompt('This server requires an API key:');
+        if (!key) {
+            return response;
+        }
+        localStorage.setItem(STORAGE_KEY, key.trim());
+        return apiFetch(input, init);
+    };
+};
@@ -69,6 +69,8 @@ const BlocksView = ({ refreshTrigger }) => {
 
     // Create a ref to store the current blocks data
     const blocksRef = useRef([]);
+    // Cursor of the differential block sync
+    const changesCursorRef = useRef(null);
 
     // Update the ref whenever blocks changes
     useEffect(() => {
@@ -79,25 +81,38 @@ const BlocksView = ({ refreshTrigger }) => {
     useEffect(() => {
         fetchBlocks();
 
-        // Set up an interval to check if blocks_config.json was modified
+        // Set up an interval to pick up block changes made elsewhere (MCP tools, other clients)
         const checkConfigInterval = setInterval(async () => {
             try {
-                // Fetch blocks data to check if it has changed
-                const response = await fetch('/api/blocks');
+                // Only fetch the blocks changed since the last sync
+                const since = changesCursorRef.current ? `?since=${encodeURIComponent(changesCursorRef.current)}` : '';
+                const response = await fetch(`/api/blocks/changes${since}`);
                 if (!response.ok) {
-                    throw new Error('Failed to fetch blocks');
+                    throw new Error('Failed to fetch block changes');
                 }
-                const data = await response.json();
-
-                // Compare with current blocks data using the ref
-                const currentBlocks = JSON.stringify(blocksRef.current);
-                const newBlocks = JSON.stringify(data);
-
-                // If blocks data has changed, update the state
-                if (currentBlocks !== newBlocks) {
-                    console.log('Blocks configuration has changed, reloading...');
-                    setBlocks(data);
+                const changes = await response.json();
+                changesCursorRef.current = changes.cursor;
+
+                // A full resync (first sync, server restart, project switch) returns every block
+                if (changes.reset) {
+                    if (JSON.stringify(blocksRef.current) !== JSON.stringify(changes.blocks)) {
+                        console.log('Blocks configuration has changed, reloading...');
+                        setBlocks(changes.blocks);
+                    }
+                    return;
                 }
+                if (changes.blocks.length === 0 && changes.deleted_block_ids.length === 0) {
+                    return;
+                }
+
+                console.log('Blocks configuration has changed, merging changes...');
+                const changedById = new Map(changes.blocks.map(block => [block.block_id, block]));
+                const currentIds = new Set(blocksRef.current.map(block => block.block_id));
+                const merged = blocksRef.current
+                    .filter(block => !changes.deleted_block_ids.includes(block.block_id))
+                    .map(block => changedById.get(block.block_id) || block)
+                    .concat(changes.blocks.filter(block => !currentIds.has(block.block_id)));
+                setBlocks(merged);
             } catch (error) {
                 console.error('Error checking blocks configuration:', error);
             }
@@ -118,6 +133,27 @@ const BlocksView = ({ refreshTrigger }) => {
     // Create a ref for the toast
     const toastRef = useRef(null);
 
+    // Read a server-sent event stream from a fetch response, calling onEvent(event, data) for each event
+    const readEventStream = async (response, onEvent) => {
+        const reader = response.body.getReader();
+        const decoder = new TextDecoder();
+        let buffer = '';
+        while (true) {
+            const { done, value } = await reader.read();
+            if (done) break;
+            buffer += decoder.decode(value, { stream: true });
+
+            let boundary;
+            while ((boundary = buffer.indexOf('\n\n')) !== -1) {
+                const message = buffer.slice(0, boundary);
+                buffer = buffer.slice(boundary + 2);
+                const event = message.match(/^event: (.*)$/m)?.[1] || 'message';
+                const data = message.match(/^data: (.*)$/m)?.[1];
+                if (data) onEvent(event, JSON.parse(data));
+            }
+        }
+    };
+
     // Function to fetch auto-complete suggestions
     const fetchAutoCompleteSuggestion = async (block_id) => {
 
@@ -132,7 +168,7 @@ const BlocksView = ({ refreshTrigger }) => {
 
         setIsAutoCompleteLoading(true);
         try {
-            const response = await fetch('/api/blocks/auto-complete', {
+            const response = await fetch('/api/blocks/auto-complete/stream', {
                 method: 'POST',
                 headers: {
                     'Content-Type': 'application/json',
@@ -144,11 +180,20 @@ const BlocksView = ({ refreshTrigger }) => {
                 throw new Error('Failed to fetch auto-complete suggestion');
             }
 
-            setShowLoadingDialog(false);
-
-            const data = await response.json();
-            setAutoCompleteSuggestion(data.suggestion);
-            setShowAutoCompleteDialog(true);
+            // Show the suggestion as it is generated
+            setAutoCompleteSuggestion('');
+            await readEventStream(response, (event, data) => {
+                if (event === 'chunk') {
+                    setShowLoadingDialog(false);
+                    setShowAutoCompleteDialog(true);
+                    setAutoCompleteSuggestion(previous => previous + data.text);
+                } else if (event === 'done') {
+                    setAutoCompleteSuggestion(data.suggestion);
+                    setShowAutoCompleteDialog(true);
+                } else if (event === 'error') {
+                    throw new Error(data.error);
+                }
+            });
         } catch (error) {
             setShowLoadingDialog(false);
             console.error('Error fetching auto-complete suggestion:', error);
@@ -290,7 +335,7 @@ const BlocksView = ({ refreshTrigger }) => {
 
         try {
             // Send the updated block to the server
-            const response = await fetch(`/api/blocks/${blockToEnhance.block_id}/enhance`, {
+            const response = await fetch(`/api/blocks/${blockToEnhance.block_id}/enhance/stream`, {
                 method: 'PUT',
                 headers: {
                     'Content-Type': 'application/json',
@@ -302,6 +347,20 @@ const BlocksView = ({ refreshTrigger }) => {
                 throw new Error('Failed to update block description');
             }
 
+            // Render the enhanced description as it is generated
+            let description = '';
+            await readEventStream(response, (event, data) => {
+                if (event === 'chunk') {
+                    setShowLoadingDialog(false);
+                    description += data.text;
+                    setBlocks(previous => previous.map(block =>
+                        block.block_id === block_id ? { ...block, description } : block
+                    ));
+                } else if (event === 'error') {
+                    throw new Error(data.error);
+                }
+            });
+
             // Reload blocks configuration
             await fetchBlocks();
 
@@ -767,7 +826,7 @@ const BlocksView = ({ refreshTrigger }) => {
     };
 
     // Execute a single task with Git integration
-    const executeGitTask = async (block_id, taskId) => {
+    const executeGitTask = async (block_id, taskId, skipPreflight = false) => {
         // Find the block and task
         const block = blocks.find(b => b.block_id === block_id);
         if (!block) {
@@ -797,10 +856,32 @@ const BlocksView = ({ refreshTrigger }) => {
                     task_id: task.task_id,
                     task_description: task.description,
                     resolve_dependencies: resolveDependencies,
-                    force_completed: forceCompleted
+                    force_completed: forceCompleted,
+                    skip_preflight: skipPreflight
                 }),
             });
 
+            // Pre-flight checks failed: show them and let the user run the task anyway
+            if (response.status === 412) {
+                const result = await response.json();
+                setRunningTasks(prev => ({
+                    ...prev,
+                    [`${block_id}-${task.task_id}`]: false
+                }));
+                const failures = result.preflight.checks
+                    .filter(check => !check.passed)
+                    .map(check => `${check.name}: ${check.message}`)
+                    .join('\n');
+                confirmDialog({
+                    message: `Pre-flight checks failed:\n${failures}\n\nRun the task anyway?`,
+                    header: 'Pre-flight Checks Failed',
+                    icon: 'pi pi-exclamation-triangle',
+                    acceptClassName: 'p-button-warning',
+                    accept: () => executeGitTask(block_id, taskId, true),
+                });
+                return;
+            }
+
             if (!response.ok) {
                 const errorText = await response.text();
                 throw new Error(`Failed to execute task with Git: ${errorText}`);
@@ -2171,7 +2252,10 @@ const BlocksView = ({ refreshTrigger }) => {
                                                             {todo.estimated_effort && (
                                                                 <div className="mb-3">
                                                                     <h4 className="m-0 mb-2">Estimated Effort</h4>
-                                                                    <p className="m-0">{todo.estimated_effort}</p>
+                                                                    <p className="m-0">
+                                                                        {todo.estimated_effort.size}
+                                                                        {todo.estimated_effort.hours != null && ` (${todo.estimated_effort.hours}h)`}
+                                                                    </p>
                                                                 </div>
                                                             )}
 
@@ -6,6 +6,7 @@ import { ScrollPanel } from 'primereact/scrollpanel';
 import { ProgressSpinner } from 'primereact/progressspinner';
 import { Checkbox } from 'primereact/checkbox';
 import './LoggerView.css';
+import { openEventStream } from '../apiKey.js';
 
 /**
  * LoggerView component for displaying real-time logs from Claude execution
@@ -131,8 +132,8 @@ const LoggerView = () => {
         setLoading(true);
         setConnected(false);
 
-        // Create a new EventSource connection
-        const eventSource = new EventSource(`/api/logs/stream/${taskId}`);
+        // Create a new event stream connection
+        const eventSource = openEventStream(`/api/logs/stream/${taskId}`);
         
         // Handle connection open
         eventSource.onopen = () => {
@@ -151,7 +152,7 @@ const LoggerView = () => {
         
         // Handle errors
         eventSource.onerror = (error) => {
-            console.error('EventSource error:', error);
+            console.error('Event stream error:', error);
             setConnected(false);
             setLoading(false);
             eventSource.close();
@@ -27,6 +27,13 @@ const ProjectView = ({ setActiveView }) => {
         openrouter_model: '',
         gemini_model: '',
         anthropic_model: '',
+        openai_model: '',
+        openai_base_url: '',
+        openai_api_key: '',
+        ollama_host: '',
+        ollama_model: '',
+        bedrock_region: '',
+        bedrock_model: '',
 
         // User-configurable prompts
         auto_complete_system_prompt: '',
@@ -55,7 +62,18 @@ const ProjectView = ({ setActiveView }) => {
         { label: 'GeminiCode', value: 'GeminiCode' },
         { label: 'OpenRouter', value: 'OpenRouter' },
         { label: 'Gemini', value: 'Gemini' },
-        { label: 'Anthropic', value: 'Anthropic' }
+        { label: 'Anthropic', value: 'Anthropic' },
+        { label: 'OpenAI / OpenAI-compatible', value: 'OpenAI' },
+        { label: 'Ollama (local)', value: 'Ollama' },
+        { label: 'AWS Bedrock', value: 'Bedrock' }
+    ];
+
+    // LLM operations that can use their own provider
+    const llmOperations = [
+        { key: 'enhance_description', label: 'Enhance description' },
+        { key: 'auto_complete', label: 'Auto-complete' },
+        { key: 'generate_tasks', label: 'Generate tasks' },
+        { key: 'process_specification', label: 'Process specification' }
     ];
 
     // OpenRouter model options
@@ -110,6 +128,13 @@ const ProjectView = ({ setActiveView }) => {
                     openrouter_model: '',
                     gemini_model: '',
                     anthropic_model: '',
+                    openai_model: '',
+                    openai_base_url: '',
+                    openai_api_key: '',
+                    ollama_host: '',
+                    ollama_model: '',
+                    bedrock_region: '',
+                    bedrock_model: '',
 
                     // User-configurable prompts
                     auto_complete_system_prompt: '',
@@ -248,12 +273,11 @@ const ProjectView = ({ setActiveView }) => {
                 body: JSON.stringify({url: projectConfig.git_repository_url}),
             });
 
+            const data = await response.json().catch(() => ({}));
             if (!response.ok) {
-                throw new Error('Failed to connect to Git repository');
+                throw new Error(data.message || 'Failed to connect to Git repository');
             }
 
-            const data = await response.json();
-
             toastRef.current.show({
                 severity: 'success',
                 summary: 'Success',
@@ -265,8 +289,8 @@ const ProjectView = ({ setActiveView }) => {
             toastRef.current.show({
                 severity: 'error',
                 summary: 'Error',
-                detail: 'Failed to connect to Git repository',
-                life: 3000
+                detail: error.message || 'Failed to connect to Git repository',
+                life: 5000
             });
         } finally {
             setTestingConnection(false);
@@ -280,6 +304,26 @@ const ProjectView = ({ setActiveView }) => {
         });
     };
 
+    const handleOperationModelChange = (operation, value) => {
+        setProjectConfig({
+            ...projectConfig,
+            operation_models: {
+                ...(projectConfig.operation_models || {}),
+                [operation]: value || null
+            }
+        });
+    };
+
+    const handleOperationProviderChange = (operation, value) => {
+        setProjectConfig({
+            ...projectConfig,
+            operation_providers: {
+                ...(projectConfig.operation_providers || {}),
+                [operation]: value || null
+            }
+        });
+    };
+
     if (loading) {
         return <div>Loading project configuration...</div>;
     }
@@ -401,6 +445,44 @@ const ProjectView = ({ setActiveView }) => {
                             />
                         </div>
 
+                        <div className="field">
+                            <label>
+                                Provider and Model per Operation
+                                <Tooltip target=".operation-providers-help" position="right">
+                                    Use a different provider or model for individual operations, e.g. a cheap model for auto-complete and a strong one for specifications. Operations left empty use the LLM provider above and the provider's model.
+                                </Tooltip>
+                                <i className="pi pi-question-circle ml-2 operation-providers-help" style={{ cursor: 'pointer' }}></i>
+                            </label>
+                            {llmOperations.map(({ key, label }) => (
+                                <div key={key} className="flex align-items-center gap-2 mb-2">
+                                    <span style={{ minWidth: '12rem' }}>{label}</span>
+                                    <Dropdown
+                                        value={(projectConfig.operation_providers || {})[key] || null}
+                                        options={llmProviderOptions}
+                                        onChange={(e) => handleOperationProviderChange(key, e.value)}
+                                        placeholder="Same as LLM provider"
+                                        showClear
+                                        className="w-full"
+                                    />
+                                    <InputText
+                                        value={(projectConfig.operation_models || {})[key] || ''}
+                                        onChange={(e) => handleOperationModelChange(key, e.target.value)}
+                                        placeholder="Provider's model"
+                                        className="w-full"
+                                    />
+                                </div>
+                            ))}
+                            <div className="flex align-items-center gap-2 mb-2">
+                                <span style={{ minWidth: '12rem' }}>Task execution (Claude CLI)</span>
+                                <InputText
+                                    value={(projectConfig.operation_models || {}).execute_task || ''}
+                                    onChange={(e) => handleOperationModelChange('execute_task', e.target.value)}
+                                    placeholder="Claude CLI default model"
+                                    className="w-full"
+                                />
+                            </div>
+                        </div>
+
                         {projectConfig.llm_provider === 'ClaudeCode' && (
                             <div className="field">
                                 <label htmlFor="anthropic_model">
@@ -500,6 +582,134 @@ const ProjectView = ({ setActiveView }) => {
                                 />
                             </div>
                         )}
+
+                        {projectConfig.llm_provider === 'OpenAI' && (
+                            <>
+                                <div className="field">
+                                    <label htmlFor="openai_model">
+                                        OpenAI Model
+                                        <Tooltip target=".openai-model-help" position="right">
+                                            The model name sent to the endpoint, e.g. gpt-4o. If empty, gpt-4o will be used.
+                                        </Tooltip>
+                                        <i className="pi pi-question-circle ml-2 openai-model-help" style={{ cursor: 'pointer' }}></i>
+                                    </label>
+                                    <InputText
+                                        id="openai_model"
+                                        value={projectConfig.openai_model || ''}
+                                        onChange={(e) => handleInputChange('openai_model', e.target.value)}
+                                        placeholder="gpt-4o"
+                                        className="w-full"
+                                    />
+                                </div>
+                                <div className="field">
+                                    <label htmlFor="openai_base_url">
+                                        Base URL
+                                        <Tooltip target=".openai-base-url-help" position="right">
+                                            Base URL of an OpenAI-compatible API. If empty, https://api.openai.com/v1 will be used.
+                                        </Tooltip>
+                                        <i className="pi pi-question-circle ml-2 openai-base-url-help" style={{ cursor: 'pointer' }}></i>
+                                    </label>
+                                    <InputText
+                                        id="openai_base_url"
+                                        value={projectConfig.openai_base_url || ''}
+                                        onChange={(e) => handleInputChange('openai_base_url', e.target.value)}
+                                        placeholder="https://api.openai.com/v1"
+                                        className="w-full"
+                                    />
+                                </div>
+                                <div className="field">
+                                    <label htmlFor="openai_api_key">
+                                        API Key
+                                        <Tooltip target=".openai-api-key-help" position="right">
+                                            {'Use {{secret:NAME}} to reference an encrypted secret instead of storing the key in the project configuration. If empty, the OPENAI_API_KEY secret or environment variable will be used.'}
+                                        </Tooltip>
+                                        <i className="pi pi-question-circle ml-2 openai-api-key-help" style={{ cursor: 'pointer' }}></i>
+                                    </label>
+                                    <InputText
+                                        id="openai_api_key"
+                                        type="password"
+                                        value={projectConfig.openai_api_key || ''}
+                                        onChange={(e) => handleInputChange('openai_api_key', e.target.value)}
+                                        placeholder="{{secret:OPENAI_API_KEY}}"
+                                        className="w-full"
+                                    />
+                                </div>
+                            </>
+                        )}
+
+                        {projectConfig.llm_provider === 'Ollama' && (
+                            <>
+                                <div className="field">
+                                    <label htmlFor="ollama_host">
+                                        Ollama Host
+                                        <Tooltip target=".ollama-host-help" position="right">
+                                            Address of the Ollama server. If empty, http://localhost:11434 will be used.
+                                        </Tooltip>
+                                        <i className="pi pi-question-circle ml-2 ollama-host-help" style={{ cursor: 'pointer' }}></i>
+                                    </label>
+                                    <InputText
+                                        id="ollama_host"
+                                        value={projectConfig.ollama_host || ''}
+                                        onChange={(e) => handleInputChange('ollama_host', e.target.value)}
+                                        placeholder="http://localhost:11434"
+                                        className="w-full"
+                                    />
+                                </div>
+                                <div className="field">
+                                    <label htmlFor="ollama_model">
+                                        Ollama Model
+                                        <Tooltip target=".ollama-model-help" position="right">
+                                            A model pulled into Ollama, e.g. llama3.1 or qwen2.5-coder. If empty, llama3.1 will be used.
+                                        </Tooltip>
+                                        <i className="pi pi-question-circle ml-2 ollama-model-help" style={{ cursor: 'pointer' }}></i>
+                                    </label>
+                                    <InputText
+                                        id="ollama_model"
+                                        value={projectConfig.ollama_model || ''}
+                                        onChange={(e) => handleInputChange('ollama_model', e.target.value)}
+                                        placeholder="llama3.1"
+                                        className="w-full"
+                                    />
+                                </div>
+                            </>
+                        )}
+
+                        {projectConfig.llm_provider === 'Bedrock' && (
+                            <>
+                                <div className="field">
+                                    <label htmlFor="bedrock_region">
+                                        AWS Region
+                                        <Tooltip target=".bedrock-region-help" position="right">
+                                            Region of the Bedrock endpoint. If empty, AWS_REGION will be used. Credentials are read from the AWS_ACCESS_KEY_ID and AWS_SECRET_ACCESS_KEY environment variables.
+                                        </Tooltip>
+                                        <i className="pi pi-question-circle ml-2 bedrock-region-help" style={{ cursor: 'pointer' }}></i>
+                                    </label>
+                                    <InputText
+                                        id="bedrock_region"
+                                        value={projectConfig.bedrock_region || ''}
+                                        onChange={(e) => handleInputChange('bedrock_region', e.target.value)}
+                                        placeholder="us-east-1"
+                                        className="w-full"
+                                    />
+                                </div>
+                                <div className="field">
+                                    <label htmlFor="bedrock_model">
+                                        Bedrock Model ID
+                                        <Tooltip target=".bedrock-model-help" position="right">
+                                            Model or inference profile ID enabled in your account. If empty, anthropic.claude-3-5-sonnet-20240620-v1:0 will be used.
+                                        </Tooltip>
+                                        <i className="pi pi-question-circle ml-2 bedrock-model-help" style={{ cursor: 'pointer' }}></i>
+                                    </label>
+                                    <InputText
+                                        id="bedrock_model"
+                                        value={projectConfig.bedrock_model || ''}
+                                        onChange={(e) => handleInputChange('bedrock_model', e.target.value)}
+                                        placeholder="anthropic.claude-3-5-sonnet-20240620-v1:0"
+                                        className="w-full"
+                                    />
+                                </div>
+                            </>
+                        )}
                     </>
                 );
             default:
@@ -31,6 +31,7 @@ const PromptSettingsView = ({ setActiveView }) => {
     const [professions, setProfessions] = useState([]);
     const [professionCategories, setProfessionCategories] = useState([]);
     const [loadingProfessions, setLoadingProfessions] = useState(false);
+    const [overriddenPrompts, setOverriddenPrompts] = useState([]);
 
     // Create a ref for the toast
     const toastRef = useRef(null);
@@ -123,7 +124,7 @@ const PromptSettingsView = ({ setActiveView }) => {
             }
 
             const data = await response.json();
-            setProjectConfig(data);
+            setProjectConfig(await withEffectivePrompts(data));
         } catch (error) {
             console.error('Error fetching project configuration:', error);
             toastRef.current.show({
@@ -137,6 +138,59 @@ const PromptSettingsView = ({ setActiveView }) => {
         }
     };
 
+    // Show the default of every prompt the project doesn't override
+    const withEffectivePrompts = async (config) => {
+        const response = await fetch('/api/project/prompts');
+        if (!response.ok) {
+            return config;
+        }
+        const prompts = await response.json();
+        const merged = { ...config };
+        prompts.forEach(prompt => {
+            if (!merged[prompt.key]) {
+                merged[prompt.key] = prompt.value;
+            }
+        });
+        setOverriddenPrompts(prompts.filter(prompt => prompt.overridden).map(prompt => prompt.key));
+        return merged;
+    };
+
+    // Reset the prompts of the active tab to their defaults
+    const resetActivePrompts = async () => {
+        const prefix = ['auto_complete', 'enhance_description', 'generate_tasks', 'process_specification'][activePromptIndex];
+        try {
+            const keys = Object.keys(projectConfig).filter(key => key.startsWith(prefix) && key.includes('_prompt'));
+            let prompts = [];
+            for (const key of keys) {
+                const response = await fetch(`/api/project/prompts/${key}`, { method: 'DELETE' });
+                if (!response.ok) {
+                    throw new Error(await response.text());
+                }
+                prompts = await response.json();
+            }
+            const config = { ...projectConfig };
+            prompts.filter(prompt => keys.includes(prompt.key)).forEach(prompt => {
+                config[prompt.key] = prompt.value;
+            });
+            setProjectConfig(config);
+            setOverriddenPrompts(prompts.filter(prompt => prompt.overridden).map(prompt => prompt.key));
+            toastRef.current.show({
+                severity: 'success',
+                summary: 'Success',
+                detail: `${promptTabs[activePromptIndex].label} prompts reset to defaults`,
+                life: 3000
+            });
+        } catch (error) {
+            console.error('Error resetting prompts:', error);
+            toastRef.current.show({
+                severity: 'error',
+                summary: 'Error',
+                detail: 'Failed to reset prompts',
+                life: 3000
+            });
+        }
+    };
+
     const saveProjectConfig = async () => {
         try {
             setSaving(true);
@@ -269,7 +323,7 @@ const PromptSettingsView = ({ setActiveView }) => {
                             <label htmlFor="auto_complete_user_prompt">
                                 User Prompt
                                 <Tooltip target=".auto-complete-user-help" position="right">
-                                    User prompt template for auto-completing block descriptions. Use {} as a placeholder for the description.
+                                    User prompt template for auto-completing block descriptions. {'Use {{input}} as a placeholder for the description; {{block_name}}, {{project_description}}, {{tech_stack}} and {{existing_blocks}} add project context.'}
                                 </Tooltip>
                                 <i className="pi pi-question-circle ml-2 auto-complete-user-help" style={{ cursor: 'pointer' }}></i>
                             </label>
@@ -324,7 +378,7 @@ const PromptSettingsView = ({ setActiveView }) => {
                             <label htmlFor="enhance_description_user_prompt">
                                 User Prompt
                                 <Tooltip target=".enhance-user-help" position="right">
-                                    User prompt template for enhancing block descriptions. Use {} as a placeholder for the description.
+                                    User prompt template for enhancing block descriptions. {'Use {{input}} as a placeholder for the description; {{block_name}}, {{project_description}}, {{tech_stack}} and {{existing_blocks}} add project context.'}
                                 </Tooltip>
                                 <i className="pi pi-question-circle ml-2 enhance-user-help" style={{ cursor: 'pointer' }}></i>
                             </label>
@@ -379,7 +433,7 @@ const PromptSettingsView = ({ setActiveView }) => {
                             <label htmlFor="generate_tasks_user_prompt">
                                 User Prompt
                                 <Tooltip target=".tasks-user-help" position="right">
-                                    User prompt template for generating tasks from block descriptions. Use {} as a placeholder for the description.
+                                    User prompt template for generating tasks from block descriptions. {'Use {{input}} as a placeholder for the description; {{block_name}}, {{project_description}}, {{tech_stack}} and {{existing_blocks}} add project context.'}
                                 </Tooltip>
                                 <i className="pi pi-question-circle ml-2 tasks-user-help" style={{ cursor: 'pointer' }}></i>
                             </label>
@@ -429,7 +483,7 @@ const PromptSettingsView = ({ setActiveView }) => {
                             <label htmlFor="generate_tasks_user_prompt_mcp">
                                 User Prompt ==MCP==
                                 <Tooltip target=".tasks-user-help" position="right">
-                                    User prompt template for generating tasks from block descriptions ==using MCP Tools==. Use {} as a placeholder for the description.
+                                    User prompt template for generating tasks from block descriptions ==using MCP Tools==. {'Use {{input}} as a placeholder for the description; {{block_name}}, {{project_description}}, {{tech_stack}} and {{existing_blocks}} add project context.'}
                                 </Tooltip>
                                 <i className="pi pi-question-circle ml-2 tasks-user-help" style={{ cursor: 'pointer' }}></i>
                             </label>
@@ -484,7 +538,7 @@ const PromptSettingsView = ({ setActiveView }) => {
                             <label htmlFor="process_specification_user_prompt">
                                 User Prompt
                                 <Tooltip target=".markdown-user-help" position="right">
-                                    User prompt template for processing markdown specifications. Use {} as a placeholder for the markdown content.
+                                    User prompt template for processing markdown specifications. {'Use {{input}} as a placeholder for the markdown content; {{project_description}}, {{tech_stack}} and {{existing_blocks}} add project context.'}
                                 </Tooltip>
                                 <i className="pi pi-question-circle ml-2 markdown-user-help" style={{ cursor: 'pointer' }}></i>
                             </label>
@@ -534,7 +588,7 @@ const PromptSettingsView = ({ setActiveView }) => {
                             <label htmlFor="process_specification_user_prompt_mcp">
                                 User Prompt ==MCP==
                                 <Tooltip target=".markdown-user-help" position="right">
-                                    User prompt template for processing markdown specifications ==using MCP Tools==. Use {} as a placeholder for the markdown content.
+                                    User prompt template for processing markdown specifications ==using MCP Tools==. {'Use {{input}} as a placeholder for the markdown content; {{project_description}}, {{tech_stack}} and {{existing_blocks}} add project context.'}
                                 </Tooltip>
                                 <i className="pi pi-question-circle ml-2 markdown-user-help" style={{ cursor: 'pointer' }}></i>
                             </label>
@@ -617,6 +671,19 @@ const PromptSettingsView = ({ setActiveView }) => {
             </div>
 
             <Card className="project-card">
+                <div className="flex justify-content-between align-items-center mb-2">
+                    <small className="text-muted">
+                        {overriddenPrompts.length > 0
+                            ? `Overridden in this project: ${overriddenPrompts.join(', ')}`
+                            : 'All prompts use the defaults'}
+                    </small>
+                    <Button
+                        label="Reset to Defaults"
+                        icon="pi pi-refresh"
+                        className="p-button-text p-button-sm"
+                        onClick={resetActivePrompts}
+                    />
+                </div>
                 <div className="p-fluid">
                     {renderPromptEditor()}
                 </div>
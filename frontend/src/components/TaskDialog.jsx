@@ -26,7 +26,7 @@ const TaskDialog = ({ visible, onHide, task, blockId, onSave }) => {
         description: '',
         acceptance_criteria: [],
         dependencies: [],
-        estimated_effort: '',
+        estimated_effort: null,
         files_affected: [],
         function_signatures: [],
         testing_requirements: [],
@@ -48,11 +48,11 @@ const TaskDialog = ({ visible, onHide, task, blockId, onSave }) => {
     const [newFunction, setNewFunction] = useState('');
     const [newTestReq, setNewTestReq] = useState('');
 
-    // Effort options
+    // Effort options, the sizes of the task's structured effort
     const effortOptions = [
-        { label: 'Small', value: 'S' },
-        { label: 'Medium', value: 'M' },
-        { label: 'Large', value: 'L' }
+        { label: 'Small', value: 'small' },
+        { label: 'Medium', value: 'medium' },
+        { label: 'Large', value: 'large' }
     ];
 
     // Initialize the form when the dialog is opened or the task changes
@@ -86,6 +86,14 @@ const TaskDialog = ({ visible, onHide, task, blockId, onSave }) => {
         }));
     };
 
+    // Handle effort changes; an estimate in hours only holds for the size it was given with
+    const handleEffortChange = (e) => {
+        setTaskData(prev => ({
+            ...prev,
+            estimated_effort: e.value ? { size: e.value } : null
+        }));
+    };
+
     // Handle array field additions
     const handleAddArrayItem = (field, value, setValue) => {
         if (value.trim()) {
@@ -239,10 +247,11 @@ const TaskDialog = ({ visible, onHide, task, blockId, onSave }) => {
                     <Dropdown
                         id="estimated_effort"
                         name="estimated_effort"
-                        value={taskData.estimated_effort}
+                        value={taskData.estimated_effort?.size ?? null}
                         options={effortOptions}
-                        onChange={handleInputChange}
+                        onChange={handleEffortChange}
                         placeholder="Select effort level"
+                        showClear
                     />
                 </div>
 
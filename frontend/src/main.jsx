@@ -2,6 +2,7 @@ import React from 'react'
 import ReactDOM from 'react-dom/client'
 import App from './App.jsx'
 import './index.css'
+import {installApiKeyFetch} from './apiKey.js'
 
 // PrimeReact imports
 import 'primereact/resources/themes/lara-dark-teal/theme.css'  // theme
@@ -9,6 +10,8 @@ import 'primereact/resources/primereact.min.css'                  // core css
 import 'primeicons/primeicons.css'                                // icons
 import 'primeflex/primeflex.css'                                  // primeflex
 
+installApiKeyFetch()
+
 ReactDOM.createRoot(document.getElementById('root')).render(
     <React.StrictMode>
         <App/>
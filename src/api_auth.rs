@@ -0,0 +1,394 @@
+use crate::mcp::tools::Permission;
+use crate::oidc::{self, AuthenticatedUser};
+use crate::project_config::{ApiKey, ApiScope, ProjectConfig, UserRole};
+use crate::project_handlers::ProjectAppState;
+use crate::request_user::{self, RequestActor};
+use crate::routes;
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::{header, Method};
+use actix_web::middleware::Next;
+use actix_web::{error, web, Error, HttpResponse, Responder};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+// Prefix of the generated keys, followed by 48 hex digits
+const KEY_PREFIX: &str = "forge_";
+
+// Characters of a key kept in the config to tell the keys apart
+const DISPLAYED_KEY_LENGTH: usize = 12;
+
+// API key as listed, without its hash
+#[derive(Debug, Serialize)]
+pub struct ApiKeyInfo {
+    pub name: String,
+    pub prefix: String,
+    pub scopes: Vec<ApiScope>,
+    pub created_at: String,
+}
+
+impl From<&ApiKey> for ApiKeyInfo {
+    fn from(key: &ApiKey) -> Self {
+        Self {
+            name: key.name.clone(),
+            prefix: key.prefix.clone(),
+            scopes: key.scopes.clone(),
+            created_at: key.created_at.clone(),
+        }
+    }
+}
+
+// Request body for creating an API key; keys get every scope by default
+#[derive(Debug, Deserialize)]
+pub struct CreateApiKeyRequest {
+    pub name: String,
+    pub scopes: Option<Vec<ApiScope>>,
+}
+
+// The created key; it is only returned once
+#[derive(Debug, Serialize)]
+pub struct CreatedApiKey {
+    pub key: String,
+    #[serde(flatten)]
+    pub info: ApiKeyInfo,
+}
+
+pub fn hash_key(key: &str) -> String {
+    hex::encode(Sha256::digest(key.as_bytes()))
+}
+
+// Generate a key with the given name and scopes, returning it with its config entry
+pub fn generate_key(name: &str, scopes: Vec<ApiScope>) -> (String, ApiKey) {
+    let bytes: [u8; 24] = rand::random();
+    let key = format!("{}{}", KEY_PREFIX, hex::encode(bytes));
+    let api_key = ApiKey {
+        name: name.to_string(),
+        key_hash: hash_key(&key),
+        prefix: key[..DISPLAYED_KEY_LENGTH].to_string(),
+        scopes,
+        created_at: chrono::Utc::now().to_rfc3339(),
+    };
+    (key, api_key)
+}
+
+pub fn find_key<'a>(keys: &'a [ApiKey], key: &str) -> Option<&'a ApiKey> {
+    let hash = hash_key(key);
+    keys.iter().find(|k| k.key_hash == hash)
+}
+
+// Every permission; the scopes of the API keys and the roles of the users are bundles of them
+const PERMISSIONS: [Permission; 9] = [
+    Permission::FileRead,
+    Permission::FileWrite,
+    Permission::Execute,
+    Permission::Network,
+    Permission::Git,
+    Permission::ProjectConfig,
+    Permission::TaskManagement,
+    Permission::Sensitive,
+    Permission::Admin,
+];
+
+// Permissions a request needs, from its method and its path below the API prefix. Settings, secrets,
+// projects, API keys, webhooks, the request audit log and backup restores are for admins.
+pub fn required_permissions(method: &Method, path: &str) -> Vec<Permission> {
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+    let read = [Method::GET, Method::HEAD, Method::OPTIONS].contains(method);
+    let permission = match segments.as_slice() {
+        // A restore replaces the project config, its API keys included
+        ["auth", ..] | ["audit", ..] | ["backups", _, "restore"] => Permission::Admin,
+        ["secrets", ..] => Permission::Sensitive,
+        ["projects", ..] => Permission::ProjectConfig,
+        _ if read => Permission::FileRead,
+        ["executor", ..] | ["tasks", _, "cancel"] | ["schedules", _, "run"] | ["git", "build" | "execute-task"] => Permission::Execute,
+        // Creating or changing a schedule sets up executions
+        ["schedules"] | ["schedules", _] if *method != Method::DELETE => Permission::Execute,
+        ["git", "branch" | "commit" | "merge" | "push" | "pull" | "pull-request"] => Permission::Git,
+        ["project", ..] | ["webhooks", ..] | ["mcp", "sessions", "config"] => Permission::ProjectConfig,
+        ["mcp", "sessions", ..] => Permission::Execute,
+        ["blocks", ..] | ["epics", ..] | ["schedules", ..] | ["ci", ..] => Permission::TaskManagement,
+        _ => Permission::FileWrite,
+    };
+    vec![permission]
+}
+
+// Scope of an API key granting a permission; None for the ones only granted with every scope: the
+// project config and the secrets hold credentials, and a key can't grant itself more than it has
+fn granting_scope(permission: &Permission) -> Option<ApiScope> {
+    match permission {
+        Permission::FileRead => Some(ApiScope::Read),
+        Permission::FileWrite | Permission::TaskManagement => Some(ApiScope::Write),
+        Permission::Execute | Permission::Git | Permission::Network => Some(ApiScope::Execute),
+        Permission::ProjectConfig | Permission::Sensitive | Permission::Admin => None,
+    }
+}
+
+// Lowest role of a user granting a permission
+fn granting_role(permission: &Permission) -> UserRole {
+    match permission {
+        Permission::FileRead => UserRole::Viewer,
+        Permission::FileWrite | Permission::TaskManagement => UserRole::Editor,
+        Permission::Execute | Permission::Git | Permission::Network => UserRole::Operator,
+        Permission::ProjectConfig | Permission::Sensitive | Permission::Admin => UserRole::Admin,
+    }
+}
+
+pub fn scope_permissions(scopes: &[ApiScope]) -> Vec<Permission> {
+    PERMISSIONS.into_iter()
+        .filter(|permission| match granting_scope(permission) {
+            Some(scope) => scopes.contains(&scope),
+            None => ApiScope::ALL.iter().all(|scope| scopes.contains(scope)),
+        })
+        .collect()
+}
+
+pub fn role_permissions(role: UserRole) -> Vec<Permission> {
+    PERMISSIONS.into_iter().filter(|permission| granting_role(permission) <= role).collect()
+}
+
+// Routes open without credentials: the sign-in, what the frontend needs to start it, the API
+// documentation and the CI webhook, which checks its own signature
+fn is_public(path: &str) -> bool {
+    matches!(path.trim_matches('/'), "auth/status" | "auth/logout" | "auth/oidc/login" | "auth/oidc/callback" | "openapi.json" | "docs" | "ci/webhook")
+}
+
+// Credential of the request, from an Authorization bearer token, the X-API-Key header or the cookie
+// of the OIDC sign-in. Keys are never read from the query string, which ends up in access logs.
+fn request_credential(req: &ServiceRequest) -> Option<String> {
+    let header_value = |name: header::HeaderName| req.headers().get(name).and_then(|v| v.to_str().ok()).map(str::trim);
+    header_value(header::AUTHORIZATION)
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .or_else(|| header_value(header::HeaderName::from_static("x-api-key")))
+        .map(|v| v.trim().to_string())
+        .or_else(|| req.cookie(oidc::TOKEN_COOKIE).map(|c| c.value().to_string()))
+        .filter(|credential| !credential.is_empty())
+}
+
+// Who a request is made by
+enum Identity {
+    ApiKey(ApiKey),
+    User(AuthenticatedUser),
+}
+
+impl Identity {
+    // Name recorded in the audit history
+    fn name(&self) -> String {
+        match self {
+            Identity::ApiKey(key) => format!("api-key:{}", key.name),
+            Identity::User(user) => user.name.clone(),
+        }
+    }
+
+    // Permissions granted by the scopes of the API key or the role of the user
+    fn grants(&self) -> Vec<Permission> {
+        match self {
+            Identity::ApiKey(key) => scope_permissions(&key.scopes),
+            Identity::User(user) => role_permissions(user.role),
+        }
+    }
+
+    fn authorize(&self, method: &Method, path: &str) -> Result<(), String> {
+        let grants = self.grants();
+        let Some(missing) = required_permissions(method, path).into_iter().find(|p| !grants.contains(p)) else {
+            return Ok(());
+        };
+        match self {
+            Identity::ApiKey(key) => {
+                let scope = granting_scope(&missing).map(|scope| format!("the {} scope", scope.as_str()));
+                Err(format!("The API key {} lacks the {:?} permission, granted by {}",
+                    key.name, missing, scope.unwrap_or_else(|| "every scope".to_string())))
+            }
+            Identity::User(user) => Err(format!("{} has the {} role, {} is required for the {:?} permission",
+                user.name, user.role.as_str(), granting_role(&missing).as_str(), missing)),
+        }
+    }
+}
+
+async fn authenticate(config: &ProjectConfig, credential: &str) -> Result<Identity, String> {
+    if let Some(oidc) = config.oidc.as_ref().filter(|_| oidc::looks_like_jwt(credential)) {
+        if let Err(e) = oidc.validate() {
+            eprintln!("Rejecting an OIDC sign-in, the OIDC config is invalid: {}", e);
+            return Err(format!("The OIDC sign-in is misconfigured: {}", e));
+        }
+        return oidc::validate_token(oidc, credential).await.map(Identity::User);
+    }
+    find_key(config.api_keys.as_deref().unwrap_or_default(), credential)
+        .map(|key| Identity::ApiKey(key.clone()))
+        .ok_or_else(|| "Invalid API key".to_string())
+}
+
+// Middleware of the API scopes. Once the project has API keys or an OIDC sign-in, requests need
+// an API key or a user granted the permissions of the route. The user is kept for the audit
+// history. An invalid OIDC config keeps the API closed: only API keys are accepted then.
+pub async fn require_authentication(req: ServiceRequest, next: Next<impl MessageBody>) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    // A config that can't be read doesn't tell whether the API is open, so the request is refused
+    let config = match req.app_data::<web::Data<ProjectAppState>>().map(|data| data.project_manager.get_config()) {
+        Some(Ok(config)) => config,
+        Some(Err(e)) => return Err(error::ErrorInternalServerError(format!("Failed to get project config: {}", e))),
+        None => ProjectConfig::default(),
+    };
+    if !config.requires_authentication() {
+        return request_user::run_as(None, next.call(req)).await;
+    }
+
+    let path = routes::api_path(req.path()).to_string();
+    let identity = match request_credential(&req) {
+        Some(credential) => authenticate(&config, &credential).await,
+        None => Err("Authentication is required".to_string()),
+    };
+    let identity = match identity {
+        Ok(identity) => identity,
+        Err(_) if is_public(&path) => return request_user::run_as(None, next.call(req)).await,
+        Err(e) => return Err(error::ErrorUnauthorized(e)),
+    };
+    if !is_public(&path) {
+        identity.authorize(req.method(), &path).map_err(error::ErrorForbidden)?;
+    }
+    if let Identity::User(user) = &identity {
+        req.extensions_mut().insert(user.clone());
+    }
+    req.extensions_mut().insert(RequestActor(identity.name()));
+    request_user::run_as(Some(identity.name()), next.call(req)).await
+}
+
+// API endpoint to list the API keys
+pub async fn get_api_keys_handler(data: web::Data<ProjectAppState>) -> impl Responder {
+    match data.project_manager.get_config() {
+        Ok(config) => HttpResponse::Ok().json(config.api_keys.unwrap_or_default().iter().map(ApiKeyInfo::from).collect::<Vec<_>>()),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Failed to get project config: {}", e)),
+    }
+}
+
+// API endpoint to create an API key; creating the first one enables the authentication
+pub async fn create_api_key_handler(data: web::Data<ProjectAppState>, request: web::Json<CreateApiKeyRequest>) -> impl Responder {
+    let request = request.into_inner();
+    let name = request.name.trim().to_string();
+    if name.is_empty() {
+        return HttpResponse::BadRequest().body("The key name can't be empty");
+    }
+    let requested = request.scopes.unwrap_or_else(|| ApiScope::ALL.to_vec());
+    let scopes: Vec<ApiScope> = ApiScope::ALL.into_iter().filter(|s| requested.contains(s)).collect();
+    if scopes.is_empty() {
+        return HttpResponse::BadRequest().body("A key needs at least one scope");
+    }
+
+    let mut config = match data.project_manager.get_config() {
+        Ok(config) => config,
+        Err(e) => return HttpResponse::InternalServerError().body(format!("Failed to get project config: {}", e)),
+    };
+    let keys = config.api_keys.get_or_insert_with(Vec::new);
+    if keys.iter().any(|k| k.name == name) {
+        return HttpResponse::Conflict().body(format!("API key {} already exists", name));
+    }
+    let (key, api_key) = generate_key(&name, scopes);
+    let info = ApiKeyInfo::from(&api_key);
+    keys.push(api_key);
+    match data.project_manager.save_config(&config) {
+        Ok(_) => HttpResponse::Created().json(CreatedApiKey { key, info }),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Failed to save project config: {}", e)),
+    }
+}
+
+// API endpoint to revoke an API key; revoking the last one disables the authentication
+pub async fn delete_api_key_handler(data: web::Data<ProjectAppState>, path: web::Path<String>) -> impl Responder {
+    let name = path.into_inner();
+    let mut config = match data.project_manager.get_config() {
+        Ok(config) => config,
+        Err(e) => return HttpResponse::InternalServerError().body(format!("Failed to get project config: {}", e)),
+    };
+    let keys = config.api_keys.get_or_insert_with(Vec::new);
+    let count = keys.len();
+    keys.retain(|k| k.name != name);
+    if keys.len() == count {
+        return HttpResponse::NotFound().body(format!("API key {} not found", name));
+    }
+    if keys.is_empty() {
+        config.api_keys = None;
+    }
+    match data.project_manager.save_config(&config) {
+        Ok(_) => HttpResponse::Ok().body("API key deleted successfully"),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Failed to save project config: {}", e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_required_permissions() {
+        assert_eq!(required_permissions(&Method::GET, "/blocks"), vec![Permission::FileRead]);
+        assert_eq!(required_permissions(&Method::PUT, "/blocks"), vec![Permission::TaskManagement]);
+        assert_eq!(required_permissions(&Method::POST, "/git/preflight"), vec![Permission::FileWrite]);
+        assert_eq!(required_permissions(&Method::POST, "/git/push"), vec![Permission::Git]);
+        assert_eq!(required_permissions(&Method::POST, "/git/pull-request"), vec![Permission::Git]);
+        assert_eq!(required_permissions(&Method::POST, "/git/execute-task"), vec![Permission::Execute]);
+        assert_eq!(required_permissions(&Method::POST, "/executor/queue/pause"), vec![Permission::Execute]);
+        assert_eq!(required_permissions(&Method::POST, "/tasks/e1/cancel"), vec![Permission::Execute]);
+        assert_eq!(required_permissions(&Method::POST, "/schedules"), vec![Permission::Execute]);
+        assert_eq!(required_permissions(&Method::PUT, "/schedules/s1"), vec![Permission::Execute]);
+        assert_eq!(required_permissions(&Method::DELETE, "/schedules/s1"), vec![Permission::TaskManagement]);
+        assert_eq!(required_permissions(&Method::GET, "/project"), vec![Permission::FileRead]);
+        assert_eq!(required_permissions(&Method::PUT, "/project"), vec![Permission::ProjectConfig]);
+        assert_eq!(required_permissions(&Method::PUT, "/webhooks"), vec![Permission::ProjectConfig]);
+        assert_eq!(required_permissions(&Method::GET, "/secrets"), vec![Permission::Sensitive]);
+        assert_eq!(required_permissions(&Method::GET, "/auth/keys"), vec![Permission::Admin]);
+        assert_eq!(required_permissions(&Method::GET, "/audit"), vec![Permission::Admin]);
+        assert_eq!(required_permissions(&Method::GET, "/backups"), vec![Permission::FileRead]);
+        assert_eq!(required_permissions(&Method::POST, "/backups"), vec![Permission::FileWrite]);
+        assert_eq!(required_permissions(&Method::POST, "/backups/20250101-120000/restore"), vec![Permission::Admin]);
+        assert_eq!(required_permissions(&Method::GET, "/mcp/sessions"), vec![Permission::FileRead]);
+        assert_eq!(required_permissions(&Method::PUT, "/mcp/sessions/config"), vec![Permission::ProjectConfig]);
+        assert_eq!(required_permissions(&Method::POST, "/mcp/sessions/evict"), vec![Permission::Execute]);
+        assert_eq!(required_permissions(&Method::DELETE, "/mcp/sessions/s1"), vec![Permission::Execute]);
+    }
+
+    #[test]
+    fn test_api_key_grants() {
+        let key = |scopes: Vec<ApiScope>| Identity::ApiKey(generate_key("ci", scopes).1);
+        let read_only = key(vec![ApiScope::Read]);
+        assert!(read_only.authorize(&Method::GET, "/blocks").is_ok());
+        assert!(read_only.authorize(&Method::PUT, "/blocks").is_err());
+        assert!(read_only.authorize(&Method::POST, "/git/execute-task").is_err());
+        assert!(read_only.authorize(&Method::POST, "/executor/queue/resume").is_err());
+
+        let writer = key(vec![ApiScope::Read, ApiScope::Write]);
+        assert!(writer.authorize(&Method::POST, "/blocks/b1/task").is_ok());
+        assert!(writer.authorize(&Method::POST, "/git/push").is_err());
+        assert!(writer.authorize(&Method::GET, "/auth/keys").is_err());
+        assert!(writer.authorize(&Method::PUT, "/project").is_err());
+        assert!(writer.authorize(&Method::GET, "/secrets").is_err());
+        assert!(writer.authorize(&Method::POST, "/schedules").is_err());
+        assert!(writer.authorize(&Method::POST, "/backups/20250101-120000/restore").is_err());
+        assert!(writer.authorize(&Method::PUT, "/mcp/sessions/config").is_err());
+
+        let admin = key(ApiScope::ALL.to_vec());
+        assert_eq!(admin.grants(), PERMISSIONS.to_vec());
+        assert!(admin.authorize(&Method::POST, "/auth/keys").is_ok());
+    }
+
+    #[test]
+    fn test_role_grants() {
+        let user = |role: UserRole| Identity::User(AuthenticatedUser {
+            subject: "u1".to_string(), name: "ada".to_string(), role, expires_at: None,
+        });
+        assert!(user(UserRole::Viewer).authorize(&Method::GET, "/blocks").is_ok());
+        assert!(user(UserRole::Viewer).authorize(&Method::POST, "/blocks/b1/task").is_err());
+        assert!(user(UserRole::Editor).authorize(&Method::POST, "/blocks/b1/task").is_ok());
+        assert!(user(UserRole::Editor).authorize(&Method::POST, "/git/push").is_err());
+        assert!(user(UserRole::Operator).authorize(&Method::POST, "/git/push").is_ok());
+        assert!(user(UserRole::Operator).authorize(&Method::PUT, "/project").is_err());
+        assert!(user(UserRole::Operator).authorize(&Method::GET, "/secrets").is_err());
+        assert!(user(UserRole::Admin).authorize(&Method::GET, "/audit").is_ok());
+    }
+
+    #[test]
+    fn test_find_key() {
+        let (key, api_key) = generate_key("ci", vec![ApiScope::Read]);
+        assert!(key.starts_with(KEY_PREFIX) && key.starts_with(&api_key.prefix));
+        assert_ne!(api_key.key_hash, key);
+        let keys = vec![api_key];
+        assert_eq!(find_key(&keys, &key).map(|k| k.name.as_str()), Some("ci"));
+        assert!(find_key(&keys, "forge_wrong").is_none());
+    }
+}
@@ -0,0 +1,124 @@
+use crate::secrets;
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+
+type HmacSha256 = Hmac<Sha256>;
+
+// AWS credentials used to sign requests
+#[derive(Debug, Clone)]
+pub struct AwsCredentials {
+    pub access_key_id: String,
+    pub secret_access_key: String,
+    pub session_token: Option<String>,
+}
+
+impl AwsCredentials {
+    // Credentials from the secrets or the standard AWS environment variables of the same names
+    pub fn from_env() -> Result<Self, String> {
+        Ok(Self {
+            access_key_id: secrets::credential("AWS_ACCESS_KEY_ID")?,
+            secret_access_key: secrets::credential("AWS_SECRET_ACCESS_KEY")?,
+            session_token: secrets::optional_credential("AWS_SESSION_TOKEN"),
+        })
+    }
+}
+
+// Percent-encode everything but the unreserved characters, as SigV4 requires
+pub fn uri_encode(value: &str) -> String {
+    let mut encoded = String::new();
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => encoded.push(byte as char),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+fn hmac_sha256(key: &[u8], message: &str) -> Vec<u8> {
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(message.as_bytes());
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+    hex::encode(Sha256::digest(data))
+}
+
+// Sign a request with AWS Signature Version 4. `path` is the request path as sent (already
+// URI-encoded once); `headers` are the extra headers to sign. Returns the headers to add to
+// the request, including the Authorization header.
+#[allow(clippy::too_many_arguments)]
+pub fn sign_request(
+    method: &str,
+    host: &str,
+    path: &str,
+    headers: &[(&str, &str)],
+    body: &[u8],
+    region: &str,
+    service: &str,
+    credentials: &AwsCredentials,
+    now: DateTime<Utc>,
+) -> Vec<(String, String)> {
+    let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+    let date = now.format("%Y%m%d").to_string();
+
+    let mut signed: Vec<(String, String)> = headers.iter()
+        .map(|(name, value)| (name.to_lowercase(), value.trim().to_string()))
+        .collect();
+    signed.push(("host".to_string(), host.to_string()));
+    signed.push(("x-amz-date".to_string(), amz_date.clone()));
+    if let Some(token) = &credentials.session_token {
+        signed.push(("x-amz-security-token".to_string(), token.clone()));
+    }
+    signed.sort();
+
+    // Services other than S3 expect every path segment to be encoded a second time
+    let canonical_uri = path.split('/').map(uri_encode).collect::<Vec<_>>().join("/");
+    let canonical_headers: String = signed.iter().map(|(name, value)| format!("{}:{}\n", name, value)).collect();
+    let signed_headers = signed.iter().map(|(name, _)| name.as_str()).collect::<Vec<_>>().join(";");
+    let canonical_request = format!("{}\n{}\n\n{}\n{}\n{}",
+        method, canonical_uri, canonical_headers, signed_headers, sha256_hex(body));
+
+    let scope = format!("{}/{}/{}/aws4_request", date, region, service);
+    let string_to_sign = format!("AWS4-HMAC-SHA256\n{}\n{}\n{}", amz_date, scope, sha256_hex(canonical_request.as_bytes()));
+
+    let key = hmac_sha256(format!("AWS4{}", credentials.secret_access_key).as_bytes(), &date);
+    let key = hmac_sha256(&key, region);
+    let key = hmac_sha256(&key, service);
+    let key = hmac_sha256(&key, "aws4_request");
+    let signature = hex::encode(hmac_sha256(&key, &string_to_sign));
+
+    let mut result: Vec<(String, String)> = vec![("x-amz-date".to_string(), amz_date)];
+    if let Some(token) = &credentials.session_token {
+        result.push(("x-amz-security-token".to_string(), token.clone()));
+    }
+    result.push(("authorization".to_string(), format!(
+        "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+        credentials.access_key_id, scope, signed_headers, signature)));
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_sign_request_matches_aws_test_suite() {
+        // "get-vanilla" case of the AWS SigV4 test suite
+        let credentials = AwsCredentials {
+            access_key_id: "AKIDEXAMPLE".to_string(),
+            secret_access_key: "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY".to_string(),
+            session_token: None,
+        };
+        let now = Utc.with_ymd_and_hms(2015, 8, 30, 12, 36, 0).unwrap();
+        let headers = sign_request("GET", "example.amazonaws.com", "/", &[], b"", "us-east-1", "service", &credentials, now);
+
+        assert_eq!(headers[0], ("x-amz-date".to_string(), "20150830T123600Z".to_string()));
+        assert_eq!(headers[1].1, "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/service/aws4_request, \
+            SignedHeaders=host;x-amz-date, Signature=5fa00fa31553b73ebf1942676e86291e8372ff2a2260956d9b8aae1d763fbf31");
+        assert_eq!(uri_encode("anthropic.claude-v2:1"), "anthropic.claude-v2%3A1");
+    }
+}
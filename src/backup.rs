@@ -0,0 +1,280 @@
+use crate::block_config::{write_file_atomic, BlockConfigManager, FileLock};
+use crate::migrations::migrate_project_config;
+use crate::project_config::{BackupConfig, ProjectConfig, ProjectConfigManager};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use tracing::{error, info};
+
+// Backups folder, relative to the project home directory
+pub const BACKUPS_DIR: &str = ".forge/backups";
+
+// How often the scheduler checks whether a backup is due
+const SCHEDULER_TICK: Duration = Duration::from_secs(60);
+
+// A snapshot of the project data files
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupInfo {
+    pub backup_id: String,
+    pub files: Vec<String>,
+}
+
+// Directory holding the backups of the current project
+pub fn backups_dir(project_manager: &ProjectConfigManager) -> PathBuf {
+    let project_home = project_manager.get_config()
+        .map(|c| c.project_home_directory)
+        .unwrap_or_default();
+
+    if !project_home.is_empty() && Path::new(&project_home).exists() {
+        Path::new(&project_home).join(BACKUPS_DIR)
+    } else {
+        PathBuf::from(BACKUPS_DIR)
+    }
+}
+
+// Project data files included in a backup
+fn backup_sources(project_manager: &ProjectConfigManager, block_manager: &BlockConfigManager) -> Vec<PathBuf> {
+    vec![
+        PathBuf::from(block_manager.config_file()),
+        PathBuf::from(block_manager.epics_file()),
+        PathBuf::from(project_manager.config_file()),
+    ]
+}
+
+// Create the backups directory, ignored by git so backups don't dirty the project working tree
+fn prepare_backups_dir(dir: &Path) -> Result<(), String> {
+    fs::create_dir_all(dir)
+        .map_err(|e| format!("Failed to create backup directory: {}", e))?;
+    let gitignore = dir.join(".gitignore");
+    if !gitignore.exists() {
+        fs::write(&gitignore, "*\n")
+            .map_err(|e| format!("Failed to write {}: {}", gitignore.display(), e))?;
+    }
+    Ok(())
+}
+
+// Snapshot the blocks config, epics and project config into a new backup folder
+pub fn create_backup(project_manager: &ProjectConfigManager, block_manager: &BlockConfigManager) -> Result<BackupInfo, String> {
+    let dir = backups_dir(project_manager);
+    prepare_backups_dir(&dir)?;
+
+    // Millisecond IDs, with a suffix for backups taken within the same millisecond
+    let timestamp = chrono::Local::now().format("%Y%m%d-%H%M%S-%3f").to_string();
+    let mut backup_id = timestamp.clone();
+    let mut suffix = 1;
+    let backup_dir = loop {
+        let candidate = dir.join(&backup_id);
+        match fs::create_dir(&candidate) {
+            Ok(()) => break candidate,
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                backup_id = format!("{}-{}", timestamp, suffix);
+                suffix += 1;
+            },
+            Err(e) => return Err(format!("Failed to create backup directory: {}", e)),
+        }
+    };
+
+    let mut files = Vec::new();
+    for source in backup_sources(project_manager, block_manager) {
+        if !source.exists() {
+            continue;
+        }
+        let file_name = source.file_name()
+            .ok_or_else(|| format!("Invalid backup source {}", source.display()))?;
+        fs::copy(&source, backup_dir.join(file_name))
+            .map_err(|e| format!("Failed to back up {}: {}", source.display(), e))?;
+        files.push(file_name.to_string_lossy().to_string());
+    }
+
+    info!("Created backup {} with {} files", backup_id, files.len());
+    Ok(BackupInfo { backup_id, files })
+}
+
+// List the available backups, most recent first
+pub fn list_backups(project_manager: &ProjectConfigManager) -> Result<Vec<BackupInfo>, String> {
+    let dir = backups_dir(project_manager);
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut backups = Vec::new();
+    for entry in fs::read_dir(&dir).map_err(|e| format!("Failed to read backups directory: {}", e))? {
+        let entry = entry.map_err(|e| format!("Failed to read backup entry: {}", e))?;
+        if !entry.path().is_dir() {
+            continue;
+        }
+
+        let mut files: Vec<String> = fs::read_dir(entry.path())
+            .map_err(|e| format!("Failed to read backup: {}", e))?
+            .filter_map(|f| f.ok())
+            .map(|f| f.file_name().to_string_lossy().to_string())
+            .collect();
+        files.sort();
+
+        backups.push(BackupInfo {
+            backup_id: entry.file_name().to_string_lossy().to_string(),
+            files,
+        });
+    }
+
+    // Backup IDs are timestamps, so they sort chronologically
+    backups.sort_by(|a, b| b.backup_id.cmp(&a.backup_id));
+    Ok(backups)
+}
+
+// Remove the oldest backups beyond the retention limit
+pub fn prune_backups(project_manager: &ProjectConfigManager, max_backups: usize) -> Result<usize, String> {
+    let backups = list_backups(project_manager)?;
+    let dir = backups_dir(project_manager);
+
+    let mut removed = 0;
+    for backup in backups.iter().skip(max_backups) {
+        fs::remove_dir_all(dir.join(&backup.backup_id))
+            .map_err(|e| format!("Failed to remove backup {}: {}", backup.backup_id, e))?;
+        removed += 1;
+    }
+
+    Ok(removed)
+}
+
+// Restore the project data files from a backup and reload them. The blocks config and epics are replaced
+// under the lock of the blocks config; the restored project config keeps the current API keys, OIDC sign-in
+// and webhooks, so a backup can't bring back revoked credentials.
+pub fn restore_backup(project_manager: &ProjectConfigManager, block_manager: &BlockConfigManager, backup_id: &str) -> Result<BackupInfo, String> {
+    if backup_id.is_empty() || backup_id.contains(['/', '\\']) || backup_id.contains("..") {
+        return Err(format!("Invalid backup ID '{}'", backup_id));
+    }
+
+    let current = project_manager.get_config()
+        .map_err(|e| format!("Failed to get project config: {}", e))?;
+    if block_manager.is_read_only() || current.spec_read_only.unwrap_or(false) {
+        return Err("The spec directory is read-only, backups can't be restored".to_string());
+    }
+
+    let backup_dir = backups_dir(project_manager).join(backup_id);
+    if !backup_dir.is_dir() {
+        return Err(format!("Backup '{}' not found", backup_id));
+    }
+
+    let mut files = Vec::new();
+    {
+        let _lock = FileLock::acquire(&block_manager.config_file())?;
+        for target in [block_manager.config_file(), block_manager.epics_file()] {
+            let Some(source) = backup_file(&backup_dir, &target) else {
+                continue;
+            };
+            let contents = fs::read(&source)
+                .map_err(|e| format!("Failed to read {}: {}", source.display(), e))?;
+            write_file_atomic(&target, &contents)?;
+            files.push(file_name(&source));
+        }
+    }
+    block_manager.load_blocks_from_file()?;
+
+    if let Some(source) = backup_file(&backup_dir, &project_manager.config_file()) {
+        let mut document: serde_json::Value = fs::read_to_string(&source)
+            .map_err(|e| format!("Failed to read {}: {}", source.display(), e))
+            .and_then(|content| serde_json::from_str(&content).map_err(|e| format!("Invalid project config in backup: {}", e)))?;
+        migrate_project_config(&mut document)?;
+        let mut config: ProjectConfig = serde_json::from_value(document)
+            .map_err(|e| format!("Invalid project config in backup: {}", e))?;
+        config.keep_access_settings(&current);
+        project_manager.save_config(&config)
+            .map_err(|e| format!("Failed to restore project config: {}", e))?;
+        files.push(file_name(&source));
+    }
+
+    info!("Restored backup {} ({} files)", backup_id, files.len());
+    Ok(BackupInfo { backup_id: backup_id.to_string(), files })
+}
+
+// Copy of a project data file in a backup, if the backup has one
+fn backup_file(backup_dir: &Path, target: &str) -> Option<PathBuf> {
+    let source = backup_dir.join(Path::new(target).file_name()?);
+    source.exists().then_some(source)
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default()
+}
+
+// Start the background thread taking scheduled backups
+pub fn start_backup_scheduler(project_manager: Arc<ProjectConfigManager>, block_manager: Arc<BlockConfigManager>) {
+    thread::spawn(move || {
+        let mut last_backup = Instant::now();
+        loop {
+            thread::sleep(SCHEDULER_TICK);
+
+            // Re-read the schedule so configuration changes apply without a restart
+            let backup_config = project_manager.get_config()
+                .ok()
+                .and_then(|c| c.backup)
+                .unwrap_or_else(BackupConfig::default);
+            if !backup_config.enabled {
+                continue;
+            }
+            if last_backup.elapsed() < Duration::from_secs(backup_config.interval_minutes.max(1) * 60) {
+                continue;
+            }
+            last_backup = Instant::now();
+
+            match create_backup(&project_manager, &block_manager) {
+                Ok(_) => {
+                    if let Err(e) = prune_backups(&project_manager, backup_config.max_backups.max(1)) {
+                        error!("Failed to prune backups: {}", e);
+                    }
+                },
+                Err(e) => error!("Scheduled backup failed: {}", e),
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Block;
+    use crate::project_config::ProjectConfig;
+
+    #[test]
+    fn test_backup_prune_and_restore() {
+        let dir = tempfile::tempdir().unwrap();
+        let project_manager = ProjectConfigManager::new(dir.path().join("project_config.json").to_str().unwrap());
+        let config = ProjectConfig { project_home_directory: dir.path().to_string_lossy().to_string(), ..ProjectConfig::default() };
+        project_manager.save_config(&config).unwrap();
+        project_manager.load_config().unwrap();
+        let block_manager = BlockConfigManager::new(dir.path().join("blocks_config.json").to_str().unwrap());
+        block_manager.add_block(Block::new("First".to_string(), String::new(), Vec::new(), Vec::new())).unwrap();
+        block_manager.save_blocks_to_file().unwrap();
+
+        // Backups taken in the same second don't collide
+        let first = create_backup(&project_manager, &block_manager).unwrap();
+        let second = create_backup(&project_manager, &block_manager).unwrap();
+        assert_ne!(first.backup_id, second.backup_id);
+        assert!(first.files.contains(&"blocks_config.json".to_string()));
+        assert_eq!(fs::read_to_string(backups_dir(&project_manager).join(".gitignore")).unwrap(), "*\n");
+        assert_eq!(list_backups(&project_manager).unwrap().len(), 2);
+
+        block_manager.add_block(Block::new("Second".to_string(), String::new(), Vec::new(), Vec::new())).unwrap();
+        block_manager.save_blocks_to_file().unwrap();
+        // API keys created after the backup survive its restore
+        let (_, api_key) = crate::api_auth::generate_key("ci", crate::project_config::ApiScope::ALL.to_vec());
+        project_manager.save_config(&ProjectConfig { api_keys: Some(vec![api_key]), ..config.clone() }).unwrap();
+        restore_backup(&project_manager, &block_manager, &first.backup_id).unwrap();
+        assert_eq!(block_manager.get_blocks().unwrap().len(), 1);
+        assert_eq!(project_manager.get_config().unwrap().api_keys.map(|keys| keys.len()), Some(1));
+        assert!(restore_backup(&project_manager, &block_manager, "../etc").is_err());
+
+        block_manager.set_read_only(true);
+        assert!(restore_backup(&project_manager, &block_manager, &first.backup_id).is_err());
+        block_manager.set_read_only(false);
+
+        assert_eq!(prune_backups(&project_manager, 1).unwrap(), 1);
+        let remaining = list_backups(&project_manager).unwrap();
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].backup_id, second.backup_id);
+    }
+}
@@ -0,0 +1,39 @@
+use crate::backup::{create_backup, list_backups, prune_backups, restore_backup};
+use crate::block_handlers::AppState;
+use actix_web::{web, HttpResponse, Responder};
+
+// API endpoint to list the available backups
+pub async fn get_backups_handler(data: web::Data<AppState>) -> impl Responder {
+    match list_backups(&data.project_manager) {
+        Ok(backups) => HttpResponse::Ok().json(backups),
+        Err(e) => HttpResponse::InternalServerError().body(e),
+    }
+}
+
+// API endpoint to take a backup immediately
+pub async fn create_backup_handler(data: web::Data<AppState>) -> impl Responder {
+    match create_backup(&data.project_manager, &data.block_manager) {
+        Ok(backup) => {
+            // Apply the retention limit to manual backups as well
+            let max_backups = data.project_manager.get_config()
+                .ok()
+                .and_then(|c| c.backup)
+                .unwrap_or_default()
+                .max_backups;
+            if let Err(e) = prune_backups(&data.project_manager, max_backups.max(1)) {
+                return HttpResponse::InternalServerError().body(e);
+            }
+            HttpResponse::Ok().json(backup)
+        },
+        Err(e) => HttpResponse::InternalServerError().body(e),
+    }
+}
+
+// API endpoint to restore the project data from a backup
+pub async fn restore_backup_handler(path: web::Path<String>, data: web::Data<AppState>) -> impl Responder {
+    let backup_id = path.into_inner();
+    match restore_backup(&data.project_manager, &data.block_manager, &backup_id) {
+        Ok(backup) => HttpResponse::Ok().json(backup),
+        Err(e) => HttpResponse::BadRequest().body(e),
+    }
+}
@@ -0,0 +1,262 @@
+use crate::block_config::BlockConfigManager;
+use crate::log_stream;
+use crate::models::{Block, CompletionCriteria, BLOCK_STATUS_COMPLETED};
+use crate::process_tracker;
+use crate::project_config::{ProjectConfig, ProjectConfigManager, TaskWorkflow, WEBHOOK_EVENT_BLOCK_COMPLETED};
+use crate::webhooks;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use tracing::{error, info};
+
+// Log stream the block completion events are published on
+pub const BLOCK_EVENTS_LOG_ID: &str = "block-events";
+
+// Completion reports folder, relative to the project home directory
+pub const REPORTS_DIR: &str = ".forge/reports";
+
+// How often the evaluator checks the completion criteria of the open blocks
+const EVALUATOR_TICK: Duration = Duration::from_secs(30);
+
+// Check the completion criteria of a block, returning the reason when they don't hold
+pub fn evaluate_block(block: &Block, criteria: &CompletionCriteria, workflow: &TaskWorkflow, project_dir: &str) -> Result<(), String> {
+    if criteria.all_tasks_done {
+        if block.todo_list.is_empty() {
+            return Err("Block has no tasks".to_string());
+        }
+        let open_tasks = block.todo_list.values().filter(|t| !workflow.is_completed(&t.status)).count();
+        if open_tasks > 0 {
+            return Err(format!("{} task(s) not completed", open_tasks));
+        }
+    }
+
+    if let Some(test_command) = criteria.test_command.as_deref().filter(|c| !c.trim().is_empty()) {
+        run_command(test_command, project_dir, &block.block_id)
+            .map_err(|e| format!("Tests failed: {}", e))?;
+    }
+
+    if let Some(min_coverage) = criteria.min_coverage {
+        let coverage_command = criteria.coverage_command.as_deref()
+            .filter(|c| !c.trim().is_empty())
+            .ok_or("min_coverage is set but no coverage_command is configured")?;
+        let output = run_command(coverage_command, project_dir, &block.block_id)
+            .map_err(|e| format!("Coverage command failed: {}", e))?;
+        let coverage = parse_coverage(&output)
+            .ok_or("No coverage percentage found in the coverage command output")?;
+        if coverage < min_coverage {
+            return Err(format!("Coverage {:.1}% is below {:.1}%", coverage, min_coverage));
+        }
+    }
+
+    Ok(())
+}
+
+// Extract the last percentage (e.g. "87.5%") from a coverage command output
+pub fn parse_coverage(output: &str) -> Option<f32> {
+    output.split_whitespace()
+        .rev()
+        .filter_map(|word| word.trim_matches(|c: char| c != '%' && c != '.' && !c.is_ascii_digit()).strip_suffix('%'))
+        .find_map(|number| number.parse::<f32>().ok())
+}
+
+// Run a shell command in the project directory, returning its output
+fn run_command(command: &str, project_dir: &str, block_id: &str) -> Result<String, String> {
+    let child = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .current_dir(project_dir)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to run '{}': {}", command, e))?;
+    let _process_guard = process_tracker::register(child.id(), &format!("block-completion:{}", block_id), command);
+
+    let output = child.wait_with_output()
+        .map_err(|e| format!("Failed to wait for '{}': {}", command, e))?;
+    let text = format!("{}{}", String::from_utf8_lossy(&output.stdout), String::from_utf8_lossy(&output.stderr));
+    if !output.status.success() {
+        return Err(format!("'{}' exited with {}", command, output.status));
+    }
+    Ok(text)
+}
+
+// Markdown summary of a completed block and its tasks
+pub fn block_report(block: &Block) -> String {
+    let mut report = format!("# {} Completion Report\n\n{}\n\n## Tasks\n\n", block.name, block.description);
+
+    let mut tasks: Vec<_> = block.todo_list.values().collect();
+    tasks.sort_by(|a, b| a.task_name.cmp(&b.task_name));
+    for task in tasks {
+        let name = if task.task_name.is_empty() { &task.description } else { &task.task_name };
+        report.push_str(&format!("- {} {}", task.status, name));
+        if !task.commit_id.is_empty() {
+            report.push_str(&format!(" ({})", task.commit_id));
+        }
+        report.push('\n');
+        for file in &task.files_modified {
+            report.push_str(&format!("  - {}\n", file));
+        }
+    }
+    report
+}
+
+// Write the completion report of a block into the reports folder
+fn write_report(block: &Block, project_dir: &str) -> Result<PathBuf, String> {
+    let reports_dir = Path::new(project_dir).join(REPORTS_DIR);
+    fs::create_dir_all(&reports_dir)
+        .map_err(|e| format!("Failed to create reports directory: {}", e))?;
+
+    let report_path = reports_dir.join(format!("{}.md", block.block_id));
+    fs::write(&report_path, block_report(block))
+        .map_err(|e| format!("Failed to write report: {}", e))?;
+    Ok(report_path)
+}
+
+// Open a pull request for the current branch of the project with the GitHub CLI
+fn create_pull_request(block: &Block, project_config: &ProjectConfig) -> Result<String, String> {
+    let main_branch = project_config.main_branch.clone().unwrap_or("main".to_string());
+    let output = Command::new("gh")
+        .args(["pr", "create", "--base", &main_branch])
+        .args(["--title", &format!("Complete block {}", block.name)])
+        .args(["--body", &block_report(block)])
+        .current_dir(&project_config.project_home_directory)
+        .output()
+        .map_err(|e| format!("Failed to run gh: {}", e))?;
+
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+// Mark a block completed, publish the event and run the completion actions
+fn complete_block(block: &Block, criteria: &CompletionCriteria, project_config: &ProjectConfig, block_manager: &BlockConfigManager) -> Result<(), String> {
+    block_manager.set_block_status(&block.block_id, Some(BLOCK_STATUS_COMPLETED.to_string()))?;
+    block_manager.save_blocks_from("block_completion")?;
+
+    info!("Block {} ({}) completed", block.name, block.block_id);
+    log_stream::add_log(BLOCK_EVENTS_LOG_ID, format!("BLOCK_COMPLETED {} {}", block.block_id, block.name));
+
+    if criteria.generate_report {
+        match write_report(block, &project_config.project_home_directory) {
+            Ok(path) => log_stream::add_log(BLOCK_EVENTS_LOG_ID, format!("BLOCK_REPORT {} {}", block.block_id, path.display())),
+            Err(e) => error!("Failed to write the report of block {}: {}", block.block_id, e),
+        }
+    }
+
+    let mut pull_request_url = None;
+    if criteria.create_pull_request {
+        match create_pull_request(block, project_config) {
+            Ok(url) => {
+                log_stream::add_log(BLOCK_EVENTS_LOG_ID, format!("BLOCK_PULL_REQUEST {} {}", block.block_id, url));
+                pull_request_url = Some(url);
+            },
+            Err(e) => error!("Failed to create the pull request of block {}: {}", block.block_id, e),
+        }
+    }
+
+    webhooks::emit(WEBHOOK_EVENT_BLOCK_COMPLETED, serde_json::json!({
+        "block_id": block.block_id,
+        "block_name": block.name,
+        "tasks": block.todo_list.len(),
+        "pull_request_url": pull_request_url,
+    }));
+    Ok(())
+}
+
+// Fingerprint of the block state the criteria depend on, to skip re-running commands for unchanged blocks
+fn block_fingerprint(block: &Block, criteria: &CompletionCriteria) -> String {
+    let mut statuses: Vec<String> = block.todo_list.values()
+        .map(|t| format!("{}={}:{}", t.task_id, t.status, t.commit_id))
+        .collect();
+    statuses.sort();
+    format!("{}|{:?}", statuses.join(","), criteria)
+}
+
+// Start the background thread auto-closing blocks whose completion criteria hold
+pub fn start_completion_evaluator(project_manager: Arc<ProjectConfigManager>, block_manager: Arc<BlockConfigManager>) {
+    thread::spawn(move || {
+        // Fingerprints of the blocks evaluated as incomplete, keyed by block ID
+        let mut evaluated: HashMap<String, String> = HashMap::new();
+        loop {
+            thread::sleep(EVALUATOR_TICK);
+
+            let project_config = match project_manager.get_config() {
+                Ok(config) => config,
+                Err(_) => continue,
+            };
+            if project_config.project_home_directory.is_empty() || !Path::new(&project_config.project_home_directory).exists() {
+                continue;
+            }
+            let workflow = project_config.task_workflow.clone().unwrap_or_default();
+
+            let blocks = match block_manager.get_blocks() {
+                Ok(blocks) => blocks,
+                Err(e) => {
+                    error!("Failed to get blocks: {}", e);
+                    continue;
+                }
+            };
+
+            for block in blocks.iter().filter(|b| b.status.is_none()) {
+                let criteria = match &block.completion_criteria {
+                    Some(criteria) => criteria,
+                    None => continue,
+                };
+
+                let fingerprint = block_fingerprint(block, criteria);
+                if evaluated.get(&block.block_id) == Some(&fingerprint) {
+                    continue;
+                }
+
+                match evaluate_block(block, criteria, &workflow, &project_config.project_home_directory) {
+                    Ok(()) => {
+                        if let Err(e) = complete_block(block, criteria, &project_config, &block_manager) {
+                            error!("Failed to complete block {}: {}", block.block_id, e);
+                            evaluated.insert(block.block_id.clone(), fingerprint);
+                        }
+                    },
+                    Err(reason) => {
+                        info!("Block {} not complete: {}", block.block_id, reason);
+                        evaluated.insert(block.block_id.clone(), fingerprint);
+                    },
+                }
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Task;
+    use crate::project_config::TASK_STATUS_COMPLETED;
+
+    #[test]
+    fn test_parse_coverage() {
+        assert_eq!(parse_coverage("lines......: 81.2% (120 of 148)\nTOTAL 87.5%"), Some(87.5));
+        assert_eq!(parse_coverage("coverage: (92%)"), Some(92.0));
+        assert_eq!(parse_coverage("no coverage here"), None);
+    }
+
+    #[test]
+    fn test_evaluate_all_tasks_done() {
+        let workflow = TaskWorkflow::default();
+        let criteria = CompletionCriteria::default();
+        let mut block = Block::new("Auth".to_string(), "Authentication".to_string(), Vec::new(), Vec::new());
+        assert!(evaluate_block(&block, &criteria, &workflow, ".").is_err());
+
+        let mut task = Task::new("Add login".to_string());
+        task.status = "[TODO]".to_string();
+        block.todo_list.insert(task.task_id.clone(), task.clone());
+        assert_eq!(evaluate_block(&block, &criteria, &workflow, ".").unwrap_err(), "1 task(s) not completed");
+
+        task.status = TASK_STATUS_COMPLETED.to_string();
+        block.todo_list.insert(task.task_id.clone(), task);
+        assert!(evaluate_block(&block, &criteria, &workflow, ".").is_ok());
+    }
+}
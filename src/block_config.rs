@@ -1,22 +1,349 @@
+use crate::config_commit::commit_config_files;
 use crate::llm_handler::BlockConnection;
-use crate::models::{Block, Connections, InputConnection, OutputConnection, Task};
+use crate::migrations::{migrate_blocks, BLOCKS_SCHEMA_VERSION};
+use crate::models::{resolve_spec_sections, Block, Connections, Epic, InputConnection, OutputConnection, SpecSection, Task};
+use crate::project_config::{ConfigAutoCommit, TaskWorkflow, WEBHOOK_EVENT_BLOCK_CREATED, WEBHOOK_EVENT_TASK_STATUS_CHANGED};
+use crate::webhooks;
 use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
 use rand::{distributions::Alphanumeric, Rng};
 use serde_json;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::io::{self, Write};
-use std::path::Path;
-use std::sync::{Arc, Mutex};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, RwLock};
+use std::thread;
+use std::time::{Duration, SystemTime};
 
 // Default config file path
 pub const DEFAULT_BLOCK_CONFIG_FILE: &str = "blocks_config.json";
 
+// Epics file, stored next to the blocks config file
+pub const EPICS_CONFIG_FILE: &str = "epics_config.json";
+
+// Revision history of the blocks config, stored next to the blocks config file
+pub const BLOCKS_HISTORY_FILE: &str = "blocks_history.json";
+
+// Maximum number of revisions kept for undo
+pub const MAX_HISTORY_REVISIONS: usize = 20;
+
+// A saved revision of the blocks config (blocks and epics)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfigRevision {
+    pub timestamp: String,
+    pub blocks: Vec<Block>,
+    #[serde(default)]
+    pub epics: Vec<Epic>,
+    // User whose change replaced this revision
+    #[serde(default)]
+    pub changed_by: Option<String>,
+}
+
+// Undo/redo stacks of blocks config revisions (most recent last)
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ConfigHistory {
+    #[serde(default)]
+    pub undo: Vec<ConfigRevision>,
+    #[serde(default)]
+    pub redo: Vec<ConfigRevision>,
+}
+
+// In-memory change journal of the blocks, feeding the differential sync. Every saved or loaded
+// state is compared with the previous one and changed blocks get a new sequence number.
+#[derive(Debug)]
+struct ChangeJournal {
+    // Random ID of the journal; cursors of another journal (e.g. before a restart) are rejected
+    journal_id: String,
+    sequence: u64,
+    // Block ID -> (fingerprint of the block, sequence of its last change)
+    blocks: HashMap<String, (u64, u64)>,
+    // Deleted block ID -> sequence of the deletion
+    deleted: HashMap<String, u64>,
+}
+
+impl ChangeJournal {
+    fn new() -> Self {
+        ChangeJournal {
+            journal_id: rand::thread_rng().sample_iter(&Alphanumeric).take(8).map(char::from).collect(),
+            sequence: 0,
+            blocks: HashMap::new(),
+            deleted: HashMap::new(),
+        }
+    }
+
+    fn cursor(&self) -> String {
+        format!("{}-{}", self.journal_id, self.sequence)
+    }
+
+    // Sequence number of a cursor issued by this journal
+    fn parse_cursor(&self, cursor: &str) -> Option<u64> {
+        let (journal_id, sequence) = cursor.rsplit_once('-')?;
+        let sequence = sequence.parse::<u64>().ok()?;
+        (journal_id == self.journal_id && sequence <= self.sequence).then_some(sequence)
+    }
+
+    // Record the current blocks, bumping the sequence if any block was added, changed or removed.
+    // Returns a description of every change.
+    fn record(&mut self, blocks: &[Block]) -> Vec<String> {
+        let next = self.sequence + 1;
+        let mut changes = Vec::new();
+
+        let mut seen = HashSet::with_capacity(blocks.len());
+        for block in blocks {
+            let fingerprint = block_fingerprint(block);
+            seen.insert(block.block_id.as_str());
+            match self.blocks.get(&block.block_id) {
+                Some((previous, _)) if *previous == fingerprint => {},
+                previous => {
+                    let action = if previous.is_some() { "Updated" } else { "Added" };
+                    changes.push(format!("{} block {} ({})", action, block.name, block.block_id));
+                    self.blocks.insert(block.block_id.clone(), (fingerprint, next));
+                    self.deleted.remove(&block.block_id);
+                },
+            }
+        }
+
+        let removed: Vec<String> = self.blocks.keys().filter(|id| !seen.contains(&id.as_str())).cloned().collect();
+        for block_id in removed {
+            changes.push(format!("Deleted block {}", block_id));
+            self.blocks.remove(&block_id);
+            self.deleted.insert(block_id, next);
+        }
+
+        if !changes.is_empty() {
+            self.sequence = next;
+        }
+        changes
+    }
+}
+
+fn block_fingerprint(block: &Block) -> u64 {
+    use std::hash::{Hash, Hasher};
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    // Tasks are kept in a HashMap, serialize them in a stable order
+    let mut value = serde_json::to_value(block).unwrap_or_default();
+    if let Some(todo_list) = value.get_mut("todo_list").and_then(|t| t.as_object_mut()) {
+        let sorted: std::collections::BTreeMap<_, _> = std::mem::take(todo_list).into_iter().collect();
+        todo_list.extend(sorted);
+    }
+    value.to_string().hash(&mut hasher);
+    hasher.finish()
+}
+
+// Blocks changed since a cursor of the differential sync
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlockChanges {
+    // Cursor to pass as `since` in the next request
+    pub cursor: String,
+    // True when the cursor was missing or unknown; `blocks` then holds every block
+    pub reset: bool,
+    pub blocks: Vec<Block>,
+    pub deleted_block_ids: Vec<String>,
+}
+
+// How long to wait for the cross-process lock, and when a lock file is considered stale
+const FILE_LOCK_TIMEOUT: Duration = Duration::from_secs(10);
+const FILE_LOCK_STALE_AFTER: Duration = Duration::from_secs(30);
+
+// Advisory lock shared by all processes writing a file: a `<file>.lock` file created exclusively
+// and removed when the lock is dropped
+#[derive(Debug)]
+pub struct FileLock {
+    lock_file: String,
+}
+
+impl FileLock {
+    pub fn acquire(file: &str) -> Result<FileLock, String> {
+        let lock_file = format!("{}.lock", file);
+        let started = SystemTime::now();
+
+        loop {
+            match fs::OpenOptions::new().write(true).create_new(true).open(&lock_file) {
+                Ok(mut f) => {
+                    let _ = write!(f, "{}", std::process::id());
+                    return Ok(FileLock { lock_file });
+                },
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+                    // Remove locks left behind by a crashed process
+                    let stale = fs::metadata(&lock_file)
+                        .and_then(|m| m.modified())
+                        .map(|modified| modified.elapsed().unwrap_or_default() > FILE_LOCK_STALE_AFTER)
+                        .unwrap_or(false);
+                    if stale {
+                        let _ = fs::remove_file(&lock_file);
+                        continue;
+                    }
+
+                    if started.elapsed().unwrap_or_default() > FILE_LOCK_TIMEOUT {
+                        return Err(format!("Timed out waiting for lock on {}", file));
+                    }
+                    thread::sleep(Duration::from_millis(50));
+                },
+                Err(e) => return Err(format!("Failed to create lock file {}: {}", lock_file, e)),
+            }
+        }
+    }
+}
+
+impl Drop for FileLock {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.lock_file);
+    }
+}
+
+// Notify the webhooks of a task status change
+fn emit_task_status_changed(block_id: &str, task_id: &str, from: &str, to: &str) {
+    webhooks::emit(WEBHOOK_EVENT_TASK_STATUS_CHANGED, serde_json::json!({
+        "block_id": block_id,
+        "task_id": task_id,
+        "from": from,
+        "to": to,
+    }));
+}
+
+// Write a file atomically: write a temporary file next to it, then rename it over the target
+pub fn write_file_atomic(path: &str, contents: &[u8]) -> Result<(), String> {
+    let tmp_path = format!("{}.tmp.{}", path, std::process::id());
+
+    let mut file = fs::File::create(&tmp_path)
+        .map_err(|e| format!("Failed to create temporary file {}: {}", tmp_path, e))?;
+    file.write_all(contents)
+        .and_then(|_| file.sync_all())
+        .map_err(|e| {
+            let _ = fs::remove_file(&tmp_path);
+            format!("Failed to write temporary file {}: {}", tmp_path, e)
+        })?;
+    drop(file);
+
+    fs::rename(&tmp_path, path).map_err(|e| {
+        let _ = fs::remove_file(&tmp_path);
+        format!("Failed to replace {}: {}", path, e)
+    })
+}
+
+// Modification time and size of a file, used to detect writes by other processes
+fn file_stamp(path: &str) -> Option<(SystemTime, u64)> {
+    let metadata = fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+// Base name of the blocks config file; the extension selects the format
+pub const BLOCK_CONFIG_BASE_NAME: &str = "blocks_config";
+
+// Serialization format of the blocks config file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlocksFormat {
+    Json,
+    Yaml,
+    Toml,
+}
+
+// Versioned blocks config document
+#[derive(Serialize, Deserialize)]
+struct BlocksDocument {
+    schema_version: u32,
+    #[serde(default)]
+    blocks: Vec<Block>,
+}
+
+impl BlocksFormat {
+    // Parse a format name ("json", "yaml"/"yml", "toml")
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "json" => Some(BlocksFormat::Json),
+            "yaml" | "yml" => Some(BlocksFormat::Yaml),
+            "toml" => Some(BlocksFormat::Toml),
+            _ => None,
+        }
+    }
+
+    // Detect the format from the file extension, defaulting to JSON
+    pub fn from_path(path: &str) -> Self {
+        Path::new(path).extension()
+            .and_then(|ext| Self::from_name(&ext.to_string_lossy()))
+            .unwrap_or(BlocksFormat::Json)
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            BlocksFormat::Json => "json",
+            BlocksFormat::Yaml => "yaml",
+            BlocksFormat::Toml => "toml",
+        }
+    }
+
+    pub fn parse(&self, content: &str) -> Result<Vec<Block>, String> {
+        self.parse_document(content).map(|(blocks, _)| blocks)
+    }
+
+    // Parse a blocks config, upgrading older schema versions; also returns whether a migration ran
+    pub fn parse_document(&self, content: &str) -> Result<(Vec<Block>, bool), String> {
+        let mut document: serde_json::Value = match self {
+            BlocksFormat::Json => serde_json::from_str(content).map_err(|e| format!("Failed to parse JSON: {}", e))?,
+            BlocksFormat::Yaml => serde_yaml::from_str(content).map_err(|e| format!("Failed to parse YAML: {}", e))?,
+            BlocksFormat::Toml => toml::from_str(content).map_err(|e| format!("Failed to parse TOML: {}", e))?,
+        };
+
+        let migrated = migrate_blocks(&mut document)?;
+        let document: BlocksDocument = serde_json::from_value(document)
+            .map_err(|e| format!("Failed to parse blocks: {}", e))?;
+        Ok((document.blocks, migrated))
+    }
+
+    pub fn serialize(&self, blocks: &[Block]) -> Result<String, String> {
+        let document = BlocksDocument {
+            schema_version: BLOCKS_SCHEMA_VERSION,
+            blocks: blocks.to_vec(),
+        };
+        match self {
+            BlocksFormat::Json => serde_json::to_string_pretty(&document).map_err(|e| format!("Failed to serialize blocks to JSON: {}", e)),
+            BlocksFormat::Yaml => serde_yaml::to_string(&document).map_err(|e| format!("Failed to serialize blocks to YAML: {}", e)),
+            BlocksFormat::Toml => toml::to_string_pretty(&document).map_err(|e| format!("Failed to serialize blocks to TOML: {}", e)),
+        }
+    }
+}
+
+// Path of the blocks config file in a directory. An explicit format selects the file;
+// otherwise an existing file of any supported format is used, defaulting to JSON.
+pub fn resolve_blocks_config_path(dir: &Path, format: Option<&str>) -> Result<PathBuf, String> {
+    if let Some(name) = format.filter(|f| !f.is_empty()) {
+        let format = BlocksFormat::from_name(name)
+            .ok_or_else(|| format!("Unsupported blocks config format '{}'", name))?;
+        return Ok(dir.join(format!("{}.{}", BLOCK_CONFIG_BASE_NAME, format.extension())));
+    }
+
+    for ext in ["json", "yaml", "yml", "toml"] {
+        let path = dir.join(format!("{}.{}", BLOCK_CONFIG_BASE_NAME, ext));
+        if path.exists() {
+            return Ok(path);
+        }
+    }
+    Ok(dir.join(format!("{}.json", BLOCK_CONFIG_BASE_NAME)))
+}
+
 // Struct to manage block configurations
 #[derive(Debug)]
 pub struct BlockConfigManager {
     blocks: Arc<Mutex<Vec<Block>>>,
-    pub config_file: String,
+    epics: Arc<Mutex<Vec<Epic>>>,
+    config_file: RwLock<String>,
+    // Directory of the project data files (executions, queue, history...) when the blocks config
+    // lives in a separate spec directory, so they are never written to the spec repository
+    data_directory: RwLock<Option<String>>,
+    // Stamp of the blocks file as last loaded or written by this manager
+    disk_stamp: Mutex<Option<(SystemTime, u64)>>,
+    // Whether the in-memory state has changes that are not saved yet
+    dirty: AtomicBool,
+    // Blocks as last loaded or written by this manager, the base for merging external changes
+    saved_blocks: Mutex<Vec<Block>>,
+    // Whether the blocks config lives in a read-only spec repository
+    read_only: AtomicBool,
+    // Change journal of the differential sync
+    journal: Mutex<ChangeJournal>,
+    // Commit the config files to the project repository after every write
+    auto_commit: Mutex<Option<ConfigAutoCommit>>,
 }
 
 // Global singleton instance
@@ -34,30 +361,94 @@ impl BlockConfigManager {
     pub fn new(config_file: &str) -> Self {
         BlockConfigManager {
             blocks: Arc::new(Mutex::new(Vec::new())),
-            config_file: config_file.to_string(),
+            epics: Arc::new(Mutex::new(Vec::new())),
+            config_file: RwLock::new(config_file.to_string()),
+            data_directory: RwLock::new(None),
+            disk_stamp: Mutex::new(None),
+            dirty: AtomicBool::new(false),
+            saved_blocks: Mutex::new(Vec::new()),
+            read_only: AtomicBool::new(false),
+            journal: Mutex::new(ChangeJournal::new()),
+            auto_commit: Mutex::new(None),
         }
     }
 
+    // Path of the blocks config file
+    pub fn config_file(&self) -> String {
+        self.config_file.read().map(|f| f.clone()).unwrap_or_default()
+    }
+
+    // Point the manager at another blocks config file and load it (used when opening another project)
+    pub fn switch_config_file(&self, config_file: &str, read_only: bool) -> Result<Vec<Block>, String> {
+        match self.config_file.write() {
+            Ok(mut current) => *current = config_file.to_string(),
+            Err(_) => return Err("Failed to acquire lock on config file".to_string()),
+        }
+        self.set_disk_stamp(None);
+        self.set_read_only(read_only);
+        if let Ok(mut epics) = self.epics.lock() {
+            epics.clear();
+        }
+        // Cursors of the previous project are no longer valid
+        if let Ok(mut journal) = self.journal.lock() {
+            *journal = ChangeJournal::new();
+        }
+        self.load_blocks_from_file()
+    }
+
+    // Store the project data files in the given directory instead of next to the blocks config file
+    pub fn set_data_directory(&self, data_directory: Option<String>) {
+        if let Ok(mut current) = self.data_directory.write() {
+            *current = data_directory.filter(|d| !d.is_empty());
+        }
+    }
+
+    // Path of a spec file stored in the same directory as the blocks config file
+    fn spec_file(&self, file_name: &str) -> String {
+        let config_file = self.config_file();
+        match Path::new(&config_file).parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent.join(file_name).to_string_lossy().to_string(),
+            _ => file_name.to_string(),
+        }
+    }
+
+    // Path of a project data file: in the data directory if one is set, otherwise next to the blocks config file
+    pub fn project_data_file(&self, file_name: &str) -> String {
+        match self.data_directory.read().ok().and_then(|d| d.clone()) {
+            Some(data_directory) => Path::new(&data_directory).join(file_name).to_string_lossy().to_string(),
+            None => self.spec_file(file_name),
+        }
+    }
+
+    // Format of the blocks config file
+    pub fn format(&self) -> BlocksFormat {
+        BlocksFormat::from_path(&self.config_file())
+    }
+
+    // Path of the epics file, part of the spec like the blocks config
+    pub fn epics_file(&self) -> String {
+        self.spec_file(EPICS_CONFIG_FILE)
+    }
+
     // Load blocks from a JSON file
     pub fn load_blocks_from_file(&self) -> Result<Vec<Block>, String> {
-        let path = Path::new(&self.config_file);
+        let config_file = self.config_file();
+        let path = Path::new(&config_file);
 
         // Check if the file exists
         if !path.exists() {
-            return Err(format!("Config file {} does not exist", self.config_file));
+            return Err(format!("Config file {} does not exist", config_file));
         }
 
-        // Read the file
+        // Read the file, remembering its stamp to detect later writes by other processes
+        let stamp = file_stamp(&config_file);
         let file_content = match fs::read_to_string(path) {
             Ok(content) => content,
             Err(e) => return Err(format!("Failed to read config file: {}", e)),
         };
 
-        // Parse the JSON
-        let blocks: Vec<Block> = match serde_json::from_str(&file_content) {
-            Ok(blocks) => blocks,
-            Err(e) => return Err(format!("Failed to parse JSON: {}", e)),
-        };
+        // Parse the file in the format given by its extension, upgrading older schema versions
+        let (blocks, migrated) = self.format().parse_document(&file_content)?;
 
         // Update the in-memory state
         let mut blocks_lock = match self.blocks.lock() {
@@ -65,37 +456,420 @@ impl BlockConfigManager {
             Err(_) => return Err("Failed to acquire lock on blocks".to_string()),
         };
         *blocks_lock = blocks.clone();
+        // Journal the blocks under the blocks lock, so a snapshot and its cursor always match
+        self.record_changes(&blocks);
+        drop(blocks_lock);
+        self.set_saved_blocks(&blocks);
+
+        // Load the epics if the epics file exists
+        self.load_epics_from_file()?;
+
+        self.set_disk_stamp(stamp);
+        self.dirty.store(false, Ordering::SeqCst);
+
+        // Write the upgraded file back so it is only migrated once
+        if migrated && !self.is_read_only() {
+            let changes = self.write_blocks_to_file()?;
+            self.commit_config("schema migration", &changes);
+            println!("Migrated blocks config {} to schema version {}", config_file, BLOCKS_SCHEMA_VERSION);
+        }
 
         Ok(blocks)
     }
 
-    // Save blocks to a JSON file
+    // Make the blocks config read-only: modifications and saves are rejected
+    pub fn set_read_only(&self, read_only: bool) {
+        self.read_only.store(read_only, Ordering::SeqCst);
+    }
+
+    pub fn is_read_only(&self) -> bool {
+        self.read_only.load(Ordering::SeqCst)
+    }
+
+    fn check_writable(&self) -> Result<(), String> {
+        if self.is_read_only() {
+            return Err(format!("Blocks config {} is read-only", self.config_file()));
+        }
+        Ok(())
+    }
+
+    fn set_saved_blocks(&self, blocks: &[Block]) {
+        if let Ok(mut saved_blocks) = self.saved_blocks.lock() {
+            *saved_blocks = blocks.to_vec();
+        }
+    }
+
+    // Apply the unsaved changes on top of the blocks written by another process.
+    // Blocks changed on both sides are a conflict: nothing is merged and the unsaved changes are kept.
+    fn merge_disk_changes(&self) -> Result<(), String> {
+        let content = fs::read_to_string(self.config_file())
+            .map_err(|e| format!("Failed to read config file: {}", e))?;
+        let disk = self.format().parse(&content)?;
+
+        let mut blocks_lock = self.blocks.lock().map_err(|_| "Failed to acquire lock on blocks".to_string())?;
+        let saved = self.saved_blocks.lock().map_err(|_| "Failed to acquire lock on saved blocks".to_string())?.clone();
+
+        let fingerprints = |blocks: &[Block]| -> HashMap<String, serde_json::Value> {
+            blocks.iter()
+                .map(|b| (b.block_id.clone(), serde_json::to_value(b).unwrap_or_default()))
+                .collect()
+        };
+        let (base, local, theirs) = (fingerprints(&saved), fingerprints(&blocks_lock), fingerprints(&disk));
+
+        let mut block_ids: Vec<&String> = base.keys().chain(local.keys()).chain(theirs.keys()).collect();
+        block_ids.sort();
+        block_ids.dedup();
+
+        let mut merged = disk.clone();
+        let mut conflicts = Vec::new();
+        for block_id in block_ids {
+            let (base, local, theirs) = (base.get(block_id), local.get(block_id), theirs.get(block_id));
+            if local == base || local == theirs {
+                continue;
+            }
+            if theirs != base {
+                conflicts.push(block_id.clone());
+                continue;
+            }
+            match blocks_lock.iter().find(|b| &b.block_id == block_id) {
+                Some(block) => match merged.iter_mut().find(|b| &b.block_id == block_id) {
+                    Some(existing) => *existing = block.clone(),
+                    None => merged.push(block.clone()),
+                },
+                None => merged.retain(|b| &b.block_id != block_id),
+            }
+        }
+
+        if !conflicts.is_empty() {
+            return Err(format!(
+                "Blocks config {} was modified by another process; blocks {} changed on both sides. \
+                 The unsaved changes are kept; reload the blocks to discard them",
+                self.config_file(), conflicts.join(", ")
+            ));
+        }
+
+        *blocks_lock = merged;
+        drop(blocks_lock);
+        self.set_saved_blocks(&disk);
+        Ok(())
+    }
+
+    fn set_disk_stamp(&self, stamp: Option<(SystemTime, u64)>) {
+        if let Ok(mut disk_stamp) = self.disk_stamp.lock() {
+            *disk_stamp = stamp;
+        }
+    }
+
+    // Whether the blocks file was written by someone else since this manager last loaded or saved it
+    fn changed_on_disk(&self) -> bool {
+        let current = file_stamp(&self.config_file());
+        match self.disk_stamp.lock() {
+            Ok(disk_stamp) => current.is_some() && current != *disk_stamp,
+            Err(_) => false,
+        }
+    }
+
+    // Whether the in-memory blocks or epics have changes that are not saved yet
+    pub fn has_unsaved_changes(&self) -> bool {
+        self.dirty.load(Ordering::SeqCst)
+    }
+
+    // Reload the blocks if another process changed the file and there are no unsaved changes
+    pub fn refresh_if_changed(&self) -> Result<bool, String> {
+        if self.dirty.load(Ordering::SeqCst) || !self.changed_on_disk() {
+            return Ok(false);
+        }
+        self.load_blocks_from_file()?;
+        Ok(true)
+    }
+
+    // Lock the blocks for a modification, picking up external changes first
+    fn lock_blocks_for_update(&self) -> Result<MutexGuard<'_, Vec<Block>>, String> {
+        self.check_writable()?;
+        self.refresh_if_changed()?;
+        let lock = self.blocks.lock().map_err(|_| "Failed to acquire lock on blocks".to_string())?;
+        self.dirty.store(true, Ordering::SeqCst);
+        Ok(lock)
+    }
+
+    // Lock the epics for a modification, picking up external changes first
+    fn lock_epics_for_update(&self) -> Result<MutexGuard<'_, Vec<Epic>>, String> {
+        self.check_writable()?;
+        self.refresh_if_changed()?;
+        let lock = self.epics.lock().map_err(|_| "Failed to acquire lock on epics".to_string())?;
+        self.dirty.store(true, Ordering::SeqCst);
+        Ok(lock)
+    }
+
+    // Load epics from the epics file (missing file means no epics)
+    fn load_epics_from_file(&self) -> Result<(), String> {
+        let epics_file = self.epics_file();
+        let path = Path::new(&epics_file);
+        if !path.exists() {
+            return Ok(());
+        }
+
+        let file_content = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read epics file: {}", e))?;
+        let epics: Vec<Epic> = serde_json::from_str(&file_content)
+            .map_err(|e| format!("Failed to parse epics JSON: {}", e))?;
+
+        let mut epics_lock = match self.epics.lock() {
+            Ok(lock) => lock,
+            Err(_) => return Err("Failed to acquire lock on epics".to_string()),
+        };
+        *epics_lock = epics;
+
+        Ok(())
+    }
+
+    // Save epics to the epics file
+    fn save_epics_to_file(&self) -> Result<(), String> {
+        let epics_lock = match self.epics.lock() {
+            Ok(lock) => lock,
+            Err(_) => return Err("Failed to acquire lock on epics".to_string()),
+        };
+
+        // Don't create an epics file for projects that don't use epics
+        let epics_file = self.epics_file();
+        if epics_lock.is_empty() && !Path::new(&epics_file).exists() {
+            return Ok(());
+        }
+
+        let json = serde_json::to_string_pretty(&*epics_lock)
+            .map_err(|e| format!("Failed to serialize epics to JSON: {}", e))?;
+        write_file_atomic(&epics_file, json.as_bytes())
+    }
+
+    // Path of the revision history file
+    pub fn history_file(&self) -> String {
+        self.project_data_file(BLOCKS_HISTORY_FILE)
+    }
+
+    // Read the revision currently stored on disk, if any
+    fn read_revision_from_disk(&self) -> Option<ConfigRevision> {
+        let blocks = self.format().parse(&fs::read_to_string(&self.config_file()).ok()?).ok()?;
+        let epics: Vec<Epic> = fs::read_to_string(self.epics_file()).ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+
+        Some(ConfigRevision {
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            blocks,
+            epics,
+            changed_by: crate::request_user::current_user(),
+        })
+    }
+
+    // Load the revision history (missing file means empty history)
+    pub fn load_history(&self) -> Result<ConfigHistory, String> {
+        let history_file = self.history_file();
+        if !Path::new(&history_file).exists() {
+            return Ok(ConfigHistory::default());
+        }
+
+        let file_content = fs::read_to_string(&history_file)
+            .map_err(|e| format!("Failed to read history file: {}", e))?;
+        serde_json::from_str(&file_content)
+            .map_err(|e| format!("Failed to parse history JSON: {}", e))
+    }
+
+    // Save the revision history
+    fn save_history(&self, history: &ConfigHistory) -> Result<(), String> {
+        let json = serde_json::to_string(history)
+            .map_err(|e| format!("Failed to serialize history to JSON: {}", e))?;
+        write_file_atomic(&self.history_file(), json.as_bytes())
+    }
+
+    // Push the revision on disk to the undo history if the in-memory state differs from it
+    fn record_revision(&self) -> Result<(), String> {
+        let previous = match self.read_revision_from_disk() {
+            Some(revision) => revision,
+            None => return Ok(()),
+        };
+
+        let unchanged = {
+            let blocks_lock = self.blocks.lock().map_err(|_| "Failed to acquire lock on blocks".to_string())?;
+            let epics_lock = self.epics.lock().map_err(|_| "Failed to acquire lock on epics".to_string())?;
+            serde_json::to_value(&previous.blocks).ok() == serde_json::to_value(&*blocks_lock).ok()
+                && serde_json::to_value(&previous.epics).ok() == serde_json::to_value(&*epics_lock).ok()
+        };
+        if unchanged {
+            return Ok(());
+        }
+
+        let mut history = self.load_history()?;
+        history.undo.push(previous);
+        if history.undo.len() > MAX_HISTORY_REVISIONS {
+            let excess = history.undo.len() - MAX_HISTORY_REVISIONS;
+            history.undo.drain(..excess);
+        }
+        history.redo.clear();
+        self.save_history(&history)
+    }
+
+    // Save blocks to a JSON file, recording the previous revision for undo.
+    // If another process changed the file since it was loaded, the unsaved changes are merged
+    // into its contents; the save fails without writing if both changed the same block.
     pub fn save_blocks_to_file(&self) -> Result<(), String> {
+        self.save_blocks_from("forge")
+    }
+
+    // Save blocks like `save_blocks_to_file`, naming the handler or tool that made the change
+    // in the auto-commit message
+    pub fn save_blocks_from(&self, source: &str) -> Result<(), String> {
+        self.check_writable()?;
+        let changes = {
+            let _lock = FileLock::acquire(&self.config_file())?;
+
+            if self.changed_on_disk() {
+                self.merge_disk_changes()?;
+            }
+
+            self.record_revision()?;
+            self.write_blocks_to_file()?
+        };
+        self.commit_config(source, &changes);
+        Ok(())
+    }
+
+    // Restore the previous revision; returns the restored blocks
+    pub fn undo(&self) -> Result<Vec<Block>, String> {
+        self.step_history(true)
+    }
+
+    // Re-apply the last undone revision; returns the restored blocks
+    pub fn redo(&self) -> Result<Vec<Block>, String> {
+        self.step_history(false)
+    }
+
+    // Move one revision through the history, saving the current state on the opposite stack
+    fn step_history(&self, undo: bool) -> Result<Vec<Block>, String> {
+        self.check_writable()?;
+        let lock = FileLock::acquire(&self.config_file())?;
+        let mut history = self.load_history()?;
+        let revision = if undo { history.undo.pop() } else { history.redo.pop() }
+            .ok_or_else(|| format!("Nothing to {}", if undo { "undo" } else { "redo" }))?;
+
+        if let Some(current) = self.read_revision_from_disk() {
+            if undo { history.redo.push(current) } else { history.undo.push(current) }
+        }
+
+        {
+            let mut blocks_lock = self.blocks.lock().map_err(|_| "Failed to acquire lock on blocks".to_string())?;
+            *blocks_lock = revision.blocks.clone();
+            let mut epics_lock = self.epics.lock().map_err(|_| "Failed to acquire lock on epics".to_string())?;
+            *epics_lock = revision.epics.clone();
+        }
+
+        let changes = self.write_blocks_to_file()?;
+        self.save_history(&history)?;
+        drop(lock);
+        self.commit_config(if undo { "undo" } else { "redo" }, &changes);
+
+        Ok(revision.blocks)
+    }
+
+    // Write blocks (and epics) to the JSON files without touching the history, returning the changes
+    // to commit
+    fn write_blocks_to_file(&self) -> Result<Vec<String>, String> {
         let blocks_lock = match self.blocks.lock() {
             Ok(lock) => lock,
             Err(_) => return Err("Failed to acquire lock on blocks".to_string()),
         };
 
-        // Serialize the blocks to JSON
-        let json = match serde_json::to_string_pretty(&*blocks_lock) {
-            Ok(json) => json,
-            Err(e) => return Err(format!("Failed to serialize blocks to JSON: {}", e)),
-        };
+        // Serialize the blocks in the format of the config file
+        let content = self.format().serialize(&blocks_lock)?;
 
         // Write to the file
-        let mut file = match fs::File::create(&self.config_file) {
-            Ok(file) => file,
-            Err(e) => return Err(format!("Failed to create config file: {}", e)),
+        write_file_atomic(&self.config_file(), content.as_bytes())?;
+        let changes = self.record_changes(&blocks_lock);
+        self.set_saved_blocks(&blocks_lock);
+        drop(blocks_lock);
+
+        self.save_epics_to_file()?;
+        self.set_disk_stamp(file_stamp(&self.config_file()));
+        self.dirty.store(false, Ordering::SeqCst);
+        Ok(changes)
+    }
+
+    // Record a saved or loaded state of the blocks in the change journal, returning the changes
+    fn record_changes(&self, blocks: &[Block]) -> Vec<String> {
+        match self.journal.lock() {
+            Ok(mut journal) => journal.record(blocks),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    // Enable or disable committing the config files to the project repository after every write
+    pub fn set_auto_commit(&self, auto_commit: Option<ConfigAutoCommit>) {
+        if let Ok(mut current) = self.auto_commit.lock() {
+            *current = auto_commit.filter(|a| a.enabled);
+        }
+    }
+
+    // Commit the config files if auto-commit is enabled. Runs after the file lock of the save is released:
+    // git may outlast FILE_LOCK_STALE_AFTER, after which other processes would take over the lock. A failed
+    // commit doesn't fail the save, the files are already written.
+    fn commit_config(&self, source: &str, changes: &[String]) {
+        let auto_commit = match self.auto_commit.lock().ok().and_then(|a| a.clone()) {
+            Some(auto_commit) => auto_commit,
+            None => return,
         };
 
-        match file.write_all(json.as_bytes()) {
-            Ok(_) => Ok(()),
-            Err(e) => Err(format!("Failed to write to config file: {}", e)),
+        let mut message = format!("Update blocks config ({})", source);
+        if !changes.is_empty() {
+            message.push_str("\n\n");
+            message.push_str(&changes.iter().map(|c| format!("- {}", c)).collect::<Vec<_>>().join("\n"));
+        }
+
+        let files = [self.config_file(), self.epics_file()];
+        if let Err(e) = commit_config_files(&files, &message, auto_commit.branch.as_deref()) {
+            eprintln!("Failed to commit blocks config: {}", e);
         }
     }
 
+    // Blocks added or changed, and IDs of the blocks deleted, since a cursor of the differential sync.
+    // A missing or unknown cursor returns all blocks with `reset` set.
+    pub fn changes_since(&self, cursor: Option<&str>) -> Result<BlockChanges, String> {
+        self.refresh_if_changed()?;
+        // Writers journal their changes while holding the blocks lock: taking both locks keeps
+        // the blocks and the cursor consistent
+        let blocks_lock = self.blocks.lock().map_err(|_| "Failed to acquire lock on blocks".to_string())?;
+        let journal = self.journal.lock().map_err(|_| "Failed to acquire lock on change journal".to_string())?;
+        let blocks = blocks_lock.clone();
+        drop(blocks_lock);
+
+        let since = match cursor.and_then(|c| journal.parse_cursor(c)) {
+            Some(since) => since,
+            None => return Ok(BlockChanges {
+                cursor: journal.cursor(),
+                reset: true,
+                blocks,
+                deleted_block_ids: Vec::new(),
+            }),
+        };
+
+        let changed = blocks.into_iter()
+            .filter(|b| journal.blocks.get(&b.block_id).map(|(_, seq)| *seq > since).unwrap_or(true))
+            .collect();
+        let mut deleted_block_ids: Vec<String> = journal.deleted.iter()
+            .filter(|(_, seq)| **seq > since)
+            .map(|(id, _)| id.clone())
+            .collect();
+        deleted_block_ids.sort();
+
+        Ok(BlockChanges {
+            cursor: journal.cursor(),
+            reset: false,
+            blocks: changed,
+            deleted_block_ids,
+        })
+    }
+
     // Get all blocks
     pub fn get_blocks(&self) -> Result<Vec<Block>, String> {
+        self.refresh_if_changed()?;
         let blocks_lock = match self.blocks.lock() {
             Ok(lock) => lock,
             Err(_) => return Err("Failed to acquire lock on blocks".to_string()),
@@ -106,10 +880,7 @@ impl BlockConfigManager {
 
     // Add a new block
     pub fn add_block(&self, mut block: Block) -> Result<(), String> {
-        let mut blocks_lock = match self.blocks.lock() {
-            Ok(lock) => lock,
-            Err(_) => return Err("Failed to acquire lock on blocks".to_string()),
-        };
+        let mut blocks_lock = self.lock_blocks_for_update()?;
 
         // Check if a block with the same name already exists
         if blocks_lock.iter().any(|b| b.name == block.name) {
@@ -127,21 +898,24 @@ impl BlockConfigManager {
             block.block_id = block_id;
         }
 
+        webhooks::emit(WEBHOOK_EVENT_BLOCK_CREATED, serde_json::json!({ "block_id": block.block_id, "name": block.name }));
         blocks_lock.push(block);
         Ok(())
     }
 
     // Update an existing block
     pub fn update_block(&self, block: Block) -> Result<(), String> {
-        let mut blocks_lock = match self.blocks.lock() {
-            Ok(lock) => lock,
-            Err(_) => return Err("Failed to acquire lock on blocks".to_string()),
-        };
+        let mut blocks_lock = self.lock_blocks_for_update()?;
 
         // Find the block to update by block_id
         let index = blocks_lock.iter().position(|b| b.block_id == block.block_id);
         match index {
             Some(i) => {
+                for task in block.todo_list.values() {
+                    if let Some(previous) = blocks_lock[i].todo_list.get(&task.task_id).filter(|t| t.status != task.status) {
+                        emit_task_status_changed(&block.block_id, &task.task_id, &previous.status, &task.status);
+                    }
+                }
                 blocks_lock[i] = block;
                 Ok(())
             },
@@ -149,12 +923,21 @@ impl BlockConfigManager {
         }
     }
 
+    // Set the status of a block (None reopens it)
+    pub fn set_block_status(&self, block_id: &str, status: Option<String>) -> Result<(), String> {
+        let mut blocks_lock = self.lock_blocks_for_update()?;
+
+        let block = blocks_lock.iter_mut()
+            .find(|b| b.block_id == block_id)
+            .ok_or_else(|| format!("Block with ID {} not found", block_id))?;
+        block.status = status;
+
+        Ok(())
+    }
+
     // Delete a block
     pub fn delete_block(&self, block_id: &str) -> Result<(), String> {
-        let mut blocks_lock = match self.blocks.lock() {
-            Ok(lock) => lock,
-            Err(_) => return Err("Failed to acquire lock on blocks".to_string()),
-        };
+        let mut blocks_lock = self.lock_blocks_for_update()?;
 
         // Find the block to delete
         let index = blocks_lock.iter().position(|b| b.block_id == block_id);
@@ -169,10 +952,7 @@ impl BlockConfigManager {
 
     // Add a todo item to a block
     pub fn add_task_item(&self, block_id: &str, todo_item: &str) -> Result<(), String> {
-        let mut blocks_lock = match self.blocks.lock() {
-            Ok(lock) => lock,
-            Err(_) => return Err("Failed to acquire lock on blocks".to_string()),
-        };
+        let mut blocks_lock = self.lock_blocks_for_update()?;
 
         // Find the block to update
         let index = blocks_lock.iter().position(|b| b.block_id == block_id);
@@ -189,10 +969,7 @@ impl BlockConfigManager {
 
     // Add a full Task object to a block
     pub fn add_task(&self, block_id: &str, task: Task) -> Result<String, String> {
-        let mut blocks_lock = match self.blocks.lock() {
-            Ok(lock) => lock,
-            Err(_) => return Err("Failed to acquire lock on blocks".to_string()),
-        };
+        let mut blocks_lock = self.lock_blocks_for_update()?;
 
         // Find the block to update
         let index = blocks_lock.iter().position(|b| b.block_id == block_id);
@@ -206,13 +983,139 @@ impl BlockConfigManager {
         }
     }
 
-    // Remove a todo item from a block
-    pub fn remove_task_item(&self, block_id: &str, task_id: String) -> Result<(), String> {
-        let mut blocks_lock = match self.blocks.lock() {
+    // Update the status of a task, enforcing the workflow transition rules
+    pub fn update_task_status(&self, block_id: &str, task_id: &str, status: &str, workflow: &TaskWorkflow) -> Result<String, String> {
+        let mut blocks_lock = self.lock_blocks_for_update()?;
+
+        let block = blocks_lock.iter_mut()
+            .find(|b| b.block_id == block_id)
+            .ok_or_else(|| format!("Block with ID {} not found", block_id))?;
+
+        let task = block.todo_list.get_mut(task_id)
+            .ok_or_else(|| format!("Task with ID {} not found in block {}", task_id, block_id))?;
+
+        let new_status = workflow.validate_transition(&task.status, status)?;
+        if task.status != new_status {
+            emit_task_status_changed(block_id, task_id, &task.status, &new_status);
+        }
+        task.status = new_status.clone();
+
+        Ok(new_status)
+    }
+
+    // Get all epics
+    pub fn get_epics(&self) -> Result<Vec<Epic>, String> {
+        self.refresh_if_changed()?;
+        let epics_lock = match self.epics.lock() {
             Ok(lock) => lock,
-            Err(_) => return Err("Failed to acquire lock on blocks".to_string()),
+            Err(_) => return Err("Failed to acquire lock on epics".to_string()),
         };
 
+        Ok(epics_lock.clone())
+    }
+
+    // Add a new epic, returning its ID
+    pub fn add_epic(&self, mut epic: Epic) -> Result<String, String> {
+        let mut epics_lock = self.lock_epics_for_update()?;
+
+        if epics_lock.iter().any(|e| e.name == epic.name) {
+            return Err(format!("Epic with name {} already exists", epic.name));
+        }
+
+        if epic.epic_id.is_empty() {
+            epic.epic_id = Epic::new(String::new(), String::new()).epic_id;
+        }
+
+        let epic_id = epic.epic_id.clone();
+        epics_lock.push(epic);
+        Ok(epic_id)
+    }
+
+    // Find an epic by name or create it, returning its ID
+    pub fn find_or_create_epic(&self, name: &str, description: &str) -> Result<String, String> {
+        if let Some(epic) = self.get_epics()?.iter().find(|e| e.name == name) {
+            return Ok(epic.epic_id.clone());
+        }
+        self.add_epic(Epic::new(name.to_string(), description.to_string()))
+    }
+
+    // Update an existing epic
+    pub fn update_epic(&self, epic: Epic) -> Result<(), String> {
+        let mut epics_lock = self.lock_epics_for_update()?;
+
+        match epics_lock.iter().position(|e| e.epic_id == epic.epic_id) {
+            Some(i) => {
+                epics_lock[i] = epic;
+                Ok(())
+            },
+            None => Err(format!("Epic with ID {} not found", epic.epic_id)),
+        }
+    }
+
+    // Delete an epic and detach its blocks
+    pub fn delete_epic(&self, epic_id: &str) -> Result<(), String> {
+        {
+            let mut epics_lock = self.lock_epics_for_update()?;
+
+            match epics_lock.iter().position(|e| e.epic_id == epic_id) {
+                Some(i) => {
+                    epics_lock.remove(i);
+                },
+                None => return Err(format!("Epic with ID {} not found", epic_id)),
+            }
+        }
+
+        let mut blocks_lock = self.lock_blocks_for_update()?;
+        for block in blocks_lock.iter_mut().filter(|b| b.epic_id.as_deref() == Some(epic_id)) {
+            block.epic_id = None;
+        }
+
+        Ok(())
+    }
+
+    // Fill in the line ranges of block spec sources that were recorded by heading only
+    // (e.g. blocks created through MCP tools while processing a specification)
+    pub fn link_spec_sources(&self, sections: &[SpecSection]) -> Result<usize, String> {
+        let mut blocks_lock = self.lock_blocks_for_update()?;
+
+        let mut linked = 0;
+        for block in blocks_lock.iter_mut() {
+            for source in block.spec_sources.iter_mut().filter(|s| s.line_start == 0) {
+                if let Some(resolved) = resolve_spec_sections(sections, &[source.heading.clone()]).pop() {
+                    if resolved.line_start != 0 {
+                        *source = resolved;
+                        linked += 1;
+                    }
+                }
+            }
+        }
+
+        Ok(linked)
+    }
+
+    // Assign a block to an epic (or detach it with None)
+    pub fn assign_block_to_epic(&self, block_id: &str, epic_id: Option<String>) -> Result<(), String> {
+        if let Some(epic_id) = &epic_id {
+            if !self.get_epics()?.iter().any(|e| &e.epic_id == epic_id) {
+                return Err(format!("Epic with ID {} not found", epic_id));
+            }
+        }
+
+        let mut blocks_lock = self.lock_blocks_for_update()?;
+
+        match blocks_lock.iter_mut().find(|b| b.block_id == block_id) {
+            Some(block) => {
+                block.epic_id = epic_id;
+                Ok(())
+            },
+            None => Err(format!("Block with ID {} not found", block_id)),
+        }
+    }
+
+    // Remove a todo item from a block
+    pub fn remove_task_item(&self, block_id: &str, task_id: String) -> Result<(), String> {
+        let mut blocks_lock = self.lock_blocks_for_update()?;
+
         // Find the block to update
         let block_index = blocks_lock.iter().position(|b| b.block_id == block_id);
         match block_index {
@@ -312,6 +1215,12 @@ pub fn generate_sample_config(filename: &str) -> Result<(), io::Error> {
                 output_connections,
             },
             todo_list: tasks,
+            epic_id: None,
+            spec_sources: Vec::new(),
+            tech_hints: None,
+            completion_criteria: None,
+            status: None,
+            env: Vec::new(),
         };
 
         blocks.push(block);
@@ -357,9 +1266,140 @@ pub fn load_blocks_from_file(filename: &str) -> Vec<Block> {
         }
     };
 
-    // Parse the JSON
-    serde_json::from_str(&file_content).unwrap_or_else(|e| {
-        eprintln!("Failed to parse JSON: {}", e);
+    // Parse the file in the format given by its extension
+    BlocksFormat::from_path(filename).parse(&file_content).unwrap_or_else(|e| {
+        eprintln!("{}", e);
         Vec::new()
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_undo_redo_restores_revisions() {
+        let dir = tempfile::tempdir().unwrap();
+        let config_file = dir.path().join("blocks_config.json");
+        let manager = BlockConfigManager::new(config_file.to_str().unwrap());
+
+        manager.add_block(Block::new("First".to_string(), String::new(), Vec::new(), Vec::new())).unwrap();
+        manager.save_blocks_to_file().unwrap();
+        manager.add_block(Block::new("Second".to_string(), String::new(), Vec::new(), Vec::new())).unwrap();
+        manager.save_blocks_to_file().unwrap();
+
+        let restored = manager.undo().unwrap();
+        assert_eq!(restored.len(), 1);
+        assert_eq!(manager.load_blocks_from_file().unwrap().len(), 1);
+
+        let restored = manager.redo().unwrap();
+        assert_eq!(restored.len(), 2);
+        assert!(manager.redo().is_err());
+    }
+    #[test]
+    fn test_save_detects_concurrent_writes() {
+        let dir = tempfile::tempdir().unwrap();
+        let config_file = dir.path().join("blocks_config.json");
+        let first = BlockConfigManager::new(config_file.to_str().unwrap());
+        let second = BlockConfigManager::new(config_file.to_str().unwrap());
+
+        first.add_block(Block::new("First".to_string(), String::new(), Vec::new(), Vec::new())).unwrap();
+        first.save_blocks_to_file().unwrap();
+
+        // Unmodified managers pick up external changes before updating
+        second.add_block(Block::new("Second".to_string(), String::new(), Vec::new(), Vec::new())).unwrap();
+        assert_eq!(second.get_blocks().unwrap().len(), 2);
+
+        // Unsaved changes racing with another writer are merged into its changes
+        first.add_block(Block::new("Third".to_string(), String::new(), Vec::new(), Vec::new())).unwrap();
+        second.save_blocks_to_file().unwrap();
+        first.save_blocks_to_file().unwrap();
+        assert_eq!(first.get_blocks().unwrap().len(), 3);
+        assert_eq!(BlockConfigManager::new(config_file.to_str().unwrap()).load_blocks_from_file().unwrap().len(), 3);
+
+        // Both changing the same block is rejected, keeping the unsaved changes
+        let mut block = second.get_blocks().unwrap().into_iter().find(|b| b.name == "First").unwrap();
+        block.description = "Changed by first".to_string();
+        first.update_block(block.clone()).unwrap();
+        block.description = "Changed by second".to_string();
+        second.update_block(block).unwrap();
+        second.save_blocks_to_file().unwrap();
+        assert!(first.save_blocks_to_file().is_err());
+        assert!(first.get_blocks().unwrap().iter().any(|b| b.description == "Changed by first"));
+        assert!(!Path::new(&format!("{}.lock", config_file.display())).exists());
+    }
+    #[test]
+    fn test_switch_config_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let other_file = dir.path().join("other").join("blocks_config.json");
+        fs::create_dir_all(other_file.parent().unwrap()).unwrap();
+        let other = BlockConfigManager::new(other_file.to_str().unwrap());
+        other.add_block(Block::new("Other".to_string(), String::new(), Vec::new(), Vec::new())).unwrap();
+        other.save_blocks_to_file().unwrap();
+
+        let manager = BlockConfigManager::new(dir.path().join("blocks_config.json").to_str().unwrap());
+        manager.add_block(Block::new("First".to_string(), String::new(), Vec::new(), Vec::new())).unwrap();
+        manager.save_blocks_to_file().unwrap();
+
+        let blocks = manager.switch_config_file(other_file.to_str().unwrap(), true).unwrap();
+        assert_eq!(blocks[0].name, "Other");
+        assert_eq!(manager.config_file(), other_file.to_str().unwrap());
+        assert!(manager.add_block(Block::new("Second".to_string(), String::new(), Vec::new(), Vec::new())).is_err());
+
+        // Data files stay out of a separate spec directory, epics stay with the spec
+        manager.set_data_directory(Some(dir.path().to_string_lossy().to_string()));
+        assert_eq!(manager.history_file(), dir.path().join(BLOCKS_HISTORY_FILE).to_string_lossy());
+        assert_eq!(manager.epics_file(), other_file.parent().unwrap().join(EPICS_CONFIG_FILE).to_string_lossy());
+    }
+    #[test]
+    fn test_changes_since_cursor() {
+        let dir = tempfile::tempdir().unwrap();
+        let manager = BlockConfigManager::new(dir.path().join("blocks_config.json").to_str().unwrap());
+        manager.add_block(Block::new("First".to_string(), String::new(), Vec::new(), Vec::new())).unwrap();
+        manager.add_block(Block::new("Second".to_string(), String::new(), Vec::new(), Vec::new())).unwrap();
+        manager.save_blocks_to_file().unwrap();
+
+        let full = manager.changes_since(None).unwrap();
+        assert!(full.reset);
+        assert_eq!(full.blocks.len(), 2);
+
+        // Nothing changed since the cursor
+        let unchanged = manager.changes_since(Some(&full.cursor)).unwrap();
+        assert!(!unchanged.reset);
+        assert!(unchanged.blocks.is_empty());
+        assert_eq!(unchanged.cursor, full.cursor);
+
+        let mut first = full.blocks.iter().find(|b| b.name == "First").unwrap().clone();
+        let second_id = full.blocks.iter().find(|b| b.name == "Second").unwrap().block_id.clone();
+        first.description = "Updated".to_string();
+        manager.update_block(first).unwrap();
+        manager.delete_block(&second_id).unwrap();
+        manager.save_blocks_to_file().unwrap();
+
+        let delta = manager.changes_since(Some(&full.cursor)).unwrap();
+        assert!(!delta.reset);
+        assert_eq!(delta.blocks.len(), 1);
+        assert_eq!(delta.blocks[0].description, "Updated");
+        assert_eq!(delta.deleted_block_ids, vec![second_id]);
+
+        // Cursors of another journal force a full resync
+        assert!(manager.changes_since(Some("unknown-1")).unwrap().reset);
+    }
+    #[test]
+    fn test_blocks_formats_round_trip() {
+        let mut block = Block::new("Parser".to_string(), "Parses input".to_string(), Vec::new(), Vec::new());
+        let task = Task::new("Write the lexer".to_string());
+        block.todo_list.insert(task.task_id.clone(), task);
+        let blocks = vec![block];
+
+        for format in [BlocksFormat::Json, BlocksFormat::Yaml, BlocksFormat::Toml] {
+            let content = format.serialize(&blocks).unwrap();
+            let parsed = format.parse(&content).unwrap();
+            assert_eq!(parsed.len(), 1, "{:?}", format);
+            assert_eq!(parsed[0].todo_list.len(), 1, "{:?}", format);
+        }
+
+        assert_eq!(BlocksFormat::from_path("project/blocks_config.yml"), BlocksFormat::Yaml);
+        assert_eq!(BlocksFormat::from_path("blocks_config"), BlocksFormat::Json);
+    }
+}
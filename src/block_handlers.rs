@@ -1,17 +1,37 @@
+use actix_web::http::header::CACHE_CONTROL;
+use actix_web::web::Bytes;
 use actix_web::{web, HttpResponse, Responder};
+use futures::stream::StreamExt;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
+use std::collections::HashMap;
 use std::sync::Arc;
 use tracing::{error, info};
 use crate::block_config::{generate_sample_config, BlockConfigManager};
-use crate::llm_handler::{auto_complete_description, enhance_description, generate_tasks, process_specification, GeneratedBlock, LLMProvider};
-use crate::models::{Block, Task};
-use crate::project_config::ProjectConfigManager;
+use crate::ci_status;
+use crate::llm_handler::{auto_complete_description, auto_complete_description_stream, enhance_description, enhance_description_stream, generate_tasks, process_specification, GeneratedBlock, LLMProvider};
+use crate::models::{blocks_to_spec_markdown, build_ownership_map, parse_spec_sections, resolve_spec_sections, Block, Effort, Epic, Task};
+use crate::project_config::{validate_timeout_minutes, LLMOperation, ProjectConfigManager};
+use crate::request_user;
+use crate::task_csv::{export_tasks_csv, import_tasks_csv};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::UnboundedReceiverStream;
 
 // Define a response type for block dependencies
 #[derive(Serialize)]
 pub struct BlockDependenciesResponse {
     pub tasks: Vec<TaskDependency>,
+    pub epic_id: Option<String>,
+    pub epic_dependencies: Vec<EpicDependency>,
+}
+
+// Dependency from one epic to another, derived from block connections and task dependencies
+#[derive(Serialize, Clone, PartialEq)]
+pub struct EpicDependency {
+    pub from_epic_id: String,
+    pub to_epic_id: String,
+    pub from_block_id: String,
+    pub to_block_id: String,
 }
 
 #[derive(Serialize)]
@@ -68,20 +88,113 @@ pub struct AppState {
     pub project_manager: Arc<ProjectConfigManager>,
 }
 
+// Save the blocks on the blocking thread pool: the save waits for the cross-process file lock
+pub async fn save_blocks(block_manager: &Arc<BlockConfigManager>, source: &'static str) -> Result<(), String> {
+    let block_manager = block_manager.clone();
+    web::block(request_user::with_current_user(move || block_manager.save_blocks_from(source))).await
+        .map_err(|e| e.to_string())?
+}
+
 // API endpoint to get blocks
-pub async fn get_blocks_handler(data: web::Data<AppState>) -> impl Responder {
-    match data.block_manager.get_blocks() {
-        Ok(blocks) => HttpResponse::Ok().json(blocks),
-        Err(e) => HttpResponse::InternalServerError().body(e),
+pub async fn get_blocks_handler(data: web::Data<AppState>, query: web::Query<BlockListQuery>) -> impl Responder {
+    let blocks = match data.block_manager.get_blocks() {
+        Ok(blocks) => blocks,
+        Err(e) => return HttpResponse::InternalServerError().body(e),
+    };
+    match list_blocks(blocks, &query) {
+        Ok((total, blocks)) => HttpResponse::Ok()
+            .insert_header(("X-Total-Count", total.to_string()))
+            .json(blocks),
+        Err(e) => HttpResponse::BadRequest().body(e),
     }
 }
 
+// Query of the block listing; without parameters every block is returned with its tasks
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct BlockListQuery {
+    pub offset: Option<usize>,
+    pub limit: Option<usize>,
+    // name, block_id, status, epic_id or task_count; a leading "-" sorts in descending order
+    pub sort: Option<String>,
+    // Comma-separated block statuses, "open" for the blocks without one
+    pub status: Option<String>,
+    // Comma-separated task statuses; other tasks are left out, and so are the blocks without any
+    pub task_status: Option<String>,
+    pub epic_id: Option<String>,
+    // full (default), summary (ID, name and status of the tasks) or none
+    pub tasks: Option<String>,
+}
+
+fn comma_separated(value: &Option<String>) -> Option<Vec<String>> {
+    value.as_deref()
+        .map(|v| v.split(',').map(|s| s.trim().to_lowercase()).filter(|s| !s.is_empty()).collect::<Vec<_>>())
+        .filter(|values| !values.is_empty())
+}
+
+// Filter, sort and page the blocks, returning the number of matching blocks with the page
+pub fn list_blocks(mut blocks: Vec<Block>, query: &BlockListQuery) -> Result<(usize, Vec<serde_json::Value>), String> {
+    let tasks = query.tasks.as_deref().unwrap_or("full");
+    if !matches!(tasks, "full" | "summary" | "none") {
+        return Err(format!("Unknown tasks option '{}', expected full, summary or none", tasks));
+    }
+
+    if let Some(statuses) = comma_separated(&query.status) {
+        blocks.retain(|b| statuses.contains(&b.status.as_deref().unwrap_or("open").to_lowercase()));
+    }
+    if let Some(epic_id) = query.epic_id.as_deref().filter(|e| !e.is_empty()) {
+        blocks.retain(|b| b.epic_id.as_deref() == Some(epic_id));
+    }
+    if let Some(statuses) = comma_separated(&query.task_status) {
+        for block in &mut blocks {
+            block.todo_list.retain(|_, task| statuses.contains(&task.status.to_lowercase()));
+        }
+        blocks.retain(|b| !b.todo_list.is_empty());
+    }
+
+    if let Some(sort) = query.sort.as_deref().filter(|s| !s.is_empty()) {
+        let (field, descending) = match sort.strip_prefix('-') {
+            Some(field) => (field, true),
+            None => (sort, false),
+        };
+        match field {
+            "name" => blocks.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase())),
+            "block_id" => blocks.sort_by(|a, b| a.block_id.cmp(&b.block_id)),
+            "status" => blocks.sort_by(|a, b| a.status.cmp(&b.status)),
+            "epic_id" => blocks.sort_by(|a, b| a.epic_id.cmp(&b.epic_id)),
+            "task_count" => blocks.sort_by_key(|b| b.todo_list.len()),
+            _ => return Err(format!("Unknown sort field '{}'", field)),
+        }
+        if descending {
+            blocks.reverse();
+        }
+    }
+
+    let total = blocks.len();
+    let page = blocks.into_iter()
+        .skip(query.offset.unwrap_or(0))
+        .take(query.limit.unwrap_or(usize::MAX))
+        .map(|block| {
+            let mut value = serde_json::to_value(&block).unwrap_or_default();
+            match tasks {
+                "summary" => value["todo_list"] = block.todo_list.iter()
+                    .map(|(id, task)| (id.clone(), json!({ "task_id": task.task_id, "task_name": task.task_name, "status": task.status })))
+                    .collect(),
+                "none" => value["todo_list"] = json!({}),
+                _ => {}
+            }
+            value
+        })
+        .collect();
+    Ok((total, page))
+}
+
 // API endpoint to add a new block
 pub async fn add_block_handler(block: web::Json<Block>, data: web::Data<AppState>) -> impl Responder {
     match data.block_manager.add_block(block.into_inner()) {
         Ok(_) => {
             // Save the updated blocks to the file
-            if let Err(e) = data.block_manager.save_blocks_to_file() {
+            if let Err(e) = save_blocks(&data.block_manager, "add_block_handler").await {
                 return HttpResponse::InternalServerError().body(e);
             }
             HttpResponse::Ok().body("Block added successfully")
@@ -99,7 +212,8 @@ async fn enhance_block_with_llm(mut block: Block, data: &web::Data<AppState>) ->
     // Enhance the description using LLM
     let enhanced_description = enhance_description(
         &block.description, 
-        project_config.llm_provider
+        project_config.llm_provider_for(LLMOperation::EnhanceDescription),
+        Some(&block.block_id)
     ).await?;
 
     // Update the block with the enhanced description
@@ -115,8 +229,10 @@ async fn generate_tasks_with_llm(mut block: Block, data: &web::Data<AppState>) -
 
     // Generate tasks based on the enhanced description
     let generated_tasks = generate_tasks(
-        &block.description, 
-        project_config.llm_provider
+        &block.description,
+        block.tech_hints.as_ref(),
+        project_config.llm_provider_for(LLMOperation::GenerateTasks),
+        Some(&block.block_id)
     ).await?;
 
     // Add the generated tasks to the block's todo list
@@ -125,6 +241,13 @@ async fn generate_tasks_with_llm(mut block: Block, data: &web::Data<AppState>) -
         block.todo_list.insert(task_id, task);
     }
 
+    // MCP-based providers create tasks directly in the blocks file; keep them
+    if let Some(stored) = data.block_manager.get_blocks()?.into_iter().find(|b| b.block_id == block.block_id) {
+        for (task_id, task) in stored.todo_list {
+            block.todo_list.entry(task_id).or_insert(task);
+        }
+    }
+
     Ok(block)
 }
 
@@ -147,7 +270,7 @@ pub async fn enhance_block_handler(block: web::Json<Block>, data: web::Data<AppS
     match data.block_manager.update_block(block) {
         Ok(_) => {
             // Save the updated blocks to the file
-            if let Err(e) = data.block_manager.save_blocks_to_file() {
+            if let Err(e) = save_blocks(&data.block_manager, "enhance_block_handler").await {
                 return HttpResponse::InternalServerError().body(e);
             }
             HttpResponse::Ok().body("Block updated successfully")
@@ -164,8 +287,8 @@ pub async fn generate_tasks_block_handler(block: web::Json<Block>, data: web::Da
             block = block_with_tasks;
         },
         Err(e) => {
-            println!("Failed to enhance block with LLM: {}", e);
-            // Continue with the original update even if LLM enhancement fails
+            println!("Failed to generate tasks with LLM: {}", e);
+            return HttpResponse::InternalServerError().body(format!("Failed to generate tasks: {}", e));
         }
     }
 
@@ -173,7 +296,7 @@ pub async fn generate_tasks_block_handler(block: web::Json<Block>, data: web::Da
     match data.block_manager.update_block(block) {
         Ok(_) => {
             // Save the updated blocks to the file
-            if let Err(e) = data.block_manager.save_blocks_to_file() {
+            if let Err(e) = save_blocks(&data.block_manager, "generate_tasks_block_handler").await {
                 return HttpResponse::InternalServerError().body(e);
             }
             HttpResponse::Ok().body("Block updated successfully")
@@ -185,13 +308,24 @@ pub async fn generate_tasks_block_handler(block: web::Json<Block>, data: web::Da
 
 // API endpoint to update an existing block
 pub async fn update_block_handler(block: web::Json<Block>, data: web::Data<AppState>) -> impl Responder {
-    let block = block.into_inner();
+    let mut block = block.into_inner();
+
+    for task in block.todo_list.values() {
+        if let Some(Err(e)) = task.timeout_minutes.map(|minutes| validate_timeout_minutes(minutes, &format!("task {}", task.task_id))) {
+            return HttpResponse::BadRequest().body(e);
+        }
+    }
+
+    // Enforce the workflow rules for any task whose status changed
+    if let Err(e) = validate_task_status_changes(&mut block, &data) {
+        return HttpResponse::BadRequest().body(e);
+    }
 
     // Update the block in the database
     match data.block_manager.update_block(block) {
         Ok(_) => {
             // Save the updated blocks to the file
-            if let Err(e) = data.block_manager.save_blocks_to_file() {
+            if let Err(e) = save_blocks(&data.block_manager, "update_block_handler").await {
                 return HttpResponse::InternalServerError().body(e);
             }
             HttpResponse::Ok().body("Block updated successfully")
@@ -200,6 +334,148 @@ pub async fn update_block_handler(block: web::Json<Block>, data: web::Data<AppSt
     }
 }
 
+// Normalize task statuses of an incoming block and check transitions against the stored block
+fn validate_task_status_changes(block: &mut Block, data: &web::Data<AppState>) -> Result<(), String> {
+    let workflow = data.project_manager.get_task_workflow();
+    let blocks = data.block_manager.get_blocks()?;
+    let existing = blocks.iter().find(|b| b.block_id == block.block_id);
+
+    let config = data.project_manager.get_config().map_err(|e| format!("Failed to get project config: {}", e))?;
+
+    for task in block.todo_list.values_mut() {
+        let stored = existing.and_then(|b| b.todo_list.get(&task.task_id));
+
+        task.status = match stored {
+            Some(stored) => workflow.validate_transition(&stored.status, &task.status)
+                .map_err(|e| format!("Task {}: {}", task.task_id, e))?,
+            None => workflow.normalize_status(&task.status)
+                .map_err(|e| format!("Task {}: {}", task.task_id, e))?,
+        };
+
+        // Tasks being completed need a green CI when the project requires it
+        if let Some(stored) = stored.filter(|stored| !workflow.is_completed(&stored.status)) {
+            if workflow.is_completed(&task.status) {
+                ci_status::check_completion(&config, stored)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// Request body for updating a task status
+#[derive(Deserialize)]
+pub struct UpdateTaskStatusRequest {
+    pub status: String,
+}
+
+// API endpoint to update the status of a single task
+pub async fn update_task_status_handler(
+    path: web::Path<(String, String)>,
+    request: web::Json<UpdateTaskStatusRequest>,
+    data: web::Data<AppState>,
+) -> impl Responder {
+    let (block_id, task_id) = path.into_inner();
+    let workflow = data.project_manager.get_task_workflow();
+
+    // Tasks being completed need a green CI when the project requires it
+    if workflow.is_completed(&request.status) {
+        let config = match data.project_manager.get_config() {
+            Ok(config) => config,
+            Err(e) => return HttpResponse::InternalServerError().body(format!("Failed to get project config: {}", e)),
+        };
+        let blocks = match data.block_manager.get_blocks() {
+            Ok(blocks) => blocks,
+            Err(e) => return HttpResponse::InternalServerError().body(e),
+        };
+        let task = blocks.iter()
+            .find(|b| b.block_id == block_id)
+            .and_then(|b| b.todo_list.get(&task_id))
+            .filter(|task| !workflow.is_completed(&task.status));
+        if let Some(Err(e)) = task.map(|task| ci_status::check_completion(&config, task)) {
+            return HttpResponse::Conflict().body(e);
+        }
+    }
+
+    match data.block_manager.update_task_status(&block_id, &task_id, &request.status, &workflow) {
+        Ok(status) => {
+            // Save the updated blocks to the file
+            if let Err(e) = save_blocks(&data.block_manager, "update_task_status_handler").await {
+                return HttpResponse::InternalServerError().body(e);
+            }
+            HttpResponse::Ok().json(json!({ "task_id": task_id, "status": status }))
+        },
+        Err(e) => HttpResponse::BadRequest().body(e),
+    }
+}
+
+// Summary of a revision in the blocks config history
+#[derive(Serialize)]
+pub struct RevisionSummary {
+    pub timestamp: String,
+    pub block_count: usize,
+    pub task_count: usize,
+    pub changed_by: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct BlocksHistoryResponse {
+    pub undo: Vec<RevisionSummary>,
+    pub redo: Vec<RevisionSummary>,
+}
+
+// API endpoint to list the undo/redo history of the blocks config
+pub async fn get_blocks_history_handler(data: web::Data<AppState>) -> impl Responder {
+    match data.block_manager.load_history() {
+        Ok(history) => {
+            let summarize = |revisions: Vec<crate::block_config::ConfigRevision>| revisions.into_iter().rev().map(|r| RevisionSummary {
+                timestamp: r.timestamp,
+                block_count: r.blocks.len(),
+                task_count: r.blocks.iter().map(|b| b.todo_list.len()).sum(),
+                changed_by: r.changed_by,
+            }).collect::<Vec<_>>();
+
+            HttpResponse::Ok().json(BlocksHistoryResponse {
+                undo: summarize(history.undo),
+                redo: summarize(history.redo),
+            })
+        },
+        Err(e) => HttpResponse::InternalServerError().body(e),
+    }
+}
+
+// API endpoint to revert the blocks config to the previous revision
+pub async fn undo_blocks_handler(data: web::Data<AppState>) -> impl Responder {
+    let block_manager = data.block_manager.clone();
+    match web::block(request_user::with_current_user(move || block_manager.undo())).await {
+        Ok(Ok(blocks)) => HttpResponse::Ok().json(blocks),
+        Ok(Err(e)) => HttpResponse::BadRequest().body(e),
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
+
+// API endpoint to re-apply the last undone blocks config revision
+pub async fn redo_blocks_handler(data: web::Data<AppState>) -> impl Responder {
+    let block_manager = data.block_manager.clone();
+    match web::block(request_user::with_current_user(move || block_manager.redo())).await {
+        Ok(Ok(blocks)) => HttpResponse::Ok().json(blocks),
+        Ok(Err(e)) => HttpResponse::BadRequest().body(e),
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
+
+// API endpoint to get the specification sections a block was generated from
+pub async fn get_block_sources_handler(path: web::Path<String>, data: web::Data<AppState>) -> impl Responder {
+    let block_id = path.into_inner();
+    match data.block_manager.get_blocks() {
+        Ok(blocks) => match blocks.into_iter().find(|b| b.block_id == block_id) {
+            Some(block) => HttpResponse::Ok().json(block.spec_sources),
+            None => HttpResponse::NotFound().body(format!("Block with ID {} not found", block_id)),
+        },
+        Err(e) => HttpResponse::InternalServerError().body(e),
+    }
+}
+
 // API endpoint to get block dependencies
 pub async fn get_block_dependencies_handler(path: web::Path<String>, data: web::Data<AppState>) -> impl Responder {
     let block_id = path.into_inner();
@@ -225,21 +501,252 @@ pub async fn get_block_dependencies_handler(path: web::Path<String>, data: web::
         })
         .collect();
 
+    // Collect the relationships from this block's epic to other epics
+    let epic_dependencies = collect_epic_dependencies(&blocks)
+        .into_iter()
+        .filter(|d| d.from_block_id == block.block_id)
+        .collect();
+
     // Create the response
-    let response = BlockDependenciesResponse { tasks };
+    let response = BlockDependenciesResponse {
+        tasks,
+        epic_id: block.epic_id.clone(),
+        epic_dependencies,
+    };
 
     // Return the response as JSON
     HttpResponse::Ok().json(response)
 }
 
 
+// Derive inter-epic dependencies: a block depends on another block through an input
+// connection (by block name) or a task dependency referencing the other block's ID
+fn collect_epic_dependencies(blocks: &[Block]) -> Vec<EpicDependency> {
+    let mut dependencies = Vec::new();
+
+    for block in blocks {
+        let from_epic_id = match &block.epic_id {
+            Some(epic_id) => epic_id,
+            None => continue,
+        };
+
+        let connected_names: Vec<&String> = block.connections.input_connections.iter()
+            .map(|c| &c.from_module)
+            .collect();
+        let task_dependencies: Vec<&String> = block.todo_list.values()
+            .flat_map(|t| t.dependencies.iter())
+            .collect();
+
+        for other in blocks.iter().filter(|b| b.block_id != block.block_id) {
+            let to_epic_id = match &other.epic_id {
+                Some(epic_id) if epic_id != from_epic_id => epic_id,
+                _ => continue,
+            };
+
+            let depends = connected_names.contains(&&other.name)
+                || task_dependencies.contains(&&other.block_id);
+            if depends {
+                let dependency = EpicDependency {
+                    from_epic_id: from_epic_id.clone(),
+                    to_epic_id: to_epic_id.clone(),
+                    from_block_id: block.block_id.clone(),
+                    to_block_id: other.block_id.clone(),
+                };
+                if !dependencies.contains(&dependency) {
+                    dependencies.push(dependency);
+                }
+            }
+        }
+    }
+
+    dependencies
+}
+
+// API endpoint to get all epics
+pub async fn get_epics_handler(data: web::Data<AppState>) -> impl Responder {
+    match data.block_manager.get_epics() {
+        Ok(epics) => HttpResponse::Ok().json(epics),
+        Err(e) => HttpResponse::InternalServerError().body(e),
+    }
+}
+
+// API endpoint to add a new epic
+pub async fn add_epic_handler(epic: web::Json<Epic>, data: web::Data<AppState>) -> impl Responder {
+    match data.block_manager.add_epic(epic.into_inner()) {
+        Ok(epic_id) => {
+            // Save the updated epics to the file
+            if let Err(e) = save_blocks(&data.block_manager, "add_epic_handler").await {
+                return HttpResponse::InternalServerError().body(e);
+            }
+            HttpResponse::Ok().json(json!({ "epic_id": epic_id }))
+        },
+        Err(e) => HttpResponse::BadRequest().body(e),
+    }
+}
+
+// API endpoint to update an existing epic
+pub async fn update_epic_handler(epic: web::Json<Epic>, data: web::Data<AppState>) -> impl Responder {
+    match data.block_manager.update_epic(epic.into_inner()) {
+        Ok(_) => {
+            // Save the updated epics to the file
+            if let Err(e) = save_blocks(&data.block_manager, "update_epic_handler").await {
+                return HttpResponse::InternalServerError().body(e);
+            }
+            HttpResponse::Ok().body("Epic updated successfully")
+        },
+        Err(e) => HttpResponse::BadRequest().body(e),
+    }
+}
+
+// API endpoint to delete an epic (its blocks are kept and detached)
+pub async fn delete_epic_handler(path: web::Path<String>, data: web::Data<AppState>) -> impl Responder {
+    let epic_id = path.into_inner();
+    match data.block_manager.delete_epic(&epic_id) {
+        Ok(_) => {
+            // Save the updated blocks and epics to the file
+            if let Err(e) = save_blocks(&data.block_manager, "delete_epic_handler").await {
+                return HttpResponse::InternalServerError().body(e);
+            }
+            HttpResponse::Ok().body("Epic deleted successfully")
+        },
+        Err(e) => HttpResponse::BadRequest().body(e),
+    }
+}
+
+// Request body for assigning a block to an epic
+#[derive(Deserialize)]
+pub struct AssignEpicRequest {
+    pub epic_id: Option<String>,
+}
+
+// API endpoint to assign a block to an epic (or detach it with a null epic_id)
+pub async fn assign_block_epic_handler(
+    path: web::Path<String>,
+    request: web::Json<AssignEpicRequest>,
+    data: web::Data<AppState>,
+) -> impl Responder {
+    let block_id = path.into_inner();
+    match data.block_manager.assign_block_to_epic(&block_id, request.into_inner().epic_id) {
+        Ok(_) => {
+            // Save the updated blocks to the file
+            if let Err(e) = save_blocks(&data.block_manager, "assign_block_epic_handler").await {
+                return HttpResponse::InternalServerError().body(e);
+            }
+            HttpResponse::Ok().body("Block epic updated successfully")
+        },
+        Err(e) => HttpResponse::BadRequest().body(e),
+    }
+}
+
+// API endpoint to get the relationships between epics
+pub async fn get_epic_dependencies_handler(data: web::Data<AppState>) -> impl Responder {
+    match data.block_manager.get_blocks() {
+        Ok(blocks) => HttpResponse::Ok().json(collect_epic_dependencies(&blocks)),
+        Err(e) => HttpResponse::InternalServerError().body(e),
+    }
+}
+
+// Query parameters for the ownership map
+#[derive(Deserialize)]
+pub struct OwnershipQuery {
+    // Only include files whose path starts with this prefix
+    pub path: Option<String>,
+}
+
+// API endpoint to get the code ownership map (file -> blocks/tasks that modified it)
+pub async fn get_ownership_map_handler(query: web::Query<OwnershipQuery>, data: web::Data<AppState>) -> impl Responder {
+    let blocks = match data.block_manager.get_blocks() {
+        Ok(blocks) => blocks,
+        Err(e) => return HttpResponse::InternalServerError().body(e),
+    };
+
+    let mut map = build_ownership_map(&blocks);
+    if let Some(prefix) = &query.path {
+        map.retain(|file, _| file.starts_with(prefix.as_str()));
+    }
+
+    HttpResponse::Ok().json(map)
+}
+
+// Query parameters for the differential block sync
+#[derive(Deserialize)]
+pub struct BlockChangesQuery {
+    // Cursor returned by the previous request; omitted for the initial full sync
+    pub since: Option<String>,
+}
+
+// API endpoint to get the blocks changed since a cursor
+pub async fn get_block_changes_handler(query: web::Query<BlockChangesQuery>, data: web::Data<AppState>) -> impl Responder {
+    match data.block_manager.changes_since(query.since.as_deref()) {
+        Ok(changes) => HttpResponse::Ok().json(changes),
+        Err(e) => HttpResponse::InternalServerError().body(e),
+    }
+}
+
+// Query parameters for the task CSV export
+#[derive(Deserialize)]
+pub struct TaskExportQuery {
+    // Only export the tasks of this block
+    pub block_id: Option<String>,
+}
+
+// API endpoint to export the tasks as CSV
+pub async fn export_tasks_csv_handler(query: web::Query<TaskExportQuery>, data: web::Data<AppState>) -> impl Responder {
+    let mut blocks = match data.block_manager.get_blocks() {
+        Ok(blocks) => blocks,
+        Err(e) => return HttpResponse::InternalServerError().body(e),
+    };
+    if let Some(block_id) = &query.block_id {
+        blocks.retain(|b| &b.block_id == block_id);
+        if blocks.is_empty() {
+            return HttpResponse::NotFound().body(format!("Block with ID {} not found", block_id));
+        }
+    }
+
+    match export_tasks_csv(&blocks) {
+        Ok(csv) => HttpResponse::Ok()
+            .content_type("text/csv; charset=utf-8")
+            .insert_header(("Content-Disposition", "attachment; filename=\"tasks.csv\""))
+            .body(csv),
+        Err(e) => HttpResponse::InternalServerError().body(e),
+    }
+}
+
+// Request body for importing tasks from CSV
+#[derive(Deserialize)]
+pub struct TaskImportRequest {
+    pub csv: String,
+    // Task field -> CSV column; unmapped fields use the column of the same name
+    #[serde(default)]
+    pub column_mapping: HashMap<String, String>,
+}
+
+// API endpoint to import tasks from CSV into a block
+pub async fn import_tasks_csv_handler(path: web::Path<String>, request: web::Json<TaskImportRequest>, data: web::Data<AppState>) -> impl Responder {
+    let block_id = path.into_inner();
+    let workflow = data.project_manager.get_task_workflow();
+
+    match import_tasks_csv(&data.block_manager, &block_id, &request.csv, &request.column_mapping, &workflow) {
+        Ok(result) => {
+            if !result.imported.is_empty() {
+                if let Err(e) = save_blocks(&data.block_manager, "import_tasks_csv_handler").await {
+                    return HttpResponse::InternalServerError().body(e);
+                }
+            }
+            info!("Imported {} tasks into block {} ({} rows rejected)", result.imported.len(), block_id, result.errors.len());
+            HttpResponse::Ok().json(result)
+        },
+        Err(e) => HttpResponse::BadRequest().body(e),
+    }
+}
+
 // API endpoint to delete a block
 pub async fn delete_block_handler(path: web::Path<String>, data: web::Data<AppState>) -> impl Responder {
     let block_id = path.into_inner();
     match data.block_manager.delete_block(&block_id) {
         Ok(_) => {
             // Save the updated blocks to the file
-            if let Err(e) = data.block_manager.save_blocks_to_file() {
+            if let Err(e) = save_blocks(&data.block_manager, "delete_block_handler").await {
                 return HttpResponse::InternalServerError().body(e);
             }
             HttpResponse::Ok().body("Block deleted successfully")
@@ -298,7 +805,10 @@ pub async fn add_task_handler(path: web::Path<String>, task_request: web::Json<T
     task.task_name = task_request.task_name;
     task.acceptance_criteria = task_request.acceptance_criteria;
     task.dependencies = task_request.dependencies;
-    task.estimated_effort = task_request.estimated_effort;
+    task.estimated_effort = match Effort::parse(&task_request.estimated_effort) {
+        Ok(effort) => effort,
+        Err(e) => return HttpResponse::BadRequest().body(e),
+    };
     task.files_affected = task_request.files_affected;
     task.function_signatures = task_request.function_signatures;
     task.testing_requirements = task_request.testing_requirements;
@@ -310,9 +820,11 @@ pub async fn add_task_handler(path: web::Path<String>, task_request: web::Json<T
     if !task_request.commit_id.is_empty() {
         task.commit_id = task_request.commit_id;
     }
-    if !task_request.status.is_empty() {
-        task.status = task_request.status;
-    }
+    let workflow = data.project_manager.get_task_workflow();
+    task.status = match workflow.normalize_status(&task_request.status) {
+        Ok(status) => status,
+        Err(e) => return HttpResponse::BadRequest().body(e),
+    };
 
     // Add the task to the block
     let task_id = task.task_id.clone();
@@ -322,7 +834,7 @@ pub async fn add_task_handler(path: web::Path<String>, task_request: web::Json<T
     match data.block_manager.update_block(blocks[block_index.unwrap()].clone()) {
         Ok(_) => {
             // Save the updated blocks to the file
-            if let Err(e) = data.block_manager.save_blocks_to_file() {
+            if let Err(e) = save_blocks(&data.block_manager, "add_task_handler").await {
                 return HttpResponse::InternalServerError().body(e);
             }
             HttpResponse::Ok().json(json!({ "task_id": task_id }))
@@ -337,7 +849,7 @@ pub async fn remove_task_handler(path: web::Path<(String, String)>, data: web::D
     match data.block_manager.remove_task_item(&block_id, task_id) {
         Ok(_) => {
             // Save the updated blocks to the file
-            if let Err(e) = data.block_manager.save_blocks_to_file() {
+            if let Err(e) = save_blocks(&data.block_manager, "remove_task_handler").await {
                 return HttpResponse::InternalServerError().body(e);
             }
             HttpResponse::Ok().body("Todo item removed successfully")
@@ -369,7 +881,7 @@ pub async fn auto_complete_handler(description: web::Json<String>, data: web::Da
 
     match auto_complete_description(
         &description, 
-        project_config.llm_provider
+        project_config.llm_provider_for(LLMOperation::AutoComplete)
     ).await {
         Ok(enhanced_description) => {
             let response = AutoCompleteResponse {
@@ -384,6 +896,80 @@ pub async fn auto_complete_handler(description: web::Json<String>, data: web::Da
     }
 }
 
+// Server-sent event; the payload is JSON so multi-line text stays on one data line
+fn sse_event(event: &str, data: serde_json::Value) -> String {
+    format!("event: {}\ndata: {}\n\n", event, data)
+}
+
+fn sse_response(rx: mpsc::UnboundedReceiver<String>) -> HttpResponse {
+    HttpResponse::Ok()
+        .insert_header((CACHE_CONTROL, "no-cache"))
+        .insert_header(("Content-Type", "text/event-stream"))
+        .streaming(UnboundedReceiverStream::new(rx).map(|event| Ok::<Bytes, actix_web::Error>(Bytes::from(event))))
+}
+
+// API endpoint streaming an auto-complete suggestion as server-sent events: "chunk" events with
+// the generated text, then "done" with the full suggestion or "error"
+pub async fn auto_complete_stream_handler(description: web::Json<String>, data: web::Data<AppState>) -> impl Responder {
+    let description = description.into_inner();
+    let project_config = match data.project_manager.get_config() {
+        Ok(config) => config,
+        Err(e) => return HttpResponse::InternalServerError().body(format!("Failed to get project config: {}", e)),
+    };
+
+    let (tx, rx) = mpsc::unbounded_channel();
+    actix_web::rt::spawn(async move {
+        let on_chunk = |chunk: &str| {
+            let _ = tx.send(sse_event("chunk", json!({ "text": chunk })));
+        };
+        let result = auto_complete_description_stream(&description, project_config.llm_provider_for(LLMOperation::AutoComplete), &on_chunk).await;
+        let _ = match result {
+            Ok(suggestion) => tx.send(sse_event("done", json!({ "suggestion": suggestion }))),
+            Err(e) => {
+                println!("Failed to generate auto-complete suggestion: {}", e);
+                tx.send(sse_event("error", json!({ "error": e })))
+            }
+        };
+    });
+    sse_response(rx)
+}
+
+// API endpoint streaming the enhanced description of a block as server-sent events; the block is
+// saved with the new description before the "done" event
+pub async fn enhance_block_stream_handler(block: web::Json<Block>, data: web::Data<AppState>) -> impl Responder {
+    let mut block = block.into_inner();
+    let project_config = match data.project_manager.get_config() {
+        Ok(config) => config,
+        Err(e) => return HttpResponse::InternalServerError().body(format!("Failed to get project config: {}", e)),
+    };
+
+    let (tx, rx) = mpsc::unbounded_channel();
+    actix_web::rt::spawn(async move {
+        let on_chunk = |chunk: &str| {
+            let _ = tx.send(sse_event("chunk", json!({ "text": chunk })));
+        };
+        let result = match enhance_description_stream(&block.description, project_config.llm_provider_for(LLMOperation::EnhanceDescription), Some(&block.block_id), &on_chunk).await {
+            Ok(description) => {
+                block.description = description;
+                match data.block_manager.update_block(block.clone()) {
+                    Ok(()) => save_blocks(&data.block_manager, "enhance_block_stream_handler").await
+                        .map(|_| block.description.clone()),
+                    Err(e) => Err(e),
+                }
+            },
+            Err(e) => Err(e),
+        };
+        let _ = match result {
+            Ok(description) => tx.send(sse_event("done", json!({ "description": description }))),
+            Err(e) => {
+                println!("Failed to enhance block with LLM: {}", e);
+                tx.send(sse_event("error", json!({ "error": e })))
+            }
+        };
+    });
+    sse_response(rx)
+}
+
 // API endpoint to process a markdown file and generate tasks
 pub async fn process_markdown_handler(request: web::Json<ProcessMarkdownRequest>, data: web::Data<AppState>) -> impl Responder {
     let request = request.into_inner();
@@ -409,9 +995,12 @@ pub async fn process_markdown_handler(request: web::Json<ProcessMarkdownRequest>
     };
 
     // Process the markdown file and generate tasks
+    let tech_hints = blocks[block_index.unwrap()].tech_hints.clone();
     match generate_tasks(
-        &request.markdown_content, 
-        project_config.llm_provider
+        &request.markdown_content,
+        tech_hints.as_ref(),
+        project_config.llm_provider_for(LLMOperation::GenerateTasks),
+        Some(&request.block_id)
     ).await {
         Ok(tasks) => {
             // Add the generated tasks to the block's todo list
@@ -426,7 +1015,7 @@ pub async fn process_markdown_handler(request: web::Json<ProcessMarkdownRequest>
             match data.block_manager.update_block(block.clone()) {
                 Ok(_) => {
                     // Save the updated blocks to the file
-                    if let Err(e) = data.block_manager.save_blocks_to_file() {
+                    if let Err(e) = save_blocks(&data.block_manager, "process_markdown_handler").await {
                         return HttpResponse::InternalServerError().body(e);
                     }
 
@@ -446,6 +1035,73 @@ pub async fn process_markdown_handler(request: web::Json<ProcessMarkdownRequest>
     }
 }
 
+// Default file name of the specification generated from the blocks
+pub const EXPORTED_SPEC_FILE: &str = "SPECIFICATION.md";
+
+// Generate the markdown specification of the current blocks and tasks
+fn generate_spec_markdown(data: &web::Data<AppState>) -> Result<String, String> {
+    let project_config = data.project_manager.get_config()
+        .map_err(|e| format!("Failed to get project config: {}", e))?;
+    let blocks = data.block_manager.get_blocks()?;
+    let epics = data.block_manager.get_epics()?;
+
+    let title = project_config.project_name.clone()
+        .filter(|n| !n.trim().is_empty())
+        .unwrap_or_else(|| "Specification".to_string());
+    let workflow = project_config.task_workflow.clone().unwrap_or_default();
+    Ok(blocks_to_spec_markdown(&title, &project_config.project_description, &blocks, &epics, &workflow))
+}
+
+// API endpoint to export the blocks back to a markdown specification
+pub async fn export_specification_handler(data: web::Data<AppState>) -> impl Responder {
+    match generate_spec_markdown(&data) {
+        Ok(markdown) => HttpResponse::Ok().content_type("text/markdown; charset=utf-8").body(markdown),
+        Err(e) => HttpResponse::InternalServerError().body(e),
+    }
+}
+
+// Request body for writing the exported specification into the spec directory
+#[derive(Deserialize)]
+pub struct WriteSpecRequest {
+    // File name relative to the spec directory (default: SPECIFICATION.md)
+    pub file_name: Option<String>,
+}
+
+// API endpoint to write the exported specification into the spec directory, so it can be committed
+pub async fn write_specification_handler(request: web::Json<WriteSpecRequest>, data: web::Data<AppState>) -> impl Responder {
+    if data.block_manager.is_read_only() {
+        return HttpResponse::BadRequest().body("Spec directory is read-only");
+    }
+
+    let file_name = request.file_name.clone()
+        .filter(|f| !f.trim().is_empty())
+        .unwrap_or_else(|| EXPORTED_SPEC_FILE.to_string());
+    if std::path::Path::new(&file_name).components().any(|c| !matches!(c, std::path::Component::Normal(_))) {
+        return HttpResponse::BadRequest().body(format!("Invalid spec file name '{}'", file_name));
+    }
+
+    let spec_directory = match data.project_manager.get_config() {
+        Ok(config) => config.spec_directory(),
+        Err(e) => return HttpResponse::InternalServerError().body(format!("Failed to get project config: {}", e)),
+    };
+    if spec_directory.is_empty() {
+        return HttpResponse::BadRequest().body("Project home directory is not set");
+    }
+
+    let markdown = match generate_spec_markdown(&data) {
+        Ok(markdown) => markdown,
+        Err(e) => return HttpResponse::InternalServerError().body(e),
+    };
+    let path = std::path::Path::new(&spec_directory).join(&file_name);
+    match std::fs::write(&path, markdown) {
+        Ok(_) => {
+            info!("Wrote specification to {}", path.display());
+            HttpResponse::Ok().json(json!({ "path": path.to_string_lossy() }))
+        },
+        Err(e) => HttpResponse::InternalServerError().body(format!("Failed to write specification: {}", e)),
+    }
+}
+
 // API endpoint to process a specification and generate blocks
 pub async fn process_specification_handler(request: web::Json<ProcessSpecRequest>, data: web::Data<AppState>) -> impl Responder {
     let request = request.into_inner();
@@ -460,13 +1116,24 @@ pub async fn process_specification_handler(request: web::Json<ProcessSpecRequest
     };
 
     // Process the specification and generate blocks
+    let llm_provider = project_config.llm_provider_for(LLMOperation::ProcessSpecification);
     match process_specification(
         &request.markdown_content, 
-        project_config.llm_provider
+        llm_provider.clone()
     ).await {
         Ok(generated_blocks) => {
-            match data.project_manager.get_config().unwrap().llm_provider.unwrap() {
+            match llm_provider.unwrap_or_default() {
                 LLMProvider::ClaudeCode => {
+                    // Blocks were created through MCP tools; resolve their spec sources against the spec
+                    let block_manager = data.block_manager.clone();
+                    let sections = parse_spec_sections(&request.markdown_content);
+                    let linked = web::block(request_user::with_current_user(move || block_manager.load_blocks_from_file()
+                        .and_then(|_| block_manager.link_spec_sources(&sections))
+                        .and_then(|_| block_manager.save_blocks_from("process_specification_handler")))).await;
+                    if let Err(e) = linked.map_err(|e| e.to_string()).and_then(|r| r) {
+                        error!("Failed to link spec sources: {}", e);
+                    }
+
                     let response = ProcessSpecResponse {
                         status: "success".to_string(),
                         message: "Successfully processed specification and created blocks using mcp.".to_string(),
@@ -475,7 +1142,9 @@ pub async fn process_specification_handler(request: web::Json<ProcessSpecRequest
                     HttpResponse::Ok().json(response)
                 }
                 _ => {
-                    match create_blocks_from_llm(generated_blocks, data)  {
+                    let markdown_content = request.markdown_content.clone();
+                    let created = web::block(request_user::with_current_user(move || create_blocks_from_llm(generated_blocks, &markdown_content, data))).await;
+                    match created.map_err(|e| e.to_string()).and_then(|r| r) {
                         Ok(response) => {
                             HttpResponse::Ok().json(response)
                         },
@@ -494,8 +1163,9 @@ pub async fn process_specification_handler(request: web::Json<ProcessSpecRequest
     }
 }
 
-pub fn create_blocks_from_llm(generated_blocks: Vec<GeneratedBlock>, data: web::Data<AppState>) -> Result<(ProcessSpecResponse), String>  {
+pub fn create_blocks_from_llm(generated_blocks: Vec<GeneratedBlock>, markdown_content: &str, data: web::Data<AppState>) -> Result<(ProcessSpecResponse), String>  {
     let mut created_blocks = Vec::new();
+    let spec_sections = parse_spec_sections(markdown_content);
 
     // Create blocks from the generated blocks
     for generated_block in generated_blocks {
@@ -506,13 +1176,21 @@ pub fn create_blocks_from_llm(generated_blocks: Vec<GeneratedBlock>, data: web::
         info!("Generated block {}: {}",block_id, block_name);
 
         // Create a new Block from the GeneratedBlock
-        let block = Block::new(
+        let mut block = Block::new(
             block_name.clone(),
             generated_block.description,
             generated_block.inputs,
             generated_block.outputs
         );
 
+        // Group the block under its epic, creating the epic on first use
+        if let Some(epic_name) = generated_block.epic.filter(|e| !e.trim().is_empty()) {
+            block.epic_id = Some(data.block_manager.find_or_create_epic(&epic_name, "")?);
+        }
+
+        // Link the block back to the specification sections it came from
+        block.spec_sources = resolve_spec_sections(&spec_sections, &generated_block.source_sections);
+
         // Add the block to the database
         match data.block_manager.add_block(block.clone()) {
             Ok(_) => {
@@ -526,7 +1204,7 @@ pub fn create_blocks_from_llm(generated_blocks: Vec<GeneratedBlock>, data: web::
     }
 
     // Save the updated blocks to the file
-    if let Err(e) = data.block_manager.save_blocks_to_file() {
+    if let Err(e) = data.block_manager.save_blocks_from("create_blocks_from_llm") {
        return Err(e);
     }
 
@@ -539,3 +1217,40 @@ pub fn create_blocks_from_llm(generated_blocks: Vec<GeneratedBlock>, data: web::
     Ok(response)
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_list_blocks() {
+        let block = |name: &str, statuses: &[&str]| {
+            let mut block = Block::new(name.to_string(), String::new(), Vec::new(), Vec::new());
+            for status in statuses {
+                let mut task = Task::new(format!("{} task", name));
+                task.status = status.to_string();
+                block.todo_list.insert(task.task_id.clone(), task);
+            }
+            block
+        };
+        let mut closed = block("Api", &["COMPLETED"]);
+        closed.status = Some("closed".to_string());
+        let blocks = vec![block("Parser", &["TODO", "COMPLETED"]), closed, block("Cli", &[])];
+
+        let query = |query: &str| list_blocks(blocks.clone(), &web::Query::<BlockListQuery>::from_query(query).unwrap());
+        let names = |(_, page): (usize, Vec<serde_json::Value>)| page.iter().map(|b| b["name"].as_str().unwrap().to_string()).collect::<Vec<_>>();
+
+        assert_eq!(names(query("sort=name").unwrap()), vec!["Api", "Cli", "Parser"]);
+        assert_eq!(names(query("sort=-task_count&limit=1").unwrap()), vec!["Parser"]);
+        assert_eq!(query("sort=name&offset=1&limit=1").unwrap().0, 3);
+        assert_eq!(names(query("status=open&sort=name").unwrap()), vec!["Cli", "Parser"]);
+
+        let (total, page) = query("task_status=todo&tasks=summary").unwrap();
+        assert_eq!(total, 1);
+        let tasks = page[0]["todo_list"].as_object().unwrap();
+        assert_eq!(tasks.len(), 1);
+        assert!(tasks.values().all(|t| t.get("description").is_none() && t["status"] == "TODO"));
+
+        assert!(query("sort=size").is_err());
+        assert!(query("tasks=some").is_err());
+    }
+}
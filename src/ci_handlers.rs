@@ -0,0 +1,35 @@
+use crate::ci_status::{self, CiReport};
+use crate::project_handlers::ProjectAppState;
+use actix_web::{web, HttpRequest, HttpResponse, Responder};
+use serde_json::json;
+
+// CI webhook: any CI system posts the status of a commit or branch, signed with ci.webhook_secret in
+// the X-Forge-Signature header, and it's attached to the tasks of that commit or branch
+pub async fn ci_webhook_handler(req: HttpRequest, body: web::Bytes, data: web::Data<ProjectAppState>) -> impl Responder {
+    let config = match data.project_manager.get_config() {
+        Ok(config) => config,
+        Err(e) => return HttpResponse::InternalServerError().body(format!("Failed to get project config: {}", e)),
+    };
+    let body = String::from_utf8_lossy(&body);
+    let signature = req.headers().get("X-Forge-Signature").and_then(|v| v.to_str().ok());
+    if let Err(e) = ci_status::verify_signature(&config, &body, signature) {
+        return HttpResponse::Unauthorized().body(e);
+    }
+    let report: CiReport = match serde_json::from_str(&body) {
+        Ok(report) => report,
+        Err(e) => return HttpResponse::BadRequest().body(format!("Invalid CI status: {}", e)),
+    };
+
+    match ci_status::record_report(&data.project_manager, &data.block_manager, &report) {
+        Ok(task_ids) => HttpResponse::Ok().json(json!({ "tasks": task_ids })),
+        Err(e) => HttpResponse::BadRequest().body(e),
+    }
+}
+
+// API endpoint to poll GitHub Actions now for the tasks whose CI status isn't final yet
+pub async fn refresh_ci_handler(data: web::Data<ProjectAppState>) -> impl Responder {
+    match ci_status::refresh_github_actions(&data.project_manager, &data.block_manager).await {
+        Ok(task_ids) => HttpResponse::Ok().json(json!({ "tasks": task_ids })),
+        Err(e) => HttpResponse::InternalServerError().body(e),
+    }
+}
@@ -0,0 +1,316 @@
+use crate::block_config::BlockConfigManager;
+use crate::models::{CiStatus, Task};
+use crate::project_config::{ProjectConfig, ProjectConfigManager, DEFAULT_CI_POLL_INTERVAL_SECONDS};
+use crate::pull_request::{config_remote_project, github_api_url, GITHUB_TOKEN_SECRET};
+use crate::secrets;
+use crate::webhooks;
+use chrono::Utc;
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use tracing::{error, info};
+
+pub const CI_STATE_PENDING: &str = "pending";
+pub const CI_STATE_SUCCESS: &str = "success";
+pub const CI_STATE_FAILURE: &str = "failure";
+
+pub const CI_SOURCE_GITHUB_ACTIONS: &str = "github_actions";
+
+// Source of the statuses pushed to the CI webhook without a name
+const DEFAULT_WEBHOOK_SOURCE: &str = "ci";
+
+// Shortest commit ID matched against the commits of the tasks
+const MIN_COMMIT_PREFIX: usize = 7;
+
+// Workflow runs listed per request
+const RUNS_PAGE_SIZE: usize = 100;
+
+// Canonical state of a status reported by a CI system
+pub fn normalize_state(state: &str) -> Option<&'static str> {
+    match state.trim().to_lowercase().as_str() {
+        "success" | "succeeded" | "passed" | "green" => Some(CI_STATE_SUCCESS),
+        "pending" | "queued" | "waiting" | "requested" | "running" | "in_progress" | "started" => Some(CI_STATE_PENDING),
+        "failure" | "failed" | "error" | "errored" | "cancelled" | "canceled" | "timed_out" | "red" => Some(CI_STATE_FAILURE),
+        _ => None,
+    }
+}
+
+// Refuse to complete a task whose CI isn't green, when the project requires it
+pub fn check_completion(config: &ProjectConfig, task: &Task) -> Result<(), String> {
+    if !config.ci.as_ref().is_some_and(|ci| ci.require_success) {
+        return Ok(());
+    }
+    match task.ci_status.as_ref().map(|status| status.state.as_str()) {
+        Some(CI_STATE_SUCCESS) => Ok(()),
+        Some(state) => Err(format!("Task {} can't be completed while its CI status is {}", task.task_id, state)),
+        None => Err(format!("Task {} can't be completed before its CI reports a status", task.task_id)),
+    }
+}
+
+// Last commit of a task, if an execution created one
+fn task_commit(task: &Task) -> Option<&str> {
+    task.commit_ids.last()
+        .map(String::as_str)
+        .or_else(|| Some(task.commit_id.as_str()).filter(|id| id.len() >= MIN_COMMIT_PREFIX && id.chars().all(|c| c.is_ascii_hexdigit())))
+}
+
+// Whether a status is about a task: one of its commits, full or abbreviated, or its branch
+fn status_matches(config: &ProjectConfig, task: &Task, commit: Option<&str>, branch: Option<&str>) -> bool {
+    let commit_matches = commit.filter(|c| c.len() >= MIN_COMMIT_PREFIX).is_some_and(|commit| {
+        task.commit_ids.iter()
+            .chain(std::iter::once(&task.commit_id))
+            .filter(|id| id.len() >= MIN_COMMIT_PREFIX)
+            .any(|id| id.starts_with(commit) || commit.starts_with(id.as_str()))
+    });
+    commit_matches || branch.is_some_and(|branch| branch == config.task_branch(task))
+}
+
+// Status pushed to the CI webhook by any CI system, for a commit or a branch
+#[derive(Debug, Clone, Deserialize)]
+pub struct CiReport {
+    pub state: String,
+    #[serde(default)]
+    pub commit: Option<String>,
+    #[serde(default)]
+    pub branch: Option<String>,
+    #[serde(default)]
+    pub url: Option<String>,
+    // Name of the CI system
+    #[serde(default)]
+    pub source: Option<String>,
+}
+
+// Check the X-Forge-Signature header of a CI webhook request; the webhook is off without a secret
+pub fn verify_signature(config: &ProjectConfig, body: &str, signature: Option<&str>) -> Result<(), String> {
+    let secret = config.ci.as_ref()
+        .and_then(|ci| ci.webhook_secret.as_deref())
+        .filter(|secret| !secret.is_empty())
+        .ok_or_else(|| "The CI webhook is disabled, set ci.webhook_secret in the project settings".to_string())?;
+    let secret = secrets::resolve(secret)?;
+    match signature {
+        Some(signature) if webhooks::verify(&secret, body, signature) => Ok(()),
+        _ => Err("Invalid CI webhook signature".to_string()),
+    }
+}
+
+// Attach statuses to the tasks they are about, returning the IDs of the updated tasks
+fn apply_statuses(
+    project_manager: &ProjectConfigManager,
+    block_manager: &BlockConfigManager,
+    statuses: &[CiStatus],
+    source: &str,
+) -> Result<Vec<String>, String> {
+    let config = project_manager.get_config().map_err(|e| format!("Failed to get project config: {}", e))?;
+    let mut updated = Vec::new();
+    for mut block in block_manager.get_blocks()? {
+        let mut changed = false;
+        for task in block.todo_list.values_mut() {
+            let status = statuses.iter()
+                .find(|status| status_matches(&config, task, status.commit.as_deref(), status.branch.as_deref()));
+            if let Some(status) = status {
+                info!("CI status of task {} is {} ({})", task.task_id, status.state, status.source);
+                task.ci_status = Some(status.clone());
+                updated.push(task.task_id.clone());
+                changed = true;
+            }
+        }
+        if changed {
+            block_manager.update_block(block)?;
+        }
+    }
+    if !updated.is_empty() {
+        block_manager.save_blocks_from(source)?;
+    }
+    Ok(updated)
+}
+
+// Attach a status pushed to the CI webhook to the tasks of its commit or branch, returning their IDs
+pub fn record_report(project_manager: &ProjectConfigManager, block_manager: &BlockConfigManager, report: &CiReport) -> Result<Vec<String>, String> {
+    let state = normalize_state(&report.state)
+        .ok_or_else(|| format!("Invalid CI state '{}', expected pending, success or failure", report.state))?;
+    let commit = report.commit.as_deref().map(str::trim).filter(|c| !c.is_empty());
+    let branch = report.branch.as_deref().map(str::trim).filter(|b| !b.is_empty());
+    if commit.is_none() && branch.is_none() {
+        return Err("A CI status needs a commit or a branch".to_string());
+    }
+    let status = CiStatus {
+        state: state.to_string(),
+        source: report.source.clone().filter(|s| !s.trim().is_empty()).unwrap_or_else(|| DEFAULT_WEBHOOK_SOURCE.to_string()),
+        commit: commit.map(str::to_string),
+        branch: branch.map(str::to_string),
+        url: report.url.clone().filter(|u| !u.trim().is_empty()),
+        updated_at: Utc::now().to_rfc3339(),
+    };
+    apply_statuses(project_manager, block_manager, &[status], "ci_status::record_report")
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct WorkflowRun {
+    workflow_id: u64,
+    // queued, in_progress or completed
+    status: String,
+    #[serde(default)]
+    conclusion: Option<String>,
+    html_url: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct WorkflowRuns {
+    workflow_runs: Vec<WorkflowRun>,
+}
+
+fn run_state(run: &WorkflowRun) -> &'static str {
+    if run.status != "completed" {
+        return CI_STATE_PENDING;
+    }
+    match run.conclusion.as_deref() {
+        Some("success") | Some("skipped") | Some("neutral") => CI_STATE_SUCCESS,
+        _ => CI_STATE_FAILURE,
+    }
+}
+
+// State of a commit from its workflow runs, newest first: only the latest run of each workflow counts,
+// a failed one fails the commit and a running one keeps it pending. Returns the URL of the run deciding it.
+fn aggregate_runs(runs: &[WorkflowRun]) -> Option<(&'static str, String)> {
+    let mut workflows = HashSet::new();
+    let latest: Vec<&WorkflowRun> = runs.iter().filter(|run| workflows.insert(run.workflow_id)).collect();
+    [CI_STATE_FAILURE, CI_STATE_PENDING, CI_STATE_SUCCESS].into_iter()
+        .find_map(|state| latest.iter().find(|run| run_state(run) == state).map(|run| (state, run.html_url.clone())))
+}
+
+// Poll GitHub Actions for the last commit of the tasks whose CI status isn't final yet, returning the
+// IDs of the updated tasks
+pub async fn refresh_github_actions(project_manager: &ProjectConfigManager, block_manager: &BlockConfigManager) -> Result<Vec<String>, String> {
+    let config = project_manager.get_config().map_err(|e| format!("Failed to get project config: {}", e))?;
+    let settings = config.ci.clone().unwrap_or_default();
+    let token = secrets::credential(GITHUB_TOKEN_SECRET)?;
+    let remote = config_remote_project(&config)
+        .ok_or_else(|| "Can't find the GitHub repository of the project".to_string())?;
+    let api_url = settings.api_url.clone()
+        .filter(|url| !url.trim().is_empty())
+        .unwrap_or_else(|| github_api_url(&remote.0));
+
+    let mut commits: Vec<(String, String)> = Vec::new();
+    for block in block_manager.get_blocks()? {
+        for task in block.todo_list.values() {
+            let commit = match task_commit(task) {
+                Some(commit) => commit,
+                None => continue,
+            };
+            let settled = task.ci_status.as_ref()
+                .is_some_and(|status| status.state != CI_STATE_PENDING && status.commit.as_deref() == Some(commit));
+            if !settled && !commits.iter().any(|(c, _)| c == commit) {
+                commits.push((commit.to_string(), config.task_branch(task)));
+            }
+        }
+    }
+
+    let client = reqwest::Client::new();
+    let mut statuses = Vec::new();
+    for (commit, branch) in commits {
+        let response = client.get(format!("{}/repos/{}/actions/runs", api_url.trim_end_matches('/'), remote.1))
+            .query(&[("head_sha", commit.as_str()), ("per_page", &RUNS_PAGE_SIZE.to_string())])
+            .bearer_auth(&token)
+            .header("Accept", "application/vnd.github+json")
+            .header("X-GitHub-Api-Version", "2022-11-28")
+            .header("User-Agent", "forge")
+            .send()
+            .await
+            .map_err(|e| format!("Failed to reach GitHub: {}", e))?;
+        if !response.status().is_success() {
+            return Err(format!("GitHub answered {} listing the workflow runs of {}", response.status(), commit));
+        }
+        let runs: WorkflowRuns = response.json().await.map_err(|e| format!("Invalid GitHub workflow runs response: {}", e))?;
+        if let Some((state, url)) = aggregate_runs(&runs.workflow_runs) {
+            statuses.push(CiStatus {
+                state: state.to_string(),
+                source: CI_SOURCE_GITHUB_ACTIONS.to_string(),
+                commit: Some(commit),
+                branch: Some(branch),
+                url: Some(url),
+                updated_at: Utc::now().to_rfc3339(),
+            });
+        }
+    }
+    apply_statuses(project_manager, block_manager, &statuses, "ci_status::refresh_github_actions")
+}
+
+// Poll GitHub Actions in the background while the project enables it
+pub fn start_ci_poller(project_manager: Arc<ProjectConfigManager>, block_manager: Arc<BlockConfigManager>) {
+    thread::spawn(move || {
+        let runtime = match tokio::runtime::Builder::new_current_thread().enable_all().build() {
+            Ok(runtime) => runtime,
+            Err(e) => {
+                error!("Failed to start the CI poller runtime: {}", e);
+                return;
+            }
+        };
+        loop {
+            // Re-read the settings so configuration changes apply without a restart
+            let settings = project_manager.get_config().ok().and_then(|c| c.ci).unwrap_or_default();
+            thread::sleep(Duration::from_secs(settings.poll_interval_seconds.unwrap_or(DEFAULT_CI_POLL_INTERVAL_SECONDS).max(1)));
+            if !settings.github_actions {
+                continue;
+            }
+            if let Err(e) = runtime.block_on(refresh_github_actions(&project_manager, &block_manager)) {
+                error!("Failed to poll GitHub Actions: {}", e);
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::project_config::CiConfig;
+
+    fn run(workflow_id: u64, status: &str, conclusion: Option<&str>) -> WorkflowRun {
+        WorkflowRun {
+            workflow_id,
+            status: status.to_string(),
+            conclusion: conclusion.map(str::to_string),
+            html_url: format!("https://github.com/o/r/actions/runs/{}-{}", workflow_id, status),
+        }
+    }
+
+    #[test]
+    fn test_aggregate_runs() {
+        assert_eq!(aggregate_runs(&[]), None);
+        // A rerun that passed hides the older failure of the same workflow
+        let runs = [run(1, "completed", Some("success")), run(1, "completed", Some("failure")), run(2, "completed", Some("skipped"))];
+        assert_eq!(aggregate_runs(&runs).unwrap().0, CI_STATE_SUCCESS);
+        let runs = [run(1, "in_progress", None), run(2, "completed", Some("success"))];
+        assert_eq!(aggregate_runs(&runs).unwrap().0, CI_STATE_PENDING);
+        let runs = [run(1, "in_progress", None), run(2, "completed", Some("timed_out"))];
+        assert_eq!(aggregate_runs(&runs).unwrap(), (CI_STATE_FAILURE, "https://github.com/o/r/actions/runs/2-completed".to_string()));
+    }
+
+    #[test]
+    fn test_check_completion() {
+        let mut config = ProjectConfig::default();
+        let mut task = Task::new("Add login".to_string());
+        task.set_commits(vec!["abcdef1234".to_string()]);
+        assert!(check_completion(&config, &task).is_ok());
+
+        config.ci = Some(CiConfig { require_success: true, ..CiConfig::default() });
+        assert!(check_completion(&config, &task).is_err());
+        assert!(status_matches(&config, &task, Some("abcdef1"), None));
+        assert!(status_matches(&config, &task, None, Some(task.task_id.as_str())));
+        assert!(!status_matches(&config, &task, Some("abc"), Some("main")));
+
+        let status = |state: &str| Some(CiStatus {
+            state: state.to_string(),
+            source: DEFAULT_WEBHOOK_SOURCE.to_string(),
+            commit: Some("abcdef1234".to_string()),
+            branch: None,
+            url: None,
+            updated_at: Utc::now().to_rfc3339(),
+        });
+        task.ci_status = status(normalize_state("in_progress").unwrap());
+        assert!(check_completion(&config, &task).is_err());
+        task.ci_status = status(normalize_state("PASSED").unwrap());
+        assert!(check_completion(&config, &task).is_ok());
+    }
+}
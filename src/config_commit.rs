@@ -0,0 +1,105 @@
+use crate::git_util::{git, git_with_env};
+use std::fs;
+use std::path::Path;
+
+// Branch the config files are committed to when none is configured. Config commits never go to the
+// checked out branch, which may be the branch of a task being executed.
+pub const DEFAULT_CONFIG_BRANCH: &str = "forge/config";
+
+// Commit config files to the git repository containing them. Returns the new commit ID,
+// or None when the files are unchanged since the last commit.
+pub fn commit_config_files(files: &[String], message: &str, branch: Option<&str>) -> Result<Option<String>, String> {
+    let files: Vec<&Path> = files.iter().map(Path::new).filter(|f| f.exists()).collect();
+    let first = match files.first() {
+        Some(first) => first,
+        None => return Ok(None),
+    };
+    let dir = match first.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+        _ => std::env::current_dir().map_err(|e| format!("Failed to get current directory: {}", e))?,
+    };
+
+    let repo = Path::new(&git(&dir, &["rev-parse", "--show-toplevel"])?).to_path_buf();
+    let repo = repo.canonicalize().unwrap_or(repo);
+    let mut relative_paths = Vec::new();
+    for file in &files {
+        let file = file.canonicalize().map_err(|e| format!("Failed to resolve {}: {}", file.display(), e))?;
+        let relative = file.strip_prefix(&repo)
+            .map_err(|_| format!("{} is not inside the repository {}", file.display(), repo.display()))?;
+        relative_paths.push(relative.to_string_lossy().to_string());
+    }
+    let paths: Vec<&str> = relative_paths.iter().map(|p| p.as_str()).collect();
+
+    let branch = branch.map(str::trim).filter(|b| !b.is_empty()).unwrap_or(DEFAULT_CONFIG_BRANCH);
+    commit_to_branch(&repo, &paths, message, branch)
+}
+
+// Commit the given paths to a branch through a temporary index, without touching the work tree
+fn commit_to_branch(repo: &Path, paths: &[&str], message: &str, branch: &str) -> Result<Option<String>, String> {
+    let reference = format!("refs/heads/{}", branch);
+    let parent = git(repo, &["rev-parse", "--verify", "--quiet", &reference]).ok();
+
+    let index_dir = tempfile::tempdir().map_err(|e| format!("Failed to create temporary index: {}", e))?;
+    let index_file = index_dir.path().join("index");
+    let index_env = [("GIT_INDEX_FILE", index_file.as_os_str())];
+    match &parent {
+        Some(parent) => git_with_env(repo, &["read-tree", parent], &index_env)?,
+        None => git_with_env(repo, &["read-tree", "--empty"], &index_env)?,
+    };
+
+    for path in paths {
+        let blob = git(repo, &["hash-object", "-w", "--", path])?;
+        let cache_info = format!("100644,{},{}", blob, path);
+        git_with_env(repo, &["update-index", "--add", "--cacheinfo", &cache_info], &index_env)?;
+    }
+    let tree = git_with_env(repo, &["write-tree"], &index_env)?;
+
+    let mut commit_args = vec!["commit-tree", tree.as_str(), "-m", message];
+    if let Some(parent) = &parent {
+        if git(repo, &["rev-parse", &format!("{}^{{tree}}", parent)])? == tree {
+            return Ok(None);
+        }
+        commit_args.extend_from_slice(&["-p", parent.as_str()]);
+    }
+    let commit = git(repo, &commit_args)?;
+    // Commits run outside the lock of the config files: the update fails rather than drop a concurrent commit
+    git(repo, &["update-ref", &reference, &commit, parent.as_deref().unwrap_or("")])?;
+    let _ = fs::remove_file(&index_file);
+
+    Ok(Some(commit))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn init_repo(dir: &Path) {
+        git(dir, &["init", "--quiet"]).unwrap();
+        git(dir, &["config", "user.email", "forge@example.com"]).unwrap();
+        git(dir, &["config", "user.name", "Forge"]).unwrap();
+        fs::write(dir.join("README.md"), "readme").unwrap();
+        git(dir, &["add", "README.md"]).unwrap();
+        git(dir, &["commit", "--quiet", "-m", "Initial commit"]).unwrap();
+    }
+
+    #[test]
+    fn test_commit_config_files() {
+        let dir = tempfile::tempdir().unwrap();
+        init_repo(dir.path());
+        let config_file = dir.path().join("blocks_config.json").to_string_lossy().to_string();
+
+        // Dedicated branch: the checked out branch is left untouched
+        fs::write(&config_file, "{}").unwrap();
+        let commit = commit_config_files(&[config_file.clone()], "Update blocks config", Some("forge-config")).unwrap();
+        assert!(commit.is_some());
+        assert_eq!(git(dir.path(), &["show", "forge-config:blocks_config.json"]).unwrap(), "{}");
+        assert!(git(dir.path(), &["log", "--format=%s", "HEAD"]).unwrap().ends_with("Initial commit"));
+        assert_eq!(commit_config_files(&[config_file.clone()], "No change", Some("forge-config")).unwrap(), None);
+
+        // Default branch, never the checked out one
+        let commit = commit_config_files(&[config_file.clone()], "Update blocks config", None).unwrap();
+        assert_eq!(commit, Some(git(dir.path(), &["rev-parse", DEFAULT_CONFIG_BRANCH]).unwrap()));
+        assert!(git(dir.path(), &["log", "--format=%s", "HEAD"]).unwrap().ends_with("Initial commit"));
+        assert_eq!(commit_config_files(&[config_file], "No change", None).unwrap(), None);
+    }
+}
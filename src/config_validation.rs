@@ -0,0 +1,430 @@
+use crate::block_config::{resolve_blocks_config_path, BlocksFormat};
+use crate::llm_handler::{LLMProvider, LLMProviderImpl};
+use crate::models::Block;
+use crate::project_config::{GitProvider, LLMOperation, ProjectConfig, TaskBranchCompletion};
+use crate::pull_request::{GITHUB_TOKEN_SECRET, GITLAB_TOKEN_SECRET};
+use crate::secrets;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// Time allowed for the remote repository and the LLM providers to answer
+const REMOTE_TIMEOUT: Duration = Duration::from_secs(20);
+const LLM_TIMEOUT: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+// A problem found by a check and how to fix it
+#[derive(Debug, Clone, Serialize)]
+pub struct ValidationIssue {
+    pub check: String,
+    pub severity: Severity,
+    pub message: String,
+    pub hint: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ValidationReport {
+    // No errors; warnings don't make a project invalid
+    pub valid: bool,
+    pub errors: Vec<ValidationIssue>,
+    pub warnings: Vec<ValidationIssue>,
+    // Names of the checks that passed
+    pub passed: Vec<String>,
+}
+
+impl ValidationReport {
+    fn issue(&mut self, check: &str, severity: Severity, message: String, hint: String) {
+        let issue = ValidationIssue { check: check.to_string(), severity, message, hint };
+        match severity {
+            Severity::Error => self.errors.push(issue),
+            Severity::Warning => self.warnings.push(issue),
+        }
+        self.valid = self.errors.is_empty();
+    }
+
+    fn error(&mut self, check: &str, message: String, hint: impl Into<String>) {
+        self.issue(check, Severity::Error, message, hint.into());
+    }
+
+    fn warning(&mut self, check: &str, message: String, hint: impl Into<String>) {
+        self.issue(check, Severity::Warning, message, hint.into());
+    }
+
+    fn pass(&mut self, check: &str) {
+        if !self.passed.iter().any(|c| c == check) {
+            self.passed.push(check.to_string());
+        }
+    }
+
+    // Whether a check reported an error
+    pub fn failed(&self, check: &str) -> bool {
+        self.errors.iter().any(|e| e.check == check)
+    }
+}
+
+// Run a command, killing it when it doesn't finish in time
+fn run_with_timeout(command: &mut Command, timeout: Duration) -> Result<std::process::Output, String> {
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to run command: {}", e))?;
+    let started = Instant::now();
+    loop {
+        match child.try_wait() {
+            Ok(Some(_)) => return child.wait_with_output().map_err(|e| format!("Failed to read command output: {}", e)),
+            Ok(None) if started.elapsed() > timeout => {
+                let _ = child.kill();
+                return Err(format!("Timed out after {} seconds", timeout.as_secs()));
+            }
+            Ok(None) => thread::sleep(Duration::from_millis(100)),
+            Err(e) => return Err(format!("Failed to wait for command: {}", e)),
+        }
+    }
+}
+
+fn git(project_dir: &str, args: &[&str]) -> Result<String, String> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(project_dir)
+        .output()
+        .map_err(|e| format!("Failed to run git: {}", e))?;
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+    }
+}
+
+fn check_repository(config: &ProjectConfig, report: &mut ValidationReport) {
+    let project_dir = config.project_home_directory.as_str();
+    if git(project_dir, &["rev-parse", "--is-inside-work-tree"]).is_err() {
+        report.error("git_repository", format!("{} is not a git repository", project_dir),
+            format!("Run 'git init' in {} or clone the project repository there", project_dir));
+        return;
+    }
+    report.pass("git_repository");
+
+    let has_commits = git(project_dir, &["rev-parse", "--verify", "--quiet", "HEAD"]).is_ok();
+    let main_branch = config.main_branch.clone().filter(|b| !b.is_empty()).unwrap_or_else(|| "main".to_string());
+    let mut branches = vec![("main_branch", main_branch)];
+    if let Some(autocommit) = config.config_autocommit.as_ref().filter(|a| a.enabled) {
+        if let Some(branch) = autocommit.branch.clone().filter(|b| !b.is_empty()) {
+            branches.push(("config_autocommit.branch", branch));
+        }
+    }
+    if let Some(branch) = config.branch_workflow().and_then(|w| w.base_branch.clone()).filter(|b| !b.is_empty()) {
+        branches.push(("branch_workflow.base_branch", branch));
+    }
+    for (field, branch) in branches {
+        if git(project_dir, &["rev-parse", "--verify", "--quiet", &format!("refs/heads/{}", branch)]).is_ok() {
+            report.pass("branch");
+        } else if !has_commits {
+            report.warning("branch", format!("The repository has no commits, so branch '{}' does not exist yet", branch),
+                "Create an initial commit before executing tasks");
+        } else {
+            report.error("branch", format!("Branch '{}' ({}) does not exist", branch, field),
+                format!("Create it with 'git branch {}' or change {} in the project settings", branch, field));
+        }
+    }
+}
+
+fn check_remote(config: &ProjectConfig, report: &mut ValidationReport) {
+    let url = match secrets::resolve(&config.git_repository_url) {
+        Ok(url) => url,
+        Err(e) => {
+            report.error("git_credentials", e, "Store the referenced secrets with PUT /api/secrets/{name}");
+            return;
+        }
+    };
+    let mut command = Command::new("git");
+    command.args(["ls-remote", "--heads", &url]).env("GIT_TERMINAL_PROMPT", "0");
+    match run_with_timeout(&mut command, REMOTE_TIMEOUT) {
+        Ok(output) if output.status.success() => report.pass("git_credentials"),
+        Ok(output) => report.error("git_credentials",
+            format!("Cannot access {}: {}", secrets::redact(&config.git_repository_url), secrets::redact(String::from_utf8_lossy(&output.stderr).trim())),
+            "Check the repository URL and reference an access token as {{secret:NAME}} in it"),
+        Err(e) => report.error("git_credentials", format!("Cannot access {}: {}", secrets::redact(&config.git_repository_url), e),
+            "Check the network access to the repository host"),
+    }
+}
+
+// Providers used by the project: the default, the per-operation overrides and the fallbacks
+pub fn configured_providers(config: &ProjectConfig) -> Vec<LLMProvider> {
+    let mut providers = Vec::new();
+    let operations = [LLMOperation::EnhanceDescription, LLMOperation::AutoComplete, LLMOperation::GenerateTasks, LLMOperation::ProcessSpecification];
+    let fallbacks = config.llm_fallbacks.clone().unwrap_or_default();
+    for provider in operations.into_iter()
+        .map(|op| config.llm_provider_for(op).unwrap_or_default())
+        .chain(fallbacks.into_iter().map(|f| f.provider))
+    {
+        if !providers.contains(&provider) {
+            providers.push(provider);
+        }
+    }
+    providers
+}
+
+fn check_provider_credentials(config: &ProjectConfig, provider: &LLMProvider, report: &mut ValidationReport) {
+    let credential_hint = |name: &str| format!("Store the key with PUT /api/secrets/{} or set the {} environment variable", name, name);
+    let required: &[&str] = match provider {
+        LLMProvider::OpenRouter => &["OPENROUTER_API_KEY"],
+        LLMProvider::Anthropic => &["ANTHROPIC_API_KEY"],
+        LLMProvider::Gemini => &["GEMINI_API_KEY"],
+        LLMProvider::Bedrock => &["AWS_ACCESS_KEY_ID", "AWS_SECRET_ACCESS_KEY"],
+        LLMProvider::OpenAI => {
+            let key = config.openai_api_key.as_deref().filter(|k| !k.trim().is_empty());
+            match key.map(secrets::resolve) {
+                Some(Err(e)) => report.error("llm_credentials", format!("OpenAI API key: {}", e), credential_hint("OPENAI_API_KEY")),
+                Some(Ok(_)) => report.pass("llm_credentials"),
+                // OpenAI-compatible servers may not need a key
+                None if secrets::optional_credential("OPENAI_API_KEY").is_none() => report.warning("llm_credentials",
+                    "No OpenAI API key is configured".to_string(), credential_hint("OPENAI_API_KEY")),
+                None => report.pass("llm_credentials"),
+            }
+            &[]
+        }
+        LLMProvider::ClaudeCode | LLMProvider::GeminiCode => {
+            let (tool, name) = if *provider == LLMProvider::ClaudeCode { ("claude", "Claude Code") } else { ("gemini", "Gemini") };
+            let installed = Command::new(tool).arg("--version").stdout(Stdio::null()).stderr(Stdio::null()).status().is_ok();
+            if installed {
+                report.pass("llm_cli");
+            } else {
+                report.error("llm_cli", format!("The {} CLI ('{}') is not installed", name, tool),
+                    format!("Install the {} CLI and make sure '{}' is on the PATH of the Forge server", name, tool));
+            }
+            &[]
+        }
+        LLMProvider::Ollama => &[],
+    };
+    for name in required {
+        if secrets::optional_credential(name).is_some() {
+            report.pass("llm_credentials");
+        } else {
+            report.error("llm_credentials", format!("{} is required by the {:?} provider but not set", name, provider), credential_hint(name));
+        }
+    }
+}
+
+// A dependency cycle among the tasks of a block, as the task ids along the cycle
+fn find_dependency_cycle(block: &Block) -> Option<Vec<String>> {
+    fn visit<'a>(block: &'a Block, task_id: &'a str, path: &mut Vec<&'a str>, done: &mut HashSet<&'a str>) -> Option<Vec<String>> {
+        if let Some(start) = path.iter().position(|id| *id == task_id) {
+            let mut cycle: Vec<String> = path[start..].iter().map(|id| id.to_string()).collect();
+            cycle.push(task_id.to_string());
+            return Some(cycle);
+        }
+        if done.contains(task_id) {
+            return None;
+        }
+        path.push(task_id);
+        if let Some(task) = block.todo_list.get(task_id) {
+            for dep in &task.dependencies {
+                if let Some(cycle) = visit(block, dep, path, done) {
+                    return Some(cycle);
+                }
+            }
+        }
+        path.pop();
+        done.insert(task_id);
+        None
+    }
+
+    let mut task_ids: Vec<&String> = block.todo_list.keys().collect();
+    task_ids.sort();
+    let mut done = HashSet::new();
+    task_ids.into_iter().find_map(|id| visit(block, id, &mut Vec::new(), &mut done))
+}
+
+fn check_dependencies(blocks: &[Block], report: &mut ValidationReport) {
+    let mut ok = true;
+    for block in blocks {
+        if let Some(cycle) = find_dependency_cycle(block) {
+            ok = false;
+            report.error("dependencies", format!("Tasks of block '{}' depend on each other in a cycle: {}", block.name, cycle.join(" -> ")),
+                "Remove one of the dependencies of the cycle so the tasks can be ordered");
+        }
+        let mut missing: HashMap<&str, Vec<&str>> = HashMap::new();
+        for (task_id, task) in &block.todo_list {
+            for dep in task.dependencies.iter().filter(|dep| !block.todo_list.contains_key(*dep)) {
+                missing.entry(task_id.as_str()).or_default().push(dep.as_str());
+            }
+        }
+        let mut missing: Vec<_> = missing.into_iter().collect();
+        missing.sort();
+        for (task_id, deps) in missing {
+            ok = false;
+            report.warning("dependencies", format!("Task {} of block '{}' depends on missing tasks: {}", task_id, block.name, deps.join(", ")),
+                "Remove the dependencies or restore the deleted tasks");
+        }
+    }
+    if ok {
+        report.pass("dependencies");
+    }
+}
+
+fn check_blocks(config: &ProjectConfig, report: &mut ValidationReport) {
+    let spec_directory = config.spec_directory();
+    if !Path::new(&spec_directory).is_dir() {
+        report.error("blocks_file", format!("Spec directory {} does not exist", spec_directory),
+            "Create the directory or change spec_directory in the project settings");
+        return;
+    }
+    let path = match resolve_blocks_config_path(Path::new(&spec_directory), config.blocks_format.as_deref()) {
+        Ok(path) => path.to_string_lossy().to_string(),
+        Err(e) => {
+            report.error("blocks_file", e, "Set blocks_format to json, yaml or toml");
+            return;
+        }
+    };
+    if !Path::new(&path).exists() {
+        report.warning("blocks_file", format!("{} does not exist yet", path), "Add blocks in the UI or process a specification");
+        return;
+    }
+    let blocks = match fs::read_to_string(&path).map_err(|e| e.to_string()).and_then(|content| BlocksFormat::from_path(&path).parse(&content)) {
+        Ok(blocks) => blocks,
+        Err(e) => {
+            report.error("blocks_file", format!("{} cannot be parsed: {}", path, e), "Fix the file or restore it from a backup");
+            return;
+        }
+    };
+    report.pass("blocks_file");
+    check_dependencies(&blocks, report);
+}
+
+// Checks that need no network access to the LLM providers
+pub fn validate_project(config: &ProjectConfig) -> ValidationReport {
+    let mut report = ValidationReport { valid: true, ..Default::default() };
+
+    let project_dir = config.project_home_directory.as_str();
+    if project_dir.is_empty() {
+        report.error("project_directory", "Project home directory is not set".to_string(),
+            "Set the project home directory in the project settings");
+    } else if !Path::new(project_dir).is_dir() {
+        report.error("project_directory", format!("Project home directory {} does not exist", project_dir),
+            "Create the directory or open an existing project");
+    } else {
+        report.pass("project_directory");
+        check_repository(config, &mut report);
+    }
+
+    if config.git_repository_url.is_empty() {
+        report.warning("git_credentials", "No Git repository URL is configured".to_string(),
+            "Set the repository URL in the project settings to push task branches");
+    } else {
+        check_remote(config, &mut report);
+    }
+
+    for provider in configured_providers(config) {
+        check_provider_credentials(config, &provider, &mut report);
+    }
+
+    if config.branch_workflow().is_some_and(|w| w.on_success == TaskBranchCompletion::PullRequest) {
+        let token = match config.git_provider.unwrap_or_default() {
+            GitProvider::GitHub => GITHUB_TOKEN_SECRET,
+            GitProvider::GitLab => GITLAB_TOKEN_SECRET,
+        };
+        if secrets::optional_credential(token).is_some() {
+            report.pass("pull_requests");
+        } else {
+            report.error("pull_requests", format!("{} is required to open the pull requests of task branches but not set", token),
+                format!("Store the token with PUT /api/secrets/{} or set the {} environment variable", token, token));
+        }
+    }
+
+    if !project_dir.is_empty() {
+        check_blocks(config, &mut report);
+    }
+
+    match config.validate_timeouts() {
+        Ok(()) => report.pass("timeouts"),
+        Err(e) => report.error("timeouts", e, "Lower the timeout_minutes of the task limits and execution hooks"),
+    }
+
+    if let Some(tls) = &config.tls {
+        match tls.validate() {
+            Ok(()) => report.pass("tls"),
+            Err(e) => report.error("tls", e, "Fix the certificate and key paths and restart the server"),
+        }
+    }
+
+    if let Some(cors) = &config.cors {
+        match cors.validate() {
+            Ok(()) => report.pass("cors"),
+            Err(e) => report.error("cors", e, "Fix the CORS settings and restart the server"),
+        }
+    }
+
+    if let Some(webhooks) = &config.webhooks {
+        let secrets_resolve = webhooks.iter().filter_map(|w| w.secret.as_deref()).try_for_each(|secret| secrets::resolve(secret).map(|_| ()));
+        match webhooks.iter().try_for_each(|webhook| webhook.validate()).and(secrets_resolve) {
+            Ok(()) => report.pass("webhooks"),
+            Err(e) => report.error("webhooks", e, "Fix the webhook settings"),
+        }
+    }
+
+    if let Some(slack) = config.slack.as_ref().filter(|slack| slack.enabled) {
+        let secrets_resolve = slack.webhook_url.iter().chain(slack.bot_token.iter()).try_for_each(|secret| secrets::resolve(secret).map(|_| ()));
+        match slack.validate().and(secrets_resolve) {
+            Ok(()) => report.pass("slack"),
+            Err(e) => report.error("slack", e, "Fix the Slack settings"),
+        }
+    }
+    report
+}
+
+// Send a minimal prompt to each provider with working credentials
+pub async fn probe_llm_providers(config: &ProjectConfig, report: &mut ValidationReport) {
+    if report.failed("llm_credentials") {
+        return;
+    }
+    for provider in configured_providers(config).into_iter().filter(|p| !p.uses_mcp()) {
+        let llm = LLMProviderImpl::new(provider.clone());
+        match tokio::time::timeout(LLM_TIMEOUT, llm.send_prompt("Reply with the single word OK.", "OK?")).await {
+            Ok(Ok(_)) => report.pass("llm_provider"),
+            Ok(Err(e)) => report.error("llm_provider", format!("The {:?} provider failed: {}", provider, secrets::redact(&e)),
+                "Check the API key, the model name and the network access of the Forge server"),
+            Err(_) => report.error("llm_provider", format!("The {:?} provider did not respond within {} seconds", provider, LLM_TIMEOUT.as_secs()),
+                "Check that the provider endpoint is reachable from the Forge server"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Task;
+
+    #[test]
+    fn test_dependency_cycles_and_missing_dependencies() {
+        let mut block = Block::new("Auth".to_string(), "Authentication".to_string(), Vec::new(), Vec::new());
+        for (id, deps) in [("a", vec![]), ("b", vec!["a", "c"]), ("c", vec!["b"]), ("d", vec!["x"])] {
+            let mut task = Task::new(format!("Task {}", id));
+            task.task_id = id.to_string();
+            task.dependencies = deps.into_iter().map(String::from).collect();
+            block.todo_list.insert(id.to_string(), task);
+        }
+
+        assert_eq!(find_dependency_cycle(&block).unwrap(), vec!["b", "c", "b"]);
+
+        let mut report = ValidationReport { valid: true, ..Default::default() };
+        check_dependencies(&[block.clone()], &mut report);
+        assert!(!report.valid && report.failed("dependencies"));
+        assert_eq!(report.warnings.len(), 1);
+        assert!(report.warnings[0].message.contains("d of block 'Auth' depends on missing tasks: x"));
+
+        block.todo_list.get_mut("c").unwrap().dependencies.clear();
+        assert!(find_dependency_cycle(&block).is_none());
+    }
+}
@@ -0,0 +1,60 @@
+use crate::project_config::CorsConfig;
+use actix_cors::Cors;
+use actix_web::http::header::{self, HeaderValue};
+use actix_web::dev::RequestHead;
+use actix_web::http::Method;
+
+const DEFAULT_METHODS: [&str; 5] = ["GET", "POST", "PUT", "DELETE", "OPTIONS"];
+
+// Whether the origin of a request is the server itself; browsers send it with same-origin
+// requests that change data
+fn is_same_origin(origin: &str, head: &RequestHead) -> bool {
+    head.headers().get(header::HOST)
+        .and_then(|host| host.to_str().ok())
+        .is_some_and(|host| origin.split_once("://").is_some_and(|(_, origin_host)| origin_host == host))
+}
+
+pub fn is_allowed_origin(config: &CorsConfig, origin: &str, head: &RequestHead) -> bool {
+    config.allowed_origins.iter().any(|allowed| allowed == "*" || allowed.eq_ignore_ascii_case(origin))
+        || is_same_origin(origin, head)
+}
+
+// CORS middleware of the configured origins; None keeps the API to its own origin
+pub fn cors_middleware(config: Option<&CorsConfig>) -> Option<Cors> {
+    let config = config.filter(|c| !c.allowed_origins.is_empty())?.clone();
+    let methods: Vec<Method> = match &config.allowed_methods {
+        Some(methods) => methods.iter().filter_map(|m| Method::from_bytes(m.to_uppercase().as_bytes()).ok()).collect(),
+        None => DEFAULT_METHODS.iter().filter_map(|m| Method::from_bytes(m.as_bytes()).ok()).collect(),
+    };
+    let mut cors = match &config.allowed_headers {
+        Some(headers) => headers.iter()
+            .filter_map(|h| header::HeaderName::from_bytes(h.as_bytes()).ok())
+            .fold(Cors::default(), |cors, h| cors.allowed_header(h)),
+        None => Cors::default().allow_any_header(),
+    }
+        .allowed_methods(methods)
+        .expose_any_header()
+        .max_age(config.max_age_seconds);
+    if config.allow_credentials {
+        cors = cors.supports_credentials();
+    }
+    Some(cors.allowed_origin_fn(move |origin: &HeaderValue, head: &RequestHead| {
+        origin.to_str().is_ok_and(|origin| is_allowed_origin(&config, origin, head))
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test::TestRequest;
+
+    #[test]
+    fn test_is_allowed_origin() {
+        let config = CorsConfig { allowed_origins: vec!["http://localhost:5173".to_string()], ..Default::default() };
+        let request = TestRequest::default().insert_header((header::HOST, "127.0.0.1:8080")).to_srv_request();
+        assert!(is_allowed_origin(&config, "http://localhost:5173", request.head()));
+        assert!(is_allowed_origin(&config, "http://127.0.0.1:8080", request.head()));
+        assert!(!is_allowed_origin(&config, "https://evil.example", request.head()));
+        assert!(is_allowed_origin(&CorsConfig { allowed_origins: vec!["*".to_string()], ..Default::default() }, "https://evil.example", request.head()));
+    }
+}
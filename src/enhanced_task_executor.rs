@@ -7,7 +7,7 @@ use std::thread;
 use std::time::Duration;
 
 use crate::log_stream;
-use crate::models::Task;
+use crate::models::{Task, TechHints};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct EnhancedTaskExecution {
@@ -52,6 +52,7 @@ impl crate::task_executor::TaskExecutor {
 
         let task = block.todo_list.get(task_id)
             .ok_or("Task not found")?;
+        let tech_hints = block.tech_hints.as_ref();
 
         // Clear any existing logs for this task
         log_stream::clear_logs(&log_task_id);
@@ -60,7 +61,7 @@ impl crate::task_executor::TaskExecutor {
         self.setup_git_branch(&log_task_id, task_id, main_branch, &project_dir)?;
 
         // Step 2: Execute task with enhanced Claude integration
-        let execution_result = self.execute_claude_task_enhanced(task, &project_dir, &log_task_id)?;
+        let execution_result = self.execute_claude_task_enhanced(task, tech_hints, &project_dir, &log_task_id)?;
 
         // Step 3: Process results and commit if successful
         if execution_result.success {
@@ -81,12 +82,13 @@ impl crate::task_executor::TaskExecutor {
     fn execute_claude_task_enhanced(
         &self,
         task: &Task,
+        tech_hints: Option<&TechHints>,
         project_dir: &str,
         log_task_id: &str,
     ) -> Result<EnhancedTaskExecution, String> {
         
         // Create enhanced prompt with structured output requirements
-        let enhanced_prompt = self.create_enhanced_task_prompt(task, project_dir)?;
+        let enhanced_prompt = self.create_enhanced_task_prompt(task, tech_hints, project_dir)?;
         
         log_stream::add_log(log_task_id, "Starting enhanced Claude execution...".to_string());
 
@@ -203,7 +205,7 @@ impl crate::task_executor::TaskExecutor {
         Ok(result)
     }
 
-    fn create_enhanced_task_prompt(&self, task: &Task, project_dir: &str) -> Result<String, String> {
+    fn create_enhanced_task_prompt(&self, task: &Task, tech_hints: Option<&TechHints>, project_dir: &str) -> Result<String, String> {
         // Analyze project structure for context
         let project_context = self.analyze_project_context(project_dir)?;
         
@@ -227,7 +229,7 @@ You are working within Forge IDE, executing a specific development task. Please
 **Key Files**: {key_files}
 **Architecture Patterns**: {patterns}
 
-## Files to Consider
+{technology}## Files to Consider
 {files_affected}
 
 ## Dependencies
@@ -285,6 +287,7 @@ Begin the task implementation now.
             project_type = project_context.project_type,
             key_files = project_context.key_files.join(", "),
             patterns = project_context.patterns.join(", "),
+            technology = tech_hints.map(|h| h.to_prompt_section()).unwrap_or_default(),
             files_affected = if task.files_affected.is_empty() {
                 "No specific files - determine based on requirements".to_string()
             } else {
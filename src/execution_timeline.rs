@@ -0,0 +1,267 @@
+use crate::block_config::{write_file_atomic, BlockConfigManager, FileLock};
+use crate::models::Block;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+// Execution history file, stored next to the blocks config
+pub const EXECUTIONS_FILE: &str = "executions.json";
+
+// Number of executions kept in the history; older ones are dropped
+const MAX_EXECUTIONS: usize = 50;
+
+// One task run within an execution
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskExecutionRecord {
+    // Run of the queue (from busy to drained) the task belonged to; not to be confused with the
+    // "block_id:task_id" execution IDs used to cancel a task
+    #[serde(alias = "run_id")]
+    pub run_id: String,
+    pub block_id: String,
+    pub task_id: String,
+    pub started_at: String,
+    pub ended_at: Option<String>,
+    pub status: Option<String>,
+}
+
+// A bar of the timeline
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimelineTask {
+    pub block_id: String,
+    pub task_id: String,
+    pub task_name: String,
+    pub started_at: String,
+    pub ended_at: Option<String>,
+    pub duration_seconds: Option<i64>,
+    pub status: Option<String>,
+    // Row of the timeline; tasks running at the same time get different lanes
+    pub lane: usize,
+}
+
+// Dependency between two tasks of the timeline
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TimelineEdge {
+    // "block_id:task_id" of the dependency and of the task depending on it
+    pub from: String,
+    pub to: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExecutionTimeline {
+    pub run_id: String,
+    pub started_at: Option<String>,
+    pub ended_at: Option<String>,
+    pub lanes: usize,
+    pub tasks: Vec<TimelineTask>,
+    pub edges: Vec<TimelineEdge>,
+}
+
+// Summary of a run for listings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExecutionSummary {
+    pub run_id: String,
+    pub started_at: Option<String>,
+    pub ended_at: Option<String>,
+    pub num_tasks: usize,
+}
+
+fn executions_file(block_manager: &BlockConfigManager) -> String {
+    block_manager.project_data_file(EXECUTIONS_FILE)
+}
+
+// Load the execution history (missing file means no executions)
+pub fn load_records(block_manager: &BlockConfigManager) -> Result<Vec<TaskExecutionRecord>, String> {
+    let file = executions_file(block_manager);
+    if !Path::new(&file).exists() {
+        return Ok(Vec::new());
+    }
+    let content = fs::read_to_string(&file).map_err(|e| format!("Failed to read executions file: {}", e))?;
+    serde_json::from_str(&content).map_err(|e| format!("Failed to parse executions JSON: {}", e))
+}
+
+// Apply a change to the execution history under the file lock, dropping the oldest executions
+fn update_records(block_manager: &BlockConfigManager, f: impl FnOnce(&mut Vec<TaskExecutionRecord>)) -> Result<(), String> {
+    let file = executions_file(block_manager);
+    let _lock = FileLock::acquire(&file)?;
+    let mut records = load_records(block_manager)?;
+    f(&mut records);
+
+    let mut run_ids: Vec<&str> = Vec::new();
+    for record in &records {
+        if !run_ids.contains(&record.run_id.as_str()) {
+            run_ids.push(&record.run_id);
+        }
+    }
+    if run_ids.len() > MAX_EXECUTIONS {
+        let dropped: Vec<String> = run_ids[..run_ids.len() - MAX_EXECUTIONS].iter().map(|id| id.to_string()).collect();
+        records.retain(|r| !dropped.contains(&r.run_id));
+    }
+
+    let json = serde_json::to_string_pretty(&records)
+        .map_err(|e| format!("Failed to serialize executions to JSON: {}", e))?;
+    write_file_atomic(&file, json.as_bytes())
+}
+
+// Record the start of a task run
+pub fn record_start(block_manager: &BlockConfigManager, run_id: &str, block_id: &str, task_id: &str) -> Result<(), String> {
+    update_records(block_manager, |records| records.push(TaskExecutionRecord {
+        run_id: run_id.to_string(),
+        block_id: block_id.to_string(),
+        task_id: task_id.to_string(),
+        started_at: Utc::now().to_rfc3339(),
+        ended_at: None,
+        status: None,
+    }))
+}
+
+// Record the end of the latest run of a task
+pub fn record_end(block_manager: &BlockConfigManager, run_id: &str, block_id: &str, task_id: &str, status: &str) -> Result<(), String> {
+    update_records(block_manager, |records| {
+        if let Some(record) = records.iter_mut().rev()
+            .find(|r| r.run_id == run_id && r.block_id == block_id && r.task_id == task_id && r.ended_at.is_none())
+        {
+            record.ended_at = Some(Utc::now().to_rfc3339());
+            record.status = Some(status.to_string());
+        }
+    })
+}
+
+// Runs in the history, most recent first
+pub fn list_executions(records: &[TaskExecutionRecord]) -> Vec<ExecutionSummary> {
+    let mut summaries: Vec<ExecutionSummary> = Vec::new();
+    for record in records {
+        match summaries.iter_mut().find(|s| s.run_id == record.run_id) {
+            Some(summary) => {
+                summary.num_tasks += 1;
+                summary.ended_at = record.ended_at.clone();
+            },
+            None => summaries.push(ExecutionSummary {
+                run_id: record.run_id.clone(),
+                started_at: Some(record.started_at.clone()),
+                ended_at: record.ended_at.clone(),
+                num_tasks: 1,
+            }),
+        }
+    }
+    summaries.reverse();
+    summaries
+}
+
+fn parse_time(time: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(time).ok().map(|t| t.with_timezone(&Utc))
+}
+
+// Build the timeline of a run: task bars with lanes and the dependency edges between them
+pub fn build_timeline(run_id: &str, records: &[TaskExecutionRecord], blocks: &[Block]) -> Option<ExecutionTimeline> {
+    let mut runs: Vec<&TaskExecutionRecord> = records.iter().filter(|r| r.run_id == run_id).collect();
+    if runs.is_empty() {
+        return None;
+    }
+    runs.sort_by(|a, b| a.started_at.cmp(&b.started_at));
+
+    // Greedy lane assignment: a task takes the first lane whose last task ended before it started
+    let mut lane_ends: Vec<Option<DateTime<Utc>>> = Vec::new();
+    let mut tasks = Vec::new();
+    for run in &runs {
+        let start = parse_time(&run.started_at);
+        let end = run.ended_at.as_deref().and_then(parse_time);
+        let lane = lane_ends.iter()
+            .position(|lane_end| matches!((lane_end, start), (Some(lane_end), Some(start)) if *lane_end <= start))
+            .unwrap_or_else(|| {
+                lane_ends.push(None);
+                lane_ends.len() - 1
+            });
+        lane_ends[lane] = end;
+
+        let task_name = blocks.iter()
+            .find(|b| b.block_id == run.block_id)
+            .and_then(|b| b.todo_list.get(&run.task_id))
+            .map(|t| if t.task_name.is_empty() { t.description.clone() } else { t.task_name.clone() })
+            .unwrap_or_default();
+
+        tasks.push(TimelineTask {
+            block_id: run.block_id.clone(),
+            task_id: run.task_id.clone(),
+            task_name,
+            started_at: run.started_at.clone(),
+            ended_at: run.ended_at.clone(),
+            duration_seconds: start.zip(end).map(|(start, end)| (end - start).num_seconds()),
+            status: run.status.clone(),
+            lane,
+        });
+    }
+
+    // Dependencies between the tasks that ran in this run
+    let mut edges = Vec::new();
+    for task in &tasks {
+        let dependencies = blocks.iter()
+            .find(|b| b.block_id == task.block_id)
+            .and_then(|b| b.todo_list.get(&task.task_id))
+            .map(|t| t.dependencies.clone())
+            .unwrap_or_default();
+        for dependency in dependencies {
+            let edge = TimelineEdge {
+                from: format!("{}:{}", task.block_id, dependency),
+                to: format!("{}:{}", task.block_id, task.task_id),
+            };
+            let ran = tasks.iter().any(|t| t.block_id == task.block_id && t.task_id == dependency);
+            if ran && !edges.contains(&edge) {
+                edges.push(edge);
+            }
+        }
+    }
+
+    Some(ExecutionTimeline {
+        run_id: run_id.to_string(),
+        started_at: tasks.first().map(|t| t.started_at.clone()),
+        ended_at: tasks.iter().filter_map(|t| t.ended_at.clone()).max(),
+        lanes: lane_ends.len(),
+        tasks,
+        edges,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Task;
+
+    fn record(task_id: &str, started_at: &str, ended_at: &str) -> TaskExecutionRecord {
+        TaskExecutionRecord {
+            run_id: "run1".to_string(),
+            block_id: "auth".to_string(),
+            task_id: task_id.to_string(),
+            started_at: started_at.to_string(),
+            ended_at: Some(ended_at.to_string()),
+            status: Some("[COMPLETED]".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_build_timeline() {
+        let mut block = Block::new("Auth".to_string(), "Authentication".to_string(), Vec::new(), Vec::new());
+        block.block_id = "auth".to_string();
+        let mut schema = Task::new("Add user schema".to_string());
+        schema.task_id = "a".to_string();
+        let mut login = Task::new("Add login".to_string());
+        login.task_id = "b".to_string();
+        login.dependencies = vec!["a".to_string()];
+        block.todo_list.insert("a".to_string(), schema);
+        block.todo_list.insert("b".to_string(), login);
+
+        let records = vec![
+            record("a", "2024-03-15T10:00:00+00:00", "2024-03-15T10:05:00+00:00"),
+            record("b", "2024-03-15T10:05:00+00:00", "2024-03-15T10:12:30+00:00"),
+            record("c", "2024-03-15T10:06:00+00:00", "2024-03-15T10:07:00+00:00"),
+        ];
+
+        let timeline = build_timeline("run1", &records, &[block]).unwrap();
+        assert_eq!(timeline.tasks.iter().map(|t| t.lane).collect::<Vec<_>>(), vec![0, 0, 1]);
+        assert_eq!(timeline.lanes, 2);
+        assert_eq!(timeline.tasks[1].duration_seconds, Some(450));
+        assert_eq!(timeline.tasks[0].task_name, "Add user schema");
+        assert_eq!(timeline.edges, vec![TimelineEdge { from: "auth:a".to_string(), to: "auth:b".to_string() }]);
+        assert!(build_timeline("other", &records, &[]).is_none());
+    }
+}
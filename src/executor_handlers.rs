@@ -0,0 +1,245 @@
+use actix_web::{web, HttpResponse, Responder};
+use serde::{Deserialize, Serialize};
+
+use crate::block_handlers::AppState;
+use crate::execution_timeline::{build_timeline, list_executions, load_records};
+use crate::log_stream::parse_time;
+use crate::session_transcript::{list_transcripts, load_transcript, session_metrics};
+use crate::process_tracker::{list_processes, reap_orphans, TrackedProcess};
+use crate::task_executor::get_task_executor;
+use crate::task_queue::QueuedTask;
+
+// Response for the execution queue
+#[derive(Debug, Serialize)]
+pub struct ExecutionQueueResponse {
+    pub queue: Vec<QueuedTask>,
+    // Estimated hours of work in the queue (tasks without an effort count as zero)
+    pub estimated_hours: f32,
+    // Whether the queue is paused (no new task is started)
+    pub paused: bool,
+}
+
+// Request body for reordering the whole queue
+#[derive(Debug, Deserialize)]
+pub struct ReorderQueueRequest {
+    // Queue entries as "block_id:task_id", in the desired order
+    pub order: Vec<String>,
+}
+
+// Request body for moving a single queued task
+#[derive(Debug, Deserialize)]
+pub struct MoveQueuedTaskRequest {
+    pub position: usize,
+}
+
+// Request body for holding or releasing a queued task
+#[derive(Debug, Deserialize)]
+pub struct HoldQueuedTaskRequest {
+    pub on_hold: bool,
+}
+
+// Convert a queue operation result into an HTTP response
+fn queue_response(result: Result<Vec<QueuedTask>, String>) -> HttpResponse {
+    match result {
+        Ok(queue) => {
+            let estimated_hours = get_task_executor().map(|e| e.estimated_queue_hours()).unwrap_or(0.0);
+            let paused = get_task_executor().map(|e| e.is_paused()).unwrap_or(false);
+            HttpResponse::Ok().json(ExecutionQueueResponse { queue, estimated_hours, paused })
+        },
+        Err(e) => HttpResponse::BadRequest().body(e),
+    }
+}
+
+// Handler to get the pending execution queue
+pub async fn get_queue_handler() -> impl Responder {
+    match get_task_executor() {
+        Ok(executor) => HttpResponse::Ok().json(ExecutionQueueResponse { queue: executor.get_queue(), estimated_hours: executor.estimated_queue_hours(), paused: executor.is_paused() }),
+        Err(e) => HttpResponse::InternalServerError().body(e),
+    }
+}
+
+// Handler to pause the execution queue after the running task finishes
+pub async fn pause_queue_handler() -> impl Responder {
+    match get_task_executor() {
+        Ok(executor) => queue_response(executor.set_paused(true).map(|_| executor.get_queue())),
+        Err(e) => HttpResponse::InternalServerError().body(e),
+    }
+}
+
+// Handler to resume a paused execution queue
+pub async fn resume_queue_handler() -> impl Responder {
+    match get_task_executor() {
+        Ok(executor) => queue_response(executor.set_paused(false).map(|_| executor.get_queue())),
+        Err(e) => HttpResponse::InternalServerError().body(e),
+    }
+}
+
+// Handler to reorder the execution queue
+pub async fn reorder_queue_handler(request: web::Json<ReorderQueueRequest>) -> impl Responder {
+    match get_task_executor() {
+        Ok(executor) => queue_response(executor.reorder_queue(&request.order)),
+        Err(e) => HttpResponse::InternalServerError().body(e),
+    }
+}
+
+// Handler to move a queued task to a given position
+pub async fn move_queued_task_handler(
+    path: web::Path<(String, String)>,
+    request: web::Json<MoveQueuedTaskRequest>,
+) -> impl Responder {
+    let (block_id, task_id) = path.into_inner();
+    match get_task_executor() {
+        Ok(executor) => queue_response(executor.move_queued_task(&block_id, &task_id, request.position)),
+        Err(e) => HttpResponse::InternalServerError().body(e),
+    }
+}
+
+// Handler to bump a queued task to the front of the queue
+pub async fn bump_queued_task_handler(path: web::Path<(String, String)>) -> impl Responder {
+    let (block_id, task_id) = path.into_inner();
+    match get_task_executor() {
+        Ok(executor) => queue_response(executor.move_queued_task(&block_id, &task_id, 0)),
+        Err(e) => HttpResponse::InternalServerError().body(e),
+    }
+}
+
+// Handler to hold or release a queued task
+pub async fn hold_queued_task_handler(
+    path: web::Path<(String, String)>,
+    request: web::Json<HoldQueuedTaskRequest>,
+) -> impl Responder {
+    let (block_id, task_id) = path.into_inner();
+    match get_task_executor() {
+        Ok(executor) => queue_response(executor.set_queued_task_hold(&block_id, &task_id, request.on_hold)),
+        Err(e) => HttpResponse::InternalServerError().body(e),
+    }
+}
+
+// Handler to remove a task from the queue
+pub async fn remove_queued_task_handler(path: web::Path<(String, String)>) -> impl Responder {
+    let (block_id, task_id) = path.into_inner();
+    match get_task_executor() {
+        Ok(executor) => queue_response(executor.remove_queued_task(&block_id, &task_id)),
+        Err(e) => HttpResponse::InternalServerError().body(e),
+    }
+}
+
+// Response listing the processes killed by a reap
+#[derive(Debug, Serialize)]
+pub struct ReapProcessesResponse {
+    pub killed: Vec<TrackedProcess>,
+}
+
+// Handler to list the child processes tracked for running executions
+pub async fn get_processes_handler() -> impl Responder {
+    HttpResponse::Ok().json(list_processes())
+}
+
+// Handler to kill the processes orphaned by crashed Forge instances
+pub async fn reap_processes_handler() -> impl Responder {
+    match web::block(reap_orphans).await {
+        Ok(Ok(killed)) => HttpResponse::Ok().json(ReapProcessesResponse { killed }),
+        Ok(Err(e)) => HttpResponse::InternalServerError().body(e),
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
+
+// Handler to cancel a queued or running task execution ("block_id:task_id")
+pub async fn cancel_execution_handler(path: web::Path<String>) -> impl Responder {
+    let execution_id = path.into_inner();
+    let executor = match get_task_executor() {
+        Ok(executor) => executor,
+        Err(e) => return HttpResponse::InternalServerError().body(e),
+    };
+    match web::block(move || executor.cancel_execution(&execution_id)).await {
+        Ok(Ok(message)) => HttpResponse::Ok().json(serde_json::json!({ "message": message })),
+        Ok(Err(e)) => HttpResponse::NotFound().body(e),
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
+
+// Handler to list the recorded executions, most recent first
+pub async fn get_executions_handler(data: web::Data<AppState>) -> impl Responder {
+    match load_records(&data.block_manager) {
+        Ok(records) => HttpResponse::Ok().json(list_executions(&records)),
+        Err(e) => HttpResponse::InternalServerError().body(e),
+    }
+}
+
+// Handler to get the timeline of a run of the queue: task durations, lanes and dependency edges
+pub async fn get_execution_timeline_handler(path: web::Path<String>, data: web::Data<AppState>) -> impl Responder {
+    let run_id = path.into_inner();
+    let records = match load_records(&data.block_manager) {
+        Ok(records) => records,
+        Err(e) => return HttpResponse::InternalServerError().body(e),
+    };
+    let blocks = match data.block_manager.get_blocks() {
+        Ok(blocks) => blocks,
+        Err(e) => return HttpResponse::InternalServerError().body(e),
+    };
+
+    match build_timeline(&run_id, &records, &blocks) {
+        Some(timeline) => HttpResponse::Ok().json(timeline),
+        None => HttpResponse::NotFound().body(format!("Run {} not found", run_id)),
+    }
+}
+
+// Query of a transcript: json (default) or markdown
+#[derive(Debug, Default, Deserialize)]
+pub struct TranscriptQuery {
+    pub format: Option<String>,
+}
+
+// Handler to list the session transcripts of a task, most recent first
+pub async fn get_transcripts_handler(path: web::Path<(String, String)>, data: web::Data<AppState>) -> impl Responder {
+    let (block_id, task_id) = path.into_inner();
+    match list_transcripts(&data.block_manager, &block_id, &task_id) {
+        Ok(transcripts) => HttpResponse::Ok().json(transcripts),
+        Err(e) if e.starts_with("Invalid") => HttpResponse::BadRequest().body(e),
+        Err(e) => HttpResponse::InternalServerError().body(e),
+    }
+}
+
+// Handler to get a session transcript ("latest" for the last run), as JSON or as a markdown export
+pub async fn get_transcript_handler(path: web::Path<(String, String, String)>, query: web::Query<TranscriptQuery>, data: web::Data<AppState>) -> impl Responder {
+    let (block_id, task_id, transcript_id) = path.into_inner();
+    let transcript = match load_transcript(&data.block_manager, &block_id, &task_id, &transcript_id) {
+        Ok(transcript) => transcript,
+        Err(e) if e.starts_with("Invalid") => return HttpResponse::BadRequest().body(e),
+        Err(e) => return HttpResponse::NotFound().body(e),
+    };
+
+    match query.format.as_deref() {
+        Some("markdown") | Some("md") => HttpResponse::Ok()
+            .content_type("text/markdown; charset=utf-8")
+            .insert_header(("Content-Disposition", format!("attachment; filename=\"transcript-{}-{}.md\"", block_id, task_id)))
+            .body(transcript.to_markdown()),
+        _ => HttpResponse::Ok().json(transcript),
+    }
+}
+
+// Filters of the session metrics
+#[derive(Debug, Default, Deserialize)]
+pub struct SessionMetricsQuery {
+    pub block_id: Option<String>,
+    // RFC 3339 time or date of the oldest session
+    pub since: Option<String>,
+    // Sessions listed, most recent first; the totals cover all of them
+    pub limit: Option<usize>,
+}
+
+// Handler to get the duration, tool calls, tokens and outcome of the Claude sessions of the tasks
+pub async fn get_session_metrics_handler(query: web::Query<SessionMetricsQuery>, data: web::Data<AppState>) -> impl Responder {
+    let since = match query.since.as_deref().filter(|s| !s.is_empty()).map(parse_time).transpose() {
+        Ok(since) => since,
+        Err(e) => return HttpResponse::BadRequest().body(e),
+    };
+    let block_id = query.block_id.clone().filter(|b| !b.is_empty());
+    let limit = query.limit.unwrap_or(100);
+    let block_manager = data.block_manager.clone();
+    match web::block(move || session_metrics(&block_manager, block_id.as_deref(), since, limit)).await {
+        Ok(Ok(report)) => HttpResponse::Ok().json(report),
+        Ok(Err(e)) => HttpResponse::BadRequest().body(e),
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
@@ -7,6 +7,8 @@ use std::process::Command;
 use std::sync::Arc;
 
 use crate::block_config::BlockConfigManager;
+use crate::preflight::{run_preflight, PreflightReport};
+use crate::pull_request::create_pull_request;
 use crate::project_config::ProjectConfigManager;
 use crate::task_executor_wrapper::enqueue_task;
 
@@ -24,6 +26,26 @@ pub struct ExecuteGitTaskRequest {
     pub task_description: String,
     pub resolve_dependencies: bool,
     pub force_completed: bool,
+    // Queue the task even if pre-flight checks fail
+    #[serde(default)]
+    pub skip_preflight: bool,
+}
+
+// Request body for running the pre-flight checks of a task
+#[derive(Debug, Deserialize)]
+pub struct PreflightRequest {
+    pub block_id: String,
+    pub task_id: String,
+    #[serde(default)]
+    pub resolve_dependencies: bool,
+}
+
+// Response of a refused task execution
+#[derive(Debug, Serialize)]
+pub struct PreflightFailedResponse {
+    pub success: bool,
+    pub message: String,
+    pub preflight: PreflightReport,
 }
 
 // Request body for creating a branch
@@ -45,6 +67,17 @@ pub struct MergeBranchRequest {
     pub target_branch: String,
 }
 
+// Request body for opening a pull request for a task
+#[derive(Debug, Deserialize)]
+pub struct CreatePullRequestRequest {
+    pub block_id: String,
+    pub task_id: String,
+    // Branch to merge into, defaults to the main branch of the project
+    pub base_branch: Option<String>,
+    #[serde(default)]
+    pub draft: bool,
+}
+
 // Response for Git operations
 #[derive(Debug, Serialize)]
 pub struct GitResponse {
@@ -461,6 +494,45 @@ pub async fn pull_handler(data: web::Data<GitAppState>) -> impl Responder {
 }
 
 // Handler to execute a task with Git integration
+// Run the pre-flight checks of a task on a blocking thread; None when they are disabled
+async fn preflight_report(data: &web::Data<GitAppState>, block_id: &str, task_id: &str, resolve_dependencies: bool) -> Result<Option<PreflightReport>, String> {
+    let project_config = data.project_manager.get_config()
+        .map_err(|e| format!("Failed to get project configuration: {}", e))?;
+    let config = project_config.preflight.clone().unwrap_or_default();
+    if !config.enabled {
+        return Ok(None);
+    }
+    let blocks = data.block_manager.get_blocks()?;
+
+    let (block_id, task_id) = (block_id.to_string(), task_id.to_string());
+    web::block(move || run_preflight(&project_config, &config, &blocks, &block_id, &task_id, resolve_dependencies))
+        .await
+        .map(Some)
+        .map_err(|e| format!("Failed to run pre-flight checks: {}", e))
+}
+
+// Handler to run the pre-flight checks of a task without queuing it
+pub async fn preflight_handler(
+    data: web::Data<GitAppState>,
+    request: web::Json<PreflightRequest>,
+) -> impl Responder {
+    let project_config = match data.project_manager.get_config() {
+        Ok(config) => config,
+        Err(e) => return HttpResponse::InternalServerError().body(format!("Failed to get project configuration: {}", e)),
+    };
+    let config = project_config.preflight.clone().unwrap_or_default();
+    let blocks = match data.block_manager.get_blocks() {
+        Ok(blocks) => blocks,
+        Err(e) => return HttpResponse::InternalServerError().body(e),
+    };
+
+    let request = request.into_inner();
+    match web::block(move || run_preflight(&project_config, &config, &blocks, &request.block_id, &request.task_id, request.resolve_dependencies)).await {
+        Ok(report) => HttpResponse::Ok().json(report),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Failed to run pre-flight checks: {}", e)),
+    }
+}
+
 pub async fn execute_git_task_handler(
     data: web::Data<GitAppState>,
     request: web::Json<ExecuteGitTaskRequest>,
@@ -468,7 +540,22 @@ pub async fn execute_git_task_handler(
     let request = request.into_inner();
     let resolve_dependencies = request.resolve_dependencies;
     let force_completed = request.force_completed;
-    
+
+    // Refuse to start when the environment isn't ready, unless overridden
+    if !request.skip_preflight {
+        match preflight_report(&data, &request.block_id, &request.task_id, resolve_dependencies).await {
+            Ok(Some(report)) if !report.passed => {
+                return HttpResponse::PreconditionFailed().json(PreflightFailedResponse {
+                    success: false,
+                    message: format!("Pre-flight checks failed: {}", report.failures()),
+                    preflight: report,
+                });
+            },
+            Ok(_) => {},
+            Err(e) => return HttpResponse::InternalServerError().json(GitResponse { success: false, message: e }),
+        }
+    }
+
     let result= enqueue_task(&*request.block_id, &*request.task_id, &*request.task_description, resolve_dependencies, force_completed);
     match result {
         Ok(_) => {
@@ -837,3 +924,18 @@ pub async fn get_branches_handler(data: web::Data<GitAppState>) -> impl Responde
         }
     }
 }
+
+// Handler to push the branch of a task and open a pull (GitLab merge) request for it
+pub async fn create_pull_request_handler(
+    data: web::Data<GitAppState>,
+    request: web::Json<CreatePullRequestRequest>,
+) -> impl Responder {
+    let request = request.into_inner();
+    match create_pull_request(&data.project_manager, &data.block_manager, &request.block_id, &request.task_id, request.base_branch, request.draft).await {
+        Ok(pull_request) => HttpResponse::Created().json(pull_request),
+        Err(e) => HttpResponse::BadRequest().json(GitResponse {
+            success: false,
+            message: format!("Failed to create pull request: {}", e),
+        }),
+    }
+}
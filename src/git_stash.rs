@@ -0,0 +1,73 @@
+use crate::git_util::git;
+
+// Stash the uncommitted changes of a working tree, returning the commit of the stash; None when
+// there was nothing to stash
+pub fn stash_save(dir: &str, message: &str, include_untracked: bool) -> Result<Option<String>, String> {
+    let untracked = if include_untracked { "--untracked-files=all" } else { "--untracked-files=no" };
+    if git(dir, &["status", "--porcelain", untracked])?.is_empty() {
+        return Ok(None);
+    }
+    let mut args = vec!["stash", "push", "--message", message];
+    if include_untracked {
+        args.push("--include-untracked");
+    }
+    git(dir, &args)?;
+    git(dir, &["rev-parse", "refs/stash"]).map(Some)
+}
+
+// Reference of a stash given by its commit or as stash@{n}. Stashes are popped by their commit so
+// stashes pushed in between don't matter.
+fn stash_reference(dir: &str, stash: &str) -> Result<String, String> {
+    if stash.starts_with("stash@{") {
+        return Ok(stash.to_string());
+    }
+    let commit = git(dir, &["rev-parse", "--verify", "--quiet", &format!("{}^{{commit}}", stash)])
+        .map_err(|_| format!("Stash {} not found", stash))?;
+    git(dir, &["stash", "list", "--format=%H"])?
+        .lines()
+        .position(|line| line == commit)
+        .map(|index| format!("stash@{{{}}}", index))
+        .ok_or_else(|| format!("Stash {} not found", stash))
+}
+
+// Restore a stash (the latest by default) and drop it, returning the commit of the stash. A
+// conflicting restore keeps the stash.
+pub fn stash_pop(dir: &str, stash: Option<&str>) -> Result<String, String> {
+    let reference = stash_reference(dir, stash.unwrap_or("stash@{0}"))?;
+    let commit = git(dir, &["rev-parse", &reference]).map_err(|_| format!("Stash {} not found", reference))?;
+    git(dir, &["stash", "pop", &reference])?;
+    Ok(commit)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_stash_save_and_pop() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = dir.path().to_string_lossy().to_string();
+        git(&repo, &["init", "--quiet", "-b", "main"]).unwrap();
+        git(&repo, &["config", "user.email", "forge@example.com"]).unwrap();
+        git(&repo, &["config", "user.name", "Forge"]).unwrap();
+        fs::write(dir.path().join("README.md"), "readme").unwrap();
+        git(&repo, &["add", "README.md"]).unwrap();
+        git(&repo, &["commit", "--quiet", "-m", "Initial commit"]).unwrap();
+        assert_eq!(stash_save(&repo, "clean", true).unwrap(), None);
+
+        fs::write(dir.path().join("README.md"), "local edit").unwrap();
+        fs::write(dir.path().join("notes.txt"), "untracked").unwrap();
+        let parked = stash_save(&repo, "before task", true).unwrap().unwrap();
+        assert!(!dir.path().join("notes.txt").exists());
+
+        // A later stash doesn't get in the way of restoring the first one
+        fs::write(dir.path().join("README.md"), "other edit").unwrap();
+        stash_save(&repo, "other", false).unwrap().unwrap();
+        assert_eq!(stash_pop(&repo, Some(&parked)).unwrap(), parked);
+        assert_eq!(fs::read_to_string(dir.path().join("README.md")).unwrap(), "local edit");
+        assert!(dir.path().join("notes.txt").exists());
+        assert_eq!(git(&repo, &["stash", "list"]).unwrap().lines().count(), 1);
+        assert!(stash_pop(&repo, Some(&parked)).is_err());
+    }
+}
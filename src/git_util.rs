@@ -0,0 +1,22 @@
+use std::ffi::OsStr;
+use std::path::Path;
+use std::process::Command;
+
+// Run git in a directory, returning the trimmed stdout; a failure returns git's stderr
+pub fn git(dir: impl AsRef<Path>, args: &[&str]) -> Result<String, String> {
+    git_with_env(dir, args, &[])
+}
+
+// Run git like `git` with extra environment variables, e.g. a temporary GIT_INDEX_FILE
+pub fn git_with_env(dir: impl AsRef<Path>, args: &[&str], env: &[(&str, &OsStr)]) -> Result<String, String> {
+    let output = Command::new("git")
+        .current_dir(dir)
+        .args(args)
+        .envs(env.iter().copied())
+        .output()
+        .map_err(|e| format!("Failed to run git {}: {}", args.join(" "), e))?;
+    if !output.status.success() {
+        return Err(format!("git {} failed: {}", args.join(" "), String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
@@ -0,0 +1,347 @@
+use crate::block_config::BlockConfigManager;
+use crate::models::Task;
+use crate::project_config::{ProjectConfig, ProjectConfigManager, TASK_STATUS_COMPLETED};
+use crate::pull_request::{config_remote_project, github_api_url, GITHUB_TOKEN_SECRET};
+use crate::secrets;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+// Issues listed per request, the maximum of the API
+const PAGE_SIZE: usize = 100;
+
+const CRITERIA_HEADING: &str = "## Acceptance criteria";
+
+// Task linked to an issue
+#[derive(Debug, Clone, Serialize)]
+pub struct IssueLink {
+    pub task_id: String,
+    pub issue_number: u64,
+}
+
+// Outcome of a sync in either direction; failed tasks or issues don't stop the others
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct IssueSyncReport {
+    pub repository: String,
+    pub created: Vec<IssueLink>,
+    pub updated: Vec<IssueLink>,
+    pub errors: Vec<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct IssueLabel {
+    name: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct IssueUser {
+    login: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Issue {
+    number: u64,
+    title: String,
+    #[serde(default)]
+    body: Option<String>,
+    state: String,
+    #[serde(default)]
+    labels: Vec<IssueLabel>,
+    #[serde(default)]
+    assignees: Vec<IssueUser>,
+    // Set on the pull requests the issues endpoint lists too
+    #[serde(default)]
+    pull_request: Option<Value>,
+}
+
+// Hidden marker linking an issue to the task it was exported from
+fn task_marker(block_id: &str, task_id: &str) -> String {
+    format!("<!-- forge:{}:{} -->", block_id, task_id)
+}
+
+// Body of the issue of a task: its description, acceptance criteria and the marker of the task
+pub fn issue_body(block_id: &str, task: &Task) -> String {
+    let mut body = task.description.trim().to_string();
+    if !task.acceptance_criteria.is_empty() {
+        body.push_str(&format!("\n\n{}\n\n", CRITERIA_HEADING));
+        for criterion in &task.acceptance_criteria {
+            body.push_str(&format!("- [ ] {}\n", criterion));
+        }
+    }
+    format!("{}\n\n{}", body.trim_end(), task_marker(block_id, &task.task_id))
+}
+
+// Description, acceptance criteria and task marker (block and task IDs) of an issue body
+pub fn parse_issue_body(body: &str) -> (String, Vec<String>, Option<(String, String)>) {
+    let mut body = body.replace("\r\n", "\n");
+    let mut marker = None;
+    if let Some(start) = body.find("<!-- forge:") {
+        if let Some(length) = body[start..].find("-->") {
+            let ids = body[start + "<!-- forge:".len()..start + length].trim().to_string();
+            marker = ids.split_once(':').map(|(block_id, task_id)| (block_id.to_string(), task_id.to_string()));
+            body.replace_range(start..start + length + "-->".len(), "");
+        }
+    }
+    let (description, criteria) = match body.split_once(CRITERIA_HEADING) {
+        Some((description, criteria)) => {
+            let criteria = criteria.lines()
+                .filter_map(|line| {
+                    let line = line.trim();
+                    ["- [ ] ", "- [x] ", "- [X] ", "- "].iter().find_map(|prefix| line.strip_prefix(prefix))
+                })
+                .map(|criterion| criterion.trim().to_string())
+                .filter(|criterion| !criterion.is_empty())
+                .collect();
+            (description, criteria)
+        }
+        None => (body.as_str(), Vec::new()),
+    };
+    (description.trim().to_string(), criteria, marker)
+}
+
+// GitHub REST API client for the issues of a repository
+struct GitHubIssues {
+    client: reqwest::Client,
+    api_url: String,
+    repository: String,
+    token: String,
+}
+
+impl GitHubIssues {
+    fn new(config: &ProjectConfig) -> Result<Self, String> {
+        let settings = config.github_issues.clone().unwrap_or_default();
+        let token = secrets::credential(GITHUB_TOKEN_SECRET)?;
+
+        let remote = config_remote_project(config);
+        let repository = settings.repository.clone()
+            .filter(|r| !r.trim().is_empty())
+            .or_else(|| remote.as_ref().map(|(_, path)| path.clone()))
+            .ok_or_else(|| "Can't find the GitHub repository; set github_issues.repository".to_string())?;
+        let api_url = settings.api_url.clone()
+            .filter(|url| !url.trim().is_empty())
+            .unwrap_or_else(|| github_api_url(remote.as_ref().map(|(host, _)| host.as_str()).unwrap_or("github.com")));
+
+        Ok(Self {
+            client: reqwest::Client::new(),
+            api_url: api_url.trim_end_matches('/').to_string(),
+            repository,
+            token,
+        })
+    }
+
+    async fn request(&self, method: reqwest::Method, path: &str, query: &[(&str, String)], body: Option<Value>) -> Result<Value, String> {
+        let mut request = self.client.request(method, format!("{}/repos/{}{}", self.api_url, self.repository, path))
+            .query(query)
+            .bearer_auth(&self.token)
+            .header("Accept", "application/vnd.github+json")
+            .header("X-GitHub-Api-Version", "2022-11-28")
+            .header("User-Agent", "forge");
+        if let Some(body) = body {
+            request = request.json(&body);
+        }
+        let response = request.send().await.map_err(|e| format!("Failed to reach GitHub: {}", e))?;
+        let status = response.status();
+        let body: Value = response.json().await.unwrap_or(Value::Null);
+        if !status.is_success() {
+            let message = body["message"].as_str().unwrap_or("Unknown error");
+            return Err(format!("GitHub answered {}: {}", status, message));
+        }
+        Ok(body)
+    }
+
+    async fn list_issues(&self, state: &str, labels: Option<&str>) -> Result<Vec<Issue>, String> {
+        let mut issues = Vec::new();
+        for page in 1.. {
+            let mut query = vec![("state", state.to_string()), ("per_page", PAGE_SIZE.to_string()), ("page", page.to_string())];
+            if let Some(labels) = labels {
+                query.push(("labels", labels.to_string()));
+            }
+            let body = self.request(reqwest::Method::GET, "/issues", &query, None).await?;
+            let batch: Vec<Issue> = serde_json::from_value(body).map_err(|e| format!("Invalid GitHub issues response: {}", e))?;
+            let done = batch.len() < PAGE_SIZE;
+            issues.extend(batch.into_iter().filter(|issue| issue.pull_request.is_none()));
+            if done {
+                break;
+            }
+        }
+        Ok(issues)
+    }
+}
+
+// Create or update the GitHub issue of every task of a block (or of the given tasks): title, body,
+// labels, assignees, and closed once the task is completed. The issue numbers are stored on the tasks.
+pub async fn sync_block_to_github_issues(
+    project_manager: &ProjectConfigManager,
+    block_manager: &BlockConfigManager,
+    block_id: &str,
+    task_ids: Option<Vec<String>>,
+) -> Result<IssueSyncReport, String> {
+    let config = project_manager.get_config().map_err(|e| format!("Failed to get project config: {}", e))?;
+    let workflow = project_manager.get_task_workflow();
+    let github = GitHubIssues::new(&config)?;
+    let settings = config.github_issues.clone().unwrap_or_default();
+
+    let mut block = block_manager.get_blocks()?
+        .into_iter()
+        .find(|b| b.block_id == block_id)
+        .ok_or_else(|| format!("Block with ID {} not found", block_id))?;
+    let mut tasks: Vec<Task> = block.todo_list.values()
+        .filter(|t| task_ids.as_ref().is_none_or(|ids| ids.contains(&t.task_id)))
+        .cloned()
+        .collect();
+    tasks.sort_by(|a, b| a.task_id.cmp(&b.task_id));
+
+    let mut report = IssueSyncReport { repository: github.repository.clone(), ..Default::default() };
+    for task in tasks {
+        let title = if task.task_name.trim().is_empty() { task.description.lines().next().unwrap_or(&task.task_id) } else { task.task_name.as_str() };
+        let mut labels = settings.labels.clone();
+        labels.extend(task.labels.iter().filter(|l| !settings.labels.contains(l)).cloned());
+        let state = if workflow.is_completed(&task.status) { "closed" } else { "open" };
+        let issue = json!({
+            "title": title.trim(),
+            "body": issue_body(block_id, &task),
+            "labels": labels,
+            "assignees": task.assignees,
+        });
+
+        let result = match task.github_issue_number {
+            Some(number) => {
+                let mut issue = issue;
+                issue["state"] = json!(state);
+                github.request(reqwest::Method::PATCH, &format!("/issues/{}", number), &[], Some(issue)).await
+                    .map(|_| report.updated.push(IssueLink { task_id: task.task_id.clone(), issue_number: number }))
+            }
+            None => match github.request(reqwest::Method::POST, "/issues", &[], Some(issue)).await {
+                Ok(created) => {
+                    let number = created["number"].as_u64().unwrap_or_default();
+                    if let Some(task) = block.todo_list.get_mut(&task.task_id) {
+                        task.github_issue_number = Some(number);
+                    }
+                    report.created.push(IssueLink { task_id: task.task_id.clone(), issue_number: number });
+                    // Issues are created open
+                    if state == "closed" {
+                        github.request(reqwest::Method::PATCH, &format!("/issues/{}", number), &[], Some(json!({ "state": "closed" }))).await.map(|_| ())
+                    } else {
+                        Ok(())
+                    }
+                }
+                Err(e) => Err(e),
+            },
+        };
+        if let Err(e) = result {
+            report.errors.push(format!("Task {}: {}", task.task_id, e));
+        }
+    }
+
+    if !report.created.is_empty() {
+        block_manager.update_block(block)?;
+        block_manager.save_blocks_from("github_issues::sync_block_to_github_issues")?;
+    }
+    Ok(report)
+}
+
+// Import the issues of the repository into a block: issues linked to a task of the block update it
+// (title, description, acceptance criteria, labels, assignees and status), the others become new tasks.
+// Issues linked to another block are skipped.
+pub async fn import_github_issues_to_block(
+    project_manager: &ProjectConfigManager,
+    block_manager: &BlockConfigManager,
+    block_id: &str,
+    state: &str,
+    labels: Option<&str>,
+) -> Result<IssueSyncReport, String> {
+    let config = project_manager.get_config().map_err(|e| format!("Failed to get project config: {}", e))?;
+    let workflow = project_manager.get_task_workflow();
+    let github = GitHubIssues::new(&config)?;
+    let settings = config.github_issues.clone().unwrap_or_default();
+    let issues = github.list_issues(state, labels).await?;
+
+    let blocks = block_manager.get_blocks()?;
+    let linked_elsewhere: Vec<u64> = blocks.iter()
+        .filter(|b| b.block_id != block_id)
+        .flat_map(|b| b.todo_list.values().filter_map(|t| t.github_issue_number))
+        .collect();
+    let mut block = blocks.into_iter()
+        .find(|b| b.block_id == block_id)
+        .ok_or_else(|| format!("Block with ID {} not found", block_id))?;
+
+    let mut report = IssueSyncReport { repository: github.repository.clone(), ..Default::default() };
+    for issue in issues {
+        let (description, acceptance_criteria, marker) = parse_issue_body(issue.body.as_deref().unwrap_or_default());
+        if linked_elsewhere.contains(&issue.number) || marker.as_ref().is_some_and(|(marked_block, _)| marked_block != block_id) {
+            continue;
+        }
+        let description = if description.is_empty() { issue.title.clone() } else { description };
+        let labels: Vec<String> = issue.labels.iter()
+            .map(|l| l.name.clone())
+            .filter(|l| !settings.labels.contains(l))
+            .collect();
+        let assignees: Vec<String> = issue.assignees.iter().map(|a| a.login.clone()).collect();
+
+        let existing = block.todo_list.values()
+            .find(|t| t.github_issue_number == Some(issue.number))
+            .or_else(|| marker.as_ref().and_then(|(_, task_id)| block.todo_list.get(task_id)))
+            .map(|t| t.task_id.clone());
+        let link = |task_id: &str| IssueLink { task_id: task_id.to_string(), issue_number: issue.number };
+        let existing = match existing {
+            Some(task_id) => block.todo_list.get_mut(&task_id),
+            None => None,
+        };
+        match existing {
+            Some(task) => {
+                task.task_name = issue.title.clone();
+                task.description = description;
+                task.acceptance_criteria = acceptance_criteria;
+                task.labels = labels;
+                task.assignees = assignees;
+                task.github_issue_number = Some(issue.number);
+                if issue.state == "closed" {
+                    task.status = TASK_STATUS_COMPLETED.to_string();
+                } else if workflow.is_completed(&task.status) {
+                    // Reopened issue
+                    task.status = workflow.initial_status();
+                }
+                report.updated.push(link(&task.task_id));
+            }
+            None => {
+                let mut task = Task::new(description);
+                task.task_name = issue.title.clone();
+                task.acceptance_criteria = acceptance_criteria;
+                task.labels = labels;
+                task.assignees = assignees;
+                task.github_issue_number = Some(issue.number);
+                task.status = if issue.state == "closed" { TASK_STATUS_COMPLETED.to_string() } else { workflow.initial_status() };
+                report.created.push(link(&task.task_id));
+                block.todo_list.insert(task.task_id.clone(), task);
+            }
+        }
+    }
+
+    if !report.created.is_empty() || !report.updated.is_empty() {
+        block_manager.update_block(block)?;
+        block_manager.save_blocks_from("github_issues::import_github_issues_to_block")?;
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_issue_body_round_trip() {
+        let mut task = Task::new("Add the login form\nwith inline errors".to_string());
+        task.acceptance_criteria = vec!["Errors are shown inline".to_string(), "The form is keyboard accessible".to_string()];
+        let body = issue_body("AB12CD", &task);
+        assert!(body.contains("- [ ] Errors are shown inline\n"));
+
+        let (description, criteria, marker) = parse_issue_body(&body);
+        assert_eq!(description, task.description);
+        assert_eq!(criteria, task.acceptance_criteria);
+        assert_eq!(marker, Some(("AB12CD".to_string(), task.task_id.clone())));
+
+        let (description, criteria, marker) = parse_issue_body("Crash on start\r\n\r\n## Acceptance criteria\r\n- [x] No crash");
+        assert_eq!(description, "Crash on start");
+        assert_eq!(criteria, vec!["No crash"]);
+        assert_eq!(marker, None);
+    }
+}
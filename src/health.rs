@@ -0,0 +1,169 @@
+use crate::block_handlers::AppState;
+use crate::config_validation::configured_providers;
+use crate::llm_handler::provider_api_url;
+use crate::project_config::ProjectConfig;
+use crate::shutdown;
+use crate::task_executor::get_task_executor;
+use actix_web::{web, HttpResponse, Responder};
+use serde::Serialize;
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::process::Command;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+// Time allowed for an LLM provider to answer the reachability check
+const PROVIDER_TIMEOUT: Duration = Duration::from_secs(5);
+
+// Probes may come every few seconds; the providers are checked at most this often
+const PROVIDER_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+// Status of a subsystem; the server isn't ready while a required one is down
+#[derive(Debug, Clone, Serialize)]
+pub struct SubsystemStatus {
+    pub ok: bool,
+    pub required: bool,
+    pub detail: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReadinessReport {
+    pub ready: bool,
+    pub checks: BTreeMap<String, SubsystemStatus>,
+}
+
+impl ReadinessReport {
+    fn new(checks: BTreeMap<String, SubsystemStatus>) -> Self {
+        let ready = checks.values().all(|check| check.ok || !check.required);
+        Self { ready, checks }
+    }
+}
+
+lazy_static::lazy_static! {
+    // Last reachability check of the LLM providers
+    static ref PROVIDER_STATUS: Mutex<Option<(Instant, SubsystemStatus)>> = Mutex::new(None);
+}
+
+fn status(ok: bool, required: bool, detail: String) -> SubsystemStatus {
+    SubsystemStatus { ok, required, detail }
+}
+
+fn check_block_store(data: &AppState) -> SubsystemStatus {
+    let config_file = data.block_manager.config_file();
+    match data.block_manager.get_blocks() {
+        Ok(_) if !Path::new(&config_file).exists() => status(false, true, format!("Blocks config {} not found", config_file)),
+        Ok(blocks) => status(true, true, format!("{} blocks loaded from {}", blocks.len(), config_file)),
+        Err(e) => status(false, true, e),
+    }
+}
+
+fn check_git(config: &ProjectConfig) -> SubsystemStatus {
+    let project_dir = &config.project_home_directory;
+    if project_dir.is_empty() {
+        return status(false, true, "No project home directory is configured".to_string());
+    }
+    match Command::new("git").args(["rev-parse", "--is-inside-work-tree"]).current_dir(project_dir).output() {
+        Ok(output) if output.status.success() => status(true, true, format!("{} is a git repository", project_dir)),
+        Ok(_) => status(false, true, format!("{} is not a git repository", project_dir)),
+        Err(e) => status(false, true, format!("Failed to run git: {}", e)),
+    }
+}
+
+fn check_executor() -> SubsystemStatus {
+    let executor = match get_task_executor() {
+        Ok(executor) => executor,
+        Err(e) => return status(false, true, e),
+    };
+    let queued = executor.get_queue().len();
+    let running = executor.running_execution();
+    let detail = format!("{} running, {} queued{}",
+        running.as_deref().unwrap_or("none"), queued, if executor.is_paused() { ", paused" } else { "" });
+    if shutdown::is_requested() {
+        return status(false, true, format!("Shutting down, {}", detail));
+    }
+    status(true, true, detail)
+}
+
+// Whether the APIs of the providers answer at all; an error status still means reachable. No prompt is
+// sent, so the check costs nothing. Not required: the server works without LLMs.
+async fn check_llm_providers(config: &ProjectConfig) -> SubsystemStatus {
+    if let Some((checked_at, status)) = PROVIDER_STATUS.lock().unwrap().as_ref() {
+        if checked_at.elapsed() < PROVIDER_CHECK_INTERVAL {
+            return status.clone();
+        }
+    }
+
+    let client = reqwest::Client::new();
+    let mut unreachable = Vec::new();
+    let mut reachable = Vec::new();
+    for provider in configured_providers(config) {
+        let Some(url) = provider_api_url(&provider, config) else {
+            continue;
+        };
+        match client.get(&url).timeout(PROVIDER_TIMEOUT).send().await {
+            Ok(_) => reachable.push(format!("{:?}", provider)),
+            Err(e) => unreachable.push(format!("{:?} ({})", provider, e)),
+        }
+    }
+    let result = if !unreachable.is_empty() {
+        status(false, false, format!("Unreachable: {}", unreachable.join(", ")))
+    } else if reachable.is_empty() {
+        status(true, false, "Only CLI agents are configured".to_string())
+    } else {
+        status(true, false, format!("Reachable: {}", reachable.join(", ")))
+    };
+    *PROVIDER_STATUS.lock().unwrap() = Some((Instant::now(), result.clone()));
+    result
+}
+
+// Liveness: the server answers requests
+pub async fn healthz_handler() -> impl Responder {
+    HttpResponse::Ok().json(json!({ "status": "ok" }))
+}
+
+// Readiness: the subsystems the server needs to do its work; 503 while a required one is down
+pub async fn readyz_handler(data: web::Data<AppState>) -> impl Responder {
+    let config = data.project_manager.get_config().unwrap_or_default();
+    let llm = check_llm_providers(&config).await;
+
+    let state = data.clone();
+    let checks = web::block(move || {
+        BTreeMap::from([
+            ("block_store".to_string(), check_block_store(&state)),
+            ("git".to_string(), check_git(&config)),
+            ("executor".to_string(), check_executor()),
+        ])
+    }).await;
+    let mut checks = match checks {
+        Ok(checks) => checks,
+        Err(e) => return HttpResponse::InternalServerError().body(format!("Failed to run the checks: {}", e)),
+    };
+    checks.insert("llm_provider".to_string(), llm);
+
+    let report = ReadinessReport::new(checks);
+    if report.ready {
+        HttpResponse::Ok().json(report)
+    } else {
+        HttpResponse::ServiceUnavailable().json(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_readiness_ignores_optional_checks() {
+        let report = ReadinessReport::new(BTreeMap::from([
+            ("git".to_string(), status(true, true, String::new())),
+            ("llm_provider".to_string(), status(false, false, String::new())),
+        ]));
+        assert!(report.ready);
+
+        let report = ReadinessReport::new(BTreeMap::from([
+            ("git".to_string(), status(false, true, String::new())),
+        ]));
+        assert!(!report.ready);
+    }
+}
@@ -0,0 +1,152 @@
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+// Remove the markdown code fence lines models wrap JSON in
+pub fn strip_fences(text: &str) -> String {
+    text.lines()
+        .filter(|line| !line.trim_start().starts_with("```"))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// Repair the JSON value starting at the beginning of `json`: drop trailing commas and comments,
+// escape raw control characters in strings and close a truncated value. Text after the value ends
+// is ignored.
+pub fn repair_json(json: &str) -> String {
+    let chars: Vec<char> = json.chars().collect();
+    let mut out = String::with_capacity(json.len());
+    let mut closers: Vec<char> = Vec::new();
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        i += 1;
+
+        if in_string {
+            match c {
+                _ if escaped => {
+                    escaped = false;
+                    out.push(c);
+                }
+                '\\' => {
+                    escaped = true;
+                    out.push(c);
+                }
+                '"' => {
+                    in_string = false;
+                    out.push(c);
+                }
+                '\n' => out.push_str("\\n"),
+                '\r' => out.push_str("\\r"),
+                '\t' => out.push_str("\\t"),
+                _ => out.push(c),
+            }
+            continue;
+        }
+
+        match c {
+            '"' => {
+                in_string = true;
+                out.push(c);
+            }
+            '{' => {
+                closers.push('}');
+                out.push(c);
+            }
+            '[' => {
+                closers.push(']');
+                out.push(c);
+            }
+            '}' | ']' => {
+                closers.pop();
+                out.push(c);
+                if closers.is_empty() {
+                    break;
+                }
+            }
+            ',' => {
+                let next = chars[i..].iter().find(|c| !c.is_whitespace());
+                if !matches!(next, Some('}') | Some(']') | None) {
+                    out.push(c);
+                }
+            }
+            '/' if chars.get(i) == Some(&'/') => {
+                while i < chars.len() && chars[i] != '\n' {
+                    i += 1;
+                }
+            }
+            '/' if chars.get(i) == Some(&'*') => {
+                i += 1;
+                while i < chars.len() && !(chars[i] == '*' && chars.get(i + 1) == Some(&'/')) {
+                    i += 1;
+                }
+                i += 2;
+            }
+            _ => out.push(c),
+        }
+    }
+
+    // Close a truncated answer
+    if in_string {
+        out.push('"');
+    }
+    while let Some(closer) = closers.pop() {
+        out.push(closer);
+    }
+    out
+}
+
+// Parse the JSON object or array in an LLM answer, repairing common errors
+pub fn parse_llm_json_value(answer: &str) -> Result<Value, String> {
+    let text = strip_fences(answer);
+    let mut starts: Vec<usize> = ['{', '['].iter().filter_map(|c| text.find(*c)).collect();
+    starts.sort();
+
+    let mut error = "The answer contains no JSON".to_string();
+    for start in starts {
+        match serde_json::from_str(&repair_json(&text[start..])) {
+            Ok(value) => return Ok(value),
+            Err(e) => error = format!("Invalid JSON: {}", e),
+        }
+    }
+    Err(error)
+}
+
+// Parse an LLM answer into `T`, repairing common JSON errors first
+pub fn parse_llm_json<T: DeserializeOwned>(answer: &str) -> Result<T, String> {
+    let value = parse_llm_json_value(answer)?;
+    serde_json::from_value(value).map_err(|e| format!("The JSON doesn't match the expected schema: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_llm_json_value() {
+        let answer = "Here are the tasks:\n```json\n{\n  \"tasks\": [\n    {\"name\": \"a\", \"status\": \"[TODO]\", },\n  ],\n}\n```\nLet me know!";
+        assert_eq!(parse_llm_json_value(answer).unwrap(), json!({"tasks": [{"name": "a", "status": "[TODO]"}]}));
+
+        // Comments, raw newlines in strings and a truncated answer
+        let answer = "[{\"a\": \"line one\nline two\" // note\n}, {\"b\": [1, 2,";
+        assert_eq!(parse_llm_json_value(answer).unwrap(), json!([{"a": "line one\nline two"}, {"b": [1, 2]}]));
+
+        // Brackets and commas inside strings are left alone
+        assert_eq!(parse_llm_json_value(r#"{"a": "x, ]"} trailing {"#).unwrap(), json!({"a": "x, ]"}));
+        assert!(parse_llm_json_value("No JSON here").is_err());
+    }
+
+    #[test]
+    fn test_parse_llm_json_validates_schema() {
+        #[derive(serde::Deserialize)]
+        struct Answer {
+            #[allow(dead_code)]
+            count: u32,
+        }
+        assert!(parse_llm_json::<Answer>(r#"{"count": 3,}"#).is_ok());
+        assert!(parse_llm_json::<Answer>(r#"{"count": "three"}"#).unwrap_err().contains("expected schema"));
+    }
+}
@@ -1,10 +1,35 @@
 // This file exposes the modules as public modules in the crate
 
 pub mod models;
+pub mod migrations;
 pub mod block_config;
+pub mod config_commit;
 pub mod block_handlers;
+pub mod aws_sigv4;
+pub mod json_repair;
+pub mod llm_audit;
 pub mod llm_handler;
+pub mod llm_usage;
+pub mod metrics;
+pub mod process_tracker;
 pub mod project_config;
+pub mod prompt_template;
+pub mod prompt_history;
+pub mod request_user;
+pub mod secrets;
+pub mod shutdown;
 pub mod task_executor;
+pub mod webhooks;
+pub mod slack;
 pub mod task_queue;
-pub mod log_stream;
\ No newline at end of file
+pub mod task_csv;
+pub mod execution_timeline;
+pub mod preflight;
+pub mod pull_request;
+pub mod worktree;
+pub mod git_stash;
+pub mod git_util;
+pub mod ci_status;
+pub mod log_stream;
+pub mod log_store;
+pub mod session_transcript;
\ No newline at end of file
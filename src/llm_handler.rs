@@ -1,10 +1,19 @@
-use crate::models::Task;
-use crate::project_config::{ProjectConfigManager, DEFAULT_AUTO_COMPLETE_SYSTEM_PROMPT, DEFAULT_AUTO_COMPLETE_USER_PROMPT, DEFAULT_ENHANCE_DESCRIPTION_SYSTEM_PROMPT, DEFAULT_ENHANCE_DESCRIPTION_USER_PROMPT, DEFAULT_GENERATE_TASKS_SYSTEM_PROMPT, DEFAULT_GENERATE_TASKS_SYSTEM_PROMPT_MCP, DEFAULT_GENERATE_TASKS_USER_PROMPT, DEFAULT_GENERATE_TASKS_USER_PROMPT_MCP, DEFAULT_PROCESS_MARKDOWN_SPEC_SYSTEM_PROMPT, DEFAULT_PROCESS_MARKDOWN_SPEC_SYSTEM_PROMPT_MCP, DEFAULT_PROCESS_MARKDOWN_SPEC_USER_PROMPT, DEFAULT_PROCESS_MARKDOWN_SPEC_USER_PROMPT_MCP, PROJECT_CONFIG_FILE};
-use reqwest::Client;
+use crate::aws_sigv4::{self, AwsCredentials};
+use crate::json_repair;
+use crate::llm_audit::{self, LLMAuditEntry};
+use crate::llm_usage::{self, CostAttribution, TokenUsage};
+use crate::models::{Task, TechHints};
+use crate::process_tracker;
+use crate::prompt_template;
+use crate::secrets;
+use crate::project_config::{LLMAuditConfig, LLMFallback, LLMOperation, ProjectConfig, ProjectConfigManager, TokenBudget, TASK_STATUS_TODO, PROJECT_CONFIG_FILE};
+use futures::StreamExt;
+use reqwest::{Client, RequestBuilder};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use std::env;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
 use tokio::process::Command;
 use std::process::Stdio;
 
@@ -34,6 +43,12 @@ pub struct GeneratedBlock {
     pub description: String,
     pub inputs: Vec<BlockConnection>,
     pub outputs: Vec<BlockConnection>,
+    // Name of the epic (feature area) the block belongs to
+    #[serde(default)]
+    pub epic: Option<String>,
+    // Headings of the specification sections the block was derived from
+    #[serde(default)]
+    pub source_sections: Vec<String>,
 }
 
 // LLM Provider enum
@@ -44,6 +59,20 @@ pub enum LLMProvider {
     OpenRouter,
     Gemini,
     Anthropic,
+    // OpenAI or any OpenAI-compatible chat completions endpoint
+    OpenAI,
+    // Local models served by Ollama
+    Ollama,
+    // AWS Bedrock, authenticated with the AWS credentials of the environment
+    Bedrock,
+}
+
+impl LLMProvider {
+    // Whether the provider is a CLI agent working through Forge's MCP tools rather than answering
+    // with text; such providers get different prompts
+    pub fn uses_mcp(&self) -> bool {
+        matches!(self, LLMProvider::ClaudeCode | LLMProvider::GeminiCode)
+    }
 }
 
 impl Default for LLMProvider {
@@ -64,6 +93,33 @@ const DEFAULT_GEMINI_MODEL: &str = "gemini-2.5-flash-preview-05-20";
 const ANTHROPIC_API_URL: &str = "https://api.anthropic.com/v1/messages";
 const DEFAULT_ANTHROPIC_MODEL: &str = "claude-sonnet-4-20250514";
 
+// OpenAI API configuration; the base URL can point to any OpenAI-compatible endpoint
+const DEFAULT_OPENAI_BASE_URL: &str = "https://api.openai.com/v1";
+const DEFAULT_OPENAI_MODEL: &str = "gpt-4o";
+
+// Ollama configuration, for running fully offline against local models
+const DEFAULT_OLLAMA_HOST: &str = "http://localhost:11434";
+const DEFAULT_OLLAMA_MODEL: &str = "llama3.1";
+
+// AWS Bedrock configuration
+const DEFAULT_BEDROCK_MODEL: &str = "anthropic.claude-3-5-sonnet-20240620-v1:0";
+
+// URL the API of a provider is called at, None for the CLI agents; used to check it's reachable
+pub fn provider_api_url(provider: &LLMProvider, config: &ProjectConfig) -> Option<String> {
+    match provider {
+        LLMProvider::ClaudeCode | LLMProvider::GeminiCode => None,
+        LLMProvider::OpenRouter => Some(OPENROUTER_API_URL.to_string()),
+        LLMProvider::Gemini => Some(GEMINI_API_URL.to_string()),
+        LLMProvider::Anthropic => Some(ANTHROPIC_API_URL.to_string()),
+        LLMProvider::OpenAI => Some(openai_chat_completions_url(config.openai_base_url.as_deref())),
+        LLMProvider::Ollama => Some(ollama_chat_url(config.ollama_host.as_deref())),
+        LLMProvider::Bedrock => config.bedrock_region.clone()
+            .filter(|r| !r.trim().is_empty())
+            .or_else(|| env::var("AWS_REGION").ok())
+            .map(|region| format!("https://bedrock-runtime.{}.amazonaws.com", region.trim())),
+    }
+}
+
 // Function to get the OpenRouter model from the project configuration
 fn get_openrouter_model(openrouter_model: Option<&str>) -> &str {
     openrouter_model.unwrap_or(DEFAULT_OPENROUTER_MODEL)
@@ -79,11 +135,106 @@ fn get_anthropic_model(anthropic_model: Option<&str>) -> &str {
     anthropic_model.unwrap_or(DEFAULT_ANTHROPIC_MODEL)
 }
 
+// Function to get the OpenAI model from the project configuration
+fn get_openai_model(openai_model: Option<&str>) -> &str {
+    openai_model.filter(|m| !m.trim().is_empty()).unwrap_or(DEFAULT_OPENAI_MODEL)
+}
+
+// Chat completions URL of an OpenAI-compatible base URL
+fn openai_chat_completions_url(base_url: Option<&str>) -> String {
+    let base_url = base_url.filter(|u| !u.trim().is_empty()).unwrap_or(DEFAULT_OPENAI_BASE_URL);
+    format!("{}/chat/completions", base_url.trim().trim_end_matches('/'))
+}
+
+// Function to get the Ollama model from the project configuration
+fn get_ollama_model(ollama_model: Option<&str>) -> &str {
+    ollama_model.filter(|m| !m.trim().is_empty()).unwrap_or(DEFAULT_OLLAMA_MODEL)
+}
+
+// Chat URL of an Ollama host
+fn ollama_chat_url(host: Option<&str>) -> String {
+    let host = host.filter(|h| !h.trim().is_empty()).unwrap_or(DEFAULT_OLLAMA_HOST);
+    format!("{}/api/chat", host.trim().trim_end_matches('/'))
+}
+
+// Struct to hold the Ollama LLM response
+#[derive(Debug, Deserialize)]
+struct OllamaResponse {
+    message: OpenRouterMessage,
+    #[serde(default)]
+    prompt_eval_count: u64,
+    #[serde(default)]
+    eval_count: u64,
+}
+
+// Function to get the Bedrock model from the project configuration
+fn get_bedrock_model(bedrock_model: Option<&str>) -> &str {
+    bedrock_model.filter(|m| !m.trim().is_empty()).unwrap_or(DEFAULT_BEDROCK_MODEL)
+}
+
+// Struct to hold the Bedrock Converse API response
+#[derive(Debug, Deserialize)]
+struct BedrockResponse {
+    output: BedrockOutput,
+    #[serde(default)]
+    usage: Option<BedrockUsage>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BedrockUsage {
+    input_tokens: u64,
+    output_tokens: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct BedrockOutput {
+    message: BedrockMessage,
+}
+
+#[derive(Debug, Deserialize)]
+struct BedrockMessage {
+    content: Vec<BedrockContent>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BedrockContent {
+    #[serde(default)]
+    text: String,
+}
+
+// Text of one line of a streamed response: OpenAI-style and Anthropic server-sent events, or
+// Ollama's newline-delimited JSON
+fn stream_delta(line: &str) -> Option<String> {
+    let line = line.trim();
+    let data = line.strip_prefix("data:").map(|d| d.trim()).unwrap_or(line);
+    if data.is_empty() || data == "[DONE]" {
+        return None;
+    }
+    let event: serde_json::Value = serde_json::from_str(data).ok()?;
+
+    let text = event["choices"][0]["delta"]["content"].as_str()
+        .or_else(|| match event["type"].as_str() {
+            Some("content_block_delta") => event["delta"]["text"].as_str(),
+            _ => None,
+        })
+        .or_else(|| event["message"]["content"].as_str())?;
+    if text.is_empty() { None } else { Some(text.to_string()) }
+}
+
 // Struct to hold the OpenRouter LLM response
 #[derive(Debug, Deserialize)]
 struct OpenRouterResponse {
     id: String,
     choices: Vec<OpenRouterChoice>,
+    #[serde(default)]
+    usage: Option<OpenRouterUsage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenRouterUsage {
+    prompt_tokens: u64,
+    completion_tokens: u64,
 }
 
 #[derive(Debug, Deserialize)]
@@ -100,6 +251,17 @@ struct OpenRouterMessage {
 #[derive(Debug, Deserialize)]
 struct GeminiResponse {
     candidates: Vec<GeminiCandidate>,
+    #[serde(default, rename = "usageMetadata")]
+    usage_metadata: Option<GeminiUsage>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GeminiUsage {
+    #[serde(default)]
+    prompt_token_count: u64,
+    #[serde(default)]
+    candidates_token_count: u64,
 }
 
 #[derive(Debug, Deserialize)]
@@ -114,6 +276,7 @@ struct GeminiContent {
 
 #[derive(Debug, Deserialize)]
 struct GeminiPart {
+    #[serde(default)]
     text: String,
 }
 
@@ -121,6 +284,14 @@ struct GeminiPart {
 #[derive(Debug, Deserialize)]
 struct AnthropicResponse {
     content: Vec<AnthropicContent>,
+    #[serde(default)]
+    usage: Option<AnthropicUsage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AnthropicUsage {
+    input_tokens: u64,
+    output_tokens: u64,
 }
 
 #[derive(Debug, Deserialize)]
@@ -137,6 +308,25 @@ pub struct LLMProviderImpl {
     openrouter_model: Option<String>,
     gemini_model: Option<String>,
     anthropic_model: Option<String>,
+    openai_model: Option<String>,
+    openai_base_url: Option<String>,
+    openai_api_key: Option<String>,
+    ollama_host: Option<String>,
+    ollama_model: Option<String>,
+    bedrock_region: Option<String>,
+    bedrock_model: Option<String>,
+    token_budget: TokenBudget,
+    // Usage reported by the provider for the last call, if it reports usage
+    last_usage: Mutex<Option<TokenUsage>>,
+    // Block/task the spend of the calls is attributed to
+    attribution: Option<CostAttribution>,
+    // Providers tried in order when this one fails
+    fallbacks: Vec<LLMFallback>,
+    // Model passed to the Claude/Gemini CLI, None for the CLI's default
+    cli_model: Option<String>,
+    // Operation the calls are made for, recorded in the audit log
+    operation: Option<LLMOperation>,
+    audit: LLMAuditConfig,
 }
 
 impl LLMProviderImpl {
@@ -159,6 +349,23 @@ impl LLMProviderImpl {
                     openrouter_model,
                     gemini_model,
                     anthropic_model,
+                    openai_model: config.openai_model,
+                    openai_base_url: config.openai_base_url,
+                    // The key may reference secrets as {{secret:NAME}}
+                    openai_api_key: config.openai_api_key.and_then(|key| secrets::resolve(&key)
+                        .map_err(|e| println!("Failed to resolve the OpenAI API key: {}", e))
+                        .ok()),
+                    ollama_host: config.ollama_host,
+                    ollama_model: config.ollama_model,
+                    bedrock_region: config.bedrock_region,
+                    bedrock_model: config.bedrock_model,
+                    token_budget: config.token_budget.unwrap_or_default(),
+                    last_usage: Mutex::new(None),
+                    attribution: None,
+                    fallbacks: config.llm_fallbacks.unwrap_or_default(),
+                    cli_model: None,
+                    operation: None,
+                    audit: config.llm_audit.unwrap_or_default(),
                 }
 
             },
@@ -171,26 +378,346 @@ impl LLMProviderImpl {
                     client: Client::new(),
                     openrouter_model: None,
                     gemini_model: None,
-                    anthropic_model: None
+                    anthropic_model: None,
+                    openai_model: None,
+                    openai_base_url: None,
+                    openai_api_key: None,
+                    ollama_host: None,
+                    ollama_model: None,
+                    bedrock_region: None,
+                    bedrock_model: None,
+                    token_budget: TokenBudget::default(),
+                    last_usage: Mutex::new(None),
+                    attribution: None,
+                    fallbacks: Vec::new(),
+                    cli_model: None,
+                    operation: None,
+                    audit: LLMAuditConfig::default(),
                 }
             }
         }
 
     }
 
-    pub async fn send_prompt(&self, system_prompt: &str, user_prompt: &str) -> Result<String, String> {
+    // Attribute the spend of the calls to a block (and task)
+    pub fn with_attribution(mut self, attribution: Option<CostAttribution>) -> Self {
+        self.attribution = attribution;
+        self
+    }
+
+    // Make the calls for an operation, using the model configured for it
+    pub fn for_operation(mut self, operation: LLMOperation) -> Self {
+        let model = ProjectConfigManager::get_instance().get_config().ok().and_then(|config| config.llm_model_for(operation));
+        self.operation = Some(operation);
+        self.with_model(model)
+    }
+
+    // Use another model than the one configured for the provider
+    pub fn with_model(mut self, model: Option<String>) -> Self {
+        let model = match model.filter(|m| !m.trim().is_empty()) {
+            Some(model) => model,
+            None => return self,
+        };
         match self.provider_type {
+            LLMProvider::OpenRouter => self.openrouter_model = Some(model),
+            LLMProvider::Gemini => self.gemini_model = Some(model),
+            LLMProvider::Anthropic => self.anthropic_model = Some(model),
+            LLMProvider::OpenAI => self.openai_model = Some(model),
+            LLMProvider::Ollama => self.ollama_model = Some(model),
+            LLMProvider::Bedrock => self.bedrock_model = Some(model),
+            LLMProvider::ClaudeCode | LLMProvider::GeminiCode => self.cli_model = Some(model),
+        }
+        self
+    }
+
+    // Model the calls go to, used to price them
+    fn model_name(&self) -> String {
+        match self.provider_type {
+            LLMProvider::ClaudeCode => self.cli_model.clone().unwrap_or_else(|| "claude-code".to_string()),
+            LLMProvider::GeminiCode => self.cli_model.clone().unwrap_or_else(|| "gemini-code".to_string()),
+            LLMProvider::OpenRouter => get_openrouter_model(self.openrouter_model.as_deref()).to_string(),
+            LLMProvider::Gemini => get_gemini_model(self.gemini_model.as_deref()).to_string(),
+            LLMProvider::Anthropic => get_anthropic_model(self.anthropic_model.as_deref()).to_string(),
+            LLMProvider::OpenAI => get_openai_model(self.openai_model.as_deref()).to_string(),
+            LLMProvider::Ollama => get_ollama_model(self.ollama_model.as_deref()).to_string(),
+            LLMProvider::Bedrock => get_bedrock_model(self.bedrock_model.as_deref()).to_string(),
+        }
+    }
+
+    // Send a prompt whose answer must be JSON, using the provider's JSON mode where it has one
+    pub async fn send_json_prompt(&self, system_prompt: &str, user_prompt: &str) -> Result<String, String> {
+        self.send_metered(system_prompt, user_prompt, true).await
+    }
+
+    // Enforce the token budget before a call
+    fn check_token_budget(&self, system_prompt: &str, user_prompt: &str) -> Result<(), String> {
+        if self.token_budget.max_tokens_per_request.is_none() && self.token_budget.max_tokens_per_day.is_none() {
+            return Ok(());
+        }
+        let estimated = llm_usage::estimate_tokens(system_prompt) + llm_usage::estimate_tokens(user_prompt);
+        llm_usage::check_budget(&self.token_budget, estimated, llm_usage::used_today())
+    }
+
+    // Record the usage of a call: as reported by the provider, otherwise estimated from the texts
+    fn record_token_usage(&self, system_prompt: &str, user_prompt: &str, response: &str) -> TokenUsage {
+        let reported = self.last_usage.lock().ok().and_then(|mut usage| usage.take());
+        let usage = reported.unwrap_or_else(|| TokenUsage {
+            prompt_tokens: llm_usage::estimate_tokens(system_prompt) + llm_usage::estimate_tokens(user_prompt),
+            completion_tokens: llm_usage::estimate_tokens(response),
+        });
+        if let Some(max) = self.token_budget.max_tokens_per_request {
+            if usage.total() > max {
+                println!("LLM call used {} tokens, above the per-request budget of {}", usage.total(), max);
+            }
+        }
+        if let Err(e) = llm_usage::record_call(&format!("{:?}", self.provider_type), &self.model_name(), usage, self.attribution.as_ref()) {
+            println!("Failed to record token usage: {}", e);
+        }
+        usage
+    }
+
+    // Write a call to the audit log
+    fn audit(&self, system_prompt: &str, user_prompt: &str, result: &Result<String, String>, usage: Option<TokenUsage>, started: Instant) {
+        if !self.audit.enabled {
+            return;
+        }
+        let entry = LLMAuditEntry {
+            id: uuid::Uuid::new_v4().to_string(),
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            provider: format!("{:?}", self.provider_type),
+            model: self.model_name(),
+            operation: self.operation.map(|op| op.name().to_string())
+                .or_else(|| self.attribution.as_ref().map(|a| a.operation.clone())),
+            block_id: self.attribution.as_ref().map(|a| a.block_id.clone()),
+            task_id: self.attribution.as_ref().and_then(|a| a.task_id.clone()),
+            system_prompt: system_prompt.to_string(),
+            user_prompt: user_prompt.to_string(),
+            response: result.as_ref().ok().cloned(),
+            error: result.as_ref().err().cloned(),
+            duration_ms: started.elapsed().as_millis() as u64,
+            usage,
+            truncated: false,
+            user: crate::request_user::current_user(),
+        };
+        if let Err(e) = llm_audit::record(entry, &self.audit) {
+            println!("Failed to write the LLM audit log: {}", e);
+        }
+    }
+
+    fn report_usage(&self, prompt_tokens: u64, completion_tokens: u64) {
+        if let Ok(mut last_usage) = self.last_usage.lock() {
+            *last_usage = Some(TokenUsage { prompt_tokens, completion_tokens });
+        }
+    }
+
+    // Send a prompt, going down the fallback chain while providers fail
+    async fn send_metered(&self, system_prompt: &str, user_prompt: &str, json_mode: bool) -> Result<String, String> {
+        self.check_token_budget(system_prompt, user_prompt)?;
+
+        let error = match self.send_checked(system_prompt, user_prompt, json_mode).await {
+            Ok(content) => return Ok(content),
+            Err(e) if self.fallbacks.is_empty() => return Err(e),
+            Err(e) => e,
+        };
+        println!("LLM provider {:?} ({}) failed: {}", self.provider_type, self.model_name(), error);
+        let mut errors = vec![format!("{:?}: {}", self.provider_type, error)];
+
+        for fallback in &self.fallbacks {
+            // MCP providers get other prompts, so they can only stand in for each other
+            if fallback.provider.uses_mcp() != self.provider_type.uses_mcp() {
+                println!("Skipping fallback {:?}: it can't answer prompts meant for {:?}", fallback.provider, self.provider_type);
+                continue;
+            }
+            let mut provider = LLMProviderImpl::new(fallback.provider.clone())
+                .with_model(fallback.model.clone())
+                .with_attribution(self.attribution.clone());
+            provider.operation = self.operation;
+            match provider.send_checked(system_prompt, user_prompt, json_mode).await {
+                Ok(content) => {
+                    println!("LLM request satisfied by fallback provider {:?} ({})", provider.provider_type, provider.model_name());
+                    return Ok(content);
+                }
+                Err(e) => {
+                    println!("Fallback provider {:?} ({}) failed: {}", provider.provider_type, provider.model_name(), e);
+                    errors.push(format!("{:?}: {}", provider.provider_type, e));
+                }
+            }
+        }
+        Err(format!("All LLM providers failed: {}", errors.join("; ")))
+    }
+
+    // One call to the provider; in JSON mode an answer without valid JSON counts as a failure
+    async fn send_checked(&self, system_prompt: &str, user_prompt: &str, json_mode: bool) -> Result<String, String> {
+        if let Ok(mut last_usage) = self.last_usage.lock() {
+            *last_usage = None;
+        }
+
+        let started = Instant::now();
+        let result = match self.provider_type {
             LLMProvider::OpenRouter => self.send_openrouter_prompt(system_prompt, user_prompt).await,
-            LLMProvider::Gemini => self.send_gemini_prompt(system_prompt, user_prompt).await,
+            LLMProvider::Gemini => self.request_gemini(system_prompt, user_prompt, json_mode).await,
             LLMProvider::Anthropic => self.send_anthropic_prompt(system_prompt, user_prompt).await,
+            LLMProvider::OpenAI => self.send_openai_prompt(system_prompt, user_prompt).await,
+            LLMProvider::Ollama => self.send_ollama_prompt(system_prompt, user_prompt).await,
+            LLMProvider::Bedrock => self.send_bedrock_prompt(system_prompt, user_prompt).await,
             LLMProvider::ClaudeCode => self.send_claudecode_prompt(system_prompt, user_prompt).await,
             LLMProvider::GeminiCode => self.send_geminicode_prompt(system_prompt, user_prompt).await,
+        };
+
+        let usage = result.as_ref().ok().map(|content| self.record_token_usage(system_prompt, user_prompt, content));
+        self.audit(system_prompt, user_prompt, &result, usage, started);
+        let content = result?;
+        if json_mode {
+            json_repair::parse_llm_json_value(&content)?;
+        }
+        Ok(content)
+    }
+
+    // Send a prompt whose answer must parse with `parse`; unparsable answers are sent back to the
+    // model with the parse error, up to JSON_REPROMPTS times
+    async fn send_structured_prompt<T>(&self, system_prompt: &str, user_prompt: &str, parse: impl Fn(&str) -> Result<T, String>) -> Result<T, String> {
+        let mut prompt = user_prompt.to_string();
+        let mut attempt = 0;
+        loop {
+            let content = self.send_json_prompt(system_prompt, &prompt).await?;
+            match parse(&content) {
+                Ok(parsed) => return Ok(parsed),
+                Err(e) if attempt < JSON_REPROMPTS => {
+                    attempt += 1;
+                    println!("Failed to parse the LLM answer ({}), re-prompting ({}/{})", e, attempt, JSON_REPROMPTS);
+                    prompt = format!("{}\n\nYour previous answer could not be used: {}\nRespond again with only valid JSON matching the schema.", user_prompt, e);
+                }
+                Err(e) => {
+                    println!("Failed to parse the LLM answer: {}\n{}", e, content);
+                    return Err(e);
+                }
+            }
+        }
+    }
+
+    // Send a prompt, passing the answer to `on_chunk` as it is generated. Providers without a
+    // streaming API deliver the whole answer as one chunk. Returns the complete answer.
+    pub async fn send_prompt_stream(&self, system_prompt: &str, user_prompt: &str, on_chunk: &dyn Fn(&str)) -> Result<String, String> {
+        let messages = json!([
+            {
+                "role": "system",
+                "content": system_prompt
+            },
+            {
+                "role": "user",
+                "content": user_prompt
+            }
+        ]);
+
+        let request = match self.provider_type {
+            LLMProvider::OpenRouter => {
+                let api_key = secrets::credential("OPENROUTER_API_KEY")?;
+                self.client.post(OPENROUTER_API_URL)
+                    .header("Authorization", format!("Bearer {}", api_key))
+                    .json(&json!({
+                        "model": get_openrouter_model(self.openrouter_model.as_deref()),
+                        "messages": messages,
+                        "stream": true
+                    }))
+            },
+            LLMProvider::OpenAI => {
+                let mut request = self.client.post(openai_chat_completions_url(self.openai_base_url.as_deref()))
+                    .json(&json!({
+                        "model": get_openai_model(self.openai_model.as_deref()),
+                        "messages": messages,
+                        "stream": true
+                    }));
+                let api_key = self.openai_api_key.clone().filter(|k| !k.trim().is_empty()).or_else(|| secrets::optional_credential("OPENAI_API_KEY"));
+                if let Some(api_key) = api_key {
+                    request = request.header("Authorization", format!("Bearer {}", api_key));
+                }
+                request
+            },
+            LLMProvider::Anthropic => {
+                let api_key = secrets::credential("ANTHROPIC_API_KEY")?;
+                self.client.post(ANTHROPIC_API_URL)
+                    .header("x-api-key", api_key)
+                    .header("anthropic-version", "2023-06-01")
+                    .json(&json!({
+                        "model": get_anthropic_model(self.anthropic_model.as_deref()),
+                        "system": system_prompt,
+                        "messages": [
+                            {
+                                "role": "user",
+                                "content": user_prompt
+                            }
+                        ],
+                        "max_tokens": 4096,
+                        "stream": true
+                    }))
+            },
+            LLMProvider::Ollama => {
+                self.client.post(ollama_chat_url(self.ollama_host.as_deref()))
+                    .json(&json!({
+                        "model": get_ollama_model(self.ollama_model.as_deref()),
+                        "messages": messages,
+                        "stream": true
+                    }))
+            },
+            _ => {
+                let content = self.send_prompt(system_prompt, user_prompt).await?;
+                on_chunk(&content);
+                return Ok(content);
+            }
+        };
+
+        self.check_token_budget(system_prompt, user_prompt)?;
+        let started = Instant::now();
+        let result = self.read_stream(request, on_chunk).await;
+        let usage = result.as_ref().ok().map(|content| self.record_token_usage(system_prompt, user_prompt, content));
+        self.audit(system_prompt, user_prompt, &result, usage, started);
+        result
+    }
+
+    // Read a streamed (SSE or newline-delimited JSON) response line by line
+    async fn read_stream(&self, request: RequestBuilder, on_chunk: &dyn Fn(&str)) -> Result<String, String> {
+        let response = request.send()
+            .await
+            .map_err(|e| format!("Failed to send streaming request: {}", e))?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(format!("Streaming request failed with {}: {}", status, body));
+        }
+
+        let mut content = String::new();
+        let mut buffer: Vec<u8> = Vec::new();
+        let mut stream = response.bytes_stream();
+        while let Some(bytes) = stream.next().await {
+            let bytes = bytes.map_err(|e| format!("Failed to read streamed response: {}", e))?;
+            buffer.extend_from_slice(&bytes);
+
+            // Only complete lines are parsed; a chunk may end in the middle of a line
+            while let Some(newline) = buffer.iter().position(|b| *b == b'\n') {
+                let line: Vec<u8> = buffer.drain(..=newline).collect();
+                if let Some(delta) = stream_delta(&String::from_utf8_lossy(&line)) {
+                    on_chunk(&delta);
+                    content.push_str(&delta);
+                }
+            }
+        }
+        if let Some(delta) = stream_delta(&String::from_utf8_lossy(&buffer)) {
+            on_chunk(&delta);
+            content.push_str(&delta);
         }
+
+        if content.is_empty() {
+            return Err("No response from LLM".to_string());
+        }
+        Ok(content)
+    }
+
+    pub async fn send_prompt(&self, system_prompt: &str, user_prompt: &str) -> Result<String, String> {
+        self.send_metered(system_prompt, user_prompt, false).await
     }
 
     async fn send_openrouter_prompt(&self, system_prompt: &str, user_prompt: &str) -> Result<String, String> {
-        let api_key = env::var("OPENROUTER_API_KEY")
-            .map_err(|_| "OPENROUTER_API_KEY environment variable not set".to_string())?;
+        let api_key = secrets::credential("OPENROUTER_API_KEY")?;
 
         // Use the provided model or fall back to the default
         let model_to_use = &self.openrouter_model;
@@ -227,6 +754,10 @@ impl LLMProviderImpl {
                 format!("Failed to parse OpenRouter response: {}", e)
             })?;
 
+        if let Some(usage) = &response_body.usage {
+            self.report_usage(usage.prompt_tokens, usage.completion_tokens);
+        }
+
         // Extract the content
         if let Some(choice) = response_body.choices.first() {
             Ok(choice.message.content.clone())
@@ -236,31 +767,223 @@ impl LLMProviderImpl {
         }
     }
 
-    async fn send_gemini_prompt(&self, system_prompt: &str, user_prompt: &str) -> Result<String, String> {
-        let api_key = env::var("GEMINI_API_KEY")
-            .map_err(|_| "GEMINI_API_KEY environment variable not set".to_string())?;
+    async fn send_openai_prompt(&self, system_prompt: &str, user_prompt: &str) -> Result<String, String> {
+        // The key from the project configuration takes precedence over the environment
+        let api_key = match self.openai_api_key.clone().filter(|k| !k.trim().is_empty()) {
+            Some(api_key) => Some(api_key),
+            None => secrets::optional_credential("OPENAI_API_KEY"),
+        };
+        let url = openai_chat_completions_url(self.openai_base_url.as_deref());
+        let model = get_openai_model(self.openai_model.as_deref());
 
-        // Use the provided model or fall back to the default
-        let model_to_use = &self.gemini_model;
+        // Create the request payload
+        let payload = json!({
+            "model": model,
+            "messages": [
+                {
+                    "role": "system",
+                    "content": system_prompt
+                },
+                {
+                    "role": "user",
+                    "content": user_prompt
+                }
+            ]
+        });
 
-        // Combine system and user prompts for Gemini (as it doesn't have separate roles)
-        let combined_prompt = format!("{}\n\n{}", system_prompt, user_prompt);
+        // Local OpenAI-compatible servers may not require a key
+        let mut request = self.client.post(&url)
+            .header("Content-Type", "application/json")
+            .json(&payload);
+        if let Some(api_key) = api_key {
+            request = request.header("Authorization", format!("Bearer {}", api_key));
+        }
 
-        // Create the request payload
+        let response = request.send()
+            .await
+            .map_err(|e| format!("Failed to send request to OpenAI ({}): {}", url, e))?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(format!("OpenAI request failed with {}: {}", status, body));
+        }
+
+        // The response has the same shape as OpenRouter's
+        let response_body = response.json::<OpenRouterResponse>()
+            .await
+            .map_err(|e| {
+                println!("Failed to parse OpenAI response: {}", e);
+                format!("Failed to parse OpenAI response: {}", e)
+            })?;
+
+        if let Some(usage) = &response_body.usage {
+            self.report_usage(usage.prompt_tokens, usage.completion_tokens);
+        }
+        if let Some(choice) = response_body.choices.first() {
+            Ok(choice.message.content.clone())
+        } else {
+            println!("No response from OpenAI");
+            Err("No response from OpenAI".to_string())
+        }
+    }
+
+    async fn send_ollama_prompt(&self, system_prompt: &str, user_prompt: &str) -> Result<String, String> {
+        let url = ollama_chat_url(self.ollama_host.as_deref());
+        let model = get_ollama_model(self.ollama_model.as_deref());
+
+        // Create the request payload, asking for the whole response at once
         let payload = json!({
+            "model": model,
+            "messages": [
+                {
+                    "role": "system",
+                    "content": system_prompt
+                },
+                {
+                    "role": "user",
+                    "content": user_prompt
+                }
+            ],
+            "stream": false
+        });
+
+        let response = self.client.post(&url)
+            .header("Content-Type", "application/json")
+            .json(&payload)
+            .send()
+            .await
+            .map_err(|e| format!("Failed to send request to Ollama ({}), is it running? {}", url, e))?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(format!("Ollama request failed with {}: {}", status, body));
+        }
+
+        let response_body = response.json::<OllamaResponse>()
+            .await
+            .map_err(|e| {
+                println!("Failed to parse Ollama response: {}", e);
+                format!("Failed to parse Ollama response: {}", e)
+            })?;
+
+        if response_body.prompt_eval_count + response_body.eval_count > 0 {
+            self.report_usage(response_body.prompt_eval_count, response_body.eval_count);
+        }
+        if response_body.message.content.is_empty() {
+            println!("No response from Ollama");
+            return Err("No response from Ollama".to_string());
+        }
+        Ok(response_body.message.content)
+    }
+
+    async fn send_bedrock_prompt(&self, system_prompt: &str, user_prompt: &str) -> Result<String, String> {
+        let credentials = AwsCredentials::from_env()?;
+        let region = match self.bedrock_region.clone().filter(|r| !r.trim().is_empty()) {
+            Some(region) => region,
+            None => env::var("AWS_REGION")
+                .or_else(|_| env::var("AWS_DEFAULT_REGION"))
+                .map_err(|_| "Bedrock region not configured and AWS_REGION not set".to_string())?,
+        };
+        let model = get_bedrock_model(self.bedrock_model.as_deref());
+
+        // Create the Converse API request payload
+        let payload = json!({
+            "system": [
+                {
+                    "text": system_prompt
+                }
+            ],
+            "messages": [
+                {
+                    "role": "user",
+                    "content": [
+                        {
+                            "text": user_prompt
+                        }
+                    ]
+                }
+            ],
+            "inferenceConfig": {
+                "maxTokens": 4096
+            }
+        });
+        let body = serde_json::to_vec(&payload).map_err(|e| format!("Failed to serialize Bedrock request: {}", e))?;
+
+        // Sign the request with SigV4
+        let host = format!("bedrock-runtime.{}.amazonaws.com", region.trim());
+        let path = format!("/model/{}/converse", aws_sigv4::uri_encode(model));
+        let signed_headers = aws_sigv4::sign_request("POST", &host, &path,
+            &[("content-type", "application/json")], &body, region.trim(), "bedrock", &credentials, chrono::Utc::now());
+
+        let mut request = self.client.post(format!("https://{}{}", host, path))
+            .header("Content-Type", "application/json")
+            .body(body);
+        for (name, value) in signed_headers {
+            request = request.header(name, value);
+        }
+
+        let response = request.send()
+            .await
+            .map_err(|e| {
+                println!("Failed to send request to Bedrock: {}", e);
+                format!("Failed to send request to Bedrock: {}", e)
+            })?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(format!("Bedrock request failed with {}: {}", status, body));
+        }
+
+        let response_body = response.json::<BedrockResponse>()
+            .await
+            .map_err(|e| {
+                println!("Failed to parse Bedrock response: {}", e);
+                format!("Failed to parse Bedrock response: {}", e)
+            })?;
+
+        if let Some(usage) = &response_body.usage {
+            self.report_usage(usage.input_tokens, usage.output_tokens);
+        }
+        let text: String = response_body.output.message.content.iter().map(|c| c.text.as_str()).collect();
+        if text.is_empty() {
+            println!("No response from Bedrock");
+            return Err("No response from Bedrock".to_string());
+        }
+        Ok(text)
+    }
+
+    async fn request_gemini(&self, system_prompt: &str, user_prompt: &str, json_mode: bool) -> Result<String, String> {
+        let api_key = secrets::credential("GEMINI_API_KEY")?;
+
+        // Use the provided model or fall back to the default
+        let model_to_use = get_gemini_model(self.gemini_model.as_deref());
+
+        // Create the request payload, constraining the output to JSON for structured prompts
+        let mut payload = json!({
+            "systemInstruction": {
+                "parts": [
+                    {
+                        "text": system_prompt
+                    }
+                ]
+            },
             "contents": [
                 {
+                    "role": "user",
                     "parts": [
                         {
-                            "text": combined_prompt
+                            "text": user_prompt
                         }
                     ]
                 }
             ]
         });
+        if json_mode {
+            payload["generationConfig"] = json!({ "responseMimeType": "application/json" });
+        }
 
         // Send the request to Gemini
-        let url = format!("{}/models/{}:generateContent?key={}", GEMINI_API_URL, model_to_use.clone().unwrap(), api_key);
+        let url = format!("{}/models/{}:generateContent?key={}", GEMINI_API_URL, model_to_use, api_key);
         let response = self.client.post(url)
             .header("Content-Type", "application/json")
             .json(&payload)
@@ -280,10 +1003,15 @@ impl LLMProviderImpl {
                 format!("Failed to parse Gemini response body: {}", e)
             })?;
 
-        // Extract the content
+        if let Some(usage) = &response_body.usage_metadata {
+            self.report_usage(usage.prompt_token_count, usage.candidates_token_count);
+        }
+
+        // Extract the content; long (e.g. JSON) answers may be split across parts
         if let Some(candidate) = response_body.candidates.first() {
-            if let Some(part) = candidate.content.parts.first() {
-                return Ok(part.text.clone());
+            let text: String = candidate.content.parts.iter().map(|p| p.text.as_str()).collect();
+            if !text.is_empty() {
+                return Ok(text);
             }
         }
         println!("No response from Gemini");
@@ -291,8 +1019,7 @@ impl LLMProviderImpl {
     }
 
     async fn send_anthropic_prompt(&self, system_prompt: &str, user_prompt: &str) -> Result<String, String> {
-        let api_key = env::var("ANTHROPIC_API_KEY")
-            .map_err(|_| "ANTHROPIC_API_KEY environment variable not set".to_string())?;
+        let api_key = secrets::credential("ANTHROPIC_API_KEY")?;
 
         // Use the provided model or fall back to the default
         let model = get_anthropic_model(self.anthropic_model.as_deref());
@@ -334,6 +1061,10 @@ impl LLMProviderImpl {
                 format!("Failed to parse Anthropic response: {}", e)
             })?;
 
+        if let Some(usage) = &response_body.usage {
+            self.report_usage(usage.input_tokens, usage.output_tokens);
+        }
+
         // Extract the content
         if let Some(content) = response_body.content.first() {
             if content.content_type == "text" {
@@ -354,7 +1085,11 @@ impl LLMProviderImpl {
             .arg("--print")
             .arg("--dangerously-skip-permissions")
             .arg("--output-format")
-            .arg("json")
+            .arg("json");
+        if let Some(model) = &self.cli_model {
+            command.arg("--model").arg(model);
+        }
+        command
             .arg(combined_prompt)
             //.stdin(Stdio::piped())
             .stdout(Stdio::piped())
@@ -364,6 +1099,9 @@ impl LLMProviderImpl {
         let mut child = command.spawn()
             .map_err(|e| format!("Failed to execute claude command: {}", e))?;
 
+        // Track the process so it can be reaped if Forge dies while waiting for it
+        let _process_guard = child.id().map(|pid| process_tracker::register(pid, "llm_prompt", "claude"));
+
         // // Write the prompt to stdin
         // if let Some(stdin) = child.stdin.take() {
         //     use tokio::io::AsyncWriteExt;
@@ -397,6 +1135,9 @@ impl LLMProviderImpl {
 
         // Execute the claude command with the specified arguments
         let mut command = Command::new("gemini");
+        if let Some(model) = &self.cli_model {
+            command.arg("--model").arg(model);
+        }
         command
             .arg("-p")
             .arg("--dangerously-skip-permissions")
@@ -409,6 +1150,9 @@ impl LLMProviderImpl {
         let mut child = command.spawn()
             .map_err(|e| format!("Failed to execute claude command: {}", e))?;
 
+        // Track the process so it can be reaped if Forge dies while waiting for it
+        let _process_guard = child.id().map(|pid| process_tracker::register(pid, "llm_prompt", "gemini"));
+
         // // Write the prompt to stdin
         // if let Some(stdin) = child.stdin.take() {
         //     use tokio::io::AsyncWriteExt;
@@ -438,80 +1182,193 @@ impl LLMProviderImpl {
 }
 
 
+// Render the project and block variables into both prompts, add the glossary and insert the input into the user prompt
+fn render_prompts(config: &ProjectConfig, block_id: Option<&str>, system_prompt: &str, user_prompt_template: &str, input: &str) -> (String, String) {
+    let mut vars = prompt_template::project_variables(config);
+    vars.extend(prompt_template::block_variables(&prompt_template::load_project_blocks(config), block_id));
+    let mut system_prompt = prompt_template::render(system_prompt, &vars);
+
+    // Ground the naming of generated content in the project glossary
+    let glossary = config.glossary_prompt_section();
+    if !glossary.is_empty() {
+        system_prompt = format!("{}\n\n{}", system_prompt, glossary);
+    }
+
+    let user_prompt = prompt_template::render_with_input(user_prompt_template, &vars, input);
+    (system_prompt, user_prompt)
+}
+
+// Times an unparsable structured answer is sent back to the model before giving up
+const JSON_REPROMPTS: usize = 2;
+
+// Parse generated tasks, filling in the fields the schema asks for but models often leave out
+fn parse_generated_tasks(content: &str) -> Result<TaskResponse, String> {
+    let mut value = json_repair::parse_llm_json_value(content)?;
+    if let Some(tasks) = value.get_mut("tasks").and_then(|t| t.as_array_mut()) {
+        for task in tasks.iter_mut().filter_map(|t| t.as_object_mut()) {
+            let mut fill = |field: &str, default: serde_json::Value| {
+                if task.get(field).is_none_or(|v| v.is_null()) {
+                    task.insert(field.to_string(), default);
+                }
+            };
+            for field in ["acceptance_criteria", "dependencies", "files_affected", "function_signatures", "testing_requirements"] {
+                fill(field, json!([]));
+            }
+            for field in ["task_name", "log", "commit_id"] {
+                fill(field, json!(""));
+            }
+            fill("status", json!(TASK_STATUS_TODO));
+            fill("task_id", json!(Task::new(String::new()).task_id));
+            if task.get("task_id").and_then(|id| id.as_str()).is_some_and(|id| id.trim().is_empty()) {
+                task.insert("task_id".to_string(), json!(Task::new(String::new()).task_id));
+            }
+        }
+        let total_tasks = tasks.len();
+        if let Some(response) = value.as_object_mut() {
+            response.entry("component_name").or_insert(json!(""));
+            response.insert("total_tasks".to_string(), json!(total_tasks));
+        }
+    }
+    serde_json::from_value(value).map_err(|e| format!("The tasks don't match the expected schema: {}", e))
+}
+
+// Prompts of an operation rendered for a sample input, with the answer when they were sent
+#[derive(Debug, Serialize)]
+pub struct PromptPreview {
+    pub operation: String,
+    pub provider: LLMProvider,
+    pub system_prompt: String,
+    pub user_prompt: String,
+    pub response: Option<String>,
+    // Repaired JSON of the answer of operations that expect structured output
+    pub parsed: Option<serde_json::Value>,
+    pub error: Option<String>,
+}
+
+// Render prompt templates like an operation would and optionally send them. The answer is only
+// returned, never applied; MCP providers aren't run since they create blocks and tasks themselves.
+pub async fn preview_prompt(operation: LLMOperation, system_template: &str, user_template: &str, input: &str, provider_type: LLMProvider, run: bool) -> Result<PromptPreview, String> {
+    let config = ProjectConfigManager::get_instance().load_config().map_err(|e| format!("Failed to load project config: {}", e))?;
+    let (system_prompt, user_prompt) = render_prompts(&config, None, system_template, user_template, input);
+    let mut preview = PromptPreview {
+        operation: operation.name().to_string(),
+        provider: provider_type.clone(),
+        system_prompt,
+        user_prompt,
+        response: None,
+        parsed: None,
+        error: None,
+    };
+    if !run {
+        return Ok(preview);
+    }
+    if provider_type.uses_mcp() {
+        preview.error = Some(format!("{:?} works through the MCP tools and would create blocks and tasks, so it can't be used for a dry run", provider_type));
+        return Ok(preview);
+    }
+
+    let provider = LLMProviderImpl::new(provider_type).for_operation(operation);
+    let structured = matches!(operation, LLMOperation::GenerateTasks | LLMOperation::ProcessSpecification);
+    let result = if structured {
+        provider.send_json_prompt(&preview.system_prompt, &preview.user_prompt).await
+    } else {
+        provider.send_prompt(&preview.system_prompt, &preview.user_prompt).await
+    };
+    match result {
+        Ok(response) => {
+            if structured {
+                match json_repair::parse_llm_json_value(&response) {
+                    Ok(value) => preview.parsed = Some(value),
+                    Err(e) => preview.error = Some(e),
+                }
+            }
+            preview.response = Some(response);
+        }
+        Err(e) => preview.error = Some(e),
+    }
+    Ok(preview)
+}
+
+// Spend attribution of an operation on a block
+fn block_attribution(block_id: Option<&str>, operation: &str) -> Option<CostAttribution> {
+    block_id.map(|block_id| CostAttribution {
+        block_id: block_id.to_string(),
+        task_id: None,
+        operation: operation.to_string(),
+    })
+}
+
 pub async fn auto_complete_description(description: &str, provider_type: Option<LLMProvider>) -> Result<String, String> {
-    let provider = LLMProviderImpl::new(provider_type.unwrap_or_default());
+    let provider = LLMProviderImpl::new(provider_type.unwrap_or_default())
+        .for_operation(LLMOperation::AutoComplete);
 
     // Load project configuration to get custom prompts
     let project_manager = ProjectConfigManager::get_instance();
     let config = project_manager.load_config().map_err(|e| format!("Failed to load project config: {}", e))?;
 
     // Get system prompt from config or use default
-    let system_prompt = config.auto_complete_system_prompt.as_deref().unwrap_or(DEFAULT_AUTO_COMPLETE_SYSTEM_PROMPT);
+    let system_prompt = config.prompt("auto_complete_system_prompt");
 
     // Get user prompt template from config or use default
-    let user_prompt_template = config.auto_complete_user_prompt.as_deref().unwrap_or(DEFAULT_AUTO_COMPLETE_USER_PROMPT);
+    let user_prompt_template = config.prompt("auto_complete_user_prompt");
 
     // Create the user prompt by formatting the template with the description
-    let user_prompt = user_prompt_template.replace("{}", description);
+    let (system_prompt, user_prompt) = render_prompts(&config, None, system_prompt, user_prompt_template, description);
 
     // Send the prompt and return the result
-    match provider.provider_type {
-        LLMProvider::OpenRouter => {
-            provider.send_openrouter_prompt(system_prompt, &user_prompt).await
-        },
-        LLMProvider::Gemini => {
-            provider.send_gemini_prompt(system_prompt, &user_prompt).await
-        },
-        LLMProvider::Anthropic => {
-            provider.send_anthropic_prompt(system_prompt, &user_prompt).await
-        },
-        LLMProvider::ClaudeCode => {
-            provider.send_claudecode_prompt(system_prompt, &user_prompt).await
-        },
-        LLMProvider::GeminiCode => {
-            provider.send_geminicode_prompt(system_prompt, &user_prompt).await
-        },
-    }
+    provider.send_prompt(&system_prompt, &user_prompt).await
 }
 
 // Function to enhance a block description using LLM
-pub async fn enhance_description(description: &str, provider_type: Option<LLMProvider>) -> Result<String, String> {
-    let provider = LLMProviderImpl::new(provider_type.unwrap_or_default());
+pub async fn enhance_description(description: &str, provider_type: Option<LLMProvider>, block_id: Option<&str>) -> Result<String, String> {
+    let provider = LLMProviderImpl::new(provider_type.unwrap_or_default())
+        .for_operation(LLMOperation::EnhanceDescription)
+        .with_attribution(block_attribution(block_id, "enhance_description"));
 
     // Load project configuration to get custom prompts
     let project_manager = ProjectConfigManager::get_instance();
     let config = project_manager.load_config().map_err(|e| format!("Failed to load project config: {}", e))?;
 
     // Get system prompt from config or use default
-    let system_prompt = config.enhance_description_system_prompt.as_deref().unwrap_or(DEFAULT_ENHANCE_DESCRIPTION_SYSTEM_PROMPT);
+    let system_prompt = config.prompt("enhance_description_system_prompt");
 
     // Get user prompt template from config or use default
-    let user_prompt_template = config.enhance_description_user_prompt.as_deref().unwrap_or(DEFAULT_ENHANCE_DESCRIPTION_USER_PROMPT);
+    let user_prompt_template = config.prompt("enhance_description_user_prompt");
 
     // Create the user prompt by formatting the template with the description
-    let user_prompt = user_prompt_template.replace("{}", description);
+    let (system_prompt, user_prompt) = render_prompts(&config, block_id, system_prompt, user_prompt_template, description);
 
 
     // Send the prompt and return the result
-    match provider.provider_type {
-        LLMProvider::OpenRouter => {
-            provider.send_openrouter_prompt(system_prompt, &user_prompt).await
-        },
-        LLMProvider::Gemini => {
-            provider.send_gemini_prompt(system_prompt, &user_prompt).await
-        },
-        LLMProvider::Anthropic => {
-            provider.send_anthropic_prompt(system_prompt, &user_prompt).await
-        },
-        LLMProvider::ClaudeCode => {
-            provider.send_claudecode_prompt(system_prompt, &user_prompt).await
-        },
-        LLMProvider::GeminiCode => {
-            provider.send_geminicode_prompt(system_prompt, &user_prompt).await
-        },
-    }
+    provider.send_prompt(&system_prompt, &user_prompt).await
 }
 
+// Stream an auto-complete suggestion through `on_chunk`, returning the complete suggestion
+pub async fn auto_complete_description_stream(description: &str, provider_type: Option<LLMProvider>, on_chunk: &dyn Fn(&str)) -> Result<String, String> {
+    let provider = LLMProviderImpl::new(provider_type.unwrap_or_default())
+        .for_operation(LLMOperation::AutoComplete);
+    let config = ProjectConfigManager::get_instance().load_config().map_err(|e| format!("Failed to load project config: {}", e))?;
+
+    let system_prompt = config.prompt("auto_complete_system_prompt");
+    let user_prompt_template = config.prompt("auto_complete_user_prompt");
+    let (system_prompt, user_prompt) = render_prompts(&config, None, system_prompt, user_prompt_template, description);
+
+    provider.send_prompt_stream(&system_prompt, &user_prompt, on_chunk).await
+}
+
+// Stream an enhanced block description through `on_chunk`, returning the complete description
+pub async fn enhance_description_stream(description: &str, provider_type: Option<LLMProvider>, block_id: Option<&str>, on_chunk: &dyn Fn(&str)) -> Result<String, String> {
+    let provider = LLMProviderImpl::new(provider_type.unwrap_or_default())
+        .for_operation(LLMOperation::EnhanceDescription)
+        .with_attribution(block_attribution(block_id, "enhance_description"));
+    let config = ProjectConfigManager::get_instance().load_config().map_err(|e| format!("Failed to load project config: {}", e))?;
+
+    let system_prompt = config.prompt("enhance_description_system_prompt");
+    let user_prompt_template = config.prompt("enhance_description_user_prompt");
+    let (system_prompt, user_prompt) = render_prompts(&config, block_id, system_prompt, user_prompt_template, description);
+
+    provider.send_prompt_stream(&system_prompt, &user_prompt, on_chunk).await
+}
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct TaskResponse {
@@ -521,8 +1378,17 @@ pub struct TaskResponse {
 }
 
 // Function to get the full task response from LLM
-pub async fn generate_tasks_response(description: &str, llm_provider: &Option<LLMProvider>) -> Result<TaskResponse, String> {
-    let llm_provider = LLMProviderImpl::new(llm_provider.clone().unwrap_or_default());
+pub async fn generate_tasks_response(description: &str, tech_hints: Option<&TechHints>, llm_provider: &Option<LLMProvider>, block_id: Option<&str>) -> Result<TaskResponse, String> {
+    let llm_provider = LLMProviderImpl::new(llm_provider.clone().unwrap_or_default())
+        .for_operation(LLMOperation::GenerateTasks)
+        .with_attribution(block_attribution(block_id, "generate_tasks"));
+
+    // Specialize the generated tasks for the block's language/framework
+    let description = match tech_hints.map(|h| h.to_prompt_section()).filter(|s| !s.is_empty()) {
+        Some(section) => format!("{}\n\n{}", description, section.trim_end()),
+        None => description.to_string(),
+    };
+    let description = description.as_str();
 
     // Load project configuration to get custom prompts
     let project_manager = ProjectConfigManager::get_instance();
@@ -530,15 +1396,15 @@ pub async fn generate_tasks_response(description: &str, llm_provider: &Option<LL
 
     match llm_provider.provider_type {
         LLMProvider::ClaudeCode | LLMProvider::GeminiCode => {
-            let system_prompt = config.generate_tasks_system_prompt_mcp.as_deref().unwrap_or(DEFAULT_GENERATE_TASKS_SYSTEM_PROMPT_MCP);
+            let system_prompt = config.prompt("generate_tasks_system_prompt_mcp");
 
             // Get user prompt template from config or use default
-            let user_prompt_template = config.generate_tasks_user_prompt_mcp.as_deref().unwrap_or(DEFAULT_GENERATE_TASKS_USER_PROMPT_MCP);
+            let user_prompt_template = config.prompt("generate_tasks_user_prompt_mcp");
 
             // Create the user prompt by formatting the template with the description
-            let user_prompt = user_prompt_template.replace("{}", description);
+            let (system_prompt, user_prompt) = render_prompts(&config, block_id, system_prompt, user_prompt_template, description);
 
-            let content = llm_provider.send_prompt(system_prompt, &user_prompt).await?;
+            let content = llm_provider.send_prompt(&system_prompt, &user_prompt).await?;
 
             println!("ClaudeCode/GeminiCode response: {}", content);
             println!("Tasks have been created directly via MCP tools");
@@ -551,35 +1417,24 @@ pub async fn generate_tasks_response(description: &str, llm_provider: &Option<LL
         },
         _ => {
             // Get system prompt from config or use default
-            let system_prompt = config.generate_tasks_system_prompt.as_deref().unwrap_or(DEFAULT_GENERATE_TASKS_SYSTEM_PROMPT);
+            let system_prompt = config.prompt("generate_tasks_system_prompt");
 
             // Get user prompt template from config or use default
-            let user_prompt_template = config.generate_tasks_user_prompt.as_deref().unwrap_or(DEFAULT_GENERATE_TASKS_USER_PROMPT);
+            let user_prompt_template = config.prompt("generate_tasks_user_prompt");
 
             // Create the user prompt by formatting the template with the description
-            let user_prompt = user_prompt_template.replace("{}", description);
-
-            let content = llm_provider.send_prompt(system_prompt, &user_prompt).await?;
-
+            let (system_prompt, user_prompt) = render_prompts(&config, block_id, system_prompt, user_prompt_template, description);
 
-            println!("{}",content);
-
-            // Extract the JSON part from the response
-            let json_start = content.find('{').unwrap_or(0);
-            let json_end = content.rfind('}').map(|i| i + 1).unwrap_or(content.len());
-            let json_str = &content[json_start..json_end];
-
-            // Parse the content as JSON
-            serde_json::from_str::<TaskResponse>(&json_str)
-                .map_err(|e| format!("Failed to parse JSON response: {}", e))
+            // Repair and validate the answer, re-prompting when it can't be parsed
+            llm_provider.send_structured_prompt(&system_prompt, &user_prompt, parse_generated_tasks).await
         }
     }
 }
 
 // Function to generate tasks for a block based on its description
-pub async fn generate_tasks(description: &str, llm_provider: Option<LLMProvider>) -> Result<Vec<Task>, String> {
+pub async fn generate_tasks(description: &str, tech_hints: Option<&TechHints>, llm_provider: Option<LLMProvider>, block_id: Option<&str>) -> Result<Vec<Task>, String> {
     // Try to get the structured task response
-    match generate_tasks_response(description, &llm_provider).await {
+    match generate_tasks_response(description, tech_hints, &llm_provider, block_id).await {
         Ok(task_response) => {
             // Extract task names from the structured response
             // let tasks: Vec<String> = task_response.tasks
@@ -589,11 +1444,9 @@ pub async fn generate_tasks(description: &str, llm_provider: Option<LLMProvider>
 
             Ok(task_response.tasks)
         },
-        Err(json_err) => {
-            println!("Failed to parse JSON response: {}", json_err);
-            println!("Falling back to text parsing");
-            let tasks: Vec<Task> = Vec::default();
-            Ok(tasks)
+        Err(e) => {
+            println!("Failed to generate tasks: {}", e);
+            Err(e)
         }
     }
 }
@@ -603,7 +1456,8 @@ pub async fn generate_tasks(description: &str, llm_provider: Option<LLMProvider>
 // Function to process a specification and generate blocks
 pub async fn process_specification(markdown_content: &str, llm_provider: Option<LLMProvider>) -> Result<Vec<GeneratedBlock>, String> {
 
-    let llm_provider = LLMProviderImpl::new(llm_provider.unwrap_or_default());
+    let llm_provider = LLMProviderImpl::new(llm_provider.unwrap_or_default())
+        .for_operation(LLMOperation::ProcessSpecification);
 
     // Load project configuration to get custom prompts
     let project_manager = ProjectConfigManager::get_instance();
@@ -612,16 +1466,16 @@ pub async fn process_specification(markdown_content: &str, llm_provider: Option<
     // For ClaudeCode/GeminiCode, use MCP prompts that create blocks/tasks directly
     match llm_provider.provider_type {
         LLMProvider::ClaudeCode | LLMProvider::GeminiCode => {
-            let system_prompt = config.process_specification_system_prompt_mcp.as_deref().unwrap_or(DEFAULT_PROCESS_MARKDOWN_SPEC_SYSTEM_PROMPT_MCP);
+            let system_prompt = config.prompt("process_specification_system_prompt_mcp");
 
             // Get MCP user prompt template from config or use default
-            let user_prompt_template = config.process_specification_user_prompt_mcp.as_deref().unwrap_or(DEFAULT_PROCESS_MARKDOWN_SPEC_USER_PROMPT_MCP);
+            let user_prompt_template = config.prompt("process_specification_user_prompt_mcp");
 
             // Create the user prompt by formatting the template with the markdown content
-            let user_prompt = user_prompt_template.replace("{}", markdown_content);
+            let (system_prompt, user_prompt) = render_prompts(&config, None, system_prompt, user_prompt_template, markdown_content);
 
             // Send the prompt and get the response
-            let content = llm_provider.send_prompt(system_prompt, &user_prompt).await?;
+            let content = llm_provider.send_prompt(&system_prompt, &user_prompt).await?;
 
             // For ClaudeCode, the MCP tools create blocks/tasks directly
             // We return an empty list since actual blocks/tasks are created via MCP tools
@@ -633,31 +1487,76 @@ pub async fn process_specification(markdown_content: &str, llm_provider: Option<
         _ => {
             // For other providers, use the original JSON-based approach
             // Get system prompt from config or use default (original prompts)
-            let system_prompt = config.process_specification_system_prompt.as_deref().unwrap_or(DEFAULT_PROCESS_MARKDOWN_SPEC_SYSTEM_PROMPT);
+            let system_prompt = config.prompt("process_specification_system_prompt");
 
             // Get user prompt template from config or use default (original prompts)
-            let user_prompt_template = config.process_specification_user_prompt.as_deref().unwrap_or(DEFAULT_PROCESS_MARKDOWN_SPEC_USER_PROMPT);
+            let user_prompt_template = config.prompt("process_specification_user_prompt");
 
             // Create the user prompt by formatting the template with the markdown content
-            let user_prompt = user_prompt_template.replace("{}", markdown_content);
-
-            // Send the prompt and get the response
-            let content = llm_provider.send_prompt(system_prompt, &user_prompt).await?;
-
-            // Extract the JSON part from the response
-            let json_start = content.find('[').unwrap_or(0);
-            let json_end = content.rfind(']').map(|i| i + 1).unwrap_or(content.len());
-            let json_str = &content[json_start..json_end];
-
-            println!("{}",json_str);
-            // Parse the JSON into a list of GeneratedBlock objects
-            let blocks: Vec<GeneratedBlock> = serde_json::from_str(json_str).map_err(|e| {
-                println!("Failed to parse generated blocks: {}", e);
-                format!("Failed to parse generated blocks: {}", e)
-            })?;
+            let (system_prompt, user_prompt) = render_prompts(&config, None, system_prompt, user_prompt_template, markdown_content);
 
-            Ok(blocks)
+            // Send the prompt and parse the answer into a list of GeneratedBlock objects, repairing
+            // and re-prompting when it can't be parsed
+            llm_provider.send_structured_prompt(&system_prompt, &user_prompt, json_repair::parse_llm_json::<Vec<GeneratedBlock>>).await
+                .map_err(|e| format!("Failed to parse generated blocks: {}", e))
         }
     }
     
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_openai_defaults() {
+        assert_eq!(openai_chat_completions_url(None), "https://api.openai.com/v1/chat/completions");
+        assert_eq!(openai_chat_completions_url(Some("http://localhost:11434/v1/")), "http://localhost:11434/v1/chat/completions");
+        assert_eq!(get_openai_model(Some(" ")), DEFAULT_OPENAI_MODEL);
+        assert_eq!(get_openai_model(Some("gpt-4.1")), "gpt-4.1");
+    }
+
+    #[test]
+    fn test_parse_generated_tasks() {
+        assert!(LLMProvider::GeminiCode.uses_mcp() && !LLMProvider::Ollama.uses_mcp());
+
+        let answer = r#"```json
+{"component_name": "Parser", "tasks": [{"task_name": "Lexer", "description": "Tokenize input", "dependencies": null, "status": "[TODO]",},]}
+```"#;
+        let response = parse_generated_tasks(answer).unwrap();
+        assert_eq!(response.total_tasks, 1);
+        assert_eq!(response.tasks[0].task_id.len(), 6);
+        assert_eq!(response.tasks[0].status, TASK_STATUS_TODO);
+        assert!(response.tasks[0].dependencies.is_empty());
+        assert!(parse_generated_tasks(r#"{"tasks": [{"task_name": "Lexer"}]}"#).is_err());
+    }
+
+    #[test]
+    fn test_stream_delta() {
+        assert_eq!(stream_delta(r#"data: {"choices":[{"delta":{"content":"Hel"}}]}"#), Some("Hel".to_string()));
+        assert_eq!(stream_delta("data: [DONE]"), None);
+        assert_eq!(stream_delta(r#"data: {"type":"content_block_delta","index":0,"delta":{"type":"text_delta","text":"lo"}}"#), Some("lo".to_string()));
+        assert_eq!(stream_delta(r#"data: {"type":"message_start","message":{"content":[]}}"#), None);
+        assert_eq!(stream_delta(r#"{"message":{"role":"assistant","content":" world"},"done":false}"#), Some(" world".to_string()));
+        assert_eq!(stream_delta("event: ping"), None);
+    }
+
+    #[test]
+    fn test_bedrock_response() {
+        let body = r#"{"output":{"message":{"role":"assistant","content":[{"text":"Hello"},{"text":" world"}]}},"stopReason":"end_turn"}"#;
+        let response: BedrockResponse = serde_json::from_str(body).unwrap();
+        let text: String = response.output.message.content.iter().map(|c| c.text.as_str()).collect();
+        assert_eq!(text, "Hello world");
+        assert_eq!(get_bedrock_model(None), DEFAULT_BEDROCK_MODEL);
+    }
+
+    #[test]
+    fn test_ollama_response() {
+        assert_eq!(ollama_chat_url(None), "http://localhost:11434/api/chat");
+        assert_eq!(ollama_chat_url(Some("http://gpu-box:11434/")), "http://gpu-box:11434/api/chat");
+
+        let body = r#"{"model":"llama3.1","message":{"role":"assistant","content":"Done"},"done":true}"#;
+        let response: OllamaResponse = serde_json::from_str(body).unwrap();
+        assert_eq!(response.message.content, "Done");
+    }
+}
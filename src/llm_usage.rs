@@ -0,0 +1,314 @@
+use crate::block_config::{write_file_atomic, FileLock};
+use crate::metrics;
+use crate::project_config::{ModelPrice, ProjectConfigManager, TokenBudget};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+// Token usage history, stored next to the project config
+pub const LLM_USAGE_FILE: &str = "llm_usage.json";
+
+// LLM spend attributed to blocks and tasks, stored next to the project config
+pub const LLM_COSTS_FILE: &str = "llm_costs.json";
+
+// Number of days kept in the usage history
+const MAX_USAGE_DAYS: usize = 90;
+
+// Built-in prices in USD per million input/output tokens. The first entry contained in the
+// model name wins, so more specific names come first.
+const MODEL_PRICES: &[(&str, f64, f64)] = &[
+    ("claude-opus-4", 15.0, 75.0),
+    ("claude-sonnet-4", 3.0, 15.0),
+    ("claude-3-7-sonnet", 3.0, 15.0),
+    ("claude-3-5-sonnet", 3.0, 15.0),
+    ("claude-3-5-haiku", 0.8, 4.0),
+    // Claude Code CLI, used for task execution and the MCP-based operations
+    ("claude-code", 3.0, 15.0),
+    ("gpt-4o-mini", 0.15, 0.6),
+    ("gpt-4o", 2.5, 10.0),
+    ("gpt-4.1-mini", 0.4, 1.6),
+    ("gpt-4.1", 2.0, 8.0),
+    ("gpt-4-turbo", 10.0, 30.0),
+    ("gemini-2.5-pro", 1.25, 10.0),
+    ("gemini-2.5-flash", 0.3, 2.5),
+    ("mistral-large", 2.0, 6.0),
+];
+
+// Tokens of one or more LLM calls
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct TokenUsage {
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+}
+
+impl TokenUsage {
+    pub fn total(&self) -> u64 {
+        self.prompt_tokens + self.completion_tokens
+    }
+
+    fn add(&mut self, other: TokenUsage) {
+        self.prompt_tokens += other.prompt_tokens;
+        self.completion_tokens += other.completion_tokens;
+    }
+}
+
+// Usage of one day (UTC)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DailyUsage {
+    pub date: String,
+    pub requests: u64,
+    pub usage: TokenUsage,
+    #[serde(default)]
+    pub cost_usd: f64,
+    pub by_provider: HashMap<String, TokenUsage>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UsageReport {
+    pub today: DailyUsage,
+    pub total: TokenUsage,
+    pub total_cost_usd: f64,
+    pub budget: TokenBudget,
+    // Tokens left today, None without a daily budget
+    pub remaining_today: Option<u64>,
+    // Most recent day first
+    pub days: Vec<DailyUsage>,
+}
+
+// Block (and task) an LLM call's spend is attributed to
+#[derive(Debug, Clone)]
+pub struct CostAttribution {
+    pub block_id: String,
+    pub task_id: Option<String>,
+    // e.g. "enhance_description", "generate_tasks", "execute_task"
+    pub operation: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SpendTotals {
+    pub requests: u64,
+    pub usage: TokenUsage,
+    pub cost_usd: f64,
+}
+
+impl SpendTotals {
+    fn add(&mut self, usage: TokenUsage, cost_usd: f64) {
+        self.requests += 1;
+        self.usage.add(usage);
+        self.cost_usd += cost_usd;
+    }
+}
+
+// LLM spend of a block, in total, per operation and per task
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BlockCost {
+    pub block_id: String,
+    pub total: SpendTotals,
+    pub by_operation: HashMap<String, SpendTotals>,
+    pub tasks: HashMap<String, SpendTotals>,
+}
+
+// Price of a model: configured prices (exact name, then contained name) before the built-in table
+pub fn model_price(model: &str, overrides: &HashMap<String, ModelPrice>) -> Option<ModelPrice> {
+    let model = model.to_lowercase();
+    if let Some(price) = overrides.iter().find(|(name, _)| name.to_lowercase() == model).map(|(_, p)| *p) {
+        return Some(price);
+    }
+    if let Some(price) = overrides.iter().find(|(name, _)| model.contains(&name.to_lowercase())).map(|(_, p)| *p) {
+        return Some(price);
+    }
+    MODEL_PRICES.iter()
+        .find(|(name, _, _)| model.contains(name))
+        .map(|(_, input, output)| ModelPrice { input_per_million: *input, output_per_million: *output })
+}
+
+pub fn cost_usd(usage: TokenUsage, price: Option<ModelPrice>) -> f64 {
+    match price {
+        Some(price) => (usage.prompt_tokens as f64 * price.input_per_million
+            + usage.completion_tokens as f64 * price.output_per_million) / 1_000_000.0,
+        None => 0.0,
+    }
+}
+
+// Rough token count of a text, for providers that don't report usage
+pub fn estimate_tokens(text: &str) -> u64 {
+    (text.chars().count() as u64).div_ceil(4)
+}
+
+fn today() -> String {
+    Utc::now().format("%Y-%m-%d").to_string()
+}
+
+// Path of a data file next to the project config
+pub(crate) fn data_file(file_name: &str) -> String {
+    ProjectConfigManager::get_instance().project_data_file(file_name)
+}
+
+fn usage_file() -> String {
+    data_file(LLM_USAGE_FILE)
+}
+
+fn load_days(file: &str) -> Result<Vec<DailyUsage>, String> {
+    if !Path::new(file).exists() {
+        return Ok(Vec::new());
+    }
+    let content = fs::read_to_string(file).map_err(|e| format!("Failed to read usage file: {}", e))?;
+    serde_json::from_str(&content).map_err(|e| format!("Failed to parse usage JSON: {}", e))
+}
+
+// Tokens used today
+pub fn used_today() -> u64 {
+    let today = today();
+    load_days(&usage_file()).unwrap_or_default()
+        .iter()
+        .find(|d| d.date == today)
+        .map(|d| d.usage.total())
+        .unwrap_or(0)
+}
+
+// Reject a call whose estimated prompt exceeds the per-request budget or the tokens left today
+pub fn check_budget(budget: &TokenBudget, estimated_prompt_tokens: u64, used_today: u64) -> Result<(), String> {
+    if let Some(max) = budget.max_tokens_per_request {
+        if estimated_prompt_tokens > max {
+            return Err(format!("Token budget exceeded: the prompt needs about {} tokens, the per-request budget is {}", estimated_prompt_tokens, max));
+        }
+    }
+    if let Some(max) = budget.max_tokens_per_day {
+        if used_today + estimated_prompt_tokens > max {
+            return Err(format!("Daily token budget exceeded: {} of {} tokens used today", used_today, max));
+        }
+    }
+    Ok(())
+}
+
+// Add the usage of an LLM call to the history
+pub fn record_usage(provider: &str, usage: TokenUsage, cost_usd: f64) -> Result<(), String> {
+    let file = usage_file();
+    let _lock = FileLock::acquire(&file)?;
+    let mut days = load_days(&file)?;
+
+    let today = today();
+    if !days.iter().any(|d| d.date == today) {
+        days.push(DailyUsage { date: today.clone(), ..Default::default() });
+    }
+    if let Some(day) = days.iter_mut().find(|d| d.date == today) {
+        day.requests += 1;
+        day.usage.add(usage);
+        day.cost_usd += cost_usd;
+        day.by_provider.entry(provider.to_string()).or_default().add(usage);
+    }
+    if days.len() > MAX_USAGE_DAYS {
+        days.drain(..days.len() - MAX_USAGE_DAYS);
+    }
+
+    let json = serde_json::to_string_pretty(&days).map_err(|e| format!("Failed to serialize usage: {}", e))?;
+    write_file_atomic(&file, json.as_bytes())
+}
+
+// Spend attributed to blocks (missing file means no spend)
+pub fn load_block_costs() -> Result<Vec<BlockCost>, String> {
+    let file = data_file(LLM_COSTS_FILE);
+    if !Path::new(&file).exists() {
+        return Ok(Vec::new());
+    }
+    let content = fs::read_to_string(&file).map_err(|e| format!("Failed to read costs file: {}", e))?;
+    serde_json::from_str(&content).map_err(|e| format!("Failed to parse costs JSON: {}", e))
+}
+
+fn record_cost(attribution: &CostAttribution, usage: TokenUsage, cost_usd: f64) -> Result<(), String> {
+    let file = data_file(LLM_COSTS_FILE);
+    let _lock = FileLock::acquire(&file)?;
+    let mut costs = load_block_costs()?;
+
+    if !costs.iter().any(|c| c.block_id == attribution.block_id) {
+        costs.push(BlockCost { block_id: attribution.block_id.clone(), ..Default::default() });
+    }
+    if let Some(block_cost) = costs.iter_mut().find(|c| c.block_id == attribution.block_id) {
+        block_cost.total.add(usage, cost_usd);
+        block_cost.by_operation.entry(attribution.operation.clone()).or_default().add(usage, cost_usd);
+        if let Some(task_id) = &attribution.task_id {
+            block_cost.tasks.entry(task_id.clone()).or_default().add(usage, cost_usd);
+        }
+    }
+
+    let json = serde_json::to_string_pretty(&costs).map_err(|e| format!("Failed to serialize costs: {}", e))?;
+    write_file_atomic(&file, json.as_bytes())
+}
+
+// Record an LLM call: its usage and cost in the daily history, and its spend on the block/task
+// that triggered it
+pub fn record_call(provider: &str, model: &str, usage: TokenUsage, attribution: Option<&CostAttribution>) -> Result<(), String> {
+    let overrides = ProjectConfigManager::get_instance().get_config()
+        .ok()
+        .and_then(|c| c.model_prices)
+        .unwrap_or_default();
+    let cost = cost_usd(usage, model_price(model, &overrides));
+
+    metrics::increment(metrics::LLM_TOKENS, &[("provider", provider), ("model", model), ("kind", "prompt")], usage.prompt_tokens as f64);
+    metrics::increment(metrics::LLM_TOKENS, &[("provider", provider), ("model", model), ("kind", "completion")], usage.completion_tokens as f64);
+    metrics::increment(metrics::LLM_COST, &[("provider", provider)], cost);
+    record_usage(provider, usage, cost)?;
+    match attribution {
+        Some(attribution) => record_cost(attribution, usage, cost),
+        None => Ok(()),
+    }
+}
+
+// Cumulative usage with today's figures and the remaining daily budget
+pub fn usage_report(budget: TokenBudget) -> Result<UsageReport, String> {
+    let mut days = load_days(&usage_file())?;
+    days.reverse();
+
+    let today = today();
+    let today_usage = days.iter().find(|d| d.date == today).cloned()
+        .unwrap_or(DailyUsage { date: today, ..Default::default() });
+    let mut total = TokenUsage::default();
+    let mut total_cost_usd = 0.0;
+    for day in &days {
+        total.add(day.usage);
+        total_cost_usd += day.cost_usd;
+    }
+
+    Ok(UsageReport {
+        total_cost_usd,
+        remaining_today: budget.max_tokens_per_day.map(|max| max.saturating_sub(today_usage.usage.total())),
+        today: today_usage,
+        total,
+        budget,
+        days,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_budget() {
+        let budget = TokenBudget { max_tokens_per_request: Some(1000), max_tokens_per_day: Some(5000) };
+        assert!(check_budget(&budget, 800, 0).is_ok());
+        assert!(check_budget(&budget, 1200, 0).unwrap_err().contains("per-request"));
+        assert!(check_budget(&budget, 800, 4500).unwrap_err().contains("4500 of 5000"));
+        assert!(check_budget(&TokenBudget::default(), 1_000_000, 1_000_000).is_ok());
+        assert_eq!(estimate_tokens("abcdefghi"), 3);
+    }
+
+    #[test]
+    fn test_model_price() {
+        let mut overrides = HashMap::new();
+        assert_eq!(model_price("gpt-4o-mini-2024-07-18", &overrides).unwrap().input_per_million, 0.15);
+        assert_eq!(model_price("anthropic/claude-sonnet-4-20250514", &overrides).unwrap().output_per_million, 15.0);
+        assert!(model_price("llama3.1", &overrides).is_none());
+
+        overrides.insert("llama3.1".to_string(), ModelPrice { input_per_million: 0.0, output_per_million: 0.0 });
+        overrides.insert("gpt-4o".to_string(), ModelPrice { input_per_million: 2.0, output_per_million: 8.0 });
+        assert_eq!(model_price("gpt-4o", &overrides).unwrap().input_per_million, 2.0);
+        assert!(model_price("llama3.1", &overrides).is_some());
+
+        let usage = TokenUsage { prompt_tokens: 1_000_000, completion_tokens: 100_000 };
+        assert!((cost_usd(usage, model_price("gpt-4o", &HashMap::new())) - 3.5).abs() < 1e-9);
+        assert_eq!(cost_usd(usage, None), 0.0);
+    }
+}
@@ -0,0 +1,261 @@
+use crate::log_stream::{parse_time, LogRecord, LogSearchPage, LogSearchQuery, LogSource, TASK_LOG_DIRECTORY};
+use chrono::{DateTime, SecondsFormat, Utc};
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension};
+use std::fs;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+// SQLite database of the task logs
+pub const LOG_DATABASE: &str = "logs/tasks.db";
+
+// Entries per page of a log search
+const DEFAULT_SEARCH_LIMIT: usize = 100;
+const MAX_SEARCH_LIMIT: usize = 1000;
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS task_logs (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        timestamp TEXT NOT NULL,
+        task_id TEXT NOT NULL,
+        block_id TEXT NOT NULL,
+        source TEXT NOT NULL,
+        level TEXT NOT NULL,
+        message TEXT NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS idx_task_logs_task_id ON task_logs (task_id);
+    CREATE INDEX IF NOT EXISTS idx_task_logs_block_id ON task_logs (block_id);
+    CREATE INDEX IF NOT EXISTS idx_task_logs_timestamp ON task_logs (timestamp);
+    CREATE INDEX IF NOT EXISTS idx_task_logs_level ON task_logs (level);
+";
+
+lazy_static::lazy_static! {
+    static ref LOG_STORE: Mutex<Option<Arc<LogStore>>> = Mutex::new(None);
+}
+
+// Store of the task log records that survives restarts
+pub struct LogStore {
+    conn: Mutex<Connection>,
+}
+
+// Timestamps are stored in UTC with a fixed precision so they sort as text
+fn normalize_time(time: DateTime<Utc>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::Micros, true)
+}
+
+fn normalize_timestamp(timestamp: &str) -> String {
+    DateTime::parse_from_rfc3339(timestamp)
+        .map(|t| normalize_time(t.with_timezone(&Utc)))
+        .unwrap_or_else(|_| timestamp.to_string())
+}
+
+fn source_name(source: LogSource) -> String {
+    serde_json::to_value(source).ok()
+        .and_then(|v| v.as_str().map(String::from))
+        .unwrap_or_default()
+}
+
+fn parse_source(name: &str) -> LogSource {
+    serde_json::from_value(serde_json::Value::String(name.to_string())).unwrap_or_default()
+}
+
+impl LogStore {
+    pub fn open(path: &str) -> Result<Self, String> {
+        if let Some(parent) = Path::new(path).parent() {
+            fs::create_dir_all(parent).map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+        }
+        let conn = Connection::open(path).map_err(|e| format!("Failed to open {}: {}", path, e))?;
+        Self::with_connection(conn)
+    }
+
+    pub fn open_in_memory() -> Result<Self, String> {
+        let conn = Connection::open_in_memory().map_err(|e| format!("Failed to open log database: {}", e))?;
+        Self::with_connection(conn)
+    }
+
+    fn with_connection(conn: Connection) -> Result<Self, String> {
+        conn.execute_batch(SCHEMA).map_err(|e| format!("Failed to create the log tables: {}", e))?;
+        Ok(Self { conn: Mutex::new(conn) })
+    }
+
+    pub fn insert(&self, record: &LogRecord) -> Result<(), String> {
+        let conn = self.conn.lock().unwrap();
+        insert_record(&conn, record)
+    }
+
+    // Import the records of the daily JSON lines files task logs were written to before
+    pub fn import_log_files(&self, directory: &str) -> Result<usize, String> {
+        let Ok(entries) = fs::read_dir(directory) else {
+            return Ok(0);
+        };
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction().map_err(|e| e.to_string())?;
+        let mut count = 0;
+        for path in entries.filter_map(|e| e.ok()).map(|e| e.path()).filter(|p| p.extension().is_some_and(|ext| ext == "jsonl")) {
+            let content = fs::read_to_string(&path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+            for record in content.lines().filter_map(|line| serde_json::from_str::<LogRecord>(line).ok()) {
+                insert_record(&tx, &record)?;
+                count += 1;
+            }
+        }
+        tx.commit().map_err(|e| e.to_string())?;
+        Ok(count)
+    }
+
+    pub fn is_empty(&self) -> Result<bool, String> {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row("SELECT id FROM task_logs LIMIT 1", [], |row| row.get::<_, i64>(0))
+            .optional()
+            .map(|id| id.is_none())
+            .map_err(|e| e.to_string())
+    }
+
+    // Search the records, most recent first
+    pub fn search(&self, query: &LogSearchQuery) -> Result<LogSearchPage, String> {
+        let non_empty = |value: &Option<String>| value.as_deref().filter(|v| !v.is_empty()).map(String::from);
+        let since = non_empty(&query.since).map(|s| parse_time(&s)).transpose()?;
+        let until = non_empty(&query.until).map(|u| parse_time(&u)).transpose()?;
+        let offset = query.offset.unwrap_or(0);
+        let limit = query.limit.unwrap_or(DEFAULT_SEARCH_LIMIT).clamp(1, MAX_SEARCH_LIMIT);
+
+        let mut conditions = Vec::new();
+        let mut values = Vec::new();
+        let mut filter = |condition: &str, value: Option<String>| {
+            if let Some(value) = value {
+                conditions.push(condition.to_string());
+                values.push(value);
+            }
+        };
+        filter("task_id = ?", non_empty(&query.task_id));
+        filter("block_id = ?", non_empty(&query.block_id));
+        filter("level = ?", non_empty(&query.level).map(|l| l.to_lowercase()));
+        filter("source = ?", query.source.map(source_name));
+        filter("instr(lower(message), ?) > 0", non_empty(&query.q).map(|q| q.to_lowercase()));
+        filter("timestamp >= ?", since.map(normalize_time));
+        filter("timestamp <= ?", until.map(normalize_time));
+        let where_clause = if conditions.is_empty() {
+            String::new()
+        } else {
+            format!("WHERE {}", conditions.join(" AND "))
+        };
+
+        let conn = self.conn.lock().unwrap();
+        let total: i64 = conn.query_row(&format!("SELECT COUNT(*) FROM task_logs {}", where_clause), params_from_iter(values.iter()), |row| row.get(0))
+            .map_err(|e| format!("Failed to search logs: {}", e))?;
+
+        let sql = format!(
+            "SELECT timestamp, task_id, source, level, message FROM task_logs {} ORDER BY timestamp DESC, id DESC LIMIT {} OFFSET {}",
+            where_clause, limit, offset
+        );
+        let mut statement = conn.prepare(&sql).map_err(|e| format!("Failed to search logs: {}", e))?;
+        let entries = statement.query_map(params_from_iter(values.iter()), |row| {
+            Ok(LogRecord {
+                timestamp: row.get(0)?,
+                task_id: row.get(1)?,
+                source: parse_source(&row.get::<_, String>(2)?),
+                level: row.get(3)?,
+                message: row.get(4)?,
+            })
+        })
+            .and_then(|rows| rows.collect::<Result<Vec<_>, _>>())
+            .map_err(|e| format!("Failed to search logs: {}", e))?;
+
+        Ok(LogSearchPage { total: total as usize, offset, limit, entries })
+    }
+
+    // Delete the records older than the given time, returning how many were removed
+    pub fn delete_before(&self, cutoff: DateTime<Utc>) -> Result<usize, String> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute("DELETE FROM task_logs WHERE timestamp < ?1", params![normalize_time(cutoff)])
+            .map_err(|e| format!("Failed to prune task logs: {}", e))
+    }
+}
+
+fn insert_record(conn: &Connection, record: &LogRecord) -> Result<(), String> {
+    let block_id = record.task_id.split_once(':').map(|(block_id, _)| block_id).unwrap_or("");
+    conn.execute(
+        "INSERT INTO task_logs (timestamp, task_id, block_id, source, level, message) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![normalize_timestamp(&record.timestamp), record.task_id, block_id, source_name(record.source), record.level, record.message],
+    )
+        .map(|_| ())
+        .map_err(|e| format!("Failed to store task log: {}", e))
+}
+
+// The log store of the server, opened on first use; task logs written to files by earlier versions
+// are imported into a new database
+pub fn get_log_store() -> Result<Arc<LogStore>, String> {
+    let mut store = LOG_STORE.lock().unwrap();
+    if let Some(store) = store.as_ref() {
+        return Ok(store.clone());
+    }
+    let opened = Arc::new(LogStore::open(LOG_DATABASE)?);
+    if opened.is_empty()? {
+        opened.import_log_files(TASK_LOG_DIRECTORY)?;
+    }
+    *store = Some(opened.clone());
+    Ok(opened)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(timestamp: &str, task_id: &str, message: &str) -> LogRecord {
+        LogRecord {
+            timestamp: timestamp.to_string(),
+            ..LogRecord::new(task_id, LogSource::Agent, message.to_string())
+        }
+    }
+
+    #[test]
+    fn test_search() {
+        let store = LogStore::open_in_memory().unwrap();
+        for r in [
+            record("2026-03-01T10:00:00+00:00", "b1:t1", "Starting execution"),
+            record("2026-03-01T10:05:00+00:00", "b1:t1", "Claude Error: build failed"),
+            record("2026-03-08T09:00:00+00:00", "b1:t2", "Build failed again"),
+            record("2026-03-08T11:01:00+02:00", "b2:t1", "Warning: retrying"),
+        ] {
+            store.insert(&r).unwrap();
+        }
+
+        let errors = store.search(&LogSearchQuery { level: Some("error".to_string()), ..Default::default() }).unwrap();
+        assert_eq!(errors.total, 2);
+        assert_eq!(errors.entries[0].message, "Build failed again");
+
+        let page = store.search(&LogSearchQuery {
+            q: Some("FAILED".to_string()),
+            until: Some("2026-03-05".to_string()),
+            ..Default::default()
+        }).unwrap();
+        assert_eq!(page.entries.len(), 1);
+        assert_eq!(page.entries[0].task_id, "b1:t1");
+
+        let page = store.search(&LogSearchQuery { offset: Some(1), limit: Some(2), ..Default::default() }).unwrap();
+        assert_eq!((page.total, page.entries.len()), (4, 2));
+        assert_eq!(page.entries[0].message, "Build failed again");
+
+        assert_eq!(store.search(&LogSearchQuery { block_id: Some("b1".to_string()), ..Default::default() }).unwrap().total, 3);
+        assert!(store.search(&LogSearchQuery { since: Some("last week".to_string()), ..Default::default() }).is_err());
+        assert_eq!(store.search(&LogSearchQuery { source: Some(LogSource::Stderr), ..Default::default() }).unwrap().total, 0);
+
+        let cutoff = DateTime::parse_from_rfc3339("2026-03-05T00:00:00+00:00").unwrap().with_timezone(&Utc);
+        assert_eq!(store.delete_before(cutoff).unwrap(), 2);
+        assert_eq!(store.search(&LogSearchQuery::default()).unwrap().total, 2);
+    }
+
+    #[test]
+    fn test_import_log_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let line = |r: LogRecord| serde_json::to_string(&r).unwrap();
+        fs::write(dir.path().join("2026-03-01.jsonl"), [
+            line(record("2026-03-01T10:00:00+00:00", "b1:t1", "Starting execution")),
+            r#"{"timestamp":"2026-03-01T10:01:00+00:00","task_id":"b1:t1","level":"info","message":"legacy"}"#.to_string(),
+        ].join("\n")).unwrap();
+
+        let store = LogStore::open_in_memory().unwrap();
+        assert!(store.is_empty().unwrap());
+        assert_eq!(store.import_log_files(&dir.path().to_string_lossy()).unwrap(), 2);
+        let page = store.search(&LogSearchQuery { source: Some(LogSource::System), ..Default::default() }).unwrap();
+        assert_eq!(page.entries[0].message, "legacy");
+    }
+}
@@ -1,7 +1,11 @@
+use crate::log_store::{get_log_store, LogStore};
+use crate::secrets;
 use actix_web::http::header::{ContentType, CACHE_CONTROL};
 use actix_web::web::Bytes;
 use actix_web::{web, HttpResponse, Responder};
+use chrono::{DateTime, NaiveDate, Utc};
 use futures::stream::StreamExt;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
@@ -9,11 +13,63 @@ use tokio::sync::mpsc;
 use tokio::time::interval;
 use tokio_stream::wrappers::ReceiverStream;
 
+// Task logs were appended to daily JSON lines files before they were stored in the log database
+pub const TASK_LOG_DIRECTORY: &str = "logs/tasks";
+
+// Where a log line came from
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogSource {
+    // Output of the coding agent
+    Agent,
+    // Hooks and other tools run for the task
+    Tool,
+    Stdout,
+    Stderr,
+    // Messages of Forge itself
+    #[default]
+    System,
+}
+
+// A structured task log line
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogRecord {
+    pub timestamp: String,
+    pub task_id: String,
+    #[serde(default)]
+    pub source: LogSource,
+    pub level: String,
+    pub message: String,
+}
+
+impl LogRecord {
+    pub fn new(task_id: &str, source: LogSource, message: String) -> Self {
+        let level = if source == LogSource::Stderr { "error" } else { log_level(&message) };
+        Self {
+            timestamp: Utc::now().to_rfc3339(),
+            task_id: task_id.to_string(),
+            source,
+            level: level.to_string(),
+            message,
+        }
+    }
+
+    // The line as shown in the text view of the logs
+    pub fn render(&self) -> String {
+        match self.source {
+            LogSource::Stderr => format!("ERROR: {}", self.message),
+            _ => self.message.clone(),
+        }
+    }
+}
+
 // Structure to hold log entries for each task
 #[derive(Debug, Clone)]
 pub struct LogEntry {
     pub timestamp: Instant,
+    // Rendered text of the record
     pub content: String,
+    pub record: LogRecord,
 }
 
 // Global log storage
@@ -28,13 +84,14 @@ impl LogStorage {
         }
     }
 
-    // Add a log entry for a specific task
-    pub fn add_log(&self, task_id: &str, content: String) {
+    // Add a log record for a specific task
+    pub fn add_record(&self, record: LogRecord) {
         let mut logs = self.logs.lock().unwrap();
-        let task_logs = logs.entry(task_id.to_string()).or_insert_with(Vec::new);
+        let task_logs = logs.entry(record.task_id.clone()).or_insert_with(Vec::new);
         task_logs.push(LogEntry {
             timestamp: Instant::now(),
-            content,
+            content: record.render(),
+            record,
         });
     }
 
@@ -54,6 +111,28 @@ impl LogStorage {
         logs.remove(task_id);
     }
 
+    // Records added to any task since the counts were taken, updating the counts; logs cleared
+    // since then are read from the start
+    pub fn get_new_records(&self, counts: &mut HashMap<String, usize>) -> Vec<LogRecord> {
+        let logs = self.logs.lock().unwrap();
+        let mut records = Vec::new();
+        for (task_id, task_logs) in logs.iter() {
+            let seen = counts.entry(task_id.clone()).or_insert(0);
+            if *seen > task_logs.len() {
+                *seen = 0;
+            }
+            records.extend(task_logs[*seen..].iter().map(|entry| entry.record.clone()));
+            *seen = task_logs.len();
+        }
+        records
+    }
+
+    // Number of entries of each task
+    pub fn get_counts(&self) -> HashMap<String, usize> {
+        let logs = self.logs.lock().unwrap();
+        logs.iter().map(|(task_id, task_logs)| (task_id.clone(), task_logs.len())).collect()
+    }
+
     // Get all task IDs with logs
     pub fn get_task_ids(&self) -> Vec<String> {
         let logs = self.logs.lock().unwrap();
@@ -71,9 +150,17 @@ pub fn get_log_storage() -> Arc<LogStorage> {
     LOG_STORAGE.clone()
 }
 
+// View of a log stream: rendered text lines (default) or JSON records
+#[derive(Debug, Default, Deserialize)]
+pub struct LogStreamQuery {
+    #[serde(default)]
+    pub format: Option<String>,
+}
+
 // Handler for streaming logs for a specific task
-pub async fn stream_logs(task_id: web::Path<String>) -> impl Responder {
+pub async fn stream_logs(task_id: web::Path<String>, query: web::Query<LogStreamQuery>) -> impl Responder {
     let task_id = task_id.into_inner();
+    let json = query.format.as_deref() == Some("json");
     let log_storage = get_log_storage();
 
     // Create a channel for sending log updates
@@ -94,7 +181,12 @@ pub async fn stream_logs(task_id: web::Path<String>) -> impl Responder {
             // If there are new logs, send them
             if logs.len() > last_log_count {
                 for i in last_log_count..logs.len() {
-                    if tx.send(format!("data: {}\n\n", logs[i].content)).await.is_err() {
+                    let data = if json {
+                        serde_json::to_string(&logs[i].record).unwrap_or_default()
+                    } else {
+                        logs[i].content.clone()
+                    };
+                    if tx.send(format!("data: {}\n\n", data)).await.is_err() {
                         // Client disconnected
                         return;
                     }
@@ -121,6 +213,56 @@ pub async fn stream_logs(task_id: web::Path<String>) -> impl Responder {
         .streaming(rx_stream.map(|item| Ok::<Bytes, actix_web::Error>(Bytes::from(item))))
 }
 
+// Filter of the live tail of all tasks
+#[derive(Debug, Default, Deserialize)]
+pub struct LogTailQuery {
+    // Only tail the tasks of this block
+    pub block_id: Option<String>,
+}
+
+// Handler streaming the output of every running task as JSON records carrying their task ID.
+// Only lines logged after the client connected are sent.
+pub async fn tail_logs(query: web::Query<LogTailQuery>) -> impl Responder {
+    let block_prefix = query.block_id.as_deref().filter(|b| !b.is_empty()).map(|b| format!("{}:", b));
+    let log_storage = get_log_storage();
+
+    let (tx, rx) = mpsc::channel(100);
+    let rx_stream = ReceiverStream::new(rx);
+
+    tokio::spawn(async move {
+        let mut counts = log_storage.get_counts();
+        let mut interval = interval(Duration::from_millis(500));
+        let mut ticks: u64 = 0;
+
+        loop {
+            interval.tick().await;
+            ticks += 1;
+
+            let mut records = log_storage.get_new_records(&mut counts);
+            records.retain(|record| block_prefix.as_deref().is_none_or(|b| record.task_id.starts_with(b)));
+            records.sort_by(|a, b| a.timestamp.cmp(&b.timestamp));
+            for record in records {
+                let data = serde_json::to_string(&record).unwrap_or_default();
+                if tx.send(format!("data: {}\n\n", data)).await.is_err() {
+                    // Client disconnected
+                    return;
+                }
+            }
+
+            // Send a keep-alive comment every 15 seconds
+            if ticks % 30 == 0 && tx.send(": keep-alive\n\n".to_string()).await.is_err() {
+                return;
+            }
+        }
+    });
+
+    HttpResponse::Ok()
+        .insert_header((CACHE_CONTROL, "no-cache"))
+        .insert_header(("Connection", "keep-alive"))
+        .insert_header(("Content-Type", "text/event-stream"))
+        .streaming(rx_stream.map(|item| Ok::<Bytes, actix_web::Error>(Bytes::from(item))))
+}
+
 // Handler for getting all task IDs with logs
 pub async fn get_task_ids() -> impl Responder {
     let log_storage = get_log_storage();
@@ -131,8 +273,172 @@ pub async fn get_task_ids() -> impl Responder {
 
 // Public function to add a log entry
 pub fn add_log(task_id: &str, content: String) {
+    add_log_from(task_id, LogSource::System, content);
+}
+
+// Add a line of output of the given source
+pub fn add_log_from(task_id: &str, source: LogSource, content: String) {
+    let record = LogRecord::new(task_id, source, secrets::redact(&content));
+    persist_log(&record);
     let log_storage = get_log_storage();
-    log_storage.add_log(task_id, content);
+    log_storage.add_record(record);
+}
+
+// Level of a log line, from the words it contains
+fn log_level(content: &str) -> &'static str {
+    let content = content.to_lowercase();
+    if content.contains("error") || content.contains("failed") || content.contains("panic") {
+        "error"
+    } else if content.contains("warn") {
+        "warning"
+    } else {
+        "info"
+    }
+}
+
+fn persist_log(record: &LogRecord) {
+    if let Err(e) = get_log_store().and_then(|store| store.insert(record)) {
+        eprintln!("Failed to persist task log: {}", e);
+    }
+}
+
+// Filters of a log search; `since` and `until` are RFC 3339 times or dates
+#[derive(Debug, Default, Deserialize)]
+pub struct LogSearchQuery {
+    pub task_id: Option<String>,
+    // Logs of all tasks of a block
+    pub block_id: Option<String>,
+    pub level: Option<String>,
+    pub source: Option<LogSource>,
+    // Case-insensitive text the message contains
+    pub q: Option<String>,
+    pub since: Option<String>,
+    pub until: Option<String>,
+    pub offset: Option<usize>,
+    pub limit: Option<usize>,
+}
+
+// A page of search results, most recent first
+#[derive(Debug, Serialize)]
+pub struct LogSearchPage {
+    pub total: usize,
+    pub offset: usize,
+    pub limit: usize,
+    pub entries: Vec<LogRecord>,
+}
+
+pub fn parse_time(value: &str) -> Result<DateTime<Utc>, String> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Ok(time.with_timezone(&Utc));
+    }
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map(|date| date.and_hms_opt(0, 0, 0).unwrap().and_utc())
+        .map_err(|_| format!("Invalid time '{}': use RFC 3339 or YYYY-MM-DD", value))
+}
+
+// Handler to search the persisted task logs by task, level, text and time range
+pub async fn search_logs_handler(query: web::Query<LogSearchQuery>) -> impl Responder {
+    match web::block(move || get_log_store()?.search(&query)).await {
+        Ok(Ok(page)) => HttpResponse::Ok().json(page),
+        Ok(Err(e)) if e.starts_with("Invalid") => HttpResponse::BadRequest().body(e),
+        Ok(Err(e)) => HttpResponse::InternalServerError().body(e),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Failed to search logs: {}", e)),
+    }
+}
+
+// A line of the merged log of a block: a record, or a marker where the log of another task starts
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum BlockLogItem {
+    Task { task_id: String, timestamp: String },
+    Log(LogRecord),
+}
+
+impl BlockLogItem {
+    pub fn render(&self) -> String {
+        match self {
+            BlockLogItem::Task { task_id, .. } => format!("=== Task {} ===", task_id),
+            BlockLogItem::Log(record) => record.render(),
+        }
+    }
+}
+
+// Filters of the merged log of a block; pages count back from the most recent record
+#[derive(Debug, Default, Deserialize)]
+pub struct BlockLogQuery {
+    pub since: Option<String>,
+    pub until: Option<String>,
+    pub offset: Option<usize>,
+    pub limit: Option<usize>,
+    // text or json (default)
+    pub format: Option<String>,
+}
+
+// A page of the merged log of a block, oldest first
+#[derive(Debug, Serialize)]
+pub struct BlockLogPage {
+    pub block_id: String,
+    pub total: usize,
+    pub offset: usize,
+    pub limit: usize,
+    pub items: Vec<BlockLogItem>,
+}
+
+// Interleave the records of a block's tasks in chronological order, with a marker whenever the
+// task changes
+fn merge_block_records(block_id: &str, mut records: Vec<LogRecord>) -> Vec<BlockLogItem> {
+    let time = |record: &LogRecord| DateTime::parse_from_rfc3339(&record.timestamp).ok().map(|t| t.with_timezone(&Utc));
+    records.sort_by_key(|record| time(record));
+
+    let prefix = format!("{}:", block_id);
+    let mut items = Vec::with_capacity(records.len());
+    let mut current_task: Option<String> = None;
+    for record in records {
+        if current_task.as_deref() != Some(record.task_id.as_str()) {
+            current_task = Some(record.task_id.clone());
+            items.push(BlockLogItem::Task {
+                task_id: record.task_id.strip_prefix(&prefix).unwrap_or(&record.task_id).to_string(),
+                timestamp: record.timestamp.clone(),
+            });
+        }
+        items.push(BlockLogItem::Log(record));
+    }
+    items
+}
+
+// The logs of all tasks of a block, merged into a single stream
+pub fn block_logs(store: &LogStore, block_id: &str, query: &BlockLogQuery) -> Result<BlockLogPage, String> {
+    let page = store.search(&LogSearchQuery {
+        block_id: Some(block_id.to_string()),
+        since: query.since.clone(),
+        until: query.until.clone(),
+        offset: query.offset,
+        limit: query.limit,
+        ..Default::default()
+    })?;
+    Ok(BlockLogPage {
+        block_id: block_id.to_string(),
+        total: page.total,
+        offset: page.offset,
+        limit: page.limit,
+        items: merge_block_records(block_id, page.entries),
+    })
+}
+
+// Handler for the logs of all tasks of a block in one chronological view
+pub async fn block_logs_handler(block_id: web::Path<String>, query: web::Query<BlockLogQuery>) -> impl Responder {
+    let block_id = block_id.into_inner();
+    let text = query.format.as_deref() == Some("text");
+    match web::block(move || block_logs(&get_log_store()?, &block_id, &query)).await {
+        Ok(Ok(page)) if text => {
+            let lines: Vec<String> = page.items.iter().map(|item| item.render()).collect();
+            HttpResponse::Ok().content_type(ContentType::plaintext()).body(lines.join("\n"))
+        }
+        Ok(Ok(page)) => HttpResponse::Ok().json(page),
+        Ok(Err(e)) if e.starts_with("Invalid") => HttpResponse::BadRequest().body(e),
+        Ok(Err(e)) => HttpResponse::InternalServerError().body(e),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Failed to read block logs: {}", e)),
+    }
 }
 
 // Public function to clear logs for a task
@@ -151,4 +457,70 @@ pub fn get_logs_str(task_id: &str) -> String {
     }
     
     log_output
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_log_records() {
+        let stderr = LogRecord::new("b1:t1", LogSource::Stderr, "npm WARN deprecated".to_string());
+        assert_eq!((stderr.level.as_str(), stderr.render()), ("error", "ERROR: npm WARN deprecated".to_string()));
+
+        let agent = LogRecord::new("b1:t1", LogSource::Agent, "Warning: tests are slow".to_string());
+        assert_eq!((agent.level.as_str(), agent.render()), ("warning", "Warning: tests are slow".to_string()));
+
+        // Records persisted before sources were recorded are read as system records
+        let legacy: LogRecord = serde_json::from_str(r#"{"timestamp":"2026-03-01T10:00:00+00:00","task_id":"t","level":"info","message":"m"}"#).unwrap();
+        assert_eq!(legacy.source, LogSource::System);
+    }
+
+    #[test]
+    fn test_new_records() {
+        let storage = LogStorage::new();
+        storage.add_record(LogRecord::new("b1:t1", LogSource::Agent, "before".to_string()));
+        let mut counts = storage.get_counts();
+
+        storage.add_record(LogRecord::new("b1:t1", LogSource::Agent, "first".to_string()));
+        storage.add_record(LogRecord::new("b2:t1", LogSource::Tool, "second".to_string()));
+        let mut messages: Vec<String> = storage.get_new_records(&mut counts).into_iter().map(|r| r.message).collect();
+        messages.sort();
+        assert_eq!(messages, vec!["first", "second"]);
+        assert!(storage.get_new_records(&mut counts).is_empty());
+
+        // A task run again after its logs were cleared is tailed from its first line
+        storage.clear_logs("b1:t1");
+        storage.add_record(LogRecord::new("b1:t1", LogSource::Agent, "rerun".to_string()));
+        let records = storage.get_new_records(&mut counts);
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].message, "rerun");
+    }
+
+    #[test]
+    fn test_block_logs() {
+        let store = LogStore::open_in_memory().unwrap();
+        let record = |timestamp: &str, task_id: &str, message: &str| LogRecord {
+            timestamp: timestamp.to_string(),
+            ..LogRecord::new(task_id, LogSource::Agent, message.to_string())
+        };
+        for r in [
+            record("2026-03-01T10:00:00+00:00", "b1:t1", "Starting t1"),
+            record("2026-03-01T10:00:01.5+00:00", "b1:t1", "Editing"),
+            record("2026-03-01T10:00:02+00:00", "b1:t2", "Starting t2"),
+            record("2026-03-01T10:00:03+00:00", "b10:t1", "Other block"),
+            record("2026-03-01T10:00:04+00:00", "b1:t1", "Done"),
+        ] {
+            store.insert(&r).unwrap();
+        }
+
+        let page = block_logs(&store, "b1", &BlockLogQuery::default()).unwrap();
+        assert_eq!(page.total, 4);
+        let lines: Vec<String> = page.items.iter().map(|item| item.render()).collect();
+        assert_eq!(lines, vec!["=== Task t1 ===", "Starting t1", "Editing", "=== Task t2 ===", "Starting t2", "=== Task t1 ===", "Done"]);
+
+        // The most recent records come first when paging
+        let page = block_logs(&store, "b1", &BlockLogQuery { limit: Some(2), ..Default::default() }).unwrap();
+        let lines: Vec<String> = page.items.iter().map(|item| item.render()).collect();
+        assert_eq!(lines, vec!["=== Task t2 ===", "Starting t2", "=== Task t1 ===", "Done"]);
+    }
+}
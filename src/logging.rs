@@ -0,0 +1,195 @@
+use crate::log_store::{get_log_store, LOG_DATABASE};
+use crate::project_config::{LogRetention, ProjectConfigManager};
+use crate::secrets;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, SystemTime};
+use tracing::{error, info};
+use tracing_appender::rolling::{self, RollingFileAppender};
+
+// Directory of the server log files; the task logs are in a subdirectory
+pub const LOG_DIRECTORY: &str = "logs";
+
+// Interval between two runs of the log pruner
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+// Files written to in the last day may still be open and are never pruned
+const ACTIVE_FILE_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+
+lazy_static::lazy_static! {
+    // Daily log file of the active project and the mode the server runs in
+    static ref LOG_FILE: Mutex<Option<(RollingFileAppender, String)>> = Mutex::new(None);
+}
+
+// Writer of the file log layer; it follows the log file of the active project and redacts secrets
+pub struct ProjectLogWriter;
+
+impl Write for ProjectLogWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if let Some((file, _)) = LOG_FILE.lock().unwrap().as_mut() {
+            file.write_all(secrets::redact(&String::from_utf8_lossy(buf)).as_bytes())?;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match LOG_FILE.lock().unwrap().as_mut() {
+            Some((file, _)) => file.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+// Log to forge-<mode>, or forge-<mode>-<project id> once a registered project is active
+pub fn open_log_file(mode: &str, project_id: Option<&str>) {
+    let prefix = match project_id {
+        Some(id) => format!("forge-{}-{}", mode, id),
+        None => format!("forge-{}", mode),
+    };
+    *LOG_FILE.lock().unwrap() = Some((rolling::daily(LOG_DIRECTORY, &prefix), mode.to_string()));
+}
+
+// Write out the buffered log lines, e.g. before the process exits
+pub fn flush() {
+    if let Some((file, _)) = LOG_FILE.lock().unwrap().as_mut() {
+        let _ = file.flush();
+    }
+}
+
+// Continue logging in the files of another project
+pub fn switch_project_log(project_id: &str) {
+    let mode = LOG_FILE.lock().unwrap().as_ref().map(|(_, mode)| mode.clone());
+    if let Some(mode) = mode {
+        open_log_file(&mode, Some(project_id));
+    }
+}
+
+// A log file with its last modification time and size
+#[derive(Debug, Clone)]
+struct LogFile {
+    path: PathBuf,
+    modified: SystemTime,
+    size: u64,
+}
+
+fn collect_log_files(dir: &Path, files: &mut Vec<LogFile>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.filter_map(|e| e.ok()) {
+        let path = entry.path();
+        // The log database and its journal are pruned by record instead
+        if path.to_string_lossy().starts_with(LOG_DATABASE) {
+            continue;
+        }
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        if metadata.is_dir() {
+            collect_log_files(&path, files);
+        } else if let Ok(modified) = metadata.modified() {
+            files.push(LogFile { path, modified, size: metadata.len() });
+        }
+    }
+}
+
+// Files to delete: those older than max_days, then the oldest ones until the total size fits
+fn expired_log_files(mut files: Vec<LogFile>, retention: &LogRetention, now: SystemTime) -> Vec<LogFile> {
+    let age = |file: &LogFile| now.duration_since(file.modified).unwrap_or_default();
+    files.sort_by_key(|f| f.modified);
+
+    let max_age = retention.max_days.map(|days| Duration::from_secs(days * 24 * 60 * 60));
+    let (mut expired, kept): (Vec<LogFile>, Vec<LogFile>) = files.into_iter()
+        .partition(|f| age(f) > ACTIVE_FILE_AGE && max_age.is_some_and(|max| age(f) > max));
+
+    if let Some(max_bytes) = retention.max_total_mb.map(|mb| mb * 1024 * 1024) {
+        let mut total: u64 = kept.iter().map(|f| f.size).sum();
+        for file in kept {
+            if total <= max_bytes {
+                break;
+            }
+            if age(&file) > ACTIVE_FILE_AGE {
+                total -= file.size;
+                expired.push(file);
+            }
+        }
+    }
+    expired
+}
+
+// Delete the server and task log files the retention policy doesn't keep, returning the number
+// of files and bytes removed
+pub fn prune_logs(retention: &LogRetention) -> (usize, u64) {
+    let mut files = Vec::new();
+    collect_log_files(Path::new(LOG_DIRECTORY), &mut files);
+
+    let (mut count, mut bytes) = (0, 0);
+    for file in expired_log_files(files, retention, SystemTime::now()) {
+        match fs::remove_file(&file.path) {
+            Ok(()) => {
+                count += 1;
+                bytes += file.size;
+            }
+            Err(e) => error!("Failed to delete log file {}: {}", file.path.display(), e),
+        }
+    }
+    (count, bytes)
+}
+
+// Delete the task log records older than max_days from the log database
+pub fn prune_task_log_records(retention: &LogRetention) -> Result<usize, String> {
+    let Some(days) = retention.max_days else {
+        return Ok(0);
+    };
+    get_log_store()?.delete_before(chrono::Utc::now() - chrono::Duration::days(days as i64))
+}
+
+// Prune the log files now and every hour, following the retention of the active project
+pub fn start_log_pruner(project_manager: Arc<ProjectConfigManager>) {
+    thread::spawn(move || loop {
+        let retention = project_manager.get_config()
+            .ok()
+            .and_then(|c| c.log_retention)
+            .unwrap_or_default();
+        let (count, bytes) = prune_logs(&retention);
+        if count > 0 {
+            info!("Pruned {} log file(s), {} KB", count, bytes / 1024);
+        }
+        match prune_task_log_records(&retention) {
+            Ok(0) => {}
+            Ok(records) => info!("Pruned {} task log record(s)", records),
+            Err(e) => error!("{}", e),
+        }
+        thread::sleep(PRUNE_INTERVAL);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expired_log_files() {
+        let now = SystemTime::now();
+        let day = Duration::from_secs(24 * 60 * 60);
+        let file = |name: &str, days: u32, mb: u64| LogFile {
+            path: PathBuf::from(name),
+            modified: now - day * days,
+            size: mb * 1024 * 1024,
+        };
+        let files = vec![file("today", 0, 300), file("old", 40, 1), file("week", 7, 300), file("month", 20, 300)];
+        let names = |retention: LogRetention| expired_log_files(files.clone(), &retention, now)
+            .into_iter()
+            .map(|f| f.path.to_string_lossy().to_string())
+            .collect::<Vec<_>>();
+
+        assert_eq!(names(LogRetention::default()), vec!["old"]);
+        assert_eq!(names(LogRetention { max_days: None, max_total_mb: Some(700) }), vec!["old", "month"]);
+        // The file of today is kept even when it alone exceeds the size
+        assert_eq!(names(LogRetention { max_days: Some(1), max_total_mb: Some(100) }), vec!["old", "month", "week"]);
+        assert!(names(LogRetention { max_days: None, max_total_mb: None }).is_empty());
+    }
+}
@@ -1,67 +1,106 @@
 use actix_files as fs;
-use actix_web::{web, App, HttpServer, Responder};
+use actix_web::{middleware, web, App, HttpServer, Responder};
 use clap::{Arg, Command};
 use dotenv::dotenv;
 use std::sync::Arc;
 use std::thread;
+use std::time::Duration;
 use tracing::{debug, error, info, warn};
 use tracing_subscriber::{self, fmt, prelude::*, EnvFilter};
-use tracing_appender::{self, rolling};
 
 // Import models from the models module
 mod models;
+mod migrations;
 mod block_config;
+mod config_commit;
 mod block_handlers;
+mod aws_sigv4;
+mod json_repair;
+mod llm_audit;
 mod llm_handler;
+mod llm_usage;
 mod profession_prompts;
 mod project_config;
+mod prompt_template;
+mod prompt_history;
+mod secrets;
+mod shutdown;
+mod health;
+mod metrics;
+mod metrics_handlers;
 mod project_handlers;
 mod git_handlers;
+mod executor_handlers;
+mod backup;
+mod block_completion;
+mod process_tracker;
+mod backup_handlers;
+mod scheduler;
+mod schedule_handlers;
 pub mod task_executor;
 mod task_executor_wrapper;
 mod task_queue;
+mod task_csv;
+mod execution_timeline;
+mod preflight;
+mod pull_request;
+mod release;
+mod github_issues;
+mod ci_status;
+mod ci_handlers;
+mod config_validation;
+mod worktree;
+mod git_stash;
+mod git_util;
 mod log_stream;
+mod log_store;
+mod session_transcript;
+mod logging;
+mod project_registry;
+mod project_init;
+mod mcp_session_handlers;
+mod api_auth;
+mod cors;
+mod rate_limit;
+mod openapi;
+mod routes;
+mod oidc;
+mod request_user;
+mod webhooks;
+mod slack;
+mod webhook_handlers;
+mod request_audit;
+mod tls;
 
 mod mcp;
-use crate::block_handlers::{generate_tasks_block_handler, process_specification_handler};
-use crate::git_handlers::pull_handler;
-use block_config::{generate_sample_config, BlockConfigManager, DEFAULT_BLOCK_CONFIG_FILE};
-use block_handlers::{
-    add_block_handler, add_task_handler, auto_complete_handler, delete_block_handler, enhance_block_handler,
-    generate_sample_config_handler, get_block_dependencies_handler, get_blocks_handler, process_markdown_handler, remove_task_handler,
-    update_block_handler, AppState, BLOCK_CONFIG_FILE
-};
-use git_handlers::{
-    build_handler, commit_handler, create_branch_handler, execute_git_task_handler, get_branches_handler, get_task_diff_handler,
-    merge_branch_handler, push_handler, GitAppState
-};
+use block_config::{generate_sample_config, resolve_blocks_config_path, BlockConfigManager, DEFAULT_BLOCK_CONFIG_FILE};
+use block_handlers::{AppState, BLOCK_CONFIG_FILE};
+use git_handlers::GitAppState;
+use api_auth::require_authentication;
 use project_config::{ProjectConfigManager, PROJECT_CONFIG_FILE};
-use project_handlers::{
-    check_project_config_handler, get_profession_prompts_handler, get_professions_handler, get_project_config_handler,
-    test_git_connection_handler, update_project_config_handler, ProjectAppState
-};
+use project_handlers::{activate_project, ProjectAppState};
 
-use crate::log_stream::{get_task_ids, stream_logs};
 use crate::mcp::{server::MCPServerConfig, MCPServer};
 use crate::mcp::transport::TransportFactory;
 use crate::task_executor_wrapper::initialize as init_task_executor;
 
+lazy_static::lazy_static! {
+    static ref APPENDER_GUARD: std::sync::Mutex<Option<tracing_appender::non_blocking::WorkerGuard>> = std::sync::Mutex::new(None);
+}
+
 // Initialize the logger with file output
 fn init_logger(mode: &str) {
     // Create a directory for logs if it doesn't exist
-    std::fs::create_dir_all("logs").unwrap_or_else(|e| {
+    std::fs::create_dir_all(logging::LOG_DIRECTORY).unwrap_or_else(|e| {
         eprintln!("Warning: Failed to create logs directory: {}", e);
     });
 
-    // Set up rolling file appender - creates a new log file each day
-    let file_appender = rolling::daily("logs", format!("forge-{}", mode));
-    let (non_blocking, _guard) = tracing_appender::non_blocking(file_appender);
+    // Set up rolling file appender - creates a new log file each day; the file follows the active project
+    logging::open_log_file(mode, None);
+    let (non_blocking, _guard) = tracing_appender::non_blocking(logging::ProjectLogWriter);
 
     // Store the guard in a static variable to keep it alive for the duration of the program
     // This is important to ensure logs are properly flushed
-    lazy_static::lazy_static! {
-        static ref APPENDER_GUARD: std::sync::Mutex<Option<tracing_appender::non_blocking::WorkerGuard>> = std::sync::Mutex::new(None);
-    }
     *APPENDER_GUARD.lock().unwrap() = Some(_guard);
 
     // Initialize the subscriber with both console and file outputs
@@ -74,13 +113,44 @@ fn init_logger(mode: &str) {
     info!("Logger initialized in {} mode", mode);
 }
 
+// Request a graceful shutdown on SIGINT/SIGTERM; a second signal exits at once
+fn handle_shutdown_signals() {
+    tokio::spawn(async {
+        shutdown::signal().await;
+        info!("Shutdown requested, letting the running work finish");
+        shutdown::request();
+        shutdown::signal().await;
+        warn!("Second shutdown signal, exiting without draining");
+        std::process::exit(130);
+    });
+}
+
+// Let the running execution finish within what's left of the drain timeout, then save the blocks
+// still unsaved and write out the buffered logs
+async fn finish_shutdown(executor: Arc<task_executor::TaskExecutor>, block_manager: Arc<BlockConfigManager>, drain_timeout: Duration) {
+    let timeout = shutdown::remaining(drain_timeout);
+    let drained = tokio::task::spawn_blocking(move || executor.drain(timeout)).await.unwrap_or(false);
+    if !drained {
+        warn!("The running execution was cancelled at the end of the drain timeout");
+    }
+    if block_manager.has_unsaved_changes() {
+        if let Err(e) = block_manager.save_blocks_from("shutdown") {
+            error!("Failed to save the blocks on shutdown: {}", e);
+        }
+    }
+    info!("Shutdown complete");
+    // Dropping the guard flushes the lines queued for the file writer
+    drop(APPENDER_GUARD.lock().unwrap().take());
+    logging::flush();
+}
+
 // Index handler to serve the frontend
 async fn index() -> impl Responder {
     fs::NamedFile::open_async("./frontend/dist/index.html").await
 }
 
 // Run MCP server in stdio mode
-async fn run_mcp_server(project_manager: Arc<ProjectConfigManager> , block_manager : Arc<BlockConfigManager>) -> std::io::Result<()> {
+async fn run_mcp_server(project_manager: Arc<ProjectConfigManager> , block_manager : Arc<BlockConfigManager>, drain_timeout: Duration) -> std::io::Result<()> {
     // Initialize tracing for MCP mode
 
     info!("Starting Forge MCP Server in stdio mode...");
@@ -107,7 +177,7 @@ async fn run_mcp_server(project_manager: Arc<ProjectConfigManager> , block_manag
     };
 
     // Create MCP server
-    let mcp_server = match MCPServer::new(mcp_config, project_manager, block_manager).await {
+    let mcp_server = match MCPServer::new(mcp_config, project_manager, block_manager.clone()).await {
         Ok(server) => server,
         Err(e) => {
             error!("Failed to create MCP server: {}", e);
@@ -124,10 +194,26 @@ async fn run_mcp_server(project_manager: Arc<ProjectConfigManager> , block_manag
         }
     };
 
+    // Clean up sessions and apply the session settings of the project while the connection is open
+    mcp_server.start_services().await;
+
     info!("MCP Server ready, handling stdio connection...");
 
-    // Handle the stdio connection
-    if let Err(e) = mcp_server.handle_connection(transport, "stdio".to_string()).await {
+    // Handle the stdio connection; on shutdown the tool call in flight gets the drain timeout to finish
+    let connection = mcp_server.handle_connection(transport, "stdio".to_string());
+    let result = tokio::select! {
+        result = connection => result,
+        _ = async {
+            shutdown::requested().await;
+            tokio::time::sleep(shutdown::remaining(drain_timeout)).await;
+        } => {
+            warn!("MCP tool call still running at the end of the drain timeout, closing the connection");
+            Ok(())
+        }
+    };
+    crate::mcp::session::clear_published_sessions(&block_manager);
+    metrics::clear_published(&block_manager);
+    if let Err(e) = result {
         error!("MCP Server connection error: {}", e);
         return Err(std::io::Error::new(std::io::ErrorKind::Other, e));
     }
@@ -136,6 +222,43 @@ async fn run_mcp_server(project_manager: Arc<ProjectConfigManager> , block_manag
     Ok(())
 }
 
+// Scaffold a project from the command line (forge init)
+async fn run_init(matches: &clap::ArgMatches) -> std::io::Result<()> {
+    let arg = |name: &str| matches.get_one::<String>(name).cloned();
+    let request = project_init::InitProjectRequest {
+        directory: arg("directory").unwrap_or_else(|| ".".to_string()),
+        name: arg("name"),
+        description: arg("description"),
+        profession_id: arg("profession"),
+        blocks_format: arg("blocks-format"),
+        generate_block: matches.get_flag("generate-block"),
+        open: false,
+    };
+    let mut report = project_init::scaffold_project(&request).map_err(std::io::Error::other)?;
+    if request.generate_block {
+        // Generate the block with the prompts of the new project
+        ProjectConfigManager::get_instance().switch_config_file(&report.project_config_file)?;
+        if let Err(e) = project_init::generate_initial_block(&mut report).await {
+            report.warnings.push(format!("No initial block generated: {}", e));
+        }
+    }
+
+    println!("Created {}", report.project_config_file);
+    println!("Created {}", report.blocks_config_file);
+    println!("Git remote: {}", report.git_repository_url.as_deref().unwrap_or("none"));
+    println!("Main branch: {}", report.config.main_branch.as_deref().unwrap_or("main"));
+    if !report.tech_stack.is_empty() {
+        println!("Tech stack: {}", report.tech_stack.join(", "));
+    }
+    println!("Profession: {}", report.profession_id.as_deref().unwrap_or("none"));
+    if let Some(block) = &report.initial_block {
+        println!("Initial block: {}", block);
+    }
+    for warning in &report.warnings {
+        println!("Warning: {}", warning);
+    }
+    Ok(())
+}
 
 #[tokio::main]
 async fn main() -> std::io::Result<()> {
@@ -149,15 +272,72 @@ async fn main() -> std::io::Result<()> {
                 .help("Run in MCP server mode (stdio transport)")
                 .action(clap::ArgAction::SetTrue)
         )
+        .arg(
+            Arg::new("host")
+                .long("host")
+                .value_name("HOST")
+                .help("Host the HTTP server binds to, e.g. 0.0.0.0 to serve the LAN (overrides server_address)")
+        )
+        .arg(
+            Arg::new("port")
+                .long("port")
+                .value_name("PORT")
+                .value_parser(clap::value_parser!(u16))
+                .help("Port the HTTP server listens on (overrides server_address)")
+        )
+        .arg(
+            Arg::new("env")
+                .long("env")
+                .value_name("ENVIRONMENT")
+                .help("Apply the config overlay of an environment, e.g. dev for project_config.dev.json (overrides FORGE_ENV)")
+        )
+        .subcommand(
+            Command::new("init")
+                .about("Scaffold a new project: config files, git remote and branch, suggested profession")
+                .arg(Arg::new("directory").default_value(".").help("Project directory"))
+                .arg(Arg::new("name").long("name").help("Project name, defaults to the directory name"))
+                .arg(Arg::new("description").long("description").help("Project description"))
+                .arg(Arg::new("profession").long("profession").help("Profession ID, defaults to the one suggested for the tech stack"))
+                .arg(Arg::new("blocks-format").long("blocks-format").help("Format of the blocks config: json, yaml or toml"))
+                .arg(
+                    Arg::new("generate-block")
+                        .long("generate-block")
+                        .help("Generate an initial block from the README")
+                        .action(clap::ArgAction::SetTrue)
+                )
+        )
         .get_matches();
 
     // Load environment variables from .env file
     dotenv().ok();
 
+    if let Some(init_matches) = matches.subcommand_matches("init") {
+        return run_init(init_matches).await;
+    }
+
     init_logger("mcp");
 
+    // Kill processes left behind by a previous Forge run that crashed mid-execution
+    match process_tracker::reap_orphans() {
+        Ok(killed) => {
+            for process in killed {
+                warn!("Killed orphaned process {} ({}) from execution {}", process.pid, process.command, process.execution_id);
+            }
+        },
+        Err(e) => warn!("Failed to reap orphaned processes: {}", e),
+    }
+
+    if let Some(environment) = matches.get_one::<String>("env") {
+        project_config::set_config_environment(environment);
+    }
+
     // Get the singleton instance of ProjectConfigManager
     let project_manager = ProjectConfigManager::get_instance();
+    if let Some(overlay_file) = project_manager.overlay_file() {
+        info!("Applying config overlay {}", overlay_file);
+    } else if let Some(environment) = project_config::config_environment() {
+        warn!("No config overlay found for environment {}", environment);
+    }
 
     // Load project configuration
     let project_config = match project_manager.load_config() {
@@ -172,15 +352,21 @@ async fn main() -> std::io::Result<()> {
         }
     };
 
-    // Determine the blocks config file path based on project home directory
-    let blocks_config_path = if !project_config.project_home_directory.is_empty() {
-        let project_dir = std::path::Path::new(&project_config.project_home_directory);
-        if project_dir.exists() {
-            let blocks_config_path = project_dir.join(BLOCK_CONFIG_FILE);
+    // Determine the blocks config file path based on the spec directory (defaults to the project home directory)
+    let spec_directory = project_config.spec_directory();
+    let blocks_config_path = if !spec_directory.is_empty() {
+        let spec_dir = std::path::Path::new(&spec_directory);
+        if spec_dir.exists() {
+            // The file extension selects the format (JSON, YAML or TOML)
+            let blocks_config_path = resolve_blocks_config_path(spec_dir, project_config.blocks_format.as_deref())
+                .unwrap_or_else(|e| {
+                    warn!("{}, using JSON", e);
+                    spec_dir.join(BLOCK_CONFIG_FILE)
+                });
             info!("Using blocks config path: {}", blocks_config_path.display());
             blocks_config_path.to_string_lossy().to_string()
         } else {
-            warn!("Project home directory does not exist, using default blocks config path");
+            warn!("Spec directory does not exist, using default blocks config path");
             BLOCK_CONFIG_FILE.to_string()
         }
     } else {
@@ -190,10 +376,31 @@ async fn main() -> std::io::Result<()> {
 
     // Create a BlockConfigManager instance with the specific config file path
     let block_manager = Arc::new(BlockConfigManager::new(&blocks_config_path));
+    // Executions, queue and history files are kept in the project home directory, not in the spec directory
+    let project_home = &project_config.project_home_directory;
+    if !project_home.is_empty() && std::path::Path::new(project_home).exists() {
+        block_manager.set_data_directory(Some(project_home.clone()));
+    }
+    if project_config.spec_read_only.unwrap_or(false) {
+        info!("Spec directory is read-only, blocks config changes will be rejected");
+        block_manager.set_read_only(true);
+    }
+    if project_config.config_autocommit.as_ref().map(|a| a.enabled).unwrap_or(false) {
+        info!("Blocks config changes will be committed to the project repository");
+    }
+    block_manager.set_auto_commit(project_config.config_autocommit.clone());
 
     // Initialize the task executor
     info!("Initializing task executor");
-    let _task_executor = init_task_executor(project_manager.clone(), block_manager.clone());
+    let task_executor = init_task_executor(project_manager.clone(), block_manager.clone());
+
+    // Switch to the project that was active when the server last ran
+    if let Ok(Some(active)) = project_registry::load_registry().map(|r| r.active_project().cloned()) {
+        match activate_project(&active.id, &project_manager, &block_manager) {
+            Ok(response) => info!("Opened active project {} ({} blocks)", active.name, response.num_blocks),
+            Err(e) => warn!("Failed to open active project {}: {}", active.name, e),
+        }
+    }
 
     // // Initialize ClaudePty singleton
     // info!("Initializing ClaudePty singleton");
@@ -235,6 +442,7 @@ async fn main() -> std::io::Result<()> {
 
     let project_app_state = web::Data::new(ProjectAppState {
         project_manager: project_manager.clone(),
+        block_manager: block_manager.clone(),
     });
 
     let git_app_state = web::Data::new(GitAppState {
@@ -242,78 +450,127 @@ async fn main() -> std::io::Result<()> {
         block_manager: block_manager.clone(),
     });
 
+    // Delete log files beyond the retention of the project
+    logging::start_log_pruner(project_manager.clone());
+
+    handle_shutdown_signals();
+    let drain_timeout = Duration::from_secs(project_manager.get_config().ok()
+        .and_then(|config| config.shutdown_drain_timeout_seconds)
+        .unwrap_or(project_config::DEFAULT_SHUTDOWN_DRAIN_TIMEOUT_SECONDS));
+
     // Create a thread for the MCP server if the flag is set
-    if matches.get_flag("mcp") {
+    let result = if matches.get_flag("mcp") {
         run_mcp_server(
             project_manager,
-            block_manager).await
+            block_manager.clone(),
+            drain_timeout).await
     } else {
+        // Take scheduled backups of the project data while the server runs
+        backup::start_backup_scheduler(project_manager.clone(), block_manager.clone());
+
+        // Auto-close blocks whose completion criteria hold
+        block_completion::start_completion_evaluator(project_manager.clone(), block_manager.clone());
+
+        // Queue the tasks of cron schedules when they are due
+        scheduler::start_task_scheduler(project_manager.clone(), block_manager.clone());
+
+        // Poll GitHub Actions for the CI status of the task commits when enabled
+        ci_status::start_ci_poller(project_manager.clone(), block_manager.clone());
+
         // Run the HTTP server in the main thread
-        info!("Starting HTTP server on 127.0.0.1:8080");
+        let config = project_manager.get_config().unwrap_or_default();
+        let server_address = project_config::resolve_server_address(
+            config.server_address.as_deref(),
+            matches.get_one::<String>("host").map(String::as_str),
+            matches.get_one::<u16>("port").copied(),
+        );
+        let tls_config = match config.tls.as_ref().map(tls::load_server_config) {
+            Some(Ok(tls_config)) => Some(tls_config),
+            Some(Err(e)) => return Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, e)),
+            None => None,
+        };
+        let loopback = server_address.parse::<std::net::SocketAddr>().is_ok_and(|address| address.ip().is_loopback())
+            || server_address.starts_with("localhost:");
+        if tls_config.is_none() && !loopback {
+            warn!("Serving plain HTTP on {}, configure tls to encrypt the traffic", server_address);
+        }
+        info!("Starting HTTP{} server on {}", if tls_config.is_some() { "S" } else { "" }, server_address);
        run_http_server(
+            server_address,
+            tls_config,
+            drain_timeout,
             app_state,
             project_app_state,
             git_app_state,
         ).await
-    }
+    };
+
+    finish_shutdown(task_executor, block_manager, drain_timeout).await;
+    result
 }
 
 async fn run_http_server(
+    server_address: String,
+    tls_config: Option<rustls::ServerConfig>,
+    drain_timeout: Duration,
     app_state: web::Data<AppState>,
     project_app_state: web::Data<ProjectAppState>,
     git_app_state: web::Data<GitAppState>
 ) -> std::io::Result<()> {
-    HttpServer::new(move || {
+    let cors_config = project_app_state.project_manager.get_config().ok().and_then(|config| config.cors);
+    if let Some(Err(e)) = cors_config.as_ref().map(|cors| cors.validate()) {
+        warn!("{}", e);
+    }
+    let server = HttpServer::new(move || {
+        let cors = cors::cors_middleware(cors_config.as_ref());
         App::new()
+            .wrap(middleware::Condition::new(cors.is_some(), cors.unwrap_or_default()))
+            .wrap(middleware::from_fn(metrics_handlers::track_http_requests))
             .app_data(app_state.clone())
             .app_data(project_app_state.clone())
             .app_data(git_app_state.clone())
-            // API routes
+            // API routes; the last middleware runs first: requests are audited, authenticated, then rate limited
+            .service(
+                web::scope(routes::API_V1_PREFIX)
+                    .wrap(middleware::from_fn(rate_limit::rate_limit))
+                    .wrap(middleware::from_fn(require_authentication))
+                    .wrap(middleware::from_fn(request_audit::audit_requests))
+                    .configure(routes::v1)
+            )
+            // The unversioned paths are aliases of v1
             .service(
-                web::scope("/api")
-                    // Block routes
-                    .route("/blocks", web::get().to(get_blocks_handler))
-                    .route("/blocks", web::post().to(add_block_handler))
-                    .route("/blocks", web::put().to(update_block_handler))
-                    .route("/blocks/{block_id}", web::delete().to(delete_block_handler))
-                    .route("/blocks/{block_id}/task", web::post().to(add_task_handler))
-                    .route("/blocks/{block_id}/delete/{task_id}", web::delete().to(remove_task_handler))
-                    .route("/blocks/{block_id}/enhance", web::put().to(enhance_block_handler))
-                    .route("/blocks/{block_id}/generate-tasks", web::put().to(generate_tasks_block_handler))
-                    .route("/blocks/auto-complete", web::post().to(auto_complete_handler))
-                    .route("/blocks/process-markdown", web::post().to(process_markdown_handler))
-                    .route("/blocks/process-spec", web::post().to(process_specification_handler))
-                    .route("/blocks/{blockId}/dependencies", web::get().to(get_block_dependencies_handler))
-                    .route("/generate-sample", web::post().to(generate_sample_config_handler))
-                    // Project routes
-                    .route("/project", web::get().to(get_project_config_handler))
-                    .route("/project", web::put().to(update_project_config_handler))
-                    .route("/project/test-git-connection", web::post().to(test_git_connection_handler))
-                    .route("/project/check-config", web::get().to(check_project_config_handler))
-                    .route("/project/professions", web::get().to(get_professions_handler))
-                    .route("/project/professions/{profession_id}/prompts", web::get().to(get_profession_prompts_handler))
-                    // Git routes
-                    .route("/git/branch", web::post().to(create_branch_handler))
-                    .route("/git/commit", web::post().to(commit_handler))
-                    .route("/git/merge", web::post().to(merge_branch_handler))
-                    .route("/git/push", web::post().to(push_handler))
-                    .route("/git/pull", web::post().to(pull_handler))
-                    .route("/git/build", web::post().to(build_handler))
-                    .route("/git/execute-task", web::post().to(execute_git_task_handler))
-                    .route("/git/task-diff", web::post().to(get_task_diff_handler))
-                    .route("/git/branches", web::get().to(get_branches_handler))
-                    // Log streaming routes
-                    .route("/logs/stream/{task_id}", web::get().to(stream_logs))
-                    .route("/logs/tasks", web::get().to(get_task_ids))
+                web::scope(routes::API_PREFIX)
+                    .wrap(middleware::from_fn(rate_limit::rate_limit))
+                    .wrap(middleware::from_fn(require_authentication))
+                    .wrap(middleware::from_fn(request_audit::audit_requests))
+                    .configure(routes::v1)
             )
 
+            // Probes and the Prometheus scrape, outside the API so they need no credentials
+            .route("/healthz", web::get().to(health::healthz_handler))
+            .route("/readyz", web::get().to(health::readyz_handler))
+            .route("/metrics", web::get().to(metrics_handlers::metrics_handler))
+
             // Serve static files from the frontend/dist directory
             .service(fs::Files::new("/assets", "./frontend/dist/assets"))
 
             // Serve the index.html for all other routes
             .default_service(web::get().to(index))
     })
-    .bind("127.0.0.1:8080")?
-    .run()
-    .await
+    // Signals are handled by Forge, so the executor stops with the server
+    .disable_signals()
+    .shutdown_timeout(drain_timeout.as_secs());
+    let server = match tls_config {
+        Some(tls_config) => server.bind_rustls_0_23(server_address, tls_config)?,
+        None => server.bind(server_address)?,
+    }
+    .run();
+
+    // Stop accepting connections on shutdown and let the requests in flight finish
+    let handle = server.handle();
+    tokio::spawn(async move {
+        shutdown::requested().await;
+        handle.stop(true).await;
+    });
+    server.await
 }
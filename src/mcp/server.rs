@@ -25,8 +25,11 @@ use crate::mcp::{
     session::{ClientInfo, SessionCleanupService, SessionId, SessionManager},
     state::{StateConfig, UnifiedStateManager},
     tools::{
-        blocks::{CreateBlockTool, ListBlocksTool},
-        tasks::{CreateTaskTool},
+        blocks::{CreateBlockTool, ListBlocksTool, RevertLastChangeTool},
+        git::{CheckMergeConflictsTool, CreatePullRequestTool, CreateReleaseTool, GitBlameTool, GitStashPopTool, GitStashSaveTool},
+        issues::{ImportGitHubIssuesToBlockTool, SyncBlockToGitHubIssuesTool},
+        project::{EditProfessionPromptTool, LookupTermTool},
+        tasks::{CancelTaskExecutionTool, CreateTaskTool, ExportTasksCsvTool, ImportTasksCsvTool, ManageExecutionQueueTool},
         filesystem::{
             create_directory::CreateDirectoryTool,
             delete::DeleteTool,
@@ -173,8 +176,12 @@ impl MCPServer {
                 enable_persistence: true,
                 max_tool_history: 1000,
                 default_permissions: crate::mcp::tools::SessionPermissions::default(),
+                cleanup_interval: crate::mcp::session::SessionConfig::default().cleanup_interval,
             }
         ));
+        if let Ok(config) = project_config.get_config() {
+            session_manager.apply_settings(&config.mcp_sessions.unwrap_or_default());
+        }
 
         // Create tool registry and register built-in tools
         let tool_registry = Arc::new(ToolRegistry::new());
@@ -211,6 +218,19 @@ impl MCPServer {
         let (shutdown_tx, mut shutdown_rx) = mpsc::channel(1);
         self.shutdown_tx = Some(shutdown_tx);
 
+        self.start_services().await;
+
+        info!("MCP Server started successfully");
+
+        // Wait for shutdown signal
+        let _ = shutdown_rx.recv().await;
+
+        info!("MCP Server shutting down...");
+        Ok(())
+    }
+
+    /// Start the cleanup and monitoring services
+    pub async fn start_services(&self) {
         // Start cleanup services
         if self.config.enable_cleanup {
             self.start_cleanup_services().await;
@@ -223,14 +243,6 @@ impl MCPServer {
 
         // Start context cleanup
         self.context_manager.start_cleanup_service().await;
-
-        info!("MCP Server started successfully");
-
-        // Wait for shutdown signal
-        let _ = shutdown_rx.recv().await;
-
-        info!("MCP Server shutting down...");
-        Ok(())
     }
 
     /// Handle a new connection
@@ -251,9 +263,16 @@ impl MCPServer {
         let mut session_id = None;
         let start_time = SystemTime::now();
 
-        // Main message loop
+        // Main message loop; a shutdown stops it between two messages, so the tool call in flight finishes
         loop {
-            match transport.receive().await {
+            let received = tokio::select! {
+                received = transport.receive() => received,
+                _ = crate::shutdown::requested() => {
+                    info!("Shutting down, closing connection {}", connection_id);
+                    break;
+                }
+            };
+            match received {
                 Ok(message) => {
                     if let Err(e) = self.handle_message(message, &mut transport, &connection_id, &mut session_id).await {
                         error!("Error handling message: {}", e);
@@ -521,9 +540,24 @@ impl MCPServer {
         registry.register_tool(Box::new(DeleteTool)).await?;
         registry.register_tool(Box::new(ListBlocksTool)).await?;
         registry.register_tool(Box::new(CreateBlockTool)).await?;
+        registry.register_tool(Box::new(RevertLastChangeTool)).await?;
         registry.register_tool(Box::new(CreateTaskTool)).await?;
-
-        info!("Registered {} built-in tools", 8);
+        registry.register_tool(Box::new(ManageExecutionQueueTool)).await?;
+        registry.register_tool(Box::new(CancelTaskExecutionTool)).await?;
+        registry.register_tool(Box::new(ExportTasksCsvTool)).await?;
+        registry.register_tool(Box::new(ImportTasksCsvTool)).await?;
+        registry.register_tool(Box::new(CreatePullRequestTool)).await?;
+        registry.register_tool(Box::new(CheckMergeConflictsTool)).await?;
+        registry.register_tool(Box::new(GitBlameTool)).await?;
+        registry.register_tool(Box::new(GitStashSaveTool)).await?;
+        registry.register_tool(Box::new(GitStashPopTool)).await?;
+        registry.register_tool(Box::new(CreateReleaseTool)).await?;
+        registry.register_tool(Box::new(SyncBlockToGitHubIssuesTool)).await?;
+        registry.register_tool(Box::new(ImportGitHubIssuesToBlockTool)).await?;
+        registry.register_tool(Box::new(LookupTermTool)).await?;
+        registry.register_tool(Box::new(EditProfessionPromptTool)).await?;
+
+        info!("Registered {} built-in tools", registry.list_tools().await.len());
         Ok(())
     }
 
@@ -531,9 +565,11 @@ impl MCPServer {
     async fn start_cleanup_services(&self) {
         let session_manager = self.session_manager.clone();
         let cleanup_interval = self.config.cleanup_interval;
+        let project_config = self.project_config.clone();
+        let block_manager = self.block_manager.clone();
 
         tokio::spawn(async move {
-            let cleanup_service = SessionCleanupService::new(session_manager);
+            let cleanup_service = SessionCleanupService::new(session_manager).with_control(project_config, block_manager);
             cleanup_service.start().await;
         });
 
@@ -622,3 +658,21 @@ impl MCPServer {
         Ok(json!({ "resources": [] }))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mcp::tools::BUILTIN_TOOL_NAMES;
+
+    #[tokio::test]
+    async fn test_builtin_tool_names_match_registry() {
+        let registry = Arc::new(ToolRegistry::new());
+        MCPServer::register_builtin_tools(&registry).await.unwrap();
+
+        let mut registered: Vec<String> = registry.list_tools().await.into_iter().map(|t| t.name).collect();
+        let mut expected: Vec<String> = BUILTIN_TOOL_NAMES.iter().map(|n| n.to_string()).collect();
+        registered.sort();
+        expected.sort();
+        assert_eq!(registered, expected);
+    }
+}
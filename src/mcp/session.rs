@@ -5,12 +5,20 @@ use std::time::{Duration, SystemTime};
 use tokio::sync::RwLock;
 use uuid::Uuid;
 
+use crate::block_config::{write_file_atomic, BlockConfigManager, FileLock};
 use crate::mcp::errors::{MCPError, MCPResult, SessionError};
 use crate::mcp::tools::{ExecutionContext, SessionPermissions, ToolExecution, UserPreferences};
+use crate::project_config::{McpSessionConfig, ProjectConfigManager};
 use tracing::info;
 
 pub const MAX_MCP_SESSIONS : usize= 2500;
 
+/// File the MCP server publishes its sessions to, stored next to the blocks config
+pub const SESSIONS_FILE: &str = "mcp_sessions.json";
+
+/// Sessions the HTTP API asked the MCP server to evict, stored next to the blocks config
+pub const SESSION_EVICTIONS_FILE: &str = "mcp_session_evictions.json";
+
 
 /// Session identifier type
 pub type SessionId = String;
@@ -20,8 +28,11 @@ pub struct SessionManager {
     /// Active sessions indexed by session ID
     sessions: Arc<RwLock<HashMap<SessionId, Session>>>,
 
-    /// Session configuration
-    config: SessionConfig,
+    /// Configuration the manager was created with
+    base_config: SessionConfig,
+
+    /// Configuration in effect: the base one adjusted by the project settings
+    config: std::sync::RwLock<SessionConfig>,
 }
 
 /// Individual session state
@@ -141,6 +152,9 @@ pub struct SessionConfig {
 
     /// Default session permissions
     pub default_permissions: SessionPermissions,
+
+    /// Interval between two runs of the session cleanup
+    pub cleanup_interval: Duration,
 }
 
 impl Default for SessionConfig {
@@ -151,6 +165,7 @@ impl Default for SessionConfig {
             enable_persistence: true,
             max_tool_history: 1000,
             default_permissions: SessionPermissions::default(),
+            cleanup_interval: Duration::from_secs(30), // 30 seconds for faster cleanup
         }
     }
 }
@@ -165,16 +180,37 @@ impl SessionManager {
     pub fn with_config(config: SessionConfig) -> Self {
         Self {
             sessions: Arc::new(RwLock::new(HashMap::new())),
-            config,
+            base_config: config.clone(),
+            config: std::sync::RwLock::new(config),
         }
     }
 
+    /// Current session configuration
+    pub fn config(&self) -> SessionConfig {
+        self.config.read().unwrap().clone()
+    }
+
+    /// Apply the session limits of the project over the configuration the manager was created with
+    pub fn apply_settings(&self, settings: &McpSessionConfig) {
+        let mut config = self.base_config.clone();
+        if let Some(max_sessions) = settings.max_sessions {
+            config.max_sessions = max_sessions;
+        }
+        if let Some(seconds) = settings.idle_timeout_seconds {
+            config.session_timeout = Duration::from_secs(seconds);
+        }
+        if let Some(seconds) = settings.cleanup_interval_seconds {
+            config.cleanup_interval = Duration::from_secs(seconds);
+        }
+        *self.config.write().unwrap() = config;
+    }
+
     /// Create a new session
     pub async fn create_session(&self, client_info: ClientInfo) -> MCPResult<SessionId> {
         let sessions = self.sessions.read().await;
 
         // Check session limit
-        if sessions.len() >= self.config.max_sessions {
+        if sessions.len() >= self.config().max_sessions {
             return Err(MCPError::Session(SessionError::LimitExceeded));
         }
 
@@ -197,7 +233,7 @@ impl SessionManager {
             },
             active_tasks: Vec::new(),
             tool_history: Vec::new(),
-            permissions: self.config.default_permissions.clone(),
+            permissions: self.config().default_permissions,
             collaboration_state: CollaborationState {
                 is_collaborative: false,
                 collaborators: Vec::new(),
@@ -220,7 +256,7 @@ impl SessionManager {
         let sessions = self.sessions.read().await;
         
         // Check session limit
-        if sessions.len() >= self.config.max_sessions {
+        if sessions.len() >= self.config().max_sessions {
             return Err(MCPError::Session(SessionError::LimitExceeded));
         }
         drop(sessions);
@@ -242,7 +278,7 @@ impl SessionManager {
             },
             active_tasks: Vec::new(),
             tool_history: Vec::new(),
-            permissions: self.config.default_permissions.clone(),
+            permissions: self.config().default_permissions,
             collaboration_state: CollaborationState {
                 is_collaborative: false,
                 collaborators: Vec::new(),
@@ -314,13 +350,14 @@ impl SessionManager {
 
     /// Clean up expired sessions
     pub async fn cleanup_expired_sessions(&self) -> usize {
+        let session_timeout = self.config().session_timeout;
         let mut sessions = self.sessions.write().await;
         let now = SystemTime::now();
         let mut expired_sessions = Vec::new();
 
         for (id, session) in sessions.iter() {
             if let Ok(elapsed) = now.duration_since(session.last_activity) {
-                if elapsed > self.config.session_timeout {
+                if elapsed > session_timeout {
                     expired_sessions.push(id.clone());
                 }
             }
@@ -355,6 +392,35 @@ impl SessionManager {
         count
     }
 
+    /// Describe the sessions and the limits in effect, for the HTTP API
+    pub async fn report(&self) -> SessionsReport {
+        let config = self.config();
+        let now = SystemTime::now();
+        let sessions = self.sessions.read().await;
+        let mut snapshots: Vec<SessionSnapshot> = sessions.values()
+            .map(|s| SessionSnapshot {
+                id: s.id.clone(),
+                client_name: s.client_info.client_name.clone(),
+                status: s.status,
+                created_at: chrono::DateTime::<chrono::Utc>::from(s.created_at).to_rfc3339(),
+                last_activity: chrono::DateTime::<chrono::Utc>::from(s.last_activity).to_rfc3339(),
+                idle_seconds: now.duration_since(s.last_activity).unwrap_or_default().as_secs(),
+                active_tasks: s.active_tasks.clone(),
+                tool_calls: s.tool_history.len(),
+            })
+            .collect();
+        snapshots.sort_by(|a, b| a.created_at.cmp(&b.created_at));
+
+        SessionsReport {
+            pid: std::process::id(),
+            updated_at: chrono::Utc::now().to_rfc3339(),
+            max_sessions: config.max_sessions,
+            idle_timeout_seconds: config.session_timeout.as_secs(),
+            cleanup_interval_seconds: config.cleanup_interval.as_secs(),
+            sessions: snapshots,
+        }
+    }
+
     /// Get session statistics
     pub async fn get_statistics(&self) -> SessionStatistics {
         let sessions = self.sessions.read().await;
@@ -402,11 +468,18 @@ impl SessionManager {
         let session = self.get_session(session_id).await
             .ok_or_else(|| MCPError::Session(SessionError::NotFound(session_id.to_string())))?;
 
+        // Filesystem tools work in the project's code directory, which may differ from the spec directory
+        let working_directory = project_config.get_config().ok()
+            .map(|c| c.project_home_directory)
+            .filter(|d| !d.is_empty())
+            .and_then(|d| std::path::PathBuf::from(d).canonicalize().ok())
+            .unwrap_or(session.context.working_directory);
+
         Ok(ExecutionContext {
             session_id: session_id.to_string(),
             project_config,
             block_manager,
-            working_directory: session.context.working_directory,
+            working_directory,
             context_store,
             execution_history: session.tool_history,
             user_preferences: session.context.user_preferences,
@@ -476,30 +549,136 @@ pub struct SessionStatistics {
     pub oldest_session: Option<SystemTime>,
 }
 
+/// A session as published to the HTTP API
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionSnapshot {
+    pub id: SessionId,
+    pub client_name: String,
+    pub status: SessionStatus,
+    pub created_at: String,
+    pub last_activity: String,
+    pub idle_seconds: u64,
+    pub active_tasks: Vec<String>,
+    pub tool_calls: usize,
+}
+
+/// Sessions of a running MCP server and the limits in effect
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionsReport {
+    /// Process of the MCP server
+    pub pid: u32,
+    pub updated_at: String,
+    pub max_sessions: usize,
+    pub idle_timeout_seconds: u64,
+    pub cleanup_interval_seconds: u64,
+    pub sessions: Vec<SessionSnapshot>,
+}
+
+/// Publish the sessions of the MCP server; the server runs in its own process, so the HTTP API
+/// reads them from the project data
+pub fn publish_sessions(block_manager: &BlockConfigManager, report: &SessionsReport) -> Result<(), String> {
+    let content = serde_json::to_string_pretty(report).map_err(|e| format!("Failed to serialize sessions: {}", e))?;
+    write_file_atomic(&block_manager.project_data_file(SESSIONS_FILE), content.as_bytes())
+}
+
+/// Remove the published sessions once the MCP server stops
+pub fn clear_published_sessions(block_manager: &BlockConfigManager) {
+    let _ = std::fs::remove_file(block_manager.project_data_file(SESSIONS_FILE));
+}
+
+/// Sessions last published by the MCP server, if it runs
+pub fn read_published_sessions(block_manager: &BlockConfigManager) -> Result<Option<SessionsReport>, String> {
+    let file = block_manager.project_data_file(SESSIONS_FILE);
+    match std::fs::read_to_string(&file) {
+        Ok(content) => serde_json::from_str(&content).map(Some).map_err(|e| format!("Failed to parse {}: {}", file, e)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(format!("Failed to read {}: {}", file, e)),
+    }
+}
+
+fn read_eviction_requests(file: &str) -> Result<Vec<SessionId>, String> {
+    match std::fs::read_to_string(file) {
+        Ok(content) => serde_json::from_str(&content).map_err(|e| format!("Failed to parse {}: {}", file, e)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(format!("Failed to read {}: {}", file, e)),
+    }
+}
+
+/// Ask the MCP server to evict sessions at its next cleanup
+pub fn request_eviction(block_manager: &BlockConfigManager, session_ids: &[SessionId]) -> Result<(), String> {
+    let file = block_manager.project_data_file(SESSION_EVICTIONS_FILE);
+    let _lock = FileLock::acquire(&file)?;
+    let mut requests = read_eviction_requests(&file)?;
+    for id in session_ids {
+        if !requests.contains(id) {
+            requests.push(id.clone());
+        }
+    }
+    let content = serde_json::to_string_pretty(&requests).map_err(|e| format!("Failed to serialize eviction requests: {}", e))?;
+    write_file_atomic(&file, content.as_bytes())
+}
+
+/// Take the pending eviction requests
+pub fn take_eviction_requests(block_manager: &BlockConfigManager) -> Result<Vec<SessionId>, String> {
+    let file = block_manager.project_data_file(SESSION_EVICTIONS_FILE);
+    let _lock = FileLock::acquire(&file)?;
+    let requests = read_eviction_requests(&file)?;
+    if !requests.is_empty() {
+        std::fs::remove_file(&file).map_err(|e| format!("Failed to remove {}: {}", file, e))?;
+    }
+    Ok(requests)
+}
+
 /// Session cleanup service
 pub struct SessionCleanupService {
     manager: Arc<SessionManager>,
-    cleanup_interval: Duration,
+
+    /// Project whose session settings and eviction requests are followed
+    control: Option<(Arc<ProjectConfigManager>, Arc<BlockConfigManager>)>,
 }
 
 impl SessionCleanupService {
     pub fn new(manager: Arc<SessionManager>) -> Self {
         Self {
             manager,
-            cleanup_interval: Duration::from_secs(30), // 30 seconds for faster cleanup
+            control: None,
         }
     }
 
+    /// Follow the session settings of the project and the evictions requested through the HTTP
+    /// API, and publish the sessions to it
+    pub fn with_control(mut self, project_config: Arc<ProjectConfigManager>, block_manager: Arc<BlockConfigManager>) -> Self {
+        self.control = Some((project_config, block_manager));
+        self
+    }
+
     /// Start the cleanup service
     pub async fn start(&self) {
         let manager = self.manager.clone();
-        let interval = self.cleanup_interval;
+        let control = self.control.clone();
 
         tokio::spawn(async move {
-            let mut cleanup_timer = tokio::time::interval(interval);
-
             loop {
-                cleanup_timer.tick().await;
+                // The interval is read on every run as the project settings may change it
+                tokio::time::sleep(manager.config().cleanup_interval).await;
+
+                if let Some((project_config, block_manager)) = &control {
+                    // Read from the file as the HTTP API may have changed the settings
+                    if let Ok(config) = project_config.load_config() {
+                        manager.apply_settings(&config.mcp_sessions.unwrap_or_default());
+                    }
+                    match take_eviction_requests(block_manager) {
+                        Ok(session_ids) => {
+                            for session_id in session_ids {
+                                match manager.terminate_session(&session_id).await {
+                                    Ok(()) => tracing::info!("Evicted session {}", session_id),
+                                    Err(e) => tracing::warn!("Failed to evict session {}: {}", session_id, e),
+                                }
+                            }
+                        }
+                        Err(e) => tracing::warn!("Failed to read session eviction requests: {}", e),
+                    }
+                }
 
                 // Clean up expired sessions
                 let cleaned_count = manager.cleanup_expired_sessions().await;
@@ -512,6 +691,24 @@ impl SessionCleanupService {
                 if temp_cleaned > 0 {
                     tracing::info!("Cleaned up {} temporary sessions", temp_cleaned);
                 }
+
+                // Reap processes left behind by Forge instances that died mid-execution
+                match tokio::task::spawn_blocking(crate::process_tracker::reap_orphans).await {
+                    Ok(Ok(killed)) if !killed.is_empty() => {
+                        tracing::info!("Reaped {} orphaned processes", killed.len());
+                    }
+                    Ok(Err(e)) => tracing::warn!("Failed to reap orphaned processes: {}", e),
+                    _ => {}
+                }
+
+                if let Some((_, block_manager)) = &control {
+                    if let Err(e) = publish_sessions(block_manager, &manager.report().await) {
+                        tracing::warn!("Failed to publish sessions: {}", e);
+                    }
+                    if let Err(e) = crate::metrics::publish(block_manager) {
+                        tracing::warn!("Failed to publish metrics: {}", e);
+                    }
+                }
             }
         });
     }
@@ -565,4 +762,33 @@ mod tests {
         let session = manager.get_session(&session_id).await;
         assert!(session.is_none());
     }
+
+    #[tokio::test]
+    async fn test_apply_settings() {
+        let manager = SessionManager::with_config(SessionConfig { max_sessions: 5, ..SessionConfig::default() });
+        manager.apply_settings(&McpSessionConfig {
+            max_sessions: Some(1),
+            idle_timeout_seconds: Some(60),
+            cleanup_interval_seconds: None,
+        });
+        let config = manager.config();
+        assert_eq!((config.max_sessions, config.session_timeout), (1, Duration::from_secs(60)));
+        assert_eq!(config.cleanup_interval, SessionConfig::default().cleanup_interval);
+
+        let client_info = || ClientInfo {
+            client_name: "test_client".to_string(),
+            client_version: "1.0.0".to_string(),
+            user_id: None,
+            capabilities: vec![],
+            connection_time: SystemTime::now(),
+        };
+        manager.create_session(client_info()).await.unwrap();
+        assert!(manager.create_session(client_info()).await.is_err());
+        assert_eq!(manager.report().await.sessions.len(), 1);
+
+        // Unset settings fall back to the configuration the manager was created with
+        manager.apply_settings(&McpSessionConfig::default());
+        assert_eq!(manager.config().max_sessions, 5);
+        manager.create_session(client_info()).await.unwrap();
+    }
 }
\ No newline at end of file
@@ -13,7 +13,7 @@ use crate::mcp::tools::{
     Content, ContextUpdate, ExecutionContext, MCPTool, Permission,
     ToolCategory, ToolError, ToolResult, ToolResultBuilder,
 };
-use crate::models::{Block, Connections, Task};
+use crate::models::{resolve_spec_sections, Block, Connections, Task, TechHints};
 
 /// Tool for listing all blocks in the forge project
 pub struct ListBlocksTool;
@@ -126,6 +126,9 @@ impl MCPTool for ListBlocksTool {
                 "block_id": block.block_id,
                 "name": block.name,
                 "description": block.description,
+                "epic_id": block.epic_id,
+                "tech_hints": block.tech_hints,
+                "status": block.status,
             });
 
             // Include tasks if requested
@@ -155,7 +158,7 @@ impl MCPTool for ListBlocksTool {
 
         // Create context update
         let context_update = ContextUpdate {
-            files_accessed: Some(vec![context.block_manager.config_file.clone()]),
+            files_accessed: Some(vec![context.block_manager.config_file()]),
             files_modified: None,
             git_status: None,
             task_updates: None,
@@ -214,6 +217,24 @@ impl MCPTool for CreateBlockTool {
                 "block_id": {
                     "type": "string",
                     "description": "Optional custom block ID (will be auto-generated if not provided)"
+                },
+                "epic": {
+                    "type": "string",
+                    "description": "Optional epic (feature area) name; the epic is created if it doesn't exist"
+                },
+                "source_sections": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "Optional headings of the specification sections the block was derived from"
+                },
+                "tech_hints": {
+                    "type": "object",
+                    "properties": {
+                        "language": { "type": "string" },
+                        "framework": { "type": "string" },
+                        "directory": { "type": "string" }
+                    },
+                    "description": "Optional language, framework and code directory the block's tasks should target"
                 }
             },
             "required": ["name", "description"]
@@ -244,6 +265,28 @@ impl MCPTool for CreateBlockTool {
             new_block.block_id = block_id;
         }
 
+        // Group the block under its epic if provided
+        if let Some(epic_name) = params["epic"].as_str().filter(|e| !e.trim().is_empty()) {
+            let epic_id = context.block_manager.find_or_create_epic(epic_name, "")
+                .map_err(|e| ToolError::ExecutionFailed(format!("Failed to resolve epic: {}", e)))?;
+            new_block.epic_id = Some(epic_id);
+        }
+
+        // Record the specification sections the block was derived from
+        if let Some(sections) = params["source_sections"].as_array() {
+            let headings: Vec<String> = sections.iter()
+                .filter_map(|v| v.as_str().map(|s| s.to_string()))
+                .collect();
+            new_block.spec_sources = resolve_spec_sections(&[], &headings);
+        }
+
+        // Record the technology the block's tasks should target
+        if !params["tech_hints"].is_null() {
+            let tech_hints: TechHints = serde_json::from_value(params["tech_hints"].clone())
+                .map_err(|e| ToolError::InvalidParams(format!("Invalid tech_hints: {}", e)))?;
+            new_block.tech_hints = Some(tech_hints);
+        }
+
         // Add the block to the block manager
         match context.block_manager.add_block(new_block.clone()) {
             Ok(_) => {
@@ -255,7 +298,7 @@ impl MCPTool for CreateBlockTool {
         }
 
         // Save the updated blocks to file
-        match context.block_manager.save_blocks_to_file() {
+        match context.block_manager.save_blocks_from("mcp:create_block") {
             Ok(_) => {
                 info!("Successfully saved blocks to file");
             }
@@ -267,8 +310,8 @@ impl MCPTool for CreateBlockTool {
 
         // Create context update
         let context_update = ContextUpdate {
-            files_accessed: Some(vec![context.block_manager.config_file.clone()]),
-            files_modified: Some(vec![context.block_manager.config_file.clone()]),
+            files_accessed: Some(vec![context.block_manager.config_file()]),
+            files_modified: Some(vec![context.block_manager.config_file()]),
             git_status: None,
             task_updates: None,
             performance_metrics: None,
@@ -287,6 +330,9 @@ impl MCPTool for CreateBlockTool {
                 "block_id": new_block.block_id,
                 "name": new_block.name,
                 "description": new_block.description,
+                "epic_id": new_block.epic_id,
+                "spec_sources": new_block.spec_sources,
+                "tech_hints": new_block.tech_hints,
                 "connections": {
                     "inputs": new_block.connections.input_connections,
                     "outputs": new_block.connections.output_connections,
@@ -312,3 +358,79 @@ impl MCPTool for CreateBlockTool {
     }
 }
 
+/// Tool for rolling back the last change to the blocks config
+pub struct RevertLastChangeTool;
+
+#[async_trait]
+impl MCPTool for RevertLastChangeTool {
+    fn name(&self) -> &str {
+        "revert_last_change"
+    }
+
+    fn description(&self) -> &str {
+        "Revert the blocks config to its previous revision, or re-apply the last reverted change"
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "redo": {
+                    "type": "boolean",
+                    "description": "Re-apply the last reverted change instead of reverting",
+                    "default": false
+                }
+            }
+        })
+    }
+
+    async fn execute(&self, params: Value, context: &mut ExecutionContext) -> Result<ToolResult, ToolError> {
+        let redo = params["redo"].as_bool().unwrap_or(false);
+
+        let result = if redo {
+            context.block_manager.redo()
+        } else {
+            context.block_manager.undo()
+        };
+        let blocks = result.map_err(ToolError::ExecutionFailed)?;
+
+        info!("{} blocks config, {} blocks restored", if redo { "Redid" } else { "Reverted" }, blocks.len());
+
+        let context_update = ContextUpdate {
+            files_accessed: Some(vec![context.block_manager.history_file()]),
+            files_modified: Some(vec![context.block_manager.config_file()]),
+            git_status: None,
+            task_updates: None,
+            performance_metrics: None,
+            custom_data: Some([
+                ("blocks_count".to_string(), json!(blocks.len())),
+                ("redo".to_string(), json!(redo)),
+            ].into_iter().collect()),
+        };
+
+        let result_data = json!({
+            "success": true,
+            "message": format!("Successfully {} the last change", if redo { "re-applied" } else { "reverted" }),
+            "blocks": blocks.iter().map(|b| json!({
+                "block_id": b.block_id,
+                "name": b.name,
+                "tasks": b.todo_list.len(),
+            })).collect::<Vec<_>>()
+        });
+
+        let formatted_result = serde_json::to_string_pretty(&result_data)
+            .map_err(|e| ToolError::Internal(format!("Failed to format result: {}", e)))?;
+
+        Ok(ToolResult::success()
+            .with_content(Content::Text { text: formatted_result })
+            .with_context_update(context_update))
+    }
+
+    fn required_permissions(&self) -> Vec<Permission> {
+        vec![Permission::FileWrite, Permission::ProjectConfig]
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Project
+    }
+}
@@ -0,0 +1,566 @@
+/// Git tools for MCP
+///
+/// This module provides tools publishing the work of tasks, such as opening
+/// a pull request for the branch of a task or checking it for merge conflicts,
+/// and tools gathering context from the history of the repository.
+
+use async_trait::async_trait;
+use chrono::{FixedOffset, TimeZone};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::process::Command;
+use tracing::info;
+
+use crate::git_stash::{stash_pop, stash_save};
+use crate::pull_request::create_pull_request;
+use crate::release::{create_release, ReleaseRequest};
+use crate::worktree::check_merge_conflicts;
+use crate::mcp::tools::{
+    Content, ExecutionContext, MCPTool, Permission, ToolCategory, ToolError, ToolResult, ToolResultBuilder,
+};
+
+/// Tool for pushing the branch of a task and opening a pull request for it on GitHub or GitLab
+pub struct CreatePullRequestTool;
+
+#[async_trait]
+impl MCPTool for CreatePullRequestTool {
+    fn name(&self) -> &str {
+        "create_pull_request"
+    }
+
+    fn description(&self) -> &str {
+        "Push the branch of a task and open a pull request for it (a merge request on GitLab), described by the task's description, acceptance criteria and execution log"
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "block_id": {
+                    "type": "string",
+                    "description": "Block ID of the task"
+                },
+                "task_id": {
+                    "type": "string",
+                    "description": "Task ID of the task"
+                },
+                "base_branch": {
+                    "type": "string",
+                    "description": "Branch to merge into, defaults to the main branch of the project"
+                },
+                "draft": {
+                    "type": "boolean",
+                    "description": "Open the pull request as a draft",
+                    "default": false
+                }
+            },
+            "required": ["block_id", "task_id"]
+        })
+    }
+
+    async fn execute(&self, params: Value, context: &mut ExecutionContext) -> Result<ToolResult, ToolError> {
+        let block_id = params["block_id"].as_str()
+            .ok_or_else(|| ToolError::InvalidParams("block_id is required".to_string()))?;
+        let task_id = params["task_id"].as_str()
+            .ok_or_else(|| ToolError::InvalidParams("task_id is required".to_string()))?;
+        let base_branch = params["base_branch"].as_str().map(|s| s.to_string());
+        let draft = params["draft"].as_bool().unwrap_or(false);
+
+        let pull_request = create_pull_request(&context.project_config, &context.block_manager, block_id, task_id, base_branch, draft)
+            .await
+            .map_err(ToolError::ExecutionFailed)?;
+        info!("Opened pull request {} for task {}", pull_request.url, task_id);
+
+        let formatted_result = serde_json::to_string_pretty(&pull_request)
+            .map_err(|e| ToolError::Internal(format!("Failed to format result: {}", e)))?;
+
+        Ok(ToolResult::success()
+            .with_content(Content::Text { text: formatted_result }))
+    }
+
+    fn required_permissions(&self) -> Vec<Permission> {
+        vec![Permission::Git, Permission::Network, Permission::TaskManagement]
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Git
+    }
+}
+
+/// Tool for finding the merge conflicts of the branch of a task with a target branch before a review
+pub struct CheckMergeConflictsTool;
+
+#[async_trait]
+impl MCPTool for CheckMergeConflictsTool {
+    fn name(&self) -> &str {
+        "check_merge_conflicts"
+    }
+
+    fn description(&self) -> &str {
+        "Trial merge of the branch of a task into a target branch in a temporary worktree, reporting the conflicting files and their conflict hunks. Neither branch is changed."
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "block_id": {
+                    "type": "string",
+                    "description": "Block ID of the task"
+                },
+                "task_id": {
+                    "type": "string",
+                    "description": "Task ID of the task"
+                },
+                "target_branch": {
+                    "type": "string",
+                    "description": "Branch to merge into, defaults to the main branch of the project"
+                }
+            },
+            "required": ["block_id", "task_id"]
+        })
+    }
+
+    async fn execute(&self, params: Value, context: &mut ExecutionContext) -> Result<ToolResult, ToolError> {
+        let block_id = params["block_id"].as_str()
+            .ok_or_else(|| ToolError::InvalidParams("block_id is required".to_string()))?;
+        let task_id = params["task_id"].as_str()
+            .ok_or_else(|| ToolError::InvalidParams("task_id is required".to_string()))?;
+
+        let config = context.project_config.get_config()
+            .map_err(|e| ToolError::ExecutionFailed(format!("Failed to get project config: {}", e)))?;
+        let blocks = context.block_manager.get_blocks()
+            .map_err(|e| ToolError::ExecutionFailed(format!("Failed to get blocks: {}", e)))?;
+        let task = blocks.iter()
+            .find(|b| b.block_id == block_id)
+            .and_then(|b| b.todo_list.get(task_id))
+            .ok_or_else(|| ToolError::InvalidParams(format!("Task {} not found in block {}", task_id, block_id)))?;
+        let branch = config.task_branch(task);
+        let target_branch = params["target_branch"].as_str()
+            .map(|s| s.to_string())
+            .unwrap_or_else(|| config.task_base_branch());
+
+        let project_dir = config.project_home_directory.clone();
+        let check = tokio::task::spawn_blocking(move || check_merge_conflicts(&project_dir, &branch, &target_branch))
+            .await
+            .map_err(|e| ToolError::Internal(format!("Merge check failed: {}", e)))?
+            .map_err(ToolError::Git)?;
+        info!("Merge check of {} into {}: {} conflicting files", check.branch, check.target_branch, check.conflicts.len());
+
+        let formatted_result = serde_json::to_string_pretty(&check)
+            .map_err(|e| ToolError::Internal(format!("Failed to format result: {}", e)))?;
+
+        Ok(ToolResult::success()
+            .with_content(Content::Text { text: formatted_result }))
+    }
+
+    fn required_permissions(&self) -> Vec<Permission> {
+        vec![Permission::Git, Permission::FileRead]
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Git
+    }
+}
+
+/// Last change of a line, as reported by git blame
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct BlameLine {
+    pub line: usize,
+    pub commit: String,
+    pub author: String,
+    pub date: String,
+    pub summary: String,
+    pub content: String,
+}
+
+/// RFC 3339 date of a git timestamp and its timezone offset, e.g. "+0130"
+fn blame_date(time: i64, tz: &str) -> String {
+    let sign = if tz.starts_with('-') { -1 } else { 1 };
+    let digits = tz.trim_start_matches(['+', '-']);
+    let hours = digits.get(..2).and_then(|h| h.parse::<i32>().ok()).unwrap_or(0);
+    let minutes = digits.get(2..4).and_then(|m| m.parse::<i32>().ok()).unwrap_or(0);
+    FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60))
+        .and_then(|offset| offset.timestamp_opt(time, 0).single())
+        .map(|date| date.to_rfc3339())
+        .unwrap_or_default()
+}
+
+/// Parse the output of `git blame --line-porcelain`, which repeats the commit details on every line
+fn parse_line_porcelain(output: &str) -> Vec<BlameLine> {
+    let mut lines = Vec::new();
+    let mut current: Option<BlameLine> = None;
+    let mut time = 0;
+    for line in output.lines() {
+        if let Some(content) = line.strip_prefix('\t') {
+            if let Some(mut blame) = current.take() {
+                blame.content = content.to_string();
+                lines.push(blame);
+            }
+        } else if let Some(blame) = current.as_mut() {
+            let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+            match key {
+                "author" => blame.author = value.to_string(),
+                "author-time" => time = value.parse().unwrap_or(0),
+                "author-tz" => blame.date = blame_date(time, value),
+                "summary" => blame.summary = value.to_string(),
+                _ => {}
+            }
+        } else {
+            // Header: commit, line in the original file, line in the final file
+            let fields: Vec<&str> = line.split(' ').collect();
+            if let (Some(commit), Some(number)) = (fields.first(), fields.get(2).and_then(|n| n.parse().ok())) {
+                current = Some(BlameLine {
+                    line: number,
+                    commit: commit.to_string(),
+                    author: String::new(),
+                    date: String::new(),
+                    summary: String::new(),
+                    content: String::new(),
+                });
+            }
+        }
+    }
+    lines
+}
+
+/// Tool reporting who last changed each line of a file range, in which commit and when
+pub struct GitBlameTool;
+
+#[async_trait]
+impl MCPTool for GitBlameTool {
+    fn name(&self) -> &str {
+        "git_blame"
+    }
+
+    fn description(&self) -> &str {
+        "Show the commit, author and date of the last change of each line of a file range, to understand recent changes and reference the right commits"
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "Path of the file, relative to the project directory"
+                },
+                "start_line": {
+                    "type": "integer",
+                    "description": "First line of the range (1-based), defaults to the first line of the file",
+                    "minimum": 1
+                },
+                "end_line": {
+                    "type": "integer",
+                    "description": "Last line of the range (inclusive), defaults to the last line of the file",
+                    "minimum": 1
+                },
+                "revision": {
+                    "type": "string",
+                    "description": "Commit or branch to blame, defaults to the working tree"
+                }
+            },
+            "required": ["path"]
+        })
+    }
+
+    async fn execute(&self, params: Value, context: &mut ExecutionContext) -> Result<ToolResult, ToolError> {
+        let path = params["path"].as_str()
+            .ok_or_else(|| ToolError::InvalidParams("path is required".to_string()))?;
+        let start_line = params["start_line"].as_u64();
+        let end_line = params["end_line"].as_u64();
+        let revision = params["revision"].as_str().map(|s| s.to_string());
+        if start_line == Some(0) || end_line == Some(0) || matches!((start_line, end_line), (Some(start), Some(end)) if start > end) {
+            return Err(ToolError::InvalidParams("Lines are 1-based and start_line can't be after end_line".to_string()));
+        }
+        if revision.as_deref().is_some_and(|r| r.starts_with('-')) {
+            return Err(ToolError::InvalidParams(format!("Invalid revision: {}", revision.unwrap_or_default())));
+        }
+
+        // Security check: ensure path is within working directory
+        let file_path = context.working_directory.join(path);
+        let canonical_path = file_path.canonicalize()
+            .map_err(|e| ToolError::FileSystem(format!("Failed to resolve path: {}", e)))?;
+        if !canonical_path.starts_with(&context.working_directory) {
+            return Err(ToolError::PermissionDenied(
+                format!("Access denied: path outside working directory: {}", path)
+            ));
+        }
+
+        let mut command = Command::new("git");
+        command.arg("blame").arg("--line-porcelain").current_dir(&context.working_directory);
+        match (start_line, end_line) {
+            (None, None) => {}
+            (start, end) => {
+                let end = end.map(|e| e.to_string()).unwrap_or_default();
+                command.arg("-L").arg(format!("{},{}", start.unwrap_or(1), end));
+            }
+        }
+        if let Some(revision) = &revision {
+            command.arg(revision);
+        }
+        command.arg("--").arg(&canonical_path);
+
+        let output = tokio::task::spawn_blocking(move || command.output())
+            .await
+            .map_err(|e| ToolError::Internal(format!("git blame failed: {}", e)))?
+            .map_err(|e| ToolError::Git(format!("Failed to run git blame: {}", e)))?;
+        if !output.status.success() {
+            return Err(ToolError::Git(String::from_utf8_lossy(&output.stderr).trim().to_string()));
+        }
+        let lines = parse_line_porcelain(&String::from_utf8_lossy(&output.stdout));
+        info!("Blamed {} lines of {}", lines.len(), path);
+
+        let result_data = json!({
+            "path": path,
+            "revision": revision,
+            "lines": lines,
+        });
+        let formatted_result = serde_json::to_string_pretty(&result_data)
+            .map_err(|e| ToolError::Internal(format!("Failed to format result: {}", e)))?;
+
+        Ok(ToolResult::success()
+            .with_content(Content::Text { text: formatted_result }))
+    }
+
+    fn required_permissions(&self) -> Vec<Permission> {
+        vec![Permission::Git, Permission::FileRead]
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Git
+    }
+}
+
+/// Tool for parking the uncommitted changes of the working directory in a stash, e.g. before switching branches
+pub struct GitStashSaveTool;
+
+#[async_trait]
+impl MCPTool for GitStashSaveTool {
+    fn name(&self) -> &str {
+        "git_stash_save"
+    }
+
+    fn description(&self) -> &str {
+        "Stash the uncommitted changes of the working directory, returning the commit of the stash to restore it with git_stash_pop"
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "message": {
+                    "type": "string",
+                    "description": "Description of the stash"
+                },
+                "include_untracked": {
+                    "type": "boolean",
+                    "description": "Stash untracked files too",
+                    "default": true
+                }
+            }
+        })
+    }
+
+    async fn execute(&self, params: Value, context: &mut ExecutionContext) -> Result<ToolResult, ToolError> {
+        let message = params["message"].as_str()
+            .map(|s| s.to_string())
+            .unwrap_or_else(|| format!("Forge: stashed by session {}", context.session_id));
+        let include_untracked = params["include_untracked"].as_bool().unwrap_or(true);
+
+        let dir = context.working_directory.to_string_lossy().to_string();
+        let stash = tokio::task::spawn_blocking(move || stash_save(&dir, &message, include_untracked))
+            .await
+            .map_err(|e| ToolError::Internal(format!("git stash failed: {}", e)))?
+            .map_err(ToolError::Git)?;
+        match &stash {
+            Some(stash) => info!("Stashed local changes in {}", stash),
+            None => info!("No local changes to stash"),
+        }
+
+        let result_data = json!({
+            "stashed": stash.is_some(),
+            "stash": stash,
+        });
+        let formatted_result = serde_json::to_string_pretty(&result_data)
+            .map_err(|e| ToolError::Internal(format!("Failed to format result: {}", e)))?;
+
+        Ok(ToolResult::success()
+            .with_content(Content::Text { text: formatted_result }))
+    }
+
+    fn required_permissions(&self) -> Vec<Permission> {
+        vec![Permission::Git, Permission::FileWrite]
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Git
+    }
+}
+
+/// Tool for restoring changes parked with git_stash_save
+pub struct GitStashPopTool;
+
+#[async_trait]
+impl MCPTool for GitStashPopTool {
+    fn name(&self) -> &str {
+        "git_stash_pop"
+    }
+
+    fn description(&self) -> &str {
+        "Restore a stash into the working directory and drop it. A stash whose changes conflict is kept."
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "stash": {
+                    "type": "string",
+                    "description": "Commit of the stash returned by git_stash_save, or a reference like stash@{1}; defaults to the latest stash"
+                }
+            }
+        })
+    }
+
+    async fn execute(&self, params: Value, context: &mut ExecutionContext) -> Result<ToolResult, ToolError> {
+        let stash = params["stash"].as_str().map(|s| s.to_string());
+        if stash.as_deref().is_some_and(|s| s.starts_with('-')) {
+            return Err(ToolError::InvalidParams(format!("Invalid stash: {}", stash.unwrap_or_default())));
+        }
+
+        let dir = context.working_directory.to_string_lossy().to_string();
+        let restored = tokio::task::spawn_blocking(move || stash_pop(&dir, stash.as_deref()))
+            .await
+            .map_err(|e| ToolError::Internal(format!("git stash failed: {}", e)))?
+            .map_err(ToolError::Git)?;
+        info!("Restored local changes from stash {}", restored);
+
+        let result_data = json!({
+            "restored": restored,
+        });
+        let formatted_result = serde_json::to_string_pretty(&result_data)
+            .map_err(|e| ToolError::Internal(format!("Failed to format result: {}", e)))?;
+
+        Ok(ToolResult::success()
+            .with_content(Content::Text { text: formatted_result }))
+    }
+
+    fn required_permissions(&self) -> Vec<Permission> {
+        vec![Permission::Git, Permission::FileWrite]
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Git
+    }
+}
+
+/// Tool for tagging a release with a changelog of the tasks it brings, optionally published as a GitHub release
+pub struct CreateReleaseTool;
+
+#[async_trait]
+impl MCPTool for CreateReleaseTool {
+    fn name(&self) -> &str {
+        "create_release"
+    }
+
+    fn description(&self) -> &str {
+        "Tag a commit as a release, with a changelog of the tasks completed since the previous tag grouped by block, and optionally publish it as a GitHub release"
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "tag": {
+                    "type": "string",
+                    "description": "Tag of the release, e.g. v1.2.0"
+                },
+                "commit": {
+                    "type": "string",
+                    "description": "Commit or branch to tag, defaults to the main branch"
+                },
+                "previous_tag": {
+                    "type": "string",
+                    "description": "Tag of the previous release, defaults to the latest tag before the commit"
+                },
+                "github_release": {
+                    "type": "boolean",
+                    "description": "Push the tag and publish a GitHub release with the changelog as notes",
+                    "default": false
+                },
+                "draft": {
+                    "type": "boolean",
+                    "description": "Publish the GitHub release as a draft",
+                    "default": false
+                },
+                "prerelease": {
+                    "type": "boolean",
+                    "description": "Mark the GitHub release as a prerelease",
+                    "default": false
+                }
+            },
+            "required": ["tag"]
+        })
+    }
+
+    async fn execute(&self, params: Value, context: &mut ExecutionContext) -> Result<ToolResult, ToolError> {
+        let tag = params["tag"].as_str()
+            .ok_or_else(|| ToolError::InvalidParams("tag is required".to_string()))?;
+        let request = ReleaseRequest {
+            tag: tag.to_string(),
+            commit: params["commit"].as_str().map(|s| s.to_string()),
+            previous_tag: params["previous_tag"].as_str().map(|s| s.to_string()),
+            github_release: params["github_release"].as_bool().unwrap_or(false),
+            draft: params["draft"].as_bool().unwrap_or(false),
+            prerelease: params["prerelease"].as_bool().unwrap_or(false),
+        };
+
+        let (project_config, block_manager) = (context.project_config.clone(), context.block_manager.clone());
+        let release = tokio::task::spawn_blocking(move || create_release(&project_config, &block_manager, &request))
+            .await
+            .map_err(|e| ToolError::Internal(format!("Release failed: {}", e)))?
+            .map_err(ToolError::ExecutionFailed)?;
+        info!("Tagged release {} at {} with {} tasks", release.tag, release.commit, release.tasks);
+
+        let formatted_result = serde_json::to_string_pretty(&release)
+            .map_err(|e| ToolError::Internal(format!("Failed to format result: {}", e)))?;
+
+        Ok(ToolResult::success()
+            .with_content(Content::Text { text: formatted_result }))
+    }
+
+    fn required_permissions(&self) -> Vec<Permission> {
+        vec![Permission::Git, Permission::Network]
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Git
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_line_porcelain() {
+        let output = "2e8e71c331ee31fdd8ae9afcf1d47c64f2849514 1 3 1\n\
+            author Ada\n\
+            author-mail <ada@example.com>\n\
+            author-time 1735725600\n\
+            author-tz +0130\n\
+            committer Ada\n\
+            summary Fix the parser\n\
+            filename src/parser.rs\n\
+            \tfn parse() {}\n";
+        assert_eq!(parse_line_porcelain(output), vec![BlameLine {
+            line: 3,
+            commit: "2e8e71c331ee31fdd8ae9afcf1d47c64f2849514".to_string(),
+            author: "Ada".to_string(),
+            date: "2025-01-01T11:30:00+01:30".to_string(),
+            summary: "Fix the parser".to_string(),
+            content: "fn parse() {}".to_string(),
+        }]);
+    }
+}
@@ -0,0 +1,139 @@
+/// Issue tracker tools for MCP
+///
+/// This module provides tools syncing the tasks of a block with the issues of
+/// the project's GitHub repository, in both directions.
+
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use tracing::info;
+
+use crate::github_issues::{import_github_issues_to_block, sync_block_to_github_issues};
+use crate::mcp::tools::{
+    Content, ExecutionContext, MCPTool, Permission, ToolCategory, ToolError, ToolResult, ToolResultBuilder,
+};
+
+/// Tool for creating or updating the GitHub issues of the tasks of a block
+pub struct SyncBlockToGitHubIssuesTool;
+
+#[async_trait]
+impl MCPTool for SyncBlockToGitHubIssuesTool {
+    fn name(&self) -> &str {
+        "sync_block_to_github_issues"
+    }
+
+    fn description(&self) -> &str {
+        "Create or update a GitHub issue for each task of a block: title, description, acceptance criteria, labels, assignees, and closed once the task is completed. Issue numbers are stored on the tasks."
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "block_id": {
+                    "type": "string",
+                    "description": "Block ID of the tasks"
+                },
+                "task_ids": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "Tasks to sync, all the tasks of the block by default"
+                }
+            },
+            "required": ["block_id"]
+        })
+    }
+
+    async fn execute(&self, params: Value, context: &mut ExecutionContext) -> Result<ToolResult, ToolError> {
+        let block_id = params["block_id"].as_str()
+            .ok_or_else(|| ToolError::InvalidParams("block_id is required".to_string()))?;
+        let task_ids = params["task_ids"].as_array()
+            .map(|ids| ids.iter().filter_map(|id| id.as_str().map(|s| s.to_string())).collect());
+
+        let report = sync_block_to_github_issues(&context.project_config, &context.block_manager, block_id, task_ids)
+            .await
+            .map_err(ToolError::ExecutionFailed)?;
+        info!("Synced block {} to {}: {} issues created, {} updated, {} errors",
+            block_id, report.repository, report.created.len(), report.updated.len(), report.errors.len());
+
+        let formatted_result = serde_json::to_string_pretty(&report)
+            .map_err(|e| ToolError::Internal(format!("Failed to format result: {}", e)))?;
+
+        Ok(ToolResult::success()
+            .with_content(Content::Text { text: formatted_result }))
+    }
+
+    fn required_permissions(&self) -> Vec<Permission> {
+        vec![Permission::Network, Permission::TaskManagement]
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Tasks
+    }
+}
+
+/// Tool for importing the GitHub issues of the repository as tasks of a block
+pub struct ImportGitHubIssuesToBlockTool;
+
+#[async_trait]
+impl MCPTool for ImportGitHubIssuesToBlockTool {
+    fn name(&self) -> &str {
+        "import_github_issues_to_block"
+    }
+
+    fn description(&self) -> &str {
+        "Import GitHub issues into a block: issues linked to a task update it (title, description, acceptance criteria, labels, assignees, status), the others become new tasks. Closed issues complete their task."
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "block_id": {
+                    "type": "string",
+                    "description": "Block ID to import the issues into"
+                },
+                "state": {
+                    "type": "string",
+                    "enum": ["open", "closed", "all"],
+                    "description": "State of the issues to import",
+                    "default": "open"
+                },
+                "labels": {
+                    "type": "string",
+                    "description": "Comma-separated labels the issues must all have"
+                }
+            },
+            "required": ["block_id"]
+        })
+    }
+
+    async fn execute(&self, params: Value, context: &mut ExecutionContext) -> Result<ToolResult, ToolError> {
+        let block_id = params["block_id"].as_str()
+            .ok_or_else(|| ToolError::InvalidParams("block_id is required".to_string()))?;
+        let state = params["state"].as_str().unwrap_or("open");
+        if !["open", "closed", "all"].contains(&state) {
+            return Err(ToolError::InvalidParams(format!("Invalid state '{}', expected open, closed or all", state)));
+        }
+        let labels = params["labels"].as_str().filter(|l| !l.trim().is_empty());
+
+        let report = import_github_issues_to_block(&context.project_config, &context.block_manager, block_id, state, labels)
+            .await
+            .map_err(ToolError::ExecutionFailed)?;
+        info!("Imported issues of {} into block {}: {} tasks created, {} updated",
+            report.repository, block_id, report.created.len(), report.updated.len());
+
+        let formatted_result = serde_json::to_string_pretty(&report)
+            .map_err(|e| ToolError::Internal(format!("Failed to format result: {}", e)))?;
+
+        Ok(ToolResult::success()
+            .with_content(Content::Text { text: formatted_result }))
+    }
+
+    fn required_permissions(&self) -> Vec<Permission> {
+        vec![Permission::Network, Permission::TaskManagement]
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Tasks
+    }
+}
@@ -6,11 +6,42 @@
 pub mod registry;
 pub mod blocks;
 pub mod filesystem;
+pub mod git;
+pub mod issues;
+pub mod project;
 pub(crate) mod tasks;
 
 // Re-export core tool types
 pub use self::registry::ToolRegistry;
 
+/// Names of the tools registered by the MCP server, used to validate the tool references of
+/// MCP prompts. Must match `MCPServer::register_builtin_tools`.
+pub const BUILTIN_TOOL_NAMES: &[&str] = &[
+    "read_file",
+    "write_file",
+    "list_directory",
+    "create_directory",
+    "delete",
+    "list_blocks",
+    "create_block",
+    "revert_last_change",
+    "create_task",
+    "manage_execution_queue",
+    "cancel_task_execution",
+    "export_tasks_csv",
+    "import_tasks_csv",
+    "lookup_term",
+    "edit_profession_prompt",
+    "create_pull_request",
+    "check_merge_conflicts",
+    "git_blame",
+    "git_stash_save",
+    "git_stash_pop",
+    "create_release",
+    "sync_block_to_github_issues",
+    "import_github_issues_to_block",
+];
+
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
@@ -458,7 +489,10 @@ impl Default for PerformanceTracker {
 
 impl PerformanceTracker {
     pub fn record_execution(&mut self, execution: ToolExecution) {
+        let outcome = if execution.result.as_ref().is_some_and(|r| r.success) { "success" } else { "failure" };
+        crate::metrics::increment(crate::metrics::MCP_TOOL_EXECUTIONS, &[("tool", &execution.tool_name), ("outcome", outcome)], 1.0);
         if let Some(duration) = execution.duration {
+            crate::metrics::observe(crate::metrics::MCP_TOOL_DURATION, &[("tool", &execution.tool_name)], duration);
             self.total_time += duration;
         }
         self.executions.push(execution);
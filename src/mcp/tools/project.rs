@@ -0,0 +1,144 @@
+/// Project knowledge tools for MCP
+///
+/// This module provides tools exposing project-level knowledge, such as
+/// the glossary of domain terms and canonical component names, and tools
+/// editing the project's profession prompts.
+
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use tracing::info;
+
+use crate::profession_prompts;
+use crate::mcp::tools::{
+    Content, ExecutionContext, MCPTool, Permission, ToolCategory, ToolError, ToolResult, ToolResultBuilder,
+};
+
+/// Tool for looking up a term in the project glossary
+pub struct LookupTermTool;
+
+#[async_trait]
+impl MCPTool for LookupTermTool {
+    fn name(&self) -> &str {
+        "lookup_term"
+    }
+
+    fn description(&self) -> &str {
+        "Look up a domain term, abbreviation or canonical component name in the project glossary"
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "term": {
+                    "type": "string",
+                    "description": "The term, alias or part of a term to look up"
+                }
+            },
+            "required": ["term"]
+        })
+    }
+
+    async fn execute(&self, params: Value, context: &mut ExecutionContext) -> Result<ToolResult, ToolError> {
+        let term = params["term"].as_str()
+            .ok_or_else(|| ToolError::InvalidParams("term is required".to_string()))?;
+
+        let config = context.project_config.get_config()
+            .map_err(|e| ToolError::ExecutionFailed(format!("Failed to get project config: {}", e)))?;
+        let matches = config.lookup_term(term);
+
+        info!("Glossary lookup for '{}' found {} terms", term, matches.len());
+
+        let result_data = json!({
+            "term": term,
+            "found": !matches.is_empty(),
+            "matches": matches,
+        });
+
+        let formatted_result = serde_json::to_string_pretty(&result_data)
+            .map_err(|e| ToolError::Internal(format!("Failed to format result: {}", e)))?;
+
+        Ok(ToolResult::success()
+            .with_content(Content::Text { text: formatted_result }))
+    }
+
+    fn required_permissions(&self) -> Vec<Permission> {
+        vec![Permission::FileRead]
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Project
+    }
+}
+
+/// Tool for editing one prompt of a profession
+pub struct EditProfessionPromptTool;
+
+#[async_trait]
+impl MCPTool for EditProfessionPromptTool {
+    fn name(&self) -> &str {
+        "edit_profession_prompt"
+    }
+
+    fn description(&self) -> &str {
+        "Edit one prompt of a profession. User prompts must contain the {{input}} placeholder and MCP prompts must reference existing tools; the edit is stored with the project"
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "profession_id": {
+                    "type": "string",
+                    "description": "ID of the profession, e.g. backend_developer"
+                },
+                "prompt_key": {
+                    "type": "string",
+                    "enum": profession_prompts::PROMPT_KEYS,
+                    "description": "The prompt to edit"
+                },
+                "value": {
+                    "type": "string",
+                    "description": "The new prompt"
+                }
+            },
+            "required": ["profession_id", "prompt_key", "value"]
+        })
+    }
+
+    async fn execute(&self, params: Value, _context: &mut ExecutionContext) -> Result<ToolResult, ToolError> {
+        let profession_id = params["profession_id"].as_str()
+            .ok_or_else(|| ToolError::InvalidParams("profession_id is required".to_string()))?;
+        let prompt_key = params["prompt_key"].as_str()
+            .ok_or_else(|| ToolError::InvalidParams("prompt_key is required".to_string()))?;
+        let value = params["value"].as_str()
+            .ok_or_else(|| ToolError::InvalidParams("value is required".to_string()))?;
+
+        profession_prompts::validate_prompt(prompt_key, value).map_err(ToolError::InvalidParams)?;
+        let profession = profession_prompts::edit_profession_prompt(profession_id, prompt_key, value)
+            .map_err(ToolError::ExecutionFailed)?;
+
+        info!("Edited prompt {} of profession {}", prompt_key, profession.id);
+
+        let result_data = json!({
+            "profession_id": profession.id,
+            "name": profession.name,
+            "prompt_key": prompt_key,
+            "updated": true,
+        });
+
+        let formatted_result = serde_json::to_string_pretty(&result_data)
+            .map_err(|e| ToolError::Internal(format!("Failed to format result: {}", e)))?;
+
+        Ok(ToolResult::success()
+            .with_content(Content::Text { text: formatted_result }))
+    }
+
+    fn required_permissions(&self) -> Vec<Permission> {
+        vec![Permission::FileWrite, Permission::ProjectConfig]
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Project
+    }
+}
@@ -290,9 +290,10 @@ impl ToolRegistry {
             duration: None,
         };
 
-        // Execute the tool
+        // Execute the tool, bounded by the session's execution time limit
+        let timeout = self.config.default_timeout.min(context.permissions.max_execution_time);
         let result = match tokio::time::timeout(
-            self.config.default_timeout,
+            timeout,
             tool.execute(params, context),
         ).await {
             Ok(Ok(mut result)) => {
@@ -321,7 +322,7 @@ impl ToolRegistry {
             }
             Err(_) => {
                 let timeout_error = ToolError::Timeout {
-                    timeout_ms: self.config.default_timeout.as_millis() as u64,
+                    timeout_ms: timeout.as_millis() as u64,
                 };
                 execution.error = Some(timeout_error.to_string());
                 execution.end_time = Some(SystemTime::now());
@@ -1,9 +1,11 @@
 use async_trait::async_trait;
 use serde_json::{json, Value};
+use std::collections::HashMap;
 use tracing::{error, info};
 use crate::mcp::MCPTool;
 use crate::mcp::tools::{Content, ContextUpdate, ExecutionContext, Permission, ToolCategory, ToolError, ToolResult, ToolResultBuilder};
-use crate::models::Task;
+use crate::models::{Effort, Task};
+use crate::task_csv::{export_tasks_csv, import_tasks_csv};
 
 /// Tool for creating a new task for a block in the forge project
 pub struct CreateTaskTool;
@@ -46,7 +48,7 @@ impl MCPTool for CreateTaskTool {
                 },
                 "estimated_effort": {
                     "type": "string",
-                    "description": "Estimated effort required (e.g., '2 hours', 'small', 'large')"
+                    "description": "Estimated effort: 'small', 'medium', 'large' or hours (e.g., '2 hours')"
                 },
                 "files_affected": {
                     "type": "array",
@@ -65,7 +67,7 @@ impl MCPTool for CreateTaskTool {
                 },
                 "status": {
                     "type": "string",
-                    "description": "Initial status of the task (default: 'TODO')"
+                    "description": "Initial status of the task (default: the workflow's initial status, '[TODO]')"
                 }
             },
             "required": ["block_id", "task_name", "description"]
@@ -92,9 +94,8 @@ impl MCPTool for CreateTaskTool {
             .map(|arr| arr.iter().filter_map(|v| v.as_str().map(|s| s.to_string())).collect())
             .unwrap_or_else(Vec::new);
 
-        let estimated_effort = params["estimated_effort"].as_str()
-            .unwrap_or("")
-            .to_string();
+        let estimated_effort = Effort::parse(params["estimated_effort"].as_str().unwrap_or(""))
+            .map_err(ToolError::InvalidParams)?;
 
         let files_affected = params["files_affected"].as_array()
             .map(|arr| arr.iter().filter_map(|v| v.as_str().map(|s| s.to_string())).collect())
@@ -108,9 +109,10 @@ impl MCPTool for CreateTaskTool {
             .map(|arr| arr.iter().filter_map(|v| v.as_str().map(|s| s.to_string())).collect())
             .unwrap_or_else(Vec::new);
 
-        let status = params["status"].as_str()
-            .unwrap_or("TODO")
-            .to_string();
+        // Normalize the status against the project workflow
+        let workflow = context.project_config.get_task_workflow();
+        let status = workflow.normalize_status(params["status"].as_str().unwrap_or(""))
+            .map_err(ToolError::InvalidParams)?;
 
         // Load blocks to verify the block exists
         match context.block_manager.load_blocks_from_file() {
@@ -153,7 +155,7 @@ impl MCPTool for CreateTaskTool {
         };
 
         // Save the updated blocks to file
-        match context.block_manager.save_blocks_to_file() {
+        match context.block_manager.save_blocks_from("mcp:create_task") {
             Ok(_) => {
                 info!("Successfully saved blocks to file after adding task");
             },
@@ -165,8 +167,8 @@ impl MCPTool for CreateTaskTool {
 
         // Create context update
         let context_update = ContextUpdate {
-            files_accessed: Some(vec![context.block_manager.config_file.clone()]),
-            files_modified: Some(vec![context.block_manager.config_file.clone()]),
+            files_accessed: Some(vec![context.block_manager.config_file()]),
+            files_modified: Some(vec![context.block_manager.config_file()]),
             git_status: None,
             task_updates: Some(vec![crate::mcp::tools::TaskUpdate {
                 task_id: actual_task_id.clone(),
@@ -218,4 +220,296 @@ impl MCPTool for CreateTaskTool {
     fn category(&self) -> ToolCategory {
         ToolCategory::Tasks
     }
-}
\ No newline at end of file
+}
+/// Extract the block and task IDs identifying a queued task
+fn queued_task_ref(params: &Value) -> Result<(&str, &str), ToolError> {
+    let block_id = params["block_id"].as_str()
+        .ok_or_else(|| ToolError::InvalidParams("block_id is required".to_string()))?;
+    let task_id = params["task_id"].as_str()
+        .ok_or_else(|| ToolError::InvalidParams("task_id is required".to_string()))?;
+    Ok((block_id, task_id))
+}
+
+/// Tool for inspecting and reordering the pending task execution queue
+pub struct ManageExecutionQueueTool;
+
+#[async_trait]
+impl MCPTool for ManageExecutionQueueTool {
+    fn name(&self) -> &str {
+        "manage_execution_queue"
+    }
+
+    fn description(&self) -> &str {
+        "List, reorder, bump, hold, release or remove tasks in the pending execution queue, or pause and resume the queue"
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "action": {
+                    "type": "string",
+                    "enum": ["list", "move", "bump", "hold", "release", "remove", "reorder", "pause", "resume"],
+                    "description": "Queue operation to perform"
+                },
+                "block_id": {
+                    "type": "string",
+                    "description": "Block ID of the queued task (required for move, bump, hold, release, remove)"
+                },
+                "task_id": {
+                    "type": "string",
+                    "description": "Task ID of the queued task (required for move, bump, hold, release, remove)"
+                },
+                "position": {
+                    "type": "integer",
+                    "description": "New zero-based queue position (required for move)"
+                },
+                "order": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "Queue entries as 'block_id:task_id' in the desired order (required for reorder)"
+                }
+            },
+            "required": ["action"]
+        })
+    }
+
+    async fn execute(&self, params: Value, _context: &mut ExecutionContext) -> Result<ToolResult, ToolError> {
+        let action = params["action"].as_str()
+            .ok_or_else(|| ToolError::InvalidParams("action is required".to_string()))?;
+
+        let executor = crate::task_executor::get_task_executor()
+            .map_err(ToolError::ExecutionFailed)?;
+
+        let queue = match action {
+            "list" => Ok(executor.get_queue()),
+            "move" => {
+                let (block_id, task_id) = queued_task_ref(&params)?;
+                let position = params["position"].as_u64()
+                    .ok_or_else(|| ToolError::InvalidParams("position is required".to_string()))?;
+                executor.move_queued_task(block_id, task_id, position as usize)
+            },
+            "bump" => {
+                let (block_id, task_id) = queued_task_ref(&params)?;
+                executor.move_queued_task(block_id, task_id, 0)
+            },
+            "hold" | "release" => {
+                let (block_id, task_id) = queued_task_ref(&params)?;
+                executor.set_queued_task_hold(block_id, task_id, action == "hold")
+            },
+            "remove" => {
+                let (block_id, task_id) = queued_task_ref(&params)?;
+                executor.remove_queued_task(block_id, task_id)
+            },
+            "pause" | "resume" => executor.set_paused(action == "pause").map(|_| executor.get_queue()),
+            "reorder" => {
+                let order: Vec<String> = params["order"].as_array()
+                    .ok_or_else(|| ToolError::InvalidParams("order is required".to_string()))?
+                    .iter()
+                    .filter_map(|v| v.as_str().map(|s| s.to_string()))
+                    .collect();
+                executor.reorder_queue(&order)
+            },
+            other => return Err(ToolError::InvalidParams(format!("Unknown action '{}'", other))),
+        }.map_err(ToolError::ExecutionFailed)?;
+
+        let formatted_result = serde_json::to_string_pretty(&json!({ "queue": queue, "paused": executor.is_paused() }))
+            .map_err(|e| ToolError::Internal(format!("Failed to format result: {}", e)))?;
+
+        Ok(ToolResult::success()
+            .with_content(Content::Text { text: formatted_result }))
+    }
+
+    fn required_permissions(&self) -> Vec<Permission> {
+        vec![Permission::TaskManagement, Permission::Execute]
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Tasks
+    }
+}
+
+/// Tool for cancelling a queued or running task execution
+pub struct CancelTaskExecutionTool;
+
+#[async_trait]
+impl MCPTool for CancelTaskExecutionTool {
+    fn name(&self) -> &str {
+        "cancel_task_execution"
+    }
+
+    fn description(&self) -> &str {
+        "Cancel a queued or running task execution, terminating its Claude process and marking the task cancelled"
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "block_id": {
+                    "type": "string",
+                    "description": "Block ID of the task"
+                },
+                "task_id": {
+                    "type": "string",
+                    "description": "Task ID of the task"
+                }
+            },
+            "required": ["block_id", "task_id"]
+        })
+    }
+
+    async fn execute(&self, params: Value, _context: &mut ExecutionContext) -> Result<ToolResult, ToolError> {
+        let (block_id, task_id) = queued_task_ref(&params)?;
+
+        let executor = crate::task_executor::get_task_executor()
+            .map_err(ToolError::ExecutionFailed)?;
+        let message = executor.cancel_execution(&format!("{}:{}", block_id, task_id))
+            .map_err(ToolError::ExecutionFailed)?;
+        info!("{}", message);
+
+        Ok(ToolResult::success()
+            .with_content(Content::Text { text: message }))
+    }
+
+    fn required_permissions(&self) -> Vec<Permission> {
+        vec![Permission::TaskManagement, Permission::Execute]
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Tasks
+    }
+}
+
+/// Tool for exporting the tasks of the forge project as CSV
+pub struct ExportTasksCsvTool;
+
+#[async_trait]
+impl MCPTool for ExportTasksCsvTool {
+    fn name(&self) -> &str {
+        "export_tasks_csv"
+    }
+
+    fn description(&self) -> &str {
+        "Export all tasks (or the tasks of one block) as CSV, one row per task"
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "block_id": {
+                    "type": "string",
+                    "description": "Optional block ID to export only the tasks of that block"
+                }
+            }
+        })
+    }
+
+    async fn execute(&self, params: Value, context: &mut ExecutionContext) -> Result<ToolResult, ToolError> {
+        let mut blocks = context.block_manager.get_blocks()
+            .map_err(|e| ToolError::ExecutionFailed(format!("Failed to get blocks: {}", e)))?;
+
+        if let Some(block_id) = params["block_id"].as_str() {
+            blocks.retain(|b| b.block_id == block_id);
+            if blocks.is_empty() {
+                return Err(ToolError::InvalidParams(format!("Block with ID '{}' not found", block_id)));
+            }
+        }
+
+        let csv = export_tasks_csv(&blocks).map_err(ToolError::ExecutionFailed)?;
+
+        Ok(ToolResult::success()
+            .with_content(Content::Text { text: csv }))
+    }
+
+    fn required_permissions(&self) -> Vec<Permission> {
+        vec![Permission::FileRead]
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Tasks
+    }
+}
+
+/// Tool for importing tasks from CSV into a block of the forge project
+pub struct ImportTasksCsvTool;
+
+#[async_trait]
+impl MCPTool for ImportTasksCsvTool {
+    fn name(&self) -> &str {
+        "import_tasks_csv"
+    }
+
+    fn description(&self) -> &str {
+        "Import tasks from CSV into a block; invalid rows are skipped and reported with their row number"
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "block_id": {
+                    "type": "string",
+                    "description": "The ID of the block to import the tasks into"
+                },
+                "csv": {
+                    "type": "string",
+                    "description": "CSV content with a header row; a description column is required"
+                },
+                "column_mapping": {
+                    "type": "object",
+                    "additionalProperties": { "type": "string" },
+                    "description": "Optional task field -> CSV column mapping (fields: task_id, task_name, description, status, estimated_effort, acceptance_criteria, dependencies, files_affected, testing_requirements)"
+                }
+            },
+            "required": ["block_id", "csv"]
+        })
+    }
+
+    async fn execute(&self, params: Value, context: &mut ExecutionContext) -> Result<ToolResult, ToolError> {
+        let block_id = params["block_id"].as_str()
+            .ok_or_else(|| ToolError::InvalidParams("block_id is required".to_string()))?;
+        let csv = params["csv"].as_str()
+            .ok_or_else(|| ToolError::InvalidParams("csv is required".to_string()))?;
+        let column_mapping: HashMap<String, String> = params["column_mapping"].as_object()
+            .map(|mapping| mapping.iter()
+                .filter_map(|(field, column)| column.as_str().map(|c| (field.clone(), c.to_string())))
+                .collect())
+            .unwrap_or_default();
+
+        let workflow = context.project_config.get_task_workflow();
+        let result = import_tasks_csv(&context.block_manager, block_id, csv, &column_mapping, &workflow)
+            .map_err(ToolError::InvalidParams)?;
+
+        if !result.imported.is_empty() {
+            context.block_manager.save_blocks_from("mcp:import_tasks_csv")
+                .map_err(|e| ToolError::ExecutionFailed(format!("Failed to save blocks: {}", e)))?;
+        }
+        info!("Imported {} tasks into block '{}' ({} rows rejected)", result.imported.len(), block_id, result.errors.len());
+
+        let context_update = ContextUpdate {
+            files_accessed: Some(vec![context.block_manager.config_file()]),
+            files_modified: if result.imported.is_empty() { None } else { Some(vec![context.block_manager.config_file()]) },
+            git_status: None,
+            task_updates: None,
+            performance_metrics: None,
+            custom_data: None,
+        };
+
+        let formatted_result = serde_json::to_string_pretty(&result)
+            .map_err(|e| ToolError::Internal(format!("Failed to format result: {}", e)))?;
+
+        Ok(ToolResult::success()
+            .with_content(Content::Text { text: formatted_result })
+            .with_context_update(context_update))
+    }
+
+    fn required_permissions(&self) -> Vec<Permission> {
+        vec![Permission::FileWrite, Permission::TaskManagement, Permission::ProjectConfig]
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Tasks
+    }
+}
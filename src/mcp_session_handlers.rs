@@ -0,0 +1,83 @@
+use crate::block_handlers::AppState;
+use crate::mcp::session::{read_published_sessions, request_eviction, SessionsReport};
+use crate::project_config::McpSessionConfig;
+use actix_web::{web, HttpResponse, Responder};
+use serde::{Deserialize, Serialize};
+
+// Session settings of the project and the sessions of the running MCP server
+#[derive(Debug, Serialize)]
+pub struct McpSessionsResponse {
+    pub settings: McpSessionConfig,
+    // Published by the MCP server at every cleanup; None when it doesn't run
+    pub server: Option<SessionsReport>,
+}
+
+// Request body for evicting the sessions idle for a while
+#[derive(Debug, Deserialize)]
+pub struct EvictIdleSessionsRequest {
+    pub idle_seconds: u64,
+}
+
+// API endpoint to get the session settings and the sessions of the MCP server
+pub async fn get_mcp_sessions_handler(data: web::Data<AppState>) -> impl Responder {
+    let settings = data.project_manager.get_config().ok().and_then(|c| c.mcp_sessions).unwrap_or_default();
+    match read_published_sessions(&data.block_manager) {
+        Ok(server) => HttpResponse::Ok().json(McpSessionsResponse { settings, server }),
+        Err(e) => HttpResponse::InternalServerError().body(e),
+    }
+}
+
+// API endpoint to change the session settings; the MCP server applies them at its next cleanup
+pub async fn update_mcp_session_config_handler(data: web::Data<AppState>, settings: web::Json<McpSessionConfig>) -> impl Responder {
+    let settings = settings.into_inner();
+    if let Err(e) = settings.validate() {
+        return HttpResponse::BadRequest().body(e);
+    }
+    let mut config = match data.project_manager.get_config() {
+        Ok(config) => config,
+        Err(e) => return HttpResponse::InternalServerError().body(format!("Failed to get project config: {}", e)),
+    };
+    config.mcp_sessions = Some(settings.clone());
+    match data.project_manager.save_config(&config) {
+        Ok(_) => HttpResponse::Ok().json(settings),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Failed to save project config: {}", e)),
+    }
+}
+
+fn queue_evictions(data: &AppState, session_ids: Vec<String>) -> HttpResponse {
+    if session_ids.is_empty() {
+        return HttpResponse::Ok().json(session_ids);
+    }
+    match request_eviction(&data.block_manager, &session_ids) {
+        Ok(()) => HttpResponse::Accepted().json(session_ids),
+        Err(e) => HttpResponse::InternalServerError().body(e),
+    }
+}
+
+// API endpoint to force the eviction of a session, e.g. one that is stuck
+pub async fn evict_mcp_session_handler(path: web::Path<String>, data: web::Data<AppState>) -> impl Responder {
+    let session_id = path.into_inner();
+    let server = match read_published_sessions(&data.block_manager) {
+        Ok(Some(server)) => server,
+        Ok(None) => return HttpResponse::Conflict().body("The MCP server isn't running"),
+        Err(e) => return HttpResponse::InternalServerError().body(e),
+    };
+    if !server.sessions.iter().any(|s| s.id == session_id) {
+        return HttpResponse::NotFound().body(format!("Session {} not found", session_id));
+    }
+    queue_evictions(&data, vec![session_id])
+}
+
+// API endpoint to evict every session without activity for the given time
+pub async fn evict_idle_mcp_sessions_handler(data: web::Data<AppState>, request: web::Json<EvictIdleSessionsRequest>) -> impl Responder {
+    let server = match read_published_sessions(&data.block_manager) {
+        Ok(Some(server)) => server,
+        Ok(None) => return HttpResponse::Conflict().body("The MCP server isn't running"),
+        Err(e) => return HttpResponse::InternalServerError().body(e),
+    };
+    let session_ids = server.sessions.into_iter()
+        .filter(|s| s.idle_seconds >= request.idle_seconds)
+        .map(|s| s.id)
+        .collect();
+    queue_evictions(&data, session_ids)
+}
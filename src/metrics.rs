@@ -0,0 +1,204 @@
+use crate::block_config::{write_file_atomic, BlockConfigManager};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::Mutex;
+use std::time::Duration;
+
+// Metrics of the MCP server process, published for the /metrics endpoint of the HTTP server
+pub const MCP_METRICS_FILE: &str = "mcp_metrics.json";
+
+pub const HTTP_REQUESTS: &str = "forge_http_requests_total";
+pub const HTTP_REQUEST_DURATION: &str = "forge_http_request_duration_seconds";
+pub const MCP_TOOL_EXECUTIONS: &str = "forge_mcp_tool_executions_total";
+pub const MCP_TOOL_DURATION: &str = "forge_mcp_tool_duration_seconds";
+pub const TASK_EXECUTIONS: &str = "forge_task_executions_total";
+pub const TASK_EXECUTION_DURATION: &str = "forge_task_execution_duration_seconds";
+pub const LLM_TOKENS: &str = "forge_llm_tokens_total";
+pub const LLM_COST: &str = "forge_llm_cost_usd_total";
+pub const TASK_QUEUE_DEPTH: &str = "forge_task_queue_depth";
+pub const TASKS_RUNNING: &str = "forge_tasks_running";
+
+// Help text and type of every metric, in the order they are rendered
+const METRICS: &[(&str, &str, &str)] = &[
+    (HTTP_REQUESTS, "counter", "HTTP requests by method, route and status"),
+    (HTTP_REQUEST_DURATION, "histogram", "Latency of the HTTP requests by method and route"),
+    (MCP_TOOL_EXECUTIONS, "counter", "MCP tool executions by tool and outcome"),
+    (MCP_TOOL_DURATION, "histogram", "Duration of the MCP tool executions by tool"),
+    (TASK_EXECUTIONS, "counter", "Task executions by final status"),
+    (TASK_EXECUTION_DURATION, "histogram", "Duration of the task executions"),
+    (LLM_TOKENS, "counter", "LLM tokens by provider, model and kind (prompt or completion)"),
+    (LLM_COST, "counter", "Estimated LLM spend in USD by provider"),
+    (TASK_QUEUE_DEPTH, "gauge", "Tasks waiting in the execution queue"),
+    (TASKS_RUNNING, "gauge", "Tasks being executed"),
+];
+
+// Upper bounds of the histogram buckets, in seconds; covers HTTP requests up to task executions
+const BUCKETS: [f64; 16] = [0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 300.0, 900.0, 3600.0];
+
+type Labels = Vec<(String, String)>;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistogramSample {
+    // Observations per bucket of BUCKETS, not cumulative; the last entry counts the ones above
+    pub buckets: Vec<u64>,
+    pub sum: f64,
+    pub count: u64,
+}
+
+impl Default for HistogramSample {
+    fn default() -> Self {
+        Self { buckets: vec![0; BUCKETS.len() + 1], sum: 0.0, count: 0 }
+    }
+}
+
+impl HistogramSample {
+    fn observe(&mut self, value: f64) {
+        let index = BUCKETS.iter().position(|bound| value <= *bound).unwrap_or(BUCKETS.len());
+        self.buckets[index] += 1;
+        self.sum += value;
+        self.count += 1;
+    }
+
+    fn merge(&mut self, other: &HistogramSample) {
+        for (bucket, count) in self.buckets.iter_mut().zip(&other.buckets) {
+            *bucket += count;
+        }
+        self.sum += other.sum;
+        self.count += other.count;
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MetricsSnapshot {
+    pub values: BTreeMap<String, Vec<(Labels, f64)>>,
+    pub histograms: BTreeMap<String, Vec<(Labels, HistogramSample)>>,
+}
+
+impl MetricsSnapshot {
+    // Add the counters and histograms of another process; gauges are set by the scrape itself
+    pub fn merge(&mut self, other: &MetricsSnapshot) {
+        for (name, samples) in &other.values {
+            for (labels, value) in samples {
+                *entry(self.values.entry(name.clone()).or_default(), labels) += value;
+            }
+        }
+        for (name, samples) in &other.histograms {
+            for (labels, histogram) in samples {
+                entry(self.histograms.entry(name.clone()).or_default(), labels).merge(histogram);
+            }
+        }
+    }
+
+    pub fn set_gauge(&mut self, name: &str, value: f64) {
+        self.values.insert(name.to_string(), vec![(Vec::new(), value)]);
+    }
+
+    // Prometheus text exposition format
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        for (name, kind, help) in METRICS {
+            let _ = writeln!(out, "# HELP {} {}\n# TYPE {} {}", name, help, name, kind);
+            for (labels, value) in self.values.get(*name).into_iter().flatten() {
+                let _ = writeln!(out, "{}{} {}", name, format_labels(labels, None), value);
+            }
+            for (labels, histogram) in self.histograms.get(*name).into_iter().flatten() {
+                let mut cumulative = 0;
+                for (index, count) in histogram.buckets.iter().enumerate() {
+                    cumulative += count;
+                    let bound = BUCKETS.get(index).map(|b| b.to_string()).unwrap_or_else(|| "+Inf".to_string());
+                    let _ = writeln!(out, "{}_bucket{} {}", name, format_labels(labels, Some(&bound)), cumulative);
+                }
+                let _ = writeln!(out, "{}_sum{} {}", name, format_labels(labels, None), histogram.sum);
+                let _ = writeln!(out, "{}_count{} {}", name, format_labels(labels, None), histogram.count);
+            }
+        }
+        out
+    }
+}
+
+fn entry<'a, T: Default>(samples: &'a mut Vec<(Labels, T)>, labels: &Labels) -> &'a mut T {
+    let index = match samples.iter().position(|(l, _)| l == labels) {
+        Some(index) => index,
+        None => {
+            samples.push((labels.clone(), T::default()));
+            samples.len() - 1
+        }
+    };
+    &mut samples[index].1
+}
+
+fn format_labels(labels: &Labels, le: Option<&str>) -> String {
+    let escape = |value: &str| value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n");
+    let mut pairs: Vec<String> = labels.iter().map(|(k, v)| format!("{}=\"{}\"", k, escape(v))).collect();
+    if let Some(le) = le {
+        pairs.push(format!("le=\"{}\"", le));
+    }
+    if pairs.is_empty() { String::new() } else { format!("{{{}}}", pairs.join(",")) }
+}
+
+fn labels(pairs: &[(&str, &str)]) -> Labels {
+    pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+}
+
+lazy_static::lazy_static! {
+    static ref REGISTRY: Mutex<MetricsSnapshot> = Mutex::new(MetricsSnapshot::default());
+}
+
+pub fn increment(name: &str, pairs: &[(&str, &str)], value: f64) {
+    if let Ok(mut registry) = REGISTRY.lock() {
+        *entry(registry.values.entry(name.to_string()).or_default(), &labels(pairs)) += value;
+    }
+}
+
+pub fn observe(name: &str, pairs: &[(&str, &str)], duration: Duration) {
+    if let Ok(mut registry) = REGISTRY.lock() {
+        entry(registry.histograms.entry(name.to_string()).or_default(), &labels(pairs)).observe(duration.as_secs_f64());
+    }
+}
+
+// Metrics recorded by this process
+pub fn snapshot() -> MetricsSnapshot {
+    REGISTRY.lock().map(|registry| registry.clone()).unwrap_or_default()
+}
+
+// Write the metrics of this process for the HTTP server to expose
+pub fn publish(block_manager: &BlockConfigManager) -> Result<(), String> {
+    let content = serde_json::to_string(&snapshot()).map_err(|e| format!("Failed to serialize metrics: {}", e))?;
+    write_file_atomic(&block_manager.project_data_file(MCP_METRICS_FILE), content.as_bytes())
+}
+
+pub fn clear_published(block_manager: &BlockConfigManager) {
+    let _ = std::fs::remove_file(block_manager.project_data_file(MCP_METRICS_FILE));
+}
+
+// Metrics last published by the MCP server, if it runs
+pub fn read_published(block_manager: &BlockConfigManager) -> Option<MetricsSnapshot> {
+    let content = std::fs::read_to_string(block_manager.project_data_file(MCP_METRICS_FILE)).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_and_merge() {
+        let mut snapshot = MetricsSnapshot::default();
+        *entry(snapshot.values.entry(MCP_TOOL_EXECUTIONS.to_string()).or_default(), &labels(&[("tool", "read_file"), ("outcome", "success")])) += 2.0;
+        entry(snapshot.histograms.entry(MCP_TOOL_DURATION.to_string()).or_default(), &labels(&[("tool", "read_file")])).observe(0.02);
+
+        let mut merged = snapshot.clone();
+        merged.merge(&snapshot);
+        merged.set_gauge(TASK_QUEUE_DEPTH, 3.0);
+        let text = merged.render();
+
+        assert!(text.contains("# TYPE forge_mcp_tool_executions_total counter"));
+        assert!(text.contains("forge_mcp_tool_executions_total{tool=\"read_file\",outcome=\"success\"} 4"));
+        assert!(text.contains("forge_mcp_tool_duration_seconds_bucket{tool=\"read_file\",le=\"0.01\"} 0"));
+        assert!(text.contains("forge_mcp_tool_duration_seconds_bucket{tool=\"read_file\",le=\"0.025\"} 2"));
+        assert!(text.contains("forge_mcp_tool_duration_seconds_bucket{tool=\"read_file\",le=\"+Inf\"} 2"));
+        assert!(text.contains("forge_mcp_tool_duration_seconds_count{tool=\"read_file\"} 2"));
+        assert!(text.contains("forge_task_queue_depth 3"));
+    }
+}
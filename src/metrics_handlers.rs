@@ -0,0 +1,36 @@
+use crate::block_handlers::AppState;
+use crate::metrics::{self, HTTP_REQUESTS, HTTP_REQUEST_DURATION, TASKS_RUNNING, TASK_QUEUE_DEPTH};
+use crate::task_executor::get_task_executor;
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::middleware::Next;
+use actix_web::{web, Error, HttpResponse, Responder};
+use std::time::Instant;
+
+// Middleware counting the requests and their latency. Requests are labelled with the route pattern,
+// not the path, so IDs don't multiply the series.
+pub async fn track_http_requests(req: ServiceRequest, next: Next<impl MessageBody>) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let started = Instant::now();
+    let method = req.method().to_string();
+    let res = next.call(req).await?;
+    let route = res.request().match_pattern().unwrap_or_else(|| "unmatched".to_string());
+    let status = res.status().as_u16().to_string();
+    metrics::increment(HTTP_REQUESTS, &[("method", &method), ("route", &route), ("status", &status)], 1.0);
+    metrics::observe(HTTP_REQUEST_DURATION, &[("method", &method), ("route", &route)], started.elapsed());
+    Ok(res)
+}
+
+// Prometheus metrics of the server, with the MCP tool executions published by the MCP server
+pub async fn metrics_handler(data: web::Data<AppState>) -> impl Responder {
+    let mut snapshot = metrics::snapshot();
+    if let Some(mcp) = metrics::read_published(&data.block_manager) {
+        snapshot.merge(&mcp);
+    }
+    if let Ok(executor) = get_task_executor() {
+        snapshot.set_gauge(TASK_QUEUE_DEPTH, executor.get_queue().len() as f64);
+        snapshot.set_gauge(TASKS_RUNNING, if executor.running_execution().is_some() { 1.0 } else { 0.0 });
+    }
+    HttpResponse::Ok()
+        .content_type("text/plain; version=0.0.4; charset=utf-8")
+        .body(snapshot.render())
+}
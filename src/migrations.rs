@@ -0,0 +1,153 @@
+use crate::models::Effort;
+use serde_json::{json, Value};
+
+// Current schema versions of the config files.
+// Files without a schema_version are version 1 (blocks config: a bare array of blocks).
+pub const BLOCKS_SCHEMA_VERSION: u32 = 2;
+pub const PROJECT_SCHEMA_VERSION: u32 = 3;
+
+// A migration upgrades a document from the given version to the next one
+type Migration = fn(&mut Value) -> Result<(), String>;
+
+// Blocks config migrations, indexed by the version they upgrade from
+const BLOCKS_MIGRATIONS: &[(u32, Migration)] = &[
+    (1, migrate_blocks_v1_to_v2),
+];
+
+// Project config migrations, indexed by the version they upgrade from
+const PROJECT_MIGRATIONS: &[(u32, Migration)] = &[
+    (1, migrate_project_v1_to_v2),
+    (2, migrate_project_v2_to_v3),
+];
+
+// Schema version of a document; documents without one are version 1
+fn schema_version(document: &Value) -> u32 {
+    document.get("schema_version")
+        .and_then(|v| v.as_u64())
+        .map(|v| v as u32)
+        .unwrap_or(1)
+}
+
+// Apply the migrations needed to bring a document to the target version.
+// Returns whether the document was changed.
+fn run_migrations(document: &mut Value, migrations: &[(u32, Migration)], target: u32, name: &str) -> Result<bool, String> {
+    let mut version = schema_version(document);
+    if version > target {
+        return Err(format!("{} schema version {} is newer than the supported version {}", name, version, target));
+    }
+
+    let migrated = version < target;
+    while version < target {
+        let (_, migration) = migrations.iter()
+            .find(|(from, _)| *from == version)
+            .ok_or_else(|| format!("No migration for {} from schema version {}", name, version))?;
+        migration(document)?;
+        version += 1;
+        document["schema_version"] = json!(version);
+    }
+
+    Ok(migrated)
+}
+
+// Upgrade a blocks config document to the current schema version
+pub fn migrate_blocks(document: &mut Value) -> Result<bool, String> {
+    run_migrations(document, BLOCKS_MIGRATIONS, BLOCKS_SCHEMA_VERSION, "Blocks config")
+}
+
+// Upgrade a project config document to the current schema version
+pub fn migrate_project_config(document: &mut Value) -> Result<bool, String> {
+    run_migrations(document, PROJECT_MIGRATIONS, PROJECT_SCHEMA_VERSION, "Project config")
+}
+
+// v1 -> v2: wrap the bare array of blocks into a versioned document and
+// convert free-form task efforts ("S", "2 hours") into the structured effort
+fn migrate_blocks_v1_to_v2(document: &mut Value) -> Result<(), String> {
+    let mut blocks = match document.take() {
+        Value::Array(blocks) => blocks,
+        Value::Object(mut object) => match object.remove("blocks") {
+            Some(Value::Array(blocks)) => blocks,
+            _ => Vec::new(),
+        },
+        other => return Err(format!("Unexpected blocks config document: {}", other)),
+    };
+
+    for block in blocks.iter_mut() {
+        if let Some(tasks) = block.get_mut("todo_list").and_then(|t| t.as_object_mut()) {
+            for task in tasks.values_mut() {
+                if let Some(effort) = task.get("estimated_effort").and_then(|e| e.as_str()).map(|e| e.to_string()) {
+                    task["estimated_effort"] = json!(Effort::parse(&effort).unwrap_or(None));
+                }
+            }
+        }
+    }
+
+    *document = json!({ "blocks": blocks });
+    Ok(())
+}
+
+// v1 -> v2: configs written before task workflows were configurable get the default workflow
+fn migrate_project_v1_to_v2(document: &mut Value) -> Result<(), String> {
+    let object = document.as_object_mut()
+        .ok_or_else(|| "Unexpected project config document".to_string())?;
+    if object.get("task_workflow").map_or(true, |w| w.is_null()) {
+        let workflow = serde_json::to_value(crate::project_config::TaskWorkflow::default())
+            .map_err(|e| format!("Failed to serialize default workflow: {}", e))?;
+        object.insert("task_workflow".to_string(), workflow);
+    }
+    Ok(())
+}
+
+// v2 -> v3: prompts used to be stored even when unchanged; unset the copies of the defaults (also
+// those from before the {{input}} placeholder) so the project follows the compiled-in prompts
+fn migrate_project_v2_to_v3(document: &mut Value) -> Result<(), String> {
+    let object = document.as_object_mut()
+        .ok_or_else(|| "Unexpected project config document".to_string())?;
+    for (key, default) in crate::project_config::PROMPT_DEFAULTS {
+        let unchanged = object.get(*key)
+            .and_then(|v| v.as_str())
+            .is_some_and(|v| v.trim().is_empty() || v == *default || v == default.replace("{{input}}", "{}"));
+        if unchanged {
+            object.insert(key.to_string(), Value::Null);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_migrate_legacy_blocks_array() {
+        let mut document = json!([
+            { "name": "A", "todo_list": { "t1": { "estimated_effort": "M" } } }
+        ]);
+
+        assert!(migrate_blocks(&mut document).unwrap());
+        assert_eq!(document["schema_version"], json!(BLOCKS_SCHEMA_VERSION));
+        assert_eq!(document["blocks"][0]["todo_list"]["t1"]["estimated_effort"]["size"], json!("medium"));
+
+        // Current documents are left untouched
+        assert!(!migrate_blocks(&mut document).unwrap());
+    }
+
+    #[test]
+    fn test_migrate_unchanged_prompts_to_defaults() {
+        use crate::project_config::DEFAULT_AUTO_COMPLETE_SYSTEM_PROMPT;
+
+        let mut document = json!({
+            "schema_version": 2,
+            "auto_complete_system_prompt": DEFAULT_AUTO_COMPLETE_SYSTEM_PROMPT,
+            "generate_tasks_system_prompt": "You write tiny tasks."
+        });
+        assert!(migrate_project_config(&mut document).unwrap());
+        assert_eq!(document["auto_complete_system_prompt"], Value::Null);
+        assert_eq!(document["generate_tasks_system_prompt"], json!("You write tiny tasks."));
+    }
+
+    #[test]
+    fn test_rejects_newer_schema() {
+        let mut document = json!({ "schema_version": PROJECT_SCHEMA_VERSION + 1 });
+        assert!(migrate_project_config(&mut document).is_err());
+    }
+}
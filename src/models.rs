@@ -1,8 +1,127 @@
 use crate::llm_handler::BlockConnection;
+use crate::project_config::TaskWorkflow;
 use rand::{distributions::Alphanumeric, Rng};
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 use std::collections::HashMap;
 
+// T-shirt size of a task's effort
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum EffortSize {
+    Small,
+    Medium,
+    Large,
+}
+
+impl EffortSize {
+    // Size matching an estimate in hours: small (up to 3h), medium (up to 6h), large (above)
+    pub fn from_hours(hours: f32) -> Self {
+        if hours <= 3.0 {
+            EffortSize::Small
+        } else if hours <= 6.0 {
+            EffortSize::Medium
+        } else {
+            EffortSize::Large
+        }
+    }
+
+    // Typical number of hours for the size, used when no explicit estimate is given
+    pub fn default_hours(&self) -> f32 {
+        match self {
+            EffortSize::Small => 2.0,
+            EffortSize::Medium => 4.5,
+            EffortSize::Large => 7.0,
+        }
+    }
+}
+
+// Normalized task effort: a size and an optional estimate in hours
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Effort {
+    pub size: EffortSize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hours: Option<f32>,
+}
+
+impl Effort {
+    // Parse a free-form effort ("S", "medium", "2 hours", "3-5h", "1 day"); empty input means no estimate
+    pub fn parse(value: &str) -> Result<Option<Effort>, String> {
+        let value = value.trim().to_lowercase();
+        if value.is_empty() {
+            return Ok(None);
+        }
+
+        let size = match value.as_str() {
+            "s" | "xs" | "small" | "simple" | "low" => Some(EffortSize::Small),
+            "m" | "medium" | "moderate" => Some(EffortSize::Medium),
+            "l" | "xl" | "large" | "complex" | "high" => Some(EffortSize::Large),
+            _ => None,
+        };
+        if let Some(size) = size {
+            return Ok(Some(Effort { size, hours: None }));
+        }
+
+        // Numeric estimates, optionally a range, in hours (default) or days
+        let numbers: Vec<f32> = value
+            .split(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .filter_map(|n| n.parse::<f32>().ok())
+            .collect();
+        if numbers.is_empty() || numbers.len() > 2 {
+            return Err(format!("Unrecognized effort '{}'", value));
+        }
+
+        let mut hours = numbers.iter().sum::<f32>() / numbers.len() as f32;
+        if value.contains("day") || value.ends_with('d') {
+            hours *= 8.0;
+        }
+
+        Ok(Some(Effort::from_hours(hours)))
+    }
+
+    pub fn from_hours(hours: f32) -> Self {
+        Effort {
+            size: EffortSize::from_hours(hours),
+            hours: Some(hours),
+        }
+    }
+
+    // Estimated hours: the explicit estimate, or the typical hours of the size
+    pub fn estimated_hours(&self) -> f32 {
+        self.hours.unwrap_or_else(|| self.size.default_hours())
+    }
+
+    // Story points on the Fibonacci scale used by issue trackers
+    pub fn story_points(&self) -> u32 {
+        match self.estimated_hours() {
+            h if h <= 2.0 => 1,
+            h if h <= 4.0 => 2,
+            h if h <= 6.0 => 3,
+            h if h <= 8.0 => 5,
+            _ => 8,
+        }
+    }
+}
+
+// Deserialize an effort from either the structured form or a legacy free-form string.
+// Unrecognized legacy values are dropped rather than failing the whole config.
+fn deserialize_effort<'de, D>(deserializer: D) -> Result<Option<Effort>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum RawEffort {
+        Structured(Effort),
+        Text(String),
+    }
+
+    Ok(match Option::<RawEffort>::deserialize(deserializer)? {
+        Some(RawEffort::Structured(effort)) => Some(effort),
+        Some(RawEffort::Text(text)) => Effort::parse(&text).unwrap_or(None),
+        None => None,
+    })
+}
+
 // Define the structure for a task
 
 // Define the structure for task response from LLM
@@ -13,13 +132,51 @@ pub struct Task {
     pub description: String,
     pub acceptance_criteria: Vec<String>,
     pub dependencies: Vec<String>,
-    pub estimated_effort: String,
+    #[serde(default, deserialize_with = "deserialize_effort")]
+    pub estimated_effort: Option<Effort>,
     pub files_affected: Vec<String>,
     pub function_signatures: Vec<String>,
     pub testing_requirements: Vec<String>,
     pub log: String,
     pub commit_id: String,
+    // Commits created while executing this task, oldest first; commit_id is the last one
+    #[serde(default)]
+    pub commit_ids: Vec<String>,
     pub status: String,
+    // Files changed by the commits created while executing this task
+    #[serde(default)]
+    pub files_modified: Vec<String>,
+    // Execution timeout overriding the project's task limits
+    #[serde(default)]
+    pub timeout_minutes: Option<u64>,
+    // Pull request opened for the branch of this task
+    #[serde(default)]
+    pub pull_request_url: Option<String>,
+    // GitHub issue tracking this task, with the labels and assignees synced with it
+    #[serde(default)]
+    pub github_issue_number: Option<u64>,
+    #[serde(default)]
+    pub labels: Vec<String>,
+    #[serde(default)]
+    pub assignees: Vec<String>,
+    // Latest CI pipeline status of the branch or commits of this task
+    #[serde(default)]
+    pub ci_status: Option<CiStatus>,
+}
+
+// Outcome of a CI pipeline: "pending", "success" or "failure", where it ran and what it tested
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CiStatus {
+    pub state: String,
+    // "github_actions", or the name sent to the CI webhook
+    pub source: String,
+    #[serde(default)]
+    pub commit: Option<String>,
+    #[serde(default)]
+    pub branch: Option<String>,
+    #[serde(default)]
+    pub url: Option<String>,
+    pub updated_at: String,
 }
 
 impl Task {
@@ -35,15 +192,30 @@ impl Task {
             description,
             acceptance_criteria: Vec::new(),
             dependencies: Vec::new(),
-            estimated_effort: "".to_string(),
+            estimated_effort: None,
             files_affected: Vec::new(),
             function_signatures: Vec::new(),
             testing_requirements: Vec::new(),
             log: String::new(),
             commit_id: "".to_string(),
-            status: "".to_string()
+            commit_ids: Vec::new(),
+            status: "".to_string(),
+            files_modified: Vec::new(),
+            timeout_minutes: None,
+            pull_request_url: None,
+            github_issue_number: None,
+            labels: Vec::new(),
+            assignees: Vec::new(),
+            ci_status: None,
+        }
+    }
 
+    // Record the commits of an execution, replacing those of a previous one
+    pub fn set_commits(&mut self, commits: Vec<String>) {
+        if let Some(last) = commits.last() {
+            self.commit_id = last.clone();
         }
+        self.commit_ids = commits;
     }
 
     /// Converts the Task attributes into a markdown-formatted prompt for LLM execution
@@ -135,6 +307,239 @@ impl Task {
     }
 }
 
+// Define the structure for an epic: a group of blocks forming a feature area
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Epic {
+    pub epic_id: String,
+    pub name: String,
+    pub description: String,
+}
+
+impl Epic {
+    pub fn new(name: String, description: String) -> Self {
+        // Generate a random 6-character alphanumeric ID
+        let epic_id: String = rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(6)
+            .map(char::from)
+            .collect();
+
+        Self {
+            epic_id,
+            name,
+            description,
+        }
+    }
+}
+
+// Entry of the code ownership map: a task (and its block) that modified a file
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FileOwner {
+    pub block_id: String,
+    pub block_name: String,
+    pub task_id: String,
+    pub task_name: String,
+    pub commit_id: String,
+}
+
+// Build the ownership map (file -> tasks that touched it) from a list of blocks
+pub fn build_ownership_map(blocks: &[Block]) -> std::collections::BTreeMap<String, Vec<FileOwner>> {
+    let mut map: std::collections::BTreeMap<String, Vec<FileOwner>> = std::collections::BTreeMap::new();
+
+    for block in blocks {
+        for task in block.todo_list.values() {
+            for file in &task.files_modified {
+                map.entry(file.clone()).or_insert_with(Vec::new).push(FileOwner {
+                    block_id: block.block_id.clone(),
+                    block_name: block.name.clone(),
+                    task_id: task.task_id.clone(),
+                    task_name: task.task_name.clone(),
+                    commit_id: task.commit_id.clone(),
+                });
+            }
+        }
+    }
+
+    map
+}
+
+// Section of a markdown specification, identified by its heading and line range (1-based, inclusive)
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct SpecSection {
+    pub heading: String,
+    #[serde(default)]
+    pub level: usize,
+    #[serde(default)]
+    pub line_start: usize,
+    #[serde(default)]
+    pub line_end: usize,
+}
+
+// Split a markdown specification into its heading sections.
+// Each section runs until the next heading of the same or a higher level.
+pub fn parse_spec_sections(markdown: &str) -> Vec<SpecSection> {
+    let lines: Vec<&str> = markdown.lines().collect();
+    let mut headings = Vec::new();
+    let mut in_code_block = false;
+
+    for (index, line) in lines.iter().enumerate() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") {
+            in_code_block = !in_code_block;
+            continue;
+        }
+        if in_code_block {
+            continue;
+        }
+
+        let level = trimmed.chars().take_while(|c| *c == '#').count();
+        if level == 0 || level > 6 || !trimmed[level..].starts_with(' ') {
+            continue;
+        }
+
+        let heading = trimmed[level..].trim().trim_end_matches('#').trim().to_string();
+        if !heading.is_empty() {
+            headings.push((index, level, heading));
+        }
+    }
+
+    headings.iter().enumerate().map(|(i, (index, level, heading))| {
+        let line_end = headings[i + 1..].iter()
+            .find(|(_, next_level, _)| next_level <= level)
+            .map(|(next_index, _, _)| *next_index)
+            .unwrap_or(lines.len());
+
+        SpecSection {
+            heading: heading.clone(),
+            level: *level,
+            line_start: index + 1,
+            line_end,
+        }
+    }).collect()
+}
+
+// Resolve section headings (as reported by the LLM) against the parsed specification sections.
+// Headings that can't be found in the specification are kept without a line range.
+pub fn resolve_spec_sections(sections: &[SpecSection], headings: &[String]) -> Vec<SpecSection> {
+    headings.iter()
+        .map(|h| h.trim().trim_start_matches('#').trim())
+        .filter(|h| !h.is_empty())
+        .map(|heading| {
+            sections.iter()
+                .find(|s| s.heading.eq_ignore_ascii_case(heading))
+                .cloned()
+                .unwrap_or_else(|| SpecSection {
+                    heading: heading.to_string(),
+                    level: 0,
+                    line_start: 0,
+                    line_end: 0,
+                })
+        })
+        .collect()
+}
+
+// Generate a markdown specification from the blocks, grouped by epic, including the task statuses.
+// Blocks are level 2 sections, or level 3 under their epic heading when the project has epics.
+pub fn blocks_to_spec_markdown(title: &str, description: &str, blocks: &[Block], epics: &[Epic], workflow: &TaskWorkflow) -> String {
+    let mut spec = format!("# {}\n\n", title);
+    if !description.trim().is_empty() {
+        spec.push_str(&format!("{}\n\n", description.trim()));
+    }
+
+    if epics.is_empty() {
+        for block in blocks {
+            push_block_spec(&mut spec, block, 2, workflow);
+        }
+        return spec;
+    }
+
+    for epic in epics {
+        let epic_blocks: Vec<&Block> = blocks.iter().filter(|b| b.epic_id.as_deref() == Some(epic.epic_id.as_str())).collect();
+        spec.push_str(&format!("## {}\n\n", epic.name));
+        if !epic.description.trim().is_empty() {
+            spec.push_str(&format!("{}\n\n", epic.description.trim()));
+        }
+        for block in epic_blocks {
+            push_block_spec(&mut spec, block, 3, workflow);
+        }
+    }
+
+    let ungrouped: Vec<&Block> = blocks.iter()
+        .filter(|b| !epics.iter().any(|e| b.epic_id.as_deref() == Some(e.epic_id.as_str())))
+        .collect();
+    if !ungrouped.is_empty() {
+        spec.push_str("## Other Blocks\n\n");
+        for block in ungrouped {
+            push_block_spec(&mut spec, block, 3, workflow);
+        }
+    }
+
+    spec
+}
+
+// Append the section of a block to a markdown specification
+fn push_block_spec(spec: &mut String, block: &Block, level: usize, workflow: &TaskWorkflow) {
+    let heading = "#".repeat(level);
+    spec.push_str(&format!("{} {}\n\n", heading, block.name));
+    if let Some(status) = &block.status {
+        spec.push_str(&format!("**Status**: {}\n\n", status));
+    }
+    if !block.description.trim().is_empty() {
+        spec.push_str(&format!("{}\n\n", block.description.trim()));
+    }
+    if let Some(tech_hints) = &block.tech_hints {
+        // Nest the technology section under the block heading
+        spec.push_str(&tech_hints.to_prompt_section().replacen("## ", &format!("{}# ", heading), 1));
+    }
+
+    for (label, connections) in [("Inputs", &block.inputs), ("Outputs", &block.outputs)] {
+        let connections: Vec<&BlockConnection> = connections.iter().filter(|c| !c.name.trim().is_empty()).collect();
+        if connections.is_empty() {
+            continue;
+        }
+        spec.push_str(&format!("**{}**\n", label));
+        for connection in connections {
+            let mut line = format!("- {}", connection.name);
+            if !connection.ctype.is_empty() {
+                line.push_str(&format!(" ({})", connection.ctype));
+            }
+            if !connection.description.is_empty() {
+                line.push_str(&format!(": {}", connection.description));
+            }
+            spec.push_str(&format!("{}\n", line));
+        }
+        spec.push('\n');
+    }
+
+    let dependencies: Vec<&str> = block.connections.input_connections.iter()
+        .map(|c| c.from_module.as_str())
+        .filter(|m| !m.is_empty())
+        .collect();
+    if !dependencies.is_empty() {
+        spec.push_str(&format!("**Depends on**: {}\n\n", dependencies.join(", ")));
+    }
+
+    if block.todo_list.is_empty() {
+        return;
+    }
+    spec.push_str(&format!("{}# Tasks\n\n", heading));
+    let mut tasks: Vec<&Task> = block.todo_list.values().collect();
+    tasks.sort_by(|a, b| a.task_name.cmp(&b.task_name).then_with(|| a.task_id.cmp(&b.task_id)));
+    for task in tasks {
+        let checkbox = if workflow.is_completed(&task.status) { "x" } else { " " };
+        let name = if task.task_name.is_empty() { &task.description } else { &task.task_name };
+        spec.push_str(&format!("- [{}] **{}** `{}`", checkbox, name, task.status));
+        if !task.task_name.is_empty() && !task.description.is_empty() {
+            spec.push_str(&format!(": {}", task.description));
+        }
+        spec.push('\n');
+        for criterion in &task.acceptance_criteria {
+            spec.push_str(&format!("  - {}\n", criterion));
+        }
+    }
+    spec.push('\n');
+}
+
 // Define the structure for module connections
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct InputConnection {
@@ -173,6 +578,123 @@ pub struct Connections {
     pub output_connections: Vec<OutputConnection>,
 }
 
+// Technology hints for a block, used to specialize generated tasks and execution prompts
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct TechHints {
+    #[serde(default)]
+    pub language: Option<String>,
+    #[serde(default)]
+    pub framework: Option<String>,
+    // Directory of the code base the block's code lives in
+    #[serde(default)]
+    pub directory: Option<String>,
+}
+
+impl TechHints {
+    // Markdown section describing the hints (empty when no hint is set)
+    pub fn to_prompt_section(&self) -> String {
+        let hints: Vec<String> = [
+            ("Language", &self.language),
+            ("Framework", &self.framework),
+            ("Directory", &self.directory),
+        ].iter()
+            .filter_map(|(label, value)| value.as_deref()
+                .filter(|v| !v.trim().is_empty())
+                .map(|v| format!("- {}: {}", label, v.trim())))
+            .collect();
+
+        if hints.is_empty() {
+            return String::new();
+        }
+        format!("## Technology\n{}\n\n", hints.join("\n"))
+    }
+}
+
+// Status of a block whose completion criteria hold
+pub const BLOCK_STATUS_COMPLETED: &str = "[COMPLETED]";
+
+// Conditions under which a block is automatically marked completed
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct CompletionCriteria {
+    // Every task of the block is completed
+    pub all_tasks_done: bool,
+    // Command that must exit successfully in the project directory (e.g. "cargo test")
+    pub test_command: Option<String>,
+    // Command printing the coverage, the last percentage in its output is compared to min_coverage
+    pub coverage_command: Option<String>,
+    pub min_coverage: Option<f32>,
+    // Actions run once the block is completed
+    pub create_pull_request: bool,
+    pub generate_report: bool,
+}
+
+impl Default for CompletionCriteria {
+    fn default() -> Self {
+        Self {
+            all_tasks_done: true,
+            test_command: None,
+            coverage_command: None,
+            min_coverage: None,
+            create_pull_request: false,
+            generate_report: false,
+        }
+    }
+}
+
+// Environment variable injected into task executions. Secrets are referenced instead of stored:
+// `from_env` reads a variable of the Forge server environment, `from_file` the trimmed content of a file.
+// Only the project's variables may reference secrets: blocks can be edited by anyone with write access.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct TaskEnvVar {
+    pub name: String,
+    #[serde(default)]
+    pub value: Option<String>,
+    #[serde(default)]
+    pub from_env: Option<String>,
+    #[serde(default)]
+    pub from_file: Option<String>,
+}
+
+impl TaskEnvVar {
+    // Whether the value comes from a secret reference, which must not be logged
+    pub fn is_secret(&self) -> bool {
+        self.from_env.is_some() || self.from_file.is_some()
+    }
+
+    pub fn resolve(&self) -> Result<String, String> {
+        if let Some(var) = &self.from_env {
+            return std::env::var(var)
+                .map_err(|_| format!("Environment variable {} references {}, which is not set", self.name, var));
+        }
+        if let Some(file) = &self.from_file {
+            return std::fs::read_to_string(file)
+                .map(|content| content.trim().to_string())
+                .map_err(|e| format!("Environment variable {} references file {}: {}", self.name, file, e));
+        }
+        Ok(self.value.clone().unwrap_or_default())
+    }
+}
+
+// Resolve the environment of a task execution; block variables override project variables
+pub fn resolve_task_env(project_env: &[TaskEnvVar], block_env: &[TaskEnvVar]) -> Result<Vec<(String, String)>, String> {
+    if let Some(var) = block_env.iter().find(|var| var.is_secret()) {
+        return Err(format!("Environment variable {} of the block references a secret, which only the project's task_env can do", var.name));
+    }
+    let mut env: Vec<(String, String)> = Vec::new();
+    for var in project_env.iter().chain(block_env) {
+        if var.name.trim().is_empty() {
+            return Err("Environment variable name cannot be empty".to_string());
+        }
+        let value = var.resolve()?;
+        match env.iter_mut().find(|(name, _)| *name == var.name) {
+            Some(existing) => existing.1 = value,
+            None => env.push((var.name.clone(), value)),
+        }
+    }
+    Ok(env)
+}
+
 // Define the structure for a software module
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Block {
@@ -183,6 +705,24 @@ pub struct Block {
     pub outputs: Vec<BlockConnection>,
     pub connections: Connections,
     pub todo_list: HashMap<String,Task>,
+    // Epic (feature area) this block belongs to
+    #[serde(default)]
+    pub epic_id: Option<String>,
+    // Specification sections the block was generated from
+    #[serde(default)]
+    pub spec_sources: Vec<SpecSection>,
+    // Language/framework/directory the block's tasks should target
+    #[serde(default)]
+    pub tech_hints: Option<TechHints>,
+    // Criteria evaluated in the background to auto-close the block
+    #[serde(default)]
+    pub completion_criteria: Option<CompletionCriteria>,
+    // Block status, None while the block is open
+    #[serde(default)]
+    pub status: Option<String>,
+    // Environment variables injected into the executions of the block's tasks
+    #[serde(default)]
+    pub env: Vec<TaskEnvVar>,
 }
 
 impl Block {
@@ -205,6 +745,12 @@ impl Block {
                 output_connections: Vec::new(),
             },
             todo_list: HashMap::new(),
+            epic_id: None,
+            spec_sources: Vec::new(),
+            tech_hints: None,
+            completion_criteria: None,
+            status: None,
+            env: Vec::new(),
         }
     }
     pub fn update_task(mut self, task: Task) {
@@ -242,6 +788,12 @@ pub fn get_blocks() -> Vec<Block> {
                 map.insert(task2.task_id.clone(), task2);
                 map
             },
+            epic_id: None,
+            spec_sources: Vec::new(),
+            tech_hints: None,
+            completion_criteria: None,
+            status: None,
+            env: Vec::new(),
         },
         Block {
             block_id: "def456".to_string(), // Sample block_id
@@ -272,6 +824,12 @@ pub fn get_blocks() -> Vec<Block> {
                 map.insert(task2.task_id.clone(), task2);
                 map
             },
+            epic_id: None,
+            spec_sources: Vec::new(),
+            tech_hints: None,
+            completion_criteria: None,
+            status: None,
+            env: Vec::new(),
         },
         Block {
             block_id: "ghi789".to_string(), // Sample block_id
@@ -296,6 +854,12 @@ pub fn get_blocks() -> Vec<Block> {
                 map.insert(task2.task_id.clone(), task2);
                 map
             },
+            epic_id: None,
+            spec_sources: Vec::new(),
+            tech_hints: None,
+            completion_criteria: None,
+            status: None,
+            env: Vec::new(),
         },
     ]
 }
@@ -305,6 +869,96 @@ pub fn get_blocks() -> Vec<Block> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_effort_parsing_and_legacy_migration() {
+        assert_eq!(Effort::parse("").unwrap(), None);
+        assert_eq!(Effort::parse("S").unwrap().unwrap().size, EffortSize::Small);
+        assert_eq!(Effort::parse("medium").unwrap().unwrap().size, EffortSize::Medium);
+
+        let effort = Effort::parse("2 hours").unwrap().unwrap();
+        assert_eq!(effort, Effort { size: EffortSize::Small, hours: Some(2.0) });
+        assert_eq!(effort.story_points(), 1);
+        assert_eq!(Effort::parse("1 day").unwrap().unwrap().size, EffortSize::Large);
+        assert_eq!(Effort::parse("4-6 hours").unwrap().unwrap().hours, Some(5.0));
+        assert!(Effort::parse("soon").is_err());
+
+        let mut json = serde_json::to_value(Task::new("legacy".to_string())).unwrap();
+        json["estimated_effort"] = serde_json::json!("L");
+        let task: Task = serde_json::from_value(json).unwrap();
+        assert_eq!(task.estimated_effort, Some(Effort { size: EffortSize::Large, hours: None }));
+    }
+
+    #[test]
+    fn test_parse_spec_sections() {
+        let markdown = "# Spec\nintro\n## Auth\nlogin\n```\n# not a heading\n```\n## Storage\nfiles\n# Appendix\n";
+        let sections = parse_spec_sections(markdown);
+
+        let headings: Vec<&str> = sections.iter().map(|s| s.heading.as_str()).collect();
+        assert_eq!(headings, vec!["Spec", "Auth", "Storage", "Appendix"]);
+        assert_eq!((sections[0].line_start, sections[0].line_end), (1, 9));
+        assert_eq!((sections[1].line_start, sections[1].line_end), (3, 7));
+        assert_eq!((sections[2].line_start, sections[2].line_end), (8, 9));
+
+        let resolved = resolve_spec_sections(&sections, &["## auth".to_string(), "Missing".to_string()]);
+        assert_eq!(resolved[0], sections[1]);
+        assert_eq!(resolved[1].line_start, 0);
+    }
+
+    #[test]
+    fn test_resolve_task_env() {
+        let var = |name: &str, value: &str| TaskEnvVar { name: name.to_string(), value: Some(value.to_string()), from_env: None, from_file: None };
+        let project_env = vec![var("FEATURE_X", "off"), var("LOG_LEVEL", "info")];
+        let block_env = vec![var("FEATURE_X", "on")];
+        assert_eq!(resolve_task_env(&project_env, &block_env).unwrap(), vec![
+            ("FEATURE_X".to_string(), "on".to_string()),
+            ("LOG_LEVEL".to_string(), "info".to_string()),
+        ]);
+
+        let secret = TaskEnvVar { name: "API_KEY".to_string(), value: None, from_env: Some("FORGE_TEST_UNSET_SECRET".to_string()), from_file: None };
+        assert!(secret.is_secret());
+        assert!(resolve_task_env(&[secret.clone()], &[]).unwrap_err().contains("FORGE_TEST_UNSET_SECRET"));
+
+        // Blocks can't read the server's environment or files
+        let block_secret = TaskEnvVar { from_env: Some("PATH".to_string()), ..secret };
+        assert!(resolve_task_env(&[], &[block_secret]).unwrap_err().contains("only the project's task_env"));
+        let block_file = TaskEnvVar { name: "KEY".to_string(), value: None, from_env: None, from_file: Some("/etc/hostname".to_string()) };
+        assert!(resolve_task_env(&project_env, &[block_file]).is_err());
+    }
+
+    #[test]
+    fn test_tech_hints_prompt_section() {
+        assert_eq!(TechHints::default().to_prompt_section(), "");
+
+        let hints = TechHints {
+            language: Some("Rust".to_string()),
+            framework: Some(" ".to_string()),
+            directory: Some("services/api".to_string()),
+        };
+        assert_eq!(hints.to_prompt_section(), "## Technology\n- Language: Rust\n- Directory: services/api\n\n");
+    }
+
+    #[test]
+    fn test_blocks_to_spec_markdown() {
+        let epic = Epic::new("Accounts".to_string(), "User accounts".to_string());
+        let mut auth = Block::new("Auth".to_string(), "Authentication".to_string(), Vec::new(), Vec::new());
+        auth.epic_id = Some(epic.epic_id.clone());
+        let mut task = Task::new("Add the login form".to_string());
+        task.task_name = "Login".to_string();
+        task.status = "[COMPLETED]".to_string();
+        task.acceptance_criteria = vec!["Valid users log in".to_string()];
+        auth.todo_list.insert(task.task_id.clone(), task);
+        let billing = Block::new("Billing".to_string(), "Invoices".to_string(), Vec::new(), Vec::new());
+
+        let spec = blocks_to_spec_markdown("Shop", "", &[auth, billing], &[epic], &TaskWorkflow::default());
+        assert!(spec.starts_with("# Shop\n\n## Accounts\n\nUser accounts\n\n### Auth\n\nAuthentication\n\n"));
+        assert!(spec.contains("#### Tasks\n\n- [x] **Login** `[COMPLETED]`: Add the login form\n  - Valid users log in\n"));
+        assert!(spec.contains("## Other Blocks\n\n### Billing\n"));
+
+        // The generated spec can be parsed back into sections
+        let headings: Vec<String> = parse_spec_sections(&spec).into_iter().map(|s| s.heading).collect();
+        assert_eq!(headings, vec!["Shop", "Accounts", "Auth", "Tasks", "Other Blocks", "Billing"]);
+    }
+
     #[test]
     fn test_input_connection_id_generation() {
         let conn = InputConnection::new("TestModule".to_string(), "TestOutput".to_string());
@@ -379,4 +1033,34 @@ mod tests {
         assert!(markdown.contains("- rand = \"0.8\""));
         assert!(markdown.contains("1. Deck Creation Test: Verify new() creates exactly 52 unique cards"));
     }
+
+    #[test]
+    fn test_build_ownership_map() {
+        let mut block = Block::new("Auth".to_string(), "Authentication".to_string(), vec![], vec![]);
+        let mut task1 = Task::new("Add login".to_string());
+        task1.files_modified = vec!["src/auth.rs".to_string(), "src/main.rs".to_string()];
+        let mut task2 = Task::new("Add logout".to_string());
+        task2.files_modified = vec!["src/auth.rs".to_string()];
+        block.todo_list.insert(task1.task_id.clone(), task1);
+        block.todo_list.insert(task2.task_id.clone(), task2);
+
+        let map = build_ownership_map(&[block]);
+
+        assert_eq!(map.len(), 2);
+        assert_eq!(map["src/auth.rs"].len(), 2);
+        assert_eq!(map["src/main.rs"].len(), 1);
+    }
+
+    #[test]
+    fn test_set_commits() {
+        let mut task = Task::new("Add login".to_string());
+        task.commit_id = "old".to_string();
+        task.set_commits(vec!["a1".to_string(), "b2".to_string()]);
+        assert_eq!(task.commit_id, "b2");
+        assert_eq!(task.commit_ids, vec!["a1", "b2"]);
+
+        task.set_commits(Vec::new());
+        assert_eq!(task.commit_id, "b2");
+        assert!(task.commit_ids.is_empty());
+    }
 }
@@ -0,0 +1,357 @@
+use crate::project_config::{OidcConfig, UserRole};
+use crate::project_handlers::ProjectAppState;
+use crate::request_user;
+use crate::secrets;
+use actix_web::cookie::{time, Cookie, SameSite};
+use actix_web::http::header;
+use actix_web::{web, HttpRequest, HttpResponse, Responder};
+use jsonwebtoken::jwk::JwkSet;
+use jsonwebtoken::{decode, decode_header, Algorithm, DecodingKey, Validation};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+// Cookie holding the ID token of the signed-in user
+pub const TOKEN_COOKIE: &str = "forge_token";
+
+// Cookie holding the state of a sign-in until the provider redirects back
+const STATE_COOKIE: &str = "forge_oidc_state";
+
+const CALLBACK_PATH: &str = "/api/v1/auth/oidc/callback";
+
+// Keys of the provider are fetched again after this time, or sooner for a token signed with an
+// unknown key
+const JWKS_MAX_AGE: Duration = Duration::from_secs(60 * 60);
+const JWKS_MIN_REFRESH: Duration = Duration::from_secs(60);
+
+// Endpoints of the discovery document of the provider
+#[derive(Debug, Clone, Deserialize)]
+pub struct ProviderMetadata {
+    pub issuer: String,
+    pub authorization_endpoint: String,
+    pub token_endpoint: String,
+    pub jwks_uri: String,
+}
+
+struct CachedProvider {
+    issuer: String,
+    metadata: ProviderMetadata,
+    jwks: JwkSet,
+    fetched: Instant,
+}
+
+lazy_static::lazy_static! {
+    static ref PROVIDER: tokio::sync::Mutex<Option<CachedProvider>> = tokio::sync::Mutex::new(None);
+}
+
+// User signed in with the provider
+#[derive(Debug, Clone, Serialize)]
+pub struct AuthenticatedUser {
+    pub subject: String,
+    // Email, preferred user name or subject; recorded in the audit history
+    pub name: String,
+    pub role: UserRole,
+    // Expiry of the token in seconds since the epoch
+    pub expires_at: Option<i64>,
+}
+
+// ID tokens are JWTs, API keys have no dots
+pub fn looks_like_jwt(token: &str) -> bool {
+    token.split('.').count() == 3
+}
+
+async fn fetch_provider(issuer: &str) -> Result<CachedProvider, String> {
+    let client = reqwest::Client::new();
+    let discovery_url = format!("{}/.well-known/openid-configuration", issuer.trim_end_matches('/'));
+    let metadata: ProviderMetadata = client.get(&discovery_url).send().await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| format!("Failed to fetch {}: {}", discovery_url, e))?
+        .json().await
+        .map_err(|e| format!("Invalid discovery document at {}: {}", discovery_url, e))?;
+    let jwks: JwkSet = client.get(&metadata.jwks_uri).send().await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| format!("Failed to fetch {}: {}", metadata.jwks_uri, e))?
+        .json().await
+        .map_err(|e| format!("Invalid key set at {}: {}", metadata.jwks_uri, e))?;
+    Ok(CachedProvider { issuer: issuer.to_string(), metadata, jwks, fetched: Instant::now() })
+}
+
+// Metadata and keys of the provider, fetched again when stale or when `kid` isn't known
+async fn provider(issuer: &str, kid: Option<&str>) -> Result<(ProviderMetadata, JwkSet), String> {
+    let mut cached = PROVIDER.lock().await;
+    let stale = match cached.as_ref() {
+        Some(provider) if provider.issuer == issuer => {
+            let age = provider.fetched.elapsed();
+            let unknown_key = kid.is_some_and(|kid| provider.jwks.find(kid).is_none());
+            age > JWKS_MAX_AGE || (unknown_key && age > JWKS_MIN_REFRESH)
+        }
+        _ => true,
+    };
+    if stale {
+        *cached = Some(fetch_provider(issuer).await?);
+    }
+    let provider = cached.as_ref().expect("provider fetched above");
+    Ok((provider.metadata.clone(), provider.jwks.clone()))
+}
+
+// Value of a claim; dots reach into nested objects, e.g. realm_access.roles
+fn claim<'a>(claims: &'a HashMap<String, Value>, path: &str) -> Option<&'a Value> {
+    let mut parts = path.split('.');
+    let first = claims.get(parts.next()?)?;
+    parts.try_fold(first, |value, part| value.get(part))
+}
+
+// Highest role the claim values of the user map to, or the default role. Only the configured
+// mapping grants roles: a claim value named like a role (e.g. "admin") doesn't grant it by itself.
+pub fn user_role(config: &OidcConfig, claims: &HashMap<String, Value>) -> Option<UserRole> {
+    let values: Vec<&str> = match claim(claims, config.roles_claim.as_deref().unwrap_or("roles")) {
+        Some(Value::String(value)) => vec![value.as_str()],
+        Some(Value::Array(values)) => values.iter().filter_map(|v| v.as_str()).collect(),
+        _ => Vec::new(),
+    };
+    let mapping = config.role_mapping.clone().unwrap_or_default();
+    values.into_iter()
+        .filter_map(|value| mapping.get(value).copied())
+        .max()
+        .or(config.default_role)
+}
+
+// Validate an ID token issued by the provider and get the user it was issued to
+pub async fn validate_token(config: &OidcConfig, token: &str) -> Result<AuthenticatedUser, String> {
+    let header = decode_header(token).map_err(|e| format!("Invalid token: {}", e))?;
+    if matches!(header.alg, Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512) {
+        return Err("Tokens signed with a shared secret aren't accepted".to_string());
+    }
+    let (metadata, jwks) = provider(&config.issuer, header.kid.as_deref()).await?;
+    let jwk = match header.kid.as_deref() {
+        Some(kid) => jwks.find(kid),
+        None if jwks.keys.len() == 1 => jwks.keys.first(),
+        None => None,
+    }
+        .ok_or("The token was signed with an unknown key")?;
+    let key = DecodingKey::from_jwk(jwk).map_err(|e| format!("Invalid key of the provider: {}", e))?;
+
+    let mut validation = Validation::new(header.alg);
+    validation.set_issuer(&[&metadata.issuer]);
+    validation.set_audience(&[config.audience.as_deref().unwrap_or(&config.client_id)]);
+    let claims = decode::<HashMap<String, Value>>(token, &key, &validation)
+        .map_err(|e| format!("Invalid token: {}", e))?
+        .claims;
+
+    let text = |name: &str| claims.get(name).and_then(|v| v.as_str()).map(String::from);
+    let subject = text("sub").ok_or("The token has no subject")?;
+    let name = text("email").or_else(|| text("preferred_username")).unwrap_or_else(|| subject.clone());
+    let role = user_role(config, &claims).ok_or_else(|| format!("{} has no role in forge", name))?;
+    Ok(AuthenticatedUser { subject, name, role, expires_at: claims.get("exp").and_then(|v| v.as_i64()) })
+}
+
+fn oidc_config(data: &ProjectAppState) -> Result<OidcConfig, HttpResponse> {
+    data.project_manager.get_config().ok()
+        .and_then(|config| config.oidc)
+        .filter(|oidc| oidc.validate().is_ok())
+        .ok_or_else(|| HttpResponse::NotFound().body("OIDC sign-in isn't configured"))
+}
+
+fn redirect_url(config: &OidcConfig, req: &HttpRequest) -> String {
+    config.redirect_url.clone().filter(|url| !url.is_empty()).unwrap_or_else(|| {
+        let info = req.connection_info();
+        format!("{}://{}{}", info.scheme(), info.host(), CALLBACK_PATH)
+    })
+}
+
+fn cookie(req: &HttpRequest, name: &'static str, value: String, max_age: time::Duration) -> Cookie<'static> {
+    Cookie::build(name, value)
+        .path("/")
+        .http_only(true)
+        .secure(req.connection_info().scheme() == "https")
+        .same_site(SameSite::Lax)
+        .max_age(max_age)
+        .finish()
+}
+
+// API endpoint starting a sign-in; redirects to the provider
+pub async fn oidc_login_handler(req: HttpRequest, data: web::Data<ProjectAppState>) -> impl Responder {
+    let config = match oidc_config(&data) {
+        Ok(config) => config,
+        Err(response) => return response,
+    };
+    let (metadata, _) = match provider(&config.issuer, None).await {
+        Ok(provider) => provider,
+        Err(e) => return HttpResponse::BadGateway().body(e),
+    };
+    let state = hex::encode(rand::random::<[u8; 16]>());
+    let redirect_uri = redirect_url(&config, &req);
+    let url = match reqwest::Url::parse_with_params(&metadata.authorization_endpoint, &[
+        ("response_type", "code"),
+        ("client_id", config.client_id.as_str()),
+        ("redirect_uri", redirect_uri.as_str()),
+        ("scope", "openid profile email"),
+        ("state", state.as_str()),
+    ]) {
+        Ok(url) => url,
+        Err(e) => return HttpResponse::BadGateway().body(format!("Invalid authorization endpoint: {}", e)),
+    };
+    HttpResponse::Found()
+        .insert_header((header::LOCATION, url.to_string()))
+        .cookie(cookie(&req, STATE_COOKIE, state, time::Duration::minutes(10)))
+        .finish()
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OidcCallbackQuery {
+    pub code: Option<String>,
+    pub state: Option<String>,
+    pub error: Option<String>,
+    pub error_description: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    id_token: Option<String>,
+}
+
+// API endpoint the provider redirects to after a sign-in; exchanges the code for an ID token kept
+// in a cookie
+pub async fn oidc_callback_handler(req: HttpRequest, data: web::Data<ProjectAppState>, query: web::Query<OidcCallbackQuery>) -> impl Responder {
+    let config = match oidc_config(&data) {
+        Ok(config) => config,
+        Err(response) => return response,
+    };
+    if let Some(error) = &query.error {
+        return HttpResponse::Unauthorized().body(format!("Sign-in failed: {} {}", error, query.error_description.as_deref().unwrap_or("")));
+    }
+    let expected_state = req.cookie(STATE_COOKIE).map(|c| c.value().to_string());
+    if expected_state.is_none() || query.state != expected_state {
+        return HttpResponse::BadRequest().body("The sign-in state doesn't match, start the sign-in again");
+    }
+    let Some(code) = query.code.clone() else {
+        return HttpResponse::BadRequest().body("The provider returned no authorization code");
+    };
+
+    let client_secret = match config.client_secret.as_deref().map(secrets::resolve).transpose() {
+        Ok(secret) => secret,
+        Err(e) => return HttpResponse::InternalServerError().body(e),
+    };
+    let (metadata, _) = match provider(&config.issuer, None).await {
+        Ok(provider) => provider,
+        Err(e) => return HttpResponse::BadGateway().body(e),
+    };
+    let redirect_uri = redirect_url(&config, &req);
+    let mut form = vec![
+        ("grant_type", "authorization_code"),
+        ("code", code.as_str()),
+        ("redirect_uri", redirect_uri.as_str()),
+        ("client_id", config.client_id.as_str()),
+    ];
+    if let Some(secret) = client_secret.as_deref() {
+        form.push(("client_secret", secret));
+    }
+    let tokens: TokenResponse = match reqwest::Client::new().post(&metadata.token_endpoint).form(&form).send().await
+        .and_then(|r| r.error_for_status())
+    {
+        Ok(response) => match response.json().await {
+            Ok(tokens) => tokens,
+            Err(e) => return HttpResponse::BadGateway().body(format!("Invalid token response: {}", e)),
+        },
+        Err(e) => return HttpResponse::BadGateway().body(format!("Failed to exchange the authorization code: {}", e)),
+    };
+    let Some(id_token) = tokens.id_token else {
+        return HttpResponse::BadGateway().body("The provider returned no ID token");
+    };
+    let user = match validate_token(&config, &id_token).await {
+        Ok(user) => user,
+        Err(e) => return HttpResponse::Forbidden().body(e),
+    };
+
+    let lifetime = user.expires_at.map_or(60 * 60, |exp| exp - chrono::Utc::now().timestamp()).max(0);
+    HttpResponse::Found()
+        .insert_header((header::LOCATION, "/"))
+        .cookie(cookie(&req, TOKEN_COOKIE, id_token, time::Duration::seconds(lifetime)))
+        .cookie(cookie(&req, STATE_COOKIE, String::new(), time::Duration::ZERO))
+        .finish()
+}
+
+// API endpoint to sign out
+pub async fn logout_handler(req: HttpRequest) -> impl Responder {
+    HttpResponse::Ok()
+        .cookie(cookie(&req, TOKEN_COOKIE, String::new(), time::Duration::ZERO))
+        .body("Signed out")
+}
+
+// Authentication of the server and the user of the request
+#[derive(Debug, Serialize)]
+pub struct AuthStatus {
+    pub authentication_required: bool,
+    pub oidc: bool,
+    pub user: Option<String>,
+    pub role: Option<UserRole>,
+}
+
+// API endpoint telling the frontend how to authenticate; open to everyone
+pub async fn auth_status_handler(req: HttpRequest, data: web::Data<ProjectAppState>) -> impl Responder {
+    let config = data.project_manager.get_config().unwrap_or_default();
+    let oidc = config.oidc.as_ref().is_some_and(|oidc| oidc.validate().is_ok());
+    HttpResponse::Ok().json(AuthStatus {
+        authentication_required: config.requires_authentication(),
+        oidc,
+        user: request_user::current_user(),
+        role: req.extensions().get::<AuthenticatedUser>().map(|user| user.role),
+    })
+}
+
+// API endpoint to get the OIDC settings
+pub async fn get_oidc_config_handler(data: web::Data<ProjectAppState>) -> impl Responder {
+    let mut oidc = data.project_manager.get_config().ok().and_then(|config| config.oidc);
+    if let Some(oidc) = oidc.as_mut() {
+        if oidc.client_secret.as_deref().is_some_and(|secret| !secrets::is_reference(secret)) {
+            oidc.client_secret = Some(secrets::REDACTED.to_string());
+        }
+    }
+    HttpResponse::Ok().json(oidc)
+}
+
+// API endpoint to change the OIDC settings; null turns the sign-in off
+pub async fn update_oidc_config_handler(data: web::Data<ProjectAppState>, oidc: web::Json<Option<OidcConfig>>) -> impl Responder {
+    let mut oidc = oidc.into_inner();
+    if let Some(Err(e)) = oidc.as_ref().map(|oidc| oidc.validate()) {
+        return HttpResponse::BadRequest().body(e);
+    }
+    let mut config = match data.project_manager.get_config() {
+        Ok(config) => config,
+        Err(e) => return HttpResponse::InternalServerError().body(format!("Failed to get project config: {}", e)),
+    };
+    // The secret comes back redacted
+    if let Some(new) = oidc.as_mut().filter(|new| new.client_secret.as_deref() == Some(secrets::REDACTED)) {
+        new.client_secret = config.oidc.as_ref().and_then(|current| current.client_secret.clone());
+    }
+    config.oidc = oidc;
+    match data.project_manager.save_config(&config) {
+        Ok(_) => HttpResponse::Ok().body("OIDC settings saved successfully"),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Failed to save project config: {}", e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_user_role() {
+        let claims = |value: Value| HashMap::from([("realm_access".to_string(), serde_json::json!({ "roles": value }))]);
+        let mut config = OidcConfig {
+            roles_claim: Some("realm_access.roles".to_string()),
+            role_mapping: Some(BTreeMap::from([("forge-ops".to_string(), UserRole::Operator)])),
+            ..Default::default()
+        };
+        assert_eq!(user_role(&config, &claims(serde_json::json!(["viewer", "forge-ops"]))), Some(UserRole::Operator));
+        assert_eq!(user_role(&config, &claims(serde_json::json!("forge-ops"))), Some(UserRole::Operator));
+        // Role names aren't roles without a mapping
+        assert_eq!(user_role(&config, &claims(serde_json::json!(["admin"]))), None);
+        assert_eq!(user_role(&config, &claims(serde_json::json!(["staff"]))), None);
+        config.default_role = Some(UserRole::Viewer);
+        assert_eq!(user_role(&config, &HashMap::new()), Some(UserRole::Viewer));
+        assert!(looks_like_jwt("a.b.c") && !looks_like_jwt("forge_0123"));
+    }
+}
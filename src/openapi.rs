@@ -0,0 +1,269 @@
+use crate::project_handlers::ProjectAppState;
+use crate::routes;
+use actix_web::{web, HttpResponse, Responder};
+use serde_json::{json, Map, Value};
+
+// A route of the API as documented in the OpenAPI document
+pub struct ApiRoute {
+    pub method: &'static str,
+    pub path: &'static str,
+    pub tag: &'static str,
+    pub summary: &'static str,
+}
+
+const fn route(method: &'static str, path: &'static str, tag: &'static str, summary: &'static str) -> ApiRoute {
+    ApiRoute { method, path, tag, summary }
+}
+
+// Every route of the API modules in routes/; a test keeps the two in sync
+pub const API_ROUTES: &[ApiRoute] = &[
+    route("get", "/openapi.json", "Docs", "OpenAPI document of the API"),
+    route("get", "/docs", "Docs", "Swagger UI of the API, when enabled in the project settings"),
+    route("get", "/auth/status", "Auth", "Authentication of the server and the user of the request"),
+    route("post", "/auth/logout", "Auth", "Sign out of the OIDC sign-in"),
+    route("get", "/auth/oidc/login", "Auth", "Start an OIDC sign-in"),
+    route("get", "/auth/oidc/callback", "Auth", "Redirect target of the OIDC provider"),
+    route("get", "/auth/oidc", "Auth", "Get the OIDC settings"),
+    route("put", "/auth/oidc", "Auth", "Change the OIDC settings"),
+    route("get", "/auth/keys", "Auth", "List the API keys"),
+    route("post", "/auth/keys", "Auth", "Create an API key; the key is only returned once"),
+    route("delete", "/auth/keys/{name}", "Auth", "Revoke an API key"),
+    route("get", "/blocks", "Blocks", "List the blocks; offset, limit, sort, status, task_status, epic_id and tasks narrow the list"),
+    route("post", "/blocks", "Blocks", "Add a block"),
+    route("put", "/blocks", "Blocks", "Update a block"),
+    route("delete", "/blocks/{block_id}", "Blocks", "Delete a block"),
+    route("post", "/blocks/{block_id}/task", "Tasks", "Add a task to a block"),
+    route("delete", "/blocks/{block_id}/delete/{task_id}", "Tasks", "Remove a task from a block"),
+    route("put", "/blocks/{block_id}/tasks/{task_id}/status", "Tasks", "Change the status of a task"),
+    route("put", "/blocks/{block_id}/enhance", "Blocks", "Enhance the description of a block with an LLM"),
+    route("put", "/blocks/{block_id}/enhance/stream", "Blocks", "Enhance the description of a block, streaming the LLM response"),
+    route("put", "/blocks/{block_id}/generate-tasks", "Tasks", "Generate the tasks of a block with an LLM"),
+    route("post", "/blocks/auto-complete", "Blocks", "Complete a block description with an LLM"),
+    route("post", "/blocks/auto-complete/stream", "Blocks", "Complete a block description, streaming the LLM response"),
+    route("post", "/blocks/process-markdown", "Blocks", "Create blocks from a markdown document"),
+    route("post", "/blocks/process-spec", "Blocks", "Create blocks from a specification"),
+    route("get", "/blocks/export-spec", "Blocks", "Export the blocks as a specification"),
+    route("post", "/blocks/export-spec", "Blocks", "Write the specification of the blocks to a file"),
+    route("get", "/blocks/{blockId}/dependencies", "Blocks", "Dependencies of a block"),
+    route("get", "/blocks/{blockId}/sources", "Blocks", "Source files of a block"),
+    route("get", "/blocks/ownership", "Blocks", "Files owned by each block"),
+    route("get", "/blocks/changes", "Blocks", "Blocks changed since a cursor"),
+    route("post", "/blocks/{blockId}/tasks/import", "Tasks", "Import the tasks of a block from CSV"),
+    route("get", "/tasks/export", "Tasks", "Export the tasks as CSV"),
+    route("post", "/tasks/{execution_id}/cancel", "Executor", "Cancel a running task execution"),
+    route("get", "/blocks/history", "Blocks", "Undo and redo history of the blocks config"),
+    route("post", "/blocks/undo", "Blocks", "Revert the blocks config to the previous revision"),
+    route("post", "/blocks/redo", "Blocks", "Re-apply the last undone revision"),
+    route("put", "/blocks/{block_id}/epic", "Epics", "Assign a block to an epic"),
+    route("get", "/epics", "Epics", "List the epics"),
+    route("post", "/epics", "Epics", "Add an epic"),
+    route("put", "/epics", "Epics", "Update an epic"),
+    route("get", "/epics/dependencies", "Epics", "Dependencies between the epics"),
+    route("delete", "/epics/{epic_id}", "Epics", "Delete an epic"),
+    route("post", "/generate-sample", "Blocks", "Generate a sample blocks config"),
+    route("get", "/project", "Project", "Get the project settings"),
+    route("put", "/project", "Project", "Change the project settings"),
+    route("post", "/project/test-git-connection", "Project", "Test the connection to a git repository"),
+    route("get", "/project/check-config", "Project", "Validate the project settings"),
+    route("get", "/project/summary", "Project", "Summary of the project"),
+    route("post", "/project/open", "Projects", "Open a project directory"),
+    route("post", "/project/init", "Projects", "Initialize a project in a directory"),
+    route("get", "/projects", "Projects", "List the registered projects"),
+    route("post", "/projects", "Projects", "Register a project"),
+    route("post", "/projects/{project_id}/activate", "Projects", "Switch to a registered project"),
+    route("delete", "/projects/{project_id}", "Projects", "Unregister a project"),
+    route("get", "/secrets", "Secrets", "List the stored secrets, without their values"),
+    route("put", "/secrets/{name}", "Secrets", "Store a secret"),
+    route("delete", "/secrets/{name}", "Secrets", "Delete a secret"),
+    route("get", "/project/professions", "Professions", "List the professions"),
+    route("post", "/project/professions", "Professions", "Create a custom profession"),
+    route("put", "/project/professions/{profession_id}", "Professions", "Update a custom profession"),
+    route("delete", "/project/professions/{profession_id}", "Professions", "Delete a custom profession"),
+    route("get", "/project/professions/{profession_id}/prompts", "Professions", "Prompts of a profession"),
+    route("put", "/project/professions/{profession_id}/prompts/{prompt_key}", "Professions", "Edit a prompt of a profession"),
+    route("delete", "/project/professions/{profession_id}/prompts/{prompt_key}", "Professions", "Reset a prompt of a profession"),
+    route("post", "/project/professions/{profession_id}/preview", "Professions", "Preview a rendered prompt of a profession"),
+    route("get", "/project/prompt-packs/export", "Prompts", "Export professions as a prompt pack"),
+    route("post", "/project/prompt-packs/import", "Prompts", "Import a prompt pack"),
+    route("get", "/project/prompts", "Prompts", "Prompts of the project"),
+    route("put", "/project/prompts/{prompt_key}", "Prompts", "Override a prompt of the project"),
+    route("delete", "/project/prompts/{prompt_key}", "Prompts", "Reset a prompt of the project"),
+    route("get", "/project/prompt-history", "Prompts", "Versions of a prompt"),
+    route("get", "/project/prompt-history/diff", "Prompts", "Diff between two versions of a prompt"),
+    route("post", "/project/prompt-history/rollback", "Prompts", "Roll a prompt back to a version"),
+    route("get", "/usage", "Usage", "Token usage and costs of the LLM calls"),
+    route("get", "/llm-audit", "Usage", "Search the audit log of the LLM calls"),
+    route("get", "/llm-audit/{entry_id}", "Usage", "Get an entry of the LLM audit log"),
+    route("post", "/git/branch", "Git", "Create a branch"),
+    route("post", "/git/commit", "Git", "Commit the changes"),
+    route("post", "/git/merge", "Git", "Merge a branch"),
+    route("post", "/git/push", "Git", "Push a branch"),
+    route("post", "/git/pull-request", "Git", "Push the branch of a task and open a GitHub pull request"),
+    route("post", "/git/pull", "Git", "Pull the main branch"),
+    route("post", "/git/build", "Git", "Build the project"),
+    route("post", "/git/execute-task", "Executor", "Execute a task"),
+    route("post", "/git/preflight", "Executor", "Check that a task can be executed"),
+    route("post", "/git/task-diff", "Git", "Diff of the changes of a task"),
+    route("get", "/git/branches", "Git", "List the branches"),
+    route("get", "/backups", "Backups", "List the backups"),
+    route("post", "/backups", "Backups", "Back up the project data"),
+    route("post", "/backups/{backup_id}/restore", "Backups", "Restore a backup"),
+    route("get", "/schedules", "Schedules", "List the schedules"),
+    route("post", "/schedules", "Schedules", "Create a schedule"),
+    route("put", "/schedules/{schedule_id}", "Schedules", "Update a schedule"),
+    route("delete", "/schedules/{schedule_id}", "Schedules", "Delete a schedule"),
+    route("post", "/schedules/{schedule_id}/run", "Schedules", "Run a schedule now"),
+    route("get", "/mcp/sessions", "MCP", "Session settings and sessions of the MCP server"),
+    route("put", "/mcp/sessions/config", "MCP", "Change the session settings of the MCP server"),
+    route("post", "/mcp/sessions/evict", "MCP", "Evict the idle MCP sessions"),
+    route("delete", "/mcp/sessions/{session_id}", "MCP", "Evict an MCP session"),
+    route("get", "/executions", "Executor", "List the task executions"),
+    route("get", "/executions/{run_id}/timeline", "Executor", "Timeline of a task execution"),
+    route("get", "/executor/processes", "Executor", "Processes started by the executor"),
+    route("post", "/executor/processes/reap", "Executor", "Kill the orphaned processes"),
+    route("get", "/executor/queue", "Executor", "Tasks waiting for execution"),
+    route("put", "/executor/queue", "Executor", "Reorder the queue"),
+    route("post", "/executor/queue/pause", "Executor", "Pause the queue"),
+    route("post", "/executor/queue/resume", "Executor", "Resume the queue"),
+    route("delete", "/executor/queue/{block_id}/{task_id}", "Executor", "Remove a task from the queue"),
+    route("post", "/executor/queue/{block_id}/{task_id}/move", "Executor", "Move a task in the queue"),
+    route("post", "/executor/queue/{block_id}/{task_id}/bump", "Executor", "Move a task to the front of the queue"),
+    route("post", "/executor/queue/{block_id}/{task_id}/hold", "Executor", "Hold or release a queued task"),
+    route("get", "/claude/sessions/metrics", "Executor", "Metrics of the Claude sessions"),
+    route("get", "/executor/transcripts/{block_id}/{task_id}", "Executor", "Session transcripts of a task"),
+    route("get", "/executor/transcripts/{block_id}/{task_id}/{transcript_id}", "Executor", "Get a session transcript"),
+    route("get", "/logs/stream/{task_id}", "Logs", "Stream the logs of a task (server-sent events)"),
+    route("get", "/logs/tasks", "Logs", "Tasks with logs"),
+    route("get", "/logs/tail", "Logs", "Follow the logs of every task (server-sent events)"),
+    route("get", "/logs/search", "Logs", "Search the task logs"),
+    route("get", "/logs/blocks/{block_id}", "Logs", "Logs of the tasks of a block"),
+    route("get", "/webhooks", "Webhooks", "List the webhooks"),
+    route("put", "/webhooks", "Webhooks", "Replace the webhooks"),
+    route("get", "/webhooks/deliveries", "Webhooks", "Query the webhook delivery log"),
+    route("post", "/webhooks/{id}/test", "Webhooks", "Send a ping to a webhook"),
+    route("post", "/ci/webhook", "CI", "Report the CI status of a commit or branch, signed with the CI webhook secret"),
+    route("post", "/ci/refresh", "CI", "Poll GitHub Actions for the CI status of the tasks"),
+    route("get", "/audit", "Audit", "Query the audit log of the mutating API calls"),
+];
+
+// Names of the {parameters} of a path
+fn path_parameters(path: &str) -> Vec<&str> {
+    path.split('/')
+        .filter_map(|segment| segment.strip_prefix('{').and_then(|s| s.strip_suffix('}')))
+        .collect()
+}
+
+fn operation(route: &ApiRoute) -> Value {
+    let parameters: Vec<Value> = path_parameters(route.path).into_iter()
+        .map(|name| json!({ "name": name, "in": "path", "required": true, "schema": { "type": "string" } }))
+        .collect();
+    let mut operation = json!({
+        "tags": [route.tag],
+        "summary": route.summary,
+        "operationId": format!("{}{}", route.method, route.path.replace(['/', '{', '}', '-', '.'], "_")),
+        "parameters": parameters,
+        "responses": {
+            "200": { "description": "Success" },
+            "400": { "description": "Invalid request" },
+            "401": { "description": "Missing or invalid credentials" },
+            "403": { "description": "The API key or user lacks the permission" },
+            "429": { "description": "Rate limit reached, see the Retry-After header" },
+        },
+    });
+    if matches!(route.method, "post" | "put") {
+        operation["requestBody"] = json!({
+            "required": false,
+            "content": { "application/json": { "schema": { "type": "object" } } },
+        });
+    }
+    operation
+}
+
+// OpenAPI 3 document of version 1 of the API
+pub fn openapi_document() -> Value {
+    let mut paths = Map::new();
+    for route in API_ROUTES {
+        let path = paths.entry(route.path).or_insert_with(|| json!({}));
+        path[route.method] = operation(route);
+    }
+    json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": "Forge API",
+            "version": env!("CARGO_PKG_VERSION"),
+        },
+        "servers": [{ "url": routes::API_V1_PREFIX }],
+        "paths": paths,
+        "components": {
+            "securitySchemes": {
+                "apiKey": { "type": "apiKey", "in": "header", "name": "X-API-Key" },
+                "bearer": { "type": "http", "scheme": "bearer", "bearerFormat": "API key or OIDC ID token" },
+            },
+        },
+        "security": [{ "apiKey": [] }, { "bearer": [] }],
+    })
+}
+
+// API endpoint serving the OpenAPI document
+pub async fn openapi_handler() -> impl Responder {
+    HttpResponse::Ok().json(openapi_document())
+}
+
+// The Swagger UI assets are pinned to an exact release, so a new version published to the CDN isn't
+// loaded into the page without a review
+const SWAGGER_UI: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+  <title>Forge API</title>
+  <link rel="stylesheet" href="https://unpkg.com/swagger-ui-dist@5.17.14/swagger-ui.css" crossorigin="anonymous">
+</head>
+<body>
+  <div id="swagger-ui"></div>
+  <script src="https://unpkg.com/swagger-ui-dist@5.17.14/swagger-ui-bundle.js" crossorigin="anonymous"></script>
+  <script>SwaggerUIBundle({ url: '/api/v1/openapi.json', dom_id: '#swagger-ui' });</script>
+</body>
+</html>"#;
+
+// API endpoint serving a Swagger UI of the document, when enabled
+pub async fn swagger_ui_handler(data: web::Data<ProjectAppState>) -> impl Responder {
+    let enabled = data.project_manager.get_config().ok().and_then(|config| config.swagger_ui).unwrap_or(false);
+    if !enabled {
+        return HttpResponse::NotFound().body("The Swagger UI is disabled, enable swagger_ui in the project settings");
+    }
+    HttpResponse::Ok().content_type("text/html; charset=utf-8").body(SWAGGER_UI)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_routes_documented() {
+        let sources = [
+            include_str!("routes/audit.rs"), include_str!("routes/auth.rs"), include_str!("routes/backups.rs"), include_str!("routes/blocks.rs"),
+            include_str!("routes/docs.rs"), include_str!("routes/epics.rs"), include_str!("routes/executor.rs"),
+            include_str!("routes/git.rs"), include_str!("routes/logs.rs"), include_str!("routes/mcp.rs"),
+            include_str!("routes/project.rs"), include_str!("routes/projects.rs"), include_str!("routes/prompts.rs"),
+            include_str!("routes/schedules.rs"), include_str!("routes/secrets.rs"),
+            include_str!("routes/webhooks.rs"), include_str!("routes/ci.rs"),
+        ];
+        let registered: Vec<(String, String)> = sources.iter().flat_map(|source| source.lines())
+            .filter_map(|line| line.trim().strip_prefix(".route(\""))
+            .filter_map(|rest| {
+                let (path, rest) = rest.split_once('"')?;
+                let method = rest.split("web::").nth(1)?.split('(').next()?;
+                Some((method.to_string(), path.to_string()))
+            })
+            .collect();
+        assert!(!registered.is_empty());
+        for (method, path) in &registered {
+            assert!(API_ROUTES.iter().any(|r| r.method == method && r.path == path), "{} {} isn't documented", method, path);
+        }
+        assert_eq!(registered.len(), API_ROUTES.len());
+
+        let document = openapi_document();
+        let operation = &document["paths"]["/blocks/{block_id}/tasks/{task_id}/status"]["put"];
+        assert_eq!(operation["parameters"].as_array().unwrap().len(), 2);
+        assert!(operation["requestBody"].is_object());
+    }
+}
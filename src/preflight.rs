@@ -0,0 +1,224 @@
+use crate::block_config::{BLOCKS_HISTORY_FILE, BLOCK_CONFIG_BASE_NAME, EPICS_CONFIG_FILE};
+use crate::execution_timeline::EXECUTIONS_FILE;
+use crate::models::Block;
+use crate::project_config::{PreflightConfig, ProjectConfig, TaskWorkflow, PROJECT_CONFIG_FILE};
+use crate::task_executor::{EXECUTOR_QUEUE_FILE, EXECUTOR_STATE_FILE};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+// Result of a single pre-flight check
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PreflightCheck {
+    pub name: String,
+    pub passed: bool,
+    pub message: String,
+}
+
+// Checks run before a task execution is queued
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PreflightReport {
+    pub passed: bool,
+    pub checks: Vec<PreflightCheck>,
+}
+
+impl PreflightReport {
+    // Names and messages of the failed checks, for error messages
+    pub fn failures(&self) -> String {
+        self.checks.iter()
+            .filter(|c| !c.passed)
+            .map(|c| format!("{}: {}", c.name, c.message))
+            .collect::<Vec<_>>()
+            .join("; ")
+    }
+}
+
+fn check(name: &str, result: Result<String, String>) -> PreflightCheck {
+    match result {
+        Ok(message) => PreflightCheck { name: name.to_string(), passed: true, message },
+        Err(message) => PreflightCheck { name: name.to_string(), passed: false, message },
+    }
+}
+
+// Forge's own data files, which may live in the project and change while it runs
+fn is_forge_data_file(path: &str) -> bool {
+    let file_name = Path::new(path).file_name().map(|f| f.to_string_lossy().to_string()).unwrap_or_default();
+    path.starts_with(".forge/")
+        || file_name.starts_with(BLOCK_CONFIG_BASE_NAME)
+        || [EPICS_CONFIG_FILE, BLOCKS_HISTORY_FILE, EXECUTOR_QUEUE_FILE, EXECUTOR_STATE_FILE, EXECUTIONS_FILE, PROJECT_CONFIG_FILE]
+            .contains(&file_name.as_str())
+}
+
+// Paths of the changes listed by `git status --porcelain -z`, except Forge's data files. A rename or
+// copy is listed once under its new path, its original path following in the next field.
+fn dirty_paths(status: &str) -> Vec<String> {
+    let mut paths = Vec::new();
+    let mut fields = status.split('\0');
+    while let Some(entry) = fields.next() {
+        let (code, path) = match (entry.get(..2), entry.get(3..)) {
+            (Some(code), Some(path)) if !path.is_empty() => (code, path),
+            _ => continue,
+        };
+        let original = if code.contains(['R', 'C']) { fields.next() } else { None };
+        if !is_forge_data_file(path) || original.map(|o| !is_forge_data_file(o)).unwrap_or(false) {
+            paths.push(path.to_string());
+        }
+    }
+    paths
+}
+
+fn check_clean_tree(project_dir: &str) -> Result<String, String> {
+    let output = Command::new("git")
+        .args(["status", "--porcelain", "-z"])
+        .current_dir(project_dir)
+        .output()
+        .map_err(|e| format!("Failed to run git status: {}", e))?;
+    if !output.status.success() {
+        return Err(format!("git status failed: {}", String::from_utf8_lossy(&output.stderr).trim()));
+    }
+
+    let dirty = dirty_paths(&String::from_utf8_lossy(&output.stdout));
+    if dirty.is_empty() {
+        Ok("Working tree is clean".to_string())
+    } else {
+        Err(format!("{} uncommitted change(s): {}", dirty.len(), dirty.join(", ")))
+    }
+}
+
+fn check_tools(tools: &[String]) -> Result<String, String> {
+    let missing: Vec<&str> = tools.iter()
+        .map(|t| t.as_str())
+        .filter(|tool| Command::new(tool).arg("--version").stdout(Stdio::null()).stderr(Stdio::null()).status().is_err())
+        .collect();
+    if missing.is_empty() {
+        Ok(format!("Found {}", tools.join(", ")))
+    } else {
+        Err(format!("Not installed: {}", missing.join(", ")))
+    }
+}
+
+fn check_build(build_command: &str, project_dir: &str) -> Result<String, String> {
+    let output = Command::new("sh")
+        .arg("-c")
+        .arg(build_command)
+        .current_dir(project_dir)
+        .output()
+        .map_err(|e| format!("Failed to run '{}': {}", build_command, e))?;
+    if output.status.success() {
+        Ok(format!("'{}' passed", build_command))
+    } else {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let last_lines: Vec<&str> = stderr.lines().rev().take(5).collect();
+        Err(format!("'{}' exited with {}: {}", build_command, output.status,
+            last_lines.into_iter().rev().collect::<Vec<_>>().join("\n")))
+    }
+}
+
+fn check_dependencies(blocks: &[Block], block_id: &str, task_id: &str, workflow: &TaskWorkflow) -> Result<String, String> {
+    let block = blocks.iter()
+        .find(|b| b.block_id == block_id)
+        .ok_or_else(|| format!("Block {} not found", block_id))?;
+    let task = block.todo_list.get(task_id)
+        .ok_or_else(|| format!("Task {} not found in block {}", task_id, block_id))?;
+
+    let open: Vec<String> = task.dependencies.iter()
+        .filter(|dep| block.todo_list.get(*dep).map(|d| !workflow.is_completed(&d.status)).unwrap_or(true))
+        .map(|dep| match block.todo_list.get(dep) {
+            Some(d) => format!("{} ({})", dep, d.status),
+            None => format!("{} (missing)", dep),
+        })
+        .collect();
+    if open.is_empty() {
+        Ok(format!("{} dependencies completed", task.dependencies.len()))
+    } else {
+        Err(format!("Dependencies not completed: {}", open.join(", ")))
+    }
+}
+
+// Validate the environment before executing a task. Dependencies are only checked when they
+// are not resolved (queued ahead of the task) by the execution itself.
+pub fn run_preflight(
+    project_config: &ProjectConfig,
+    config: &PreflightConfig,
+    blocks: &[Block],
+    block_id: &str,
+    task_id: &str,
+    resolve_dependencies: bool,
+) -> PreflightReport {
+    let project_dir = project_config.project_home_directory.as_str();
+    let mut checks = vec![check("project_directory", if project_dir.is_empty() {
+        Err("Project home directory is not set".to_string())
+    } else if !Path::new(project_dir).is_dir() {
+        Err(format!("Project home directory {} does not exist", project_dir))
+    } else {
+        Ok(project_dir.to_string())
+    })];
+    let has_project_dir = checks[0].passed;
+
+    if !config.required_tools.is_empty() {
+        checks.push(check("required_tools", check_tools(&config.required_tools)));
+    }
+    // Uncommitted changes don't reach a task running in its own worktree
+    let uses_worktrees = project_config.worktrees.as_ref().map(|w| w.enabled).unwrap_or(false);
+    if has_project_dir && config.require_clean_tree && !uses_worktrees {
+        checks.push(check("clean_working_tree", check_clean_tree(project_dir)));
+    }
+    if let Some(build_command) = config.build_command.as_deref().filter(|c| !c.trim().is_empty()) {
+        if has_project_dir {
+            checks.push(check("build", check_build(build_command, project_dir)));
+        }
+    }
+    if !resolve_dependencies {
+        let workflow = project_config.task_workflow.clone().unwrap_or_default();
+        checks.push(check("dependencies", check_dependencies(blocks, block_id, task_id, &workflow)));
+    }
+
+    PreflightReport {
+        passed: checks.iter().all(|c| c.passed),
+        checks,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Task;
+    use crate::project_config::TASK_STATUS_COMPLETED;
+
+    #[test]
+    fn test_dependency_check() {
+        let mut block = Block::new("Auth".to_string(), "Authentication".to_string(), Vec::new(), Vec::new());
+        let mut schema = Task::new("Add user schema".to_string());
+        schema.task_id = "a".to_string();
+        schema.status = "[TODO]".to_string();
+        let mut login = Task::new("Add login".to_string());
+        login.task_id = "b".to_string();
+        login.dependencies = vec!["a".to_string()];
+        block.todo_list.insert("a".to_string(), schema.clone());
+        block.todo_list.insert("b".to_string(), login);
+        let block_id = block.block_id.clone();
+        let workflow = TaskWorkflow::default();
+
+        let mut blocks = vec![block];
+        assert_eq!(check_dependencies(&blocks, &block_id, "b", &workflow).unwrap_err(), "Dependencies not completed: a ([TODO])");
+
+        schema.status = TASK_STATUS_COMPLETED.to_string();
+        blocks[0].todo_list.insert("a".to_string(), schema);
+        assert!(check_dependencies(&blocks, &block_id, "b", &workflow).is_ok());
+    }
+
+    #[test]
+    fn test_forge_data_files_are_ignored() {
+        assert!(is_forge_data_file("blocks_config.yaml"));
+        assert!(is_forge_data_file("specs/executor_queue.json"));
+        assert!(is_forge_data_file(".forge/reports/abc.md"));
+        assert!(!is_forge_data_file("src/main.rs"));
+    }
+
+    #[test]
+    fn test_dirty_paths() {
+        let status = " M src/main.rs\0R  src/new name.rs\0src/old.rs\0?? executions.json\0R  executor_queue.json\0executor_state.json\0";
+        assert_eq!(dirty_paths(status), vec!["src/main.rs".to_string(), "src/new name.rs".to_string()]);
+        assert!(dirty_paths("").is_empty());
+    }
+}
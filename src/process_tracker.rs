@@ -0,0 +1,247 @@
+use crate::block_config::{write_file_atomic, FileLock};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use tracing::{info, warn};
+
+// Run file listing the child processes spawned by running Forge instances
+pub const PROCESS_RUN_FILE: &str = ".forge/processes.json";
+
+// A child process spawned for an execution
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrackedProcess {
+    pub pid: u32,
+    // PID of the Forge process that spawned it
+    pub owner_pid: u32,
+    // Execution the process belongs to (e.g. "block_id:task_id")
+    pub execution_id: String,
+    // Program name, checked before killing to guard against PID reuse
+    pub command: String,
+    pub started_at: String,
+}
+
+// Keeps a child process registered in the run file until dropped
+pub struct ProcessGuard {
+    pid: u32,
+}
+
+impl Drop for ProcessGuard {
+    fn drop(&mut self) {
+        if let Err(e) = unregister(self.pid) {
+            warn!("Failed to unregister process {}: {}", self.pid, e);
+        }
+    }
+}
+
+fn read_run_file() -> Vec<TrackedProcess> {
+    fs::read_to_string(PROCESS_RUN_FILE)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn write_run_file(processes: &[TrackedProcess]) -> Result<(), String> {
+    if let Some(parent) = Path::new(PROCESS_RUN_FILE).parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create run directory: {}", e))?;
+    }
+    let json = serde_json::to_string_pretty(processes)
+        .map_err(|e| format!("Failed to serialize processes: {}", e))?;
+    write_file_atomic(PROCESS_RUN_FILE, json.as_bytes())
+}
+
+// Apply a change to the run file under the cross-process lock
+fn update_run_file<T>(f: impl FnOnce(&mut Vec<TrackedProcess>) -> T) -> Result<T, String> {
+    if let Some(parent) = Path::new(PROCESS_RUN_FILE).parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create run directory: {}", e))?;
+    }
+    let _lock = FileLock::acquire(PROCESS_RUN_FILE)?;
+    let mut processes = read_run_file();
+    let result = f(&mut processes);
+    write_run_file(&processes)?;
+    Ok(result)
+}
+
+// Record a spawned child process; it stays tracked until the returned guard is dropped
+pub fn register(pid: u32, execution_id: &str, command: &str) -> ProcessGuard {
+    let process = TrackedProcess {
+        pid,
+        owner_pid: std::process::id(),
+        execution_id: execution_id.to_string(),
+        command: command.to_string(),
+        started_at: chrono::Utc::now().to_rfc3339(),
+    };
+
+    if let Err(e) = update_run_file(|processes| {
+        processes.retain(|p| p.pid != pid);
+        processes.push(process);
+    }) {
+        warn!("Failed to register process {}: {}", pid, e);
+    }
+
+    ProcessGuard { pid }
+}
+
+// Remove a process from the run file
+pub fn unregister(pid: u32) -> Result<(), String> {
+    update_run_file(|processes| processes.retain(|p| p.pid != pid))
+}
+
+// List the tracked processes
+pub fn list_processes() -> Vec<TrackedProcess> {
+    read_run_file()
+}
+
+// Command line of the program running as `pid`, if the process exists
+#[cfg(unix)]
+fn process_command(pid: u32) -> Option<String> {
+    let output = Command::new("ps").args(["-p", &pid.to_string(), "-o", "args="]).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let command = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if command.is_empty() { None } else { Some(command) }
+}
+
+#[cfg(windows)]
+fn process_command(pid: u32) -> Option<String> {
+    let output = Command::new("tasklist")
+        .args(["/FI", &format!("PID eq {}", pid), "/FO", "CSV", "/NH"])
+        .output()
+        .ok()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let name = stdout.split(',').next()?.trim_matches('"').trim().to_string();
+    if name.is_empty() || name.starts_with("INFO:") { None } else { Some(name) }
+}
+
+// Start a command in its own process group, so it can be killed together with the processes it starts
+#[cfg(unix)]
+pub fn own_process_group(command: &mut Command) {
+    use std::os::unix::process::CommandExt;
+    command.process_group(0);
+}
+
+#[cfg(windows)]
+pub fn own_process_group(_command: &mut Command) {}
+
+// Signal the process group led by `pid`, falling back to the process alone if it leads none
+#[cfg(unix)]
+fn kill_process(pid: u32) -> bool {
+    let signal = |target: &str| Command::new("kill").args(["-TERM", "--", target]).status().map(|s| s.success()).unwrap_or(false);
+    signal(&format!("-{}", pid)) || signal(&pid.to_string())
+}
+
+#[cfg(windows)]
+fn kill_process(pid: u32) -> bool {
+    Command::new("taskkill").args(["/PID", &pid.to_string(), "/T", "/F"]).status().map(|s| s.success()).unwrap_or(false)
+}
+
+// Kill the process group led by `pid` (started with `own_process_group`)
+#[cfg(unix)]
+pub fn kill_process_group(pid: u32) {
+    let _ = Command::new("kill").args(["-KILL", "--", &format!("-{}", pid)]).status();
+}
+
+#[cfg(windows)]
+pub fn kill_process_group(pid: u32) {
+    let _ = Command::new("taskkill").args(["/PID", &pid.to_string(), "/T", "/F"]).status();
+}
+
+// Resident memory in kilobytes of a process and all its descendants, given (pid, parent pid, rss) entries
+#[cfg_attr(windows, allow(dead_code))]
+fn tree_memory_kb(pid: u32, processes: &[(u32, u32, u64)]) -> Option<u64> {
+    let root = processes.iter().find(|(p, _, _)| *p == pid)?;
+    let mut total = root.2;
+    let mut pending = vec![pid];
+    while let Some(parent) = pending.pop() {
+        for (child, _, rss) in processes.iter().filter(|(p, ppid, _)| *ppid == parent && *p != parent) {
+            total += rss;
+            pending.push(*child);
+        }
+    }
+    Some(total)
+}
+
+// Resident memory of a process and its child processes in kilobytes, if it runs
+#[cfg(unix)]
+pub fn process_tree_memory_kb(pid: u32) -> Option<u64> {
+    let output = Command::new("ps").args(["-A", "-o", "pid=,ppid=,rss="]).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let processes: Vec<(u32, u32, u64)> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace().map(|f| f.parse::<u64>().ok());
+            Some((fields.next()?? as u32, fields.next()?? as u32, fields.next()??))
+        })
+        .collect();
+    tree_memory_kb(pid, &processes)
+}
+
+#[cfg(windows)]
+pub fn process_tree_memory_kb(_pid: u32) -> Option<u64> {
+    None
+}
+
+// Terminate the processes this Forge instance spawned for an execution, returning the processes signalled
+pub fn terminate_execution(execution_id: &str) -> Vec<TrackedProcess> {
+    let current_pid = std::process::id();
+    let processes: Vec<TrackedProcess> = read_run_file()
+        .into_iter()
+        .filter(|p| p.owner_pid == current_pid && p.execution_id == execution_id)
+        .collect();
+
+    processes.into_iter()
+        .filter(|p| {
+            let killed = kill_process(p.pid);
+            if killed {
+                info!("Terminated process {} ({}) of execution {}", p.pid, p.command, p.execution_id);
+            }
+            killed
+        })
+        .collect()
+}
+
+// Kill the tracked processes whose Forge owner is gone, returning the processes that were killed.
+// Entries for processes that already exited are dropped from the run file.
+pub fn reap_orphans() -> Result<Vec<TrackedProcess>, String> {
+    let current_pid = std::process::id();
+
+    let killed = update_run_file(|processes| {
+        let mut killed = Vec::new();
+        processes.retain(|p| {
+            if p.owner_pid == current_pid || process_command(p.owner_pid).is_some() {
+                return true;
+            }
+
+            // Only kill the process if the PID still runs the program that was spawned
+            let running = process_command(p.pid)
+                .map(|c| c.to_lowercase().contains(&p.command.to_lowercase()))
+                .unwrap_or(false);
+            if running && kill_process(p.pid) {
+                killed.push(p.clone());
+            }
+            false
+        });
+        killed
+    })?;
+
+    for process in &killed {
+        info!("Killed orphaned process {} ({}) of execution {}", process.pid, process.command, process.execution_id);
+    }
+    Ok(killed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tree_memory_kb() {
+        let processes = [(1, 0, 10), (100, 1, 200), (101, 100, 50), (102, 101, 25), (200, 1, 1000)];
+        assert_eq!(tree_memory_kb(100, &processes), Some(275));
+        assert_eq!(tree_memory_kb(102, &processes), Some(25));
+        assert_eq!(tree_memory_kb(300, &processes), None);
+    }
+}
@@ -1,5 +1,40 @@
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
-use crate::project_config::{DEFAULT_GENERATE_TASKS_SYSTEM_PROMPT_MCP, DEFAULT_GENERATE_TASKS_USER_PROMPT_MCP};
+use crate::block_config::{write_file_atomic, FileLock};
+use crate::mcp::tools::BUILTIN_TOOL_NAMES;
+use crate::prompt_history::{self, PromptState};
+use crate::project_config::{ProjectConfigManager, DEFAULT_GENERATE_TASKS_SYSTEM_PROMPT_MCP, DEFAULT_GENERATE_TASKS_USER_PROMPT_MCP};
+use regex::Regex;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+// Professions defined by the user, stored next to the project config
+pub const CUSTOM_PROFESSIONS_FILE: &str = "custom_professions.json";
+
+// Edited prompts of built-in professions, per profession id and prompt key
+pub const PROFESSION_PROMPT_OVERRIDES_FILE: &str = "profession_prompt_overrides.json";
+
+// Keys of the prompts of a profession
+pub const PROMPT_KEYS: &[&str] = &[
+    "auto_complete_system_prompt",
+    "auto_complete_user_prompt",
+    "enhance_description_system_prompt",
+    "enhance_description_user_prompt",
+    "generate_tasks_system_prompt",
+    "generate_tasks_user_prompt",
+    "generate_tasks_system_prompt_mcp",
+    "generate_tasks_user_prompt_mcp",
+    "process_specification_system_prompt",
+    "process_specification_user_prompt",
+    "process_specification_system_prompt_mcp",
+    "process_specification_user_prompt_mcp",
+];
+
+lazy_static::lazy_static! {
+    // Tool names are referenced in backticks, e.g. `create_task`
+    static ref TOOL_REFERENCE: Regex = Regex::new(r"`([a-z][a-z0-9]*(?:_[a-z0-9]+)+)`").unwrap();
+}
 
 // Define profession categories
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
@@ -74,8 +109,60 @@ pub struct ProfessionPrompts {
     pub process_specification_user_prompt_mcp: String,
 }
 
-// Function to get all professions with their default prompts
+impl ProfessionPrompts {
+    pub fn prompt(&self, key: &str) -> Option<&str> {
+        let prompt = match key {
+            "auto_complete_system_prompt" => &self.auto_complete_system_prompt,
+            "auto_complete_user_prompt" => &self.auto_complete_user_prompt,
+            "enhance_description_system_prompt" => &self.enhance_description_system_prompt,
+            "enhance_description_user_prompt" => &self.enhance_description_user_prompt,
+            "generate_tasks_system_prompt" => &self.generate_tasks_system_prompt,
+            "generate_tasks_user_prompt" => &self.generate_tasks_user_prompt,
+            "generate_tasks_system_prompt_mcp" => &self.generate_tasks_system_prompt_mcp,
+            "generate_tasks_user_prompt_mcp" => &self.generate_tasks_user_prompt_mcp,
+            "process_specification_system_prompt" => &self.process_specification_system_prompt,
+            "process_specification_user_prompt" => &self.process_specification_user_prompt,
+            "process_specification_system_prompt_mcp" => &self.process_specification_system_prompt_mcp,
+            "process_specification_user_prompt_mcp" => &self.process_specification_user_prompt_mcp,
+            _ => return None,
+        };
+        Some(prompt)
+    }
+
+    fn prompt_mut(&mut self, key: &str) -> Option<&mut String> {
+        match key {
+            "auto_complete_system_prompt" => Some(&mut self.auto_complete_system_prompt),
+            "auto_complete_user_prompt" => Some(&mut self.auto_complete_user_prompt),
+            "enhance_description_system_prompt" => Some(&mut self.enhance_description_system_prompt),
+            "enhance_description_user_prompt" => Some(&mut self.enhance_description_user_prompt),
+            "generate_tasks_system_prompt" => Some(&mut self.generate_tasks_system_prompt),
+            "generate_tasks_user_prompt" => Some(&mut self.generate_tasks_user_prompt),
+            "generate_tasks_system_prompt_mcp" => Some(&mut self.generate_tasks_system_prompt_mcp),
+            "generate_tasks_user_prompt_mcp" => Some(&mut self.generate_tasks_user_prompt_mcp),
+            "process_specification_system_prompt" => Some(&mut self.process_specification_system_prompt),
+            "process_specification_user_prompt" => Some(&mut self.process_specification_user_prompt),
+            "process_specification_system_prompt_mcp" => Some(&mut self.process_specification_system_prompt_mcp),
+            "process_specification_user_prompt_mcp" => Some(&mut self.process_specification_user_prompt_mcp),
+            _ => None,
+        }
+    }
+}
+
+// Function to get all professions with their default prompts, followed by the user's custom professions
 pub fn get_all_professions() -> Vec<Profession> {
+    let mut professions = built_in_professions();
+    match load_prompt_overrides() {
+        Ok(overrides) => apply_prompt_overrides(&mut professions, &overrides),
+        Err(e) => println!("Failed to load profession prompt overrides: {}", e),
+    }
+    match load_custom_professions() {
+        Ok(custom) => professions.extend(custom),
+        Err(e) => println!("Failed to load custom professions: {}", e),
+    }
+    professions
+}
+
+fn built_in_professions() -> Vec<Profession> {
     vec![
         // Engineering & Development
         Profession {
@@ -238,6 +325,449 @@ pub fn get_profession_by_id(id: &str) -> Option<Profession> {
     get_all_professions().into_iter().find(|p| p.id == id)
 }
 
+pub fn is_built_in(id: &str) -> bool {
+    built_in_professions().iter().any(|p| p.id == id)
+}
+
+// Request to create or update a custom profession
+#[derive(Debug, Clone, Deserialize)]
+pub struct CustomProfessionRequest {
+    pub name: String,
+    #[serde(default)]
+    pub category: Option<ProfessionCategory>,
+    // Prompts of the profession; when omitted they are copied from base_profession_id (or the
+    // fill-in-the-blank template) on creation and left unchanged on update
+    #[serde(default)]
+    pub prompts: Option<ProfessionPrompts>,
+    #[serde(default)]
+    pub base_profession_id: Option<String>,
+}
+
+fn custom_professions_file() -> String {
+    ProjectConfigManager::get_instance().project_data_file(CUSTOM_PROFESSIONS_FILE)
+}
+
+fn prompt_overrides_file() -> String {
+    ProjectConfigManager::get_instance().project_data_file(PROFESSION_PROMPT_OVERRIDES_FILE)
+}
+
+// Contents of a JSON data file; a missing file means no data
+fn load_json_file<T: DeserializeOwned + Default>(file: &str) -> Result<T, String> {
+    if !Path::new(file).exists() {
+        return Ok(T::default());
+    }
+    let content = fs::read_to_string(file).map_err(|e| format!("Failed to read {}: {}", file, e))?;
+    serde_json::from_str(&content).map_err(|e| format!("Failed to parse {}: {}", file, e))
+}
+
+// Apply a change to a JSON data file under the file lock
+fn modify_json_file<T: DeserializeOwned + Serialize + Default, R>(file: &str, change: impl FnOnce(&mut T) -> Result<R, String>) -> Result<R, String> {
+    let _lock = FileLock::acquire(file)?;
+    let mut data: T = load_json_file(file)?;
+    let result = change(&mut data)?;
+    let json = serde_json::to_string_pretty(&data).map_err(|e| format!("Failed to serialize {}: {}", file, e))?;
+    write_file_atomic(file, json.as_bytes())?;
+    Ok(result)
+}
+
+pub fn load_custom_professions() -> Result<Vec<Profession>, String> {
+    load_json_file(&custom_professions_file())
+}
+
+fn modify_custom_professions<T>(change: impl FnOnce(&mut Vec<Profession>) -> Result<T, String>) -> Result<T, String> {
+    modify_json_file(&custom_professions_file(), change)
+}
+
+type PromptOverrides = HashMap<String, HashMap<String, String>>;
+
+fn load_prompt_overrides() -> Result<PromptOverrides, String> {
+    load_json_file(&prompt_overrides_file())
+}
+
+fn apply_prompt_overrides(professions: &mut [Profession], overrides: &PromptOverrides) {
+    for profession in professions.iter_mut() {
+        for (key, value) in overrides.get(&profession.id).into_iter().flatten() {
+            if let Some(prompt) = profession.prompts.prompt_mut(key) {
+                *prompt = value.clone();
+            }
+        }
+    }
+}
+
+// Check an edited prompt: user prompts need the {{input}} placeholder (or the legacy {}), and MCP
+// prompts must reference their operation's tools and no tools that don't exist
+pub fn validate_prompt(key: &str, value: &str) -> Result<(), String> {
+    if !PROMPT_KEYS.contains(&key) {
+        return Err(format!("Unknown prompt '{}', expected one of: {}", key, PROMPT_KEYS.join(", ")));
+    }
+
+    let mut errors = Vec::new();
+    if value.trim().is_empty() {
+        errors.push("The prompt cannot be empty".to_string());
+    }
+    if key.contains("_user_prompt") && !value.contains("{{input}}") && !value.contains("{}") {
+        errors.push("User prompts must contain the {{input}} placeholder the input is inserted at".to_string());
+    }
+    if key.ends_with("_mcp") {
+        let mut unknown: Vec<String> = TOOL_REFERENCE.captures_iter(value)
+            .map(|caps| caps[1].to_string())
+            .filter(|name| !BUILTIN_TOOL_NAMES.contains(&name.as_str()))
+            .collect();
+        unknown.dedup();
+        if !unknown.is_empty() {
+            errors.push(format!("Unknown MCP tools referenced: {}", unknown.join(", ")));
+        }
+
+        let operation_tools: &[&str] = if key.starts_with("generate_tasks") { &["create_task"] } else { &["create_block", "create_task"] };
+        if !operation_tools.iter().any(|tool| value.contains(tool)) {
+            errors.push(format!("MCP prompts must reference the {} tool", operation_tools.join(" or ")));
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors.join("; "))
+    }
+}
+
+// Set one prompt of a profession: custom professions are changed in place, built-in professions
+// get a per-project override of the compiled-in prompt
+pub fn edit_profession_prompt(profession_id: &str, key: &str, value: &str) -> Result<Profession, String> {
+    validate_prompt(key, value)?;
+
+    with_prompt_history(profession_id, || {
+        if is_built_in(profession_id) {
+            modify_json_file(&prompt_overrides_file(), |overrides: &mut PromptOverrides| {
+                overrides.entry(profession_id.to_string()).or_default().insert(key.to_string(), value.to_string());
+                Ok(())
+            })?;
+            return get_profession_by_id(profession_id).ok_or_else(|| format!("Profession with ID '{}' not found", profession_id));
+        }
+
+        modify_custom_professions(|professions| {
+            let profession = professions.iter_mut()
+                .find(|p| p.id == profession_id)
+                .ok_or_else(|| format!("Profession with ID '{}' not found", profession_id))?;
+            if let Some(prompt) = profession.prompts.prompt_mut(key) {
+                *prompt = value.to_string();
+            }
+            Ok(profession.clone())
+        })
+    })
+}
+
+// Restore the compiled-in prompt of a built-in profession
+pub fn reset_profession_prompt(profession_id: &str, key: &str) -> Result<Profession, String> {
+    if !is_built_in(profession_id) {
+        return Err(format!("Only prompts of built-in professions can be reset, '{}' is not one", profession_id));
+    }
+    with_prompt_history(profession_id, || {
+        modify_json_file(&prompt_overrides_file(), |overrides: &mut PromptOverrides| {
+            if let Some(prompts) = overrides.get_mut(profession_id) {
+                prompts.remove(key);
+                if prompts.is_empty() {
+                    overrides.remove(profession_id);
+                }
+            }
+            Ok(())
+        })?;
+        get_profession_by_id(profession_id).ok_or_else(|| format!("Profession with ID '{}' not found", profession_id))
+    })
+}
+
+// Restore a version of a profession prompt from the prompt history
+pub fn rollback_profession_prompt(profession_id: &str, key: &str, version: u32) -> Result<Profession, String> {
+    let version = prompt_history::get_version(profession_id, key, version)?;
+    if version.default {
+        reset_profession_prompt(profession_id, key)
+    } else {
+        edit_profession_prompt(profession_id, key, &version.value)
+    }
+}
+
+// Text of each prompt of a profession and whether it is the compiled-in one
+fn prompt_states(profession_id: &str) -> HashMap<String, PromptState> {
+    let Some(profession) = get_profession_by_id(profession_id) else {
+        return HashMap::new();
+    };
+    let overridden = load_prompt_overrides().unwrap_or_default().remove(profession_id).unwrap_or_default();
+    let built_in = is_built_in(profession_id);
+    prompt_map(&profession.prompts).into_iter()
+        .filter_map(|(key, value)| {
+            let default = built_in && !overridden.contains_key(&key);
+            value.as_str().map(|value| (key, PromptState { value: value.to_string(), default }))
+        })
+        .collect()
+}
+
+// Run a change of a profession and add the prompts it changed to the prompt history
+fn with_prompt_history<T>(profession_id: &str, change: impl FnOnce() -> Result<T, String>) -> Result<T, String> {
+    let before = prompt_states(profession_id);
+    let result = change()?;
+    let mut after = prompt_states(profession_id);
+    for (key, previous) in before {
+        if let Some(current) = after.remove(&key) {
+            if let Err(e) = prompt_history::record_change(profession_id, &key, previous, current) {
+                println!("Failed to record prompt history: {}", e);
+            }
+        }
+    }
+    Ok(result)
+}
+
+// Id of a new custom profession: "custom_" and the slug of its name, made unique
+fn custom_profession_id(name: &str, taken: &[String]) -> String {
+    let slug: String = name.to_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect::<String>()
+        .split('_')
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("_");
+    let base = format!("custom_{}", if slug.is_empty() { "profession" } else { &slug });
+
+    let mut id = base.clone();
+    let mut suffix = 2;
+    while taken.contains(&id) {
+        id = format!("{}_{}", base, suffix);
+        suffix += 1;
+    }
+    id
+}
+
+fn validate_name(name: &str, id: Option<&str>, professions: &[Profession]) -> Result<(), String> {
+    if name.trim().is_empty() {
+        return Err("Profession name cannot be empty".to_string());
+    }
+    if professions.iter().any(|p| p.name.eq_ignore_ascii_case(name.trim()) && Some(p.id.as_str()) != id) {
+        return Err(format!("A profession named '{}' already exists", name.trim()));
+    }
+    Ok(())
+}
+
+pub fn create_custom_profession(request: CustomProfessionRequest) -> Result<Profession, String> {
+    let prompts = match (request.prompts, request.base_profession_id.as_deref()) {
+        (Some(prompts), _) => prompts,
+        (None, Some(base_id)) => get_profession_by_id(base_id)
+            .ok_or_else(|| format!("Profession with ID '{}' not found", base_id))?
+            .prompts,
+        (None, None) => create_custom_prompts(),
+    };
+    let built_in = built_in_professions();
+
+    modify_custom_professions(|professions| {
+        let all: Vec<Profession> = built_in.iter().chain(professions.iter()).cloned().collect();
+        validate_name(&request.name, None, &all)?;
+        let taken: Vec<String> = all.iter().map(|p| p.id.clone()).collect();
+
+        let profession = Profession {
+            id: custom_profession_id(&request.name, &taken),
+            name: request.name.trim().to_string(),
+            category: request.category.unwrap_or(ProfessionCategory::Custom),
+            prompts,
+        };
+        professions.push(profession.clone());
+        Ok(profession)
+    })
+}
+
+pub fn update_custom_profession(id: &str, request: CustomProfessionRequest) -> Result<Profession, String> {
+    if is_built_in(id) {
+        return Err(format!("Built-in profession '{}' cannot be modified", id));
+    }
+    let built_in = built_in_professions();
+
+    with_prompt_history(id, || modify_custom_professions(|professions| {
+        let all: Vec<Profession> = built_in.iter().chain(professions.iter()).cloned().collect();
+        validate_name(&request.name, Some(id), &all)?;
+
+        let profession = professions.iter_mut()
+            .find(|p| p.id == id)
+            .ok_or_else(|| format!("Profession with ID '{}' not found", id))?;
+        profession.name = request.name.trim().to_string();
+        if let Some(category) = request.category {
+            profession.category = category;
+        }
+        if let Some(prompts) = request.prompts {
+            profession.prompts = prompts;
+        }
+        Ok(profession.clone())
+    }))
+}
+
+pub fn delete_custom_profession(id: &str) -> Result<(), String> {
+    if is_built_in(id) {
+        return Err(format!("Built-in profession '{}' cannot be deleted", id));
+    }
+    modify_custom_professions(|professions| {
+        let count = professions.len();
+        professions.retain(|p| p.id != id);
+        if professions.len() == count {
+            return Err(format!("Profession with ID '{}' not found", id));
+        }
+        Ok(())
+    })
+}
+
+// Version of the prompt pack format written by this version of Forge
+pub const PROMPT_PACK_FORMAT_VERSION: u32 = 1;
+
+// Shareable set of professions with their prompts
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PromptPack {
+    pub format_version: u32,
+    pub name: String,
+    // Version of the pack itself, chosen by its author (e.g. "1.2.0")
+    #[serde(default)]
+    pub version: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub author: Option<String>,
+    #[serde(default)]
+    pub exported_at: String,
+    pub professions: Vec<Profession>,
+}
+
+// What to do with a pack profession whose id or name is already in use
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PackConflictStrategy {
+    #[default]
+    Skip,
+    Overwrite,
+    // Import as a new custom profession next to the existing one
+    Rename,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct PackImportReport {
+    pub imported: Vec<String>,
+    pub overwritten: Vec<String>,
+    // Pack id and the id the profession was imported as
+    pub renamed: Vec<(String, String)>,
+    pub skipped: Vec<String>,
+    pub errors: Vec<String>,
+}
+
+// Prompts of a profession by key
+fn prompt_map(prompts: &ProfessionPrompts) -> serde_json::Map<String, serde_json::Value> {
+    match serde_json::to_value(prompts) {
+        Ok(serde_json::Value::Object(map)) => map,
+        _ => serde_json::Map::new(),
+    }
+}
+
+// Export professions (all when `profession_ids` is empty) with their current prompts
+pub fn export_prompt_pack(profession_ids: &[String], name: &str, version: &str) -> Result<PromptPack, String> {
+    let professions: Vec<Profession> = get_all_professions().into_iter()
+        .filter(|p| profession_ids.is_empty() || profession_ids.contains(&p.id))
+        .collect();
+    if let Some(missing) = profession_ids.iter().find(|id| !professions.iter().any(|p| &&p.id == id)) {
+        return Err(format!("Profession with ID '{}' not found", missing));
+    }
+
+    Ok(PromptPack {
+        format_version: PROMPT_PACK_FORMAT_VERSION,
+        name: if name.trim().is_empty() { "Forge prompt pack".to_string() } else { name.trim().to_string() },
+        version: version.to_string(),
+        description: String::new(),
+        author: None,
+        exported_at: chrono::Utc::now().to_rfc3339(),
+        professions,
+    })
+}
+
+// Read a pack from JSON or YAML
+pub fn parse_prompt_pack(content: &str) -> Result<PromptPack, String> {
+    let pack: PromptPack = match serde_json::from_str(content) {
+        Ok(pack) => pack,
+        Err(json_error) => serde_yaml::from_str(content)
+            .map_err(|yaml_error| format!("Failed to parse prompt pack as JSON ({}) or YAML ({})", json_error, yaml_error))?,
+    };
+    if pack.format_version > PROMPT_PACK_FORMAT_VERSION {
+        return Err(format!("Prompt pack format version {} is newer than the supported version {}", pack.format_version, PROMPT_PACK_FORMAT_VERSION));
+    }
+    Ok(pack)
+}
+
+// Merge a pack into the custom professions and the prompt overrides of built-in professions
+fn merge_prompt_pack(pack: PromptPack, strategy: PackConflictStrategy, built_in: &[Profession], custom: &mut Vec<Profession>, overrides: &mut PromptOverrides) -> PackImportReport {
+    let mut report = PackImportReport::default();
+
+    for mut profession in pack.professions {
+        let prompts = prompt_map(&profession.prompts);
+        let invalid: Vec<String> = PROMPT_KEYS.iter()
+            .filter_map(|key| validate_prompt(key, prompts.get(*key).and_then(|v| v.as_str()).unwrap_or_default()).err().map(|e| format!("{}: {}", key, e)))
+            .collect();
+        if !invalid.is_empty() {
+            report.errors.push(format!("{}: {}", profession.id, invalid.join("; ")));
+            continue;
+        }
+
+        let built_in_match = built_in.iter().find(|p| p.id == profession.id);
+        let custom_match = custom.iter().position(|p| p.id == profession.id || p.name.eq_ignore_ascii_case(&profession.name));
+        let conflict = built_in_match.is_some() || custom_match.is_some()
+            || built_in.iter().any(|p| p.name.eq_ignore_ascii_case(&profession.name));
+
+        match (conflict, strategy) {
+            (false, _) => {
+                report.imported.push(profession.id.clone());
+                custom.push(profession);
+            }
+            (true, PackConflictStrategy::Skip) => report.skipped.push(profession.id),
+            (true, PackConflictStrategy::Overwrite) if built_in_match.is_some() => {
+                // Store the prompts that differ from the compiled-in ones as overrides
+                let compiled = prompt_map(&built_in_match.unwrap().prompts);
+                let changed: HashMap<String, String> = prompts.iter()
+                    .filter(|(key, value)| compiled.get(*key) != Some(*value))
+                    .filter_map(|(key, value)| value.as_str().map(|v| (key.clone(), v.to_string())))
+                    .collect();
+                if changed.is_empty() {
+                    overrides.remove(&profession.id);
+                } else {
+                    overrides.insert(profession.id.clone(), changed);
+                }
+                report.overwritten.push(profession.id);
+            }
+            (true, PackConflictStrategy::Overwrite) if custom_match.is_some() => {
+                let index = custom_match.unwrap();
+                profession.id = custom[index].id.clone();
+                report.overwritten.push(profession.id.clone());
+                custom[index] = profession;
+            }
+            // A custom profession named like a built-in one can't overwrite it; import it renamed
+            (true, _) => {
+                let taken: Vec<String> = built_in.iter().chain(custom.iter()).map(|p| p.id.clone()).collect();
+                let names: Vec<String> = built_in.iter().chain(custom.iter()).map(|p| p.name.to_lowercase()).collect();
+                let mut name = format!("{} (imported)", profession.name);
+                let mut suffix = 2;
+                while names.contains(&name.to_lowercase()) {
+                    name = format!("{} (imported {})", profession.name, suffix);
+                    suffix += 1;
+                }
+                let id = custom_profession_id(&name, &taken);
+                report.renamed.push((profession.id.clone(), id.clone()));
+                profession.id = id;
+                profession.name = name;
+                custom.push(profession);
+            }
+        }
+    }
+    report
+}
+
+pub fn import_prompt_pack(pack: PromptPack, strategy: PackConflictStrategy) -> Result<PackImportReport, String> {
+    let built_in = built_in_professions();
+    modify_custom_professions(|custom| {
+        modify_json_file(&prompt_overrides_file(), |overrides: &mut PromptOverrides| {
+            Ok(merge_prompt_pack(pack, strategy, &built_in, custom, overrides))
+        })
+    })
+}
+
 // Default prompts for each profession
 fn create_frontend_developer_prompts() -> ProfessionPrompts {
     ProfessionPrompts {
@@ -250,7 +780,7 @@ fn create_frontend_developer_prompts() -> ProfessionPrompts {
 Maintain the original intent and technical level. Be specific about technologies like React, Vue, Angular, CSS frameworks, or state management when relevant.
 
 Partial description:
-{}
+{{input}}
 
 ".to_string(),
         enhance_description_system_prompt: "You are a technical writing expert specializing in frontend development documentation. Transform brief component descriptions into comprehensive, implementation-ready specifications that frontend developers can directly use for coding.".to_string(),
@@ -272,7 +802,7 @@ Partial description:
 - Maintain focus on implementation details
 
 Original description:
-{}
+{{input}}
 
 ".to_string(),
         generate_tasks_system_prompt: "You are a senior frontend developer and project manager expert at breaking down UI components into granular, executable development tasks. Focus on creating tasks that are specific, measurable, and can be directly implemented by frontend developers.".to_string(),
@@ -297,7 +827,7 @@ Based on the frontend component description below, generate a prioritized list o
       \"dependencies\": [
         \"task_id or block_id (must use IDs only, not names)\"
       ],
-      \"estimated_effort\": \"S|M|L\",
+      \"estimated_effort\": \"small|medium|large\",
       \"files_affected\": [
         \"string\"
       ],
@@ -322,11 +852,11 @@ Based on the frontend component description below, generate a prioritized list o
 - Include relevant file names, component names, or code locations
 - Specify testing requirements including unit and UI tests
 - Indicate dependencies between tasks using task_id or block_id ONLY (never use names or descriptive strings)
-- Use effort indicators: S (Simple, 1-3 hours), M (Medium, 3-6 hours), L (Large, 6-8 hours)
+- Use effort indicators: small (1-3 hours), medium (3-6 hours), large (6-8 hours)
 - Task ID: task_id should be a random alpha numeric string of 6 characters.
 
 **Component Description:**
-{}
+{{input}}
 
 **Output Requirements:**
 - Return ONLY valid JSON
@@ -399,7 +929,7 @@ create_task:
 
 Now analyze the following frontend component description and create the appropriate tasks:
 
-{}".to_string(),
+{{input}}".to_string(),
         process_specification_system_prompt: "You are a frontend architecture analyst expert at parsing technical specifications and extracting structured UI component implementation details. Your output must be valid JSON that can be directly consumed by frontend development tools.".to_string(),
         process_specification_user_prompt: "Analyze the following frontend technical specification markdown and extract structured implementation blocks for UI components. 
 
@@ -434,7 +964,7 @@ Now analyze the following frontend component description and create the appropri
 - Ensure each component is self-contained where possible
 
 Specification document:
-{}
+{{input}}
 ".to_string(),
         process_specification_system_prompt_mcp: "You are a frontend architecture analyst expert at parsing technical specifications and creating structured UI component implementation details using MCP tools. You will use the `create_block` and `create_task` MCP tools to directly create forge Blocks and Tasks for frontend components.
 
@@ -497,7 +1027,7 @@ create_task:
 
 Now analyze the following specification and create the appropriate blocks and tasks:
 
-{}".to_string(),
+{{input}}".to_string(),
     }
 }
 
@@ -512,7 +1042,7 @@ fn create_backend_developer_prompts() -> ProfessionPrompts {
 Maintain the original intent and technical level. Be specific about technologies, frameworks, or database systems when relevant.
 
 Partial description:
-{}
+{{input}}
 
 ".to_string(),
         enhance_description_system_prompt: "You are a technical writing expert specializing in backend development documentation. Transform brief component descriptions into comprehensive, implementation-ready specifications that backend developers can directly use for coding.".to_string(),
@@ -534,7 +1064,7 @@ Partial description:
 - Maintain focus on implementation details
 
 Original description:
-{}
+{{input}}
 
 ".to_string(),
         generate_tasks_system_prompt: "You are a senior backend developer and project manager expert at breaking down server-side components into granular, executable development tasks. Focus on creating tasks that are specific, measurable, and can be directly implemented by backend developers.".to_string(),
@@ -559,7 +1089,7 @@ Based on the backend component description below, generate a prioritized list of
       \"dependencies\": [
         \"task_id or block_id (must use IDs only, not names)\"
       ],
-      \"estimated_effort\": \"S|M|L\",
+      \"estimated_effort\": \"small|medium|large\",
       \"files_affected\": [
         \"string\"
       ],
@@ -584,11 +1114,11 @@ Based on the backend component description below, generate a prioritized list of
 - Include relevant file names, function names, or code locations
 - Specify testing requirements including unit and integration tests
 - Indicate dependencies between tasks using task_id or block_id ONLY (never use names or descriptive strings)
-- Use effort indicators: S (Simple, 1-3 hours), M (Medium, 3-6 hours), L (Large, 6-8 hours)
+- Use effort indicators: small (1-3 hours), medium (3-6 hours), large (6-8 hours)
 - Task ID: task_id should be a random alpha numeric string of 6 characters.
 
 **Component Description:**
-{}
+{{input}}
 
 **Output Requirements:**
 - Return ONLY valid JSON
@@ -663,7 +1193,7 @@ create_task:
 
 Now analyze the following backend component description and create the appropriate tasks:
 
-{}".to_string(),
+{{input}}".to_string(),
         process_specification_system_prompt: "You are a backend architecture analyst expert at parsing technical specifications and extracting structured server-side implementation details. Your output must be valid JSON that can be directly consumed by backend development tools.".to_string(),
         process_specification_user_prompt: "Analyze the following backend technical specification markdown and extract structured implementation blocks for server-side components. 
 
@@ -698,7 +1228,7 @@ Now analyze the following backend component description and create the appropria
 - Ensure each service is self-contained where possible
 
 Specification document:
-{}
+{{input}}
 ".to_string(),
         process_specification_system_prompt_mcp: "You are a backend architecture analyst expert at parsing technical specifications and creating structured server-side implementation details using MCP tools. You will use the `create_block` and `create_task` MCP tools to directly create forge Blocks and Tasks for backend services.
 
@@ -761,7 +1291,7 @@ create_task:
 
 Now analyze the following specification and create the appropriate blocks and tasks:
 
-{}".to_string(),
+{{input}}".to_string(),
     }
 }
 
@@ -778,7 +1308,7 @@ fn create_fullstack_developer_prompts() -> ProfessionPrompts {
 Maintain the original intent and technical level. Be specific about technologies, frameworks, or architectural patterns when relevant.
 
 Partial description:
-{}
+{{input}}
 
 ".to_string(),
         enhance_description_system_prompt: "You are a technical writing expert specializing in fullstack development documentation. Transform brief component descriptions into comprehensive, implementation-ready specifications that cover both frontend and backend aspects.".to_string(),
@@ -800,7 +1330,7 @@ Partial description:
 - Maintain focus on implementation details
 
 Original description:
-{}
+{{input}}
 
 ".to_string(),
         generate_tasks_system_prompt: "You are a senior fullstack developer and project manager expert at breaking down software components into granular, executable development tasks covering both frontend and backend aspects.".to_string(),
@@ -872,7 +1402,7 @@ create_task:
 
 Now analyze the following fullstack component description and create the appropriate tasks:
 
-{}".to_string(),
+{{input}}".to_string(),
         process_specification_system_prompt: "You are a fullstack architecture analyst expert at parsing technical specifications and extracting structured implementation components for both frontend and backend systems.".to_string(),
         process_specification_user_prompt: crate::project_config::DEFAULT_PROCESS_MARKDOWN_SPEC_USER_PROMPT.to_string(),
         process_specification_system_prompt_mcp: "You are a fullstack architecture analyst expert at parsing technical specifications and creating structured implementation components for both frontend and backend systems using MCP tools. You will use the `create_block` and `create_task` MCP tools to directly create forge Blocks and Tasks based on specifications.
@@ -937,7 +1467,7 @@ create_task:
 
 Now analyze the following specification and create the appropriate blocks and tasks:
 
-{}".to_string(),
+{{input}}".to_string(),
     }
 }
 
@@ -953,7 +1483,7 @@ fn create_mobile_developer_prompts() -> ProfessionPrompts {
 Maintain the original intent and technical level. Be specific about technologies like iOS/Swift, Android/Kotlin, React Native, or Flutter when relevant.
 
 Partial description:
-{}
+{{input}}
 
 ".to_string(),
         enhance_description_system_prompt: "You are a technical writing expert specializing in mobile app development documentation. Transform brief component descriptions into comprehensive, implementation-ready specifications that mobile developers can directly use for coding.".to_string(),
@@ -975,7 +1505,7 @@ Partial description:
 - Maintain focus on implementation details
 
 Original description:
-{}
+{{input}}
 
 ".to_string(),
         generate_tasks_system_prompt: "You are a senior mobile developer and project manager expert at breaking down mobile app components into granular, executable development tasks. Focus on creating tasks that are specific, measurable, and can be directly implemented by mobile developers.".to_string(),
@@ -1047,7 +1577,7 @@ create_task:
 
 Now analyze the following mobile component description and create the appropriate tasks:
 
-{}".to_string(),
+{{input}}".to_string(),
         process_specification_system_prompt: "You are a mobile architecture analyst expert at parsing technical specifications and extracting structured implementation components for mobile applications. Your output must be valid JSON that can be directly consumed by mobile development tools.".to_string(),
         process_specification_user_prompt: crate::project_config::DEFAULT_PROCESS_MARKDOWN_SPEC_USER_PROMPT.to_string(),
         process_specification_system_prompt_mcp: crate::project_config::DEFAULT_PROCESS_MARKDOWN_SPEC_SYSTEM_PROMPT_MCP.to_string(),
@@ -1067,7 +1597,7 @@ fn create_devops_engineer_prompts() -> ProfessionPrompts {
 Maintain the original intent and technical level. Be specific about technologies like Docker, Kubernetes, AWS/Azure/GCP, or CI/CD tools when relevant.
 
 Partial description:
-{}
+{{input}}
 
 ".to_string(),
         enhance_description_system_prompt: "You are a technical writing expert specializing in DevOps and infrastructure documentation. Transform brief component descriptions into comprehensive, implementation-ready specifications that DevOps engineers can directly use.".to_string(),
@@ -1089,7 +1619,7 @@ Partial description:
 - Maintain focus on implementation details
 
 Original description:
-{}
+{{input}}
 
 ".to_string(),
         generate_tasks_system_prompt: "You are a senior DevOps engineer and project manager expert at breaking down infrastructure and automation components into granular, executable development tasks. Focus on creating tasks that are specific, measurable, and can be directly implemented by DevOps engineers.".to_string(),
@@ -1161,7 +1691,7 @@ create_task:
 
 Now analyze the following DevOps component description and create the appropriate tasks:
 
-{}".to_string(),
+{{input}}".to_string(),
         process_specification_system_prompt: "You are a DevOps architecture analyst expert at parsing technical specifications and extracting structured implementation components for infrastructure and automation. Your output must be valid JSON that can be directly consumed by DevOps tools.".to_string(),
         process_specification_user_prompt: crate::project_config::DEFAULT_PROCESS_MARKDOWN_SPEC_USER_PROMPT.to_string(),
         process_specification_system_prompt_mcp: crate::project_config::DEFAULT_PROCESS_MARKDOWN_SPEC_SYSTEM_PROMPT_MCP.to_string(),
@@ -1182,7 +1712,7 @@ fn create_ui_designer_prompts() -> ProfessionPrompts {
 Maintain the original intent and design language. Be specific about design tools, frameworks, or methodologies when relevant.
 
 Partial description:
-{}
+{{input}}
 
 ".to_string(),
         enhance_description_system_prompt: "You are a technical writing expert specializing in UI design documentation. Transform brief design descriptions into comprehensive, implementation-ready specifications that designers and developers can directly use.".to_string(),
@@ -1204,7 +1734,7 @@ Partial description:
 - Maintain focus on visual and interaction details
 
 Original description:
-{}
+{{input}}
 
 ".to_string(),
         generate_tasks_system_prompt: "You are a senior UI designer and project manager expert at breaking down design components into granular, executable design and implementation tasks. Focus on creating tasks that are specific, measurable, and can be directly implemented by designers and frontend developers.".to_string(),
@@ -1276,7 +1806,7 @@ create_task:
 
 Now analyze the following UI design component description and create the appropriate tasks:
 
-{}".to_string(),
+{{input}}".to_string(),
         process_specification_system_prompt: "You are a UI design analyst expert at parsing design specifications and extracting structured implementation components for interface elements. Your output must be valid JSON that can be directly consumed by design and development tools.".to_string(),
         process_specification_user_prompt: crate::project_config::DEFAULT_PROCESS_MARKDOWN_SPEC_USER_PROMPT.to_string(),
         process_specification_system_prompt_mcp: crate::project_config::DEFAULT_PROCESS_MARKDOWN_SPEC_SYSTEM_PROMPT_MCP.to_string(),
@@ -1296,7 +1826,7 @@ fn create_ux_designer_prompts() -> ProfessionPrompts {
 Maintain the original intent and design thinking. Be specific about UX methodologies, research techniques, or design principles when relevant.
 
 Partial description:
-{}
+{{input}}
 
 ".to_string(),
         enhance_description_system_prompt: "You are a technical writing expert specializing in UX design documentation. Transform brief experience design descriptions into comprehensive, implementation-ready specifications that UX designers and product teams can directly use.".to_string(),
@@ -1318,7 +1848,7 @@ Partial description:
 - Maintain focus on user-centered design principles
 
 Original description:
-{}
+{{input}}
 
 ".to_string(),
         generate_tasks_system_prompt: "You are a senior UX designer and project manager expert at breaking down experience design into granular, executable research and design tasks. Focus on creating tasks that are specific, measurable, and can be directly implemented by UX professionals.".to_string(),
@@ -1390,7 +1920,7 @@ create_task:
 
 Now analyze the following UX design component description and create the appropriate tasks:
 
-{}".to_string(),
+{{input}}".to_string(),
         process_specification_system_prompt: "You are a UX design analyst expert at parsing experience specifications and extracting structured implementation components for user flows and interactions. Your output must be valid JSON that can be directly consumed by design and product teams.".to_string(),
         process_specification_user_prompt: crate::project_config::DEFAULT_PROCESS_MARKDOWN_SPEC_USER_PROMPT.to_string(),
         process_specification_system_prompt_mcp: crate::project_config::DEFAULT_PROCESS_MARKDOWN_SPEC_SYSTEM_PROMPT_MCP.to_string(),
@@ -1411,7 +1941,7 @@ fn create_data_scientist_prompts() -> ProfessionPrompts {
 Maintain the original intent and technical level. Be specific about statistical methods, ML frameworks, or data processing techniques when relevant.
 
 Partial description:
-{}
+{{input}}
 
 ".to_string(),
         enhance_description_system_prompt: "You are a technical writing expert specializing in data science documentation. Transform brief analytical component descriptions into comprehensive, implementation-ready specifications that data scientists can directly use.".to_string(),
@@ -1433,7 +1963,7 @@ Partial description:
 - Maintain focus on implementation details
 
 Original description:
-{}
+{{input}}
 
 ".to_string(),
         generate_tasks_system_prompt: "You are a senior data scientist and project manager expert at breaking down analytical components into granular, executable data science tasks. Focus on creating tasks that are specific, measurable, and can be directly implemented by data scientists.".to_string(),
@@ -1505,7 +2035,7 @@ create_task:
 
 Now analyze the following data science component description and create the appropriate tasks:
 
-{}".to_string(),
+{{input}}".to_string(),
         process_specification_system_prompt: "You are a data science architecture analyst expert at parsing analytical specifications and extracting structured implementation components for data processing and modeling. Your output must be valid JSON that can be directly consumed by data science tools.".to_string(),
         process_specification_user_prompt: crate::project_config::DEFAULT_PROCESS_MARKDOWN_SPEC_USER_PROMPT.to_string(),
         process_specification_system_prompt_mcp: crate::project_config::DEFAULT_PROCESS_MARKDOWN_SPEC_SYSTEM_PROMPT_MCP.to_string(),
@@ -1525,7 +2055,7 @@ fn create_ml_engineer_prompts() -> ProfessionPrompts {
 Maintain the original intent and technical level. Be specific about ML frameworks, model architectures, or MLOps practices when relevant.
 
 Partial description:
-{}
+{{input}}
 
 ".to_string(),
         enhance_description_system_prompt: "You are a technical writing expert specializing in machine learning engineering documentation. Transform brief ML component descriptions into comprehensive, implementation-ready specifications that ML engineers can directly use for coding.".to_string(),
@@ -1547,7 +2077,7 @@ Partial description:
 - Maintain focus on implementation details
 
 Original description:
-{}
+{{input}}
 
 ".to_string(),
         generate_tasks_system_prompt: "You are a senior machine learning engineer and project manager expert at breaking down ML components into granular, executable development tasks. Focus on creating tasks that are specific, measurable, and can be directly implemented by ML engineers.".to_string(),
@@ -1573,7 +2103,7 @@ fn create_software_architect_prompts() -> ProfessionPrompts {
 Maintain the original intent and technical level. Be specific about architectural patterns, system boundaries, or technical constraints when relevant.
 
 Partial description:
-{}
+{{input}}
 
 ".to_string(),
         enhance_description_system_prompt: "You are a technical writing expert specializing in software architecture documentation. Transform brief architectural descriptions into comprehensive, implementation-ready specifications that development teams can directly use.".to_string(),
@@ -1595,7 +2125,7 @@ Partial description:
 - Maintain focus on system-level design decisions
 
 Original description:
-{}
+{{input}}
 
 ".to_string(),
         generate_tasks_system_prompt: "You are a senior software architect and project manager expert at breaking down architectural components into granular, executable development tasks. Focus on creating tasks that are specific, measurable, and can be directly implemented by development teams.".to_string(),
@@ -1621,7 +2151,7 @@ fn create_tech_lead_prompts() -> ProfessionPrompts {
 Maintain the original intent and technical level. Be specific about technologies, team structure, or delivery constraints when relevant.
 
 Partial description:
-{}
+{{input}}
 
 ".to_string(),
         enhance_description_system_prompt: "You are a technical writing expert specializing in technical leadership documentation. Transform brief component descriptions into comprehensive, implementation-ready specifications that development teams can directly use while considering team capabilities and project constraints.".to_string(),
@@ -1643,7 +2173,7 @@ Partial description:
 - Maintain focus on both technical details and delivery aspects
 
 Original description:
-{}
+{{input}}
 
 ".to_string(),
         generate_tasks_system_prompt: "You are a senior technical lead and project manager expert at breaking down software components into granular, executable development tasks. Focus on creating tasks that are specific, measurable, and can be directly assigned to team members with appropriate skills.".to_string(),
@@ -1669,7 +2199,7 @@ fn create_product_manager_prompts() -> ProfessionPrompts {
 Maintain the original intent and product vision. Be specific about user needs, market positioning, or strategic goals when relevant.
 
 Partial description:
-{}
+{{input}}
 
 ".to_string(),
         enhance_description_system_prompt: "You are a technical writing expert specializing in product management documentation. Transform brief feature descriptions into comprehensive, implementation-ready specifications that product and development teams can directly use.".to_string(),
@@ -1691,7 +2221,7 @@ Partial description:
 - Maintain focus on user value and business outcomes
 
 Original description:
-{}
+{{input}}
 
 ".to_string(),
         generate_tasks_system_prompt: "You are a senior product manager expert at breaking down product features into granular, executable development tasks. Focus on creating tasks that are specific, measurable, and can be directly implemented by development teams while maintaining alignment with product goals.".to_string(),
@@ -1717,7 +2247,7 @@ fn create_project_manager_prompts() -> ProfessionPrompts {
 Maintain the original intent and project scope. Be specific about delivery methodologies, team structure, or project constraints when relevant.
 
 Partial description:
-{}
+{{input}}
 
 ".to_string(),
         enhance_description_system_prompt: "You are a technical writing expert specializing in project management documentation. Transform brief project component descriptions into comprehensive, implementation-ready specifications that project teams can directly use for planning and execution.".to_string(),
@@ -1739,7 +2269,7 @@ Partial description:
 - Maintain focus on delivery planning and execution
 
 Original description:
-{}
+{{input}}
 
 ".to_string(),
         generate_tasks_system_prompt: "You are a senior project manager expert at breaking down project components into granular, executable tasks with clear ownership and timelines. Focus on creating tasks that are specific, measurable, and can be directly assigned to team members.".to_string(),
@@ -1765,7 +2295,7 @@ fn create_security_engineer_prompts() -> ProfessionPrompts {
 Maintain the original intent and technical level. Be specific about security standards, attack vectors, or defensive techniques when relevant.
 
 Partial description:
-{}
+{{input}}
 
 ".to_string(),
         enhance_description_system_prompt: "You are a technical writing expert specializing in security engineering documentation. Transform brief security component descriptions into comprehensive, implementation-ready specifications that security engineers can directly use for implementation.".to_string(),
@@ -1787,7 +2317,7 @@ Partial description:
 - Maintain focus on implementation details
 
 Original description:
-{}
+{{input}}
 
 ".to_string(),
         generate_tasks_system_prompt: "You are a senior security engineer and project manager expert at breaking down security components into granular, executable development tasks. Focus on creating tasks that are specific, measurable, and can be directly implemented by security and development teams.".to_string(),
@@ -1813,7 +2343,7 @@ fn create_cloud_engineer_prompts() -> ProfessionPrompts {
 Maintain the original intent and technical level. Be specific about cloud providers (AWS, Azure, GCP), IaC tools, or service configurations when relevant.
 
 Partial description:
-{}
+{{input}}
 
 ".to_string(),
         enhance_description_system_prompt: "You are a technical writing expert specializing in cloud engineering documentation. Transform brief cloud component descriptions into comprehensive, implementation-ready specifications that cloud engineers can directly use for deployment.".to_string(),
@@ -1835,7 +2365,7 @@ Partial description:
 - Maintain focus on implementation details
 
 Original description:
-{}
+{{input}}
 
 ".to_string(),
         generate_tasks_system_prompt: "You are a senior cloud engineer and project manager expert at breaking down cloud infrastructure components into granular, executable development tasks. Focus on creating tasks that are specific, measurable, and can be directly implemented by cloud engineers.".to_string(),
@@ -1861,7 +2391,7 @@ fn create_growth_engineer_prompts() -> ProfessionPrompts {
 Maintain the original intent and technical level. Be specific about analytics tools, growth frameworks, or experimentation methodologies when relevant.
 
 Partial description:
-{}
+{{input}}
 
 ".to_string(),
         enhance_description_system_prompt: "You are a technical writing expert specializing in growth engineering documentation. Transform brief growth component descriptions into comprehensive, implementation-ready specifications that growth engineers can directly use for implementation.".to_string(),
@@ -1883,7 +2413,7 @@ Partial description:
 - Maintain focus on measurable outcomes and implementation details
 
 Original description:
-{}
+{{input}}
 
 ".to_string(),
         generate_tasks_system_prompt: "You are a senior growth engineer and project manager expert at breaking down growth initiatives into granular, executable development tasks. Focus on creating tasks that are specific, measurable, and can be directly implemented by growth and development teams.".to_string(),
@@ -1909,7 +2439,7 @@ fn create_technical_writer_prompts() -> ProfessionPrompts {
 Maintain the original intent and technical level. Be specific about documentation types, tools, or standards when relevant.
 
 Partial description:
-{}
+{{input}}
 
 ".to_string(),
         enhance_description_system_prompt: "You are a documentation expert specializing in technical writing for software products. Transform brief documentation descriptions into comprehensive, implementation-ready specifications that technical writers can directly use.".to_string(),
@@ -1931,7 +2461,7 @@ Partial description:
 - Maintain focus on user comprehension and information architecture
 
 Original description:
-{}
+{{input}}
 
 ".to_string(),
         generate_tasks_system_prompt: "You are a senior technical writer and project manager expert at breaking down documentation projects into granular, executable writing tasks. Focus on creating tasks that are specific, measurable, and can be directly implemented by documentation teams.".to_string(),
@@ -1956,7 +2486,7 @@ fn create_custom_prompts() -> ProfessionPrompts {
 Maintain the original intent and ______ ______. Be specific about ______ types, tools, or ______ when relevant.
 
 Partial description:
-{}
+{{input}}
 
 ".to_string(),
         enhance_description_system_prompt: "You are a ______ expert specializing in ______ ______ for ______ ______. Transform brief ______ descriptions into comprehensive, ______ ready ______ that ______ can directly use.".to_string(),
@@ -1978,7 +2508,7 @@ Partial description:
 - Maintain focus on user comprehension and information architecture
 
 Original description:
-{}
+{{input}}
 
 ".to_string(),
         generate_tasks_system_prompt: "You are a senior ______ ______ and ______ expert at breaking down ______ projects into granular, e______ tasks. Focus on creating tasks that are specific, measurable, and can be directly implemented by ______ teams.".to_string(),
@@ -2008,3 +2538,89 @@ pub fn get_default_prompts(profession_id: Option<&str>) -> ProfessionPrompts {
         None => create_software_architect_prompts(), // Default to software architect
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_custom_profession_id() {
+        let taken = vec!["custom_qa_lead".to_string(), "custom_qa_lead_2".to_string()];
+        assert_eq!(custom_profession_id("Data Engineer", &taken), "custom_data_engineer");
+        assert_eq!(custom_profession_id("QA / Lead", &taken), "custom_qa_lead_3");
+        assert_eq!(custom_profession_id("!!!", &taken), "custom_profession");
+    }
+
+    #[test]
+    fn test_validate_prompt() {
+        assert!(validate_prompt("generate_tasks_user_prompt", "Break down:\n{{input}}").is_ok());
+        assert!(validate_prompt("generate_tasks_user_prompt", "Break down:\n{}").is_ok());
+        assert!(validate_prompt("generate_tasks_system_prompt", "You are a developer").is_ok());
+        assert!(validate_prompt("unknown_prompt", "{}").unwrap_err().contains("Unknown prompt"));
+        assert!(validate_prompt("enhance_description_user_prompt", "Improve the description").unwrap_err().contains("placeholder"));
+
+        assert!(validate_prompt("process_specification_user_prompt_mcp", "Use `create_block` and `create_task` for:\n{}").is_ok());
+        let error = validate_prompt("generate_tasks_user_prompt_mcp", "Use `create_tasks` for:\n{}").unwrap_err();
+        assert!(error.contains("Unknown MCP tools referenced: create_tasks"));
+
+        // The compiled-in prompts pass their own validation
+        for profession in built_in_professions() {
+            let mut prompts = profession.prompts.clone();
+            for key in PROMPT_KEYS {
+                let value = prompts.prompt_mut(key).unwrap().clone();
+                assert!(validate_prompt(key, &value).is_ok(), "{} {}", profession.id, key);
+            }
+        }
+    }
+
+    #[test]
+    fn test_merge_prompt_pack() {
+        let built_in = built_in_professions();
+        let writer = built_in.iter().find(|p| p.id == "technical_writer").unwrap().clone();
+        let mut tuned_writer = writer.clone();
+        tuned_writer.prompts.auto_complete_system_prompt = "You are a terse technical writer.".to_string();
+        let mut localizer = writer.clone();
+        localizer.id = "custom_localizer".to_string();
+        localizer.name = "Localizer".to_string();
+        let mut broken = localizer.clone();
+        broken.id = "custom_broken".to_string();
+        broken.prompts.enhance_description_user_prompt = "No placeholder".to_string();
+
+        let pack = PromptPack {
+            format_version: PROMPT_PACK_FORMAT_VERSION,
+            name: "Team prompts".to_string(),
+            version: "1.0.0".to_string(),
+            description: String::new(),
+            author: None,
+            exported_at: String::new(),
+            professions: vec![tuned_writer, localizer, broken],
+        };
+
+        // Overwriting a built-in profession stores only the changed prompts
+        let mut custom = Vec::new();
+        let mut overrides = PromptOverrides::new();
+        let report = merge_prompt_pack(pack.clone(), PackConflictStrategy::Overwrite, &built_in, &mut custom, &mut overrides);
+        assert_eq!(report.overwritten, vec!["technical_writer"]);
+        assert_eq!(report.imported, vec!["custom_localizer"]);
+        assert_eq!(report.errors.len(), 1);
+        assert_eq!(overrides["technical_writer"].len(), 1);
+
+        // Importing again renames the conflicting professions
+        let report = merge_prompt_pack(pack, PackConflictStrategy::Rename, &built_in, &mut custom, &mut overrides);
+        assert_eq!(report.renamed, vec![
+            ("technical_writer".to_string(), "custom_technical_writer_imported".to_string()),
+            ("custom_localizer".to_string(), "custom_localizer_imported".to_string()),
+        ]);
+        assert_eq!(custom.len(), 3);
+        assert!(parse_prompt_pack("format_version: 9\nname: x\nprofessions: []").unwrap_err().contains("newer"));
+    }
+
+    #[test]
+    fn test_validate_name() {
+        let professions = built_in_professions();
+        assert!(validate_name("  ", None, &professions).is_err());
+        assert!(validate_name("technical writer", None, &professions).is_err());
+        assert!(validate_name("Technical Writer", Some("technical_writer"), &professions).is_ok());
+        assert!(validate_name("Localization Engineer", None, &professions).is_ok());
+    }
+}
@@ -1,12 +1,27 @@
+use crate::prompt_history::{self, PromptState, PROJECT_SCOPE};
+use crate::secrets;
+use base64::Engine;
 use lazy_static::lazy_static;
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
 use std::io::{self};
 use std::path::Path;
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Mutex, RwLock};
 use std::fs;
+use std::time::Duration;
 
 pub const PROJECT_CONFIG_FILE: &str = "project_config.json";
 
+// Environment variable selecting the config overlay, e.g. FORGE_ENV=dev loads project_config.dev.json
+// over project_config.json; the --env flag takes precedence
+pub const CONFIG_ENVIRONMENT_VAR: &str = "FORGE_ENV";
+
+// Address of the web server when the project doesn't configure one
+pub const DEFAULT_SERVER_ADDRESS: &str = "127.0.0.1:8080";
+
+// Time running work gets to finish on shutdown when the project doesn't configure one
+pub const DEFAULT_SHUTDOWN_DRAIN_TIMEOUT_SECONDS: u64 = 60;
+
 // Default prompts for LLM
 // Improved prompts for LLM with enhanced specificity and structure
 
@@ -20,7 +35,7 @@ pub const DEFAULT_AUTO_COMPLETE_USER_PROMPT: &str = "Complete the following part
 Maintain the original intent and technical level. Be specific about technologies, patterns, or frameworks when relevant.
 
 Partial description:
-{}
+{{input}}
 
 Complete description:";
 
@@ -43,7 +58,7 @@ pub const DEFAULT_ENHANCE_DESCRIPTION_USER_PROMPT: &str = "Transform the followi
 - Maintain focus on implementation details
 
 Original description:
-{}
+{{input}}
 ";
 
 pub const DEFAULT_GENERATE_TASKS_SYSTEM_PROMPT: &str = "You are a senior software developer and project manager expert at breaking down software components into granular, executable development tasks. Focus on creating tasks that are specific, measurable, and can be directly implemented by developers.";
@@ -60,7 +75,7 @@ Based on the software component description below, generate a prioritized list o
   \"total_tasks\": number,
   \"tasks\": [
     {
-      \"task_id\": string,
+      \"task_id\": \"string\",
       \"task_name\": \"string\",
       \"description\": \"string\", 
       \"acceptance_criteria\": [
@@ -69,7 +84,7 @@ Based on the software component description below, generate a prioritized list o
       \"dependencies\": [
         \"task_id\"
       ],
-      \"estimated_effort\": \"S|M|L\",
+      \"estimated_effort\": \"small|medium|large\",
       \"files_affected\": [
         \"string\"
       ],
@@ -81,7 +96,7 @@ Based on the software component description below, generate a prioritized list o
       ],
       \"log\": \"\",
       \"commit_id\": \"\",
-      \"status\": \"[TODO]\", 
+      \"status\": \"[TODO]\"
     }
   ]
 }
@@ -93,11 +108,11 @@ Based on the software component description below, generate a prioritized list o
 - Include relevant file names, function signatures, or code locations
 - Specify testing requirements where applicable
 - Indicate dependencies between tasks using task_id only (no names or other identifiers)
-- Use effort indicators: S (Simple, 1-3 hours), M (Medium, 3-6 hours), L (Large, 6-8 hours)
+- Use effort indicators: small (1-3 hours), medium (3-6 hours), large (6-8 hours)
 - Task ID: task_id should be a random alpha numeric string of 6 characters.
 
 **Component Description:**
-{}
+{{input}}
 
 **Output Requirements:**
 - Return ONLY valid JSON
@@ -111,6 +126,7 @@ pub const DEFAULT_GENERATE_TASKS_SYSTEM_PROMPT_MCP: &str = "You are a senior sof
 
 **Available MCP Tools:**
 - `create_task`: Creates a detailed task with comprehensive metadata including acceptance criteria, dependencies, effort estimation, and testing requirements
+- `lookup_term`: Looks up domain terms and canonical component names in the project glossary
 
 **Your Role:**
 - Analyze software component descriptions and identify implementation requirements
@@ -177,7 +193,7 @@ create_task:
 
 Now analyze the following component description and create the appropriate tasks:
 
-{}";
+{{input}}";
 
 pub const DEFAULT_PROCESS_MARKDOWN_SPEC_SYSTEM_PROMPT: &str = "You are a software architecture analyst expert at parsing technical specifications and extracting structured implementation components. Your output must be valid JSON that can be directly consumed by automated development tools.";
 
@@ -203,7 +219,9 @@ pub const DEFAULT_PROCESS_MARKDOWN_SPEC_USER_PROMPT: &str = "Analyze the followi
   \"outputs\": [
     {\"name\": \"outputName\", \"ctype\": \"dataType\", \"description\": \"expected result format\"}
   ],
-  \"dependencies\": [\"block_abc123\", \"block_def456\"]
+  \"dependencies\": [\"block_abc123\", \"block_def456\"],
+  \"epic\": \"Feature area name\",
+  \"source_sections\": [\"Exact markdown heading the block was derived from\"]
 }
 ```
 
@@ -211,18 +229,21 @@ pub const DEFAULT_PROCESS_MARKDOWN_SPEC_USER_PROMPT: &str = "Analyze the followi
 - Extract only implementable components (ignore documentation sections)
 - Infer missing technical details from context
 - Group related functionality into logical blocks
+- Assign each block to an epic (feature area) using the same epic name for related blocks
+- List in source_sections the exact headings of the specification sections each block was derived from
 - Ensure each block is self-contained where possible
 
 Specification document:
-{}
+{{input}}
 ";
 
 // MCP-based prompts for processing specifications using create_block and create_task tools
 pub const DEFAULT_PROCESS_MARKDOWN_SPEC_SYSTEM_PROMPT_MCP: &str = "You are a software architecture analyst expert at parsing technical specifications and creating structured implementation components using MCP tools. You will use the `create_block` and `create_task` MCP tools to directly create forge Blocks and their associated Tasks based on specifications.
 
 **Available MCP Tools:**
-- `create_block`: Creates a new block with name, description, and optional block_id
+- `create_block`: Creates a new block with name, description, optional block_id, optional epic and optional source_sections
 - `create_task`: Creates a detailed task for a block with comprehensive metadata
+- `lookup_term`: Looks up domain terms and canonical component names in the project glossary
 
 **Your Role:**
 - Parse technical specifications and identify implementation components
@@ -239,6 +260,8 @@ pub const DEFAULT_PROCESS_MARKDOWN_SPEC_USER_PROMPT_MCP: &str = "Analyze the fol
    - Clear, descriptive names (CamelCase)
    - Detailed implementation descriptions
    - Technical specifics and scope
+   - The epic (feature area) the block belongs to, reusing the same epic name for related blocks
+   - The exact headings of the specification sections the block was derived from (source_sections)
 3. **Create tasks** using `create_task` for each implementation requirement with:
    - Specific, actionable task names
    - Detailed descriptions of what needs to be implemented
@@ -274,7 +297,9 @@ pub const DEFAULT_PROCESS_MARKDOWN_SPEC_USER_PROMPT_MCP: &str = "Analyze the fol
 create_block:
 {
   \"name\": \"UserAuthenticationService\",
-  \"description\": \"Handles user authentication with JWT tokens, password hashing, and session management\"
+  \"description\": \"Handles user authentication with JWT tokens, password hashing, and session management\",
+  \"epic\": \"Identity and Access\",
+  \"source_sections\": [\"Authentication\"]
 }
 
 create_task:
@@ -293,9 +318,29 @@ create_task:
 
 Now analyze the following specification and create the appropriate blocks and tasks:
 
-{}
+{{input}}
 ";
 
+// User-configurable prompts by key with the compiled-in default each one falls back to
+pub const PROMPT_DEFAULTS: &[(&str, &str)] = &[
+    ("auto_complete_system_prompt", DEFAULT_AUTO_COMPLETE_SYSTEM_PROMPT),
+    ("auto_complete_user_prompt", DEFAULT_AUTO_COMPLETE_USER_PROMPT),
+    ("enhance_description_system_prompt", DEFAULT_ENHANCE_DESCRIPTION_SYSTEM_PROMPT),
+    ("enhance_description_user_prompt", DEFAULT_ENHANCE_DESCRIPTION_USER_PROMPT),
+    ("generate_tasks_system_prompt", DEFAULT_GENERATE_TASKS_SYSTEM_PROMPT),
+    ("generate_tasks_user_prompt", DEFAULT_GENERATE_TASKS_USER_PROMPT),
+    ("generate_tasks_system_prompt_mcp", DEFAULT_GENERATE_TASKS_SYSTEM_PROMPT_MCP),
+    ("generate_tasks_user_prompt_mcp", DEFAULT_GENERATE_TASKS_USER_PROMPT_MCP),
+    ("process_specification_system_prompt", DEFAULT_PROCESS_MARKDOWN_SPEC_SYSTEM_PROMPT),
+    ("process_specification_user_prompt", DEFAULT_PROCESS_MARKDOWN_SPEC_USER_PROMPT),
+    ("process_specification_system_prompt_mcp", DEFAULT_PROCESS_MARKDOWN_SPEC_SYSTEM_PROMPT_MCP),
+    ("process_specification_user_prompt_mcp", DEFAULT_PROCESS_MARKDOWN_SPEC_USER_PROMPT_MCP),
+];
+
+pub fn default_prompt(key: &str) -> Option<&'static str> {
+    PROMPT_DEFAULTS.iter().find(|(k, _)| *k == key).map(|(_, default)| *default)
+}
+
 // Additional helper prompts for common scenarios
 pub const DEFAULT_CODE_REVIEW_SYSTEM_PROMPT: &str = "You are a senior code reviewer with expertise in software quality, security, and maintainability. Provide constructive feedback focused on improvements that enhance code reliability and developer productivity.";
 
@@ -309,7 +354,7 @@ pub const DEFAULT_CODE_REVIEW_USER_PROMPT: &str = "Review the following code for
 Provide specific, actionable recommendations with examples where helpful.
 
 Code to review:
-{}
+{{input}}
 
 Review feedback:";
 
@@ -331,26 +376,1081 @@ pub const DEFAULT_REFACTOR_USER_PROMPT: &str = "Analyze the following code and s
 - Prioritize changes by impact
 
 Code to refactor:
-{}
+{{input}}
 
 Refactoring suggestions:";
 
 
+// Canonical task statuses used by the default workflow
+pub const TASK_STATUS_TODO: &str = "[TODO]";
+pub const TASK_STATUS_IN_PROGRESS: &str = "[IN-PROGRESS]";
+pub const TASK_STATUS_COMPLETED: &str = "[COMPLETED]";
+pub const TASK_STATUS_FAILED: &str = "[FAILED]";
+pub const TASK_STATUS_CANCELLED: &str = "[CANCELLED]";
+
+// Task workflow: the set of valid statuses and the transitions allowed between them
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskWorkflow {
+    // Valid statuses, the first one is used as the initial status of new tasks
+    pub statuses: Vec<String>,
+    // Allowed transitions: status -> statuses it may move to
+    pub transitions: HashMap<String, Vec<String>>,
+    // Alternative spellings mapped to a canonical status (e.g. "DONE" -> "[COMPLETED]")
+    #[serde(default)]
+    pub aliases: HashMap<String, String>,
+}
+
+impl Default for TaskWorkflow {
+    fn default() -> Self {
+        let mut transitions = HashMap::new();
+        transitions.insert(TASK_STATUS_TODO.to_string(), vec![
+            TASK_STATUS_IN_PROGRESS.to_string(),
+            TASK_STATUS_COMPLETED.to_string(),
+            TASK_STATUS_CANCELLED.to_string(),
+        ]);
+        transitions.insert(TASK_STATUS_IN_PROGRESS.to_string(), vec![
+            TASK_STATUS_TODO.to_string(),
+            TASK_STATUS_COMPLETED.to_string(),
+            TASK_STATUS_FAILED.to_string(),
+            TASK_STATUS_CANCELLED.to_string(),
+        ]);
+        transitions.insert(TASK_STATUS_COMPLETED.to_string(), vec![
+            TASK_STATUS_TODO.to_string(),
+            TASK_STATUS_IN_PROGRESS.to_string(),
+        ]);
+        transitions.insert(TASK_STATUS_FAILED.to_string(), vec![
+            TASK_STATUS_TODO.to_string(),
+            TASK_STATUS_IN_PROGRESS.to_string(),
+        ]);
+        transitions.insert(TASK_STATUS_CANCELLED.to_string(), vec![
+            TASK_STATUS_TODO.to_string(),
+            TASK_STATUS_IN_PROGRESS.to_string(),
+        ]);
+
+        let mut aliases = HashMap::new();
+        aliases.insert("DONE".to_string(), TASK_STATUS_COMPLETED.to_string());
+        aliases.insert("IN_PROGRESS".to_string(), TASK_STATUS_IN_PROGRESS.to_string());
+        aliases.insert("INPROGRESS".to_string(), TASK_STATUS_IN_PROGRESS.to_string());
+        aliases.insert("ERROR".to_string(), TASK_STATUS_FAILED.to_string());
+        aliases.insert("CANCELED".to_string(), TASK_STATUS_CANCELLED.to_string());
+
+        Self {
+            statuses: vec![
+                TASK_STATUS_TODO.to_string(),
+                TASK_STATUS_IN_PROGRESS.to_string(),
+                TASK_STATUS_COMPLETED.to_string(),
+                TASK_STATUS_FAILED.to_string(),
+                TASK_STATUS_CANCELLED.to_string(),
+            ],
+            transitions,
+            aliases,
+        }
+    }
+}
+
+impl TaskWorkflow {
+    // Strip brackets and whitespace and uppercase, so "[todo]" and "TODO" compare equal
+    fn status_key(status: &str) -> String {
+        status.trim().trim_start_matches('[').trim_end_matches(']').trim().to_uppercase()
+    }
+
+    // Initial status for newly created tasks
+    pub fn initial_status(&self) -> String {
+        self.statuses.first().cloned().unwrap_or_else(|| TASK_STATUS_TODO.to_string())
+    }
+
+    // Map a free-form status string onto a canonical workflow status
+    pub fn normalize_status(&self, status: &str) -> Result<String, String> {
+        if status.trim().is_empty() {
+            return Ok(self.initial_status());
+        }
+
+        let key = Self::status_key(status);
+        if let Some(canonical) = self.statuses.iter().find(|s| Self::status_key(s) == key) {
+            return Ok(canonical.clone());
+        }
+
+        if let Some((_, target)) = self.aliases.iter().find(|(alias, _)| Self::status_key(alias) == key) {
+            if let Some(canonical) = self.statuses.iter().find(|s| Self::status_key(s) == Self::status_key(target)) {
+                return Ok(canonical.clone());
+            }
+        }
+
+        Err(format!("Invalid task status '{}'. Valid statuses: {}", status, self.statuses.join(", ")))
+    }
+
+    // Check if a task may move from one status to another
+    pub fn can_transition(&self, from: &str, to: &str) -> bool {
+        let to = match self.normalize_status(to) {
+            Ok(to) => to,
+            Err(_) => return false,
+        };
+
+        // Tasks with a legacy or unknown status may be moved into any valid status
+        let from = match self.normalize_status(from) {
+            Ok(from) => from,
+            Err(_) => return true,
+        };
+
+        if from == to {
+            return true;
+        }
+
+        self.transitions.get(&from)
+            .map(|targets| targets.iter().any(|t| Self::status_key(t) == Self::status_key(&to)))
+            .unwrap_or(false)
+    }
+
+    // Normalize the target status and verify the transition, returning the canonical status
+    pub fn validate_transition(&self, from: &str, to: &str) -> Result<String, String> {
+        let canonical = self.normalize_status(to)?;
+        if !self.can_transition(from, &canonical) {
+            return Err(format!("Transition from '{}' to '{}' is not allowed", from, canonical));
+        }
+        Ok(canonical)
+    }
+
+    // Check whether a status counts as completed
+    pub fn is_completed(&self, status: &str) -> bool {
+        self.normalize_status(status)
+            .map(|s| s == TASK_STATUS_COMPLETED)
+            .unwrap_or(false)
+    }
+}
+
+// LLM operations that can use their own provider
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LLMOperation {
+    EnhanceDescription,
+    AutoComplete,
+    GenerateTasks,
+    ProcessSpecification,
+}
+
+impl LLMOperation {
+    pub fn name(&self) -> &'static str {
+        match self {
+            LLMOperation::EnhanceDescription => "enhance_description",
+            LLMOperation::AutoComplete => "auto_complete",
+            LLMOperation::GenerateTasks => "generate_tasks",
+            LLMOperation::ProcessSpecification => "process_specification",
+        }
+    }
+}
+
+// Provider overrides per LLM operation; operations without an override use llm_provider
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OperationProviders {
+    pub enhance_description: Option<crate::llm_handler::LLMProvider>,
+    pub auto_complete: Option<crate::llm_handler::LLMProvider>,
+    pub generate_tasks: Option<crate::llm_handler::LLMProvider>,
+    pub process_specification: Option<crate::llm_handler::LLMProvider>,
+}
+
+// Model overrides per operation, e.g. a cheap model for auto-complete and a strong one for
+// specifications; operations without an override use the model configured for their provider
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OperationModels {
+    pub enhance_description: Option<String>,
+    pub auto_complete: Option<String>,
+    pub generate_tasks: Option<String>,
+    pub process_specification: Option<String>,
+    // Model of the Claude CLI executing tasks
+    pub execute_task: Option<String>,
+}
+
+// Audit log of LLM requests and responses
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LLMAuditConfig {
+    pub enabled: bool,
+    // Longer prompts and responses are truncated; unlimited if unset
+    pub max_body_chars: Option<usize>,
+    // Size at which the oldest half of the log is dropped
+    pub max_size_mb: u64,
+}
+
+impl Default for LLMAuditConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_body_chars: Some(20000),
+            max_size_mb: 50,
+        }
+    }
+}
+
+// Audit log of the API calls changing something
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RequestAuditConfig {
+    pub enabled: bool,
+    // Longer payload summaries are truncated
+    pub max_payload_chars: usize,
+    // Size at which the oldest half of the log is dropped
+    pub max_size_mb: u64,
+}
+
+impl Default for RequestAuditConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_payload_chars: 2000,
+            max_size_mb: 50,
+        }
+    }
+}
+
+// Entry of the fallback chain: provider (and model) tried when the previous one fails
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct LLMFallback {
+    pub provider: crate::llm_handler::LLMProvider,
+    // Model of the provider, defaults to the provider's configured model
+    #[serde(default)]
+    pub model: Option<String>,
+}
+
+// Token budgets of LLM calls; calls that would exceed them are rejected
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TokenBudget {
+    pub max_tokens_per_request: Option<u64>,
+    // Budget of a UTC day, across all providers
+    pub max_tokens_per_day: Option<u64>,
+}
+
+// Price of an LLM model in USD per million tokens
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ModelPrice {
+    pub input_per_million: f64,
+    pub output_per_million: f64,
+}
+
+// Glossary entry: a domain term, abbreviation or canonical component name
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct GlossaryTerm {
+    pub term: String,
+    pub definition: String,
+    #[serde(default)]
+    pub aliases: Vec<String>,
+}
+
+impl GlossaryTerm {
+    fn matches_exactly(&self, query: &str) -> bool {
+        self.term.eq_ignore_ascii_case(query) || self.aliases.iter().any(|a| a.eq_ignore_ascii_case(query))
+    }
+
+    fn matches_partially(&self, query: &str) -> bool {
+        let query = query.to_lowercase();
+        self.term.to_lowercase().contains(&query)
+            || self.aliases.iter().any(|a| a.to_lowercase().contains(&query))
+    }
+}
+
+// Schedule and retention of automatic project data backups
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupConfig {
+    pub enabled: bool,
+    // Minutes between two automatic backups
+    pub interval_minutes: u64,
+    // Number of backups kept; older ones are removed
+    pub max_backups: usize,
+}
+
+impl Default for BackupConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            interval_minutes: 60,
+            max_backups: 24,
+        }
+    }
+}
+
+// Retention of the server and task log files; the oldest files are pruned first
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LogRetention {
+    // Days log files are kept, unlimited if unset
+    pub max_days: Option<u64>,
+    // Total size of the log files in MB, unlimited if unset
+    pub max_total_mb: Option<u64>,
+}
+
+impl Default for LogRetention {
+    fn default() -> Self {
+        Self {
+            max_days: Some(30),
+            max_total_mb: Some(1024),
+        }
+    }
+}
+
+// Limits of the MCP server sessions; unset values use the defaults of the server mode
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct McpSessionConfig {
+    // Sessions open at the same time
+    pub max_sessions: Option<usize>,
+    // Seconds without activity after which a session is closed
+    pub idle_timeout_seconds: Option<u64>,
+    // Seconds between two runs of the session cleanup
+    pub cleanup_interval_seconds: Option<u64>,
+}
+
+impl McpSessionConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.max_sessions == Some(0) {
+            return Err("max_sessions must be at least 1".to_string());
+        }
+        if self.idle_timeout_seconds == Some(0) {
+            return Err("idle_timeout_seconds must be at least 1".to_string());
+        }
+        if self.cleanup_interval_seconds == Some(0) {
+            return Err("cleanup_interval_seconds must be at least 1".to_string());
+        }
+        Ok(())
+    }
+}
+
+// Permission granted by an API key of the HTTP server
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ApiScope {
+    // Read the blocks, tasks, logs and settings
+    Read,
+    // Change the blocks and tasks; the project settings and secrets need every scope
+    Write,
+    // Run tasks and git operations, and schedule them
+    Execute,
+}
+
+impl ApiScope {
+    pub const ALL: [ApiScope; 3] = [ApiScope::Read, ApiScope::Write, ApiScope::Execute];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ApiScope::Read => "read",
+            ApiScope::Write => "write",
+            ApiScope::Execute => "execute",
+        }
+    }
+}
+
+// API key of the HTTP server; only the SHA-256 hash of the key is stored
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiKey {
+    pub name: String,
+    pub key_hash: String,
+    // First characters of the key, to tell the keys apart
+    pub prefix: String,
+    pub scopes: Vec<ApiScope>,
+    pub created_at: String,
+}
+
+// Role of a user signed in with OIDC; every role has the permissions of the ones before it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UserRole {
+    // Read the blocks, tasks, logs and settings
+    Viewer,
+    // Change the blocks and tasks
+    Editor,
+    // Run tasks and git operations
+    Operator,
+    // Manage the settings, secrets, projects and API keys
+    Admin,
+}
+
+impl UserRole {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UserRole::Viewer => "viewer",
+            UserRole::Editor => "editor",
+            UserRole::Operator => "operator",
+            UserRole::Admin => "admin",
+        }
+    }
+}
+
+// Sign-in with an OpenID Connect provider; the HTTP API then accepts the ID tokens it issues
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OidcConfig {
+    // Issuer URL, its discovery document is at <issuer>/.well-known/openid-configuration
+    pub issuer: String,
+    pub client_id: String,
+    // May reference a stored secret as {{secret:NAME}}
+    pub client_secret: Option<String>,
+    // URL of /api/v1/auth/oidc/callback as the provider redirects to it; defaults to the server address
+    pub redirect_url: Option<String>,
+    // Expected audience of the tokens, defaults to the client ID
+    pub audience: Option<String>,
+    // Claim holding the roles or groups of the user, defaults to "roles"
+    pub roles_claim: Option<String>,
+    // Claim value -> role; values without a mapping grant no role
+    pub role_mapping: Option<BTreeMap<String, UserRole>>,
+    // Role of the users none of whose claim values map to a role; they are rejected when unset
+    pub default_role: Option<UserRole>,
+}
+
+impl OidcConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.issuer.trim().is_empty() {
+            return Err("The OIDC issuer can't be empty".to_string());
+        }
+        if self.client_id.trim().is_empty() {
+            return Err("The OIDC client ID can't be empty".to_string());
+        }
+        Ok(())
+    }
+}
+
+// Cross-origin access to the HTTP API, for a frontend served from another origin
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CorsConfig {
+    // Origins allowed to call the API, e.g. http://localhost:5173, or "*" for any origin
+    pub allowed_origins: Vec<String>,
+    // Defaults to GET, POST, PUT, DELETE and OPTIONS
+    pub allowed_methods: Option<Vec<String>>,
+    // Defaults to any header
+    pub allowed_headers: Option<Vec<String>>,
+    // Send cookies (the OIDC sign-in) with cross-origin requests
+    pub allow_credentials: bool,
+    // Seconds browsers may cache a preflight response
+    pub max_age_seconds: Option<usize>,
+}
+
+impl CorsConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        for origin in &self.allowed_origins {
+            let valid = origin == "*" || reqwest::Url::parse(origin)
+                .is_ok_and(|url| matches!(url.scheme(), "http" | "https") && url.host().is_some() && url.path() == "/" && !origin.ends_with('/'));
+            if !valid {
+                return Err(format!("Invalid CORS origin '{}', expected scheme://host[:port]", origin));
+            }
+        }
+        for method in self.allowed_methods.iter().flatten() {
+            if actix_web::http::Method::from_bytes(method.as_bytes()).is_err() {
+                return Err(format!("Invalid CORS method '{}'", method));
+            }
+        }
+        for header in self.allowed_headers.iter().flatten() {
+            if actix_web::http::header::HeaderName::from_bytes(header.as_bytes()).is_err() {
+                return Err(format!("Invalid CORS header '{}'", header));
+            }
+        }
+        if self.allow_credentials && self.allowed_origins.iter().any(|o| o == "*") {
+            return Err("Credentials can't be allowed for any origin, list the origins".to_string());
+        }
+        Ok(())
+    }
+}
+
+// Service hosting the git repository, where the pull (merge) requests of tasks are opened
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GitProvider {
+    #[default]
+    GitHub,
+    GitLab,
+}
+
+// Pull (merge) requests opened for tasks
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PullRequestConfig {
+    // Labels added to every request
+    pub labels: Vec<String>,
+    // User name of the assignee
+    pub assignee: Option<String>,
+    // API of a self-managed GitLab, defaults to https://<host of the remote>/api/v4
+    pub gitlab_api_url: Option<String>,
+}
+
+// GitHub Issues synced with the tasks of the blocks
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GitHubIssuesConfig {
+    // "owner/repo", defaults to the repository of the git remote
+    pub repository: Option<String>,
+    // API of a GitHub Enterprise server, defaults to https://api.github.com for github.com remotes
+    // and https://<host>/api/v3 otherwise
+    pub api_url: Option<String>,
+    // Labels added to every exported issue; they aren't copied onto imported tasks
+    pub labels: Vec<String>,
+}
+
+// CI pipeline status attached to the tasks, polled from GitHub Actions or pushed to the CI webhook
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CiConfig {
+    // Poll GitHub Actions for the runs of the task branches and commits
+    pub github_actions: bool,
+    // Seconds between polls, DEFAULT_CI_POLL_INTERVAL_SECONDS by default
+    pub poll_interval_seconds: Option<u64>,
+    // API of a GitHub Enterprise server, defaults to that of the host of the remote
+    pub api_url: Option<String>,
+    // Key of the HMAC-SHA256 signature the CI webhook requires, may be a {{secret:NAME}} reference
+    pub webhook_secret: Option<String>,
+    // Refuse to mark a task completed until its CI status is success
+    pub require_success: bool,
+}
+
+pub const DEFAULT_CI_POLL_INTERVAL_SECONDS: u64 = 60;
+
+// PEM files of the HTTPS certificate (with its chain) and private key
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TlsConfig {
+    pub cert_file: String,
+    pub key_file: String,
+}
+
+impl TlsConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        for (name, file) in [("certificate", &self.cert_file), ("key", &self.key_file)] {
+            if !Path::new(file).is_file() {
+                return Err(format!("TLS {} file {} not found", name, file));
+            }
+        }
+        Ok(())
+    }
+}
+
+// Address the server binds to: the configured one (or the default) with the host and port given
+// on the command line
+pub fn resolve_server_address(configured: Option<&str>, host: Option<&str>, port: Option<u16>) -> String {
+    let address = configured.filter(|a| !a.is_empty()).unwrap_or(DEFAULT_SERVER_ADDRESS);
+    let (configured_host, configured_port) = address.rsplit_once(':').unwrap_or((address, "8080"));
+    let host = host.unwrap_or(configured_host);
+    let port = port.map(|p| p.to_string()).unwrap_or_else(|| configured_port.to_string());
+    // IPv6 hosts are bracketed in an address
+    if host.contains(':') && !host.starts_with('[') {
+        format!("[{}]:{}", host, port)
+    } else {
+        format!("{}:{}", host, port)
+    }
+}
+
+// Token bucket of a client: `burst` requests at once, refilled at `requests_per_minute`
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RateLimit {
+    pub requests_per_minute: u32,
+    pub burst: u32,
+}
+
+// Limits of the expensive endpoints per client (API key, user or IP address)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RateLimitConfig {
+    pub enabled: bool,
+    // Endpoints calling an LLM: spec processing, enhancing, task generation and auto-complete
+    pub llm: RateLimit,
+    // Endpoints running tasks and builds
+    pub execution: RateLimit,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            llm: RateLimit { requests_per_minute: 20, burst: 5 },
+            execution: RateLimit { requests_per_minute: 10, burst: 5 },
+        }
+    }
+}
+
+impl RateLimitConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        for (name, limit) in [("llm", &self.llm), ("execution", &self.execution)] {
+            if limit.requests_per_minute == 0 || limit.burst == 0 {
+                return Err(format!("The {} rate limit needs at least one request per minute and a burst of one", name));
+            }
+        }
+        Ok(())
+    }
+}
+
+// Events a webhook can subscribe to
+pub const WEBHOOK_EVENT_TASK_STATUS_CHANGED: &str = "task.status_changed";
+pub const WEBHOOK_EVENT_BLOCK_CREATED: &str = "block.created";
+pub const WEBHOOK_EVENT_EXECUTION_FINISHED: &str = "execution.finished";
+pub const WEBHOOK_EVENT_EXECUTION_FAILED: &str = "execution.failed";
+pub const WEBHOOK_EVENT_EXECUTION_STARTED: &str = "execution.started";
+pub const WEBHOOK_EVENT_PULL_REQUEST_OPENED: &str = "pull_request.opened";
+pub const WEBHOOK_EVENT_BLOCK_COMPLETED: &str = "block.completed";
+pub const WEBHOOK_EVENTS: [&str; 7] = [
+    WEBHOOK_EVENT_TASK_STATUS_CHANGED,
+    WEBHOOK_EVENT_BLOCK_CREATED,
+    WEBHOOK_EVENT_EXECUTION_FINISHED,
+    WEBHOOK_EVENT_EXECUTION_FAILED,
+    WEBHOOK_EVENT_EXECUTION_STARTED,
+    WEBHOOK_EVENT_PULL_REQUEST_OPENED,
+    WEBHOOK_EVENT_BLOCK_COMPLETED,
+];
+
+// Slack messages posted on events, through an incoming webhook or a bot token
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SlackConfig {
+    pub enabled: bool,
+    // URL of an incoming webhook, may be a {{secret:NAME}} reference
+    pub webhook_url: Option<String>,
+    // Bot token posting to the channel when there's no webhook URL, may be a {{secret:NAME}} reference
+    pub bot_token: Option<String>,
+    pub channel: Option<String>,
+    // Events posted; "*" posts all of them
+    pub events: Vec<String>,
+    // Message of an event with {{placeholders}} of its data, replacing the default message
+    pub templates: HashMap<String, String>,
+}
+
+impl SlackConfig {
+    pub fn subscribes_to(&self, event: &str) -> bool {
+        self.enabled && self.events.iter().any(|e| e == "*" || e == event)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        let has_webhook = self.webhook_url.as_deref().is_some_and(|url| !url.trim().is_empty());
+        let has_bot = self.bot_token.as_deref().is_some_and(|token| !token.trim().is_empty());
+        if !has_webhook && !has_bot {
+            return Err("Slack needs a webhook_url or a bot_token".to_string());
+        }
+        if !has_webhook && self.channel.as_deref().is_none_or(|channel| channel.trim().is_empty()) {
+            return Err("Slack needs a channel to post to with a bot token".to_string());
+        }
+        if let Some(event) = self.events.iter().chain(self.templates.keys()).find(|e| *e != "*" && !WEBHOOK_EVENTS.contains(&e.as_str())) {
+            return Err(format!("Unknown Slack event '{}', expected one of: {}", event, WEBHOOK_EVENTS.join(", ")));
+        }
+        Ok(())
+    }
+}
+
+// An URL receiving the events it subscribed to as signed JSON POSTs
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WebhookConfig {
+    pub id: String,
+    pub url: String,
+    // Subscribed events; "*" subscribes to all of them
+    pub events: Vec<String>,
+    // Key of the HMAC-SHA256 signature of the payloads, may be a {{secret:NAME}} reference
+    pub secret: Option<String>,
+    #[serde(default = "default_webhook_enabled")]
+    pub enabled: bool,
+    // Attempts of a delivery before it's given up, with an exponential backoff between them
+    pub max_attempts: Option<u32>,
+}
+
+fn default_webhook_enabled() -> bool {
+    true
+}
+
+impl WebhookConfig {
+    pub fn subscribes_to(&self, event: &str) -> bool {
+        self.enabled && self.events.iter().any(|e| e == "*" || e == event)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.id.trim().is_empty() {
+            return Err("A webhook needs an ID".to_string());
+        }
+        let valid_url = reqwest::Url::parse(&self.url).is_ok_and(|url| matches!(url.scheme(), "http" | "https") && url.host().is_some());
+        if !valid_url {
+            return Err(format!("Invalid URL '{}' of webhook {}", self.url, self.id));
+        }
+        if self.events.is_empty() {
+            return Err(format!("Webhook {} doesn't subscribe to any event", self.id));
+        }
+        if let Some(event) = self.events.iter().find(|e| *e != "*" && !WEBHOOK_EVENTS.contains(&e.as_str())) {
+            return Err(format!("Unknown event '{}' of webhook {}, expected one of: {}", event, self.id, WEBHOOK_EVENTS.join(", ")));
+        }
+        if self.max_attempts == Some(0) {
+            return Err(format!("Webhook {} needs at least one delivery attempt", self.id));
+        }
+        Ok(())
+    }
+}
+
+// Error classes of failed task executions, used to decide which failures are retried
+pub const ERROR_CLASS_RATE_LIMIT: &str = "rate_limit";
+pub const ERROR_CLASS_NETWORK: &str = "network";
+pub const ERROR_CLASS_TIMEOUT: &str = "timeout";
+pub const ERROR_CLASS_LLM: &str = "llm";
+pub const ERROR_CLASS_CONFIGURATION: &str = "configuration";
+pub const ERROR_CLASS_OTHER: &str = "other";
+
+// Automatic retries of failed task executions
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TaskRetryPolicy {
+    // Total number of attempts, including the first one (1 disables retries)
+    pub max_attempts: u32,
+    // Delay before the first retry; doubled (by `backoff_multiplier`) for every further retry
+    pub backoff_seconds: u64,
+    pub backoff_multiplier: f64,
+    pub max_backoff_seconds: u64,
+    // Error classes that are retried (rate_limit, network, timeout, llm, configuration, other)
+    pub retry_on: Vec<String>,
+}
+
+impl Default for TaskRetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 1,
+            backoff_seconds: 30,
+            backoff_multiplier: 2.0,
+            max_backoff_seconds: 600,
+            retry_on: vec![
+                ERROR_CLASS_RATE_LIMIT.to_string(),
+                ERROR_CLASS_NETWORK.to_string(),
+                ERROR_CLASS_TIMEOUT.to_string(),
+                ERROR_CLASS_LLM.to_string(),
+            ],
+        }
+    }
+}
+
+impl TaskRetryPolicy {
+    // Classify the error of a failed task execution
+    pub fn classify_error(error: &str) -> &'static str {
+        let error = error.to_lowercase();
+        let matches = |patterns: &[&str]| patterns.iter().any(|p| error.contains(p));
+
+        if matches(&["rate limit", "rate_limit", "429", "too many requests", "overloaded"]) {
+            ERROR_CLASS_RATE_LIMIT
+        } else if matches(&["timed out", "timeout", "deadline exceeded"]) {
+            ERROR_CLASS_TIMEOUT
+        } else if matches(&["connection", "network", "dns", "could not resolve", "unreachable", "econnreset", "503", "502"]) {
+            ERROR_CLASS_NETWORK
+        } else if matches(&["project home directory", "description cannot be empty", "block not found", "project configuration"]) {
+            ERROR_CLASS_CONFIGURATION
+        } else if matches(&["claude cli"]) {
+            ERROR_CLASS_LLM
+        } else {
+            ERROR_CLASS_OTHER
+        }
+    }
+
+    // Delay before the next attempt after a failed `attempt` (1-based), or None if the failure is final
+    pub fn retry_delay(&self, attempt: u32, error_class: &str) -> Option<Duration> {
+        if attempt >= self.max_attempts || !self.retry_on.iter().any(|c| c == error_class) {
+            return None;
+        }
+        let backoff = self.backoff_seconds as f64 * self.backoff_multiplier.max(1.0).powi(attempt as i32 - 1);
+        Some(Duration::from_secs_f64(backoff.min(self.max_backoff_seconds as f64)))
+    }
+}
+
+// Resource limits of task executions; a Claude process exceeding them is killed with its child processes.
+// Both limits are opt-in.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TaskLimits {
+    // Wall-clock limit of one execution attempt (tasks may override it), unlimited if unset
+    pub timeout_minutes: Option<u64>,
+    // Resident memory limit of the Claude process and its child processes, unlimited if unset
+    pub max_memory_mb: Option<u64>,
+}
+
+// Longest timeout of a task execution or hook, a week
+pub const MAX_TIMEOUT_MINUTES: u64 = 7 * 24 * 60;
+
+pub fn validate_timeout_minutes(minutes: u64, of: &str) -> Result<(), String> {
+    if minutes > MAX_TIMEOUT_MINUTES {
+        return Err(format!("The timeout of {} is {} minutes, the maximum is {}", of, minutes, MAX_TIMEOUT_MINUTES));
+    }
+    Ok(())
+}
+
+impl TaskLimits {
+    pub fn validate(&self) -> Result<(), String> {
+        self.timeout_minutes.map_or(Ok(()), |minutes| validate_timeout_minutes(minutes, "task executions"))
+    }
+}
+
+// Checks run before a task execution is queued
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PreflightConfig {
+    pub enabled: bool,
+    // Refuse to run with uncommitted changes, which would end up in the task's commit
+    pub require_clean_tree: bool,
+    // Command that must succeed in the project directory, e.g. "cargo build"
+    pub build_command: Option<String>,
+    // Programs that must be installed
+    pub required_tools: Vec<String>,
+}
+
+// Opt-in: an enabled project refuses to queue tasks whose checks fail
+impl Default for PreflightConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            require_clean_tree: true,
+            build_command: None,
+            required_tools: vec!["git".to_string(), "claude".to_string()],
+        }
+    }
+}
+
+// Shell command run in the project directory before or after each task execution.
+// Hooks are only shell commands: task executions run outside the MCP server, so its tools can't be called
+// from a hook.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExecutionHook {
+    pub name: String,
+    pub command: String,
+    // Whether a failure of the hook fails the task execution
+    #[serde(default = "default_hook_blocking")]
+    pub blocking: bool,
+    #[serde(default = "default_hook_timeout_minutes")]
+    pub timeout_minutes: u64,
+}
+
+fn default_hook_blocking() -> bool {
+    true
+}
+
+fn default_hook_timeout_minutes() -> u64 {
+    10
+}
+
+// Hooks of task executions: `before` runs ahead of Claude, `after` once Claude succeeded and before the commit
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ExecutionHooks {
+    #[serde(default)]
+    pub before: Vec<ExecutionHook>,
+    #[serde(default)]
+    pub after: Vec<ExecutionHook>,
+}
+
+impl ExecutionHooks {
+    pub fn validate(&self) -> Result<(), String> {
+        self.before.iter().chain(&self.after)
+            .try_for_each(|hook| validate_timeout_minutes(hook.timeout_minutes, &format!("hook '{}'", hook.name)))
+    }
+}
+
+// Isolation of task executions in git worktrees, so concurrent tasks don't share a working tree
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WorktreeConfig {
+    pub enabled: bool,
+    // Directory holding the worktrees; defaults to "<project home directory>.worktrees"
+    pub root: Option<String>,
+    // Keep the worktree and branch of a failed execution for inspection
+    pub keep_failed: bool,
+}
+
+// What a successful execution does with its branch under the branch-per-task workflow
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskBranchCompletion {
+    // Merge the branch back into the base branch and delete it
+    #[default]
+    Merge,
+    // Push the branch and open a pull request for it on the git provider
+    PullRequest,
+}
+
+// Branch-per-task workflow: every execution runs on its own task/{task_id}-{slug} branch, created
+// from the base branch and merged back or proposed as a pull request
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BranchWorkflowConfig {
+    pub enabled: bool,
+    // Branch the task branches start from and go back to; defaults to the main branch
+    pub base_branch: Option<String>,
+    pub on_success: TaskBranchCompletion,
+}
+
+// Part of a task branch name describing the task: lowercase words joined by dashes
+fn branch_slug(text: &str) -> String {
+    let words: Vec<String> = text
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(|w| w.to_ascii_lowercase())
+        .collect();
+    let mut slug = String::new();
+    for word in words {
+        if slug.len() + word.len() + 1 > MAX_BRANCH_SLUG_LENGTH {
+            break;
+        }
+        if !slug.is_empty() {
+            slug.push('-');
+        }
+        slug.push_str(&word);
+    }
+    slug
+}
+
+// Longest slug of a task branch name
+const MAX_BRANCH_SLUG_LENGTH: usize = 40;
+
+// Cron schedule executing the tasks of a block
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskSchedule {
+    #[serde(default)]
+    pub schedule_id: String,
+    pub name: String,
+    // Five-field cron expression evaluated in UTC, e.g. "0 2 * * *" for every night at 02:00
+    pub cron: String,
+    pub block_id: String,
+    // Tasks to execute; empty means every task of the block
+    #[serde(default)]
+    pub task_ids: Vec<String>,
+    #[serde(default = "default_schedule_enabled")]
+    pub enabled: bool,
+}
+
+fn default_schedule_enabled() -> bool {
+    true
+}
+
+// Automatic commits of the blocks config files to the project repository
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ConfigAutoCommit {
+    pub enabled: bool,
+    // Commit to this branch without checking it out; defaults to forge/config
+    #[serde(default)]
+    pub branch: Option<String>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProjectConfig {
+    // Schema version of the config file, upgraded on load by the migration runner
+    pub schema_version: Option<u32>,
     pub git_repository_url: String,
     pub project_home_directory: String,
     pub project_description: String,
     pub main_branch: Option<String>,
+    // Service pull requests are opened on, GitHub by default
+    pub git_provider: Option<GitProvider>,
+    pub pull_requests: Option<PullRequestConfig>,
+    pub github_issues: Option<GitHubIssuesConfig>,
+    pub ci: Option<CiConfig>,
+
+    // Directory holding the blocks config (e.g. a separate docs repository); defaults to the
+    // project home directory, which remains the code working directory and holds the execution data files
+    pub spec_directory: Option<String>,
+    // Reject changes to the blocks config, for specs kept in a read-only repository
+    pub spec_read_only: Option<bool>,
+    // Format of the blocks config file: "json", "yaml" or "toml" (auto-detected when unset)
+    pub blocks_format: Option<String>,
+
+    // Project metadata available to prompts as {{project_name}}, {{tech_stack}},
+    // {{primary_language}} and {{conventions_summary}}; detected from the project directory when unset
+    pub project_name: Option<String>,
+    pub tech_stack: Option<String>,
+    pub primary_language: Option<String>,
+    pub conventions_summary: Option<String>,
     pub llm_provider: Option<crate::llm_handler::LLMProvider>,
+    // Providers of individual LLM operations, e.g. Gemini for task generation only
+    pub operation_providers: Option<OperationProviders>,
+    pub operation_models: Option<OperationModels>,
+    // Providers tried in order when a call errors, is rate-limited or returns invalid JSON
+    pub llm_fallbacks: Option<Vec<LLMFallback>>,
+    // Token budgets of LLM calls
+    pub token_budget: Option<TokenBudget>,
+    pub llm_audit: Option<LLMAuditConfig>,
+    // Audit log of the mutating API calls, on by default
+    pub request_audit: Option<RequestAuditConfig>,
+    // Prices of models missing from (or priced differently than in) the built-in price table
+    pub model_prices: Option<HashMap<String, ModelPrice>>,
     pub openrouter_model: Option<String>,
     pub gemini_model: Option<String>,
     pub anthropic_model: Option<String>,
+    // OpenAI-compatible provider: model, base URL (defaults to the OpenAI API) and API key
+    // (defaults to the OPENAI_API_KEY environment variable)
+    pub openai_model: Option<String>,
+    pub openai_base_url: Option<String>,
+    pub openai_api_key: Option<String>,
+    // Ollama provider: host (defaults to http://localhost:11434) and local model
+    pub ollama_host: Option<String>,
+    pub ollama_model: Option<String>,
+    // AWS Bedrock provider: region (defaults to AWS_REGION) and model ID; credentials come
+    // from the AWS_ACCESS_KEY_ID/AWS_SECRET_ACCESS_KEY/AWS_SESSION_TOKEN environment variables
+    pub bedrock_region: Option<String>,
+    pub bedrock_model: Option<String>,
 
     // Selected profession for prompts
     pub selected_profession_id: Option<String>,
 
-    // User-configurable prompts
+    // Task workflow states and transition rules
+    pub task_workflow: Option<TaskWorkflow>,
+
+    // Automatic backups of the blocks and project config
+    pub backup: Option<BackupConfig>,
+
+    // Pruning of old log files
+    pub log_retention: Option<LogRetention>,
+
+    // Commit the blocks config to git whenever it changes
+    pub config_autocommit: Option<ConfigAutoCommit>,
+
+    // Retries of failed task executions
+    pub task_retry: Option<TaskRetryPolicy>,
+
+    // Timeout and memory limit of task executions
+    pub task_limits: Option<TaskLimits>,
+
+    // Pre-flight checks of task executions
+    pub preflight: Option<PreflightConfig>,
+
+    // Shell hooks run before and after each task execution
+    pub execution_hooks: Option<ExecutionHooks>,
+
+    // Environment variables injected into task executions (blocks may override them)
+    pub task_env: Option<Vec<crate::models::TaskEnvVar>>,
+
+    // Run each task in its own git worktree and merge it back into the main branch
+    pub worktrees: Option<WorktreeConfig>,
+    // Run each execution on its own task branch, merged back or opened as a pull request
+    pub branch_workflow: Option<BranchWorkflowConfig>,
+
+    // Cron schedules of task executions
+    pub schedules: Option<Vec<TaskSchedule>>,
+
+    // Project glossary included in LLM prompts
+    pub glossary: Option<Vec<GlossaryTerm>>,
+
+    // Address the web server listens on (host:port), defaults to 127.0.0.1:8080
+    pub server_address: Option<String>,
+
+    // Certificate and key of HTTPS; the server speaks plain HTTP without them
+    pub tls: Option<TlsConfig>,
+
+    // Time running executions and MCP tool calls get to finish on shutdown, defaults to 60 seconds
+    pub shutdown_drain_timeout_seconds: Option<u64>,
+
+    // Session limits of the MCP server, applied while it runs
+    pub mcp_sessions: Option<McpSessionConfig>,
+
+    // API keys required by the HTTP server; the API is open while none is configured
+    pub api_keys: Option<Vec<ApiKey>>,
+
+    // OIDC sign-in of the users of the HTTP API, with the roles they are given
+    pub oidc: Option<OidcConfig>,
+
+    // Origins other than the server's own allowed to call the API; applied at startup
+    pub cors: Option<CorsConfig>,
+
+    // Per-client limits of the endpoints calling LLMs or running tasks; on by default
+    pub rate_limits: Option<RateLimitConfig>,
+
+    // Serve a Swagger UI of the API at /api/docs
+    pub swagger_ui: Option<bool>,
+
+    // URLs notified of task and block events
+    pub webhooks: Option<Vec<WebhookConfig>>,
+    // Slack messages posted on the same events
+    pub slack: Option<SlackConfig>,
+
+    // User-configurable prompts; unset prompts fall back to the compiled-in defaults
     pub auto_complete_system_prompt: Option<String>,
     pub auto_complete_user_prompt: Option<String>,
     pub enhance_description_system_prompt: Option<String>,
@@ -365,34 +1465,288 @@ pub struct ProjectConfig {
     pub process_specification_user_prompt_mcp: Option<String>,
 }
 
+impl ProjectConfig {
+    // Provider of an LLM operation: its override if set, otherwise the project's provider
+    pub fn llm_provider_for(&self, operation: LLMOperation) -> Option<crate::llm_handler::LLMProvider> {
+        let providers = self.operation_providers.clone().unwrap_or_default();
+        let provider = match operation {
+            LLMOperation::EnhanceDescription => providers.enhance_description,
+            LLMOperation::AutoComplete => providers.auto_complete,
+            LLMOperation::GenerateTasks => providers.generate_tasks,
+            LLMOperation::ProcessSpecification => providers.process_specification,
+        };
+        provider.or_else(|| self.llm_provider.clone())
+    }
+
+    // Model override of an operation, None to use the provider's configured model
+    pub fn llm_model_for(&self, operation: LLMOperation) -> Option<String> {
+        let models = self.operation_models.clone().unwrap_or_default();
+        let model = match operation {
+            LLMOperation::EnhanceDescription => models.enhance_description,
+            LLMOperation::AutoComplete => models.auto_complete,
+            LLMOperation::GenerateTasks => models.generate_tasks,
+            LLMOperation::ProcessSpecification => models.process_specification,
+        };
+        model.filter(|m| !m.trim().is_empty())
+    }
+
+    // Model of the Claude CLI executing tasks, None for the CLI's default
+    pub fn execution_model(&self) -> Option<String> {
+        self.operation_models.as_ref()
+            .and_then(|m| m.execute_task.clone())
+            .filter(|m| !m.trim().is_empty())
+    }
+
+    // Whether the HTTP API requires credentials: once API keys or an OIDC sign-in are configured
+    pub fn requires_authentication(&self) -> bool {
+        self.api_keys.as_ref().is_some_and(|keys| !keys.is_empty()) || self.oidc.is_some()
+    }
+
+    // Keep the API keys, the OIDC sign-in and the webhooks of the current config; they are only changed
+    // through their own endpoints
+    pub fn keep_access_settings(&mut self, current: &ProjectConfig) {
+        self.api_keys = current.api_keys.clone();
+        self.oidc = current.oidc.clone();
+        self.webhooks = current.webhooks.clone();
+    }
+
+    // Timeouts of the task limits and execution hooks
+    pub fn validate_timeouts(&self) -> Result<(), String> {
+        self.task_limits.as_ref().map_or(Ok(()), |limits| limits.validate())?;
+        self.execution_hooks.as_ref().map_or(Ok(()), |hooks| hooks.validate())
+    }
+
+    // Branch-per-task workflow, if enabled
+    pub fn branch_workflow(&self) -> Option<&BranchWorkflowConfig> {
+        self.branch_workflow.as_ref().filter(|w| w.enabled)
+    }
+
+    // Branch task executions start from and go back to
+    pub fn task_base_branch(&self) -> String {
+        self.branch_workflow()
+            .and_then(|w| w.base_branch.clone())
+            .or_else(|| self.main_branch.clone())
+            .filter(|b| !b.trim().is_empty())
+            .unwrap_or_else(|| "main".to_string())
+    }
+
+    // Branch of the executions of a task: task/{task_id}-{slug} with the branch-per-task workflow,
+    // the task ID otherwise
+    pub fn task_branch(&self, task: &crate::models::Task) -> String {
+        if self.branch_workflow().is_none() {
+            return task.task_id.clone();
+        }
+        let title = if task.task_name.trim().is_empty() { task.description.lines().next().unwrap_or("") } else { task.task_name.as_str() };
+        match branch_slug(title) {
+            slug if slug.is_empty() => format!("task/{}", task.task_id),
+            slug => format!("task/{}-{}", task.task_id, slug),
+        }
+    }
+
+    // Directory of the blocks config: the spec directory if set, otherwise the project home directory
+    pub fn spec_directory(&self) -> String {
+        self.spec_directory.clone()
+            .filter(|d| !d.is_empty())
+            .unwrap_or_else(|| self.project_home_directory.clone())
+    }
+
+    // Look up a glossary term by name or alias; exact matches win over partial ones
+    pub fn lookup_term(&self, query: &str) -> Vec<GlossaryTerm> {
+        let glossary = self.glossary.as_deref().unwrap_or_default();
+        let query = query.trim();
+        if query.is_empty() {
+            return Vec::new();
+        }
+
+        let exact: Vec<GlossaryTerm> = glossary.iter().filter(|t| t.matches_exactly(query)).cloned().collect();
+        if !exact.is_empty() {
+            return exact;
+        }
+        glossary.iter().filter(|t| t.matches_partially(query)).cloned().collect()
+    }
+
+    // Glossary formatted for inclusion in a prompt (empty when there is no glossary)
+    pub fn glossary_prompt_section(&self) -> String {
+        let glossary = self.glossary.as_deref().unwrap_or_default();
+        if glossary.is_empty() {
+            return String::new();
+        }
+
+        let mut section = String::from("**Project glossary** (use these terms and canonical names exactly):\n");
+        for term in glossary {
+            if term.aliases.is_empty() {
+                section.push_str(&format!("- {}: {}\n", term.term, term.definition));
+            } else {
+                section.push_str(&format!("- {} (also: {}): {}\n", term.term, term.aliases.join(", "), term.definition));
+            }
+        }
+        section
+    }
+
+    fn prompt_field_mut(&mut self, key: &str) -> Option<&mut Option<String>> {
+        match key {
+            "auto_complete_system_prompt" => Some(&mut self.auto_complete_system_prompt),
+            "auto_complete_user_prompt" => Some(&mut self.auto_complete_user_prompt),
+            "enhance_description_system_prompt" => Some(&mut self.enhance_description_system_prompt),
+            "enhance_description_user_prompt" => Some(&mut self.enhance_description_user_prompt),
+            "generate_tasks_system_prompt" => Some(&mut self.generate_tasks_system_prompt),
+            "generate_tasks_user_prompt" => Some(&mut self.generate_tasks_user_prompt),
+            "generate_tasks_system_prompt_mcp" => Some(&mut self.generate_tasks_system_prompt_mcp),
+            "generate_tasks_user_prompt_mcp" => Some(&mut self.generate_tasks_user_prompt_mcp),
+            "process_specification_system_prompt" => Some(&mut self.process_specification_system_prompt),
+            "process_specification_user_prompt" => Some(&mut self.process_specification_user_prompt),
+            "process_specification_system_prompt_mcp" => Some(&mut self.process_specification_system_prompt_mcp),
+            "process_specification_user_prompt_mcp" => Some(&mut self.process_specification_user_prompt_mcp),
+            _ => None,
+        }
+    }
+
+    // Project override of a prompt; empty prompts and copies of the default don't count
+    pub fn prompt_override(&self, key: &str) -> Option<&str> {
+        let value = match key {
+            "auto_complete_system_prompt" => &self.auto_complete_system_prompt,
+            "auto_complete_user_prompt" => &self.auto_complete_user_prompt,
+            "enhance_description_system_prompt" => &self.enhance_description_system_prompt,
+            "enhance_description_user_prompt" => &self.enhance_description_user_prompt,
+            "generate_tasks_system_prompt" => &self.generate_tasks_system_prompt,
+            "generate_tasks_user_prompt" => &self.generate_tasks_user_prompt,
+            "generate_tasks_system_prompt_mcp" => &self.generate_tasks_system_prompt_mcp,
+            "generate_tasks_user_prompt_mcp" => &self.generate_tasks_user_prompt_mcp,
+            "process_specification_system_prompt" => &self.process_specification_system_prompt,
+            "process_specification_user_prompt" => &self.process_specification_user_prompt,
+            "process_specification_system_prompt_mcp" => &self.process_specification_system_prompt_mcp,
+            "process_specification_user_prompt_mcp" => &self.process_specification_user_prompt_mcp,
+            _ => return None,
+        };
+        value.as_deref().filter(|v| !v.trim().is_empty() && Some(*v) != default_prompt(key))
+    }
+
+    // Prompt used for an operation: the project override or the compiled-in default
+    pub fn prompt(&self, key: &str) -> &str {
+        self.prompt_override(key).or_else(|| default_prompt(key)).unwrap_or_default()
+    }
+
+    // Override a prompt, or reset it to the default with None
+    pub fn set_prompt_override(&mut self, key: &str, value: Option<String>) -> Result<(), String> {
+        let field = self.prompt_field_mut(key).ok_or_else(|| format!("Unknown prompt '{}'", key))?;
+        *field = value;
+        self.normalize_prompt_overrides();
+        Ok(())
+    }
+
+    // Unset the prompts that don't override anything so they follow later changes of the defaults
+    pub fn normalize_prompt_overrides(&mut self) {
+        for (key, _) in PROMPT_DEFAULTS {
+            if self.prompt_override(key).is_none() {
+                if let Some(field) = self.prompt_field_mut(key) {
+                    *field = None;
+                }
+            }
+        }
+    }
+}
+
+// Add the prompts changed by a config update to the prompt history
+fn record_prompt_changes(previous: &ProjectConfig, config: &ProjectConfig) {
+    for (key, _) in PROMPT_DEFAULTS {
+        let state = |c: &ProjectConfig| PromptState { value: c.prompt(key).to_string(), default: c.prompt_override(key).is_none() };
+        if let Err(e) = prompt_history::record_change(PROJECT_SCOPE, key, state(previous), state(config)) {
+            eprintln!("Failed to record prompt history: {}", e);
+        }
+    }
+}
+
 impl Default for ProjectConfig {
     fn default() -> Self {
         Self {
+            schema_version: Some(crate::migrations::PROJECT_SCHEMA_VERSION),
             git_repository_url: String::new(),
             project_home_directory: String::new(),
             project_description: String::new(),
             main_branch: Some("main".to_string()),
+            git_provider: None,
+            pull_requests: None,
+            github_issues: None,
+            ci: None,
+            spec_directory: None,
+            spec_read_only: None,
+            blocks_format: None,
+            project_name: None,
+            tech_stack: None,
+            primary_language: None,
+            conventions_summary: None,
             llm_provider: None,
+            operation_providers: None,
+            operation_models: None,
+            llm_fallbacks: None,
+            token_budget: None,
+            llm_audit: None,
+            request_audit: None,
+            model_prices: None,
             openrouter_model: None,
             gemini_model: None,
             anthropic_model: None,
+            openai_model: None,
+            openai_base_url: None,
+            openai_api_key: None,
+            ollama_host: None,
+            ollama_model: None,
+            bedrock_region: None,
+            bedrock_model: None,
 
             // Default profession is software architect
             selected_profession_id: Some("software_architect".to_string()),
 
-            // Default values for user-configurable prompts
-            auto_complete_system_prompt: Some(DEFAULT_AUTO_COMPLETE_SYSTEM_PROMPT.to_string()),
-            auto_complete_user_prompt: Some(DEFAULT_AUTO_COMPLETE_USER_PROMPT.to_string()),
-            enhance_description_system_prompt: Some(DEFAULT_ENHANCE_DESCRIPTION_SYSTEM_PROMPT.to_string()),
-            enhance_description_user_prompt: Some(DEFAULT_ENHANCE_DESCRIPTION_USER_PROMPT.to_string()),
-            generate_tasks_system_prompt: Some(DEFAULT_GENERATE_TASKS_SYSTEM_PROMPT.to_string()),
-            generate_tasks_user_prompt: Some(DEFAULT_GENERATE_TASKS_USER_PROMPT.to_string()),
-            generate_tasks_system_prompt_mcp: Some(DEFAULT_GENERATE_TASKS_SYSTEM_PROMPT_MCP.to_string()),
-            generate_tasks_user_prompt_mcp: Some(DEFAULT_GENERATE_TASKS_USER_PROMPT_MCP.to_string()),
-            process_specification_system_prompt: Some(DEFAULT_PROCESS_MARKDOWN_SPEC_SYSTEM_PROMPT.to_string()),
-            process_specification_user_prompt: Some(DEFAULT_PROCESS_MARKDOWN_SPEC_USER_PROMPT.to_string()),
-            process_specification_system_prompt_mcp: Some(DEFAULT_PROCESS_MARKDOWN_SPEC_SYSTEM_PROMPT_MCP.to_string()),
-            process_specification_user_prompt_mcp: Some(DEFAULT_PROCESS_MARKDOWN_SPEC_USER_PROMPT_MCP.to_string()),
+            task_workflow: Some(TaskWorkflow::default()),
+
+            backup: Some(BackupConfig::default()),
+            log_retention: None,
+
+            config_autocommit: None,
+
+            task_retry: None,
+
+            task_limits: None,
+
+            preflight: None,
+
+            execution_hooks: None,
+
+            task_env: None,
+
+            worktrees: None,
+            branch_workflow: None,
+
+            schedules: None,
+
+            glossary: None,
+
+            server_address: None,
+            tls: None,
+            shutdown_drain_timeout_seconds: None,
+
+            mcp_sessions: None,
+            api_keys: None,
+            oidc: None,
+            cors: None,
+            rate_limits: None,
+            swagger_ui: None,
+            webhooks: None,
+            slack: None,
+
+            // Prompts aren't overridden until the project changes them
+            auto_complete_system_prompt: None,
+            auto_complete_user_prompt: None,
+            enhance_description_system_prompt: None,
+            enhance_description_user_prompt: None,
+            generate_tasks_system_prompt: None,
+            generate_tasks_user_prompt: None,
+            generate_tasks_system_prompt_mcp: None,
+            generate_tasks_user_prompt_mcp: None,
+            process_specification_system_prompt: None,
+            process_specification_user_prompt: None,
+            process_specification_system_prompt_mcp: None,
+            process_specification_user_prompt_mcp: None,
         }
     }
 }
@@ -400,11 +1754,83 @@ impl Default for ProjectConfig {
 // Global singleton instance
 lazy_static! {
     static ref PROJECT_MANAGER: Arc<ProjectConfigManager> = Arc::new(ProjectConfigManager::new(PROJECT_CONFIG_FILE));
+
+    // Environment whose config overlay is applied over the project config
+    static ref CONFIG_ENVIRONMENT: RwLock<Option<String>> = RwLock::new(
+        std::env::var(CONFIG_ENVIRONMENT_VAR).ok().filter(|e| !e.trim().is_empty())
+    );
+}
+
+// Select the config overlay environment (from the --env flag)
+pub fn set_config_environment(environment: &str) {
+    *CONFIG_ENVIRONMENT.write().unwrap() = Some(environment.to_string()).filter(|e| !e.trim().is_empty());
+}
+
+pub fn config_environment() -> Option<String> {
+    CONFIG_ENVIRONMENT.read().unwrap().clone()
+}
+
+// Overlay of a config file for an environment: project_config.json -> project_config.dev.json
+pub fn overlay_file_name(config_file: &str, environment: &str) -> String {
+    let path = Path::new(config_file);
+    let stem = path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+    let file_name = match path.extension() {
+        Some(ext) => format!("{}.{}.{}", stem, environment, ext.to_string_lossy()),
+        None => format!("{}.{}", stem, environment),
+    };
+    path.with_file_name(file_name).to_string_lossy().to_string()
+}
+
+// Apply an overlay: its values replace the base ones, nested objects are merged key by key
+fn merge_overlay(base: &mut serde_json::Value, overlay: &serde_json::Value) {
+    match (base, overlay) {
+        (serde_json::Value::Object(base), serde_json::Value::Object(overlay)) => {
+            for (key, value) in overlay {
+                merge_overlay(base.entry(key.clone()).or_insert(serde_json::Value::Null), value);
+            }
+        }
+        (base, overlay) => *base = overlay.clone(),
+    }
+}
+
+// Split a config being saved: the fields set by the overlay keep their base value in the base
+// file and get the saved value in the returned overlay
+fn split_overlay(
+    saved: &mut serde_json::Map<String, serde_json::Value>,
+    base: &serde_json::Value,
+    overlay: &serde_json::Map<String, serde_json::Value>,
+) -> serde_json::Map<String, serde_json::Value> {
+    let mut new_overlay = serde_json::Map::new();
+    for (key, overlay_value) in overlay {
+        let base_value = base.get(key);
+        let Some(saved_value) = saved.get_mut(key) else {
+            new_overlay.insert(key.clone(), overlay_value.clone());
+            continue;
+        };
+        match (saved_value, overlay_value) {
+            (serde_json::Value::Object(saved_object), serde_json::Value::Object(overlay_object)) => {
+                let nested = split_overlay(saved_object, base_value.unwrap_or(&serde_json::Value::Null), overlay_object);
+                new_overlay.insert(key.clone(), serde_json::Value::Object(nested));
+            }
+            (saved_value, _) => {
+                new_overlay.insert(key.clone(), saved_value.clone());
+                // Fields missing from the base file keep the saved value so the base stays complete
+                if let Some(base_value) = base_value {
+                    *saved_value = base_value.clone();
+                }
+            }
+        }
+    }
+    new_overlay
+}
+
+fn read_json(file: &str) -> io::Result<serde_json::Value> {
+    Ok(serde_json::from_str(&fs::read_to_string(file)?)?)
 }
 
 #[derive(Debug)]
 pub struct ProjectConfigManager {
-    config_file: String,
+    config_file: RwLock<String>,
     config: Mutex<ProjectConfig>,
 }
 
@@ -416,13 +1842,14 @@ impl ProjectConfigManager {
 
     pub fn new(config_file: &str) -> Self {
         Self {
-            config_file: config_file.to_string(),
+            config_file: RwLock::new(config_file.to_string()),
             config: Mutex::new(ProjectConfig::default()),
         }
     }
 
     pub fn load_config(&self) -> io::Result<ProjectConfig> {
-        let config_path = Path::new(&self.config_file);
+        let config_file = self.config_file();
+        let config_path = Path::new(&config_file);
 
         //Print the current working directory:
         //let cwd_path = env::current_dir()?;
@@ -434,7 +1861,21 @@ impl ProjectConfigManager {
         }
 
         let config_str = fs::read_to_string(config_path)?;
-        let config: ProjectConfig = serde_json::from_str(&config_str)?;
+        let mut document: serde_json::Value = serde_json::from_str(&config_str)?;
+
+        // Upgrade configs written with an older schema and write them back
+        let migrated = crate::migrations::migrate_project_config(&mut document)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        if migrated {
+            let base: ProjectConfig = serde_json::from_value(document.clone())?;
+            fs::write(config_path, serde_json::to_string_pretty(&base)?)?;
+        }
+
+        // Apply the overlay of the selected environment; overlays use the current schema
+        if let Some(overlay_file) = self.overlay_file() {
+            merge_overlay(&mut document, &read_json(&overlay_file)?);
+        }
+        let config: ProjectConfig = serde_json::from_value(document)?;
 
         // Update the internal config
         let mut internal_config = self.config.lock().unwrap();
@@ -444,19 +1885,35 @@ impl ProjectConfigManager {
     }
 
     pub fn save_config(&self, config: &ProjectConfig) -> io::Result<()> {
+        let mut config = config.clone();
+        config.schema_version = Some(crate::migrations::PROJECT_SCHEMA_VERSION);
+        config.normalize_prompt_overrides();
+        let config = &config;
         let config_str = serde_json::to_string_pretty(config)?;
 
         // Update the internal config
-        let mut internal_config = self.config.lock().unwrap();
-        *internal_config = config.clone();
+        let previous = std::mem::replace(&mut *self.config.lock().unwrap(), config.clone());
 
         // Create the directory if it doesn't exist
-        if let Some(parent) = Path::new(&self.config_file).parent() {
+        let config_file = self.config_file();
+        if let Some(parent) = Path::new(&config_file).parent() {
             fs::create_dir_all(parent)?;
         }
 
-        // Write the config to file
-        fs::write(&self.config_file, config_str)?;
+        // Write the config to file; with an overlay, the fields it sets are written to the overlay
+        match self.overlay_file() {
+            Some(overlay_file) => {
+                let base = if Path::new(&config_file).exists() { read_json(&config_file)? } else { serde_json::Value::Null };
+                let mut saved = serde_json::to_value(config)?;
+                if let (serde_json::Value::Object(saved), serde_json::Value::Object(overlay)) = (&mut saved, read_json(&overlay_file)?) {
+                    let overlay = split_overlay(saved, &base, &overlay);
+                    fs::write(&overlay_file, serde_json::to_string_pretty(&overlay)?)?;
+                }
+                fs::write(&config_file, serde_json::to_string_pretty(&saved)?)?;
+            }
+            None => fs::write(&config_file, config_str)?,
+        }
+        record_prompt_changes(&previous, config);
 
         // If project_home_directory is specified, create it if it doesn't exist
         if !config.project_home_directory.is_empty() {
@@ -469,24 +1926,415 @@ impl ProjectConfigManager {
         Ok(())
     }
 
+    // Path of the project config file
+    pub fn config_file(&self) -> String {
+        self.config_file.read().map(|f| f.clone()).unwrap_or_default()
+    }
+
+    // Path of the overlay of the selected environment, if there is one
+    pub fn overlay_file(&self) -> Option<String> {
+        config_environment()
+            .map(|environment| overlay_file_name(&self.config_file(), &environment))
+            .filter(|file| Path::new(file).exists())
+    }
+
+    // Path of a project data file stored in the same directory as the project config file
+    pub fn project_data_file(&self, file_name: &str) -> String {
+        let config_file = self.config_file();
+        match Path::new(&config_file).parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent.join(file_name).to_string_lossy().to_string(),
+            _ => file_name.to_string(),
+        }
+    }
+
+    // Point the manager at another project config file and load it (used when opening another project)
+    pub fn switch_config_file(&self, config_file: &str) -> io::Result<ProjectConfig> {
+        match self.config_file.write() {
+            Ok(mut current) => *current = config_file.to_string(),
+            Err(_) => return Err(io::Error::new(io::ErrorKind::Other, "Failed to acquire lock on config file")),
+        }
+        self.load_config()
+    }
+
     pub fn get_config(&self) -> io::Result<ProjectConfig> {
         let config = self.config.lock().unwrap();
         Ok(config.clone())
     }
+
+    // Get the configured task workflow, falling back to the default one
+    pub fn get_task_workflow(&self) -> TaskWorkflow {
+        let config = self.config.lock().unwrap();
+        config.task_workflow.clone().unwrap_or_default()
+    }
+
+    // Get the retry policy of failed task executions (no retries if not configured)
+    pub fn get_task_retry_policy(&self) -> TaskRetryPolicy {
+        let config = self.config.lock().unwrap();
+        config.task_retry.clone().unwrap_or_default()
+    }
+}
+
+// Time allowed for the remote to answer the connection test
+const GIT_CONNECTION_TIMEOUT: Duration = Duration::from_secs(20);
+
+// Credentials the connection test uses; `default` is whatever git is configured with
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(tag = "method", rename_all = "snake_case")]
+pub enum GitAuth {
+    #[default]
+    Default,
+    SshAgent,
+    SshKey { key_path: String },
+    // HTTPS with a token stored as a secret (or environment variable), GITHUB_TOKEN or GITLAB_TOKEN
+    // by default depending on the git provider
+    Token {
+        #[serde(default)]
+        token_secret: Option<String>,
+        #[serde(default)]
+        username: Option<String>,
+    },
+}
+
+impl GitAuth {
+    fn name(&self) -> &'static str {
+        match self {
+            GitAuth::Default => "default",
+            GitAuth::SshAgent => "ssh_agent",
+            GitAuth::SshKey { .. } => "ssh_key",
+            GitAuth::Token { .. } => "token",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GitConnectionStatus {
+    Connected,
+    InvalidConfiguration,
+    AuthFailed,
+    HostUnreachable,
+    RepoNotFound,
+    Failed,
 }
 
-// Function to test Git repository connection
-pub async fn test_git_connection(url: &str) -> Result<String, String> {
-    // This is a simple check to see if the URL is valid
-    // In a real implementation, you might want to use a Git library to actually test the connection
+// Outcome of a connection test: what went wrong, with the output of git when it ran
+#[derive(Debug, Clone, Serialize)]
+pub struct GitConnectionCheck {
+    pub status: GitConnectionStatus,
+    pub method: String,
+    pub message: String,
+    pub detail: Option<String>,
+}
+
+impl GitConnectionCheck {
+    pub fn is_connected(&self) -> bool {
+        self.status == GitConnectionStatus::Connected
+    }
+}
+
+// Kind of failure reported by git ls-remote. Authentication failures are recognized before the
+// "not found" patterns, which also match e.g. a missing identity file reported next to them.
+pub fn classify_git_error(stderr: &str) -> GitConnectionStatus {
+    let stderr = stderr.to_lowercase();
+    let matches = |patterns: &[&str]| patterns.iter().any(|p| stderr.contains(p));
+    if matches(&["could not resolve host", "could not resolve hostname", "connection refused", "connection timed out",
+        "operation timed out", "network is unreachable", "no route to host", "failed to connect"]) {
+        GitConnectionStatus::HostUnreachable
+    } else if matches(&["permission denied", "authentication failed", "could not read username", "could not read password",
+        "terminal prompts disabled", "invalid username or password", "returned error: 401", "returned error: 403", "access denied"]) {
+        GitConnectionStatus::AuthFailed
+    } else if matches(&["repository not found", "does not appear to be a git repository", "not found", "does not exist", "returned error: 404"]) {
+        GitConnectionStatus::RepoNotFound
+    } else {
+        GitConnectionStatus::Failed
+    }
+}
+
+fn connection_check(status: GitConnectionStatus, auth: &GitAuth, message: String, detail: Option<String>) -> GitConnectionCheck {
+    GitConnectionCheck { status, method: auth.name().to_string(), message, detail }
+}
+
+// Test the connection to a git repository by listing its branches with the given credentials.
+// Interactive prompts are disabled so missing credentials fail instead of hanging.
+pub async fn test_git_connection(url: &str, auth: &GitAuth, provider: GitProvider) -> GitConnectionCheck {
+    let invalid = |message: String| connection_check(GitConnectionStatus::InvalidConfiguration, auth, message, None);
     if url.is_empty() {
-        return Err("Git repository URL cannot be empty".to_string());
+        return invalid("Git repository URL cannot be empty".to_string());
+    }
+    let is_https = url.starts_with("http://") || url.starts_with("https://");
+    let is_ssh = url.starts_with("git@") || url.starts_with("ssh://");
+    if !(is_https || is_ssh) {
+        return invalid("Invalid Git repository URL format".to_string());
+    }
+
+    // Credentials are referenced as {{secret:NAME}}; they must exist
+    let resolved_url = match secrets::resolve(url) {
+        Ok(resolved_url) => resolved_url,
+        Err(e) => return invalid(e),
+    };
+
+    // Host keys of new SSH hosts are accepted for the test only: they go to a temporary file listed
+    // before the user's known_hosts, whose keys are still checked
+    let known_hosts = match tempfile::NamedTempFile::new() {
+        Ok(known_hosts) => known_hosts,
+        Err(e) => return connection_check(GitConnectionStatus::Failed, auth, format!("Failed to create a known hosts file: {}", e), None),
+    };
+    let ssh_options = format!("-o BatchMode=yes -o StrictHostKeyChecking=accept-new -o UserKnownHostsFile='{} ~/.ssh/known_hosts'",
+        known_hosts.path().to_string_lossy().replace('\'', "'\\''"));
+
+    let mut command = tokio::process::Command::new("git");
+    command.args(["ls-remote", "--heads", "--", &resolved_url])
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .env("GIT_SSH_COMMAND", format!("ssh {}", ssh_options))
+        .kill_on_drop(true);
+    match auth {
+        GitAuth::Default => {}
+        GitAuth::SshAgent => {
+            if !is_ssh {
+                return invalid("The SSH agent only authenticates SSH URLs (git@host:owner/repo.git)".to_string());
+            }
+            if std::env::var_os("SSH_AUTH_SOCK").is_none() {
+                return invalid("No SSH agent is running: SSH_AUTH_SOCK is not set for the server".to_string());
+            }
+        }
+        GitAuth::SshKey { key_path } => {
+            if !is_ssh {
+                return invalid("SSH keys only authenticate SSH URLs (git@host:owner/repo.git)".to_string());
+            }
+            if !Path::new(key_path).is_file() {
+                return invalid(format!("SSH key {} not found", key_path));
+            }
+            let key_path = key_path.replace('\'', "'\\''");
+            command.env("GIT_SSH_COMMAND", format!(
+                "ssh -i '{}' -o IdentitiesOnly=yes -o IdentityAgent=none {}", key_path, ssh_options));
+        }
+        GitAuth::Token { token_secret, username } => {
+            // Not over plain http, where the token would be sent in clear
+            if !url.starts_with("https://") {
+                return invalid("Tokens only authenticate HTTPS URLs".to_string());
+            }
+            let name = token_secret.clone().unwrap_or_else(|| match provider {
+                GitProvider::GitHub => crate::pull_request::GITHUB_TOKEN_SECRET.to_string(),
+                GitProvider::GitLab => crate::pull_request::GITLAB_TOKEN_SECRET.to_string(),
+            });
+            let token = match secrets::credential(&name) {
+                Ok(token) => token,
+                Err(e) => return invalid(e),
+            };
+            // Passed through the environment so the token stays out of the command line
+            let username = username.as_deref().unwrap_or("x-access-token");
+            let basic = base64::engine::general_purpose::STANDARD.encode(format!("{}:{}", username, token));
+            command.env("GIT_CONFIG_COUNT", "1")
+                .env("GIT_CONFIG_KEY_0", "http.extraHeader")
+                .env("GIT_CONFIG_VALUE_0", format!("Authorization: Basic {}", basic));
+        }
+    }
+
+    let output = match tokio::time::timeout(GIT_CONNECTION_TIMEOUT, command.output()).await {
+        Ok(Ok(output)) => output,
+        Ok(Err(e)) => return connection_check(GitConnectionStatus::Failed, auth, format!("Failed to run git: {}", e), None),
+        Err(_) => return connection_check(GitConnectionStatus::HostUnreachable, auth,
+            format!("The repository didn't answer within {} seconds", GIT_CONNECTION_TIMEOUT.as_secs()), None),
+    };
+    if output.status.success() {
+        let branches = String::from_utf8_lossy(&output.stdout).lines().count();
+        return connection_check(GitConnectionStatus::Connected, auth,
+            format!("Successfully connected to Git repository ({} branches)", branches), None);
+    }
+
+    let detail = secrets::redact(String::from_utf8_lossy(&output.stderr).trim());
+    let status = classify_git_error(&detail);
+    let message = match status {
+        GitConnectionStatus::HostUnreachable => "The Git host can't be reached",
+        // Hosts answer "not found" to unauthenticated requests for private repositories too
+        GitConnectionStatus::RepoNotFound => "Repository not found, or private and the credentials can't access it",
+        GitConnectionStatus::AuthFailed => "Authentication to the Git repository failed",
+        _ => "Failed to connect to the Git repository",
+    };
+    connection_check(status, auth, message.to_string(), Some(detail))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_workflow_normalizes_legacy_statuses() {
+        let workflow = TaskWorkflow::default();
+
+        assert_eq!(workflow.normalize_status("TODO").unwrap(), TASK_STATUS_TODO);
+        assert_eq!(workflow.normalize_status("[todo]").unwrap(), TASK_STATUS_TODO);
+        assert_eq!(workflow.normalize_status("").unwrap(), TASK_STATUS_TODO);
+        assert_eq!(workflow.normalize_status("DONE").unwrap(), TASK_STATUS_COMPLETED);
+        assert_eq!(workflow.normalize_status("COMPLETED").unwrap(), TASK_STATUS_COMPLETED);
+        assert!(workflow.normalize_status("SOMETHING").is_err());
+    }
+
+    #[test]
+    fn test_classify_git_error() {
+        assert_eq!(classify_git_error("ssh: Could not resolve hostname gitlab.example: Name or service not known"), GitConnectionStatus::HostUnreachable);
+        assert_eq!(classify_git_error("git@github.com: Permission denied (publickey)."), GitConnectionStatus::AuthFailed);
+        assert_eq!(classify_git_error("fatal: could not read Username for 'https://github.com': terminal prompts disabled"), GitConnectionStatus::AuthFailed);
+        assert_eq!(classify_git_error("remote: Repository not found.\nfatal: repository 'https://github.com/o/r.git/' not found"), GitConnectionStatus::RepoNotFound);
+        assert_eq!(classify_git_error("Warning: Identity file /keys/deploy not found\ngit@github.com: Permission denied (publickey)."), GitConnectionStatus::AuthFailed);
+        assert_eq!(classify_git_error("fatal: protocol error"), GitConnectionStatus::Failed);
+    }
+
+    #[test]
+    fn test_workflow_transitions() {
+        let workflow = TaskWorkflow::default();
+
+        assert!(workflow.can_transition("[TODO]", "[IN-PROGRESS]"));
+        assert!(workflow.can_transition("[IN-PROGRESS]", "DONE"));
+        assert!(workflow.can_transition("[FAILED]", "TODO"));
+        assert!(!workflow.can_transition("[TODO]", "[FAILED]"));
+        assert!(workflow.validate_transition("[COMPLETED]", "[FAILED]").is_err());
+    }
+
+    #[test]
+    fn test_task_retry_policy() {
+        let policy = TaskRetryPolicy { max_attempts: 3, backoff_seconds: 10, ..Default::default() };
+
+        assert_eq!(TaskRetryPolicy::classify_error("HTTP 429 Too Many Requests"), ERROR_CLASS_RATE_LIMIT);
+        assert_eq!(TaskRetryPolicy::classify_error("Connection reset by peer"), ERROR_CLASS_NETWORK);
+        assert_eq!(TaskRetryPolicy::classify_error("Claude CLI command failed with exit code: Some(1)"), ERROR_CLASS_LLM);
+        assert_eq!(TaskRetryPolicy::classify_error("Project home directory is not set"), ERROR_CLASS_CONFIGURATION);
+
+        assert_eq!(policy.retry_delay(1, ERROR_CLASS_NETWORK), Some(Duration::from_secs(10)));
+        assert_eq!(policy.retry_delay(2, ERROR_CLASS_NETWORK), Some(Duration::from_secs(20)));
+        assert_eq!(policy.retry_delay(3, ERROR_CLASS_NETWORK), None);
+        assert_eq!(policy.retry_delay(1, ERROR_CLASS_CONFIGURATION), None);
+        assert_eq!(TaskRetryPolicy::default().retry_delay(1, ERROR_CLASS_LLM), None);
+    }
+    #[test]
+    fn test_glossary_lookup() {
+        let mut config = ProjectConfig::default();
+        config.glossary = Some(vec![
+            GlossaryTerm { term: "Block".to_string(), definition: "A software component".to_string(), aliases: vec!["module".to_string()] },
+            GlossaryTerm { term: "Block Manager".to_string(), definition: "Stores blocks".to_string(), aliases: Vec::new() },
+        ]);
+
+        assert_eq!(config.lookup_term("MODULE").len(), 1);
+        assert_eq!(config.lookup_term("block")[0].term, "Block");
+        assert_eq!(config.lookup_term("manager")[0].term, "Block Manager");
+        assert!(config.lookup_term("epic").is_empty());
+        assert!(config.glossary_prompt_section().contains("- Block (also: module): A software component"));
     }
 
-    if !(url.starts_with("http://") || url.starts_with("https://") || url.starts_with("git@")) {
-        return Err("Invalid Git repository URL format".to_string());
+    #[test]
+    fn test_operation_provider_override() {
+        use crate::llm_handler::LLMProvider;
+
+        let mut config = ProjectConfig::default();
+        config.llm_provider = Some(LLMProvider::Anthropic);
+        config.operation_providers = Some(OperationProviders {
+            generate_tasks: Some(LLMProvider::Gemini),
+            ..Default::default()
+        });
+        assert_eq!(config.llm_provider_for(LLMOperation::GenerateTasks), Some(LLMProvider::Gemini));
+        assert_eq!(config.llm_provider_for(LLMOperation::AutoComplete), Some(LLMProvider::Anthropic));
+
+        config.operation_models = Some(OperationModels {
+            auto_complete: Some("gpt-4o-mini".to_string()),
+            execute_task: Some(" ".to_string()),
+            ..Default::default()
+        });
+        assert_eq!(config.llm_model_for(LLMOperation::AutoComplete), Some("gpt-4o-mini".to_string()));
+        assert_eq!(config.llm_model_for(LLMOperation::GenerateTasks), None);
+        assert_eq!(config.execution_model(), None);
     }
 
-    // For now, just return success if the URL looks valid
-    Ok("Successfully connected to Git repository".to_string())
+    #[test]
+    fn test_prompt_overrides_fall_back_to_defaults() {
+        let mut config = ProjectConfig::default();
+        assert_eq!(config.prompt("generate_tasks_system_prompt"), DEFAULT_GENERATE_TASKS_SYSTEM_PROMPT);
+
+        config.generate_tasks_system_prompt = Some("You write tiny tasks.".to_string());
+        config.auto_complete_user_prompt = Some(DEFAULT_AUTO_COMPLETE_USER_PROMPT.to_string());
+        config.enhance_description_user_prompt = Some(" ".to_string());
+        assert_eq!(config.prompt("generate_tasks_system_prompt"), "You write tiny tasks.");
+        assert_eq!(config.prompt_override("auto_complete_user_prompt"), None);
+        assert_eq!(config.prompt("enhance_description_user_prompt"), DEFAULT_ENHANCE_DESCRIPTION_USER_PROMPT);
+
+        config.normalize_prompt_overrides();
+        assert!(config.auto_complete_user_prompt.is_none() && config.enhance_description_user_prompt.is_none());
+
+        config.set_prompt_override("generate_tasks_system_prompt", None).unwrap();
+        assert_eq!(config.prompt("generate_tasks_system_prompt"), DEFAULT_GENERATE_TASKS_SYSTEM_PROMPT);
+        assert!(config.set_prompt_override("unknown_prompt", None).is_err());
+    }
+
+    #[test]
+    fn test_config_overlays() {
+        assert_eq!(overlay_file_name("project_config.json", "dev"), "project_config.dev.json");
+        assert_eq!(overlay_file_name("/work/app/project_config.json", "prod"), "/work/app/project_config.prod.json");
+
+        let base = serde_json::json!({
+            "git_repository_url": "https://github.com/o/app.git",
+            "openrouter_model": "base-model",
+            "worktrees": { "enabled": false, "directory": ".worktrees" }
+        });
+        let overlay = serde_json::json!({
+            "openrouter_model": "dev-model",
+            "worktrees": { "enabled": true }
+        });
+        let mut merged = base.clone();
+        merge_overlay(&mut merged, &overlay);
+        assert_eq!(merged["openrouter_model"], "dev-model");
+        assert_eq!(merged["worktrees"], serde_json::json!({ "enabled": true, "directory": ".worktrees" }));
+
+        // Saving writes the changed overlay fields to the overlay and keeps the base values
+        merged["openrouter_model"] = "other-model".into();
+        merged["git_repository_url"] = "https://github.com/o/fork.git".into();
+        let mut saved = merged.as_object().unwrap().clone();
+        let new_overlay = split_overlay(&mut saved, &base, overlay.as_object().unwrap());
+        assert_eq!(serde_json::Value::Object(new_overlay), serde_json::json!({
+            "openrouter_model": "other-model",
+            "worktrees": { "enabled": true }
+        }));
+        assert_eq!(saved["openrouter_model"], "base-model");
+        assert_eq!(saved["worktrees"]["enabled"], false);
+        assert_eq!(saved["git_repository_url"], "https://github.com/o/fork.git");
+    }
+
+    #[test]
+    fn test_resolve_server_address() {
+        assert_eq!(resolve_server_address(None, None, None), DEFAULT_SERVER_ADDRESS);
+        assert_eq!(resolve_server_address(Some("0.0.0.0:9000"), None, None), "0.0.0.0:9000");
+        assert_eq!(resolve_server_address(Some("0.0.0.0:9000"), None, Some(8443)), "0.0.0.0:8443");
+        assert_eq!(resolve_server_address(None, Some("192.168.1.10"), None), "192.168.1.10:8080");
+        assert_eq!(resolve_server_address(Some("[::1]:9000"), Some("::"), None), "[::]:9000");
+    }
+
+    #[test]
+    fn test_task_branch() {
+        let mut task = crate::models::Task::new("Add the login form\nwith validation".to_string());
+        task.task_id = "AB12CD".to_string();
+        let mut config = ProjectConfig::default();
+        assert_eq!(config.task_branch(&task), "AB12CD");
+        assert_eq!(config.task_base_branch(), "main");
+
+        config.branch_workflow = Some(BranchWorkflowConfig { enabled: true, base_branch: Some("develop".to_string()), ..Default::default() });
+        assert_eq!(config.task_branch(&task), "task/AB12CD-add-the-login-form");
+        assert_eq!(config.task_base_branch(), "develop");
+        task.task_name = "Fix: crash on ÄÖ input (again!) and make the long titles of tasks fit".to_string();
+        assert_eq!(config.task_branch(&task), "task/AB12CD-fix-crash-on-input-again-and-make-the");
+    }
+
+    #[test]
+    fn test_validate_timeouts() {
+        let mut config = ProjectConfig::default();
+        assert!(config.validate_timeouts().is_ok());
+
+        config.task_limits = Some(TaskLimits { timeout_minutes: Some(MAX_TIMEOUT_MINUTES), max_memory_mb: None });
+        assert!(config.validate_timeouts().is_ok());
+        config.task_limits = Some(TaskLimits { timeout_minutes: Some(u64::MAX), max_memory_mb: None });
+        assert!(config.validate_timeouts().is_err());
+
+        config.task_limits = None;
+        let hook = ExecutionHook { name: "build".to_string(), command: "make".to_string(), blocking: true, timeout_minutes: u64::MAX / 60 + 1 };
+        config.execution_hooks = Some(ExecutionHooks { before: vec![], after: vec![hook] });
+        assert!(config.validate_timeouts().unwrap_err().contains("hook 'build'"));
+    }
 }
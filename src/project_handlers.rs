@@ -1,24 +1,36 @@
-use crate::profession_prompts::{self, ProfessionCategory};
-use crate::project_config::{test_git_connection, ProjectConfig, ProjectConfigManager};
+use crate::block_config::{resolve_blocks_config_path, BlockConfigManager};
+use crate::config_validation::{self, ValidationReport};
+use crate::llm_audit::{self, AuditQuery};
+use crate::llm_handler::{self, LLMProvider};
+use crate::llm_usage;
+use crate::logging;
+use crate::profession_prompts::{self, CustomProfessionRequest, PackConflictStrategy, ProfessionCategory};
+use crate::prompt_history::{self, PROJECT_SCOPE};
+use crate::project_init::{self, InitProjectRequest};
+use crate::project_registry;
+use crate::request_user::with_current_user;
+use crate::secrets::{self, REDACTED};
+use crate::task_executor;
+use crate::project_config::{test_git_connection, GitAuth, LLMOperation, ProjectConfig, ProjectConfigManager, PROJECT_CONFIG_FILE, PROMPT_DEFAULTS};
 use actix_web::{web, HttpResponse, Responder};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
 use std::sync::Arc;
 
 // AppState for project handlers
 pub struct ProjectAppState {
     pub project_manager: Arc<ProjectConfigManager>,
+    pub block_manager: Arc<BlockConfigManager>,
 }
 
 // Request body for testing Git connection
 #[derive(Debug, Deserialize)]
 pub struct TestGitConnectionRequest {
     pub url: String,
-}
-
-// Response for testing Git connection
-#[derive(Debug, Serialize)]
-pub struct TestGitConnectionResponse {
-    pub message: String,
+    // Credentials to test, git's own configuration by default
+    #[serde(default)]
+    pub auth: GitAuth,
 }
 
 // Response for checking if project settings are configured
@@ -26,6 +38,7 @@ pub struct TestGitConnectionResponse {
 pub struct ProjectConfigStatusResponse {
     pub configured: bool,
     pub message: String,
+    pub report: Option<ValidationReport>,
 }
 
 // Response for getting all professions
@@ -34,6 +47,8 @@ pub struct ProfessionResponse {
     pub id: String,
     pub name: String,
     pub category: String,
+    // Whether the profession was defined by the user (and can be edited or deleted)
+    pub custom: bool,
 }
 
 // Response for getting all profession categories
@@ -66,10 +81,64 @@ pub struct ProfessionPromptsResponse {
     pub process_specification_user_prompt_mcp: String,
 }
 
+// Hide the credentials stored in the config; secret references are returned as they are
+fn redact_config(mut config: ProjectConfig) -> ProjectConfig {
+    if config.openai_api_key.as_deref().is_some_and(|key| !key.is_empty() && !secrets::is_reference(key)) {
+        config.openai_api_key = Some(REDACTED.to_string());
+    }
+    config.git_repository_url = secrets::redact(&config.git_repository_url);
+    for webhook in config.webhooks.iter_mut().flatten() {
+        if webhook.secret.as_deref().is_some_and(|secret| !secret.is_empty() && !secrets::is_reference(secret)) {
+            webhook.secret = Some(REDACTED.to_string());
+        }
+    }
+    if let Some(oidc) = config.oidc.as_mut() {
+        if oidc.client_secret.as_deref().is_some_and(|secret| !secret.is_empty() && !secrets::is_reference(secret)) {
+            oidc.client_secret = Some(REDACTED.to_string());
+        }
+    }
+    if let Some(slack) = config.slack.as_mut() {
+        for credential in [&mut slack.webhook_url, &mut slack.bot_token] {
+            if credential.as_deref().is_some_and(|value| !value.is_empty() && !secrets::is_reference(value)) {
+                *credential = Some(REDACTED.to_string());
+            }
+        }
+    }
+    if let Some(ci) = config.ci.as_mut() {
+        if ci.webhook_secret.as_deref().is_some_and(|secret| !secret.is_empty() && !secrets::is_reference(secret)) {
+            ci.webhook_secret = Some(REDACTED.to_string());
+        }
+    }
+    config
+}
+
+// Keep the stored credentials that come back redacted
+fn restore_redacted(config: &mut ProjectConfig, current: &ProjectConfig) {
+    if config.openai_api_key.as_deref() == Some(REDACTED) {
+        config.openai_api_key = current.openai_api_key.clone();
+    }
+    if config.git_repository_url.contains(REDACTED) {
+        config.git_repository_url = current.git_repository_url.clone();
+    }
+    if let Some(slack) = config.slack.as_mut() {
+        let current_slack = current.slack.as_ref();
+        if slack.webhook_url.as_deref() == Some(REDACTED) {
+            slack.webhook_url = current_slack.and_then(|current| current.webhook_url.clone());
+        }
+        if slack.bot_token.as_deref() == Some(REDACTED) {
+            slack.bot_token = current_slack.and_then(|current| current.bot_token.clone());
+        }
+    }
+    if let Some(ci) = config.ci.as_mut().filter(|ci| ci.webhook_secret.as_deref() == Some(REDACTED)) {
+        ci.webhook_secret = current.ci.as_ref().and_then(|current| current.webhook_secret.clone());
+    }
+    config.keep_access_settings(current);
+}
+
 // Handler to get project configuration
 pub async fn get_project_config_handler(data: web::Data<ProjectAppState>) -> impl Responder {
     match data.project_manager.load_config() {
-        Ok(config) => HttpResponse::Ok().json(config),
+        Ok(config) => HttpResponse::Ok().json(redact_config(config)),
         Err(e) => {
             eprintln!("Error loading project config: {}", e);
             HttpResponse::InternalServerError().body(format!("Error loading project config: {}", e))
@@ -82,6 +151,13 @@ pub async fn update_project_config_handler(
     data: web::Data<ProjectAppState>,
     config: web::Json<ProjectConfig>,
 ) -> impl Responder {
+    let mut config = config.into_inner();
+    if let Err(e) = config.validate_timeouts() {
+        return HttpResponse::BadRequest().body(e);
+    }
+    if let Ok(current) = data.project_manager.get_config() {
+        restore_redacted(&mut config, &current);
+    }
     match data.project_manager.save_config(&config) {
         Ok(_) => {
             // If project_home_directory is specified, ensure it exists
@@ -99,7 +175,8 @@ pub async fn update_project_config_handler(
                     }
                 }
             }
-            HttpResponse::Ok().json(config)
+            data.block_manager.set_auto_commit(config.config_autocommit.clone());
+            HttpResponse::Ok().json(redact_config(config))
         },
         Err(e) => {
             eprintln!("Error saving project config: {}", e);
@@ -108,52 +185,391 @@ pub async fn update_project_config_handler(
     }
 }
 
-// Handler to test Git connection
-pub async fn test_git_connection_handler(
-    request: web::Json<TestGitConnectionRequest>,
+// Request body for opening an existing project directory
+#[derive(Debug, Deserialize)]
+pub struct OpenProjectRequest {
+    pub directory: String,
+}
+
+// Response for opening an existing project directory
+#[derive(Debug, Serialize)]
+pub struct OpenProjectResponse {
+    pub project_config_file: String,
+    pub blocks_config_file: String,
+    pub num_blocks: usize,
+    pub read_only: bool,
+    pub config: ProjectConfig,
+}
+
+// Validate the project and blocks config of an existing directory, then switch the active managers to it.
+// Both files are fully loaded (and migrated to the current schema) before anything is switched.
+pub fn open_project(directory: &str, project_manager: &ProjectConfigManager, block_manager: &BlockConfigManager) -> Result<OpenProjectResponse, String> {
+    let dir = Path::new(directory);
+    if !dir.is_dir() {
+        return Err(format!("Directory {} does not exist", directory));
+    }
+
+    let project_config_file = dir.join(PROJECT_CONFIG_FILE).to_string_lossy().to_string();
+    if !Path::new(&project_config_file).exists() {
+        return Err(format!("No {} found in {}", PROJECT_CONFIG_FILE, directory));
+    }
+    let mut config = ProjectConfigManager::new(&project_config_file).load_config()
+        .map_err(|e| format!("Invalid project config {}: {}", project_config_file, e))?;
+
+    // The blocks config lives in the spec directory if one is configured, otherwise in the project home
+    // directory; relative paths are relative to the project directory
+    let spec_dir = dir.join(config.spec_directory());
+    let blocks_config_file = resolve_blocks_config_path(&spec_dir, config.blocks_format.as_deref())?
+        .to_string_lossy()
+        .to_string();
+    if !Path::new(&blocks_config_file).exists() {
+        return Err(format!("No blocks config found in {}", spec_dir.display()));
+    }
+    let read_only = config.spec_read_only.unwrap_or(false);
+    let candidate = BlockConfigManager::new(&blocks_config_file);
+    candidate.set_read_only(read_only);
+    candidate.load_blocks_from_file()
+        .map_err(|e| format!("Invalid blocks config {}: {}", blocks_config_file, e))?;
+
+    // Save the pending block edits of the current project before leaving it
+    if block_manager.has_unsaved_changes() {
+        block_manager.save_blocks_from("open_project")
+            .map_err(|e| format!("Failed to save the blocks of the current project: {}", e))?;
+    }
+
+    // Switching projects must not turn the authentication of the API off: a project without API keys
+    // or an OIDC sign-in takes those of the project it replaces
+    if let Ok(current) = project_manager.get_config() {
+        if current.requires_authentication() && !config.requires_authentication() {
+            config.api_keys = current.api_keys;
+            config.oidc = current.oidc;
+            ProjectConfigManager::new(&project_config_file).save_config(&config)
+                .map_err(|e| format!("Failed to copy the API credentials to {}: {}", project_config_file, e))?;
+        }
+    }
+
+    let config = project_manager.switch_config_file(&project_config_file)
+        .map_err(|e| format!("Failed to open project config {}: {}", project_config_file, e))?;
+    block_manager.set_auto_commit(config.config_autocommit.clone());
+    block_manager.set_data_directory(Some(directory.to_string()));
+    let blocks = block_manager.switch_config_file(&blocks_config_file, read_only)?;
+
+    Ok(OpenProjectResponse {
+        project_config_file,
+        blocks_config_file,
+        num_blocks: blocks.len(),
+        read_only,
+        config,
+    })
+}
+
+// Handler to open an existing project directory without restarting
+pub async fn open_project_handler(
+    data: web::Data<ProjectAppState>,
+    request: web::Json<OpenProjectRequest>,
 ) -> impl Responder {
-    match test_git_connection(&request.url).await {
-        Ok(message) => HttpResponse::Ok().json(TestGitConnectionResponse { message }),
+    match open_project(&request.directory, &data.project_manager, &data.block_manager) {
+        Ok(response) => {
+            println!("Opened project {} ({} blocks)", request.directory, response.num_blocks);
+            HttpResponse::Ok().json(response)
+        },
         Err(e) => {
-            eprintln!("Error testing Git connection: {}", e);
-            HttpResponse::BadRequest().json(TestGitConnectionResponse { message: e })
+            eprintln!("Error opening project: {}", e);
+            HttpResponse::BadRequest().body(e)
         }
     }
 }
 
-// Handler to check if project settings are configured
-pub async fn check_project_config_handler(data: web::Data<ProjectAppState>) -> impl Responder {
-    match data.project_manager.get_config() {
-        Ok(config) => {
-            // Check if project_home_directory is set
-            if config.project_home_directory.is_empty() {
-                HttpResponse::Ok().json(ProjectConfigStatusResponse {
-                    configured: false,
-                    message: "Project home directory is not set".to_string(),
-                })
-            } else {
-                // Check if the directory exists
-                let project_dir = std::path::Path::new(&config.project_home_directory);
-                if !project_dir.exists() {
-                    HttpResponse::Ok().json(ProjectConfigStatusResponse {
-                        configured: false,
-                        message: "Project home directory does not exist".to_string(),
-                    })
-                } else {
-                    HttpResponse::Ok().json(ProjectConfigStatusResponse {
-                        configured: true,
-                        message: "Project settings are configured".to_string(),
-                    })
-                }
-            }
+// Handler to scaffold a new project in a directory, optionally generating an initial block from
+// its README and switching the server to it
+pub async fn init_project_handler(
+    data: web::Data<ProjectAppState>,
+    request: web::Json<InitProjectRequest>,
+) -> impl Responder {
+    let request = request.into_inner();
+    let scaffold_request = request.clone();
+    let mut report = match web::block(move || project_init::scaffold_project(&scaffold_request)).await {
+        Ok(Ok(report)) => report,
+        Ok(Err(e)) if e.contains("already exists") => return HttpResponse::Conflict().body(e),
+        Ok(Err(e)) => return HttpResponse::BadRequest().body(e),
+        Err(e) => return HttpResponse::InternalServerError().body(format!("Failed to initialize project: {}", e)),
+    };
+
+    if request.generate_block {
+        if let Err(e) = project_init::generate_initial_block(&mut report).await {
+            report.warnings.push(format!("No initial block generated: {}", e));
+        }
+    }
+    if request.open {
+        if let Err(e) = open_project(&report.config.project_home_directory, &data.project_manager, &data.block_manager) {
+            report.warnings.push(format!("Failed to open the new project: {}", e));
+        }
+    }
+    println!("Initialized project in {}", report.config.project_home_directory);
+    HttpResponse::Created().json(report)
+}
+
+// Request body for registering a project
+#[derive(Debug, Deserialize)]
+pub struct RegisterProjectRequest {
+    pub name: String,
+    pub directory: String,
+    // Switch to the project right away
+    #[serde(default)]
+    pub activate: bool,
+}
+
+// Switch the server to a registered project: the project and blocks config, the execution queue
+// and the log files all move to it
+pub fn activate_project(id: &str, project_manager: &ProjectConfigManager, block_manager: &BlockConfigManager) -> Result<OpenProjectResponse, String> {
+    let registry = project_registry::load_registry()?;
+    let profile = registry.get(id)?;
+
+    let executor = task_executor::get_task_executor().ok();
+    if let Some(executor) = &executor {
+        executor.detach_project()?;
+    }
+    let opened = open_project(&profile.directory, project_manager, block_manager);
+    // Resume the queue of whichever project is open now
+    if let Some(executor) = &executor {
+        executor.attach_project();
+    }
+    let response = opened?;
+
+    project_registry::set_active(id)?;
+    logging::switch_project_log(id);
+    Ok(response)
+}
+
+// Handler to list the registered projects and the active one
+pub async fn get_projects_handler() -> impl Responder {
+    match project_registry::load_registry() {
+        Ok(registry) => HttpResponse::Ok().json(registry),
+        Err(e) => HttpResponse::InternalServerError().body(e),
+    }
+}
+
+// Handler to register a project directory, optionally switching to it
+pub async fn register_project_handler(
+    data: web::Data<ProjectAppState>,
+    request: web::Json<RegisterProjectRequest>,
+) -> impl Responder {
+    let profile = match project_registry::register_project(&request.name, &request.directory) {
+        Ok(profile) => profile,
+        Err(e) => return HttpResponse::BadRequest().body(e),
+    };
+    if request.activate {
+        if let Err(e) = activate_project(&profile.id, &data.project_manager, &data.block_manager) {
+            return HttpResponse::Conflict().body(format!("Registered project '{}' but failed to switch to it: {}", profile.id, e));
+        }
+    }
+    HttpResponse::Created().json(profile)
+}
+
+// Handler to switch the server to a registered project without restarting
+pub async fn activate_project_handler(data: web::Data<ProjectAppState>, path: web::Path<String>) -> impl Responder {
+    let id = path.into_inner();
+    match activate_project(&id, &data.project_manager, &data.block_manager) {
+        Ok(response) => {
+            println!("Switched to project {} ({} blocks)", id, response.num_blocks);
+            HttpResponse::Ok().json(response)
         },
+        Err(e) if e.contains("not found") => HttpResponse::NotFound().body(e),
+        Err(e) => HttpResponse::Conflict().body(e),
+    }
+}
+
+// Handler to unregister a project
+pub async fn remove_project_handler(path: web::Path<String>) -> impl Responder {
+    match project_registry::remove_project(&path.into_inner()) {
+        Ok(()) => HttpResponse::Ok().body("Project removed successfully"),
+        Err(e) if e.contains("not found") => HttpResponse::NotFound().body(e),
+        Err(e) => HttpResponse::Conflict().body(e),
+    }
+}
+
+// Request body for storing a secret
+#[derive(Debug, Deserialize)]
+pub struct SetSecretRequest {
+    pub value: String,
+}
+
+// Handler to list the stored secrets; their values are never returned
+pub async fn get_secrets_handler() -> impl Responder {
+    match web::block(secrets::list_secrets).await {
+        Ok(Ok(secrets)) => HttpResponse::Ok().json(secrets),
+        Ok(Err(e)) => HttpResponse::InternalServerError().body(e),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Failed to list secrets: {}", e)),
+    }
+}
+
+// Handler to store a secret, encrypted; config values reference it as {{secret:NAME}}
+pub async fn set_secret_handler(path: web::Path<String>, request: web::Json<SetSecretRequest>) -> impl Responder {
+    let name = path.into_inner();
+    let value = request.into_inner().value;
+    match web::block(move || secrets::set_secret(&name, &value)).await {
+        Ok(Ok(info)) => HttpResponse::Ok().json(info),
+        Ok(Err(e)) if e.starts_with("Invalid") || e.contains("empty") => HttpResponse::BadRequest().body(e),
+        Ok(Err(e)) => HttpResponse::InternalServerError().body(e),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Failed to store secret: {}", e)),
+    }
+}
+
+// Handler to delete a secret
+pub async fn delete_secret_handler(path: web::Path<String>) -> impl Responder {
+    let name = path.into_inner();
+    match web::block(move || secrets::delete_secret(&name)).await {
+        Ok(Ok(())) => HttpResponse::Ok().body("Secret deleted successfully"),
+        Ok(Err(e)) if e.contains("not found") => HttpResponse::NotFound().body(e),
+        Ok(Err(e)) => HttpResponse::InternalServerError().body(e),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Failed to delete secret: {}", e)),
+    }
+}
+
+// Handler to test Git connection
+pub async fn test_git_connection_handler(
+    data: web::Data<ProjectAppState>,
+    request: web::Json<TestGitConnectionRequest>,
+) -> impl Responder {
+    let provider = data.project_manager.get_config().ok().and_then(|c| c.git_provider).unwrap_or_default();
+    let check = test_git_connection(&request.url, &request.auth, provider).await;
+    if check.is_connected() {
+        HttpResponse::Ok().json(check)
+    } else {
+        eprintln!("Error testing Git connection: {}", check.message);
+        HttpResponse::BadRequest().json(check)
+    }
+}
+
+// Query of the config check; probing sends a short prompt to each configured LLM provider
+#[derive(Debug, Deserialize)]
+pub struct CheckProjectConfigQuery {
+    #[serde(default)]
+    pub probe_llm: bool,
+}
+
+// Handler to validate the project settings, returning the errors and warnings found with
+// remediation hints
+pub async fn check_project_config_handler(data: web::Data<ProjectAppState>, query: web::Query<CheckProjectConfigQuery>) -> impl Responder {
+    let config = match data.project_manager.get_config() {
+        Ok(config) => config,
         Err(e) => {
             eprintln!("Error checking project config: {}", e);
-            HttpResponse::Ok().json(ProjectConfigStatusResponse {
+            return HttpResponse::Ok().json(ProjectConfigStatusResponse {
                 configured: false,
                 message: format!("Error checking project config: {}", e),
-            })
+                report: None,
+            });
         }
+    };
+
+    let validated = config.clone();
+    let mut report = match web::block(move || config_validation::validate_project(&validated)).await {
+        Ok(report) => report,
+        Err(e) => return HttpResponse::InternalServerError().body(format!("Failed to validate project config: {}", e)),
+    };
+    if query.probe_llm {
+        config_validation::probe_llm_providers(&config, &mut report).await;
+    }
+
+    // The project can be used once its directory exists; other problems are reported in the report
+    let directory_error = report.errors.iter().find(|e| e.check == "project_directory").map(|e| e.message.clone());
+    HttpResponse::Ok().json(ProjectConfigStatusResponse {
+        configured: directory_error.is_none(),
+        message: directory_error.unwrap_or_else(|| match (report.errors.len(), report.warnings.len()) {
+            (0, 0) => "Project settings are configured".to_string(),
+            (errors, warnings) => format!("Project settings are configured with {} error(s) and {} warning(s)", errors, warnings),
+        }),
+        report: Some(report),
+    })
+}
+
+// Handler to get the cumulative LLM token usage and the remaining budget
+pub async fn get_usage_handler(data: web::Data<ProjectAppState>) -> impl Responder {
+    let budget = match data.project_manager.get_config() {
+        Ok(config) => config.token_budget.unwrap_or_default(),
+        Err(e) => return HttpResponse::InternalServerError().body(format!("Failed to get project config: {}", e)),
+    };
+
+    match web::block(move || llm_usage::usage_report(budget)).await {
+        Ok(Ok(report)) => HttpResponse::Ok().json(report),
+        Ok(Err(e)) => HttpResponse::InternalServerError().body(e),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Failed to read usage: {}", e)),
+    }
+}
+
+// LLM spend of a block, with the name of the block
+#[derive(Debug, Serialize)]
+pub struct BlockCostSummary {
+    pub name: String,
+    #[serde(flatten)]
+    pub cost: llm_usage::BlockCost,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ProjectSummary {
+    pub project_name: Option<String>,
+    pub blocks: usize,
+    pub tasks: usize,
+    pub tasks_by_status: HashMap<String, usize>,
+    pub llm_cost_usd: f64,
+    // Spend attributed to blocks, most expensive first
+    pub block_costs: Vec<BlockCostSummary>,
+}
+
+// Handler to get an overview of the project: blocks, task progress and LLM spend per block/task
+pub async fn get_project_summary_handler(data: web::Data<ProjectAppState>) -> impl Responder {
+    let project_name = data.project_manager.get_config().map(|c| c.project_name).unwrap_or_default();
+    let blocks = match data.block_manager.get_blocks() {
+        Ok(blocks) => blocks,
+        Err(e) => return HttpResponse::InternalServerError().body(e),
+    };
+    let costs = match web::block(llm_usage::load_block_costs).await {
+        Ok(Ok(costs)) => costs,
+        Ok(Err(e)) => return HttpResponse::InternalServerError().body(e),
+        Err(e) => return HttpResponse::InternalServerError().body(format!("Failed to read costs: {}", e)),
+    };
+
+    let mut tasks_by_status: HashMap<String, usize> = HashMap::new();
+    for task in blocks.iter().flat_map(|b| b.todo_list.values()) {
+        *tasks_by_status.entry(task.status.clone()).or_insert(0) += 1;
+    }
+
+    // Costs of deleted blocks are kept, under the block id
+    let mut block_costs: Vec<BlockCostSummary> = costs.into_iter()
+        .map(|cost| BlockCostSummary {
+            name: blocks.iter().find(|b| b.block_id == cost.block_id).map(|b| b.name.clone()).unwrap_or_default(),
+            cost,
+        })
+        .collect();
+    block_costs.sort_by(|a, b| b.cost.total.cost_usd.total_cmp(&a.cost.total.cost_usd));
+
+    HttpResponse::Ok().json(ProjectSummary {
+        project_name,
+        blocks: blocks.len(),
+        tasks: blocks.iter().map(|b| b.todo_list.len()).sum(),
+        tasks_by_status,
+        llm_cost_usd: block_costs.iter().map(|c| c.cost.total.cost_usd).sum(),
+        block_costs,
+    })
+}
+
+// Handler to browse the LLM audit log, most recent calls first
+pub async fn get_llm_audit_handler(query: web::Query<AuditQuery>) -> impl Responder {
+    match web::block(move || llm_audit::query(&query)).await {
+        Ok(Ok(entries)) => HttpResponse::Ok().json(entries),
+        Ok(Err(e)) => HttpResponse::InternalServerError().body(e),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Failed to read audit log: {}", e)),
+    }
+}
+
+pub async fn get_llm_audit_entry_handler(path: web::Path<String>) -> impl Responder {
+    let entry_id = path.into_inner();
+    match web::block(move || llm_audit::get_entry(&entry_id)).await {
+        Ok(Ok(Some(entry))) => HttpResponse::Ok().json(entry),
+        Ok(Ok(None)) => HttpResponse::NotFound().body("Audit entry not found"),
+        Ok(Err(e)) => HttpResponse::InternalServerError().body(e),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Failed to read audit log: {}", e)),
     }
 }
 
@@ -168,6 +584,7 @@ pub async fn get_professions_handler() -> impl Responder {
     // Process each profession
     for profession in all_professions {
         let profession_response = ProfessionResponse {
+            custom: !profession_prompts::is_built_in(&profession.id),
             id: profession.id,
             name: profession.name,
             category: profession.category.display_name().to_string(),
@@ -192,6 +609,134 @@ pub async fn get_professions_handler() -> impl Responder {
     HttpResponse::Ok().json(AllProfessionsResponse { categories })
 }
 
+// Status of a failed custom profession change
+fn profession_error_response(e: String) -> HttpResponse {
+    if e.contains("not found") {
+        HttpResponse::NotFound().body(e)
+    } else {
+        HttpResponse::BadRequest().body(e)
+    }
+}
+
+// Handler to create a custom profession
+pub async fn create_profession_handler(request: web::Json<CustomProfessionRequest>) -> impl Responder {
+    match web::block(with_current_user(move || profession_prompts::create_custom_profession(request.into_inner()))).await {
+        Ok(Ok(profession)) => HttpResponse::Created().json(profession),
+        Ok(Err(e)) => profession_error_response(e),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Failed to create profession: {}", e)),
+    }
+}
+
+// Handler to update a custom profession
+pub async fn update_profession_handler(path: web::Path<String>, request: web::Json<CustomProfessionRequest>) -> impl Responder {
+    let profession_id = path.into_inner();
+    match web::block(with_current_user(move || profession_prompts::update_custom_profession(&profession_id, request.into_inner()))).await {
+        Ok(Ok(profession)) => HttpResponse::Ok().json(profession),
+        Ok(Err(e)) => profession_error_response(e),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Failed to update profession: {}", e)),
+    }
+}
+
+// Handler to delete a custom profession
+pub async fn delete_profession_handler(path: web::Path<String>) -> impl Responder {
+    let profession_id = path.into_inner();
+    match web::block(with_current_user(move || profession_prompts::delete_custom_profession(&profession_id))).await {
+        Ok(Ok(())) => HttpResponse::Ok().body("Profession deleted successfully"),
+        Ok(Err(e)) => profession_error_response(e),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Failed to delete profession: {}", e)),
+    }
+}
+
+// Request body for editing a profession prompt
+#[derive(Debug, Deserialize)]
+pub struct EditProfessionPromptRequest {
+    pub value: String,
+}
+
+// Handler to edit one prompt of a profession; the edit is validated and stored with the project
+pub async fn edit_profession_prompt_handler(path: web::Path<(String, String)>, request: web::Json<EditProfessionPromptRequest>) -> impl Responder {
+    let (profession_id, prompt_key) = path.into_inner();
+    match web::block(with_current_user(move || profession_prompts::edit_profession_prompt(&profession_id, &prompt_key, &request.value))).await {
+        Ok(Ok(profession)) => HttpResponse::Ok().json(profession),
+        Ok(Err(e)) => profession_error_response(e),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Failed to edit prompt: {}", e)),
+    }
+}
+
+// Handler to restore the compiled-in prompt of a built-in profession
+pub async fn reset_profession_prompt_handler(path: web::Path<(String, String)>) -> impl Responder {
+    let (profession_id, prompt_key) = path.into_inner();
+    match web::block(with_current_user(move || profession_prompts::reset_profession_prompt(&profession_id, &prompt_key))).await {
+        Ok(Ok(profession)) => HttpResponse::Ok().json(profession),
+        Ok(Err(e)) => profession_error_response(e),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Failed to reset prompt: {}", e)),
+    }
+}
+
+// Query of a prompt pack export
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct PromptPackExportQuery {
+    // Comma-separated profession ids; all professions when omitted
+    pub profession_ids: Option<String>,
+    // "json" (default) or "yaml"
+    pub format: Option<String>,
+    pub name: Option<String>,
+    pub version: Option<String>,
+}
+
+// Handler to export professions as a prompt pack file
+pub async fn export_prompt_pack_handler(query: web::Query<PromptPackExportQuery>) -> impl Responder {
+    let query = query.into_inner();
+    let profession_ids: Vec<String> = query.profession_ids.unwrap_or_default()
+        .split(',')
+        .map(|id| id.trim().to_string())
+        .filter(|id| !id.is_empty())
+        .collect();
+    let yaml = query.format.as_deref() == Some("yaml");
+
+    let pack = match web::block(move || {
+        profession_prompts::export_prompt_pack(&profession_ids, query.name.as_deref().unwrap_or_default(), query.version.as_deref().unwrap_or("1.0.0"))
+    }).await {
+        Ok(Ok(pack)) => pack,
+        Ok(Err(e)) => return profession_error_response(e),
+        Err(e) => return HttpResponse::InternalServerError().body(format!("Failed to export prompt pack: {}", e)),
+    };
+
+    let (content, extension, content_type) = if yaml {
+        (serde_yaml::to_string(&pack).map_err(|e| e.to_string()), "yaml", "application/yaml")
+    } else {
+        (serde_json::to_string_pretty(&pack).map_err(|e| e.to_string()), "json", "application/json")
+    };
+    match content {
+        Ok(content) => HttpResponse::Ok()
+            .content_type(content_type)
+            .insert_header(("Content-Disposition", format!("attachment; filename=\"prompt-pack.{}\"", extension)))
+            .body(content),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Failed to serialize prompt pack: {}", e)),
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct PromptPackImportQuery {
+    pub conflict: PackConflictStrategy,
+}
+
+// Handler to import a JSON or YAML prompt pack sent as the request body
+pub async fn import_prompt_pack_handler(query: web::Query<PromptPackImportQuery>, body: String) -> impl Responder {
+    let pack = match profession_prompts::parse_prompt_pack(&body) {
+        Ok(pack) => pack,
+        Err(e) => return HttpResponse::BadRequest().body(e),
+    };
+    let strategy = query.conflict;
+    match web::block(with_current_user(move || profession_prompts::import_prompt_pack(pack, strategy))).await {
+        Ok(Ok(report)) => HttpResponse::Ok().json(report),
+        Ok(Err(e)) => HttpResponse::InternalServerError().body(e),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Failed to import prompt pack: {}", e)),
+    }
+}
+
 // Handler to get profession-specific prompts
 pub async fn get_profession_prompts_handler(path: web::Path<String>) -> impl Responder {
     let profession_id = path.into_inner();
@@ -219,3 +764,192 @@ pub async fn get_profession_prompts_handler(path: web::Path<String>) -> impl Res
         HttpResponse::NotFound().body(format!("Profession with ID '{}' not found", profession_id))
     }
 }
+
+// Sample input to try a profession's prompts with
+#[derive(Debug, Deserialize)]
+pub struct PromptPreviewRequest {
+    // Block description (or specification markdown for process_specification)
+    pub description: String,
+    #[serde(default = "default_preview_operation")]
+    pub operation: LLMOperation,
+    // Provider of the operation in the project config when omitted
+    #[serde(default)]
+    pub provider: Option<LLMProvider>,
+    // Send the prompts to the LLM; otherwise they are only rendered
+    #[serde(default)]
+    pub run: bool,
+}
+
+fn default_preview_operation() -> LLMOperation {
+    LLMOperation::GenerateTasks
+}
+
+// Handler to render a profession's prompts for a sample description and optionally dry-run them
+pub async fn preview_profession_prompt_handler(data: web::Data<ProjectAppState>, path: web::Path<String>, request: web::Json<PromptPreviewRequest>) -> impl Responder {
+    let profession_id = path.into_inner();
+    let request = request.into_inner();
+    if request.description.trim().is_empty() {
+        return HttpResponse::BadRequest().body("A sample description is required");
+    }
+    let profession = match profession_prompts::get_profession_by_id(&profession_id) {
+        Some(profession) => profession,
+        None => return HttpResponse::NotFound().body(format!("Profession with ID '{}' not found", profession_id)),
+    };
+    let provider = match request.provider {
+        Some(provider) => provider,
+        None => match data.project_manager.get_config() {
+            Ok(config) => config.llm_provider_for(request.operation).unwrap_or_default(),
+            Err(e) => return HttpResponse::InternalServerError().body(format!("Failed to get project config: {}", e)),
+        },
+    };
+
+    // Providers working through MCP tools get the MCP variants of the task and specification prompts
+    let mcp = provider.uses_mcp() && matches!(request.operation, LLMOperation::GenerateTasks | LLMOperation::ProcessSpecification);
+    let suffix = if mcp { "_mcp" } else { "" };
+    let operation = request.operation.name();
+    let system_template = profession.prompts.prompt(&format!("{}_system_prompt{}", operation, suffix)).unwrap_or_default().to_string();
+    let user_template = profession.prompts.prompt(&format!("{}_user_prompt{}", operation, suffix)).unwrap_or_default().to_string();
+
+    match llm_handler::preview_prompt(request.operation, &system_template, &user_template, &request.description, provider, request.run).await {
+        Ok(preview) => HttpResponse::Ok().json(preview),
+        Err(e) => HttpResponse::InternalServerError().body(e),
+    }
+}
+
+// A project prompt with the default it falls back to
+#[derive(Debug, Serialize)]
+pub struct ProjectPromptResponse {
+    pub key: String,
+    // Prompt used by the project: its override or the default
+    pub value: String,
+    pub default: String,
+    pub overridden: bool,
+}
+
+fn project_prompt_responses(config: &ProjectConfig) -> Vec<ProjectPromptResponse> {
+    PROMPT_DEFAULTS.iter()
+        .map(|(key, default)| ProjectPromptResponse {
+            key: key.to_string(),
+            value: config.prompt(key).to_string(),
+            default: default.to_string(),
+            overridden: config.prompt_override(key).is_some(),
+        })
+        .collect()
+}
+
+// Handler to list the project prompts and which of them are overridden
+pub async fn get_project_prompts_handler(data: web::Data<ProjectAppState>) -> impl Responder {
+    match data.project_manager.load_config() {
+        Ok(config) => HttpResponse::Ok().json(project_prompt_responses(&config)),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Error loading project config: {}", e)),
+    }
+}
+
+// Set (Some) or reset (None) a project prompt override and save the config
+fn change_project_prompt(data: &ProjectAppState, key: &str, value: Option<String>) -> HttpResponse {
+    if !PROMPT_DEFAULTS.iter().any(|(k, _)| *k == key) {
+        return HttpResponse::NotFound().body(format!("Prompt '{}' not found", key));
+    }
+    if let Some(value) = &value {
+        if let Err(e) = profession_prompts::validate_prompt(key, value) {
+            return HttpResponse::BadRequest().body(e);
+        }
+    }
+
+    let mut config = match data.project_manager.load_config() {
+        Ok(config) => config,
+        Err(e) => return HttpResponse::InternalServerError().body(format!("Error loading project config: {}", e)),
+    };
+    if let Err(e) = config.set_prompt_override(key, value) {
+        return HttpResponse::BadRequest().body(e);
+    }
+    match data.project_manager.save_config(&config) {
+        Ok(()) => HttpResponse::Ok().json(project_prompt_responses(&config)),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Error saving project config: {}", e)),
+    }
+}
+
+// Handler to override one project prompt
+pub async fn set_project_prompt_handler(data: web::Data<ProjectAppState>, path: web::Path<String>, request: web::Json<EditProfessionPromptRequest>) -> impl Responder {
+    change_project_prompt(&data, &path.into_inner(), Some(request.into_inner().value))
+}
+
+// Handler to reset one project prompt to its default
+pub async fn reset_project_prompt_handler(data: web::Data<ProjectAppState>, path: web::Path<String>) -> impl Responder {
+    change_project_prompt(&data, &path.into_inner(), None)
+}
+
+// Prompt of the project ("project" scope) or of a profession (scope is the profession id)
+#[derive(Debug, Deserialize)]
+pub struct PromptHistoryQuery {
+    #[serde(default = "default_prompt_scope")]
+    pub scope: String,
+    pub key: String,
+}
+
+fn default_prompt_scope() -> String {
+    PROJECT_SCOPE.to_string()
+}
+
+// Handler to list the versions of a prompt, most recent first
+pub async fn get_prompt_history_handler(query: web::Query<PromptHistoryQuery>) -> impl Responder {
+    match web::block(move || prompt_history::history(&query.scope, &query.key)).await {
+        Ok(Ok(versions)) => HttpResponse::Ok().json(versions),
+        Ok(Err(e)) => HttpResponse::InternalServerError().body(e),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Failed to read prompt history: {}", e)),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PromptDiffQuery {
+    #[serde(flatten)]
+    pub prompt: PromptHistoryQuery,
+    pub from: u32,
+    // Latest version when omitted
+    pub to: Option<u32>,
+}
+
+// Handler to compare two versions of a prompt
+pub async fn get_prompt_diff_handler(query: web::Query<PromptDiffQuery>) -> impl Responder {
+    let query = query.into_inner();
+    match web::block(move || prompt_history::diff(&query.prompt.scope, &query.prompt.key, query.from, query.to)).await {
+        Ok(Ok(diff)) => HttpResponse::Ok().json(diff),
+        Ok(Err(e)) => profession_error_response(e),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Failed to diff prompt versions: {}", e)),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PromptRollbackRequest {
+    #[serde(flatten)]
+    pub prompt: PromptHistoryQuery,
+    pub version: u32,
+}
+
+// Handler to restore a previous version of a prompt; the rollback becomes the newest version
+pub async fn rollback_prompt_handler(data: web::Data<ProjectAppState>, request: web::Json<PromptRollbackRequest>) -> impl Responder {
+    let PromptRollbackRequest { prompt: PromptHistoryQuery { scope, key }, version } = request.into_inner();
+
+    if scope == PROJECT_SCOPE {
+        let version = match prompt_history::get_version(&scope, &key, version) {
+            Ok(version) => version,
+            Err(e) => return profession_error_response(e),
+        };
+        let response = change_project_prompt(&data, &key, (!version.default).then_some(version.value));
+        if !response.status().is_success() {
+            return response;
+        }
+    } else {
+        let (profession_id, prompt_key) = (scope.clone(), key.clone());
+        match web::block(with_current_user(move || profession_prompts::rollback_profession_prompt(&profession_id, &prompt_key, version))).await {
+            Ok(Ok(_)) => {}
+            Ok(Err(e)) => return profession_error_response(e),
+            Err(e) => return HttpResponse::InternalServerError().body(format!("Failed to roll back prompt: {}", e)),
+        }
+    }
+
+    match prompt_history::history(&scope, &key) {
+        Ok(versions) => HttpResponse::Ok().json(versions),
+        Err(e) => HttpResponse::InternalServerError().body(e),
+    }
+}
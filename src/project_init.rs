@@ -0,0 +1,229 @@
+use crate::block_config::{resolve_blocks_config_path, write_file_atomic, BlocksFormat};
+use crate::llm_handler;
+use crate::models::Block;
+use crate::prompt_template::{analyze_project, ProjectAnalysis};
+use crate::project_config::{LLMOperation, ProjectConfig, ProjectConfigManager, PROJECT_CONFIG_FILE};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+// README files an initial block can be generated from, in order of preference
+const README_FILES: &[&str] = &["README.md", "README.rst", "README.txt", "README", "readme.md"];
+
+// Characters of the README sent to the LLM
+const README_MAX_CHARS: usize = 8000;
+
+// Manifests whose dependencies hint at the kind of project
+const MANIFEST_FILES: &[&str] = &["package.json", "requirements.txt", "pyproject.toml", "build.gradle", "build.gradle.kts", "pubspec.yaml"];
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct InitProjectRequest {
+    pub directory: String,
+    // Defaults to the directory name
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+    // Defaults to the profession suggested for the detected tech stack
+    #[serde(default)]
+    pub profession_id: Option<String>,
+    // json, yaml or toml; defaults to json
+    #[serde(default)]
+    pub blocks_format: Option<String>,
+    // Generate an initial block from the README of the repository
+    #[serde(default)]
+    pub generate_block: bool,
+    // Switch the server to the new project
+    #[serde(default)]
+    pub open: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct InitProjectReport {
+    pub project_config_file: String,
+    pub blocks_config_file: String,
+    pub git_repository_url: Option<String>,
+    pub main_branch: Option<String>,
+    pub primary_language: Option<String>,
+    pub tech_stack: Vec<String>,
+    pub profession_id: Option<String>,
+    // Name of the block generated from the README
+    pub initial_block: Option<String>,
+    pub warnings: Vec<String>,
+    #[serde(skip)]
+    pub config: ProjectConfig,
+}
+
+fn git(dir: &Path, args: &[&str]) -> Option<String> {
+    let output = Command::new("git").args(args).current_dir(dir).output().ok()?;
+    let stdout = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (output.status.success() && !stdout.is_empty()).then_some(stdout)
+}
+
+// URL of the origin remote, or of the first remote
+fn detect_git_remote(dir: &Path) -> Option<String> {
+    git(dir, &["remote", "get-url", "origin"]).or_else(|| {
+        let remote = git(dir, &["remote"])?.lines().next()?.to_string();
+        git(dir, &["remote", "get-url", &remote])
+    })
+}
+
+// Default branch of the origin remote, or the checked out branch
+fn detect_default_branch(dir: &Path) -> Option<String> {
+    git(dir, &["symbolic-ref", "--short", "refs/remotes/origin/HEAD"])
+        .map(|branch| branch.trim_start_matches("origin/").to_string())
+        .or_else(|| git(dir, &["symbolic-ref", "--short", "HEAD"]))
+}
+
+// Profession matching the detected technologies and the dependencies listed in the manifests
+fn suggest_profession(analysis: &ProjectAnalysis, manifests: &str) -> Option<&'static str> {
+    let manifests = manifests.to_lowercase();
+    let uses = |deps: &[&str]| deps.iter().any(|dep| manifests.contains(dep));
+
+    if uses(&["com.android", "flutter", "react-native", "\"expo\""]) {
+        return Some("mobile_developer");
+    }
+    if uses(&["torch", "tensorflow", "scikit-learn", "keras", "transformers"]) {
+        return Some("ml_engineer");
+    }
+    if uses(&["pandas", "jupyter", "matplotlib"]) {
+        return Some("data_scientist");
+    }
+
+    let frontend = uses(&["\"react\"", "\"vue\"", "\"svelte\"", "@angular/core", "\"next\""]);
+    let backend = uses(&["\"express\"", "\"fastify\"", "@nestjs/core", "\"koa\""])
+        || analysis.primary_language.as_deref().is_some_and(|l| !matches!(l, "JavaScript" | "TypeScript"));
+    match (frontend, backend) {
+        (true, true) => Some("fullstack_developer"),
+        (true, false) => Some("frontend_developer"),
+        (false, true) => Some("backend_developer"),
+        (false, false) if analysis.tech_stack.iter().any(|t| t == "Docker") => Some("devops_engineer"),
+        (false, false) if analysis.primary_language.is_some() => Some("fullstack_developer"),
+        (false, false) => None,
+    }
+}
+
+// Create the project and blocks config of a directory, filled in from its repository
+pub fn scaffold_project(request: &InitProjectRequest) -> Result<InitProjectReport, String> {
+    let dir = Path::new(&request.directory);
+    fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", request.directory, e))?;
+    let dir = dir.canonicalize().map_err(|e| format!("Failed to resolve {}: {}", request.directory, e))?;
+    let project_config_file = dir.join(PROJECT_CONFIG_FILE).to_string_lossy().to_string();
+    if Path::new(&project_config_file).exists() {
+        return Err(format!("{} already exists; open the project instead", project_config_file));
+    }
+
+    let mut warnings = Vec::new();
+    let is_repository = git(&dir, &["rev-parse", "--is-inside-work-tree"]).is_some();
+    if !is_repository {
+        warnings.push(format!("{} is not a git repository; run 'git init' before executing tasks", dir.display()));
+    }
+    let git_repository_url = detect_git_remote(&dir);
+    if is_repository && git_repository_url.is_none() {
+        warnings.push("The repository has no remote; set the Git repository URL to push task branches".to_string());
+    }
+    let main_branch = detect_default_branch(&dir);
+
+    let directory = dir.to_string_lossy().to_string();
+    let analysis = analyze_project(&directory);
+    let manifests: String = MANIFEST_FILES.iter()
+        .filter_map(|file| fs::read_to_string(dir.join(file)).ok())
+        .collect::<Vec<_>>()
+        .join("\n");
+    let profession_id = request.profession_id.clone()
+        .filter(|id| !id.is_empty())
+        .or_else(|| suggest_profession(&analysis, &manifests).map(String::from));
+    if let Some(id) = &profession_id {
+        if crate::profession_prompts::get_profession_by_id(id).is_none() {
+            return Err(format!("Profession '{}' not found", id));
+        }
+    }
+
+    let name = request.name.clone()
+        .filter(|n| !n.trim().is_empty())
+        .or_else(|| dir.file_name().map(|n| n.to_string_lossy().to_string()));
+    let config = ProjectConfig {
+        git_repository_url: git_repository_url.clone().unwrap_or_default(),
+        project_home_directory: directory.clone(),
+        project_description: request.description.clone().unwrap_or_default(),
+        main_branch: Some(main_branch.clone().unwrap_or_else(|| "main".to_string())),
+        project_name: name,
+        blocks_format: request.blocks_format.clone().filter(|f| !f.is_empty()),
+        selected_profession_id: profession_id.clone(),
+        ..ProjectConfig::default()
+    };
+
+    let blocks_config_file = resolve_blocks_config_path(&dir, config.blocks_format.as_deref())?
+        .to_string_lossy()
+        .to_string();
+    if !Path::new(&blocks_config_file).exists() {
+        let content = BlocksFormat::from_path(&blocks_config_file).serialize(&[])?;
+        write_file_atomic(&blocks_config_file, content.as_bytes())?;
+    }
+    ProjectConfigManager::new(&project_config_file).save_config(&config)
+        .map_err(|e| format!("Failed to write {}: {}", project_config_file, e))?;
+
+    Ok(InitProjectReport {
+        project_config_file,
+        blocks_config_file,
+        git_repository_url,
+        main_branch,
+        primary_language: analysis.primary_language,
+        tech_stack: analysis.tech_stack,
+        profession_id,
+        initial_block: None,
+        warnings,
+        config,
+    })
+}
+
+// Generate a block describing the project from its README and add it to the blocks config.
+// Uses the prompts of the project the config manager has open.
+pub async fn generate_initial_block(report: &mut InitProjectReport) -> Result<(), String> {
+    let dir = Path::new(&report.config.project_home_directory);
+    let readme = README_FILES.iter()
+        .find_map(|file| fs::read_to_string(dir.join(file)).ok())
+        .filter(|content| !content.trim().is_empty())
+        .ok_or_else(|| format!("No README found in {}", dir.display()))?;
+    let excerpt: String = readme.chars().take(README_MAX_CHARS).collect();
+
+    let provider = report.config.llm_provider_for(LLMOperation::EnhanceDescription);
+    let description = llm_handler::enhance_description(&excerpt, provider, None).await?;
+    let name = report.config.project_name.clone().unwrap_or_else(|| "Project".to_string());
+    let block = Block::new(name.clone(), description, Vec::new(), Vec::new());
+
+    let format = BlocksFormat::from_path(&report.blocks_config_file);
+    let mut blocks = fs::read_to_string(&report.blocks_config_file).ok()
+        .map(|content| format.parse(&content))
+        .transpose()?
+        .unwrap_or_default();
+    blocks.push(block);
+    write_file_atomic(&report.blocks_config_file, format.serialize(&blocks)?.as_bytes())?;
+    report.initial_block = Some(name);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn analysis(language: Option<&str>, tech_stack: &[&str]) -> ProjectAnalysis {
+        ProjectAnalysis {
+            primary_language: language.map(String::from),
+            tech_stack: tech_stack.iter().map(|t| t.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_suggest_profession() {
+        let node = analysis(Some("TypeScript"), &["Node.js", "TypeScript"]);
+        assert_eq!(suggest_profession(&node, r#"{"dependencies": {"react": "18"}}"#), Some("frontend_developer"));
+        assert_eq!(suggest_profession(&node, r#"{"dependencies": {"react": "18", "express": "4"}}"#), Some("fullstack_developer"));
+        assert_eq!(suggest_profession(&node, r#"{"dependencies": {"fastify": "4"}}"#), Some("backend_developer"));
+        assert_eq!(suggest_profession(&analysis(Some("Rust"), &["Cargo"]), ""), Some("backend_developer"));
+        assert_eq!(suggest_profession(&analysis(Some("Python"), &["pip"]), "torch==2.1\nnumpy"), Some("ml_engineer"));
+        assert_eq!(suggest_profession(&analysis(None, &["Docker"]), ""), Some("devops_engineer"));
+        assert_eq!(suggest_profession(&analysis(None, &[]), ""), None);
+    }
+}
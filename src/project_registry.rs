@@ -0,0 +1,147 @@
+use crate::block_config::{write_file_atomic, FileLock};
+use crate::project_config::PROJECT_CONFIG_FILE;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+// Projects registered with this Forge server, stored in the directory the server was started in
+pub const PROJECTS_FILE: &str = "forge_projects.json";
+
+// A registered project directory the server can switch to
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectProfile {
+    pub id: String,
+    pub name: String,
+    pub directory: String,
+    pub created_at: String,
+    #[serde(default)]
+    pub last_opened_at: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProjectRegistry {
+    // Id of the project the server works on
+    pub active: Option<String>,
+    pub projects: Vec<ProjectProfile>,
+}
+
+impl ProjectRegistry {
+    pub fn get(&self, id: &str) -> Result<&ProjectProfile, String> {
+        self.projects.iter()
+            .find(|p| p.id == id)
+            .ok_or_else(|| format!("Project '{}' not found", id))
+    }
+
+    pub fn active_project(&self) -> Option<&ProjectProfile> {
+        self.active.as_deref().and_then(|id| self.get(id).ok())
+    }
+
+    // Add a project; its id is the slug of its name, made unique
+    fn add(&mut self, name: &str, directory: &str) -> Result<ProjectProfile, String> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err("Project name cannot be empty".to_string());
+        }
+        if let Some(existing) = self.projects.iter().find(|p| p.directory == directory) {
+            return Err(format!("Directory {} is already registered as project '{}'", directory, existing.id));
+        }
+
+        let slug: String = name.to_lowercase()
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+            .collect::<String>()
+            .split('-')
+            .filter(|part| !part.is_empty())
+            .collect::<Vec<_>>()
+            .join("-");
+        let base = if slug.is_empty() { "project".to_string() } else { slug };
+        let mut id = base.clone();
+        let mut suffix = 2;
+        while self.projects.iter().any(|p| p.id == id) {
+            id = format!("{}-{}", base, suffix);
+            suffix += 1;
+        }
+
+        let profile = ProjectProfile {
+            id,
+            name: name.to_string(),
+            directory: directory.to_string(),
+            created_at: Utc::now().to_rfc3339(),
+            last_opened_at: None,
+        };
+        self.projects.push(profile.clone());
+        Ok(profile)
+    }
+}
+
+pub fn load_registry() -> Result<ProjectRegistry, String> {
+    if !Path::new(PROJECTS_FILE).exists() {
+        return Ok(ProjectRegistry::default());
+    }
+    let content = fs::read_to_string(PROJECTS_FILE).map_err(|e| format!("Failed to read {}: {}", PROJECTS_FILE, e))?;
+    serde_json::from_str(&content).map_err(|e| format!("Failed to parse {}: {}", PROJECTS_FILE, e))
+}
+
+fn modify_registry<T>(change: impl FnOnce(&mut ProjectRegistry) -> Result<T, String>) -> Result<T, String> {
+    let _lock = FileLock::acquire(PROJECTS_FILE)?;
+    let mut registry = load_registry()?;
+    let result = change(&mut registry)?;
+    let json = serde_json::to_string_pretty(&registry).map_err(|e| format!("Failed to serialize {}: {}", PROJECTS_FILE, e))?;
+    write_file_atomic(PROJECTS_FILE, json.as_bytes())?;
+    Ok(result)
+}
+
+// Register a directory containing a project config
+pub fn register_project(name: &str, directory: &str) -> Result<ProjectProfile, String> {
+    let dir = Path::new(directory);
+    if !dir.join(PROJECT_CONFIG_FILE).exists() {
+        return Err(format!("No {} found in {}", PROJECT_CONFIG_FILE, directory));
+    }
+    let directory = dir.canonicalize()
+        .map(|d| d.to_string_lossy().to_string())
+        .unwrap_or_else(|_| directory.to_string());
+    modify_registry(|registry| registry.add(name, &directory))
+}
+
+// Mark a project as the active one after the server switched to it
+pub fn set_active(id: &str) -> Result<ProjectProfile, String> {
+    modify_registry(|registry| {
+        let profile = registry.projects.iter_mut()
+            .find(|p| p.id == id)
+            .ok_or_else(|| format!("Project '{}' not found", id))?;
+        profile.last_opened_at = Some(Utc::now().to_rfc3339());
+        let profile = profile.clone();
+        registry.active = Some(profile.id.clone());
+        Ok(profile)
+    })
+}
+
+// Unregister a project; the project files are left alone
+pub fn remove_project(id: &str) -> Result<(), String> {
+    modify_registry(|registry| {
+        if registry.active.as_deref() == Some(id) {
+            return Err(format!("Project '{}' is active; switch to another project first", id));
+        }
+        registry.get(id)?;
+        registry.projects.retain(|p| p.id != id);
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_add_project() {
+        let mut registry = ProjectRegistry::default();
+        assert_eq!(registry.add("Forge Web", "/work/forge").unwrap().id, "forge-web");
+        assert_eq!(registry.add("forge web!", "/work/forge-2").unwrap().id, "forge-web-2");
+        assert!(registry.add("Other", "/work/forge").unwrap_err().contains("already registered"));
+        assert!(registry.add(" ", "/work/empty").is_err());
+
+        registry.active = Some("forge-web-2".to_string());
+        assert_eq!(registry.active_project().unwrap().directory, "/work/forge-2");
+    }
+}
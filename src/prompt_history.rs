@@ -0,0 +1,180 @@
+use crate::block_config::{write_file_atomic, FileLock};
+use crate::project_config::ProjectConfigManager;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use similar::TextDiff;
+use std::fs;
+use std::path::Path;
+
+// Versions of edited prompts, stored next to the project config
+pub const PROMPT_HISTORY_FILE: &str = "prompt_history.json";
+
+// Scope of the prompts of the project config; other scopes are profession ids
+pub const PROJECT_SCOPE: &str = "project";
+
+// Number of versions kept per prompt
+const MAX_VERSIONS: usize = 50;
+
+// Text of a prompt and whether it is the default rather than an override
+#[derive(Debug, Clone, PartialEq)]
+pub struct PromptState {
+    pub value: String,
+    pub default: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PromptVersion {
+    pub scope: String,
+    pub key: String,
+    pub version: u32,
+    pub value: String,
+    pub default: bool,
+    pub timestamp: String,
+    // User who made the edit; None for the prompt before its first edit
+    #[serde(default)]
+    pub user: Option<String>,
+}
+
+impl PromptVersion {
+    pub fn state(&self) -> PromptState {
+        PromptState { value: self.value.clone(), default: self.default }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct PromptDiff {
+    pub scope: String,
+    pub key: String,
+    pub from_version: u32,
+    pub to_version: u32,
+    // Unified diff of the two versions
+    pub diff: String,
+}
+
+fn history_file() -> String {
+    ProjectConfigManager::get_instance().project_data_file(PROMPT_HISTORY_FILE)
+}
+
+fn load_versions(file: &str) -> Result<Vec<PromptVersion>, String> {
+    if !Path::new(file).exists() {
+        return Ok(Vec::new());
+    }
+    let content = fs::read_to_string(file).map_err(|e| format!("Failed to read prompt history: {}", e))?;
+    serde_json::from_str(&content).map_err(|e| format!("Failed to parse prompt history: {}", e))
+}
+
+// Add a change to the versions; the prompt before its first edit becomes version 1 so it can be
+// rolled back to
+fn push_change(versions: &mut Vec<PromptVersion>, scope: &str, key: &str, previous: PromptState, current: PromptState) -> bool {
+    let push = |versions: &mut Vec<PromptVersion>, state: PromptState, user: Option<String>| {
+        let existing: Vec<&PromptVersion> = versions.iter().filter(|v| v.scope == scope && v.key == key).collect();
+        if existing.last().is_some_and(|last| last.state() == state) {
+            return false;
+        }
+        let version = existing.last().map_or(1, |last| last.version + 1);
+        if existing.len() >= MAX_VERSIONS {
+            if let Some(oldest) = versions.iter().position(|v| v.scope == scope && v.key == key) {
+                versions.remove(oldest);
+            }
+        }
+        versions.push(PromptVersion {
+            scope: scope.to_string(),
+            key: key.to_string(),
+            version,
+            value: state.value,
+            default: state.default,
+            timestamp: Utc::now().to_rfc3339(),
+            user,
+        });
+        true
+    };
+
+    if previous == current {
+        return false;
+    }
+    push(versions, previous, None);
+    push(versions, current, crate::request_user::current_user())
+}
+
+// Record an edit of a prompt
+pub fn record_change(scope: &str, key: &str, previous: PromptState, current: PromptState) -> Result<(), String> {
+    if previous == current {
+        return Ok(());
+    }
+    let file = history_file();
+    let _lock = FileLock::acquire(&file)?;
+    let mut versions = load_versions(&file)?;
+    if push_change(&mut versions, scope, key, previous, current) {
+        let json = serde_json::to_string_pretty(&versions).map_err(|e| format!("Failed to serialize prompt history: {}", e))?;
+        write_file_atomic(&file, json.as_bytes())?;
+    }
+    Ok(())
+}
+
+// Versions of a prompt, most recent first
+pub fn history(scope: &str, key: &str) -> Result<Vec<PromptVersion>, String> {
+    Ok(load_versions(&history_file())?.into_iter()
+        .rev()
+        .filter(|v| v.scope == scope && v.key == key)
+        .collect())
+}
+
+pub fn get_version(scope: &str, key: &str, version: u32) -> Result<PromptVersion, String> {
+    history(scope, key)?.into_iter()
+        .find(|v| v.version == version)
+        .ok_or_else(|| format!("Version {} of prompt '{}' in '{}' not found", version, key, scope))
+}
+
+fn unified_diff(from: &PromptVersion, to: &PromptVersion) -> String {
+    TextDiff::from_lines(&from.value, &to.value)
+        .unified_diff()
+        .context_radius(3)
+        .header(&format!("version {}", from.version), &format!("version {}", to.version))
+        .to_string()
+}
+
+// Diff between two versions of a prompt; `to` defaults to the latest version
+pub fn diff(scope: &str, key: &str, from: u32, to: Option<u32>) -> Result<PromptDiff, String> {
+    let versions = history(scope, key)?;
+    let find = |version: u32| versions.iter()
+        .find(|v| v.version == version)
+        .ok_or_else(|| format!("Version {} of prompt '{}' in '{}' not found", version, key, scope));
+    let from = find(from)?;
+    let to = match to {
+        Some(version) => find(version)?,
+        None => versions.first().ok_or_else(|| format!("Prompt '{}' in '{}' has no history", key, scope))?,
+    };
+
+    Ok(PromptDiff {
+        scope: scope.to_string(),
+        key: key.to_string(),
+        from_version: from.version,
+        to_version: to.version,
+        diff: unified_diff(from, to),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(value: &str, default: bool) -> PromptState {
+        PromptState { value: value.to_string(), default }
+    }
+
+    #[test]
+    fn test_push_change_keeps_the_original_prompt() {
+        let mut versions = Vec::new();
+        assert!(push_change(&mut versions, PROJECT_SCOPE, "a", state("Default\n", true), state("Edited\n", false)));
+        assert!(push_change(&mut versions, PROJECT_SCOPE, "a", state("Edited\n", false), state("Edited again\n", false)));
+        assert!(!push_change(&mut versions, PROJECT_SCOPE, "a", state("Same\n", false), state("Same\n", false)));
+        assert!(push_change(&mut versions, "backend_developer", "a", state("Other\n", true), state("Changed\n", false)));
+
+        let project: Vec<u32> = versions.iter().filter(|v| v.scope == PROJECT_SCOPE).map(|v| v.version).collect();
+        assert_eq!(project, vec![1, 2, 3]);
+        assert!(versions[0].default && !versions[1].default);
+
+        let diff = unified_diff(&versions[0], &versions[2]);
+        assert!(diff.contains("-Default") && diff.contains("+Edited again"));
+    }
+}
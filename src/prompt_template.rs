@@ -0,0 +1,192 @@
+use crate::block_config::{resolve_blocks_config_path, BlocksFormat};
+use crate::models::Block;
+use crate::project_config::ProjectConfig;
+use regex::Regex;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+// Variable the input of an operation (block description or specification) is inserted at
+pub const INPUT_VARIABLE: &str = "input";
+
+// Characters of a block description shown in the list of existing blocks
+const BLOCK_SUMMARY_CHARS: usize = 120;
+
+// Marker files used to detect the technologies of a project
+const TECH_MARKERS: &[(&str, &str, &str)] = &[
+    ("Cargo.toml", "Rust", "Cargo"),
+    ("package.json", "JavaScript", "Node.js"),
+    ("tsconfig.json", "TypeScript", "TypeScript"),
+    ("pyproject.toml", "Python", "Python"),
+    ("requirements.txt", "Python", "pip"),
+    ("go.mod", "Go", "Go modules"),
+    ("pom.xml", "Java", "Maven"),
+    ("build.gradle", "Java", "Gradle"),
+    ("build.gradle.kts", "Kotlin", "Gradle"),
+    ("Gemfile", "Ruby", "Bundler"),
+    ("composer.json", "PHP", "Composer"),
+    ("CMakeLists.txt", "C++", "CMake"),
+    ("Dockerfile", "", "Docker"),
+];
+
+// Technologies detected in a project directory
+#[derive(Debug, Clone, Default)]
+pub struct ProjectAnalysis {
+    pub primary_language: Option<String>,
+    pub tech_stack: Vec<String>,
+}
+
+// Inspect the project directory for well-known build and manifest files
+pub fn analyze_project(project_dir: &str) -> ProjectAnalysis {
+    let mut analysis = ProjectAnalysis::default();
+    if project_dir.is_empty() {
+        return analysis;
+    }
+
+    let dir = Path::new(project_dir);
+    for (marker, language, tool) in TECH_MARKERS {
+        if !dir.join(marker).exists() {
+            continue;
+        }
+
+        // TypeScript takes precedence over plain JavaScript
+        if *language == "TypeScript" && analysis.primary_language.as_deref() == Some("JavaScript") {
+            analysis.primary_language = Some(language.to_string());
+        }
+        if analysis.primary_language.is_none() && !language.is_empty() {
+            analysis.primary_language = Some(language.to_string());
+        }
+        if !analysis.tech_stack.contains(&tool.to_string()) {
+            analysis.tech_stack.push(tool.to_string());
+        }
+    }
+
+    analysis
+}
+
+// Collect the project metadata variables available to every prompt.
+// Values set explicitly in the project config win over the detected ones.
+pub fn project_variables(config: &ProjectConfig) -> HashMap<String, String> {
+    let analysis = analyze_project(&config.project_home_directory);
+    let mut vars = HashMap::new();
+
+    let project_name = config.project_name.clone()
+        .filter(|n| !n.is_empty())
+        .or_else(|| Path::new(&config.project_home_directory)
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string()))
+        .unwrap_or_default();
+
+    let tech_stack = config.tech_stack.clone()
+        .filter(|t| !t.is_empty())
+        .unwrap_or_else(|| analysis.tech_stack.join(", "));
+
+    let primary_language = config.primary_language.clone()
+        .filter(|l| !l.is_empty())
+        .or(analysis.primary_language)
+        .unwrap_or_default();
+
+    vars.insert("project_name".to_string(), project_name);
+    vars.insert("project_description".to_string(), config.project_description.clone());
+    vars.insert("tech_stack".to_string(), tech_stack);
+    vars.insert("primary_language".to_string(), primary_language);
+    vars.insert("conventions_summary".to_string(), config.conventions_summary.clone().unwrap_or_default());
+
+    vars
+}
+
+// Blocks of the project as last saved in the spec directory
+pub fn load_project_blocks(config: &ProjectConfig) -> Vec<Block> {
+    let spec_directory = config.spec_directory();
+    if spec_directory.is_empty() {
+        return Vec::new();
+    }
+    let path = match resolve_blocks_config_path(Path::new(&spec_directory), config.blocks_format.as_deref()) {
+        Ok(path) => path.to_string_lossy().to_string(),
+        Err(_) => return Vec::new(),
+    };
+    fs::read_to_string(&path).ok()
+        .and_then(|content| BlocksFormat::from_path(&path).parse(&content).ok())
+        .unwrap_or_default()
+}
+
+// Variables about the block a prompt is for and the other blocks of the project
+pub fn block_variables(blocks: &[Block], block_id: Option<&str>) -> HashMap<String, String> {
+    let mut vars = HashMap::new();
+    let block_name = block_id
+        .and_then(|id| blocks.iter().find(|b| b.block_id == id))
+        .map(|b| b.name.clone())
+        .unwrap_or_default();
+
+    let existing_blocks: Vec<String> = blocks.iter()
+        .filter(|b| Some(b.block_id.as_str()) != block_id)
+        .map(|b| {
+            let summary: String = b.description.lines().next().unwrap_or_default().chars().take(BLOCK_SUMMARY_CHARS).collect();
+            if summary.trim().is_empty() {
+                format!("- {}", b.name)
+            } else {
+                format!("- {}: {}", b.name, summary.trim())
+            }
+        })
+        .collect();
+
+    vars.insert("block_name".to_string(), block_name);
+    vars.insert("existing_blocks".to_string(), existing_blocks.join("\n"));
+    vars
+}
+
+// Render a user prompt template with the input of the operation. Templates written before named
+// variables existed put the input at a bare {}, which still works.
+pub fn render_with_input(template: &str, vars: &HashMap<String, String>, input: &str) -> String {
+    let mut vars = vars.clone();
+    vars.insert(INPUT_VARIABLE.to_string(), input.to_string());
+    render(&template.replace("{}", &format!("{{{{{}}}}}", INPUT_VARIABLE)), &vars)
+}
+
+// Replace {{name}} placeholders with their values; unknown placeholders are left untouched
+pub fn render(template: &str, vars: &HashMap<String, String>) -> String {
+    let re = Regex::new(r"\{\{\s*([a-zA-Z0-9_]+)\s*\}\}").unwrap();
+    re.replace_all(template, |caps: &regex::Captures| {
+        match vars.get(&caps[1]) {
+            Some(value) => value.clone(),
+            None => caps[0].to_string(),
+        }
+    }).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_replaces_known_variables() {
+        let mut vars = HashMap::new();
+        vars.insert("project_name".to_string(), "forge".to_string());
+        vars.insert("primary_language".to_string(), "Rust".to_string());
+
+        let rendered = render("Project {{project_name}} written in {{ primary_language }} ({{unknown}})", &vars);
+        assert_eq!(rendered, "Project forge written in Rust ({{unknown}})");
+    }
+
+    #[test]
+    fn test_render_with_input_and_block_variables() {
+        let mut parser = Block::new("Parser".to_string(), "Parses the input.\nMore details".to_string(), Vec::new(), Vec::new());
+        parser.block_id = "P1".to_string();
+        let mut store = Block::new("Store".to_string(), String::new(), Vec::new(), Vec::new());
+        store.block_id = "S1".to_string();
+        let blocks = vec![parser, store];
+        let vars = block_variables(&blocks, Some("S1"));
+        assert_eq!(vars["block_name"], "Store");
+        assert_eq!(vars["existing_blocks"], "- Parser: Parses the input.");
+
+        // The input isn't rendered again, so braces in it are kept
+        assert_eq!(render_with_input("{{block_name}}: {{input}}", &vars, "uses {{x}}"), "Store: uses {{x}}");
+        assert_eq!(render_with_input("Legacy:\n{}", &vars, "text"), "Legacy:\ntext");
+    }
+
+    #[test]
+    fn test_render_keeps_positional_placeholder() {
+        let vars = HashMap::new();
+        assert_eq!(render("Description:\n{}", &vars), "Description:\n{}");
+    }
+}
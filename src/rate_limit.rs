@@ -0,0 +1,154 @@
+use crate::project_config::{RateLimit, RateLimitConfig};
+use crate::project_handlers::ProjectAppState;
+use crate::request_user;
+use crate::routes;
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::error::InternalError;
+use actix_web::http::{header, Method};
+use actix_web::middleware::Next;
+use actix_web::{web, Error, HttpResponse};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+// Buckets of the clients without requests for this long are dropped once there are many
+const IDLE_BUCKET_AGE: Duration = Duration::from_secs(10 * 60);
+const MAX_BUCKETS: usize = 1000;
+
+// Endpoints sharing a rate limit
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RateLimitGroup {
+    Llm,
+    Execution,
+}
+
+impl RateLimitGroup {
+    fn limit(&self, config: &RateLimitConfig) -> RateLimit {
+        match self {
+            RateLimitGroup::Llm => config.llm,
+            RateLimitGroup::Execution => config.execution,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            RateLimitGroup::Llm => "LLM",
+            RateLimitGroup::Execution => "execution",
+        }
+    }
+}
+
+// Group of an expensive endpoint, from the method and the path below the API prefix
+pub fn rate_limit_group(method: &Method, path: &str) -> Option<RateLimitGroup> {
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+    match (method.as_str(), segments.as_slice()) {
+        ("PUT", ["blocks", _, "enhance" | "generate-tasks"] | ["blocks", _, "enhance", "stream"])
+        | ("POST", ["blocks", "auto-complete" | "process-markdown" | "process-spec"] | ["blocks", "auto-complete", "stream"])
+        | ("GET", ["project", "check-config"]) => Some(RateLimitGroup::Llm),
+        ("POST", ["git", "execute-task" | "build"] | ["schedules", _, "run"]) => Some(RateLimitGroup::Execution),
+        _ => None,
+    }
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    // Take a token, or return how long until the next one
+    fn take(&mut self, limit: RateLimit, now: Instant) -> Result<(), Duration> {
+        let per_second = limit.requests_per_minute as f64 / 60.0;
+        let refilled = now.saturating_duration_since(self.updated).as_secs_f64() * per_second;
+        self.tokens = (self.tokens + refilled).min(limit.burst as f64);
+        self.updated = now;
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - self.tokens) / per_second))
+        }
+    }
+}
+
+// Token buckets per group and client
+#[derive(Default)]
+pub struct RateLimiter {
+    buckets: Mutex<HashMap<(RateLimitGroup, String), Bucket>>,
+}
+
+impl RateLimiter {
+    pub fn check(&self, group: RateLimitGroup, client: &str, limit: RateLimit, now: Instant) -> Result<(), Duration> {
+        let mut buckets = self.buckets.lock().unwrap();
+        if buckets.len() >= MAX_BUCKETS {
+            buckets.retain(|_, bucket| now.saturating_duration_since(bucket.updated) < IDLE_BUCKET_AGE);
+        }
+        buckets.entry((group, client.to_string()))
+            .or_insert(Bucket { tokens: limit.burst as f64, updated: now })
+            .take(limit, now)
+    }
+}
+
+lazy_static::lazy_static! {
+    static ref RATE_LIMITER: RateLimiter = RateLimiter::default();
+}
+
+// Middleware of the API scopes limiting the requests of each client to the expensive endpoints.
+// It runs after the authentication, so clients are told apart by API key or user, then by IP.
+pub async fn rate_limit(req: ServiceRequest, next: Next<impl MessageBody>) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let path = routes::api_path(req.path());
+    if let Some(group) = rate_limit_group(req.method(), path) {
+        let config = req.app_data::<web::Data<ProjectAppState>>()
+            .and_then(|data| data.project_manager.get_config().ok())
+            .and_then(|config| config.rate_limits)
+            .filter(|config| config.validate().is_ok())
+            .unwrap_or_default();
+        if config.enabled {
+            let limit = group.limit(&config);
+            let client = request_user::current_user()
+                .or_else(|| req.peer_addr().map(|addr| format!("ip:{}", addr.ip())))
+                .unwrap_or_default();
+            if let Err(wait) = RATE_LIMITER.check(group, &client, limit, Instant::now()) {
+                let seconds = wait.as_secs_f64().ceil().max(1.0) as u64;
+                let response = HttpResponse::TooManyRequests()
+                    .insert_header((header::RETRY_AFTER, seconds.to_string()))
+                    .body(format!("Limit of {} {} requests per minute reached, retry in {} seconds",
+                        limit.requests_per_minute, group.name(), seconds));
+                return Err(InternalError::from_response("Rate limit reached", response).into());
+            }
+        }
+    }
+    next.call(req).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rate_limit_group() {
+        assert_eq!(rate_limit_group(&Method::POST, "/blocks/process-spec"), Some(RateLimitGroup::Llm));
+        assert_eq!(rate_limit_group(&Method::PUT, "/blocks/b1/enhance/stream"), Some(RateLimitGroup::Llm));
+        assert_eq!(rate_limit_group(&Method::POST, "/git/execute-task"), Some(RateLimitGroup::Execution));
+        assert_eq!(rate_limit_group(&Method::GET, "/blocks"), None);
+    }
+
+    #[test]
+    fn test_rate_limiter() {
+        let limiter = RateLimiter::default();
+        let limit = RateLimit { requests_per_minute: 6, burst: 2 };
+        let start = Instant::now();
+        let check = |client: &str, seconds: u64| limiter.check(RateLimitGroup::Llm, client, limit, start + Duration::from_secs(seconds));
+
+        assert!(check("ip:10.0.0.1", 0).is_ok());
+        assert!(check("ip:10.0.0.1", 0).is_ok());
+        assert_eq!(check("ip:10.0.0.1", 0), Err(Duration::from_secs(10)));
+        // Other clients have their own bucket
+        assert!(check("api-key:ci", 0).is_ok());
+        // One token is back after 10 seconds
+        assert!(check("ip:10.0.0.1", 10).is_ok());
+        assert!(check("ip:10.0.0.1", 11).is_err());
+    }
+}
@@ -0,0 +1,184 @@
+use crate::block_config::BlockConfigManager;
+use crate::git_util::git;
+use crate::models::{Block, Task};
+use crate::project_config::{GitProvider, ProjectConfigManager, TASK_STATUS_COMPLETED};
+use crate::pull_request::GITHUB_TOKEN_SECRET;
+use crate::secrets;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::process::Command;
+
+// Shortest task commit ID matched against the commits of a release
+const MIN_COMMIT_PREFIX: usize = 7;
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ReleaseRequest {
+    pub tag: String,
+    // Commit to tag, defaults to the head of the branch the tasks are merged into
+    pub commit: Option<String>,
+    // Tag of the previous release, defaults to the latest tag before the commit
+    pub previous_tag: Option<String>,
+    pub github_release: bool,
+    pub draft: bool,
+    pub prerelease: bool,
+}
+
+// Release created: its tag, the changelog of the tasks it brings and the URL of its GitHub release
+#[derive(Debug, Clone, Serialize)]
+pub struct Release {
+    pub tag: String,
+    pub commit: String,
+    pub previous_tag: Option<String>,
+    pub tasks: usize,
+    pub changelog: String,
+    pub url: Option<String>,
+}
+
+// Whether one of the commits of a task, full or abbreviated, is among the commits of the release
+fn task_in_release(task: &Task, commits: &[String]) -> bool {
+    task.commit_ids.iter()
+        .chain(std::iter::once(&task.commit_id))
+        .filter(|id| id.len() >= MIN_COMMIT_PREFIX)
+        .any(|id| commits.iter().any(|commit| commit.starts_with(id.as_str())))
+}
+
+// Changelog section of a release: the completed tasks whose commits it contains, grouped by block
+pub fn changelog_section(tag: &str, date: &str, blocks: &[Block], commits: &[String]) -> (String, usize) {
+    let mut section = format!("## {} ({})\n", tag, date);
+    let mut count = 0;
+    for block in blocks {
+        let mut entries: Vec<String> = block.todo_list.values()
+            .filter(|t| t.status == TASK_STATUS_COMPLETED && task_in_release(t, commits))
+            .map(|task| {
+                let title = if task.task_name.trim().is_empty() { task.description.lines().next().unwrap_or_default() } else { task.task_name.as_str() };
+                let short: String = task.commit_id.chars().take(MIN_COMMIT_PREFIX).collect();
+                format!("- {} ({})\n", title.trim(), short)
+            })
+            .collect();
+        if entries.is_empty() {
+            continue;
+        }
+        entries.sort();
+        count += entries.len();
+        section.push_str(&format!("\n### {}\n\n{}", block.name, entries.concat()));
+    }
+    if count == 0 {
+        section.push_str("\nNo tasks were completed in this release.\n");
+    }
+    (section, count)
+}
+
+// Publish a GitHub release of a pushed tag with the GitHub CLI, which prints its URL
+fn create_github_release(project_dir: &str, request: &ReleaseRequest, notes: &str) -> Result<String, String> {
+    let token = secrets::credential(GITHUB_TOKEN_SECRET)?;
+    let mut command = Command::new("gh");
+    command
+        .args(["release", "create", &request.tag, "--verify-tag", "--title", &request.tag, "--notes", notes])
+        .env("GH_TOKEN", token)
+        .current_dir(project_dir);
+    if request.draft {
+        command.arg("--draft");
+    }
+    if request.prerelease {
+        command.arg("--prerelease");
+    }
+    let output = command.output().map_err(|e| format!("Failed to run gh: {}", e))?;
+    if !output.status.success() {
+        return Err(secrets::redact(String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).lines().last().unwrap_or_default().trim().to_string())
+}
+
+// Tag a commit with the changelog of the tasks completed since the previous tag, optionally
+// publishing a GitHub release with it
+pub fn create_release(
+    project_manager: &ProjectConfigManager,
+    block_manager: &BlockConfigManager,
+    request: &ReleaseRequest,
+) -> Result<Release, String> {
+    let config = project_manager.get_config().map_err(|e| format!("Failed to get project config: {}", e))?;
+    let project_dir = config.project_home_directory.clone();
+    if project_dir.is_empty() || !Path::new(&project_dir).exists() {
+        return Err(format!("Project home directory does not exist: {}", project_dir));
+    }
+    if request.tag.starts_with('-') || git(&project_dir, &["check-ref-format", &format!("refs/tags/{}", request.tag)]).is_err() {
+        return Err(format!("Invalid tag name: {}", request.tag));
+    }
+    if git(&project_dir, &["rev-parse", "--verify", "--quiet", &format!("refs/tags/{}", request.tag)]).is_ok() {
+        return Err(format!("Tag {} already exists", request.tag));
+    }
+    if request.github_release && config.git_provider.unwrap_or_default() != GitProvider::GitHub {
+        return Err("Releases can only be published on GitHub".to_string());
+    }
+
+    let target = request.commit.clone().unwrap_or_else(|| config.task_base_branch());
+    if target.starts_with('-') {
+        return Err(format!("Invalid commit: {}", target));
+    }
+    let commit = git(&project_dir, &["rev-parse", "--verify", "--quiet", &format!("{}^{{commit}}", target)])
+        .map_err(|_| format!("Commit {} not found", target))?;
+    let previous_tag = match &request.previous_tag {
+        Some(tag) => Some(tag.clone()),
+        None => git(&project_dir, &["describe", "--tags", "--abbrev=0", &commit]).ok(),
+    };
+    let range = match &previous_tag {
+        Some(tag) => format!("refs/tags/{}..{}", tag, commit),
+        None => commit.clone(),
+    };
+    let commits: Vec<String> = git(&project_dir, &["rev-list", &range])?
+        .lines()
+        .map(str::to_string)
+        .collect();
+
+    let blocks = block_manager.get_blocks()?;
+    let (changelog, tasks) = changelog_section(&request.tag, &Utc::now().format("%Y-%m-%d").to_string(), &blocks, &commits);
+    // Verbatim, as git strips the lines starting with # otherwise
+    git(&project_dir, &["tag", "--annotate", "--cleanup=verbatim", &request.tag, "--message", &changelog, &commit])?;
+
+    let url = if request.github_release {
+        git(&project_dir, &["push", "origin", &format!("refs/tags/{}", request.tag)])?;
+        Some(create_github_release(&project_dir, request, &changelog)?)
+    } else {
+        None
+    };
+
+    Ok(Release {
+        tag: request.tag.clone(),
+        commit,
+        previous_tag,
+        tasks,
+        changelog,
+        url,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_changelog_section() {
+        let mut block = Block::new("Auth".to_string(), "Authentication".to_string(), vec![], vec![]);
+        let mut login = Task::new("Add login".to_string());
+        login.task_name = "Add login".to_string();
+        login.status = TASK_STATUS_COMPLETED.to_string();
+        login.set_commits(vec!["abcdef1234".to_string()]);
+        let mut logout = Task::new("Add logout\nwith a confirmation".to_string());
+        logout.status = TASK_STATUS_COMPLETED.to_string();
+        logout.commit_id = "1234567".to_string();
+        let mut pending = Task::new("Add signup".to_string());
+        pending.commit_id = "abcdef1234".to_string();
+        let mut older = Task::new("Add session".to_string());
+        older.status = TASK_STATUS_COMPLETED.to_string();
+        older.commit_id = "9999999".to_string();
+        for task in [login, logout, pending, older] {
+            block.todo_list.insert(task.task_id.clone(), task);
+        }
+
+        let commits = vec!["abcdef1234".to_string(), "1234567890".to_string()];
+        let (section, count) = changelog_section("v1.0.0", "2025-01-01", &[block], &commits);
+        assert_eq!(count, 2);
+        assert_eq!(section, "## v1.0.0 (2025-01-01)\n\n### Auth\n\n- Add login (abcdef1)\n- Add logout (1234567)\n");
+    }
+}
@@ -0,0 +1,298 @@
+use crate::block_config::{write_file_atomic, FileLock};
+use crate::project_config::{ProjectConfigManager, RequestAuditConfig};
+use crate::project_handlers::ProjectAppState;
+use crate::request_user::RequestActor;
+use crate::routes;
+use crate::secrets::{self, REDACTED};
+use actix_web::body::MessageBody;
+use actix_web::dev::{Payload, ServiceRequest, ServiceResponse};
+use actix_web::http::{header, Method};
+use actix_web::middleware::Next;
+use actix_web::{web, Error, HttpResponse, Responder};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::time::Instant;
+
+// Audit log of the mutating API calls, one JSON entry per line, stored next to the project config
+pub const REQUEST_AUDIT_FILE: &str = "request_audit.jsonl";
+
+// Number of entries returned by a query without a limit
+const DEFAULT_QUERY_LIMIT: usize = 50;
+
+// Larger bodies are summarized by their type and size only
+const MAX_INSPECTED_PAYLOAD_BYTES: usize = 64 * 1024;
+
+// Fields whose values are hidden in the payload summaries
+const SENSITIVE_FIELDS: [&str; 6] = ["password", "secret", "token", "api_key", "apikey", "private_key"];
+
+// One API call: who made it, on what, with what and how it ended
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RequestAuditEntry {
+    pub id: String,
+    pub timestamp: String,
+    // API key or user of the call; None when authentication is off or failed
+    pub actor: Option<String>,
+    pub client_ip: Option<String>,
+    pub method: String,
+    pub path: String,
+    // Route pattern the call matched, e.g. /api/v1/blocks/{block_id}
+    pub route: Option<String>,
+    pub query: Option<String>,
+    pub payload: Option<String>,
+    pub status: u16,
+    pub error: Option<String>,
+    pub duration_ms: u64,
+}
+
+// Filters of the audit log; all given filters must match
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct RequestAuditQuery {
+    pub actor: Option<String>,
+    pub method: Option<String>,
+    // Part of the path
+    pub path: Option<String>,
+    // "ok" (status below 400) or "error"
+    pub status: Option<String>,
+    // RFC 3339 bounds of the timestamp
+    pub since: Option<String>,
+    pub until: Option<String>,
+    pub limit: Option<usize>,
+    pub offset: Option<usize>,
+}
+
+impl RequestAuditQuery {
+    fn matches(&self, entry: &RequestAuditEntry) -> bool {
+        let status_matches = match self.status.as_deref() {
+            Some("ok") => entry.status < 400,
+            Some("error") => entry.status >= 400,
+            _ => true,
+        };
+        self.actor.as_deref().is_none_or(|actor| entry.actor.as_deref() == Some(actor))
+            && self.method.as_deref().is_none_or(|method| entry.method.eq_ignore_ascii_case(method))
+            && self.path.as_deref().is_none_or(|path| entry.path.contains(path))
+            && self.since.as_deref().is_none_or(|since| entry.timestamp.as_str() >= since)
+            && self.until.as_deref().is_none_or(|until| entry.timestamp.as_str() <= until)
+            && status_matches
+    }
+}
+
+fn is_mutating(method: &Method) -> bool {
+    [Method::POST, Method::PUT, Method::PATCH, Method::DELETE].contains(method)
+}
+
+// Hide the values of sensitive fields, at any depth
+fn redact_fields(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                let key = key.to_lowercase();
+                if SENSITIVE_FIELDS.iter().any(|field| key.contains(field)) && !value.is_null() {
+                    *value = Value::String(REDACTED.to_string());
+                } else {
+                    redact_fields(value);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(redact_fields),
+        _ => {}
+    }
+}
+
+// Summary of a JSON body: the body with its secrets hidden, cut to `max_chars` characters
+pub fn summarize_json(body: &[u8], max_chars: usize) -> String {
+    let summary = match serde_json::from_slice::<Value>(body) {
+        Ok(mut value) => {
+            redact_fields(&mut value);
+            secrets::redact(&value.to_string())
+        }
+        Err(_) => format!("Invalid JSON ({} bytes)", body.len()),
+    };
+    let length = summary.chars().count();
+    if length > max_chars {
+        let cut: String = summary.chars().take(max_chars).collect();
+        format!("{}... [{} characters truncated]", cut, length - max_chars)
+    } else {
+        summary
+    }
+}
+
+// Summary of the body of a request; small JSON bodies are read and put back for the handler
+async fn summarize_payload(req: &mut ServiceRequest, max_chars: usize) -> Option<String> {
+    let header_value = |name: header::HeaderName| req.headers().get(name).and_then(|v| v.to_str().ok()).map(str::to_string);
+    let length = header_value(header::CONTENT_LENGTH).and_then(|v| v.parse::<usize>().ok()).unwrap_or(0);
+    if length == 0 {
+        return None;
+    }
+    // Secret values are never logged
+    if routes::api_path(req.path()).starts_with("/secrets") {
+        return Some(REDACTED.to_string());
+    }
+    let content_type = header_value(header::CONTENT_TYPE).unwrap_or_else(|| "unknown content".to_string());
+    if !content_type.starts_with("application/json") || length > MAX_INSPECTED_PAYLOAD_BYTES {
+        return Some(format!("{} ({} bytes)", content_type, length));
+    }
+    let body = req.extract::<web::Bytes>().await.ok()?;
+    let summary = summarize_json(&body, max_chars);
+    req.set_payload(Payload::from(body));
+    Some(summary)
+}
+
+// Query string without the API keys some clients pass in it
+fn redact_query(query: &str) -> Option<String> {
+    let pairs: Vec<&str> = query.split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| if pair.starts_with("api_key=") { "api_key=[REDACTED]" } else { pair })
+        .collect();
+    (!pairs.is_empty()).then(|| secrets::redact(&pairs.join("&")))
+}
+
+// Middleware of the API scopes recording every mutating call, including the rejected ones. It runs
+// before the authentication and reads the caller it found from the request extensions.
+pub async fn audit_requests(mut req: ServiceRequest, next: Next<impl MessageBody>) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let config = req.app_data::<web::Data<ProjectAppState>>()
+        .and_then(|data| data.project_manager.get_config().ok())
+        .and_then(|config| config.request_audit)
+        .unwrap_or_default();
+    if !config.enabled || !is_mutating(req.method()) {
+        return next.call(req).await;
+    }
+
+    let started = Instant::now();
+    let payload = summarize_payload(&mut req, config.max_payload_chars).await;
+    let method = req.method().to_string();
+    let path = req.path().to_string();
+    let query = redact_query(req.query_string());
+    let client_ip = req.connection_info().realip_remote_addr().map(str::to_string);
+
+    let result = next.call(req).await;
+    let (status, actor, route, error) = match &result {
+        Ok(res) => (
+            res.status().as_u16(),
+            res.request().extensions().get::<RequestActor>().map(|actor| actor.0.clone()),
+            res.request().match_pattern(),
+            None,
+        ),
+        Err(e) => (e.as_response_error().status_code().as_u16(), None, None, Some(e.to_string())),
+    };
+    let entry = RequestAuditEntry {
+        id: uuid::Uuid::new_v4().to_string(),
+        timestamp: Utc::now().to_rfc3339(),
+        actor,
+        client_ip,
+        method,
+        path,
+        route,
+        query,
+        payload,
+        status,
+        error,
+        duration_ms: started.elapsed().as_millis() as u64,
+    };
+    tokio::task::spawn_blocking(move || {
+        if let Err(e) = record(&entry, &config) {
+            eprintln!("Failed to record API call in the audit log: {}", e);
+        }
+    });
+    result
+}
+
+fn audit_file() -> String {
+    ProjectConfigManager::get_instance().project_data_file(REQUEST_AUDIT_FILE)
+}
+
+// Entries of the log, oldest first; unreadable lines are skipped
+fn load_entries(file: &str) -> Result<Vec<RequestAuditEntry>, String> {
+    if !Path::new(file).exists() {
+        return Ok(Vec::new());
+    }
+    let content = fs::read_to_string(file).map_err(|e| format!("Failed to read request audit log: {}", e))?;
+    Ok(content.lines().filter_map(|line| serde_json::from_str(line).ok()).collect())
+}
+
+// Append a call to the log, dropping the oldest half once it outgrows its maximum size
+fn record(entry: &RequestAuditEntry, config: &RequestAuditConfig) -> Result<(), String> {
+    let file = audit_file();
+    let _lock = FileLock::acquire(&file)?;
+    let line = serde_json::to_string(entry).map_err(|e| format!("Failed to serialize audit entry: {}", e))?;
+    let mut log = OpenOptions::new().create(true).append(true).open(&file)
+        .map_err(|e| format!("Failed to open request audit log: {}", e))?;
+    writeln!(log, "{}", line).map_err(|e| format!("Failed to write request audit log: {}", e))?;
+    drop(log);
+
+    let size = fs::metadata(&file).map(|m| m.len()).unwrap_or(0);
+    if size > config.max_size_mb * 1024 * 1024 {
+        let entries = load_entries(&file)?;
+        let kept: Vec<String> = entries[entries.len() / 2..].iter()
+            .filter_map(|e| serde_json::to_string(e).ok())
+            .collect();
+        write_file_atomic(&file, format!("{}\n", kept.join("\n")).as_bytes())?;
+    }
+    Ok(())
+}
+
+// Entries matching a query, most recent first
+pub fn query(query: &RequestAuditQuery) -> Result<Vec<RequestAuditEntry>, String> {
+    let entries = load_entries(&audit_file())?;
+    Ok(entries.into_iter()
+        .rev()
+        .filter(|entry| query.matches(entry))
+        .skip(query.offset.unwrap_or(0))
+        .take(query.limit.unwrap_or(DEFAULT_QUERY_LIMIT))
+        .collect())
+}
+
+// API endpoint to browse the audit log of the API calls, most recent first
+pub async fn get_request_audit_handler(query: web::Query<RequestAuditQuery>) -> impl Responder {
+    match web::block(move || self::query(&query)).await {
+        Ok(Ok(entries)) => HttpResponse::Ok().json(entries),
+        Ok(Err(e)) => HttpResponse::InternalServerError().body(e),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Failed to read request audit log: {}", e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summarize_json() {
+        let body = br#"{"name": "deploy", "oidc": {"client_secret": "abc"}, "keys": [{"api_key": "forge_123"}]}"#;
+        let summary = summarize_json(body, 1000);
+        assert!(summary.contains("\"name\":\"deploy\""));
+        assert!(summary.contains("\"client_secret\":\"[REDACTED]\""));
+        assert!(summary.contains("\"api_key\":\"[REDACTED]\""));
+        assert!(!summary.contains("forge_123"));
+
+        assert_eq!(summarize_json(br#"{"name": "deploy"}"#, 5), "{\"nam... [12 characters truncated]");
+        assert_eq!(redact_query("api_key=forge_123&force=true").as_deref(), Some("api_key=[REDACTED]&force=true"));
+        assert_eq!(redact_query(""), None);
+    }
+
+    #[test]
+    fn test_query_matches() {
+        let entry = RequestAuditEntry {
+            id: "1".to_string(),
+            timestamp: "2025-01-01T10:00:00+00:00".to_string(),
+            actor: Some("api-key:ci".to_string()),
+            client_ip: None,
+            method: "DELETE".to_string(),
+            path: "/api/v1/blocks/AB12CD".to_string(),
+            route: Some("/api/v1/blocks/{block_id}".to_string()),
+            query: None,
+            payload: None,
+            status: 403,
+            error: Some("The API key ci lacks the write scope".to_string()),
+            duration_ms: 3,
+        };
+        let query = |q: &str| web::Query::<RequestAuditQuery>::from_query(q).unwrap().into_inner();
+        assert!(query("actor=api-key:ci&method=delete&status=error").matches(&entry));
+        assert!(query("path=/blocks&since=2025-01-01T00:00:00%2B00:00").matches(&entry));
+        assert!(!query("status=ok").matches(&entry));
+        assert!(!query("until=2024-12-31").matches(&entry));
+    }
+}
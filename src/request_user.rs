@@ -0,0 +1,53 @@
+use std::cell::RefCell;
+use std::future::Future;
+
+tokio::task_local! {
+    // User an API request is handled for, recorded in the audit history
+    static REQUEST_USER: Option<String>;
+}
+
+thread_local! {
+    // User of the blocking closure running on this thread
+    static BLOCKING_USER: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+// Name of the authenticated caller of a request, kept in its extensions for the request audit log
+#[derive(Debug, Clone)]
+pub struct RequestActor(pub String);
+
+// User of the current API request; None outside of requests or when authentication is off
+pub fn current_user() -> Option<String> {
+    REQUEST_USER.try_with(|user| user.clone()).ok().flatten()
+        .or_else(|| BLOCKING_USER.with(|user| user.borrow().clone()))
+}
+
+// Handle a request for the given user
+pub async fn run_as<F: Future>(user: Option<String>, future: F) -> F::Output {
+    REQUEST_USER.scope(user, future).await
+}
+
+// Keep the user of the request in a closure run by web::block
+pub fn with_current_user<R>(f: impl FnOnce() -> R) -> impl FnOnce() -> R {
+    let user = current_user();
+    move || {
+        let previous = BLOCKING_USER.with(|current| current.replace(user));
+        let result = f();
+        BLOCKING_USER.with(|current| *current.borrow_mut() = previous);
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_current_user() {
+        assert_eq!(current_user(), None);
+        let user = run_as(Some("ada@example.com".to_string()), async {
+            tokio::task::spawn_blocking(with_current_user(current_user)).await.unwrap()
+        }).await;
+        assert_eq!(user.as_deref(), Some("ada@example.com"));
+        assert_eq!(current_user(), None);
+    }
+}
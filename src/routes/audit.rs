@@ -0,0 +1,7 @@
+use crate::request_audit::get_request_audit_handler;
+use actix_web::web;
+
+// Audit log of the mutating API calls
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.route("/audit", web::get().to(get_request_audit_handler));
+}
@@ -0,0 +1,20 @@
+use crate::api_auth::{create_api_key_handler, delete_api_key_handler, get_api_keys_handler};
+use crate::oidc::{
+    auth_status_handler, get_oidc_config_handler, logout_handler, oidc_callback_handler, oidc_login_handler,
+    update_oidc_config_handler
+};
+use actix_web::web;
+
+// Authentication: API keys and the OIDC sign-in
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg
+        .route("/auth/status", web::get().to(auth_status_handler))
+        .route("/auth/logout", web::post().to(logout_handler))
+        .route("/auth/oidc/login", web::get().to(oidc_login_handler))
+        .route("/auth/oidc/callback", web::get().to(oidc_callback_handler))
+        .route("/auth/oidc", web::get().to(get_oidc_config_handler))
+        .route("/auth/oidc", web::put().to(update_oidc_config_handler))
+        .route("/auth/keys", web::get().to(get_api_keys_handler))
+        .route("/auth/keys", web::post().to(create_api_key_handler))
+        .route("/auth/keys/{name}", web::delete().to(delete_api_key_handler));
+}
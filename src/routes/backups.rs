@@ -0,0 +1,10 @@
+use crate::backup_handlers::{create_backup_handler, get_backups_handler, restore_backup_handler};
+use actix_web::web;
+
+// Backups of the project data
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg
+        .route("/backups", web::get().to(get_backups_handler))
+        .route("/backups", web::post().to(create_backup_handler))
+        .route("/backups/{backup_id}/restore", web::post().to(restore_backup_handler));
+}
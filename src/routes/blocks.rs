@@ -0,0 +1,44 @@
+use crate::block_handlers::{
+    add_block_handler, add_task_handler, assign_block_epic_handler, auto_complete_handler, auto_complete_stream_handler,
+    delete_block_handler, enhance_block_handler, enhance_block_stream_handler, export_specification_handler,
+    export_tasks_csv_handler, generate_sample_config_handler, generate_tasks_block_handler, get_block_changes_handler,
+    get_block_dependencies_handler, get_block_sources_handler, get_blocks_handler, get_blocks_history_handler,
+    get_ownership_map_handler, import_tasks_csv_handler, process_markdown_handler, process_specification_handler,
+    redo_blocks_handler, remove_task_handler, undo_blocks_handler, update_block_handler, update_task_status_handler,
+    write_specification_handler
+};
+use crate::executor_handlers::cancel_execution_handler;
+use actix_web::web;
+
+// Blocks and their tasks
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg
+        .route("/blocks", web::get().to(get_blocks_handler))
+        .route("/blocks", web::post().to(add_block_handler))
+        .route("/blocks", web::put().to(update_block_handler))
+        .route("/blocks/{block_id}", web::delete().to(delete_block_handler))
+        .route("/blocks/{block_id}/task", web::post().to(add_task_handler))
+        .route("/blocks/{block_id}/delete/{task_id}", web::delete().to(remove_task_handler))
+        .route("/blocks/{block_id}/tasks/{task_id}/status", web::put().to(update_task_status_handler))
+        .route("/blocks/{block_id}/enhance", web::put().to(enhance_block_handler))
+        .route("/blocks/{block_id}/enhance/stream", web::put().to(enhance_block_stream_handler))
+        .route("/blocks/{block_id}/generate-tasks", web::put().to(generate_tasks_block_handler))
+        .route("/blocks/auto-complete", web::post().to(auto_complete_handler))
+        .route("/blocks/auto-complete/stream", web::post().to(auto_complete_stream_handler))
+        .route("/blocks/process-markdown", web::post().to(process_markdown_handler))
+        .route("/blocks/process-spec", web::post().to(process_specification_handler))
+        .route("/blocks/export-spec", web::get().to(export_specification_handler))
+        .route("/blocks/export-spec", web::post().to(write_specification_handler))
+        .route("/blocks/{blockId}/dependencies", web::get().to(get_block_dependencies_handler))
+        .route("/blocks/{blockId}/sources", web::get().to(get_block_sources_handler))
+        .route("/blocks/ownership", web::get().to(get_ownership_map_handler))
+        .route("/blocks/changes", web::get().to(get_block_changes_handler))
+        .route("/blocks/{blockId}/tasks/import", web::post().to(import_tasks_csv_handler))
+        .route("/tasks/export", web::get().to(export_tasks_csv_handler))
+        .route("/tasks/{execution_id}/cancel", web::post().to(cancel_execution_handler))
+        .route("/blocks/history", web::get().to(get_blocks_history_handler))
+        .route("/blocks/undo", web::post().to(undo_blocks_handler))
+        .route("/blocks/redo", web::post().to(redo_blocks_handler))
+        .route("/blocks/{block_id}/epic", web::put().to(assign_block_epic_handler))
+        .route("/generate-sample", web::post().to(generate_sample_config_handler));
+}
@@ -0,0 +1,9 @@
+use crate::ci_handlers::{ci_webhook_handler, refresh_ci_handler};
+use actix_web::web;
+
+// CI pipeline statuses of the tasks: the signed webhook CI systems report to, and GitHub Actions polling
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg
+        .route("/ci/webhook", web::post().to(ci_webhook_handler))
+        .route("/ci/refresh", web::post().to(refresh_ci_handler));
+}
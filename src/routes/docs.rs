@@ -0,0 +1,9 @@
+use crate::openapi::{openapi_handler, swagger_ui_handler};
+use actix_web::web;
+
+// API documentation
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg
+        .route("/openapi.json", web::get().to(openapi_handler))
+        .route("/docs", web::get().to(swagger_ui_handler));
+}
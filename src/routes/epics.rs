@@ -0,0 +1,14 @@
+use crate::block_handlers::{
+    add_epic_handler, delete_epic_handler, get_epic_dependencies_handler, get_epics_handler, update_epic_handler
+};
+use actix_web::web;
+
+// Epics
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg
+        .route("/epics", web::get().to(get_epics_handler))
+        .route("/epics", web::post().to(add_epic_handler))
+        .route("/epics", web::put().to(update_epic_handler))
+        .route("/epics/dependencies", web::get().to(get_epic_dependencies_handler))
+        .route("/epics/{epic_id}", web::delete().to(delete_epic_handler));
+}
@@ -0,0 +1,27 @@
+use crate::executor_handlers::{
+    bump_queued_task_handler, get_execution_timeline_handler, get_executions_handler, get_processes_handler,
+    get_queue_handler, get_session_metrics_handler, get_transcript_handler, get_transcripts_handler,
+    hold_queued_task_handler, move_queued_task_handler, pause_queue_handler, reap_processes_handler,
+    remove_queued_task_handler, reorder_queue_handler, resume_queue_handler
+};
+use actix_web::web;
+
+// Executions, the queue and the session transcripts
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg
+        .route("/executions", web::get().to(get_executions_handler))
+        .route("/executions/{run_id}/timeline", web::get().to(get_execution_timeline_handler))
+        .route("/executor/processes", web::get().to(get_processes_handler))
+        .route("/executor/processes/reap", web::post().to(reap_processes_handler))
+        .route("/executor/queue", web::get().to(get_queue_handler))
+        .route("/executor/queue", web::put().to(reorder_queue_handler))
+        .route("/executor/queue/pause", web::post().to(pause_queue_handler))
+        .route("/executor/queue/resume", web::post().to(resume_queue_handler))
+        .route("/executor/queue/{block_id}/{task_id}", web::delete().to(remove_queued_task_handler))
+        .route("/executor/queue/{block_id}/{task_id}/move", web::post().to(move_queued_task_handler))
+        .route("/executor/queue/{block_id}/{task_id}/bump", web::post().to(bump_queued_task_handler))
+        .route("/executor/queue/{block_id}/{task_id}/hold", web::post().to(hold_queued_task_handler))
+        .route("/claude/sessions/metrics", web::get().to(get_session_metrics_handler))
+        .route("/executor/transcripts/{block_id}/{task_id}", web::get().to(get_transcripts_handler))
+        .route("/executor/transcripts/{block_id}/{task_id}/{transcript_id}", web::get().to(get_transcript_handler));
+}
@@ -0,0 +1,21 @@
+use crate::git_handlers::{
+    build_handler, commit_handler, create_branch_handler, create_pull_request_handler, execute_git_task_handler,
+    get_branches_handler, get_task_diff_handler, merge_branch_handler, preflight_handler, pull_handler, push_handler
+};
+use actix_web::web;
+
+// Git operations and task execution
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg
+        .route("/git/branch", web::post().to(create_branch_handler))
+        .route("/git/commit", web::post().to(commit_handler))
+        .route("/git/merge", web::post().to(merge_branch_handler))
+        .route("/git/push", web::post().to(push_handler))
+        .route("/git/pull-request", web::post().to(create_pull_request_handler))
+        .route("/git/pull", web::post().to(pull_handler))
+        .route("/git/build", web::post().to(build_handler))
+        .route("/git/execute-task", web::post().to(execute_git_task_handler))
+        .route("/git/preflight", web::post().to(preflight_handler))
+        .route("/git/task-diff", web::post().to(get_task_diff_handler))
+        .route("/git/branches", web::get().to(get_branches_handler));
+}
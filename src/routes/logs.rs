@@ -0,0 +1,12 @@
+use crate::log_stream::{block_logs_handler, get_task_ids, search_logs_handler, stream_logs, tail_logs};
+use actix_web::web;
+
+// Task logs
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg
+        .route("/logs/stream/{task_id}", web::get().to(stream_logs))
+        .route("/logs/tasks", web::get().to(get_task_ids))
+        .route("/logs/tail", web::get().to(tail_logs))
+        .route("/logs/search", web::get().to(search_logs_handler))
+        .route("/logs/blocks/{block_id}", web::get().to(block_logs_handler));
+}
@@ -0,0 +1,14 @@
+use crate::mcp_session_handlers::{
+    evict_idle_mcp_sessions_handler, evict_mcp_session_handler, get_mcp_sessions_handler,
+    update_mcp_session_config_handler
+};
+use actix_web::web;
+
+// Sessions of the MCP server
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg
+        .route("/mcp/sessions", web::get().to(get_mcp_sessions_handler))
+        .route("/mcp/sessions/config", web::put().to(update_mcp_session_config_handler))
+        .route("/mcp/sessions/evict", web::post().to(evict_idle_mcp_sessions_handler))
+        .route("/mcp/sessions/{session_id}", web::delete().to(evict_mcp_session_handler));
+}
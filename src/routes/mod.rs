@@ -0,0 +1,70 @@
+// Routes of the HTTP API, one module per resource. Every version of the API is a scope built from
+// these modules: a new version reuses the modules of the resources that didn't change and gets
+// its own module for the others, so the shipped frontend keeps working on the old paths.
+use actix_web::web;
+
+pub mod audit;
+pub mod auth;
+pub mod backups;
+pub mod blocks;
+pub mod ci;
+pub mod docs;
+pub mod epics;
+pub mod executor;
+pub mod git;
+pub mod logs;
+pub mod mcp;
+pub mod project;
+pub mod projects;
+pub mod prompts;
+pub mod schedules;
+pub mod secrets;
+pub mod webhooks;
+
+// Current version of the API
+pub const API_V1_PREFIX: &str = "/api/v1";
+
+// Unversioned prefix, an alias of v1 kept for the frontend and existing clients
+pub const API_PREFIX: &str = "/api";
+
+// Routes of version 1 of the API
+pub fn v1(cfg: &mut web::ServiceConfig) {
+    cfg.configure(docs::configure)
+        .configure(auth::configure)
+        .configure(audit::configure)
+        .configure(blocks::configure)
+        .configure(epics::configure)
+        .configure(project::configure)
+        .configure(projects::configure)
+        .configure(secrets::configure)
+        .configure(prompts::configure)
+        .configure(git::configure)
+        .configure(backups::configure)
+        .configure(schedules::configure)
+        .configure(mcp::configure)
+        .configure(executor::configure)
+        .configure(logs::configure)
+        .configure(webhooks::configure)
+        .configure(ci::configure);
+}
+
+// Path of a request below the API prefix of its version, e.g. /blocks for /api/v1/blocks
+pub fn api_path(path: &str) -> &str {
+    path.strip_prefix(API_V1_PREFIX)
+        .filter(|rest| rest.is_empty() || rest.starts_with('/'))
+        .or_else(|| path.strip_prefix(API_PREFIX))
+        .unwrap_or(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_api_path() {
+        assert_eq!(api_path("/api/v1/blocks"), "/blocks");
+        assert_eq!(api_path("/api/blocks"), "/blocks");
+        assert_eq!(api_path("/api/v1"), "");
+        assert_eq!(api_path("/api/v10/blocks"), "/v10/blocks");
+    }
+}
@@ -0,0 +1,18 @@
+use crate::project_handlers::{
+    check_project_config_handler, get_llm_audit_entry_handler, get_llm_audit_handler, get_project_config_handler,
+    get_project_summary_handler, get_usage_handler, test_git_connection_handler, update_project_config_handler
+};
+use actix_web::web;
+
+// Settings, usage and audit of the active project
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg
+        .route("/project", web::get().to(get_project_config_handler))
+        .route("/project", web::put().to(update_project_config_handler))
+        .route("/project/test-git-connection", web::post().to(test_git_connection_handler))
+        .route("/project/check-config", web::get().to(check_project_config_handler))
+        .route("/project/summary", web::get().to(get_project_summary_handler))
+        .route("/usage", web::get().to(get_usage_handler))
+        .route("/llm-audit", web::get().to(get_llm_audit_handler))
+        .route("/llm-audit/{entry_id}", web::get().to(get_llm_audit_entry_handler));
+}
@@ -0,0 +1,16 @@
+use crate::project_handlers::{
+    activate_project_handler, get_projects_handler, init_project_handler, open_project_handler,
+    register_project_handler, remove_project_handler
+};
+use actix_web::web;
+
+// Registered projects
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg
+        .route("/project/open", web::post().to(open_project_handler))
+        .route("/project/init", web::post().to(init_project_handler))
+        .route("/projects", web::get().to(get_projects_handler))
+        .route("/projects", web::post().to(register_project_handler))
+        .route("/projects/{project_id}/activate", web::post().to(activate_project_handler))
+        .route("/projects/{project_id}", web::delete().to(remove_project_handler));
+}
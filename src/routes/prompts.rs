@@ -0,0 +1,29 @@
+use crate::project_handlers::{
+    create_profession_handler, delete_profession_handler, edit_profession_prompt_handler, export_prompt_pack_handler,
+    get_profession_prompts_handler, get_professions_handler, get_project_prompts_handler, get_prompt_diff_handler,
+    get_prompt_history_handler, import_prompt_pack_handler, preview_profession_prompt_handler,
+    reset_profession_prompt_handler, reset_project_prompt_handler, rollback_prompt_handler, set_project_prompt_handler,
+    update_profession_handler
+};
+use actix_web::web;
+
+// Professions, prompts and their history
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg
+        .route("/project/professions", web::get().to(get_professions_handler))
+        .route("/project/professions", web::post().to(create_profession_handler))
+        .route("/project/professions/{profession_id}", web::put().to(update_profession_handler))
+        .route("/project/professions/{profession_id}", web::delete().to(delete_profession_handler))
+        .route("/project/professions/{profession_id}/prompts", web::get().to(get_profession_prompts_handler))
+        .route("/project/professions/{profession_id}/prompts/{prompt_key}", web::put().to(edit_profession_prompt_handler))
+        .route("/project/professions/{profession_id}/prompts/{prompt_key}", web::delete().to(reset_profession_prompt_handler))
+        .route("/project/professions/{profession_id}/preview", web::post().to(preview_profession_prompt_handler))
+        .route("/project/prompt-packs/export", web::get().to(export_prompt_pack_handler))
+        .route("/project/prompt-packs/import", web::post().to(import_prompt_pack_handler))
+        .route("/project/prompts", web::get().to(get_project_prompts_handler))
+        .route("/project/prompts/{prompt_key}", web::put().to(set_project_prompt_handler))
+        .route("/project/prompts/{prompt_key}", web::delete().to(reset_project_prompt_handler))
+        .route("/project/prompt-history", web::get().to(get_prompt_history_handler))
+        .route("/project/prompt-history/diff", web::get().to(get_prompt_diff_handler))
+        .route("/project/prompt-history/rollback", web::post().to(rollback_prompt_handler));
+}
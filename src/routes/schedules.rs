@@ -0,0 +1,15 @@
+use crate::schedule_handlers::{
+    create_schedule_handler, delete_schedule_handler, get_schedules_handler, run_schedule_handler,
+    update_schedule_handler
+};
+use actix_web::web;
+
+// Scheduled task executions
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg
+        .route("/schedules", web::get().to(get_schedules_handler))
+        .route("/schedules", web::post().to(create_schedule_handler))
+        .route("/schedules/{schedule_id}", web::put().to(update_schedule_handler))
+        .route("/schedules/{schedule_id}", web::delete().to(delete_schedule_handler))
+        .route("/schedules/{schedule_id}/run", web::post().to(run_schedule_handler));
+}
@@ -0,0 +1,10 @@
+use crate::project_handlers::{delete_secret_handler, get_secrets_handler, set_secret_handler};
+use actix_web::web;
+
+// Stored secrets
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg
+        .route("/secrets", web::get().to(get_secrets_handler))
+        .route("/secrets/{name}", web::put().to(set_secret_handler))
+        .route("/secrets/{name}", web::delete().to(delete_secret_handler));
+}
@@ -0,0 +1,11 @@
+use crate::webhook_handlers::{get_webhook_deliveries_handler, get_webhooks_handler, test_webhook_handler, update_webhooks_handler};
+use actix_web::web;
+
+// Webhooks notified of task and block events, and their delivery log
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg
+        .route("/webhooks", web::get().to(get_webhooks_handler))
+        .route("/webhooks", web::put().to(update_webhooks_handler))
+        .route("/webhooks/deliveries", web::get().to(get_webhook_deliveries_handler))
+        .route("/webhooks/{id}/test", web::post().to(test_webhook_handler));
+}
@@ -0,0 +1,58 @@
+use crate::block_handlers::AppState;
+use crate::project_config::TaskSchedule;
+use crate::scheduler::{create_schedule, delete_schedule, list_schedules, run_schedule, update_schedule};
+use actix_web::{web, HttpResponse, Responder};
+
+// API endpoint to list the schedules with their next run times
+pub async fn get_schedules_handler(data: web::Data<AppState>) -> impl Responder {
+    match list_schedules(&data.project_manager) {
+        Ok(schedules) => HttpResponse::Ok().json(schedules),
+        Err(e) => HttpResponse::InternalServerError().body(e),
+    }
+}
+
+// API endpoint to add a schedule
+pub async fn create_schedule_handler(data: web::Data<AppState>, schedule: web::Json<TaskSchedule>) -> impl Responder {
+    match create_schedule(&data.project_manager, &data.block_manager, schedule.into_inner()) {
+        Ok(schedule) => HttpResponse::Created().json(schedule),
+        Err(e) => HttpResponse::BadRequest().body(e),
+    }
+}
+
+// API endpoint to replace a schedule
+pub async fn update_schedule_handler(path: web::Path<String>, data: web::Data<AppState>, schedule: web::Json<TaskSchedule>) -> impl Responder {
+    let schedule_id = path.into_inner();
+    match update_schedule(&data.project_manager, &data.block_manager, &schedule_id, schedule.into_inner()) {
+        Ok(schedule) => HttpResponse::Ok().json(schedule),
+        Err(e) if e.contains("not found") => HttpResponse::NotFound().body(e),
+        Err(e) => HttpResponse::BadRequest().body(e),
+    }
+}
+
+// API endpoint to remove a schedule
+pub async fn delete_schedule_handler(path: web::Path<String>, data: web::Data<AppState>) -> impl Responder {
+    let schedule_id = path.into_inner();
+    match delete_schedule(&data.project_manager, &schedule_id) {
+        Ok(_) => HttpResponse::Ok().body(format!("Schedule {} deleted", schedule_id)),
+        Err(e) => HttpResponse::NotFound().body(e),
+    }
+}
+
+// API endpoint to queue the tasks of a schedule immediately
+pub async fn run_schedule_handler(path: web::Path<String>, data: web::Data<AppState>) -> impl Responder {
+    let schedule_id = path.into_inner();
+    let schedule = data.project_manager.get_config()
+        .ok()
+        .and_then(|c| c.schedules)
+        .unwrap_or_default()
+        .into_iter()
+        .find(|s| s.schedule_id == schedule_id);
+
+    match schedule {
+        Some(schedule) => match run_schedule(&data.block_manager, &schedule) {
+            Ok(queued) => HttpResponse::Ok().json(serde_json::json!({ "queued_task_ids": queued })),
+            Err(e) => HttpResponse::InternalServerError().body(e),
+        },
+        None => HttpResponse::NotFound().body(format!("Schedule with ID {} not found", schedule_id)),
+    }
+}
@@ -0,0 +1,380 @@
+use crate::block_config::BlockConfigManager;
+use crate::log_stream;
+use crate::models::Block;
+use crate::project_config::{ProjectConfigManager, TaskSchedule};
+use crate::task_executor::get_task_executor;
+use chrono::{DateTime, Datelike, Duration as ChronoDuration, DurationRound, Timelike, Utc};
+use lazy_static::lazy_static;
+use rand::{distributions::Alphanumeric, Rng};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use tracing::{error, info};
+
+// Log stream the scheduled runs are published on
+pub const SCHEDULER_LOG_ID: &str = "scheduler";
+
+// How often the scheduler checks for due schedules
+const SCHEDULER_TICK: Duration = Duration::from_secs(20);
+
+// How far ahead the next run of a schedule is searched
+const MAX_LOOKAHEAD_DAYS: i64 = 366 * 5;
+
+// Parsed five-field cron expression (minute hour day-of-month month day-of-week), evaluated in UTC
+#[derive(Debug, Clone, PartialEq)]
+pub struct CronSchedule {
+    minutes: Vec<bool>,
+    hours: Vec<bool>,
+    days_of_month: Vec<bool>,
+    months: Vec<bool>,
+    days_of_week: Vec<bool>,
+    // Whether the day fields were restricted; when both are, a day matching either one runs
+    day_of_month_restricted: bool,
+    day_of_week_restricted: bool,
+}
+
+impl CronSchedule {
+    // Parse a cron expression such as "30 2 * * 1-5", or one of @hourly, @daily, @weekly, @monthly
+    pub fn parse(expression: &str) -> Result<Self, String> {
+        let expression = match expression.trim() {
+            "@hourly" => "0 * * * *",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            "@monthly" => "0 0 1 * *",
+            "@yearly" | "@annually" => "0 0 1 1 *",
+            other => other,
+        };
+
+        let fields: Vec<&str> = expression.split_whitespace().collect();
+        if fields.len() != 5 {
+            return Err(format!("Cron expression '{}' must have 5 fields (minute hour day month weekday)", expression));
+        }
+
+        let mut days_of_week = parse_field(fields[4], 0, 7, "day of week")?;
+        // Both 0 and 7 are Sunday
+        if days_of_week[7] {
+            days_of_week[0] = true;
+        }
+        days_of_week.truncate(7);
+
+        Ok(CronSchedule {
+            minutes: parse_field(fields[0], 0, 59, "minute")?,
+            hours: parse_field(fields[1], 0, 23, "hour")?,
+            days_of_month: parse_field(fields[2], 1, 31, "day of month")?,
+            months: parse_field(fields[3], 1, 12, "month")?,
+            days_of_week,
+            day_of_month_restricted: fields[2] != "*",
+            day_of_week_restricted: fields[4] != "*",
+        })
+    }
+
+    fn matches_day(&self, time: &DateTime<Utc>) -> bool {
+        if !self.months[time.month() as usize] {
+            return false;
+        }
+        let day_of_month = self.days_of_month[time.day() as usize];
+        let day_of_week = self.days_of_week[time.weekday().num_days_from_sunday() as usize];
+        match (self.day_of_month_restricted, self.day_of_week_restricted) {
+            (true, true) => day_of_month || day_of_week,
+            _ => day_of_month && day_of_week,
+        }
+    }
+
+    // First run time strictly after `time`
+    pub fn next_after(&self, time: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let mut next = time.duration_trunc(ChronoDuration::minutes(1)).ok()? + ChronoDuration::minutes(1);
+        let limit = time + ChronoDuration::days(MAX_LOOKAHEAD_DAYS);
+
+        while next <= limit {
+            if !self.matches_day(&next) {
+                next = next.duration_trunc(ChronoDuration::days(1)).ok()? + ChronoDuration::days(1);
+            } else if !self.hours[next.hour() as usize] {
+                next = next.duration_trunc(ChronoDuration::hours(1)).ok()? + ChronoDuration::hours(1);
+            } else if !self.minutes[next.minute() as usize] {
+                next = next + ChronoDuration::minutes(1);
+            } else {
+                return Some(next);
+            }
+        }
+        None
+    }
+}
+
+// Parse one cron field ("*", "5", "1-5", "*/15", "0-30/10", "1,15") into a table indexed by value
+fn parse_field(field: &str, min: u32, max: u32, name: &str) -> Result<Vec<bool>, String> {
+    let mut values = vec![false; max as usize + 1];
+    let invalid = || format!("Invalid {} field '{}' in cron expression", name, field);
+
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, step.parse::<u32>().map_err(|_| invalid())?),
+            None => (part, 1),
+        };
+        if step == 0 {
+            return Err(invalid());
+        }
+
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((start, end)) = range.split_once('-') {
+            (start.parse::<u32>().map_err(|_| invalid())?, end.parse::<u32>().map_err(|_| invalid())?)
+        } else {
+            let value = range.parse::<u32>().map_err(|_| invalid())?;
+            // "5/10" means from 5 to the end of the range
+            (value, if part.contains('/') { max } else { value })
+        };
+        if start < min || end > max || start > end {
+            return Err(invalid());
+        }
+
+        for value in (start..=end).step_by(step as usize) {
+            values[value as usize] = true;
+        }
+    }
+    Ok(values)
+}
+
+// A schedule with its run times, as returned by the API
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduleStatus {
+    #[serde(flatten)]
+    pub schedule: TaskSchedule,
+    pub next_run: Option<String>,
+    pub last_run: Option<String>,
+    // Error of the cron expression, if it doesn't parse
+    pub error: Option<String>,
+}
+
+lazy_static! {
+    // Time of the last run of each schedule since the server started, keyed by schedule ID
+    static ref LAST_RUNS: Mutex<HashMap<String, DateTime<Utc>>> = Mutex::new(HashMap::new());
+}
+
+fn last_run(schedule_id: &str) -> Option<DateTime<Utc>> {
+    LAST_RUNS.lock().ok().and_then(|runs| runs.get(schedule_id).cloned())
+}
+
+// Configured schedules with their next and last run times
+pub fn list_schedules(project_manager: &ProjectConfigManager) -> Result<Vec<ScheduleStatus>, String> {
+    let config = project_manager.get_config().map_err(|e| format!("Failed to get project config: {}", e))?;
+    let now = Utc::now();
+
+    Ok(config.schedules.unwrap_or_default()
+        .into_iter()
+        .map(|schedule| {
+            let (next_run, error) = match CronSchedule::parse(&schedule.cron) {
+                Ok(cron) if schedule.enabled => (cron.next_after(now).map(|t| t.to_rfc3339()), None),
+                Ok(_) => (None, None),
+                Err(e) => (None, Some(e)),
+            };
+            let last_run = last_run(&schedule.schedule_id).map(|t| t.to_rfc3339());
+            ScheduleStatus { schedule, next_run, last_run, error }
+        })
+        .collect())
+}
+
+// Check a schedule before it is stored
+fn validate_schedule(schedule: &TaskSchedule, block_manager: &BlockConfigManager) -> Result<(), String> {
+    CronSchedule::parse(&schedule.cron)?;
+
+    let blocks = block_manager.get_blocks()?;
+    let block = blocks.iter()
+        .find(|b| b.block_id == schedule.block_id)
+        .ok_or_else(|| format!("Block with ID {} not found", schedule.block_id))?;
+    if let Some(task_id) = schedule.task_ids.iter().find(|id| !block.todo_list.contains_key(*id)) {
+        return Err(format!("Task {} not found in block {}", task_id, schedule.block_id));
+    }
+    Ok(())
+}
+
+// Store the schedules in the project config
+fn save_schedules(project_manager: &ProjectConfigManager, schedules: Vec<TaskSchedule>) -> Result<(), String> {
+    let mut config = project_manager.get_config().map_err(|e| format!("Failed to get project config: {}", e))?;
+    config.schedules = Some(schedules);
+    project_manager.save_config(&config).map_err(|e| format!("Failed to save project config: {}", e))
+}
+
+// Add a schedule, generating its ID
+pub fn create_schedule(project_manager: &ProjectConfigManager, block_manager: &BlockConfigManager, mut schedule: TaskSchedule) -> Result<TaskSchedule, String> {
+    validate_schedule(&schedule, block_manager)?;
+    schedule.schedule_id = rand::thread_rng().sample_iter(&Alphanumeric).take(6).map(char::from).collect();
+
+    let mut schedules = project_manager.get_config().ok().and_then(|c| c.schedules).unwrap_or_default();
+    schedules.push(schedule.clone());
+    save_schedules(project_manager, schedules)?;
+    Ok(schedule)
+}
+
+// Replace a schedule, keeping its ID
+pub fn update_schedule(project_manager: &ProjectConfigManager, block_manager: &BlockConfigManager, schedule_id: &str, mut schedule: TaskSchedule) -> Result<TaskSchedule, String> {
+    validate_schedule(&schedule, block_manager)?;
+    schedule.schedule_id = schedule_id.to_string();
+
+    let mut schedules = project_manager.get_config().ok().and_then(|c| c.schedules).unwrap_or_default();
+    let existing = schedules.iter_mut()
+        .find(|s| s.schedule_id == schedule_id)
+        .ok_or_else(|| format!("Schedule with ID {} not found", schedule_id))?;
+    *existing = schedule.clone();
+    save_schedules(project_manager, schedules)?;
+    Ok(schedule)
+}
+
+pub fn delete_schedule(project_manager: &ProjectConfigManager, schedule_id: &str) -> Result<(), String> {
+    let mut schedules = project_manager.get_config().ok().and_then(|c| c.schedules).unwrap_or_default();
+    let count = schedules.len();
+    schedules.retain(|s| s.schedule_id != schedule_id);
+    if schedules.len() == count {
+        return Err(format!("Schedule with ID {} not found", schedule_id));
+    }
+    save_schedules(project_manager, schedules)
+}
+
+// Order the tasks so that dependencies within the set run first; cycles keep the remaining tasks in ID order
+fn execution_order(block: &Block, task_ids: &[String]) -> Vec<String> {
+    let mut pending: Vec<String> = task_ids.to_vec();
+    pending.sort();
+    pending.dedup();
+    let selected: HashSet<String> = pending.iter().cloned().collect();
+
+    let mut order: Vec<String> = Vec::new();
+    while !pending.is_empty() {
+        let ready = pending.iter().position(|id| {
+            block.todo_list.get(id)
+                .map(|task| task.dependencies.iter().all(|dep| !selected.contains(dep) || order.contains(dep)))
+                .unwrap_or(true)
+        }).unwrap_or(0);
+        order.push(pending.remove(ready));
+    }
+    order
+}
+
+// Queue the tasks of a schedule (all tasks of the block when none are listed). Tasks run again even if completed.
+pub fn run_schedule(block_manager: &BlockConfigManager, schedule: &TaskSchedule) -> Result<Vec<String>, String> {
+    let blocks = block_manager.get_blocks()?;
+    let block = blocks.iter()
+        .find(|b| b.block_id == schedule.block_id)
+        .ok_or_else(|| format!("Block with ID {} not found", schedule.block_id))?;
+
+    let task_ids = if schedule.task_ids.is_empty() {
+        block.todo_list.keys().cloned().collect()
+    } else {
+        schedule.task_ids.clone()
+    };
+
+    let executor = get_task_executor()?;
+    let mut queued = Vec::new();
+    for task_id in execution_order(block, &task_ids) {
+        let task = match block.todo_list.get(&task_id) {
+            Some(task) => task,
+            None => {
+                error!("Scheduled task {} not found in block {}", task_id, block.block_id);
+                continue;
+            }
+        };
+        executor.enqueue_task(&block.block_id, &task_id, &task.description, false, true)?;
+        queued.push(task_id);
+    }
+
+    if let Ok(mut runs) = LAST_RUNS.lock() {
+        runs.insert(schedule.schedule_id.clone(), Utc::now());
+    }
+    log_stream::add_log(SCHEDULER_LOG_ID, format!("SCHEDULE_RUN {} {} queued {} task(s)", schedule.schedule_id, schedule.name, queued.len()));
+    Ok(queued)
+}
+
+// Start the background thread queuing the tasks of due schedules
+pub fn start_task_scheduler(project_manager: Arc<ProjectConfigManager>, block_manager: Arc<BlockConfigManager>) {
+    thread::spawn(move || {
+        // Next run of each schedule, keyed by schedule ID and cron expression so edits reschedule
+        let mut next_runs: HashMap<(String, String), DateTime<Utc>> = HashMap::new();
+        loop {
+            thread::sleep(SCHEDULER_TICK);
+
+            // Re-read the schedules so configuration changes apply without a restart
+            let schedules = project_manager.get_config()
+                .ok()
+                .and_then(|c| c.schedules)
+                .unwrap_or_default();
+            let now = Utc::now();
+
+            let mut active = HashSet::new();
+            for schedule in schedules.iter().filter(|s| s.enabled) {
+                let cron = match CronSchedule::parse(&schedule.cron) {
+                    Ok(cron) => cron,
+                    Err(_) => continue,
+                };
+                let key = (schedule.schedule_id.clone(), schedule.cron.clone());
+                active.insert(key.clone());
+
+                // Schedules seen for the first time start from now; missed runs are not caught up
+                let next_run = match next_runs.get(&key) {
+                    Some(next_run) => *next_run,
+                    None => match cron.next_after(now) {
+                        Some(next_run) => {
+                            next_runs.insert(key, next_run);
+                            continue;
+                        },
+                        None => continue,
+                    },
+                };
+                if next_run > now {
+                    continue;
+                }
+
+                info!("Running schedule {} ({})", schedule.name, schedule.schedule_id);
+                if let Err(e) = run_schedule(&block_manager, schedule) {
+                    error!("Scheduled run of {} failed: {}", schedule.schedule_id, e);
+                }
+                match cron.next_after(now) {
+                    Some(next_run) => { next_runs.insert(key, next_run); },
+                    None => { next_runs.remove(&key); },
+                }
+            }
+            next_runs.retain(|key, _| active.contains(key));
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Task;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_cron_next_after() {
+        let start = Utc.with_ymd_and_hms(2024, 3, 15, 10, 17, 42).unwrap();
+
+        let nightly = CronSchedule::parse("30 2 * * *").unwrap();
+        assert_eq!(nightly.next_after(start), Some(Utc.with_ymd_and_hms(2024, 3, 16, 2, 30, 0).unwrap()));
+
+        let quarter_hours = CronSchedule::parse("*/15 * * * *").unwrap();
+        assert_eq!(quarter_hours.next_after(start), Some(Utc.with_ymd_and_hms(2024, 3, 15, 10, 30, 0).unwrap()));
+
+        // 2024-03-15 is a Friday, the next weekday run is on Monday
+        let weekdays = CronSchedule::parse("0 9 * * 1-5").unwrap();
+        assert_eq!(weekdays.next_after(Utc.with_ymd_and_hms(2024, 3, 15, 9, 0, 0).unwrap()),
+            Some(Utc.with_ymd_and_hms(2024, 3, 18, 9, 0, 0).unwrap()));
+
+        assert_eq!(CronSchedule::parse("@monthly").unwrap().next_after(start), Some(Utc.with_ymd_and_hms(2024, 4, 1, 0, 0, 0).unwrap()));
+        assert!(CronSchedule::parse("0 25 * * *").is_err());
+        assert!(CronSchedule::parse("* * *").is_err());
+        assert!(CronSchedule::parse("*/0 * * * *").is_err());
+    }
+
+    #[test]
+    fn test_execution_order_runs_dependencies_first() {
+        let mut block = Block::new("Auth".to_string(), "Authentication".to_string(), Vec::new(), Vec::new());
+        let mut login = Task::new("Add login".to_string());
+        login.task_id = "a".to_string();
+        login.dependencies = vec!["b".to_string()];
+        let mut schema = Task::new("Add user schema".to_string());
+        schema.task_id = "b".to_string();
+        block.todo_list.insert("a".to_string(), login);
+        block.todo_list.insert("b".to_string(), schema);
+
+        assert_eq!(execution_order(&block, &["a".to_string(), "b".to_string()]), vec!["b", "a"]);
+    }
+}
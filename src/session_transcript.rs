@@ -0,0 +1,643 @@
+use crate::block_config::{write_file_atomic, BlockConfigManager};
+use crate::log_stream::LogSource;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Directory of the session transcripts, stored next to the blocks config
+pub const TRANSCRIPTS_DIRECTORY: &str = "transcripts";
+
+// Transcripts kept per task; the oldest runs are dropped
+const MAX_TRANSCRIPTS_PER_TASK: usize = 10;
+
+// Characters of a tool input or result shown in the task log
+const LOG_PREVIEW_CHARS: usize = 300;
+
+// Limits of the context of a failed session replayed into the next attempt
+const REPLAY_MESSAGES: usize = 3;
+const REPLAY_TOOL_ERRORS: usize = 5;
+const REPLAY_FILES_READ: usize = 20;
+const REPLAY_EXCERPT_CHARS: usize = 1500;
+
+// Tools that change the files of the working tree
+const EDIT_TOOLS: &[&str] = &["Edit", "MultiEdit", "Write", "NotebookEdit"];
+
+// A step of a Claude session
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TranscriptEvent {
+    // Prompt the session was started with
+    Prompt { text: String },
+    Text { text: String },
+    ToolUse { id: String, name: String, input: Value },
+    ToolResult { tool_use_id: String, content: String, is_error: bool },
+    // End of the session as reported by the CLI; input tokens include the cached ones
+    Result {
+        is_error: bool,
+        num_turns: Option<u64>,
+        duration_ms: Option<u64>,
+        cost_usd: Option<f64>,
+        input_tokens: Option<u64>,
+        output_tokens: Option<u64>,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TranscriptEntry {
+    pub timestamp: String,
+    #[serde(flatten)]
+    pub event: TranscriptEvent,
+}
+
+// The messages and tool calls of a Claude session run for a task
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionTranscript {
+    pub block_id: String,
+    pub task_id: String,
+    pub session_id: Option<String>,
+    pub model: Option<String>,
+    pub started_at: String,
+    pub ended_at: Option<String>,
+    pub entries: Vec<TranscriptEntry>,
+}
+
+// Listing entry of a transcript
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TranscriptSummary {
+    pub transcript_id: String,
+    pub session_id: Option<String>,
+    pub started_at: String,
+    pub ended_at: Option<String>,
+    pub num_entries: usize,
+}
+
+// Figures of a session, for the session metrics
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionMetrics {
+    pub block_id: String,
+    pub task_id: String,
+    pub session_id: Option<String>,
+    pub started_at: String,
+    pub duration_seconds: Option<i64>,
+    pub num_turns: Option<u64>,
+    pub tool_calls: usize,
+    pub tool_errors: usize,
+    pub tool_calls_by_name: BTreeMap<String, usize>,
+    // Most tool calls requested in a single message
+    pub max_parallel_tool_calls: usize,
+    pub input_tokens: Option<u64>,
+    pub output_tokens: Option<u64>,
+    pub cost_usd: Option<f64>,
+    // The CLI reported success; sessions without a result were killed or crashed
+    pub succeeded: bool,
+}
+
+// Totals over the sessions of a metrics report
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SessionMetricsTotals {
+    pub sessions: usize,
+    // Distinct tasks with a successful session
+    pub tasks_completed: usize,
+    pub failures: usize,
+    pub tool_calls: usize,
+    pub tool_errors: usize,
+    pub max_parallel_tool_calls: usize,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cost_usd: f64,
+    pub average_duration_seconds: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionMetricsReport {
+    pub totals: SessionMetricsTotals,
+    // Most recent first
+    pub sessions: Vec<SessionMetrics>,
+}
+
+fn truncate(text: &str, max_chars: usize) -> String {
+    let text = text.trim();
+    if text.chars().count() <= max_chars {
+        return text.to_string();
+    }
+    format!("{}...", text.chars().take(max_chars).collect::<String>())
+}
+
+fn preview(text: &str) -> String {
+    truncate(text, LOG_PREVIEW_CHARS)
+}
+
+// The end of a text, where the error of a failed execution usually is
+fn tail(text: &str, max_chars: usize) -> String {
+    let text = text.trim();
+    let count = text.chars().count();
+    if count <= max_chars {
+        return text.to_string();
+    }
+    format!("...{}", text.chars().skip(count - max_chars).collect::<String>())
+}
+
+// Content of a tool result: a string or a list of text blocks
+fn tool_result_text(content: &Value) -> String {
+    match content {
+        Value::String(text) => text.clone(),
+        Value::Array(blocks) => blocks.iter()
+            .filter_map(|b| b.get("text").and_then(|t| t.as_str()))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
+impl SessionTranscript {
+    pub fn new(block_id: &str, task_id: &str, model: Option<String>, prompt: &str) -> Self {
+        let started_at = Utc::now().to_rfc3339();
+        Self {
+            block_id: block_id.to_string(),
+            task_id: task_id.to_string(),
+            session_id: None,
+            model,
+            started_at: started_at.clone(),
+            ended_at: None,
+            entries: vec![TranscriptEntry { timestamp: started_at, event: TranscriptEvent::Prompt { text: prompt.to_string() } }],
+        }
+    }
+
+    fn push(&mut self, event: TranscriptEvent) {
+        self.entries.push(TranscriptEntry { timestamp: Utc::now().to_rfc3339(), event });
+    }
+
+    // Record a line of the stream-json output of the Claude CLI, returning the lines to show in
+    // the task log. Lines that aren't JSON messages are kept as text.
+    pub fn record_stream_line(&mut self, line: &str) -> Vec<(LogSource, String)> {
+        let Ok(message) = serde_json::from_str::<Value>(line) else {
+            self.push(TranscriptEvent::Text { text: line.to_string() });
+            return vec![(LogSource::Agent, line.to_string())];
+        };
+        let str_field = |value: &Value, name: &str| value.get(name).and_then(|v| v.as_str()).unwrap_or_default().to_string();
+        let mut log = Vec::new();
+
+        match message.get("type").and_then(|t| t.as_str()) {
+            Some("system") => {
+                if let Some(session_id) = message.get("session_id").and_then(|s| s.as_str()) {
+                    self.session_id = Some(session_id.to_string());
+                    log.push((LogSource::System, format!("Claude session {} started", session_id)));
+                }
+                if let Some(model) = message.get("model").and_then(|m| m.as_str()) {
+                    self.model = Some(model.to_string());
+                }
+            }
+            Some(role @ ("assistant" | "user")) => {
+                let content = message.pointer("/message/content").and_then(|c| c.as_array()).cloned().unwrap_or_default();
+                for block in content {
+                    match block.get("type").and_then(|t| t.as_str()) {
+                        Some("text") if role == "assistant" => {
+                            let text = str_field(&block, "text");
+                            log.extend(text.lines().map(|l| (LogSource::Agent, l.to_string())));
+                            self.push(TranscriptEvent::Text { text });
+                        }
+                        Some("tool_use") => {
+                            let name = str_field(&block, "name");
+                            let input = block.get("input").cloned().unwrap_or(Value::Null);
+                            log.push((LogSource::Tool, format!("-> {} {}", name, preview(&input.to_string()))));
+                            self.push(TranscriptEvent::ToolUse { id: str_field(&block, "id"), name, input });
+                        }
+                        Some("tool_result") => {
+                            let content = tool_result_text(block.get("content").unwrap_or(&Value::Null));
+                            let is_error = block.get("is_error").and_then(|e| e.as_bool()).unwrap_or(false);
+                            let source = if is_error { LogSource::Stderr } else { LogSource::Tool };
+                            log.push((source, format!("<- {}", preview(&content))));
+                            self.push(TranscriptEvent::ToolResult { tool_use_id: str_field(&block, "tool_use_id"), content, is_error });
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            Some("result") => {
+                let is_error = message.get("is_error").and_then(|e| e.as_bool()).unwrap_or(false);
+                let num_turns = message.get("num_turns").and_then(|n| n.as_u64());
+                let cost_usd = message.get("total_cost_usd").or_else(|| message.get("cost_usd")).and_then(|c| c.as_f64());
+                let usage = message.get("usage");
+                let tokens = |fields: &[&str]| usage.map(|u| fields.iter().filter_map(|f| u.get(*f).and_then(|n| n.as_u64())).sum::<u64>());
+                log.push((LogSource::System, format!(
+                    "Claude session finished{} after {} turn(s)",
+                    if is_error { " with an error" } else { "" },
+                    num_turns.unwrap_or(0)
+                )));
+                self.push(TranscriptEvent::Result {
+                    is_error,
+                    num_turns,
+                    duration_ms: message.get("duration_ms").and_then(|d| d.as_u64()),
+                    cost_usd,
+                    input_tokens: tokens(&["input_tokens", "cache_creation_input_tokens", "cache_read_input_tokens"]),
+                    output_tokens: tokens(&["output_tokens"]),
+                });
+            }
+            _ => {}
+        }
+        log
+    }
+
+    pub fn finish(&mut self) {
+        self.ended_at = Some(Utc::now().to_rfc3339());
+    }
+
+    fn transcript_id(&self) -> String {
+        self.started_at.replace([':', '+'], "-").replace('.', "_")
+    }
+
+    fn summary(&self, transcript_id: String) -> TranscriptSummary {
+        TranscriptSummary {
+            transcript_id,
+            session_id: self.session_id.clone(),
+            started_at: self.started_at.clone(),
+            ended_at: self.ended_at.clone(),
+            num_entries: self.entries.len(),
+        }
+    }
+
+    pub fn metrics(&self) -> SessionMetrics {
+        let duration_seconds = self.ended_at.as_deref()
+            .and_then(|end| DateTime::parse_from_rfc3339(end).ok())
+            .zip(DateTime::parse_from_rfc3339(&self.started_at).ok())
+            .map(|(end, start)| (end - start).num_seconds());
+        let mut metrics = SessionMetrics {
+            block_id: self.block_id.clone(),
+            task_id: self.task_id.clone(),
+            session_id: self.session_id.clone(),
+            started_at: self.started_at.clone(),
+            duration_seconds,
+            num_turns: None,
+            tool_calls: 0,
+            tool_errors: 0,
+            tool_calls_by_name: BTreeMap::new(),
+            max_parallel_tool_calls: 0,
+            input_tokens: None,
+            output_tokens: None,
+            cost_usd: None,
+            succeeded: false,
+        };
+
+        // Tool calls of a message are recorded one after the other, before their results
+        let mut parallel = 0;
+        for entry in &self.entries {
+            match &entry.event {
+                TranscriptEvent::ToolUse { name, .. } => {
+                    metrics.tool_calls += 1;
+                    *metrics.tool_calls_by_name.entry(name.clone()).or_insert(0) += 1;
+                    parallel += 1;
+                    metrics.max_parallel_tool_calls = metrics.max_parallel_tool_calls.max(parallel);
+                    continue;
+                }
+                TranscriptEvent::ToolResult { is_error: true, .. } => metrics.tool_errors += 1,
+                TranscriptEvent::Result { is_error, num_turns, cost_usd, input_tokens, output_tokens, .. } => {
+                    metrics.succeeded = !is_error;
+                    metrics.num_turns = *num_turns;
+                    metrics.cost_usd = *cost_usd;
+                    metrics.input_tokens = *input_tokens;
+                    metrics.output_tokens = *output_tokens;
+                }
+                _ => {}
+            }
+            parallel = 0;
+        }
+        metrics
+    }
+
+    // Files passed to the tools of the session, without duplicates: those edited, and those only read
+    pub fn files_touched(&self) -> (Vec<String>, Vec<String>) {
+        let (mut edited, mut read) = (Vec::new(), Vec::new());
+        for entry in &self.entries {
+            let TranscriptEvent::ToolUse { name, input, .. } = &entry.event else {
+                continue;
+            };
+            let Some(path) = ["file_path", "notebook_path", "path"].iter().find_map(|key| input.get(*key).and_then(|p| p.as_str())) else {
+                continue;
+            };
+            let files = if EDIT_TOOLS.contains(&name.as_str()) { &mut edited } else { &mut read };
+            if !files.iter().any(|f| f == path) {
+                files.push(path.to_string());
+            }
+        }
+        read.retain(|f| !edited.contains(f));
+        (edited, read)
+    }
+
+    // Prompt section handing the context of this failed session to a new attempt at the task
+    pub fn replay_prompt(&self, failure: &str) -> String {
+        let mut prompt = String::from("\n\n## Previous attempt\n\n");
+        prompt.push_str("A previous session already worked on this task and failed; its changes were discarded. ");
+        prompt.push_str("Use what it learned to avoid repeating its mistakes instead of starting from zero.\n");
+
+        let (edited, read) = self.files_touched();
+        if !edited.is_empty() {
+            prompt.push_str(&format!("\nFiles it changed:\n{}\n", edited.iter().map(|f| format!("- {}", f)).collect::<Vec<_>>().join("\n")));
+        }
+        if !read.is_empty() {
+            let files: Vec<String> = read.iter().take(REPLAY_FILES_READ).map(|f| format!("- {}", f)).collect();
+            prompt.push_str(&format!("\nFiles it read:\n{}\n", files.join("\n")));
+        }
+
+        let texts: Vec<&str> = self.entries.iter()
+            .filter_map(|e| match &e.event {
+                TranscriptEvent::Text { text } if !text.trim().is_empty() => Some(text.as_str()),
+                _ => None,
+            })
+            .collect();
+        if !texts.is_empty() {
+            prompt.push_str("\nIts last messages:\n");
+            for text in &texts[texts.len().saturating_sub(REPLAY_MESSAGES)..] {
+                prompt.push_str(&format!("\n> {}\n", truncate(text, REPLAY_EXCERPT_CHARS).replace('\n', "\n> ")));
+            }
+        }
+
+        let errors: Vec<&str> = self.entries.iter()
+            .filter_map(|e| match &e.event {
+                TranscriptEvent::ToolResult { content, is_error: true, .. } => Some(content.as_str()),
+                _ => None,
+            })
+            .collect();
+        if !errors.is_empty() {
+            prompt.push_str("\nTool errors it ran into:\n");
+            for error in &errors[errors.len().saturating_sub(REPLAY_TOOL_ERRORS)..] {
+                prompt.push_str(&format!("\n```\n{}\n```\n", truncate(error, REPLAY_EXCERPT_CHARS)));
+            }
+        }
+
+        if !failure.trim().is_empty() {
+            prompt.push_str(&format!("\nWhy the attempt failed:\n\n```\n{}\n```\n", tail(failure, REPLAY_EXCERPT_CHARS)));
+        }
+        prompt
+    }
+
+    // The transcript as a markdown document that can be attached to a pull request
+    pub fn to_markdown(&self) -> String {
+        let mut md = format!("# Session transcript of task {} ({})\n\n", self.task_id, self.block_id);
+        if let Some(session_id) = &self.session_id {
+            md.push_str(&format!("- Session: `{}`\n", session_id));
+        }
+        if let Some(model) = &self.model {
+            md.push_str(&format!("- Model: {}\n", model));
+        }
+        md.push_str(&format!("- Started: {}\n", self.started_at));
+        if let Some(ended_at) = &self.ended_at {
+            md.push_str(&format!("- Ended: {}\n", ended_at));
+        }
+
+        for entry in &self.entries {
+            match &entry.event {
+                TranscriptEvent::Prompt { text } => md.push_str(&format!("\n## Prompt\n\n{}\n", text.trim())),
+                TranscriptEvent::Text { text } => md.push_str(&format!("\n## Assistant\n\n{}\n", text.trim())),
+                TranscriptEvent::ToolUse { name, input, .. } => {
+                    let input = serde_json::to_string_pretty(input).unwrap_or_default();
+                    md.push_str(&format!("\n### Tool call: {}\n\n```json\n{}\n```\n", name, input));
+                }
+                TranscriptEvent::ToolResult { content, is_error, .. } => {
+                    let title = if *is_error { "Tool error" } else { "Tool result" };
+                    md.push_str(&format!("\n<details><summary>{}</summary>\n\n```\n{}\n```\n\n</details>\n", title, content.trim_end()));
+                }
+                TranscriptEvent::Result { is_error, num_turns, duration_ms, cost_usd, input_tokens, output_tokens } => {
+                    md.push_str(&format!("\n## Result\n\n- Status: {}\n", if *is_error { "error" } else { "success" }));
+                    if let Some(turns) = num_turns {
+                        md.push_str(&format!("- Turns: {}\n", turns));
+                    }
+                    if let Some(ms) = duration_ms {
+                        md.push_str(&format!("- Duration: {:.1}s\n", *ms as f64 / 1000.0));
+                    }
+                    if let (Some(input), Some(output)) = (input_tokens, output_tokens) {
+                        md.push_str(&format!("- Tokens: {} in, {} out\n", input, output));
+                    }
+                    if let Some(cost) = cost_usd {
+                        md.push_str(&format!("- Cost: ${:.4}\n", cost));
+                    }
+                }
+            }
+        }
+        md
+    }
+}
+
+// IDs are used as path components, so they can't navigate the file system
+fn check_id(id: &str) -> Result<(), String> {
+    if id.is_empty() || id.contains(['/', '\\']) || id.starts_with('.') {
+        return Err(format!("Invalid ID '{}'", id));
+    }
+    Ok(())
+}
+
+fn task_directory(block_manager: &BlockConfigManager, block_id: &str, task_id: &str) -> Result<PathBuf, String> {
+    check_id(block_id)?;
+    check_id(task_id)?;
+    Ok(Path::new(&block_manager.project_data_file(TRANSCRIPTS_DIRECTORY)).join(block_id).join(task_id))
+}
+
+// Save the transcript of a session, dropping the oldest ones of the task beyond the limit
+pub fn save_transcript(block_manager: &BlockConfigManager, transcript: &SessionTranscript) -> Result<String, String> {
+    let dir = task_directory(block_manager, &transcript.block_id, &transcript.task_id)?;
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    let transcript_id = transcript.transcript_id();
+    let content = serde_json::to_string_pretty(transcript).map_err(|e| format!("Failed to serialize transcript: {}", e))?;
+    write_file_atomic(&dir.join(format!("{}.json", transcript_id)).to_string_lossy(), content.as_bytes())?;
+
+    let ids = transcript_ids(&dir);
+    for old in ids.iter().take(ids.len().saturating_sub(MAX_TRANSCRIPTS_PER_TASK)) {
+        let _ = fs::remove_file(dir.join(format!("{}.json", old)));
+    }
+    Ok(transcript_id)
+}
+
+// IDs of the transcripts in a task directory, oldest first
+fn transcript_ids(dir: &Path) -> Vec<String> {
+    let mut ids: Vec<String> = fs::read_dir(dir).map(|entries| entries.filter_map(|e| e.ok())
+        .filter_map(|e| e.file_name().to_string_lossy().strip_suffix(".json").map(String::from))
+        .collect())
+        .unwrap_or_default();
+    ids.sort();
+    ids
+}
+
+// Transcripts of the runs of a task, most recent first
+pub fn list_transcripts(block_manager: &BlockConfigManager, block_id: &str, task_id: &str) -> Result<Vec<TranscriptSummary>, String> {
+    let dir = task_directory(block_manager, block_id, task_id)?;
+    let mut summaries = Vec::new();
+    for id in transcript_ids(&dir).into_iter().rev() {
+        let transcript = load_transcript(block_manager, block_id, task_id, &id)?;
+        summaries.push(transcript.summary(id));
+    }
+    Ok(summaries)
+}
+
+// Transcripts of all the tasks, optionally of one block
+fn load_all_transcripts(block_manager: &BlockConfigManager, block_id: Option<&str>) -> Vec<SessionTranscript> {
+    let root = PathBuf::from(block_manager.project_data_file(TRANSCRIPTS_DIRECTORY));
+    let subdirectories = |dir: &Path| -> Vec<PathBuf> {
+        fs::read_dir(dir).map(|entries| entries.filter_map(|e| e.ok())
+            .map(|e| e.path())
+            .filter(|p| p.is_dir())
+            .collect())
+            .unwrap_or_default()
+    };
+    let block_dirs = match block_id {
+        Some(block_id) => vec![root.join(block_id)],
+        None => subdirectories(&root),
+    };
+
+    let mut transcripts = Vec::new();
+    for task_dir in block_dirs.iter().flat_map(|dir| subdirectories(dir)) {
+        for id in transcript_ids(&task_dir) {
+            let content = fs::read_to_string(task_dir.join(format!("{}.json", id)));
+            if let Some(transcript) = content.ok().and_then(|c| serde_json::from_str::<SessionTranscript>(&c).ok()) {
+                transcripts.push(transcript);
+            }
+        }
+    }
+    transcripts
+}
+
+// Metrics of the sessions started since a time, most recent first, with their totals
+pub fn session_metrics(block_manager: &BlockConfigManager, block_id: Option<&str>, since: Option<DateTime<Utc>>, limit: usize) -> Result<SessionMetricsReport, String> {
+    if let Some(block_id) = block_id {
+        check_id(block_id)?;
+    }
+    let mut sessions: Vec<SessionMetrics> = load_all_transcripts(block_manager, block_id).iter()
+        .filter(|t| since.is_none_or(|since| DateTime::parse_from_rfc3339(&t.started_at).is_ok_and(|started| started >= since)))
+        .map(|t| t.metrics())
+        .collect();
+    sessions.sort_by(|a, b| b.started_at.cmp(&a.started_at));
+
+    let totals = metrics_totals(&sessions);
+    sessions.truncate(limit);
+    Ok(SessionMetricsReport { totals, sessions })
+}
+
+fn metrics_totals(sessions: &[SessionMetrics]) -> SessionMetricsTotals {
+    let durations: Vec<i64> = sessions.iter().filter_map(|s| s.duration_seconds).collect();
+    let completed: HashSet<(&str, &str)> = sessions.iter()
+        .filter(|s| s.succeeded)
+        .map(|s| (s.block_id.as_str(), s.task_id.as_str()))
+        .collect();
+    SessionMetricsTotals {
+        sessions: sessions.len(),
+        tasks_completed: completed.len(),
+        failures: sessions.iter().filter(|s| !s.succeeded).count(),
+        tool_calls: sessions.iter().map(|s| s.tool_calls).sum(),
+        tool_errors: sessions.iter().map(|s| s.tool_errors).sum(),
+        max_parallel_tool_calls: sessions.iter().map(|s| s.max_parallel_tool_calls).max().unwrap_or(0),
+        input_tokens: sessions.iter().filter_map(|s| s.input_tokens).sum(),
+        output_tokens: sessions.iter().filter_map(|s| s.output_tokens).sum(),
+        cost_usd: sessions.iter().filter_map(|s| s.cost_usd).sum(),
+        average_duration_seconds: (!durations.is_empty()).then(|| durations.iter().sum::<i64>() as f64 / durations.len() as f64),
+    }
+}
+
+// Load a transcript; "latest" is the most recent run of the task
+pub fn load_transcript(block_manager: &BlockConfigManager, block_id: &str, task_id: &str, transcript_id: &str) -> Result<SessionTranscript, String> {
+    let dir = task_directory(block_manager, block_id, task_id)?;
+    let transcript_id = match transcript_id {
+        "latest" => transcript_ids(&dir).pop().ok_or_else(|| format!("No transcript found for task {}:{}", block_id, task_id))?,
+        id => {
+            check_id(id)?;
+            id.to_string()
+        }
+    };
+    let file = dir.join(format!("{}.json", transcript_id));
+    let content = fs::read_to_string(&file).map_err(|_| format!("Transcript {} not found", transcript_id))?;
+    serde_json::from_str(&content).map_err(|e| format!("Failed to parse transcript {}: {}", transcript_id, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_stream_line() {
+        let mut transcript = SessionTranscript::new("b1", "t1", None, "Add a login form");
+        let lines = [
+            r#"{"type":"system","subtype":"init","session_id":"s-1","model":"claude-sonnet"}"#,
+            r#"{"type":"assistant","message":{"content":[{"type":"text","text":"Reading the form.\nThen editing it."},{"type":"tool_use","id":"tu1","name":"Read","input":{"file_path":"src/form.ts"}}]}}"#,
+            r#"{"type":"user","message":{"content":[{"type":"tool_result","tool_use_id":"tu1","content":[{"type":"text","text":"export {}"}],"is_error":false}]}}"#,
+            r#"{"type":"result","subtype":"success","is_error":false,"num_turns":2,"duration_ms":1500,"total_cost_usd":0.01}"#,
+            "not json",
+        ];
+        let log: Vec<(LogSource, String)> = lines.iter().flat_map(|l| transcript.record_stream_line(l)).collect();
+
+        assert_eq!(transcript.session_id.as_deref(), Some("s-1"));
+        assert_eq!(transcript.model.as_deref(), Some("claude-sonnet"));
+        assert_eq!(log[1], (LogSource::Agent, "Reading the form.".to_string()));
+        assert_eq!(log[3], (LogSource::Tool, r#"-> Read {"file_path":"src/form.ts"}"#.to_string()));
+        assert_eq!(log[4], (LogSource::Tool, "<- export {}".to_string()));
+        assert_eq!(log.last().unwrap(), &(LogSource::Agent, "not json".to_string()));
+        // Prompt, text, tool use, tool result, result and the raw line
+        assert_eq!(transcript.entries.len(), 6);
+        assert_eq!(transcript.entries[3].event, TranscriptEvent::ToolResult {
+            tool_use_id: "tu1".to_string(),
+            content: "export {}".to_string(),
+            is_error: false,
+        });
+
+        let md = transcript.to_markdown();
+        assert!(md.contains("## Prompt\n\nAdd a login form"));
+        assert!(md.contains("### Tool call: Read"));
+        assert!(md.contains("- Cost: $0.0100"));
+    }
+
+    #[test]
+    fn test_replay_prompt() {
+        let mut transcript = SessionTranscript::new("b1", "t1", None, "Add a login form");
+        for line in [
+            r#"{"type":"assistant","message":{"content":[{"type":"tool_use","id":"tu1","name":"Read","input":{"file_path":"src/form.ts"}},{"type":"tool_use","id":"tu2","name":"Read","input":{"file_path":"src/api.ts"}}]}}"#,
+            r#"{"type":"assistant","message":{"content":[{"type":"tool_use","id":"tu3","name":"Edit","input":{"file_path":"src/form.ts","old_string":"a","new_string":"b"}}]}}"#,
+            r#"{"type":"user","message":{"content":[{"type":"tool_result","tool_use_id":"tu4","content":"npm test: 2 failing","is_error":true}]}}"#,
+            r#"{"type":"assistant","message":{"content":[{"type":"text","text":"The tests still fail."}]}}"#,
+        ] {
+            transcript.record_stream_line(line);
+        }
+
+        assert_eq!(transcript.files_touched(), (vec!["src/form.ts".to_string()], vec!["src/api.ts".to_string()]));
+        let prompt = transcript.replay_prompt("Claude CLI command failed with exit code: Some(1)");
+        assert!(prompt.contains("Files it changed:\n- src/form.ts\n"));
+        assert!(prompt.contains("Files it read:\n- src/api.ts\n"));
+        assert!(prompt.contains("> The tests still fail."));
+        assert!(prompt.contains("npm test: 2 failing"));
+        assert!(prompt.contains("exit code: Some(1)"));
+        assert_eq!(tail("abcdef", 3), "...def");
+    }
+
+    #[test]
+    fn test_metrics() {
+        let mut transcript = SessionTranscript::new("b1", "t1", None, "Add a login form");
+        for line in [
+            r#"{"type":"assistant","message":{"content":[{"type":"tool_use","id":"tu1","name":"Read","input":{}},{"type":"tool_use","id":"tu2","name":"Read","input":{}},{"type":"tool_use","id":"tu3","name":"Grep","input":{}}]}}"#,
+            r#"{"type":"user","message":{"content":[{"type":"tool_result","tool_use_id":"tu1","content":"","is_error":true}]}}"#,
+            r#"{"type":"assistant","message":{"content":[{"type":"tool_use","id":"tu4","name":"Edit","input":{}}]}}"#,
+            r#"{"type":"result","is_error":false,"num_turns":3,"total_cost_usd":0.02,"usage":{"input_tokens":100,"cache_read_input_tokens":900,"output_tokens":50}}"#,
+        ] {
+            transcript.record_stream_line(line);
+        }
+        transcript.ended_at = Some((DateTime::parse_from_rfc3339(&transcript.started_at).unwrap() + chrono::Duration::seconds(90)).to_rfc3339());
+
+        let metrics = transcript.metrics();
+        assert_eq!((metrics.tool_calls, metrics.tool_errors, metrics.max_parallel_tool_calls), (4, 1, 3));
+        assert_eq!(metrics.tool_calls_by_name.get("Read"), Some(&2));
+        assert_eq!((metrics.input_tokens, metrics.output_tokens), (Some(1000), Some(50)));
+        assert_eq!(metrics.duration_seconds, Some(90));
+        assert!(metrics.succeeded);
+
+        // A killed session has no result
+        let failed = SessionTranscript::new("b1", "t2", None, "Add a logout button").metrics();
+        let totals = metrics_totals(&[metrics.clone(), metrics, failed]);
+        assert_eq!((totals.sessions, totals.tasks_completed, totals.failures), (3, 1, 1));
+        assert_eq!((totals.input_tokens, totals.average_duration_seconds), (2000, Some(90.0)));
+    }
+
+    #[test]
+    fn test_check_id() {
+        assert!(check_id("2026-03-01T10-00-00_123-00-00").is_ok());
+        assert!(check_id("..").is_err());
+        assert!(check_id("a/b").is_err());
+    }
+}
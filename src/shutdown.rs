@@ -0,0 +1,62 @@
+use lazy_static::lazy_static;
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+
+// Graceful shutdown: once requested, no new execution starts and the running work gets the drain
+// timeout of the project to finish
+
+lazy_static! {
+    // Time the shutdown was requested at
+    static ref SHUTDOWN: watch::Sender<Option<Instant>> = watch::channel(None).0;
+}
+
+pub fn request() {
+    // A second request keeps the time of the first
+    SHUTDOWN.send_if_modified(|requested_at| {
+        if requested_at.is_some() {
+            return false;
+        }
+        *requested_at = Some(Instant::now());
+        true
+    });
+}
+
+pub fn is_requested() -> bool {
+    SHUTDOWN.borrow().is_some()
+}
+
+// Time left of a drain timeout counted from the shutdown request
+pub fn remaining(timeout: Duration) -> Duration {
+    match *SHUTDOWN.borrow() {
+        Some(requested_at) => timeout.saturating_sub(requested_at.elapsed()),
+        None => timeout,
+    }
+}
+
+// Resolve once a shutdown is requested
+pub async fn requested() {
+    let mut receiver = SHUTDOWN.subscribe();
+    let _ = receiver.wait_for(|requested_at| requested_at.is_some()).await;
+}
+
+// Resolve on the next SIGINT or SIGTERM
+pub async fn signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => {},
+                    _ = terminate.recv() => {},
+                }
+            }
+            Err(_) => { let _ = tokio::signal::ctrl_c().await; }
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = tokio::signal::ctrl_c().await;
+    }
+}
+
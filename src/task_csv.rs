@@ -0,0 +1,232 @@
+use crate::block_config::BlockConfigManager;
+use crate::models::{Block, Effort, Task};
+use crate::project_config::TaskWorkflow;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+// Columns of the task export
+pub const EXPORT_COLUMNS: &[&str] = &[
+    "block_id", "block_name", "task_id", "task_name", "description", "status", "estimated_effort",
+    "acceptance_criteria", "dependencies", "files_affected", "testing_requirements", "commit_id",
+];
+
+// Task fields that can be imported; unmapped fields are read from the column of the same name
+pub const IMPORT_FIELDS: &[&str] = &[
+    "task_id", "task_name", "description", "status", "estimated_effort",
+    "acceptance_criteria", "dependencies", "files_affected", "testing_requirements",
+];
+
+// Separator of list values (acceptance criteria, dependencies, ...) within a cell
+const LIST_SEPARATOR: char = ';';
+
+// Validation error of a single CSV row (row numbers are file line numbers, the header is row 1)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CsvRowError {
+    pub row: u64,
+    pub message: String,
+}
+
+// Outcome of a CSV import: the IDs of the imported tasks and the rejected rows
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CsvImportResult {
+    pub imported: Vec<String>,
+    pub errors: Vec<CsvRowError>,
+}
+
+// Export the tasks of the given blocks as CSV, one row per task
+pub fn export_tasks_csv(blocks: &[Block]) -> Result<String, String> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer.write_record(EXPORT_COLUMNS).map_err(|e| format!("Failed to write CSV: {}", e))?;
+
+    for block in blocks {
+        let mut tasks: Vec<&Task> = block.todo_list.values().collect();
+        tasks.sort_by(|a, b| a.task_name.cmp(&b.task_name).then_with(|| a.task_id.cmp(&b.task_id)));
+
+        for task in tasks {
+            let effort = task.estimated_effort.as_ref()
+                .map(|e| match e.hours {
+                    Some(hours) => format!("{} hours", hours),
+                    None => serde_json::to_value(e.size).ok().and_then(|v| v.as_str().map(String::from)).unwrap_or_default(),
+                })
+                .unwrap_or_default();
+
+            writer.write_record([
+                block.block_id.as_str(),
+                block.name.as_str(),
+                task.task_id.as_str(),
+                task.task_name.as_str(),
+                task.description.as_str(),
+                task.status.as_str(),
+                effort.as_str(),
+                join_list(&task.acceptance_criteria).as_str(),
+                join_list(&task.dependencies).as_str(),
+                join_list(&task.files_affected).as_str(),
+                join_list(&task.testing_requirements).as_str(),
+                task.commit_id.as_str(),
+            ]).map_err(|e| format!("Failed to write CSV: {}", e))?;
+        }
+    }
+
+    let bytes = writer.into_inner().map_err(|e| format!("Failed to write CSV: {}", e))?;
+    String::from_utf8(bytes).map_err(|e| format!("Failed to write CSV: {}", e))
+}
+
+// Parse tasks from CSV into (row, task) pairs. `column_mapping` maps task fields to CSV headers;
+// a mapping or header problem fails the whole import, invalid rows are reported and skipped
+pub fn parse_tasks_csv(content: &str, column_mapping: &HashMap<String, String>, workflow: &TaskWorkflow) -> Result<(Vec<(u64, Task)>, Vec<CsvRowError>), String> {
+    if let Some(field) = column_mapping.keys().find(|f| !IMPORT_FIELDS.contains(&f.as_str())) {
+        return Err(format!("Unknown task field '{}' in column mapping (expected one of: {})", field, IMPORT_FIELDS.join(", ")));
+    }
+
+    let mut reader = csv::ReaderBuilder::new().trim(csv::Trim::All).from_reader(content.as_bytes());
+    let headers = reader.headers().map_err(|e| format!("Failed to read CSV header: {}", e))?.clone();
+
+    // Resolve the column index of every field, explicit mappings must name an existing column
+    let mut columns: HashMap<&'static str, usize> = HashMap::new();
+    for &field in IMPORT_FIELDS {
+        let header = column_mapping.get(field).map(|h| h.as_str()).unwrap_or(field);
+        match headers.iter().position(|h| h.eq_ignore_ascii_case(header)) {
+            Some(index) => { columns.insert(field, index); },
+            None if column_mapping.contains_key(field) => return Err(format!("Column '{}' mapped to '{}' not found in CSV header", header, field)),
+            None => {},
+        }
+    }
+    if !columns.contains_key("description") {
+        return Err("CSV has no description column".to_string());
+    }
+
+    let mut tasks = Vec::new();
+    let mut errors = Vec::new();
+    let mut seen_ids = HashSet::new();
+    for (index, record) in reader.records().enumerate() {
+        let record = match record {
+            Ok(record) => record,
+            Err(e) => {
+                let row = e.position().map(|p| p.line()).unwrap_or(index as u64 + 2);
+                errors.push(CsvRowError { row, message: format!("Malformed row: {}", e) });
+                continue;
+            }
+        };
+        let row = record.position().map(|p| p.line()).unwrap_or(index as u64 + 2);
+        if record.iter().all(|cell| cell.is_empty()) {
+            continue;
+        }
+
+        let cell = |field: &'static str| columns.get(field).and_then(|i| record.get(*i)).unwrap_or("");
+        match task_from_row(&cell, workflow) {
+            Ok(task) if !seen_ids.insert(task.task_id.clone()) => {
+                errors.push(CsvRowError { row, message: format!("Duplicate task ID '{}'", task.task_id) });
+            },
+            Ok(task) => tasks.push((row, task)),
+            Err(message) => errors.push(CsvRowError { row, message }),
+        }
+    }
+
+    Ok((tasks, errors))
+}
+
+// Import tasks from CSV into a block; tasks whose ID already exists in the block are rejected
+pub fn import_tasks_csv(
+    block_manager: &BlockConfigManager,
+    block_id: &str,
+    content: &str,
+    column_mapping: &HashMap<String, String>,
+    workflow: &TaskWorkflow,
+) -> Result<CsvImportResult, String> {
+    let block = block_manager.get_blocks()?
+        .into_iter()
+        .find(|b| b.block_id == block_id)
+        .ok_or_else(|| format!("Block with ID {} not found", block_id))?;
+
+    let (tasks, errors) = parse_tasks_csv(content, column_mapping, workflow)?;
+    let mut result = CsvImportResult { imported: Vec::new(), errors };
+    for (row, task) in tasks {
+        if block.todo_list.contains_key(&task.task_id) {
+            result.errors.push(CsvRowError { row, message: format!("Task ID '{}' already exists in block {}", task.task_id, block_id) });
+            continue;
+        }
+        result.imported.push(block_manager.add_task(block_id, task)?);
+    }
+    result.errors.sort_by_key(|e| e.row);
+
+    Ok(result)
+}
+
+// Build a task from the cells of a row
+fn task_from_row<'r>(cell: &dyn Fn(&'static str) -> &'r str, workflow: &TaskWorkflow) -> Result<Task, String> {
+    let description = cell("description");
+    if description.is_empty() {
+        return Err("Description is required".to_string());
+    }
+
+    let mut task = Task::new(description.to_string());
+    if !cell("task_id").is_empty() {
+        task.task_id = cell("task_id").to_string();
+    }
+    task.task_name = cell("task_name").to_string();
+    task.status = workflow.normalize_status(cell("status"))?;
+    task.estimated_effort = Effort::parse(cell("estimated_effort"))?;
+    task.acceptance_criteria = split_list(cell("acceptance_criteria"));
+    task.dependencies = split_list(cell("dependencies"));
+    task.files_affected = split_list(cell("files_affected"));
+    task.testing_requirements = split_list(cell("testing_requirements"));
+    Ok(task)
+}
+
+fn join_list(values: &[String]) -> String {
+    values.join(&format!("{} ", LIST_SEPARATOR))
+}
+
+fn split_list(value: &str) -> Vec<String> {
+    value.split(LIST_SEPARATOR)
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_export_and_parse_round_trip() {
+        let mut block = Block::new("Auth".to_string(), "Authentication".to_string(), Vec::new(), Vec::new());
+        let mut task = Task::new("Add login, with \"remember me\"".to_string());
+        task.task_name = "Login".to_string();
+        task.status = "[TODO]".to_string();
+        task.acceptance_criteria = vec!["Valid users log in".to_string(), "Invalid users are rejected".to_string()];
+        task.estimated_effort = Effort::parse("3 hours").unwrap();
+        block.todo_list.insert(task.task_id.clone(), task.clone());
+
+        let csv = export_tasks_csv(&[block]).unwrap();
+        let (tasks, errors) = parse_tasks_csv(&csv, &HashMap::new(), &TaskWorkflow::default()).unwrap();
+        assert!(errors.is_empty());
+        assert_eq!(tasks.len(), 1);
+        let (row, imported) = &tasks[0];
+        assert_eq!(*row, 2);
+        assert_eq!(imported.task_id, task.task_id);
+        assert_eq!(imported.description, task.description);
+        assert_eq!(imported.acceptance_criteria, task.acceptance_criteria);
+        assert_eq!(imported.estimated_effort, task.estimated_effort);
+    }
+
+    #[test]
+    fn test_parse_with_column_mapping_and_row_errors() {
+        let csv = "Summary,Details,State\nLogin,Add login,done\nLogout,,todo\nReset,Add reset,bogus\n";
+        let mapping: HashMap<String, String> = [
+            ("task_name".to_string(), "Summary".to_string()),
+            ("description".to_string(), "Details".to_string()),
+            ("status".to_string(), "State".to_string()),
+        ].into_iter().collect();
+
+        let (tasks, errors) = parse_tasks_csv(csv, &mapping, &TaskWorkflow::default()).unwrap();
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].1.task_name, "Login");
+        assert_eq!(tasks[0].1.status, "[COMPLETED]");
+        assert_eq!(errors.iter().map(|e| e.row).collect::<Vec<_>>(), vec![3, 4]);
+        assert_eq!(errors[0].message, "Description is required");
+
+        let mapping: HashMap<String, String> = [("description".to_string(), "Missing".to_string())].into_iter().collect();
+        assert!(parse_tasks_csv(csv, &mapping, &TaskWorkflow::default()).is_err());
+    }
+}
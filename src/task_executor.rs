@@ -1,21 +1,51 @@
 use crate::block_config::BlockConfigManager;
+use crate::execution_timeline;
+use crate::git_stash;
+use crate::git_util::git;
+use crate::llm_usage::{self, CostAttribution, TokenUsage};
 use crate::log_stream;
-use crate::log_stream::get_logs_str;
-use crate::models::Task;
-use crate::project_config::ProjectConfigManager;
+use crate::metrics;
+use crate::process_tracker;
+use crate::pull_request;
+use crate::log_stream::{get_logs_str, LogSource};
+use crate::models::{resolve_task_env, Task};
+use crate::session_transcript::{self, SessionTranscript};
+use crate::shutdown;
+use crate::project_config::{ExecutionHook, ProjectConfigManager, TaskBranchCompletion, TaskRetryPolicy, TASK_STATUS_CANCELLED, TASK_STATUS_COMPLETED, TASK_STATUS_FAILED, TASK_STATUS_IN_PROGRESS, WEBHOOK_EVENT_EXECUTION_FAILED, WEBHOOK_EVENT_EXECUTION_FINISHED, WEBHOOK_EVENT_EXECUTION_STARTED};
 use crate::task_queue::QueuedTask;
+use crate::slack;
+use crate::webhooks;
+use crate::worktree::{self, TaskWorktree};
+use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet, VecDeque};
 use std::io::{BufRead, BufReader, Write};
 use std::path::Path;
-use std::process::{Command, Stdio};
+use std::process::{Child, Command, ExitStatus, Stdio};
 use std::sync::{Arc, Mutex, RwLock};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
+
+// File used to persist the pending execution queue, stored next to the blocks config
+pub const EXECUTOR_QUEUE_FILE: &str = "executor_queue.json";
+
+// File used to persist whether the queue is paused, stored next to the blocks config
+pub const EXECUTOR_STATE_FILE: &str = "executor_state.json";
+
+// Persisted executor state
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ExecutorState {
+    pub paused: bool,
+    pub paused_at: Option<String>,
+}
 
 // Singleton task executor that manages a global execution queue
 pub struct TaskExecutor {
     queue: Mutex<VecDeque<QueuedTask>>,
     in_progress: RwLock<HashSet<String>>, // Set of task IDs currently in the queue or being processed
+    running: Mutex<Option<String>>, // Execution ID ("block_id:task_id") of the task being processed
+    cancel_requested: Mutex<HashSet<String>>, // Executions to stop at the next opportunity
+    state: Mutex<ExecutorState>, // Paused executors finish the running task but start no new one
+    current_run: Mutex<Option<String>>, // ID of the run of the queue in the timeline, until the queue drains
     project_manager: Arc<ProjectConfigManager>,
     block_manager: Arc<BlockConfigManager>,
 }
@@ -26,10 +56,18 @@ impl TaskExecutor {
         let executor = Arc::new(Self {
             queue: Mutex::new(VecDeque::new()),
             in_progress: RwLock::new(HashSet::new()),
+            running: Mutex::new(None),
+            cancel_requested: Mutex::new(HashSet::new()),
+            state: Mutex::new(ExecutorState::default()),
+            current_run: Mutex::new(None),
             project_manager,
             block_manager,
         });
 
+        // Restore the queue and paused state persisted by a previous run
+        executor.load_queue();
+        executor.load_state();
+
         // Start the background thread for processing the queue
         TaskExecutor::start_background_thread(executor.clone());
 
@@ -43,15 +81,73 @@ impl TaskExecutor {
                 // Process any tasks in the queue
                 if let Some(task) = executor.get_next_task() {
                     println!("Processing task: {}:{}", task.block_id, task.task_id);
+                    let task_id = task.get_unique_id();
+                    if let Ok(mut running) = executor.running.lock() {
+                        *running = Some(task_id.clone());
+                    }
 
-                    // Execute the task
-                    executor.execute_task(task.clone());
+                    // Execute the task, recording it in the execution timeline; retries continue the first attempt's entry
+                    let run_id = executor.current_run_id();
+                    if task.attempt.is_none() {
+                        if let Err(e) = execution_timeline::record_start(&executor.block_manager, &run_id, &task.block_id, &task.task_id) {
+                            println!("Failed to record task start: {}", e);
+                        }
+                    }
+                    let started = Instant::now();
+                    let (block_name, task_name) = executor.task_names(&task.block_id, &task.task_id);
+                    if task.attempt.is_none() {
+                        webhooks::emit(WEBHOOK_EVENT_EXECUTION_STARTED, serde_json::json!({
+                            "run_id": run_id,
+                            "block_id": task.block_id,
+                            "block_name": block_name,
+                            "task_id": task.task_id,
+                            "task_name": task_name,
+                        }));
+                    }
+                    if !executor.execute_task(task.clone()) {
+                        // Put back in the queue for a retry: the task stays in progress
+                        if let Ok(mut running) = executor.running.lock() {
+                            *running = None;
+                        }
+                        continue;
+                    }
+                    let finished_task = executor.block_manager.get_blocks().ok()
+                        .and_then(|blocks| blocks.into_iter().find(|b| b.block_id == task.block_id))
+                        .and_then(|block| block.todo_list.get(&task.task_id).cloned());
+                    let status = finished_task.as_ref().map(|t| t.status.clone()).unwrap_or_default();
+                    if let Err(e) = execution_timeline::record_end(&executor.block_manager, &run_id, &task.block_id, &task.task_id, &status) {
+                        println!("Failed to record task end: {}", e);
+                    }
+                    metrics::increment(metrics::TASK_EXECUTIONS, &[("status", &status)], 1.0);
+                    metrics::observe(metrics::TASK_EXECUTION_DURATION, &[], started.elapsed());
+                    let event = if status == TASK_STATUS_FAILED { WEBHOOK_EVENT_EXECUTION_FAILED } else { WEBHOOK_EVENT_EXECUTION_FINISHED };
+                    webhooks::emit(event, serde_json::json!({
+                        "run_id": run_id,
+                        "block_id": task.block_id,
+                        "block_name": block_name,
+                        "task_id": task.task_id,
+                        "task_name": task_name,
+                        "status": status,
+                        "log_excerpt": slack::log_excerpt(finished_task.as_ref().map(|t| t.log.as_str()).unwrap_or_default()),
+                    }));
 
                     // Remove the task from the in_progress set
-                    let task_id = task.get_unique_id();
+                    if let Ok(mut running) = executor.running.lock() {
+                        *running = None;
+                    }
+                    if let Ok(mut cancel_requested) = executor.cancel_requested.lock() {
+                        cancel_requested.remove(&task_id);
+                    }
                     if let Ok(mut in_progress) = executor.in_progress.write() {
                         in_progress.remove(&task_id);
                     }
+
+                    // The execution ends when the queue drains
+                    if executor.get_queue().is_empty() {
+                        if let Ok(mut current_run) = executor.current_run.lock() {
+                            *current_run = None;
+                        }
+                    }
                 }
 
                 // Sleep for a short time before checking the queue again
@@ -60,11 +156,11 @@ impl TaskExecutor {
         });
     }
 
-    pub fn execute_git_task(&self, block_id: &String, task_id: &String) -> Result<(String, String), String> {
-        // Create a unique task ID for logging
+    // `replay` is the context of a failed previous session, added to the task prompt
+    pub fn execute_git_task(&self, block_id: &String, task_id: &String, replay: Option<&str>) -> Result<(String, String), String> {
+        // Create a unique task ID for logging. The task log, hook output included, is kept under it,
+        // so the log returned by the execution is read with this key too.
         let log_task_id = format!("{}:{}", block_id, task_id);
-        // Clear any existing logs for this task
-        log_stream::clear_logs(&log_task_id);
 
         // Get the project home directory from the project config
         let project_config = match self.project_manager.get_config() {
@@ -72,7 +168,8 @@ impl TaskExecutor {
             Err(_) => return Err("Failed to get project configuration".to_string()),
         };
 
-        let main_branch = &project_config.main_branch.unwrap_or("main".to_string());
+        // The base branch of the branch-per-task workflow, the main branch otherwise
+        let main_branch = &project_config.task_base_branch();
 
         let project_dir = project_config.project_home_directory.clone();
         if project_dir.is_empty() {
@@ -106,67 +203,164 @@ impl TaskExecutor {
             return Err(error_msg)
         }
 
-        // Get the task prompt
-        let task_prompt = task_opt.to_prompt();
-
-        // Step 1: Pull latest main branch
-        println!("Step 1: Pulling latest main branch");
-        let msg = format!("Step 1: Pulling latest main branch {}",  task_id);
-        log_stream::add_log(&task_id, msg.clone());
+        // Get the task prompt, specialized for the block's language/framework
+        let mut task_prompt = task_opt.to_prompt();
+        if let Some(tech_hints) = &block.tech_hints {
+            task_prompt.push_str(&tech_hints.to_prompt_section());
+        }
+        if let Some(replay) = replay {
+            task_prompt.push_str(replay);
+        }
 
-        let pull_output = Command::new("git")
-            .arg("checkout")
-            .arg(main_branch)
-            .current_dir(&project_dir)
-            .output();
+        // Environment of the execution's subprocesses; only the names are logged as values may be secrets
+        let task_env = match resolve_task_env(project_config.task_env.as_deref().unwrap_or_default(), &block.env) {
+            Ok(task_env) => task_env,
+            Err(e) => {
+                log_stream::add_log(&log_task_id, e.clone());
+                return Err(e);
+            }
+        };
+        if !task_env.is_empty() {
+            let names: Vec<&str> = task_env.iter().map(|(name, _)| name.as_str()).collect();
+            log_stream::add_log(&log_task_id, format!("Injecting environment variables: {}", names.join(", ")));
+        }
 
-        if let Err(e) = pull_output {
-            let task_id = task_id.clone();
-            let error_msg = format!("Failed to checkout {} branch, task: {} error: {}", main_branch, task_id, e);
-            log_stream::add_log(&log_task_id, error_msg.clone());
-            return Err(error_msg)
+        // In worktree mode the branch gets its own working tree, removed when the execution ends
+        let worktree_config = project_config.worktrees.clone().unwrap_or_default();
+
+        // Park uncommitted local changes so they neither block the branch switches nor end up in
+        // the commit of the task. Worktrees leave the project checkout alone.
+        let mut parked = None;
+        if !worktree_config.enabled {
+            match ParkedChanges::park(&project_dir, task_id, &log_task_id) {
+                Ok(changes) => parked = Some(changes),
+                Err(e) => {
+                    let error_msg = format!("Failed to stash local changes: {}", e);
+                    log_stream::add_log(&log_task_id, error_msg.clone());
+                    return Err(error_msg);
+                }
+            }
         }
 
-        let pull_output = Command::new("git")
-            .arg("pull")
-            .current_dir(&project_dir)
-            .output();
+        // Step 1: Pull latest main branch. Worktrees leave the project's checkout alone: other
+        // executions may be running in it, so the branch starts from the local main branch.
+        if !worktree_config.enabled {
+            println!("Step 1: Pulling latest main branch");
+            let msg = format!("Step 1: Pulling latest main branch {}",  task_id);
+            log_stream::add_log(&task_id, msg.clone());
+
+            let pull_output = Command::new("git")
+                .arg("checkout")
+                .arg(main_branch)
+                .current_dir(&project_dir)
+                .output();
+
+            if let Err(e) = pull_output {
+                let task_id = task_id.clone();
+                let error_msg = format!("Failed to checkout {} branch, task: {} error: {}", main_branch, task_id, e);
+                log_stream::add_log(&log_task_id, error_msg.clone());
+                return Err(error_msg)
+            }
 
-        if let Err(e) = pull_output {
-            let task_id = task_id.clone();
-            let error_msg = format!("Failed to pull latest changes from git. task: {}, error: {}", task_id, e);
-            log_stream::add_log(&log_task_id, error_msg.clone());
-            return Err(error_msg);
+            let pull_output = Command::new("git")
+                .arg("pull")
+                .current_dir(&project_dir)
+                .output();
+
+            if let Err(e) = pull_output {
+                let task_id = task_id.clone();
+                let error_msg = format!("Failed to pull latest changes from git. task: {}, error: {}", task_id, e);
+                log_stream::add_log(&log_task_id, error_msg.clone());
+                return Err(error_msg);
+            }
         }
 
         // Step 2: Create a task-specific branch
-        println!("Step 2: Creating task-specific branch using task ID: {}", task_id);
-        let msg = format!("Step 2: Creating task-specific branch using task ID {}",  task_id);
+        let task_branch = project_config.task_branch(task_opt);
+        let open_pull_request = project_config.branch_workflow().is_some_and(|w| w.on_success == TaskBranchCompletion::PullRequest);
+        println!("Step 2: Creating task-specific branch {}", task_branch);
+        let msg = format!("Step 2: Creating task-specific branch {} {}", task_branch, task_id);
         log_stream::add_log(&task_id, msg.clone());
 
-        let branch_output = Command::new("git")
-            .arg("checkout")
-            .arg("-b")
-            .arg(&task_id)
-            .current_dir(&project_dir)
-            .output();
-
-        if let Err(e) = branch_output {
-            let task_id = task_id.clone();
-            return Err(format!("Failed to create task branch: {}", e));
+        let mut task_worktree = None;
+        if worktree_config.enabled {
+            let path = worktree::worktree_path(&project_dir, &worktree_config, &task_branch);
+            match TaskWorktree::create(&project_dir, path, &task_branch, main_branch) {
+                Ok(mut created) => {
+                    // A failed execution leaves the worktree behind when configured
+                    created.set_keep(worktree_config.keep_failed);
+                    log_stream::add_log(&log_task_id, format!("Created worktree {}", created.path().display()));
+                    task_worktree = Some(created);
+                },
+                Err(e) => {
+                    let error_msg = format!("Failed to create task worktree: {}", e);
+                    log_stream::add_log(&log_task_id, error_msg.clone());
+                    return Err(error_msg);
+                }
+            }
+        } else {
+            let branch_output = Command::new("git")
+                .arg("checkout")
+                .arg("-b")
+                .arg(&task_branch)
+                .current_dir(&project_dir)
+                .output();
+
+            if let Err(e) = branch_output {
+                return Err(format!("Failed to create task branch: {}", e));
+            }
+            // From now on a failed execution leaves the task branch before the parked changes come back
+            if let Some(parked) = parked.as_mut() {
+                parked.set_branches(&task_branch, main_branch);
+            }
         }
+        let work_dir = match &task_worktree {
+            Some(task_worktree) => task_worktree.path().to_string_lossy().to_string(),
+            None => project_dir.clone(),
+        };
+
+        // Run the pre-execution hooks in the working tree of the task
+        let hooks = project_config.execution_hooks.clone().unwrap_or_default();
+        run_hooks(&hooks.before, "before", &work_dir, &task_env, block_id, task_id, &log_task_id)?;
+
+        // Commits made after this point belong to the task, whether the agent or Forge makes them
+        let start_commit = match git(&work_dir, &["rev-parse", "HEAD"]) {
+            Ok(commit) => Some(commit),
+            Err(e) => {
+                log_stream::add_log(&log_task_id, format!("Failed to get the starting commit: {}", e));
+                None
+            }
+        };
 
         // Step 3: Execute the task using Claude CLI
         println!("Step 3: Executing task");
         let msg = format!("Step 3: Executing task {}",  task_id);
         log_stream::add_log(&task_id, msg.clone());
 
+        // Don't start Claude if the execution was cancelled during the git steps
+        if self.is_cancel_requested(&log_task_id) {
+            return Err("Execution cancelled".to_string());
+        }
+
         // Log the start of the task
         log_stream::add_log(&log_task_id, "Starting Claude execution...".to_string());
-
-        let result = Command::new("claude")
-            .arg("--dangerously-skip-permissions")
-            .current_dir(&project_dir)
+        let claude_log_start = log_stream::get_log_storage().get_logs(&log_task_id).len();
+
+        // The session is streamed as JSON messages so its transcript can be recorded
+        let mut claude = Command::new("claude");
+        claude.arg("--dangerously-skip-permissions")
+            .arg("--print")
+            .arg("--output-format").arg("stream-json")
+            .arg("--verbose");
+        if let Some(model) = project_config.execution_model() {
+            log_stream::add_log(&log_task_id, format!("Using model {}", model));
+            claude.arg("--model").arg(model);
+        }
+        // Claude runs in its own process group, so the tools it starts are killed along with it
+        process_tracker::own_process_group(&mut claude);
+        let result = claude
+            .current_dir(&work_dir)
+            .envs(task_env.iter().map(|(name, value)| (name, value)))
             .stdin(Stdio::piped())
             .stdout(Stdio::piped())
             .stderr(Stdio::piped())
@@ -181,6 +375,9 @@ impl TaskExecutor {
             }
         };
 
+        // Track the process so it can be reaped if Forge dies during the execution
+        let _process_guard = process_tracker::register(child.id(), &log_task_id, "claude");
+
 
         // Write the task description to the command's stdin
         if let Some(mut stdin) = child.stdin.take() {
@@ -192,21 +389,27 @@ impl TaskExecutor {
 
         }
 
-        // Stream stdout in real-time
+        // Stream stdout in real-time, recording the messages of the session in its transcript
+        let transcript = Arc::new(Mutex::new(SessionTranscript::new(block_id, task_id, project_config.execution_model(), &task_prompt)));
+        let mut stdout_reader = None;
         if let Some(stdout) = child.stdout.take() {
             let reader = BufReader::new(stdout);
             let log_task_id_clone = log_task_id.clone();
+            let transcript = transcript.clone();
 
             // Spawn a thread to read stdout line by line
-            thread::spawn(move || {
+            stdout_reader = Some(thread::spawn(move || {
                 for line in reader.lines() {
                     if let Ok(line) = line {
-                        // Add the line to the log storage
-                        log_stream::add_log(&log_task_id_clone, line.clone());
-                        println!("Claude: {}", line);
+                        // Add the rendered messages to the log storage
+                        let log_lines = transcript.lock().unwrap().record_stream_line(&line);
+                        for (source, text) in log_lines {
+                            println!("Claude: {}", text);
+                            log_stream::add_log_from(&log_task_id_clone, source, text);
+                        }
                     }
                 }
-            });
+            }));
         }
 
         // Stream stderr in real-time
@@ -218,36 +421,81 @@ impl TaskExecutor {
             thread::spawn(move || {
                 for line in reader.lines() {
                     if let Ok(line) = line {
-                        // Add the line to the log storage with an error prefix
-                        log_stream::add_log(&log_task_id_clone, format!("ERROR: {}", line));
+                        // Add the line to the log storage; it is rendered with an error prefix
+                        log_stream::add_log_from(&log_task_id_clone, LogSource::Stderr, line.clone());
                         println!("Claude ERROR: {}", line);
                     }
                 }
             });
         }
 
-        // Wait for the command to complete
-        let status = match child.wait() {
+        // Wait for the command to complete, killing it when it exceeds the task limits
+        let limits = project_config.task_limits.clone().unwrap_or_default();
+        let timeout = task_opt.timeout_minutes.or(limits.timeout_minutes).map(|m| Duration::from_secs(m.max(1).saturating_mul(60)));
+        let status = wait_with_limits(&mut child, timeout, limits.max_memory_mb);
+
+        // Save the transcript once the whole output was read, including that of killed sessions
+        if let Some(reader) = stdout_reader {
+            // Tools the session left running may keep the output open, so the wait is bounded
+            let deadline = Instant::now() + OUTPUT_DRAIN_TIMEOUT;
+            while !reader.is_finished() && Instant::now() < deadline {
+                thread::sleep(Duration::from_millis(50));
+            }
+        }
+        let mut transcript = transcript.lock().unwrap().clone();
+        transcript.finish();
+        match session_transcript::save_transcript(&self.block_manager, &transcript) {
+            Ok(transcript_id) => log_stream::add_log(&log_task_id, format!("Saved session transcript {}", transcript_id)),
+            Err(e) => log_stream::add_log(&log_task_id, format!("Failed to save the session transcript: {}", e)),
+        }
+
+        let status = match status {
             Ok(status) => status,
             Err(e) => {
-                let error_msg = format!("Failed to wait for Claude CLI command: {}", e);
-                log_stream::add_log(&log_task_id, error_msg.clone());
-                return Err(error_msg);
+                log_stream::add_log(&log_task_id, e.clone());
+                return Err(e);
             }
         };
 
+        // Attribute the spend of the execution to the task; the usage is estimated from the prompt
+        // and the output when the CLI didn't report it
+        let claude_output: Vec<String> = log_stream::get_log_storage().get_logs(&log_task_id)
+            .into_iter()
+            .skip(claude_log_start)
+            .map(|l| l.content)
+            .collect();
+        let session_metrics = transcript.metrics();
+        let usage = TokenUsage {
+            prompt_tokens: session_metrics.input_tokens.unwrap_or_else(|| llm_usage::estimate_tokens(&task_prompt)),
+            completion_tokens: session_metrics.output_tokens.unwrap_or_else(|| llm_usage::estimate_tokens(&claude_output.join("\n"))),
+        };
+        let attribution = CostAttribution {
+            block_id: block_id.to_string(),
+            task_id: Some(task_id.to_string()),
+            operation: "execute_task".to_string(),
+        };
+        let model = project_config.execution_model().unwrap_or_else(|| "claude-code".to_string());
+        if let Err(e) = llm_usage::record_call("ClaudeCode", &model, usage, Some(&attribution)) {
+            println!("Failed to record the cost of task {}: {}", task_id, e);
+        }
+
         let task_success = status.success();
 
 
         if !task_success {
             let error_msg = format!("Claude CLI command failed with exit code: {:?}", status.code());
             log_stream::add_log(&log_task_id, error_msg.clone());
-            return Err(get_logs_str(task_id));
+            return Err(get_logs_str(&log_task_id));
         }
 
         log_stream::add_log(&log_task_id, "Claude CLI command completed successfully".to_string());
         println!("Claude CLI command completed successfully");
 
+        // Run the post-execution hooks (e.g. tests) before committing the work
+        if let Err(e) = run_hooks(&hooks.after, "after", &work_dir, &task_env, block_id, task_id, &log_task_id) {
+            return Err(format!("{}\n{}", get_logs_str(&log_task_id), e));
+        }
+
         // Step 4: Commit changes
         println!("Step 4: Committing changes");
         let msg = format!("Step 4: Committing changes {}",  task_id);
@@ -256,13 +504,13 @@ impl TaskExecutor {
         let add_output = Command::new("git")
             .arg("add")
             .arg(".")
-            .current_dir(&project_dir)
+            .current_dir(&work_dir)
             .output();
 
         if let Err(e) = add_output {
             let error_msg = format!("Failed to stage changes into git (add): {}", e);
             log_stream::add_log(&log_task_id, error_msg.clone());
-            return Err(get_logs_str(task_id));
+            return Err(get_logs_str(&log_task_id));
         }
 
         // Use the task description as a commit message
@@ -271,20 +519,40 @@ impl TaskExecutor {
             .arg("commit")
             .arg("-m")
             .arg(&commit_message)
-            .current_dir(&project_dir)
+            .current_dir(&work_dir)
             .output();
 
         if let Err(e) = commit_output {
             let error_msg = format!("Failed to commit changes into git: {}", e);
             log_stream::add_log(&log_task_id, error_msg.clone());
-            return Err(get_logs_str(task_id));
+            return Err(get_logs_str(&log_task_id));
+        }
+
+        // Count the commits of the execution before a rebase rewrites them
+        let created_commits = start_commit.as_deref()
+            .and_then(|start| git(&work_dir, &["rev-list", "--count", &format!("{}..HEAD", start)]).ok())
+            .and_then(|count| count.parse::<usize>().ok());
+
+        // In worktree mode, merge the branch back before recording the (possibly rebased) commit
+        if let Some(task_worktree) = task_worktree.as_mut().filter(|_| !open_pull_request) {
+            let msg = format!("Step 5: Merging back to {} {}", main_branch, task_id);
+            log_stream::add_log(&log_task_id, msg.clone());
+
+            if let Err(e) = task_worktree.merge_back(main_branch) {
+                // Keep the branch so the work of the execution can still be merged by hand
+                task_worktree.set_keep_branch(true);
+                let error_msg = format!("Failed to merge task branch, keeping it: {}", e);
+                log_stream::add_log(&log_task_id, error_msg.clone());
+                return Err(get_logs_str(&log_task_id));
+            }
+            task_worktree.set_keep(false);
         }
 
         // Get the commit ID
         let commit_id_output = Command::new("git")
             .arg("rev-parse")
             .arg("HEAD")
-            .current_dir(&project_dir)
+            .current_dir(&work_dir)
             .output();
 
         let commit_id = match commit_id_output {
@@ -294,100 +562,633 @@ impl TaskExecutor {
                 } else {
                     let error_msg = format!("Failed to get commit id: {:?}, {}", status.code(),  String::from_utf8_lossy(&output.stderr));
                     log_stream::add_log(&log_task_id, error_msg.clone());
-                    return Err(get_logs_str(task_id));
+                    return Err(get_logs_str(&log_task_id));
                 }
             }
             Err(e) => {
                 let error_msg = format!("Failed to get commit id: {:?}, {}", status.code(), e);
                 log_stream::add_log(&log_task_id, error_msg.clone());
-                return Err(get_logs_str(task_id));
+                return Err(get_logs_str(&log_task_id));
             }
         };
 
-        let commit_id = commit_id.unwrap_or("No commit id".to_string());
-        self.update_task_commit_id(&block_id, &task_id, commit_id.as_str());
+
+        // The commits of the execution, oldest first: those of the agent and the one of Step 4.
+        // They are the last ones of the branch, rebased or not.
+        let commit_ids = match created_commits {
+            Some(0) => {
+                log_stream::add_log(&log_task_id, format!("No commit was created by the execution of {}", task_id));
+                Vec::new()
+            }
+            Some(count) => match git(&work_dir, &["rev-list", "--reverse", "-n", &count.to_string(), "HEAD"]) {
+                Ok(output) => output.lines().map(str::to_string).collect(),
+                Err(e) => {
+                    log_stream::add_log(&log_task_id, format!("Failed to list the commits of {}: {}", task_id, e));
+                    commit_id.into_iter().collect()
+                }
+            },
+            None => commit_id.into_iter().collect(),
+        };
+        let commit_id = commit_ids.last().cloned().unwrap_or("No commit id".to_string());
+        self.update_task_commit_ids(&block_id, &task_id, commit_ids.clone());
+
+        // Record the files changed by the commits for the ownership map
+        if !commit_ids.is_empty() {
+            let files_output = Command::new("git")
+                .arg("show")
+                .arg("--name-only")
+                .arg("--pretty=format:")
+                .args(&commit_ids)
+                .current_dir(&project_dir)
+                .output();
+
+            match files_output {
+                Ok(output) if output.status.success() => {
+                    let files: Vec<String> = String::from_utf8_lossy(&output.stdout)
+                        .lines()
+                        .map(|l| l.trim().to_string())
+                        .filter(|l| !l.is_empty())
+                        .collect();
+                    self.update_task_files_modified(&block_id, &task_id, files);
+                }
+                Ok(output) => {
+                    let error_msg = format!("Failed to list files of commit {}: {}", commit_id, String::from_utf8_lossy(&output.stderr));
+                    log_stream::add_log(&log_task_id, error_msg);
+                }
+                Err(e) => {
+                    let error_msg = format!("Failed to list files of commit {}: {}", commit_id, e);
+                    log_stream::add_log(&log_task_id, error_msg);
+                }
+            }
+        }
 
         let msg = format!("Commit id: {}, {}",  task_id, commit_id);
         log_stream::add_log(&log_task_id, msg.clone());
 
-        // Step 5: Merge back to main
-        let msg = format!("Step 5: Merging back to main {}",  task_id);
-        log_stream::add_log(&log_task_id, msg.clone());
+        if open_pull_request {
+            // Step 5: Propose the branch as a pull request instead of merging it
+            let msg = format!("Step 5: Opening a pull request for {} {}", task_branch, task_id);
+            log_stream::add_log(&log_task_id, msg.clone());
+
+            // The worktree goes, the branch stays for the pull request
+            if let Some(mut task_worktree) = task_worktree.take() {
+                task_worktree.set_keep(false);
+                task_worktree.set_keep_branch(true);
+            } else if let Err(e) = Command::new("git").args(["checkout", main_branch.as_str()]).current_dir(&project_dir).output() {
+                log_stream::add_log(&log_task_id, format!("Failed to checkout {} branch: {}", main_branch, e));
+            }
 
-        let checkout_output = Command::new("git")
-            .arg("checkout")
-            .arg(main_branch)
-            .current_dir(&project_dir)
-            .output();
+            // The work is committed either way; a failed pull request can be opened again later
+            match self.open_pull_request(block_id, task_id, &task_branch, &commit_id, &project_dir) {
+                Ok(url) => log_stream::add_log(&log_task_id, format!("Pull request: {}", url)),
+                Err(e) => log_stream::add_log(&log_task_id, format!("Failed to open a pull request for {}: {}", task_branch, e)),
+            }
+        } else if let Some(task_worktree) = task_worktree.take() {
+            // Step 6: Clean up (remove the worktree and the merged task branch)
+            let msg = format!("Step 6: Cleaning up {}",  task_id);
+            log_stream::add_log(&log_task_id, msg.clone());
+            drop(task_worktree);
+        } else {
+            // Step 5: Merge back to main
+            let msg = format!("Step 5: Merging back to main {}",  task_id);
+            log_stream::add_log(&log_task_id, msg.clone());
+
+            let checkout_output = Command::new("git")
+                .arg("checkout")
+                .arg(main_branch)
+                .current_dir(&project_dir)
+                .output();
+
+            if let Err(e) = checkout_output {
+                let error_msg = format!("Failed to checkout {} branch: {}",  main_branch, e);
+                log_stream::add_log(&log_task_id, error_msg.clone());
+                return Err(get_logs_str(&log_task_id));
+            }
 
-        if let Err(e) = checkout_output {
-            let error_msg = format!("Failed to checkout {} branch: {}",  main_branch, e);
-            log_stream::add_log(&log_task_id, error_msg.clone());
-            return Err(get_logs_str(task_id));
-        }
+            let merge_output = Command::new("git")
+                .arg("merge")
+                .arg("--ff-only")
+                .arg(&task_branch)
+                .current_dir(&project_dir)
+                .output();
 
-        let merge_output = Command::new("git")
-            .arg("merge")
-            .arg("--ff-only")
-            .arg(&task_id)
-            .current_dir(&project_dir)
-            .output();
+            if let Err(e) = merge_output {
+                let error_msg = format!("Failed to merge task branch: {}",  e);
+                log_stream::add_log(&log_task_id, error_msg.clone());
+                return Err(get_logs_str(&log_task_id));
+            }
 
-        if let Err(e) = merge_output {
-            let error_msg = format!("Failed to merge task branch: {}",  e);
-            log_stream::add_log(&log_task_id, error_msg.clone());
-            return Err(get_logs_str(task_id));
-        }
+            // Step 6: Clean up (delete the task branch)
+            let msg = format!("Step 6: Cleaning up {}",  task_id);
+            log_stream::add_log(&log_task_id, msg.clone());
 
-        // Step 6: Clean up (delete the task branch)
-        let msg = format!("Step 6: Cleaning up {}",  task_id);
-        log_stream::add_log(&log_task_id, msg.clone());
+            let delete_output = Command::new("git")
+                .arg("branch")
+                .arg("-d")
+                .arg(&task_branch)
+                .current_dir(&project_dir)
+                .output();
 
-        let delete_output = Command::new("git")
-            .arg("branch")
-            .arg("-d")
-            .arg(&task_id)
-            .current_dir(&project_dir)
-            .output();
+            if let Err(e) = delete_output {
+                let error_msg = format!("Failed to delete task branch: {}",  e);
+                log_stream::add_log(&log_task_id, error_msg.clone());
+            }
+        }
 
-        if let Err(e) = delete_output {
-            let error_msg = format!("Failed to delete task branch: {}",  e);
-            log_stream::add_log(&log_task_id, error_msg.clone());
+        if let Some(parked) = parked {
+            parked.restore();
         }
 
         let msg = format!("Task ended: {}",  task_id);
         log_stream::add_log(&log_task_id, msg.clone());
 
-        Ok((get_logs_str(task_id), commit_id))
+        // The task log holds the Claude and hook output of the execution
+        Ok((get_logs_str(&log_task_id), commit_id))
     }
 
-    // Get the next task from the queue
+    // Get the next task from the queue, skipping tasks that are on hold; none while paused or
+    // shutting down (the queue is kept for the next run)
     fn get_next_task(&self) -> Option<QueuedTask> {
-        if let Ok(mut queue) = self.queue.lock() {
-            queue.pop_front()
+        if self.is_paused() || shutdown::is_requested() {
+            return None;
+        }
+
+        let next = if let Ok(mut queue) = self.queue.lock() {
+            let index = queue.iter().position(|t| t.is_ready())?;
+            queue.remove(index)
         } else {
             None
+        };
+
+        if next.is_some() {
+            self.save_queue();
+        }
+        next
+    }
+
+    // Path of the persisted queue file
+    fn queue_file(&self) -> String {
+        self.block_manager.project_data_file(EXECUTOR_QUEUE_FILE)
+    }
+
+    // Load the persisted queue and mark its tasks as in progress
+    fn load_queue(&self) {
+        let queue_file = self.queue_file();
+        if !Path::new(&queue_file).exists() {
+            return;
+        }
+
+        let tasks: Vec<QueuedTask> = match std::fs::read_to_string(&queue_file)
+            .map_err(|e| e.to_string())
+            .and_then(|content| serde_json::from_str(&content).map_err(|e| e.to_string()))
+        {
+            Ok(tasks) => tasks,
+            Err(e) => {
+                println!("Failed to load execution queue from {}: {}", queue_file, e);
+                return;
+            }
+        };
+
+        if let (Ok(mut queue), Ok(mut in_progress)) = (self.queue.lock(), self.in_progress.write()) {
+            for task in tasks {
+                in_progress.insert(task.get_unique_id());
+                queue.push_back(task);
+            }
+            println!("Restored {} queued tasks from {}", queue.len(), queue_file);
         }
     }
 
-    // Execute a task
-    fn execute_task(&self, task: QueuedTask) {
-        // This is a placeholder for the actual task execution logic
-        // In a real implementation, this would call the appropriate handler based on the task type
-        self.update_task_status(&task.block_id, &task.task_id, "[IN-PROGRESS]");
+    // Persist the pending queue so the order survives restarts
+    fn save_queue(&self) {
+        let tasks: Vec<QueuedTask> = match self.queue.lock() {
+            Ok(queue) => queue.iter().cloned().collect(),
+            Err(_) => return,
+        };
 
-        println!("Executing task: {}:{}", task.block_id, task.task_id);
-        match self.execute_git_task(&task.block_id, &task.task_id) {
+        let result = serde_json::to_string_pretty(&tasks)
+            .map_err(|e| e.to_string())
+            .and_then(|json| std::fs::write(self.queue_file(), json).map_err(|e| e.to_string()));
+        if let Err(e) = result {
+            println!("Failed to save execution queue: {}", e);
+        }
+    }
+
+    // Path of the persisted executor state file
+    fn state_file(&self) -> String {
+        self.block_manager.project_data_file(EXECUTOR_STATE_FILE)
+    }
+
+    // Load the persisted paused state
+    fn load_state(&self) {
+        let state_file = self.state_file();
+        if !Path::new(&state_file).exists() {
+            return;
+        }
+
+        match std::fs::read_to_string(&state_file)
+            .map_err(|e| e.to_string())
+            .and_then(|content| serde_json::from_str::<ExecutorState>(&content).map_err(|e| e.to_string()))
+        {
+            Ok(state) => {
+                if state.paused {
+                    println!("Execution queue is paused since {}", state.paused_at.as_deref().unwrap_or("an earlier run"));
+                }
+                if let Ok(mut current) = self.state.lock() {
+                    *current = state;
+                }
+            },
+            Err(e) => println!("Failed to load executor state from {}: {}", state_file, e),
+        }
+    }
+
+    // Let go of the current project's queue before the managers are switched to another project.
+    // The queue stays persisted with its project; fails while a task is running.
+    pub fn detach_project(&self) -> Result<(), String> {
+        let mut queue = self.queue.lock().map_err(|_| "Failed to acquire lock on queue".to_string())?;
+        if let Some(running) = self.running.lock().map_err(|_| "Failed to acquire lock on running task".to_string())?.as_ref() {
+            return Err(format!("Task {} is running; wait for it to finish or cancel it before switching projects", running));
+        }
+        queue.clear();
+        if let Ok(mut in_progress) = self.in_progress.write() {
+            in_progress.clear();
+        }
+        if let Ok(mut state) = self.state.lock() {
+            *state = ExecutorState::default();
+        }
+        if let Ok(mut current_run) = self.current_run.lock() {
+            *current_run = None;
+        }
+        Ok(())
+    }
+
+    // Pick up the queue and paused state of the project the managers point at now
+    pub fn attach_project(&self) {
+        self.load_queue();
+        self.load_state();
+    }
+
+    // Pause or resume the queue; a paused queue lets the running task finish and starts no new one
+    pub fn set_paused(&self, paused: bool) -> Result<ExecutorState, String> {
+        let state = {
+            let mut state = self.state.lock().map_err(|_| "Failed to acquire lock on executor state".to_string())?;
+            if state.paused != paused {
+                state.paused = paused;
+                state.paused_at = if paused { Some(chrono::Utc::now().to_rfc3339()) } else { None };
+            }
+            state.clone()
+        };
+
+        let json = serde_json::to_string_pretty(&state)
+            .map_err(|e| format!("Failed to serialize executor state: {}", e))?;
+        std::fs::write(self.state_file(), json)
+            .map_err(|e| format!("Failed to save executor state: {}", e))?;
+        Ok(state)
+    }
+
+    // Execution ID ("block_id:task_id") of the task being processed
+    pub fn running_execution(&self) -> Option<String> {
+        self.running.lock().ok().and_then(|running| running.clone())
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.state.lock().map(|s| s.paused).unwrap_or(false)
+    }
+
+    // Get the pending tasks in execution order
+    pub fn get_queue(&self) -> Vec<QueuedTask> {
+        match self.queue.lock() {
+            Ok(queue) => queue.iter().cloned().collect(),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    // Estimated hours of work left in the queue, from the normalized task efforts
+    pub fn estimated_queue_hours(&self) -> f32 {
+        let blocks = self.block_manager.get_blocks().unwrap_or_default();
+        self.get_queue().iter()
+            .filter_map(|queued| blocks.iter()
+                .find(|b| b.block_id == queued.block_id)
+                .and_then(|b| b.todo_list.get(&queued.task_id))
+                .and_then(|t| t.estimated_effort.as_ref()))
+            .map(|effort| effort.estimated_hours())
+            .sum()
+    }
+
+    // Move a queued task to a new position (0 = next to run)
+    pub fn move_queued_task(&self, block_id: &str, task_id: &str, position: usize) -> Result<Vec<QueuedTask>, String> {
+        {
+            let mut queue = self.queue.lock().map_err(|_| "Failed to acquire lock on queue".to_string())?;
+            let index = queue.iter()
+                .position(|t| t.block_id == block_id && t.task_id == task_id)
+                .ok_or_else(|| format!("Task {}:{} is not in the queue", block_id, task_id))?;
+            let task = queue.remove(index).unwrap();
+            let position = position.min(queue.len());
+            queue.insert(position, task);
+        }
+
+        self.save_queue();
+        Ok(self.get_queue())
+    }
+
+    // Reorder the whole queue; tasks not listed keep their relative order after the listed ones
+    pub fn reorder_queue(&self, order: &[String]) -> Result<Vec<QueuedTask>, String> {
+        {
+            let mut queue = self.queue.lock().map_err(|_| "Failed to acquire lock on queue".to_string())?;
+            for unique_id in order {
+                if !queue.iter().any(|t| &t.get_unique_id() == unique_id) {
+                    return Err(format!("Task {} is not in the queue", unique_id));
+                }
+            }
+
+            let mut reordered = VecDeque::new();
+            for unique_id in order {
+                if let Some(index) = queue.iter().position(|t| &t.get_unique_id() == unique_id) {
+                    reordered.push_back(queue.remove(index).unwrap());
+                }
+            }
+            reordered.extend(queue.drain(..));
+            *queue = reordered;
+        }
+
+        self.save_queue();
+        Ok(self.get_queue())
+    }
+
+    // Put a queued task on hold or release it
+    pub fn set_queued_task_hold(&self, block_id: &str, task_id: &str, on_hold: bool) -> Result<Vec<QueuedTask>, String> {
+        {
+            let mut queue = self.queue.lock().map_err(|_| "Failed to acquire lock on queue".to_string())?;
+            let task = queue.iter_mut()
+                .find(|t| t.block_id == block_id && t.task_id == task_id)
+                .ok_or_else(|| format!("Task {}:{} is not in the queue", block_id, task_id))?;
+            task.on_hold = on_hold;
+            task.status = if on_hold { "held".to_string() } else { "queued".to_string() };
+        }
+
+        self.save_queue();
+        Ok(self.get_queue())
+    }
+
+    // Remove a task from the queue without executing it
+    pub fn remove_queued_task(&self, block_id: &str, task_id: &str) -> Result<Vec<QueuedTask>, String> {
+        {
+            let mut queue = self.queue.lock().map_err(|_| "Failed to acquire lock on queue".to_string())?;
+            let index = queue.iter()
+                .position(|t| t.block_id == block_id && t.task_id == task_id)
+                .ok_or_else(|| format!("Task {}:{} is not in the queue", block_id, task_id))?;
+            queue.remove(index);
+        }
+
+        if let Ok(mut in_progress) = self.in_progress.write() {
+            in_progress.remove(&format!("{}:{}", block_id, task_id));
+        }
+
+        self.save_queue();
+        Ok(self.get_queue())
+    }
+
+    // Execute an attempt of a task. A failure the project's retry policy retries puts the task back
+    // in the queue with its backoff, so other tasks run meanwhile; returns false in that case.
+    fn execute_task(&self, task: QueuedTask) -> bool {
+        let log_task_id = task.get_unique_id();
+        let retry_policy = self.project_manager.get_task_retry_policy();
+        let attempt = task.attempt.unwrap_or(1);
+
+        let replay = match &task.last_error {
+            // A retry replays the session of the failed attempt
+            Some(last_error) => self.replay_context(&task, last_error),
+            None => {
+                // Clear any existing logs for this task; the logs of all attempts are kept
+                log_stream::clear_logs(&log_task_id);
+
+                // A task run again after failing continues from the context of its last session
+                let replay = self.block_manager.get_blocks().ok()
+                    .and_then(|blocks| blocks.into_iter().find(|b| b.block_id == task.block_id))
+                    .and_then(|block| block.todo_list.get(&task.task_id).cloned())
+                    .filter(|t| t.status == TASK_STATUS_FAILED)
+                    .and_then(|t| self.replay_context(&task, &t.log));
+
+                // A task the workflow doesn't let start is left as it is
+                if let Err(e) = self.update_task_status(&task.block_id, &task.task_id, TASK_STATUS_IN_PROGRESS) {
+                    println!("Task {} not executed: {}", log_task_id, e);
+                    log_stream::add_log(&log_task_id, format!("Task not executed: {}", e));
+                    return true;
+                }
+                replay
+            },
+        };
+
+        if self.is_cancel_requested(&log_task_id) {
+            self.finish_cancelled_task(&task);
+            return true;
+        }
+
+        println!("Executing task: {}:{} (attempt {})", task.block_id, task.task_id, attempt);
+        log_stream::add_log(&log_task_id, format!("Attempt {}/{}", attempt, retry_policy.max_attempts.max(1)));
+        let attempt_start = log_stream::get_log_storage().get_logs(&log_task_id).len();
+
+        match self.execute_git_task(&task.block_id, &task.task_id, replay.as_deref()) {
             Ok((log, commit_id)) => {
                 // Update the task status in the block config
-                self.update_task_status_with_log_and_commit_id(task.block_id, task.task_id, "[COMPLETED]".to_string(), log, commit_id );
-            } ,
+                self.update_task_status_with_log_and_commit_id(task.block_id, task.task_id, TASK_STATUS_COMPLETED.to_string(), log, commit_id );
+                true
+            },
+            Err(_) if self.is_cancel_requested(&log_task_id) => {
+                self.finish_cancelled_task(&task);
+                true
+            },
             Err(err_str) => {
-                self.update_task_status_with_log_and_commit_id(task.block_id, task.task_id, "[FAILED]".to_string(), err_str, "No commit id".to_string() );
+                // Classify on the error and the output of this attempt (e.g. Claude's stderr)
+                let attempt_log: Vec<String> = log_stream::get_log_storage().get_logs(&log_task_id)
+                    .into_iter()
+                    .skip(attempt_start)
+                    .map(|l| l.content)
+                    .collect();
+                let error_class = TaskRetryPolicy::classify_error(&format!("{}\n{}", err_str, attempt_log.join("\n")));
+                match retry_policy.retry_delay(attempt, error_class) {
+                    Some(delay) => {
+                        let msg = format!("Attempt {} failed ({}), retrying in {}s", attempt, error_class, delay.as_secs());
+                        println!("Task {}: {}", log_task_id, msg);
+                        log_stream::add_log(&log_task_id, msg);
+                        self.reset_task_branch(&task.block_id, &task.task_id);
+                        let retry_at = chrono::Utc::now() + chrono::Duration::from_std(delay).unwrap_or_else(|_| chrono::Duration::zero());
+                        self.requeue_for_retry(QueuedTask {
+                            attempt: Some(attempt + 1),
+                            retry_at: Some(retry_at.to_rfc3339()),
+                            last_error: Some(err_str),
+                            ..task
+                        });
+                        false
+                    },
+                    None => {
+                        log_stream::add_log(&log_task_id, format!("Attempt {} failed ({}), giving up", attempt, error_class));
+                        // Keep the record of every attempt in the task log
+                        let log = format!("{}{}", get_logs_str(&log_task_id), err_str);
+                        self.update_task_status_with_log_and_commit_id(task.block_id, task.task_id, TASK_STATUS_FAILED.to_string(), log, "No commit id".to_string() );
+                        true
+                    },
+                }
             },
         }
     }
 
+    // Put a task back at the end of the queue to retry it once its backoff elapsed
+    fn requeue_for_retry(&self, task: QueuedTask) {
+        if let Ok(mut queue) = self.queue.lock() {
+            queue.push_back(task);
+        }
+        self.save_queue();
+    }
+
+    // Names of a block and of one of its tasks, for the events; the first line of the description
+    // stands for a task without a name
+    fn task_names(&self, block_id: &str, task_id: &str) -> (String, String) {
+        let blocks = self.block_manager.get_blocks().unwrap_or_default();
+        let block = blocks.iter().find(|b| b.block_id == block_id);
+        let task_name = block.and_then(|b| b.todo_list.get(task_id))
+            .map(|t| if t.task_name.trim().is_empty() { t.description.lines().next().unwrap_or_default().to_string() } else { t.task_name.clone() })
+            .unwrap_or_else(|| task_id.to_string());
+        (block.map(|b| b.name.clone()).unwrap_or_else(|| block_id.to_string()), task_name)
+    }
+
+    // Prompt section with the context of the last session of a task, which failed with the given error
+    fn replay_context(&self, task: &QueuedTask, failure: &str) -> Option<String> {
+        let transcript = session_transcript::load_transcript(&self.block_manager, &task.block_id, &task.task_id, "latest").ok()?;
+        let session = transcript.session_id.clone().unwrap_or_else(|| transcript.started_at.clone());
+        log_stream::add_log(&task.get_unique_id(), format!("Replaying the context of the previous session {}", session));
+        Some(transcript.replay_prompt(failure))
+    }
+
+    // ID of the running execution, starting a new one if the executor was idle
+    fn current_run_id(&self) -> String {
+        let mut current_run = match self.current_run.lock() {
+            Ok(lock) => lock,
+            Err(_) => return chrono::Utc::now().format("%Y%m%d-%H%M%S").to_string(),
+        };
+        current_run
+            .get_or_insert_with(|| chrono::Utc::now().format("%Y%m%d-%H%M%S").to_string())
+            .clone()
+    }
+
+    fn is_cancel_requested(&self, execution_id: &str) -> bool {
+        self.cancel_requested.lock().map(|c| c.contains(execution_id)).unwrap_or(false)
+    }
+
+    // Clean up after a cancelled execution and mark the task cancelled
+    fn finish_cancelled_task(&self, task: &QueuedTask) {
+        let log_task_id = task.get_unique_id();
+        log_stream::add_log(&log_task_id, "Execution cancelled".to_string());
+        self.reset_task_branch(&task.block_id, &task.task_id);
+
+        // Workflows without a cancelled status record the cancellation as a failure
+        let workflow = self.project_manager.get_task_workflow();
+        let status = workflow.normalize_status(TASK_STATUS_CANCELLED).unwrap_or(TASK_STATUS_FAILED.to_string());
+        self.update_task_status_with_log_and_commit_id(task.block_id.clone(), task.task_id.clone(), status, get_logs_str(&log_task_id), "No commit id".to_string());
+    }
+
+    // Cancel an execution ("block_id:task_id"): a queued task is removed from the queue, a running one
+    // has its processes terminated. Either way the task is marked cancelled.
+    pub fn cancel_execution(&self, execution_id: &str) -> Result<String, String> {
+        let (block_id, task_id) = execution_id.split_once(':')
+            .ok_or_else(|| format!("Invalid execution ID '{}', expected block_id:task_id", execution_id))?;
+
+        let is_running = self.running.lock()
+            .map(|r| r.as_deref() == Some(execution_id))
+            .unwrap_or(false);
+        if !is_running {
+            self.remove_queued_task(block_id, task_id)
+                .map_err(|_| format!("Execution {} is neither running nor queued", execution_id))?;
+            log_stream::add_log(execution_id, "Execution cancelled".to_string());
+            let workflow = self.project_manager.get_task_workflow();
+            let status = workflow.normalize_status(TASK_STATUS_CANCELLED).unwrap_or(TASK_STATUS_FAILED.to_string());
+            if let Err(e) = self.update_task_status(block_id, task_id, &status) {
+                return Ok(format!("Removed queued execution {}, the task keeps its status: {}", execution_id, e));
+            }
+            return Ok(format!("Removed queued execution {}", execution_id));
+        }
+
+        if let Ok(mut cancel_requested) = self.cancel_requested.lock() {
+            cancel_requested.insert(execution_id.to_string());
+        }
+        let terminated = process_tracker::terminate_execution(execution_id);
+        log_stream::add_log(execution_id, format!("Cancellation requested, terminated {} process(es)", terminated.len()));
+        Ok(format!("Cancelling execution {}", execution_id))
+    }
+
+    // Wait for the running task to finish; past the timeout it's cancelled, so its processes are
+    // terminated and its status saved before the process exits. Returns whether it finished in time.
+    pub fn drain(&self, timeout: Duration) -> bool {
+        let running = || self.running_execution();
+        let deadline = Instant::now() + timeout;
+        while running().is_some() && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(200));
+        }
+        let Some(execution_id) = running() else {
+            return true;
+        };
+
+        println!("Execution {} didn't finish within the drain timeout, cancelling it", execution_id);
+        if let Err(e) = self.cancel_execution(&execution_id) {
+            println!("Failed to cancel execution {}: {}", execution_id, e);
+        }
+        let deadline = Instant::now() + CANCEL_GRACE_PERIOD;
+        while running().is_some() && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(200));
+        }
+        false
+    }
+
+    // Return to the main branch and drop the task branch of a failed attempt, so the next attempt starts clean
+    fn reset_task_branch(&self, block_id: &str, task_id: &str) {
+        let project_config = match self.project_manager.get_config() {
+            Ok(config) if !config.project_home_directory.is_empty() => config,
+            _ => return,
+        };
+        let main_branch = project_config.task_base_branch();
+        let task_branch = self.block_manager.get_blocks().ok()
+            .and_then(|blocks| blocks.into_iter().find(|b| b.block_id == block_id))
+            .and_then(|block| block.todo_list.get(task_id).map(|task| project_config.task_branch(task)))
+            .unwrap_or_else(|| task_id.to_string());
+
+        // In worktree mode the project checkout was never touched; the worktree is recreated by the next attempt
+        if project_config.worktrees.as_ref().map(|w| w.enabled).unwrap_or(false) {
+            return;
+        }
+
+        // The execution discarded its changes and left the task branch before restoring the parked
+        // local changes, so the working tree is the user's again and is neither reset nor cleaned here.
+        // The checkout only matters if the execution couldn't leave the branch, and fails rather than
+        // overwrite changes.
+        let project_dir = &project_config.project_home_directory;
+        if git(project_dir, &["rev-parse", "--abbrev-ref", "HEAD"]).ok().as_deref() == Some(task_branch.as_str()) {
+            if let Err(e) = git(project_dir, &["checkout", &main_branch]) {
+                println!("Failed to leave task branch {}, keeping it: {}", task_branch, e);
+                return;
+            }
+        }
+        let _ = git(project_dir, &["branch", "-D", &task_branch]);
+    }
+
+    // Push the branch of a task and open its pull request; the branch of a task that has one is only pushed
+    fn open_pull_request(&self, block_id: &str, task_id: &str, branch: &str, commit_id: &str, project_dir: &str) -> Result<String, String> {
+        let existing = self.block_manager.get_blocks()?
+            .into_iter()
+            .find(|b| b.block_id == block_id)
+            .and_then(|b| b.todo_list.get(task_id).and_then(|t| t.pull_request_url.clone()));
+        if let Some(url) = existing {
+            pull_request::push_task_branch(project_dir, branch, commit_id)?;
+            return Ok(url);
+        }
+
+        // The executor runs on its own thread, outside the async runtime
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .map_err(|e| format!("Failed to start a runtime: {}", e))?;
+        runtime.block_on(pull_request::create_pull_request(&self.project_manager, &self.block_manager, block_id, task_id, None, false))
+            .map(|pull_request| pull_request.url)
+    }
+
     // Helper function to update task status, log, and commit ID
     fn update_task_status_with_log_and_commit_id(&self,
         block_id: String,
@@ -417,14 +1218,26 @@ impl TaskExecutor {
             .ok_or("Block not found")?;
 
         // Get the task, clone it, and update the clone
+        let mut rejected = None;
         let task_opt = block.todo_list.get(task_id);
         if let Some(task_original) = task_opt {
+            // Validate the status change against the project workflow; a rejected one (e.g. the task
+            // was moved meanwhile) keeps the status but still records the log and the commit
+            let workflow = self.project_manager.get_task_workflow();
+            let (status, log) = match workflow.validate_transition(&task_original.status, status) {
+                Ok(status) => (status, log.to_string()),
+                Err(e) => {
+                    rejected = Some(e.clone());
+                    (task_original.status.clone(), format!("{}\nStatus not updated: {}", log, e))
+                },
+            };
+
             // Clone the task
             let mut task_updated = task_original.clone();
             // Update task fields
-            task_updated.status = status.to_string();
+            task_updated.status = status.clone();
             task_updated.description = format!("{} {}", task_updated.description, status);
-            task_updated.log = log.to_string();
+            task_updated.log = log;
             task_updated.commit_id = commit_id;
 
             // Update the task in the block's todo_list
@@ -436,7 +1249,10 @@ impl TaskExecutor {
         match self.block_manager.update_block(block.clone()) {
             Ok(_) => {
                 // Save the updated blocks to the file
-                self.block_manager.save_blocks_to_file().map_err(|e| format!("Failed to save blocks to file: {}", e))?;
+                self.block_manager.save_blocks_from("task_executor::update_task_and_save").map_err(|e| format!("Failed to save blocks to file: {}", e))?;
+                if let Some(e) = rejected {
+                    return Err(format!("Task {} kept its status: {}", task_id, e));
+                }
             },
             Err(e) => {
                 println!("Failed to update block: {}", e);
@@ -450,19 +1266,29 @@ impl TaskExecutor {
         Ok(())
     }
 
-    // Update the status of a task in the block config
-    fn update_task_status(&self, block_id: &str, task_id: &str, status: &str) {
+    // Update the status of a task in the block config; Err when the workflow rejects the transition
+    fn update_task_status(&self, block_id: &str, task_id: &str, status: &str) -> Result<(), String> {
+        let workflow = self.project_manager.get_task_workflow();
+        self.block_manager.update_task_status(block_id, task_id, status, &workflow)?;
+        // Save the updated blocks to the file
+        if let Err(e) = self.block_manager.save_blocks_from("task_executor::update_task_status") {
+            println!("Failed to save blocks to file: {}", e);
+        }
+        Ok(())
+    }
+
+    fn update_task_commit_ids(&self, block_id: &str, task_id: &str, commit_ids: Vec<String>) {
         if let Ok(mut blocks) = self.block_manager.get_blocks() {
             if let Some(block) = blocks.iter_mut().find(|b| b.block_id == block_id) {
                 if let Some(task) = block.todo_list.get_mut(task_id) {
-                    task.status = status.to_string();
+                    task.set_commits(commit_ids);
 
                     // Update the block in the database
                     if let Err(e) = self.block_manager.update_block(block.clone()) {
                         println!("Failed to update block: {}", e);
                     } else {
                         // Save the updated blocks to the file
-                        if let Err(e) = self.block_manager.save_blocks_to_file() {
+                        if let Err(e) = self.block_manager.save_blocks_from("task_executor::update_task_commit_ids") {
                             println!("Failed to save blocks to file: {}", e);
                         }
                     }
@@ -471,18 +1297,23 @@ impl TaskExecutor {
         }
     }
 
-    fn update_task_commit_id(&self, block_id: &str, task_id: &str, commit_id: &str) {
+    // Merge the files changed by a task execution into the task's ownership record
+    fn update_task_files_modified(&self, block_id: &str, task_id: &str, files: Vec<String>) {
         if let Ok(mut blocks) = self.block_manager.get_blocks() {
             if let Some(block) = blocks.iter_mut().find(|b| b.block_id == block_id) {
                 if let Some(task) = block.todo_list.get_mut(task_id) {
-                    task.commit_id = commit_id.to_string();
+                    for file in files {
+                        if !task.files_modified.contains(&file) {
+                            task.files_modified.push(file);
+                        }
+                    }
 
                     // Update the block in the database
                     if let Err(e) = self.block_manager.update_block(block.clone()) {
                         println!("Failed to update block: {}", e);
                     } else {
                         // Save the updated blocks to the file
-                        if let Err(e) = self.block_manager.save_blocks_to_file() {
+                        if let Err(e) = self.block_manager.save_blocks_from("task_executor::update_task_files_modified") {
                             println!("Failed to save blocks to file: {}", e);
                         }
                     }
@@ -493,6 +1324,9 @@ impl TaskExecutor {
 
     // Add a task to the queue, optionally resolving dependencies
     pub fn enqueue_task(&self, block_id: &str, task_id: &str, task_description: &str, resolve_dependencies: bool, force_completed: bool) -> Result<String, String> {
+        if shutdown::is_requested() {
+            return Err("Forge is shutting down, no new executions are accepted".to_string());
+        }
         let task_unique_id = format!("{}:{}", block_id, task_id);
 
         // Check if the task is already in the queue
@@ -536,6 +1370,7 @@ impl TaskExecutor {
                 }
             }
 
+            self.save_queue();
             Ok(format!("Added task {}:{} and its dependencies to the queue", block_id, task_id))
         } else {
             // Just add the requested task to the queue
@@ -554,6 +1389,7 @@ impl TaskExecutor {
                 }
             }
 
+            self.save_queue();
             Ok(format!("Added task {}:{} to the queue", block_id, task_id))
         }
     }
@@ -605,8 +1441,9 @@ impl TaskExecutor {
         let mut completed_tasks = HashSet::new();
 
         // Helper function to check if a task is completed
+        let workflow = self.project_manager.get_task_workflow();
         let is_task_completed = |task: &Task| -> bool {
-            task.status.contains("[COMPLETED]")
+            workflow.is_completed(&task.status)
         };
 
         if !force_completed {
@@ -683,6 +1520,179 @@ impl TaskExecutor {
     }
 }
 
+// Uncommitted changes of the project directory stashed during an execution; restored when dropped,
+// so also when the execution fails. A failed execution first has its leftovers on the task branch
+// discarded and the base branch checked out, so they can't overwrite the restored changes.
+struct ParkedChanges {
+    project_dir: String,
+    stash: Option<String>,
+    log_task_id: String,
+    // Task branch of the execution and the branch it started from, once the task branch is checked out
+    branches: Option<(String, String)>,
+}
+
+impl ParkedChanges {
+    fn park(project_dir: &str, task_id: &str, log_task_id: &str) -> Result<Self, String> {
+        let stash = git_stash::stash_save(project_dir, &format!("Forge: local changes before task {}", task_id), true)?;
+        if let Some(stash) = &stash {
+            log_stream::add_log(log_task_id, format!("Stashed local changes in {}", stash));
+        }
+        Ok(Self {
+            project_dir: project_dir.to_string(),
+            stash,
+            log_task_id: log_task_id.to_string(),
+            branches: None,
+        })
+    }
+
+    fn set_branches(&mut self, task_branch: &str, base_branch: &str) {
+        self.branches = Some((task_branch.to_string(), base_branch.to_string()));
+    }
+
+    // A failed restore leaves the changes in their stash
+    fn restore(mut self) {
+        self.pop();
+    }
+
+    // Leave the task branch if the execution is still on it, discarding what it left in the working
+    // tree. Untracked files can be cleaned as those of the user are parked with the other changes.
+    fn leave_task_branch(&mut self) {
+        let Some((task_branch, base_branch)) = self.branches.take() else {
+            return;
+        };
+        if git(&self.project_dir, &["rev-parse", "--abbrev-ref", "HEAD"]).ok().as_deref() != Some(task_branch.as_str()) {
+            return;
+        }
+        let left = git(&self.project_dir, &["reset", "--hard"])
+            .and_then(|_| git(&self.project_dir, &["clean", "-fd"]))
+            .and_then(|_| git(&self.project_dir, &["checkout", &base_branch]));
+        match left {
+            Ok(_) => log_stream::add_log(&self.log_task_id, format!("Discarded the changes left on {} and checked out {}", task_branch, base_branch)),
+            Err(e) => log_stream::add_log(&self.log_task_id, format!("Failed to leave task branch {}: {}", task_branch, e)),
+        }
+    }
+
+    fn pop(&mut self) {
+        self.leave_task_branch();
+        let Some(stash) = self.stash.take() else {
+            return;
+        };
+        match git_stash::stash_pop(&self.project_dir, Some(&stash)) {
+            Ok(_) => log_stream::add_log(&self.log_task_id, format!("Restored local changes from stash {}", stash)),
+            Err(e) => log_stream::add_log(&self.log_task_id, format!("Failed to restore local changes, they stay in stash {}: {}", stash, e)),
+        }
+    }
+}
+
+impl Drop for ParkedChanges {
+    fn drop(&mut self) {
+        self.pop();
+    }
+}
+
+// Run execution hooks in order, appending their output to the task log. Returns an error when
+// a blocking hook fails; failures of non-blocking hooks are only logged.
+fn run_hooks(hooks: &[ExecutionHook], stage: &str, project_dir: &str, env: &[(String, String)], block_id: &str, task_id: &str, log_task_id: &str) -> Result<(), String> {
+    for hook in hooks {
+        log_stream::add_log(log_task_id, format!("Running {} hook '{}': {}", stage, hook.name, hook.command));
+        let result = run_hook(hook, stage, project_dir, env, block_id, task_id, log_task_id);
+
+        match result {
+            Ok(()) => log_stream::add_log(log_task_id, format!("Hook '{}' passed", hook.name)),
+            Err(e) if hook.blocking => {
+                let error_msg = format!("Blocking {} hook '{}' failed: {}", stage, hook.name, e);
+                log_stream::add_log(log_task_id, error_msg.clone());
+                return Err(error_msg);
+            },
+            Err(e) => log_stream::add_log(log_task_id, format!("Hook '{}' failed (non-blocking): {}", hook.name, e)),
+        }
+    }
+    Ok(())
+}
+
+fn run_hook(hook: &ExecutionHook, stage: &str, project_dir: &str, env: &[(String, String)], block_id: &str, task_id: &str, log_task_id: &str) -> Result<(), String> {
+    let mut command = Command::new("sh");
+    process_tracker::own_process_group(&mut command);
+    let mut child = command
+        .arg("-c")
+        .arg(&hook.command)
+        .current_dir(project_dir)
+        .envs(env.iter().map(|(name, value)| (name, value)))
+        .env("FORGE_BLOCK_ID", block_id)
+        .env("FORGE_TASK_ID", task_id)
+        .env("FORGE_HOOK_STAGE", stage)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to run '{}': {}", hook.command, e))?;
+    let _process_guard = process_tracker::register(child.id(), log_task_id, "sh");
+
+    // Stream the output into the task log
+    let mut readers = Vec::new();
+    let stdout = child.stdout.take().map(|s| Box::new(s) as Box<dyn std::io::Read + Send>);
+    let stderr = child.stderr.take().map(|s| Box::new(s) as Box<dyn std::io::Read + Send>);
+    for stream in [stdout, stderr].into_iter().flatten() {
+        let log_task_id = log_task_id.to_string();
+        let prefix = format!("[{}] ", hook.name);
+        readers.push(thread::spawn(move || {
+            for line in BufReader::new(stream).lines().map_while(Result::ok) {
+                log_stream::add_log_from(&log_task_id, LogSource::Tool, format!("{}{}", prefix, line));
+            }
+        }));
+    }
+
+    let status = wait_with_limits(&mut child, Some(Duration::from_secs(hook.timeout_minutes.max(1).saturating_mul(60))), None)
+        .map_err(|e| e.replace("Claude CLI command", "hook"))?;
+    for reader in readers {
+        let _ = reader.join();
+    }
+
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("exited with {}", status))
+    }
+}
+
+// How often a running Claude process is checked against the task limits
+const LIMIT_CHECK_INTERVAL: Duration = Duration::from_millis(500);
+
+// How long the output of a finished Claude process is read before its transcript is saved
+const OUTPUT_DRAIN_TIMEOUT: Duration = Duration::from_secs(5);
+
+// How long a task cancelled at shutdown gets to save its status and transcript
+const CANCEL_GRACE_PERIOD: Duration = Duration::from_secs(15);
+
+// Wait for a child process, killing its process group when it runs longer than `timeout` or when
+// it and its child processes use more than `max_memory_mb`
+fn wait_with_limits(child: &mut Child, timeout: Option<Duration>, max_memory_mb: Option<u64>) -> Result<ExitStatus, String> {
+    let started = Instant::now();
+    loop {
+        match child.try_wait() {
+            Ok(Some(status)) => return Ok(status),
+            Ok(None) => {},
+            Err(e) => return Err(format!("Failed to wait for Claude CLI command: {}", e)),
+        }
+
+        let exceeded = if let Some(timeout) = timeout.filter(|t| started.elapsed() > *t) {
+            Some(format!("Timeout: Claude CLI command exceeded {} minutes and was killed", timeout.as_secs() / 60))
+        } else {
+            max_memory_mb
+                .zip(process_tracker::process_tree_memory_kb(child.id()))
+                .filter(|(limit, used_kb)| used_kb / 1024 > *limit)
+                .map(|(limit, used_kb)| format!("Memory limit exceeded: Claude CLI command used {} MB (limit {} MB) and was killed", used_kb / 1024, limit))
+        };
+        if let Some(error) = exceeded {
+            process_tracker::kill_process_group(child.id());
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(error);
+        }
+
+        thread::sleep(LIMIT_CHECK_INTERVAL);
+    }
+}
+
 // Lazy static instance for the global task executor
 lazy_static::lazy_static! {
     static ref TASK_EXECUTOR: Mutex<Option<Arc<TaskExecutor>>> = Mutex::new(None);
@@ -706,3 +1716,150 @@ pub fn get_task_executor() -> Result<Arc<TaskExecutor>, String> {
         Err("Task executor has not been initialized".to_string())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Block;
+    use crate::project_config::{ExecutionHooks, ProjectConfig};
+    use std::fs;
+
+    fn hook(name: &str, command: &str, blocking: bool) -> ExecutionHook {
+        ExecutionHook { name: name.to_string(), command: command.to_string(), blocking, timeout_minutes: 1 }
+    }
+
+    fn git(dir: &Path, args: &[&str]) -> String {
+        crate::git_util::git(dir, args).unwrap()
+    }
+
+    // Executor of a project whose checkout has a local edit and an untracked file, with a task whose
+    // execution starts with the given hook
+    fn executor_with_local_changes(dir: &Path, before_hook: &str) -> (TaskExecutor, String, String) {
+        let repo = dir.join("repo");
+        fs::create_dir_all(&repo).unwrap();
+        git(&repo, &["init", "--quiet", "-b", "main"]);
+        git(&repo, &["config", "user.email", "forge@example.com"]);
+        git(&repo, &["config", "user.name", "Forge"]);
+        fs::write(repo.join("README.md"), "readme").unwrap();
+        git(&repo, &["add", "README.md"]);
+        git(&repo, &["commit", "--quiet", "-m", "Initial commit"]);
+        fs::write(repo.join("README.md"), "local edit").unwrap();
+        fs::write(repo.join("notes.txt"), "untracked").unwrap();
+
+        let project_manager = Arc::new(ProjectConfigManager::new(dir.join("project_config.json").to_str().unwrap()));
+        let config = ProjectConfig {
+            project_home_directory: repo.to_string_lossy().to_string(),
+            execution_hooks: Some(ExecutionHooks { before: vec![hook("setup", before_hook, true)], after: Vec::new() }),
+            ..ProjectConfig::default()
+        };
+        project_manager.save_config(&config).unwrap();
+        project_manager.load_config().unwrap();
+
+        let block_manager = Arc::new(BlockConfigManager::new(dir.join("blocks_config.json").to_str().unwrap()));
+        let mut block = Block::new("Docs".to_string(), String::new(), Vec::new(), Vec::new());
+        let mut task = Task::new("Write the docs".to_string());
+        task.status = TASK_STATUS_IN_PROGRESS.to_string();
+        let (block_id, task_id) = (block.block_id.clone(), task.task_id.clone());
+        block.todo_list.insert(task_id.clone(), task);
+        block_manager.add_block(block).unwrap();
+        block_manager.save_blocks_to_file().unwrap();
+
+        let executor = TaskExecutor {
+            queue: Mutex::new(VecDeque::new()),
+            in_progress: RwLock::new(HashSet::new()),
+            running: Mutex::new(None),
+            cancel_requested: Mutex::new(HashSet::new()),
+            state: Mutex::new(ExecutorState::default()),
+            current_run: Mutex::new(None),
+            project_manager,
+            block_manager,
+        };
+        (executor, block_id, task_id)
+    }
+
+    // The local changes are back and the checkout is on the main branch, without what the execution left
+    fn assert_local_changes_restored(repo: &Path) {
+        assert_eq!(git(repo, &["rev-parse", "--abbrev-ref", "HEAD"]), "main");
+        assert_eq!(fs::read_to_string(repo.join("README.md")).unwrap(), "local edit");
+        assert_eq!(fs::read_to_string(repo.join("notes.txt")).unwrap(), "untracked");
+        assert!(!repo.join("leftover.txt").exists());
+        assert!(git(repo, &["stash", "list"]).is_empty());
+    }
+
+    #[test]
+    fn test_failed_execution_keeps_local_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = dir.path().join("repo");
+        let (executor, block_id, task_id) = executor_with_local_changes(dir.path(), "echo agent > README.md; echo junk > leftover.txt; exit 1");
+
+        let err = executor.execute_git_task(&block_id, &task_id, None).unwrap_err();
+        assert!(err.contains("Blocking before hook 'setup' failed"));
+        assert_local_changes_restored(&repo);
+
+        // The next attempt starts without the task branch, the local changes are left alone
+        assert!(!git(&repo, &["branch", "--list", &task_id]).is_empty());
+        executor.reset_task_branch(&block_id, &task_id);
+        assert!(git(&repo, &["branch", "--list", &task_id]).is_empty());
+        assert_local_changes_restored(&repo);
+    }
+
+    #[test]
+    fn test_cancel_running_execution() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = dir.path().join("repo");
+        let (executor, block_id, task_id) = executor_with_local_changes(dir.path(), "echo junk > leftover.txt; sleep 30");
+        let executor = Arc::new(executor);
+        let execution_id = format!("{}:{}", block_id, task_id);
+        *executor.running.lock().unwrap() = Some(execution_id.clone());
+
+        let running = {
+            let (executor, block_id, task_id) = (executor.clone(), block_id.clone(), task_id.clone());
+            thread::spawn(move || executor.execute_git_task(&block_id, &task_id, None))
+        };
+        let started = Instant::now();
+        while !repo.join("leftover.txt").exists() {
+            assert!(started.elapsed() < Duration::from_secs(10), "the hook didn't start");
+            thread::sleep(Duration::from_millis(50));
+        }
+
+        executor.cancel_execution(&execution_id).unwrap();
+        assert!(running.join().unwrap().is_err());
+        assert!(started.elapsed() < Duration::from_secs(30), "the hook wasn't terminated");
+        assert!(executor.is_cancel_requested(&execution_id));
+
+        let task = QueuedTask::new(block_id.clone(), task_id.clone(), "Write the docs".to_string());
+        executor.finish_cancelled_task(&task);
+        assert!(git(&repo, &["branch", "--list", &task_id]).is_empty());
+        assert_local_changes_restored(&repo);
+        let blocks = executor.block_manager.get_blocks().unwrap();
+        let block = blocks.iter().find(|b| b.block_id == block_id).unwrap();
+        assert!(block.todo_list[&task_id].log.contains("Execution cancelled"));
+    }
+
+    #[test]
+    fn test_run_hooks() {
+        let dir = tempfile::tempdir().unwrap();
+        let project_dir = dir.path().to_str().unwrap();
+        let log_task_id = "hook-block:hook-task";
+        let env = vec![("GREETING".to_string(), "hello".to_string())];
+
+        let hooks = vec![
+            hook("greet", "echo $GREETING from $FORGE_TASK_ID at $FORGE_HOOK_STAGE", true),
+            hook("lint", "echo lint warning >&2; exit 3", false),
+        ];
+        assert!(run_hooks(&hooks, "after", project_dir, &env, "hook-block", "hook-task", log_task_id).is_ok());
+        let log = get_logs_str(log_task_id);
+        assert!(log.contains("[greet] hello from hook-task at after"));
+        assert!(log.contains("[lint] lint warning"));
+        assert!(log.contains("Hook 'lint' failed (non-blocking)"));
+
+        // A blocking failure stops the hooks that follow it
+        let hooks = vec![
+            hook("test", "exit 1", true),
+            hook("never", "touch never-ran", true),
+        ];
+        let err = run_hooks(&hooks, "before", project_dir, &env, "hook-block", "hook-task", log_task_id).unwrap_err();
+        assert!(err.contains("Blocking before hook 'test' failed"));
+        assert!(!dir.path().join("never-ran").exists());
+    }
+}
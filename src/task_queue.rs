@@ -1,13 +1,26 @@
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 
 // Structure to represent a task in the execution queue
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct QueuedTask {
     pub block_id: String,
     pub task_id: String,
     pub task_description: String,
     pub status: String,
+    // Held tasks stay in the queue but are skipped by the executor
+    #[serde(default)]
+    pub on_hold: bool,
+    // Attempt number of a task put back in the queue after a failed attempt, to be retried
+    #[serde(default)]
+    pub attempt: Option<u32>,
+    // Time (RFC 3339) before which the retry is not started; other tasks run in the meantime
+    #[serde(default)]
+    pub retry_at: Option<String>,
+    // Error of the failed attempt, replayed in the prompt of the retry
+    #[serde(default)]
+    pub last_error: Option<String>,
 }
 
 impl QueuedTask {
@@ -17,8 +30,20 @@ impl QueuedTask {
             task_id,
             task_description,
             status: "queued".to_string(),
+            on_hold: false,
+            attempt: None,
+            retry_at: None,
+            last_error: None,
         }
     }
+
+    // Whether the task can be started now: not held, and past its retry time if it's a retry
+    pub fn is_ready(&self) -> bool {
+        !self.on_hold && self.retry_at.as_deref()
+            .and_then(|t| chrono::DateTime::parse_from_rfc3339(t).ok())
+            .map(|t| t <= chrono::Utc::now())
+            .unwrap_or(true)
+    }
     
     // Create a unique identifier for the task to check for duplicates
     pub fn get_unique_id(&self) -> String {
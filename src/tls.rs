@@ -0,0 +1,30 @@
+use crate::project_config::TlsConfig;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use rustls::ServerConfig;
+use std::fs::File;
+use std::io::BufReader;
+use std::sync::Arc;
+
+fn open(file: &str) -> Result<BufReader<File>, String> {
+    File::open(file).map(BufReader::new).map_err(|e| format!("Failed to open {}: {}", file, e))
+}
+
+// Server config of HTTPS from the PEM certificate chain and private key of the project
+pub fn load_server_config(tls: &TlsConfig) -> Result<ServerConfig, String> {
+    let certs: Vec<CertificateDer<'static>> = rustls_pemfile::certs(&mut open(&tls.cert_file)?)
+        .collect::<Result<_, _>>()
+        .map_err(|e| format!("Failed to read the certificates of {}: {}", tls.cert_file, e))?;
+    if certs.is_empty() {
+        return Err(format!("No certificate found in {}", tls.cert_file));
+    }
+    let key: PrivateKeyDer<'static> = rustls_pemfile::private_key(&mut open(&tls.key_file)?)
+        .map_err(|e| format!("Failed to read the private key of {}: {}", tls.key_file, e))?
+        .ok_or_else(|| format!("No private key found in {}", tls.key_file))?;
+
+    ServerConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+        .with_safe_default_protocol_versions()
+        .map_err(|e| format!("Failed to set up TLS: {}", e))?
+        .with_no_client_auth()
+        .with_single_cert(certs, key)
+        .map_err(|e| format!("Invalid TLS certificate or key: {}", e))
+}
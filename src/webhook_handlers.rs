@@ -0,0 +1,79 @@
+use crate::project_config::WebhookConfig;
+use crate::project_handlers::ProjectAppState;
+use crate::secrets::{self, REDACTED};
+use crate::webhooks::{self, DeliveryQuery};
+use actix_web::{web, HttpResponse, Responder};
+use std::collections::HashSet;
+
+// Webhooks with their literal secrets hidden; secret references are shown as they are
+fn redact_webhooks(webhooks: Vec<WebhookConfig>) -> Vec<WebhookConfig> {
+    webhooks.into_iter()
+        .map(|mut webhook| {
+            if webhook.secret.as_deref().is_some_and(|secret| !secret.is_empty() && !secrets::is_reference(secret)) {
+                webhook.secret = Some(REDACTED.to_string());
+            }
+            webhook
+        })
+        .collect()
+}
+
+// API endpoint to list the webhooks
+pub async fn get_webhooks_handler(data: web::Data<ProjectAppState>) -> impl Responder {
+    match data.project_manager.get_config() {
+        Ok(config) => HttpResponse::Ok().json(redact_webhooks(config.webhooks.unwrap_or_default())),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Failed to get project config: {}", e)),
+    }
+}
+
+// API endpoint to replace the webhooks; secrets sent back redacted keep their stored value
+pub async fn update_webhooks_handler(data: web::Data<ProjectAppState>, webhooks: web::Json<Vec<WebhookConfig>>) -> impl Responder {
+    let mut webhooks = webhooks.into_inner();
+    let mut ids = HashSet::new();
+    for webhook in &webhooks {
+        if let Err(e) = webhook.validate() {
+            return HttpResponse::BadRequest().body(e);
+        }
+        if !ids.insert(webhook.id.as_str()) {
+            return HttpResponse::BadRequest().body(format!("Duplicate webhook ID {}", webhook.id));
+        }
+    }
+
+    let mut config = match data.project_manager.get_config() {
+        Ok(config) => config,
+        Err(e) => return HttpResponse::InternalServerError().body(format!("Failed to get project config: {}", e)),
+    };
+    let current = config.webhooks.take().unwrap_or_default();
+    for webhook in webhooks.iter_mut().filter(|w| w.secret.as_deref() == Some(REDACTED)) {
+        webhook.secret = current.iter().find(|c| c.id == webhook.id).and_then(|c| c.secret.clone());
+    }
+
+    config.webhooks = Some(webhooks.clone());
+    match data.project_manager.save_config(&config) {
+        Ok(_) => HttpResponse::Ok().json(redact_webhooks(webhooks)),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Failed to save project config: {}", e)),
+    }
+}
+
+// API endpoint to send a ping to a webhook and return the outcome of the delivery
+pub async fn test_webhook_handler(path: web::Path<String>, data: web::Data<ProjectAppState>) -> impl Responder {
+    let webhook_id = path.into_inner();
+    let webhook = match data.project_manager.get_config() {
+        Ok(config) => config.webhooks.unwrap_or_default().into_iter().find(|w| w.id == webhook_id),
+        Err(e) => return HttpResponse::InternalServerError().body(format!("Failed to get project config: {}", e)),
+    };
+    match webhook {
+        Some(webhook) => match webhooks::send_test(webhook).await {
+            Ok(delivery) => HttpResponse::Ok().json(delivery),
+            Err(e) => HttpResponse::InternalServerError().body(e),
+        },
+        None => HttpResponse::NotFound().body(format!("Webhook {} not found", webhook_id)),
+    }
+}
+
+// API endpoint to query the delivery log, most recent first
+pub async fn get_webhook_deliveries_handler(query: web::Query<DeliveryQuery>) -> impl Responder {
+    match webhooks::query(&query) {
+        Ok(deliveries) => HttpResponse::Ok().json(deliveries),
+        Err(e) => HttpResponse::InternalServerError().body(e),
+    }
+}
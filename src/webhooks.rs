@@ -0,0 +1,347 @@
+use crate::block_config::{write_file_atomic, FileLock};
+use crate::project_config::{ProjectConfigManager, WebhookConfig};
+use crate::secrets;
+use crate::slack;
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::Sha256;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedSender};
+
+type HmacSha256 = Hmac<Sha256>;
+
+// Log of the webhook deliveries, one JSON entry per line, stored next to the project config
+pub const WEBHOOK_DELIVERIES_FILE: &str = "webhook_deliveries.jsonl";
+
+// Event sent by the test endpoint
+pub const WEBHOOK_EVENT_PING: &str = "ping";
+
+// Number of deliveries kept in the log; older ones are dropped
+const MAX_DELIVERIES: usize = 500;
+
+// Number of deliveries returned by a query without a limit
+const DEFAULT_QUERY_LIMIT: usize = 50;
+
+const DEFAULT_MAX_ATTEMPTS: u32 = 5;
+
+// Delay before the first retry, doubled for every further retry
+const INITIAL_BACKOFF: Duration = Duration::from_secs(2);
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+// Outcome of the delivery of an event to a webhook, after its retries
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookDelivery {
+    pub id: String,
+    pub webhook_id: String,
+    pub event: String,
+    pub url: String,
+    pub timestamp: String,
+    pub attempts: u32,
+    pub delivered: bool,
+    // Status code of the last attempt, None when the request failed
+    pub status_code: Option<u16>,
+    pub error: Option<String>,
+    pub duration_ms: u64,
+}
+
+// Filters of the delivery log; all given filters must match
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct DeliveryQuery {
+    pub webhook_id: Option<String>,
+    pub event: Option<String>,
+    // "delivered" or "failed"
+    pub status: Option<String>,
+    pub limit: Option<usize>,
+    pub offset: Option<usize>,
+}
+
+impl DeliveryQuery {
+    fn matches(&self, delivery: &WebhookDelivery) -> bool {
+        let status_matches = match self.status.as_deref() {
+            Some("delivered") => delivery.delivered,
+            Some("failed") => !delivery.delivered,
+            _ => true,
+        };
+        self.webhook_id.as_deref().is_none_or(|id| id == delivery.webhook_id)
+            && self.event.as_deref().is_none_or(|event| event == delivery.event)
+            && status_matches
+    }
+}
+
+// A payload waiting to be delivered to a webhook
+struct PendingDelivery {
+    id: String,
+    webhook: WebhookConfig,
+    event: String,
+    body: String,
+}
+
+lazy_static::lazy_static! {
+    // Sender to the delivery thread, started by the first event with a subscriber
+    static ref DELIVERY_QUEUE: Mutex<Option<UnboundedSender<PendingDelivery>>> = Mutex::new(None);
+}
+
+// Signature of a payload, sent in the X-Forge-Signature header
+pub fn sign(secret: &str, body: &str) -> String {
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(body.as_bytes());
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+// Check a signature made by `sign`, in constant time so it can't be guessed byte by byte
+pub fn verify(secret: &str, body: &str, signature: &str) -> bool {
+    let Some(digest) = signature.trim().strip_prefix("sha256=").and_then(|hex_digest| hex::decode(hex_digest).ok()) else {
+        return false;
+    };
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(body.as_bytes());
+    mac.verify_slice(&digest).is_ok()
+}
+
+fn payload(delivery_id: &str, event: &str, data: &Value) -> String {
+    json!({
+        "id": delivery_id,
+        "event": event,
+        "timestamp": Utc::now().to_rfc3339(),
+        "data": data,
+    }).to_string()
+}
+
+// Notify the webhooks and Slack of an event; deliveries happen in the background
+pub fn emit(event: &str, data: Value) {
+    let config = match ProjectConfigManager::get_instance().get_config() {
+        Ok(config) => config,
+        Err(_) => return,
+    };
+    if let Some(slack_config) = &config.slack {
+        slack::notify(slack_config, event, &data);
+    }
+    for webhook in config.webhooks.unwrap_or_default().into_iter().filter(|w| w.subscribes_to(event)) {
+        let id = uuid::Uuid::new_v4().to_string();
+        let body = payload(&id, event, &data);
+        enqueue(PendingDelivery { id, webhook, event: event.to_string(), body });
+    }
+}
+
+fn enqueue(delivery: PendingDelivery) {
+    let mut queue = match DELIVERY_QUEUE.lock() {
+        Ok(queue) => queue,
+        Err(_) => return,
+    };
+    let sender = queue.get_or_insert_with(start_delivery_thread);
+    if let Err(e) = sender.send(delivery) {
+        println!("Failed to queue webhook delivery: {}", e);
+    }
+}
+
+// Deliveries run on their own runtime, so events can be emitted from any thread
+fn start_delivery_thread() -> UnboundedSender<PendingDelivery> {
+    let (sender, mut receiver) = unbounded_channel::<PendingDelivery>();
+    thread::spawn(move || {
+        let runtime = match tokio::runtime::Builder::new_current_thread().enable_all().build() {
+            Ok(runtime) => runtime,
+            Err(e) => {
+                println!("Failed to start the webhook delivery runtime: {}", e);
+                return;
+            }
+        };
+        runtime.block_on(async move {
+            while let Some(pending) = receiver.recv().await {
+                tokio::spawn(async move {
+                    let max_attempts = pending.webhook.max_attempts.unwrap_or(DEFAULT_MAX_ATTEMPTS);
+                    let delivery = deliver(&pending, max_attempts).await;
+                    if let Err(e) = record(&delivery) {
+                        println!("Failed to record webhook delivery: {}", e);
+                    }
+                });
+            }
+        });
+    });
+    sender
+}
+
+async fn send(client: &reqwest::Client, pending: &PendingDelivery) -> Result<u16, (Option<u16>, String)> {
+    let mut request = client.post(&pending.webhook.url)
+        .timeout(REQUEST_TIMEOUT)
+        .header("Content-Type", "application/json")
+        .header("X-Forge-Event", &pending.event)
+        .header("X-Forge-Delivery", &pending.id);
+    if let Some(secret) = pending.webhook.secret.as_deref().filter(|s| !s.is_empty()) {
+        let secret = secrets::resolve(secret).map_err(|e| (None, e))?;
+        request = request.header("X-Forge-Signature", sign(&secret, &pending.body));
+    }
+
+    let response = request.body(pending.body.clone()).send().await.map_err(|e| (None, e.to_string()))?;
+    let status = response.status();
+    if status.is_success() {
+        Ok(status.as_u16())
+    } else {
+        Err((Some(status.as_u16()), format!("The webhook answered {}", status)))
+    }
+}
+
+// Post a payload, retrying failed attempts with an exponential backoff
+async fn deliver(pending: &PendingDelivery, max_attempts: u32) -> WebhookDelivery {
+    let client = reqwest::Client::new();
+    let started = Instant::now();
+    let mut attempts = 0;
+    let mut backoff = INITIAL_BACKOFF;
+    let outcome = loop {
+        attempts += 1;
+        match send(&client, pending).await {
+            Ok(status_code) => break Ok(status_code),
+            Err(e) if attempts >= max_attempts => break Err(e),
+            Err(_) => {
+                tokio::time::sleep(backoff).await;
+                backoff *= 2;
+            }
+        }
+    };
+
+    let (status_code, error) = match outcome {
+        Ok(status_code) => (Some(status_code), None),
+        Err((status_code, error)) => (status_code, Some(error)),
+    };
+    WebhookDelivery {
+        id: pending.id.clone(),
+        webhook_id: pending.webhook.id.clone(),
+        event: pending.event.clone(),
+        url: pending.webhook.url.clone(),
+        timestamp: Utc::now().to_rfc3339(),
+        attempts,
+        delivered: error.is_none(),
+        status_code,
+        error,
+        duration_ms: started.elapsed().as_millis() as u64,
+    }
+}
+
+// Send a ping to a webhook right away, without retries, and log the delivery
+pub async fn send_test(webhook: WebhookConfig) -> Result<WebhookDelivery, String> {
+    let id = uuid::Uuid::new_v4().to_string();
+    let data = json!({ "webhook_id": webhook.id });
+    let pending = PendingDelivery { body: payload(&id, WEBHOOK_EVENT_PING, &data), id, webhook, event: WEBHOOK_EVENT_PING.to_string() };
+    let delivery = deliver(&pending, 1).await;
+    record(&delivery)?;
+    Ok(delivery)
+}
+
+fn deliveries_file() -> String {
+    ProjectConfigManager::get_instance().project_data_file(WEBHOOK_DELIVERIES_FILE)
+}
+
+// Deliveries of the log, oldest first; unreadable lines are skipped
+fn load_deliveries(file: &str) -> Result<Vec<WebhookDelivery>, String> {
+    if !Path::new(file).exists() {
+        return Ok(Vec::new());
+    }
+    let content = fs::read_to_string(file).map_err(|e| format!("Failed to read webhook delivery log: {}", e))?;
+    Ok(content.lines().filter_map(|line| serde_json::from_str(line).ok()).collect())
+}
+
+// Append a delivery to the log, dropping the oldest ones past the maximum
+fn record(delivery: &WebhookDelivery) -> Result<(), String> {
+    let file = deliveries_file();
+    let _lock = FileLock::acquire(&file)?;
+    let line = serde_json::to_string(delivery).map_err(|e| format!("Failed to serialize webhook delivery: {}", e))?;
+    let mut log = OpenOptions::new().create(true).append(true).open(&file)
+        .map_err(|e| format!("Failed to open webhook delivery log: {}", e))?;
+    writeln!(log, "{}", line).map_err(|e| format!("Failed to write webhook delivery log: {}", e))?;
+    drop(log);
+
+    let deliveries = load_deliveries(&file)?;
+    if deliveries.len() > MAX_DELIVERIES {
+        let kept: Vec<String> = deliveries[deliveries.len() - MAX_DELIVERIES..].iter()
+            .filter_map(|d| serde_json::to_string(d).ok())
+            .collect();
+        write_file_atomic(&file, format!("{}\n", kept.join("\n")).as_bytes())?;
+    }
+    Ok(())
+}
+
+// Deliveries matching a query, most recent first
+pub fn query(query: &DeliveryQuery) -> Result<Vec<WebhookDelivery>, String> {
+    let deliveries = load_deliveries(&deliveries_file())?;
+    Ok(deliveries.into_iter()
+        .rev()
+        .filter(|delivery| query.matches(delivery))
+        .skip(query.offset.unwrap_or(0))
+        .take(query.limit.unwrap_or(DEFAULT_QUERY_LIMIT))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::project_config::{WEBHOOK_EVENT_BLOCK_CREATED, WEBHOOK_EVENT_TASK_STATUS_CHANGED};
+
+    fn webhook(events: &[&str]) -> WebhookConfig {
+        WebhookConfig {
+            id: "ci".to_string(),
+            url: "https://ci.example.com/hooks/forge".to_string(),
+            events: events.iter().map(|e| e.to_string()).collect(),
+            secret: Some("s3cret".to_string()),
+            enabled: true,
+            max_attempts: None,
+        }
+    }
+
+    #[test]
+    fn test_sign() {
+        // Reference value from `echo -n '{"a":1}' | openssl dgst -sha256 -hmac s3cret`
+        assert_eq!(sign("s3cret", "{\"a\":1}"), "sha256=5910e62016ef5034272c926c27071992a465c2335cecf41851bda071577f4f6d");
+        assert_ne!(sign("s3cret", "{\"a\":2}"), sign("s3cret", "{\"a\":1}"));
+
+        assert!(verify("s3cret", "{\"a\":1}", &sign("s3cret", "{\"a\":1}")));
+        assert!(!verify("s3cret", "{\"a\":2}", &sign("s3cret", "{\"a\":1}")));
+        assert!(!verify("other", "{\"a\":1}", &sign("s3cret", "{\"a\":1}")));
+        assert!(!verify("s3cret", "{\"a\":1}", "sha256=zz"));
+    }
+
+    #[test]
+    fn test_subscriptions_and_validation() {
+        let hook = webhook(&[WEBHOOK_EVENT_BLOCK_CREATED]);
+        assert!(hook.subscribes_to(WEBHOOK_EVENT_BLOCK_CREATED));
+        assert!(!hook.subscribes_to(WEBHOOK_EVENT_TASK_STATUS_CHANGED));
+        assert!(webhook(&["*"]).subscribes_to(WEBHOOK_EVENT_TASK_STATUS_CHANGED));
+        assert!(!WebhookConfig { enabled: false, ..webhook(&["*"]) }.subscribes_to(WEBHOOK_EVENT_BLOCK_CREATED));
+
+        assert!(hook.validate().is_ok());
+        assert!(webhook(&["task.deleted"]).validate().is_err());
+        assert!(webhook(&[]).validate().is_err());
+        assert!(WebhookConfig { url: "ftp://example.com".to_string(), ..webhook(&["*"]) }.validate().is_err());
+    }
+
+    #[test]
+    fn test_query_matches() {
+        let delivery = WebhookDelivery {
+            id: "1".to_string(),
+            webhook_id: "ci".to_string(),
+            event: WEBHOOK_EVENT_BLOCK_CREATED.to_string(),
+            url: "https://ci.example.com/hooks/forge".to_string(),
+            timestamp: "2025-01-01T00:00:00Z".to_string(),
+            attempts: 3,
+            delivered: false,
+            status_code: Some(503),
+            error: Some("The webhook answered 503".to_string()),
+            duration_ms: 6000,
+        };
+        let query = |webhook_id: Option<&str>, status: Option<&str>| DeliveryQuery {
+            webhook_id: webhook_id.map(|s| s.to_string()),
+            status: status.map(|s| s.to_string()),
+            ..Default::default()
+        };
+        assert!(query(Some("ci"), Some("failed")).matches(&delivery));
+        assert!(!query(Some("ci"), Some("delivered")).matches(&delivery));
+        assert!(!query(Some("slack"), None).matches(&delivery));
+    }
+}
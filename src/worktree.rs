@@ -0,0 +1,265 @@
+use crate::git_util::git;
+use crate::project_config::WorktreeConfig;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+lazy_static::lazy_static! {
+    // Merges into the main branch are serialized so concurrent tasks rebase onto each other
+    static ref MERGE_LOCK: Mutex<()> = Mutex::new(());
+}
+
+// Directory of the worktree of a task branch
+pub fn worktree_path(project_dir: &str, config: &WorktreeConfig, branch: &str) -> PathBuf {
+    let root = match config.root.as_deref().filter(|r| !r.trim().is_empty()) {
+        Some(root) => PathBuf::from(root),
+        None => PathBuf::from(format!("{}.worktrees", project_dir.trim_end_matches('/'))),
+    };
+    root.join(branch.replace('/', "-"))
+}
+
+// Worktree of a task execution on its own branch; removed together with the branch when dropped
+pub struct TaskWorktree {
+    project_dir: PathBuf,
+    path: PathBuf,
+    branch: String,
+    keep: bool,
+    keep_branch: bool,
+}
+
+impl TaskWorktree {
+    // Create a worktree checking out a new branch from `base`, replacing leftovers of an earlier run
+    pub fn create(project_dir: &str, path: PathBuf, branch: &str, base: &str) -> Result<Self, String> {
+        let project_dir = PathBuf::from(project_dir);
+        let _ = git(&project_dir, &["worktree", "prune"]);
+        if path.exists() {
+            git(&project_dir, &["worktree", "remove", "--force", &path.to_string_lossy()])?;
+        }
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| format!("Failed to create worktree directory: {}", e))?;
+        }
+
+        git(&project_dir, &["worktree", "add", "-B", branch, &path.to_string_lossy(), base])?;
+        Ok(Self {
+            project_dir,
+            path,
+            branch: branch.to_string(),
+            keep: false,
+            keep_branch: false,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    // Whether to leave the worktree and its branch in place when dropped, e.g. to inspect a failed execution
+    pub fn set_keep(&mut self, keep: bool) {
+        self.keep = keep;
+    }
+
+    // Whether to leave the branch in place when dropped, e.g. for a pull request
+    pub fn set_keep_branch(&mut self, keep_branch: bool) {
+        self.keep_branch = keep_branch;
+    }
+
+    // Bring the task branch into the main branch: fast-forward when possible, otherwise rebase
+    // the task branch onto the main branch first. Returns the commit the main branch points to.
+    pub fn merge_back(&self, main_branch: &str) -> Result<String, String> {
+        let _merge_lock = MERGE_LOCK.lock().map_err(|_| "Failed to acquire merge lock".to_string())?;
+
+        let main_ref = format!("refs/heads/{}", main_branch);
+        let is_ancestor = git(&self.path, &["merge-base", "--is-ancestor", &main_ref, "HEAD"]).is_ok();
+        if !is_ancestor {
+            if let Err(e) = git(&self.path, &["rebase", &main_ref]) {
+                let _ = git(&self.path, &["rebase", "--abort"]);
+                return Err(format!("Failed to rebase {} onto {}: {}", self.branch, main_branch, e));
+            }
+        }
+        let head = git(&self.path, &["rev-parse", "HEAD"])?;
+
+        // A checked out main branch is merged so its working tree follows; otherwise move the ref
+        let checked_out = git(&self.project_dir, &["rev-parse", "--abbrev-ref", "HEAD"]).unwrap_or_default();
+        if checked_out == main_branch {
+            git(&self.project_dir, &["merge", "--ff-only", &self.branch])?;
+        } else {
+            let old = git(&self.project_dir, &["rev-parse", &main_ref])?;
+            git(&self.project_dir, &["update-ref", &main_ref, &head, &old])?;
+        }
+        Ok(head)
+    }
+}
+
+impl Drop for TaskWorktree {
+    fn drop(&mut self) {
+        if self.keep {
+            return;
+        }
+        if let Err(e) = git(&self.project_dir, &["worktree", "remove", "--force", &self.path.to_string_lossy()]) {
+            println!("Failed to remove worktree {}: {}", self.path.display(), e);
+        }
+        if !self.keep_branch {
+            let _ = git(&self.project_dir, &["branch", "-D", &self.branch]);
+        }
+    }
+}
+
+// File conflicting in a trial merge, with its conflict hunks (none for binary files or a
+// modify/delete conflict)
+#[derive(Debug, Clone, Serialize)]
+pub struct MergeConflict {
+    pub file: String,
+    pub hunks: Vec<String>,
+}
+
+// Outcome of a trial merge of a branch into a target branch
+#[derive(Debug, Clone, Serialize)]
+pub struct MergeCheck {
+    pub branch: String,
+    pub target_branch: String,
+    pub clean: bool,
+    pub conflicts: Vec<MergeConflict>,
+}
+
+// Conflict hunks of a file, from the `<<<<<<<` marker to the `>>>>>>>` one
+fn conflict_hunks(content: &str) -> Vec<String> {
+    let mut hunks = Vec::new();
+    let mut hunk: Option<Vec<&str>> = None;
+    for line in content.lines() {
+        if line.starts_with("<<<<<<<") {
+            hunk = Some(vec![line]);
+        } else if let Some(lines) = hunk.as_mut() {
+            lines.push(line);
+            if line.starts_with(">>>>>>>") {
+                hunks.push(lines.join("\n"));
+                hunk = None;
+            }
+        }
+    }
+    hunks
+}
+
+// Merge `branch` into `target` in a temporary detached worktree to find their conflicts. Neither
+// branch changes and the worktree is removed afterwards.
+pub fn check_merge_conflicts(project_dir: &str, branch: &str, target: &str) -> Result<MergeCheck, String> {
+    let project_dir = PathBuf::from(project_dir);
+    for name in [branch, target] {
+        git(&project_dir, &["rev-parse", "--verify", "--quiet", &format!("{}^{{commit}}", name)])
+            .map_err(|_| format!("Branch {} not found", name))?;
+    }
+
+    let dir = tempfile::Builder::new()
+        .prefix("forge-merge-check-")
+        .tempdir()
+        .map_err(|e| format!("Failed to create temporary directory: {}", e))?;
+    let path = dir.path().to_string_lossy().to_string();
+    git(&project_dir, &["worktree", "add", "--detach", &path, target])?;
+
+    let conflicts = trial_merge(dir.path(), branch);
+    if let Err(e) = git(&project_dir, &["worktree", "remove", "--force", &path]) {
+        println!("Failed to remove worktree {}: {}", path, e);
+    }
+    let _ = git(&project_dir, &["worktree", "prune"]);
+
+    let conflicts = conflicts?;
+    Ok(MergeCheck {
+        branch: branch.to_string(),
+        target_branch: target.to_string(),
+        clean: conflicts.is_empty(),
+        conflicts,
+    })
+}
+
+// Merge a branch without committing, returning the conflicting files
+fn trial_merge(worktree: &Path, branch: &str) -> Result<Vec<MergeConflict>, String> {
+    let merge = git(worktree, &["-c", "user.name=Forge", "-c", "user.email=forge@localhost", "merge", "--no-commit", "--no-ff", branch]);
+    let conflicted = git(worktree, &["diff", "--name-only", "--diff-filter=U"])?;
+    if conflicted.is_empty() {
+        // A failure without conflicts is not a verdict on the branches
+        return merge.map(|_| Vec::new());
+    }
+    Ok(conflicted.lines()
+        .map(|file| MergeConflict {
+            file: file.to_string(),
+            hunks: std::fs::read_to_string(worktree.join(file)).map(|c| conflict_hunks(&c)).unwrap_or_default(),
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn init_repo(dir: &Path) {
+        git(dir, &["init", "--quiet", "-b", "main"]).unwrap();
+        git(dir, &["config", "user.email", "forge@example.com"]).unwrap();
+        git(dir, &["config", "user.name", "Forge"]).unwrap();
+        fs::write(dir.join("README.md"), "readme").unwrap();
+        git(dir, &["add", "README.md"]).unwrap();
+        git(dir, &["commit", "--quiet", "-m", "Initial commit"]).unwrap();
+    }
+
+    fn commit_file(dir: &Path, file: &str, message: &str) {
+        fs::write(dir.join(file), message).unwrap();
+        git(dir, &["add", file]).unwrap();
+        git(dir, &["commit", "--quiet", "-m", message]).unwrap();
+    }
+
+    #[test]
+    fn test_parallel_worktrees_merge_back() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = dir.path().join("repo");
+        fs::create_dir(&repo).unwrap();
+        init_repo(&repo);
+        let project_dir = repo.to_string_lossy().to_string();
+        let config = WorktreeConfig::default();
+
+        let first = TaskWorktree::create(&project_dir, worktree_path(&project_dir, &config, "task-a"), "task-a", "main").unwrap();
+        let second = TaskWorktree::create(&project_dir, worktree_path(&project_dir, &config, "task-b"), "task-b", "main").unwrap();
+        assert_eq!(first.path(), dir.path().join("repo.worktrees").join("task-a"));
+        commit_file(first.path(), "a.txt", "Add a");
+        commit_file(second.path(), "b.txt", "Add b");
+
+        // The first merge fast-forwards, the second is rebased onto it
+        first.merge_back("main").unwrap();
+        let head = second.merge_back("main").unwrap();
+        assert_eq!(git(&repo, &["rev-parse", "main"]).unwrap(), head);
+        assert!(repo.join("a.txt").exists() && repo.join("b.txt").exists());
+
+        let first_path = first.path().to_path_buf();
+        drop(first);
+        drop(second);
+        assert!(!first_path.exists());
+        assert!(git(&repo, &["rev-parse", "--verify", "--quiet", "refs/heads/task-a"]).is_err());
+    }
+
+    #[test]
+    fn test_check_merge_conflicts() {
+        let dir = tempfile::tempdir().unwrap();
+        init_repo(dir.path());
+        let project_dir = dir.path().to_string_lossy().to_string();
+
+        git(dir.path(), &["checkout", "--quiet", "-b", "task-a"]).unwrap();
+        commit_file(dir.path(), "README.md", "From the task");
+        commit_file(dir.path(), "a.txt", "Add a");
+        git(dir.path(), &["checkout", "--quiet", "main"]).unwrap();
+        commit_file(dir.path(), "README.md", "From main");
+
+        let check = check_merge_conflicts(&project_dir, "task-a", "main").unwrap();
+        assert!(!check.clean);
+        assert_eq!(check.conflicts.len(), 1);
+        assert_eq!(check.conflicts[0].file, "README.md");
+        assert_eq!(check.conflicts[0].hunks, vec!["<<<<<<< HEAD\nFrom main\n=======\nFrom the task\n>>>>>>> task-a"]);
+
+        // The trial leaves the repository as it was
+        assert_eq!(git(dir.path(), &["status", "--porcelain"]).unwrap(), "");
+        assert_eq!(git(dir.path(), &["worktree", "list", "--porcelain"]).unwrap().matches("worktree ").count(), 1);
+
+        assert!(!check_merge_conflicts(&project_dir, "main", "task-a").unwrap().clean);
+        git(dir.path(), &["checkout", "--quiet", "-b", "task-b"]).unwrap();
+        commit_file(dir.path(), "b.txt", "Add b");
+        assert!(check_merge_conflicts(&project_dir, "task-b", "main").unwrap().clean);
+        assert!(check_merge_conflicts(&project_dir, "missing", "main").is_err());
+    }
+}